@@ -0,0 +1,68 @@
+#version 330
+
+// Post-processing of the drawn world: bloom around bright colors,
+// a vignette and a petri-dish lens distortion, each switched on
+// and off by its uniform.
+
+in vec2 fragTexCoord;
+in vec4 fragColor;
+
+uniform sampler2D texture0;
+uniform vec4 colDiffuse;
+
+uniform vec2 resolution;
+uniform int bloom;
+uniform int vignette;
+uniform int lens;
+
+out vec4 finalColor;
+
+// How strongly a color glows: bright and saturated colors do,
+// so light backgrounds and text do not.
+float glow(vec3 color) {
+    float high = max(color.r, max(color.g, color.b));
+    float low = min(color.r, min(color.g, color.b));
+    float saturation = high > 0.0 ? (high - low) / high : 0.0;
+    return smoothstep(0.6, 1.0, high) * saturation;
+}
+
+void main() {
+    vec2 uv = fragTexCoord;
+
+    if (lens == 1) {
+        // barrel distortion, stronger towards the rim of the dish
+        vec2 centered = uv * 2.0 - 1.0;
+        centered *= 1.0 - 0.08 * dot(centered, centered);
+        uv = centered * 0.5 + 0.5;
+    }
+
+    vec4 color = texture(texture0, uv);
+
+    if (bloom == 1) {
+        vec2 texel = 2.0 / resolution;
+        vec3 sum = vec3(0.0);
+        float weights = 0.0;
+        for (int x = -4; x <= 4; x++) {
+            for (int y = -4; y <= 4; y++) {
+                float weight = exp(-float(x * x + y * y) / 8.0);
+                vec3 sample = texture(texture0, uv + vec2(x, y) * texel).rgb;
+                sum += sample * glow(sample) * weight;
+                weights += weight;
+            }
+        }
+        color.rgb += sum / weights * 1.5;
+    }
+
+    if (vignette == 1) {
+        float distance = length(uv - 0.5);
+        color.rgb *= 1.0 - smoothstep(0.45, 0.8, distance) * 0.6;
+    }
+
+    if (lens == 1) {
+        // the rim of the dish
+        float rim = length(fragTexCoord * 2.0 - 1.0);
+        color.rgb *= 1.0 - smoothstep(1.25, 1.4, rim);
+    }
+
+    finalColor = color * colDiffuse * fragColor;
+}
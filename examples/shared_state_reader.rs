@@ -0,0 +1,64 @@
+//! Follow a world run with `--shared-state <path>`, printing a line
+//! for each tick it reads: the population, the food and where the
+//! blobs are on average.
+//!
+//! The layout is that of `src/shared_state.rs`, written out here so
+//! the reader needs nothing of the game. A reader in another language
+//! maps the file into memory instead of reading it, the same way.
+//!
+//! ```text
+//! cargo run --release -- --shared-state /dev/shm/blobs
+//! cargo run --example shared_state_reader /dev/shm/blobs
+//! ```
+
+use std::{convert::TryInto, env, fs, io::{self, Read, Seek, SeekFrom}, thread, time::Duration};
+
+const MAGIC: [u8; 8] = *b"BLOBSHM\0";
+const VERSION: u32 = 2;
+const HEADER_SIZE: u64 = 64;
+const SLOT_HEADER_SIZE: u64 = 32;
+//  the index of x in the arrays of blobs, after the keys
+const X_FIELD: u64 = 1;
+
+fn read_at(file: &mut fs::File, at: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    file.seek(SeekFrom::Start(at))?;
+    file.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 { u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap()) }
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 { u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap()) }
+
+fn main() -> io::Result<()> {
+    let path = env::args().nth(1).unwrap_or_else(|| "/dev/shm/blobs".to_string());
+    let mut file = fs::File::open(&path)?;
+    let header = read_at(&mut file, 0, HEADER_SIZE as usize)?;
+    if header[..8] != MAGIC || u32_at(&header, 8) != VERSION {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not shared state of version {}", path, VERSION)));
+    }
+    let (slots, blob_capacity, slot_size) = (u32_at(&header, 12) as u64, u32_at(&header, 16) as u64, u32_at(&header, 24) as u64);
+
+    let mut last = 0;
+    loop {
+        let latest = u64_at(&read_at(&mut file, 32, 8)?, 0);
+        if latest == last || latest == 0 {
+            thread::sleep(Duration::from_millis(5));
+            continue;
+        }
+        let slot = HEADER_SIZE + (latest - 1) % slots * slot_size;
+        let slot_header = read_at(&mut file, slot, SLOT_HEADER_SIZE as usize)?;
+        let blobs = u32_at(&slot_header, 16) as usize;
+        let positions = read_at(&mut file, slot + SLOT_HEADER_SIZE + X_FIELD * 4 * blob_capacity, 4 * blob_capacity as usize * 2)?;
+        //  taken only if the writer did not come back to the slot meanwhile
+        if u64_at(&slot_header, 0) != latest || u64_at(&read_at(&mut file, slot, 8)?, 0) != latest {
+            continue;
+        }
+        last = latest;
+        let value = |array: usize, i: usize| f32::from_le_bytes(positions[(array * blob_capacity as usize + i) * 4..][..4].try_into().unwrap());
+        let mean = |array: usize| (0..blobs).map(|i| value(array, i)).sum::<f32>() / blobs.max(1) as f32;
+        println!("tick {}: {} blobs, {} food, blobs around ({:.0}, {:.0})",
+            u64_at(&slot_header, 8), u32_at(&slot_header, 24), u32_at(&slot_header, 28), mean(0), mean(1));
+    }
+}
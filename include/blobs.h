@@ -0,0 +1,65 @@
+/*
+ * A C interface to the blob simulation, see src/ffi.rs.
+ *
+ * No function unwinds into the host: one that fails inside returns as
+ * for a NULL simulation, and the simulation is best destroyed.
+ *
+ * Kept by hand with src/ffi.rs, whose test checks that every function
+ * it exports is declared here.
+ */
+
+#ifndef BLOBS_H
+#define BLOBS_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct BlobsSim BlobsSim;
+
+/* An empty world of a size, from a seed, or NULL for a size that is not positive. */
+BlobsSim *blobs_sim_create(float width, float height, uint64_t seed);
+void blobs_sim_destroy(BlobsSim *sim);
+
+void blobs_sim_step(BlobsSim *sim, uint32_t ticks);
+uint64_t blobs_sim_tick(const BlobsSim *sim);
+
+/*
+ * Apply a command, returning 0, or -1 for one that is not valid or
+ * places something outside the world or is not finite:
+ * "spawn-blob", "spawn-food <x> <y>", "move <key> <x> <y>",
+ * "velocity <key> <x> <y>", "impulse <key> <x> <y>",
+ * "spawn-drone <x> <y>", "steer-drone <key> <x> <y>", "remove-drone <key>",
+ * "repel <x> <y> <radius>", "attract <x> <y> <radius>" or "calm <x> <y> <radius>".
+ */
+int blobs_sim_push_command(BlobsSim *sim, const char *command);
+
+/*
+ * The functions below fill room for `capacity` blobs or foods and
+ * return how many they filled, in the same order until the next step.
+ */
+size_t blobs_sim_blob_count(const BlobsSim *sim);
+size_t blobs_sim_blob_keys(const BlobsSim *sim, uint64_t *out, size_t capacity);
+/* x and y for each blob. */
+size_t blobs_sim_blob_positions(const BlobsSim *sim, float *out, size_t capacity);
+size_t blobs_sim_blob_radii(const BlobsSim *sim, float *out, size_t capacity);
+/* Red, green, blue and alpha for each blob. */
+size_t blobs_sim_blob_colors(const BlobsSim *sim, uint8_t *out, size_t capacity);
+
+size_t blobs_sim_food_count(const BlobsSim *sim);
+/* x and y for each food. */
+size_t blobs_sim_food_positions(const BlobsSim *sim, float *out, size_t capacity);
+
+/* Drones observe without being seen, see src/drones.rs. */
+size_t blobs_sim_drone_keys(const BlobsSim *sim, uint64_t *out, size_t capacity);
+/* x, y, radius and 1 for food or 0 for a blob, for each thing the drone sees. */
+size_t blobs_sim_drone_sightings(const BlobsSim *sim, uint64_t drone, float *out, size_t capacity);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
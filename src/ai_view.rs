@@ -0,0 +1,111 @@
+//! A debug view of what every blob is trying to do, for diagnosing
+//! emergent behavior across the whole population at once.
+//!
+//! For each drawn blob:
+//!
+//! * a thin line shows its heading, as long as it moves in a second
+//! * a longer line shows where its brain last decided to go, out to
+//!   the edge of its sight
+//! * a third line shows its velocity from outside forces, if any
+//! * a ring around it shows its state, see `State`
+//!
+//! With many blobs the lines clutter, so `A` cycles through drawing
+//! every blob, every 4th, every 16th and none.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let stride = ai_view::next(None);
+//! ai_view::draw(&mut draw, &sim, stride.unwrap(), &theme, zoom);
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    simulation::{Blob, Simulation},
+    shapes,
+    theme::Theme,
+};
+
+/// How many blobs are skipped between drawn ones, cycled through with `A`.
+pub const STRIDES: [usize; 3] = [1, 4, 16];
+/// How far from its heading a target may be for a blob to count as steering, in degrees.
+const ALIGNED: f32 = 30.;
+
+/// What a blob is doing, as far as can be told from outside.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// It sees nothing it cares about and keeps its heading.
+    Wandering,
+    /// It is turning toward a target away from its heading.
+    Turning,
+    /// It is heading about where it wants to go.
+    Steering,
+    /// It is pushed around faster than it moves by itself.
+    Shoved,
+}
+
+impl State {
+    pub fn of(blob: &Blob) -> Self {
+        if blob.velocity().length() > blob.speed {
+            return Self::Shoved;
+        }
+        match blob.target_direction() {
+            None => Self::Wandering,
+            Some(target) if blob.direction().normalized().dot(target) < ALIGNED.to_radians().cos() => Self::Turning,
+            Some(_) => Self::Steering,
+        }
+    }
+
+    pub fn color(self, theme: &Theme) -> Color {
+        match self {
+            Self::Wandering => theme.muted_text,
+            Self::Turning => theme.warning,
+            Self::Steering => theme.ok,
+            Self::Shoved => theme.error,
+        }
+    }
+}
+
+/// The stride after another, or none after the last.
+pub fn next(stride: Option<usize>) -> Option<usize> {
+    match stride {
+        None => Some(STRIDES[0]),
+        Some(stride) => STRIDES.iter().cloned().skip_while(|&s| s != stride).nth(1),
+    }
+}
+
+/// Draw every `stride`th blob of a world, in world coordinates.
+pub fn draw<D: RaylibDraw>(draw: &mut D, sim: &Simulation, stride: usize, theme: &Theme, zoom: f32) {
+    let thickness = 1. / zoom;
+    for (_, blob) in sim.blobs().step_by(stride.max(1)) {
+        let pos = blob.pos();
+        let state = State::of(blob);
+        let heading = blob.direction().normalized();
+        draw.draw_line_ex(pos, pos + heading * (blob.radius() + blob.speed), thickness, theme.text);
+        if let Some(target) = blob.target_direction() {
+            draw.draw_line_ex(pos, pos + target * blob.sight_depth(), thickness, state.color(theme).fade(0.7));
+        }
+        if blob.velocity() != Vector2::zero() {
+            draw.draw_line_ex(pos, pos + blob.velocity(), 2. * thickness, theme.error);
+        }
+        shapes::draw_circle_outline(draw, pos, blob.radius() + 2. / zoom, 2., zoom, state.color(theme));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strides() {
+        let mut strides = vec![];
+        let mut stride = None;
+        loop {
+            stride = next(stride);
+            strides.push(stride);
+            if stride.is_none() { break; }
+        }
+        assert_eq!(strides, vec![Some(1), Some(4), Some(16), None]);
+    }
+}
@@ -0,0 +1,183 @@
+//! Finds unusual moments in the statistics, so unattended runs flag
+//! their own interesting moments.
+//!
+//! New samples are checked as they are recorded:
+//!
+//! * the population crashes when it falls to half of its highest
+//!   over the last `CRASH_WINDOW` samples
+//! * births spike when more blobs are added between two samples than
+//!   usual by more than some standard deviations
+//! * a trait shifts when its mean over all blobs moves away from usual
+//!   by more than some standard deviations
+//!
+//! What is usual is an exponentially weighted mean and variance of
+//! the samples so far, which follows slow trends but not sudden ones.
+//! Each anomaly is bookmarked at its tick and shown as a notice, and a
+//! series that raised one stays quiet for `COOLDOWN` samples.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::anomalies::{self, Detector};
+//! let mut detector = Detector::new(anomalies::DEFAULT_SIGMA);
+//! for anomaly in detector.update(sim.stats()) {
+//!     sim.bookmark(anomaly.tick, &anomaly.description);
+//! }
+//! ```
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::stats::StatsStore;
+
+/// How many standard deviations from usual are unusual, unless given.
+pub const DEFAULT_SIGMA: f32 = 4.;
+/// The traits whose means are watched, by series name.
+pub const TRAITS: [&str; 2] = ["mean speed", "mean size"];
+/// How many of the latest samples a crash is measured against.
+pub const CRASH_WINDOW: usize = 10;
+/// The fewest blobs a population must have had to crash.
+pub const MIN_CRASH_POPULATION: f32 = 10.;
+/// How many samples to learn from before raising anything.
+const WARMUP: u32 = 20;
+const COOLDOWN: u32 = 10;
+/// How much of what is usual each new sample makes.
+const WEIGHT: f32 = 0.05;
+
+/// Something unusual in the statistics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Anomaly {
+    pub tick: u64,
+    pub description: String,
+}
+
+/// What is usual for a series.
+#[derive(Debug, Clone, Default)]
+struct Baseline {
+    mean: f32,
+    variance: f32,
+    count: u32,
+    quiet: u32,
+    recent: VecDeque<f32>,
+}
+
+impl Baseline {
+    /// How many standard deviations a value is from the mean, once
+    /// enough samples were seen, with deviations no smaller than `floor`.
+    fn sigmas(&self, value: f32, floor: f32) -> Option<f32> {
+        if self.count < WARMUP { return None; }
+        Some((value - self.mean) / self.variance.sqrt().max(floor))
+    }
+
+    fn learn(&mut self, value: f32) {
+        if self.count == 0 {
+            self.mean = value;
+        }
+        let difference = value - self.mean;
+        let increment = WEIGHT * difference;
+        self.mean += increment;
+        self.variance = (1. - WEIGHT) * (self.variance + difference * increment);
+        self.count += 1;
+        self.quiet = self.quiet.saturating_sub(1);
+        self.recent.push_back(value);
+        if self.recent.len() > CRASH_WINDOW {
+            self.recent.pop_front();
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Detector {
+    sigma: f32,
+    baselines: BTreeMap<&'static str, Baseline>,
+    //  the tick of the latest sample checked
+    checked: Option<u64>,
+}
+
+impl Detector {
+    pub fn new(sigma: f32) -> Self {
+        Self { sigma, baselines: BTreeMap::new(), checked: None }
+    }
+
+    /// Check the samples recorded since the last update.
+    pub fn update(&mut self, stats: &StatsStore) -> Vec<Anomaly> {
+        let mut ret = vec![];
+        let mut latest = self.checked;
+        for &name in ["population", "births"].iter().chain(TRAITS.iter()) {
+            let id = match stats.id(name) {
+                Some(id) => id,
+                None => continue,
+            };
+            let checked = self.checked;
+            let samples = stats.samples(id).iter().filter(|sample| checked.is_none_or(|checked| sample.tick > checked));
+            let baseline = self.baselines.entry(name).or_default();
+            for sample in samples {
+                latest = latest.max(Some(sample.tick));
+                let description = if baseline.quiet > 0 { None } else {
+                    check(name, sample.value, baseline, self.sigma)
+                };
+                if let Some(description) = description {
+                    ret.push(Anomaly { tick: sample.tick, description });
+                    baseline.quiet = COOLDOWN;
+                    baseline.recent.clear();
+                }
+                baseline.learn(sample.value);
+            }
+        }
+        self.checked = latest;
+        ret.sort_by_key(|anomaly| anomaly.tick);
+        ret
+    }
+}
+
+/// Describe how a new sample of a series is unusual, if it is.
+fn check(name: &str, value: f32, baseline: &Baseline, sigma: f32) -> Option<String> {
+    match name {
+        "population" => {
+            let highest = baseline.recent.iter().cloned().fold(0., f32::max);
+            Some(format!("Population crashed from {} to {}", highest, value))
+                .filter(|_| highest >= MIN_CRASH_POPULATION && value <= highest / 2.)
+        },
+        //  there is always at least a birth of deviation, or a quiet world would spike at its first birth
+        "births" => baseline.sigmas(value, 1.)
+            .filter(|&sigmas| sigmas > sigma)
+            .map(|_| format!("Births spiked to {}, usually {:.1}", value, baseline.mean)),
+        _ => baseline.sigmas(value, 0.01 * baseline.mean.abs())
+            .filter(|sigmas| sigmas.abs() > sigma)
+            .map(|sigmas| format!("{} shifted to {:.2}, {:+.1} sigma from {:.2}", name, value, sigmas, baseline.mean)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detector() {
+        let mut stats = StatsStore::new();
+        let (population, births, speed) = (stats.register("population"), stats.register("births"), stats.register("mean speed"));
+        for tick in 0..40 {
+            stats.push(population, tick, 40. + (tick % 3) as f32);
+            stats.push(births, tick, 1.);
+            stats.push(speed, tick, 50. + (tick % 2) as f32);
+        }
+        let mut detector = Detector::new(DEFAULT_SIGMA);
+        assert_eq!(detector.update(&stats), vec![]);
+        assert_eq!(detector.update(&stats), vec![], "samples are only checked once");
+
+        stats.push(population, 40, 15.);
+        stats.push(births, 40, 12.);
+        stats.push(speed, 40, 50.5);
+        let found: Vec<_> = detector.update(&stats).into_iter().map(|anomaly| anomaly.description).collect();
+        assert_eq!(found, vec!["Population crashed from 42 to 15".to_string(), "Births spiked to 12, usually 1.0".to_string()]);
+
+        //  a series that raised an anomaly stays quiet for a while
+        stats.push(population, 41, 5.);
+        stats.push(speed, 41, 80.);
+        let found: Vec<_> = detector.update(&stats).into_iter().map(|anomaly| (anomaly.tick, anomaly.description)).collect();
+        assert_eq!(found.len(), 1);
+        assert!(found[0].1.starts_with("mean speed shifted to 80.00"), "{:?}", found);
+    }
+}
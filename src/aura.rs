@@ -0,0 +1,148 @@
+//! Auras: fields a blob projects around itself that act on the blobs
+//! near it, most strongly close by and fading to nothing at their edge.
+//!
+//! A blob is born with the aura of its parent, if it has one, and an
+//! `aura_strength` gene from 0 to 1 that varies in children like the
+//! others. The strength scales both what the aura does and its cost,
+//! which is added to the blob's upkeep, so an aura is only kept by
+//! evolution where it pays:
+//!
+//! * `slow` slows the blobs that are not its kin, by up to `SLOWING`
+//!   of their speed
+//! * `heal` hands its own energy to its hungry kin, at up to `HEALING`
+//!   a second, while it stores more than half of what it can
+//! * `repel` pushes away the blobs that are not its kin and eat what it
+//!   eats
+//!
+//! Kin are blobs of the same species; a blob without one has none.
+//! An aura reaches `RADIUS` times the radius of its blob. Species give
+//! their blobs an aura with `aura = "heal"` and the range of its
+//! strength as the trait `aura_strength`, and random blobs are given a
+//! random one.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::aura::{self, Aura};
+//! # let pos = Vector2::new(100., 100.);
+//! let healer = sim.insert_blob(BlobConfig { aura: Aura::Heal, aura_strength: 0.8, ..BlobConfig::new(pos) });
+//! let field = aura::field(sim.get_blob(healer).unwrap());
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{physics::{Field, Force}, shapes, simulation::{Blob, Simulation}};
+
+/// How many times its radius an aura reaches.
+pub const RADIUS: f32 = 6.;
+/// The part of their speed a full slowing aura takes from blobs at its center.
+pub const SLOWING: f32 = 0.6;
+/// The energy a full healing aura hands over each second at its center.
+pub const HEALING: f32 = 0.5;
+/// How fast a full repelling aura accelerates blobs at its center.
+pub const REPELLING: f32 = 300.;
+/// The upkeep of a full aura, see `Blob::upkeep`.
+pub const UPKEEP: f32 = 0.5;
+
+/// What an aura does, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Aura {
+    #[default]
+    None,
+    Slow,
+    Heal,
+    Repel,
+}
+
+impl Aura {
+    pub const ALL: [Self; 4] = [Self::None, Self::Slow, Self::Heal, Self::Repel];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Slow => "slow",
+            Self::Heal => "heal",
+            Self::Repel => "repel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|aura| aura.name() == name)
+    }
+
+    /// Whether it acts on a blob, of a blob that has it.
+    pub fn affects(&self, owner: &Blob, other: &Blob) -> bool {
+        let kin = owner.species.is_some() && owner.species == other.species;
+        match self {
+            Self::None => false,
+            Self::Slow => !kin,
+            Self::Heal => kin,
+            Self::Repel => !kin && ((owner.diet.eats_food() && other.diet.eats_food()) || (owner.diet.eats_blobs() && other.diet.eats_blobs())),
+        }
+    }
+}
+
+/// The field of a blob's aura, if it has one of any strength: its
+/// force is what the aura does at its center.
+pub fn field(blob: &Blob) -> Option<Field> {
+    let strength = blob.aura_strength;
+    let force = match blob.aura {
+        _ if strength <= 0. => return None,
+        Aura::None => return None,
+        Aura::Slow => Force::Damping(SLOWING * strength),
+        Aura::Heal => Force::Damping(HEALING * strength),
+        Aura::Repel => Force::Radial(REPELLING * strength),
+    };
+    Some(Field { center: blob.pos(), radius: blob.radius() * RADIUS, force })
+}
+
+/// Draw the auras of a world as faint rings in the colors of their blobs.
+pub fn draw<D: RaylibDraw>(draw: &mut D, sim: &Simulation, zoom: f32) {
+    for (_, blob) in sim.blobs() {
+        if let Some(field) = field(blob) {
+            shapes::draw_circle_outline(draw, field.center, field.radius, 1., zoom, blob.color.fade(0.15 + 0.25 * blob.aura_strength));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{simulation::{BlobConfig, SimulationConfig}, species::Diet};
+
+    #[test]
+    fn test_aura() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(400., 400.)));
+        let species = |name: &str, pos: Vector2| BlobConfig { species: Some(name.to_string()), speed: 0., ..BlobConfig::new(pos) };
+        sim.insert_blob(BlobConfig { aura: Aura::Heal, aura_strength: 1., ..species("Grazer", Vector2::new(100., 100.)) });
+        let kin = sim.insert_blob(BlobConfig { hunger: 8., ..species("Grazer", Vector2::new(115., 100.)) });
+        let stranger = sim.insert_blob(BlobConfig { hunger: 8., ..species("Hunter", Vector2::new(100., 118.)) });
+        let far = sim.insert_blob(BlobConfig { hunger: 8., ..species("Grazer", Vector2::new(300., 300.)) });
+        let stored = sim.stored_energy();
+        sim.step();
+        let hunger = |sim: &Simulation, key| sim.get_blob(key).unwrap().hunger;
+        //  the kin nearby was fed by the healer, and nothing was made from nothing
+        assert!(hunger(&sim, kin) < hunger(&sim, stranger) && hunger(&sim, far) == hunger(&sim, stranger));
+        assert!(sim.stored_energy() < stored);
+
+        //  a repelling aura pushes competitors, not kin or blobs eating something else
+        let repeller = sim.insert_blob(BlobConfig { aura: Aura::Repel, aura_strength: 1., ..species("Grazer", Vector2::new(300., 100.)) });
+        let thief = sim.insert_blob(species("Thief", Vector2::new(315., 100.)));
+        let hunter = sim.insert_blob(BlobConfig { diet: Diet::Carnivore, ..species("Hunter", Vector2::new(280., 100.)) });
+        sim.step();
+        let velocity = |sim: &Simulation, key| sim.get_blob(key).unwrap().velocity();
+        assert!(velocity(&sim, thief).x > 0. && velocity(&sim, hunter) == Vector2::zero());
+        assert!(sim.get_blob(repeller).unwrap().upkeep() > sim.get_blob(thief).unwrap().upkeep());
+
+        //  a slowing aura slows strangers
+        let slower = sim.insert_blob(BlobConfig { aura: Aura::Slow, aura_strength: 1., ..species("Grazer", Vector2::new(100., 300.)) });
+        let runner = sim.insert_blob(BlobConfig { speed: 50., ..species("Runner", Vector2::new(110., 300.)) });
+        sim.trace_blob(Some(runner));
+        sim.step();
+        assert!(sim.trace().unwrap().entries()[0].slowed > 0.2 && field(sim.get_blob(slower).unwrap()).is_some());
+        assert_eq!(Aura::from_name("heal"), Some(Aura::Heal));
+    }
+}
@@ -0,0 +1,175 @@
+//! Pausing by itself at the moments that matter, so a run watched
+//! from afar stops where the watcher would have wanted to look.
+//!
+//! `--pause-on` takes a list of triggers separated by commas, as in
+//! `predation,dying,population<20`:
+//!
+//! * `predation` the first time a blob kills another
+//! * `dying` when a selected blob is about to starve, its hunger at
+//!   `DYING` of the most it can bear
+//! * `population<N` when the population falls below `N`, once it has
+//!   been as many or more
+//! * `species` when a species is counted for the first time, see
+//!   `census`, such as one added by hand, by a scenario or by the chat
+//!
+//! Each world is checked after every frame that ticked it, and the
+//! first trigger that fires pauses the simulation with a banner
+//! telling why, until it is resumed. A trigger fires once when what
+//! it waits for starts to happen, and `dying` and `population` again
+//! only after it stopped to; `predation` fires once a run.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut pause = AutoPause::new(autopause::parse("predation,population<20")?);
+//! step_worlds(...);
+//! if let Some(reason) = pause.check(&sim, first_tick, &selected) {
+//!     paused = true;
+//! }
+//! ```
+
+use std::collections::BTreeSet;
+
+use crate::{
+    keyed_set::Key,
+    simulation::{Blob, Event, Simulation},
+};
+
+/// The part of the most hunger a blob can bear at which it is about to starve.
+pub const DYING: f32 = 0.9;
+
+/// What a run pauses at, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Predation,
+    Dying,
+    Population(usize),
+    Species,
+}
+
+impl Trigger {
+    pub const NAMES: [&'static str; 4] = ["predation", "dying", "population<N", "species"];
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text.trim() {
+            "predation" => Ok(Self::Predation),
+            "dying" => Ok(Self::Dying),
+            "species" => Ok(Self::Species),
+            text => {
+                let count = text.strip_prefix("population<")
+                    .ok_or_else(|| format!("unknown trigger `{}`, expected one of {}", text, Self::NAMES.join(", ")))?;
+                count.trim().parse().map(Self::Population).map_err(|_| format!("invalid population `{}`", count))
+            },
+        }
+    }
+}
+
+/// The triggers of a list separated by commas.
+pub fn parse(text: &str) -> Result<Vec<Trigger>, String> {
+    text.split(',').filter(|text| !text.trim().is_empty()).map(Trigger::parse).collect()
+}
+
+/// The triggers of a world and what they have seen, see the module.
+#[derive(Debug, Clone, Default)]
+pub struct AutoPause {
+    triggers: Vec<Trigger>,
+    killed: bool,
+    //  whether the population is below the count, so it fires only as it falls
+    low: bool,
+    //  the selected blobs already about to starve
+    dying: BTreeSet<Key<Blob>>,
+    //  the species counted so far, none before the first check
+    species: Option<BTreeSet<String>>,
+}
+
+impl AutoPause {
+    pub fn new(triggers: Vec<Trigger>) -> Self {
+        //  a world starting below the count has not fallen yet
+        Self { triggers, low: true, ..Self::default() }
+    }
+
+    pub fn is_empty(&self) -> bool { self.triggers.is_empty() }
+
+    /// Why the world should pause after the ticks from a tick, if it
+    /// should, with the selected blobs of the world.
+    pub fn check(&mut self, sim: &Simulation, since: u64, selected: &[Key<Blob>]) -> Option<String> {
+        let mut reasons = vec![];
+        for &trigger in &self.triggers {
+            match trigger {
+                Trigger::Predation => if !self.killed {
+                    let kill = sim.events_since(since).find_map(|(_, event)| match event {
+                        Event::Killed { victim, killer } => Some((victim.clone(), killer.clone())),
+                        _ => None,
+                    });
+                    if let Some((victim, killer)) = kill {
+                        self.killed = true;
+                        let name = |name: Option<String>| name.unwrap_or_else(|| "a blob".to_string());
+                        reasons.push(format!("the first predation, {} killed {}", name(killer), name(victim)));
+                    }
+                },
+                Trigger::Dying => {
+                    let dying: BTreeSet<_> = selected.iter().copied()
+                        .filter(|&key| sim.get_blob(key).is_some_and(|blob| blob.hunger >= DYING * blob.max_hunger))
+                        .collect();
+                    for &key in dying.difference(&self.dying) {
+                        let name = sim.get_blob(key).and_then(|blob| blob.name.clone()).unwrap_or_else(|| "a selected blob".to_string());
+                        reasons.push(format!("{} is about to starve", name));
+                    }
+                    self.dying = dying;
+                },
+                Trigger::Population(count) => {
+                    let population = sim.blobs().count();
+                    let low = population < count;
+                    if low && !self.low {
+                        reasons.push(format!("the population fell below {}", count));
+                    }
+                    self.low = low;
+                },
+                Trigger::Species => {
+                    let species: BTreeSet<_> = sim.census().records().map(|(name, _)| name.to_string()).collect();
+                    if let Some(known) = &self.species {
+                        reasons.extend(species.difference(known).map(|name| format!("a new species, {}", name)));
+                    }
+                    self.species = Some(species);
+                },
+            }
+        }
+        (!reasons.is_empty()).then(|| reasons.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_autopause() {
+        assert_eq!(parse("predation, population<5,species").unwrap(), [Trigger::Predation, Trigger::Population(5), Trigger::Species]);
+        assert!(parse("population<many").unwrap_err().contains("many"));
+        assert!(parse("sunrise").unwrap_err().contains("expected one of"));
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let ada = sim.insert_blob(BlobConfig { name: Some("Ada".to_string()), ..BlobConfig::new(Vector2::new(100., 100.)) });
+        sim.insert_blob(BlobConfig { species: Some("Grazer".to_string()), ..BlobConfig::new(Vector2::new(200., 200.)) });
+        sim.step();
+        let mut pause = AutoPause::new(parse("dying,population<2,species").unwrap());
+        assert_eq!(pause.check(&sim, 0, &[ada]), None);
+
+        //  each fires as what it waits for starts, and not again while it lasts
+        let blob = sim.get_blob_mut(ada).unwrap();
+        blob.hunger = blob.max_hunger * 0.95;
+        assert_eq!(pause.check(&sim, 1, &[ada]).as_deref(), Some("Ada is about to starve"));
+        assert_eq!(pause.check(&sim, 1, &[ada]), None);
+        sim.insert_blob(BlobConfig { species: Some("Hunter".to_string()), ..BlobConfig::new(Vector2::new(50., 250.)) });
+        sim.step();
+        assert_eq!(pause.check(&sim, 1, &[ada]).as_deref(), Some("a new species, Hunter"));
+        let keys: Vec<_> = sim.blobs().map(|(key, _)| key).filter(|&key| key != ada).collect();
+        for key in keys {
+            sim.remove_blob(key);
+        }
+        assert_eq!(pause.check(&sim, 2, &[ada]).as_deref(), Some("the population fell below 2"));
+        assert_eq!(pause.check(&sim, 2, &[ada]), None);
+    }
+}
@@ -0,0 +1,212 @@
+//! The `bench` subcommand, which measures how fast the simulation runs
+//! on this machine, to size worlds to hardware and to compare releases.
+//!
+//! Unlike the brief benchmark of the first launch, see `benchmark`, it
+//! runs a world of as many random blobs as asked for as many ticks,
+//! without a window, and reports the ticks a second, where the time of
+//! a tick went, see `profile`, and the most memory held:
+//!
+//! ```text
+//! 10000 blobs, 1000 ticks in 12.31 s, 81.2 ticks/s
+//! phase          time  per tick  share
+//! hooks        0.01 s   0.01 ms     0%
+//! collisions   3.20 s   3.20 ms    26%
+//! brains       5.91 s   5.91 ms    48%
+//! peak memory 48.0 MiB estimated, 131.2 MiB resident
+//! ```
+//!
+//! The world grows with the blobs so that they are as crowded at any
+//! count, see `world_size`, and food is put back as it is eaten so that
+//! the work of a tick stays alike. Only the steps are timed. The
+//! estimated memory is the largest total of the world's stores, see
+//! `memory`, and the resident memory the largest the process held, as
+//! far as the system tells. The report can be written as JSON too, see
+//! `write_json`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::io;
+//! # use blobs::bench::{self, Bench};
+//! let report = bench::run(&Bench { blobs: 10_000, ..Bench::default() });
+//! bench::write_report(&mut io::stdout(), &report)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use raylib::prelude::*;
+
+use crate::{
+    memory::{self, Usage},
+    profile::Profile,
+    simulation::{BlobConfig, Simulation, SimulationConfig},
+};
+
+/// Room each blob is given, in square units.
+const ROOM: f32 = 1600.;
+/// Foods kept in the world for each blob.
+const FOOD_SHARE: f32 = 0.5;
+/// Ticks between samples of the estimated memory.
+const SAMPLE_PERIOD: u64 = 100;
+
+/// What to run, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bench {
+    pub blobs: usize,
+    pub ticks: u64,
+    pub seed: u64,
+}
+
+impl Default for Bench {
+    fn default() -> Self {
+        Self { blobs: 1000, ticks: 1000, seed: 1 }
+    }
+}
+
+/// A square world with room for the blobs, within the sizes a world may be.
+pub fn world_size(blobs: usize) -> Vector2 {
+    let side = (blobs as f32 * ROOM).sqrt().clamp(300., 20000.);
+    Vector2::new(side, side)
+}
+
+/// What a run measured.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub bench: Bench,
+    /// The time spent stepping, over all ticks.
+    pub elapsed: Duration,
+    pub profile: Profile,
+    /// The blobs alive when the run ended.
+    pub blobs: usize,
+    /// The most bytes the world's stores were estimated to hold.
+    pub peak_estimated: usize,
+    /// The most bytes the process held in memory, if the system tells.
+    pub peak_resident: Option<usize>,
+}
+
+impl Report {
+    pub fn ticks_per_second(&self) -> f64 {
+        self.bench.ticks as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Run a world for the ticks, see the module.
+pub fn run(bench: &Bench) -> Report {
+    let size = world_size(bench.blobs);
+    let mut sim = Simulation::new(SimulationConfig { seed: bench.seed, ..SimulationConfig::new(size) });
+    let mut rng = StdRng::from_rng(sim.rng()).unwrap();
+    for _ in 0..bench.blobs {
+        sim.insert_blob(BlobConfig::randomized(size, &mut rng));
+    }
+    let foods = (bench.blobs as f32 * FOOD_SHARE) as usize;
+    sim.set_profiling(true);
+
+    let mut elapsed = Duration::ZERO;
+    let mut peak_estimated = 0;
+    for tick in 0..bench.ticks {
+        while sim.foods().count() < foods {
+            let pos = Vector2::new(rng.gen(), rng.gen()) * size;
+            sim.insert_food(pos);
+        }
+        let start = Instant::now();
+        sim.step();
+        elapsed += start.elapsed();
+        if tick % SAMPLE_PERIOD == 0 || tick + 1 == bench.ticks {
+            let mut usage = Usage::new();
+            sim.account(&mut usage, "");
+            peak_estimated = peak_estimated.max(usage.total());
+        }
+    }
+    Report {
+        bench: bench.clone(),
+        elapsed,
+        profile: sim.profile().cloned().unwrap_or_default(),
+        blobs: sim.blobs().count(),
+        peak_estimated,
+        peak_resident: peak_resident(),
+    }
+}
+
+/// The most memory the process has held, from `/proc` where there is one.
+fn peak_resident() -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kibibytes: usize = line["VmHWM:".len()..].trim().trim_end_matches("kB").trim().parse().ok()?;
+    Some(kibibytes << 10)
+}
+
+/// Write the report as a table, see the module.
+pub fn write_report<W: Write>(out: &mut W, report: &Report) -> io::Result<()> {
+    let Bench { blobs, ticks, .. } = report.bench;
+    writeln!(out, "{} blobs, {} ticks in {:.2} s, {:.1} ticks/s", blobs, ticks, report.elapsed.as_secs_f64(), report.ticks_per_second())?;
+    let total = report.profile.total().as_secs_f64().max(f64::EPSILON);
+    let ticks = report.profile.ticks().max(1) as f64;
+    writeln!(out, "phase          time  per tick  share")?;
+    for &(phase, time) in report.profile.phases() {
+        let seconds = time.as_secs_f64();
+        writeln!(out, "{:10} {:>6.2} s  {:>5.2} ms  {:>4.0}%", phase, seconds, 1000. * seconds / ticks, 100. * seconds / total)?;
+    }
+    let resident = report.peak_resident.map_or_else(String::new, |bytes| format!(", {} resident", memory::format_bytes(bytes)));
+    writeln!(out, "peak memory {} estimated{}", memory::format_bytes(report.peak_estimated), resident)
+}
+
+/// Write the report as a JSON object, with times in seconds and memory in bytes.
+pub fn write_json<W: Write>(out: &mut W, report: &Report) -> io::Result<()> {
+    let Bench { blobs, ticks, seed } = report.bench;
+    let phases: Vec<_> = report.profile.phases().iter()
+        .map(|(phase, time)| format!(r#""{}":{}"#, phase, time.as_secs_f64()))
+        .collect();
+    let resident = report.peak_resident.map_or_else(|| "null".to_string(), |bytes| bytes.to_string());
+    writeln!(out,
+        r#"{{"version":"{}","blobs":{},"ticks":{},"seed":{},"seconds":{},"ticks_per_second":{},"blobs_at_end":{},"phases":{{{}}},"peak_estimated_bytes":{},"peak_resident_bytes":{}}}"#,
+        env!("CARGO_PKG_VERSION"), blobs, ticks, seed, report.elapsed.as_secs_f64(), report.ticks_per_second(), report.blobs,
+        phases.join(","), report.peak_estimated, resident,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bench() {
+        assert!((world_size(1000).x - 1264.9).abs() < 0.1);
+        assert_eq!(world_size(1), Vector2::new(300., 300.));
+
+        let bench = Bench { blobs: 50, ticks: 20, seed: 3 };
+        let report = run(&bench);
+        assert_eq!(report.profile.ticks(), 20);
+        let phases: Vec<_> = report.profile.phases().iter().map(|&(phase, _)| phase).collect();
+        assert_eq!(phases, ["hooks", "collisions", "brains", "forces", "physics", "lifecycle", "upkeep"]);
+        assert!(report.profile.total() <= report.elapsed && report.peak_estimated > 0);
+        //  profiling leaves the world as it would be
+        let mut profiled = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        profiled.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let mut plain = profiled.fork();
+        profiled.set_profiling(true);
+        for _ in 0..10 {
+            profiled.step();
+            plain.step();
+        }
+        assert_eq!(profiled.state_hash(), plain.state_hash());
+
+        let mut out = vec![];
+        write_report(&mut out, &report).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        assert!(table.starts_with("50 blobs, 20 ticks in "));
+        assert_eq!(table.lines().count(), phases.len() + 3);
+        assert!(table.lines().last().unwrap().starts_with("peak memory "));
+
+        let mut out = vec![];
+        write_json(&mut out, &report).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert!(json.contains(r#""blobs":50,"ticks":20,"seed":3,"#) && json.contains(r#""phases":{"hooks":"#));
+        assert!(json.trim_end().ends_with('}'));
+    }
+}
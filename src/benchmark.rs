@@ -0,0 +1,142 @@
+//! A brief benchmark of the machine, run once on the first launch.
+//!
+//! A standard scene is simulated and drawn at each quality tier while
+//! the window opens, and from how long that takes the entities the
+//! machine can simulate and the quality it can draw at are recommended
+//! and saved to `PATH`, so later launches start from them without
+//! measuring again. Deleting the file measures again.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let recommendation = match Recommendation::load(benchmark::PATH)? {
+//!     Some(recommendation) => recommendation,
+//!     None => benchmark::recommend(&measure(), target_fps),
+//! };
+//! ```
+
+use std::{fmt, fs, io, str::FromStr};
+
+use crate::quality::Tier;
+
+/// Where the recommendation is kept between launches.
+pub const PATH: &str = "benchmark.txt";
+/// The part of a frame the simulation may take.
+const SIMULATION_SHARE: f32 = 0.5;
+/// The part of a frame drawing may take.
+const DRAWING_SHARE: f32 = 0.5;
+/// The part of a frame drawing may take at the full tier, as
+/// post-processing is not drawn offscreen but costs as much again.
+const FULL_SHARE: f32 = 0.25;
+const MIN_ENTITIES: usize = 500;
+const MAX_ENTITIES: usize = 100_000;
+
+/// How long the standard scene took.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurement {
+    /// Blobs and foods in the scene.
+    pub entities: usize,
+    /// Seconds a tick of the scene took.
+    pub tick: f32,
+    /// Seconds drawing the scene took at each tier but the full one.
+    pub frames: Vec<(Tier, f32)>,
+}
+
+/// Settings suited to the machine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Recommendation {
+    /// Blobs and foods, as the entity budget.
+    pub entities: usize,
+    /// The tier to start drawing at.
+    pub tier: Tier,
+}
+
+impl Recommendation {
+    /// The saved recommendation, if the benchmark was run before.
+    pub fn load(path: &str) -> Result<Option<Self>, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => text.parse().map(Some).map_err(|e| format!("{}: {}", path, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Recommendation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "# measured on the first launch, delete to measure again")?;
+        writeln!(f, "entities = {}", self.entities)?;
+        writeln!(f, "tier = {}", self.tier.name())
+    }
+}
+
+impl FromStr for Recommendation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut entities = None;
+        let mut tier = None;
+        for line in s.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            let (key, value) = line.split_once('=').ok_or_else(|| format!("expected `key = value`, got `{}`", line))?;
+            let value = value.trim();
+            match key.trim() {
+                "entities" => entities = Some(value.parse().map_err(|_| format!("invalid number `{}`", value))?),
+                "tier" => tier = Some(Tier::from_name(value).ok_or_else(|| format!("unknown tier `{}`", value))?),
+                key => return Err(format!("unknown key `{}`", key)),
+            }
+        }
+        Ok(Self {
+            entities: entities.ok_or("missing `entities`")?,
+            tier: tier.ok_or("missing `tier`")?,
+        })
+    }
+}
+
+/// The settings that hold a frame rate on the machine measured.
+pub fn recommend(measurement: &Measurement, target_fps: u32) -> Recommendation {
+    let budget = 1. / target_fps as f32;
+    //  ticks take about as long as there are entities
+    let per_entity = measurement.tick / measurement.entities as f32;
+    let entities = (SIMULATION_SHARE * budget / per_entity) as usize;
+    let entities = (entities / 100 * 100).clamp(MIN_ENTITIES, MAX_ENTITIES);
+    let fits = |tier: Tier, share: f32| measurement.frames.iter()
+        .any(|&(measured, seconds)| measured == tier && seconds <= share * budget);
+    let tier = if fits(Tier::NoEffects, FULL_SHARE) {
+        Tier::Full
+    } else {
+        Tier::ALL.iter().cloned()
+            .find(|&tier| fits(tier, DRAWING_SHARE))
+            .unwrap_or(Tier::NoHeatmap)
+    };
+    Recommendation { entities, tier }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommend() {
+        let measurement = |tick: f32, frame: f32| Measurement {
+            entities: 1000,
+            tick,
+            frames: vec![(Tier::NoEffects, frame), (Tier::NoLabels, frame / 2.), (Tier::NoHeatmap, frame / 4.)],
+        };
+        //  a tick of a thousand entities in 1ms leaves room for about 8300 in half of 1/60s
+        let fast = recommend(&measurement(0.001, 0.001), 60);
+        assert_eq!(fast, Recommendation { entities: 8300, tier: Tier::Full });
+        assert_eq!(recommend(&measurement(0.001, 0.006), 60).tier, Tier::NoEffects);
+        assert_eq!(recommend(&measurement(0.001, 0.012), 60).tier, Tier::NoLabels);
+        assert_eq!(recommend(&measurement(0.001, 0.03), 60).tier, Tier::NoHeatmap);
+        let slow = recommend(&measurement(1., 1.), 60);
+        assert_eq!(slow.entities, MIN_ENTITIES);
+
+        assert_eq!(fast.to_string().parse(), Ok(fast));
+        assert!("entities = 10".parse::<Recommendation>().is_err());
+        assert!("entities = 10\ntier = best".parse::<Recommendation>().is_err());
+    }
+}
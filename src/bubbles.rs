@@ -0,0 +1,282 @@
+//! Speech bubbles over blobs, saying what they are up to.
+//!
+//! With `Shift+D`, a blob that starts doing something else says so in
+//! a short line over it: `food!` as it starts foraging, `run!` as it
+//! flees, `zzz` as it goes back to wandering and `so hungry` as it is
+//! about to starve. The lines are picked in turn from a pack, the one
+//! below unless there is a `bubbles.txt` beside the game, with a line
+//! for each thing said, as in
+//!
+//! ```text
+//! # what a blob says as it starts to
+//! forage: food!
+//! flee: run!
+//! wander: zzz
+//! hungry: so hungry
+//! ```
+//!
+//! A blob says nothing again for a few seconds after it spoke, so a
+//! blob torn between two things does not chatter, and the oldest
+//! bubble gives way once too many are shown. Bubbles follow their
+//! blob, and are not drawn off the view or in views zoomed out far.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut bubbles = Bubbles::new(Pack::load(bubbles::PATH)?);
+//! sim.step();
+//! bubbles.collect(&sim);
+//! bubbles.update(frame_seconds);
+//! bubbles.draw(&mut draw, &sim, view, rect, &theme, &fonts);
+//! ```
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    io,
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    fonts::Fonts,
+    keyed_set::Key,
+    simulation::{Blob, Mood, Simulation},
+    theme::Theme,
+    window::Camera,
+};
+
+/// The pack replacing the one built in, if there is one.
+pub const PATH: &str = "bubbles.txt";
+
+/// The pack built in, see the module.
+const DEFAULT: &str = "\
+forage: food!
+forage: snack time
+forage: mine!
+flee: run!
+flee: eek!
+flee: nope
+wander: zzz
+wander: la la la
+wander: hmm
+hungry: so hungry
+hungry: starving...
+";
+
+/// What a blob is up to, as far as what it says goes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    Forage,
+    Flee,
+    Wander,
+    Hungry,
+}
+
+impl State {
+    pub const ALL: [Self; 4] = [Self::Forage, Self::Flee, Self::Wander, Self::Hungry];
+    /// The part of the most hunger it can bear at which a blob is hungry.
+    const HUNGRY: f32 = 0.8;
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Forage => "forage",
+            Self::Flee => "flee",
+            Self::Wander => "wander",
+            Self::Hungry => "hungry",
+        }
+    }
+
+    /// What a blob is up to, hunger first.
+    pub fn of(blob: &Blob) -> Self {
+        if blob.hunger >= Self::HUNGRY * blob.max_hunger {
+            return Self::Hungry;
+        }
+        match blob.mood() {
+            Mood::Forage => Self::Forage,
+            Mood::Flee => Self::Flee,
+            Mood::Wander => Self::Wander,
+        }
+    }
+}
+
+/// The lines said for each state, see the module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pack {
+    lines: [Vec<String>; 4],
+}
+
+impl Default for Pack {
+    fn default() -> Self { Self::parse(DEFAULT).unwrap() }
+}
+
+impl Pack {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines: [Vec<String>; 4] = Default::default();
+        for (i, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, said) = line.split_once(':').ok_or_else(|| format!("line {}: expected `state: line`", i))?;
+            let state = State::ALL.iter().position(|state| state.name() == name.trim())
+                .ok_or_else(|| format!("line {}: unknown state `{}`", i, name.trim()))?;
+            lines[state].push(said.trim().to_string());
+        }
+        Ok(Self { lines })
+    }
+
+    /// The pack at a path, or the one built in if there is none there.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {}", path, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    pub fn lines(&self, state: State) -> &[String] {
+        &self.lines[State::ALL.iter().position(|&s| s == state).unwrap()]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Bubble {
+    blob: Key<Blob>,
+    text: String,
+    //  seconds since it appeared
+    age: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Bubbles {
+    pack: Pack,
+    //  oldest first
+    bubbles: VecDeque<Bubble>,
+    //  what each blob was last up to, and the seconds until it may speak again
+    states: BTreeMap<Key<Blob>, (State, f32)>,
+    //  how many lines were said, to pick the next in turn
+    said: usize,
+}
+
+impl Bubbles {
+    /// How many seconds a bubble is shown.
+    const LIFETIME: f32 = 1.6;
+    /// How many seconds a blob says nothing after it spoke.
+    const COOLDOWN: f32 = 4.;
+    /// How many bubbles are shown at most.
+    const MAX_BUBBLES: usize = 60;
+    /// The zoom below which bubbles are not drawn.
+    const MIN_ZOOM: f32 = 0.75;
+    const FONT_SIZE: i32 = 14;
+    const PADDING: i32 = 4;
+
+    pub fn new(pack: Pack) -> Self {
+        Self { pack, bubbles: VecDeque::new(), states: BTreeMap::new(), said: 0 }
+    }
+
+    /// Let the blobs that started something else in the world's last
+    /// tick say so.
+    pub fn collect(&mut self, sim: &Simulation) {
+        self.states.retain(|&key, _| sim.get_blob(key).is_some());
+        for (key, blob) in sim.blobs() {
+            let state = State::of(blob);
+            let (was, quiet) = self.states.entry(key).or_insert((state, 0.));
+            if *was == state {
+                continue;
+            }
+            *was = state;
+            let lines = self.pack.lines(state);
+            if *quiet > 0. || lines.is_empty() {
+                continue;
+            }
+            *quiet = Self::COOLDOWN;
+            let text = lines[self.said % lines.len()].clone();
+            self.said += 1;
+            self.bubbles.retain(|bubble| bubble.blob != key);
+            if self.bubbles.len() == Self::MAX_BUBBLES {
+                self.bubbles.pop_front();
+            }
+            self.bubbles.push_back(Bubble { blob: key, text, age: 0. });
+        }
+    }
+
+    /// Age the bubbles and cooldowns by some seconds, dropping the
+    /// bubbles that faded out.
+    pub fn update(&mut self, seconds: f32) {
+        for bubble in &mut self.bubbles {
+            bubble.age += seconds;
+        }
+        self.bubbles.retain(|bubble| bubble.age < Self::LIFETIME);
+        for (_, quiet) in self.states.values_mut() {
+            *quiet = (*quiet - seconds).max(0.);
+        }
+    }
+
+    /// Draw the bubbles over the blobs of a world in a view on the
+    /// window, in screen coordinates.
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, sim: &Simulation, view: Camera, rect: Rectangle, theme: &Theme, fonts: &Fonts) {
+        if view.zoom < Self::MIN_ZOOM { return; }
+        for bubble in &self.bubbles {
+            let blob = match sim.get_blob(bubble.blob) {
+                Some(blob) => blob,
+                None => continue,
+            };
+            let top = view.world_to_screen(blob.pos() - Vector2::new(0., blob.radius()));
+            if !rect.check_collision_point_rec(top) { continue; }
+            let fade = 1. - (bubble.age / Self::LIFETIME).powi(4);
+            let width = fonts.measure(&bubble.text, Self::FONT_SIZE) + 2 * Self::PADDING;
+            let height = Self::FONT_SIZE + 2 * Self::PADDING;
+            let (left, bottom) = (top.x as i32 - width / 2, top.y as i32 - 8);
+            let bounds = Rectangle::new(left as f32, (bottom - height) as f32, width as f32, height as f32);
+            draw.draw_rectangle_rounded(bounds, 0.5, 6, theme.panel.fade(fade));
+            draw.draw_triangle(
+                Vector2::new(top.x - 4., bottom as f32),
+                Vector2::new(top.x, top.y - 2.),
+                Vector2::new(top.x + 4., bottom as f32),
+                theme.panel.fade(fade),
+            );
+            fonts.draw_text(draw, &bubble.text, left + Self::PADDING, bottom - height + Self::PADDING, Self::FONT_SIZE, theme.text.fade(fade));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_bubbles() {
+        let pack = Pack::default();
+        assert_eq!(pack.lines(State::Forage)[0], "food!");
+        assert_eq!(Pack::parse("# comment\n\nflee: away!").unwrap().lines(State::Flee), ["away!"]);
+        assert!(Pack::parse("sleep: zzz").unwrap_err().contains("unknown state"));
+        assert!(Pack::parse("zzz").unwrap_err().starts_with("line 1"));
+        assert_eq!(Pack::load("no-such-bubbles.txt").unwrap(), pack);
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let ada = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let mut bubbles = Bubbles::new(Pack::parse("hungry: so hungry\nwander: zzz").unwrap());
+        bubbles.collect(&sim);
+        assert!(bubbles.bubbles.is_empty());
+
+        //  a blob speaks as it starts something else, and not again until it cooled down
+        let blob = sim.get_blob_mut(ada).unwrap();
+        blob.hunger = blob.max_hunger * 0.85;
+        bubbles.collect(&sim);
+        assert_eq!(bubbles.bubbles[0].text, "so hungry");
+        sim.get_blob_mut(ada).unwrap().hunger = 0.;
+        bubbles.collect(&sim);
+        assert_eq!(bubbles.bubbles.len(), 1);
+        bubbles.update(Bubbles::COOLDOWN);
+        assert!(bubbles.bubbles.is_empty());
+        let blob = sim.get_blob_mut(ada).unwrap();
+        blob.hunger = blob.max_hunger * 0.85;
+        bubbles.collect(&sim);
+        assert_eq!(bubbles.bubbles.len(), 1);
+
+        sim.remove_blob(ada);
+        bubbles.collect(&sim);
+        assert!(bubbles.states.is_empty());
+    }
+}
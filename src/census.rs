@@ -0,0 +1,161 @@
+//! The species of a world counted after every tick, with a record of
+//! each that died out.
+//!
+//! A species is counted from the first tick it has a blob, noting the
+//! most blobs it ever had at once and the latest generation any of
+//! them reached. When its last blob is gone the record is closed as
+//! an `Extinction`, kept for the rest of the run, logged as
+//! `Event::Extinct` and bookmarked on the timeline; a species added
+//! again afterwards, by hand or by a scenario, starts a record of
+//! its own.
+//!
+//! Renaming a species, with shift and `S`, renames its blobs and its
+//! record. Renamed to the name of another living species, it merges
+//! with it: the two count as one from then on, counted from the
+//! earlier of their first ticks. Blobs without a species are not
+//! counted. The census is history, like the events, and is not saved:
+//! a loaded world counts its species from the tick it was loaded at.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! sim.step();
+//! for extinction in sim.census().extinctions() {
+//!     println!("{} died out at tick {}, having been {} at most", extinction.species, extinction.tick, extinction.peak);
+//! }
+//! sim.rename_species("Grazer", "Browser");
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::simulation::Blob;
+
+/// What is known of a living species.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub population: usize,
+    /// The most blobs it had at once.
+    pub peak: usize,
+    /// The tick it was first counted at.
+    pub first_tick: u64,
+    /// The latest generation a blob of it reached.
+    pub generations: u32,
+}
+
+/// A species that died out, with how it did while it lived.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extinction {
+    pub species: String,
+    /// The tick its last blob was gone at.
+    pub tick: u64,
+    pub first_tick: u64,
+    pub peak: usize,
+    pub generations: u32,
+}
+
+/// The living species of a world and those that died out, see the module.
+#[derive(Debug, Clone, Default)]
+pub struct Census {
+    records: BTreeMap<String, Record>,
+    extinctions: Vec<Extinction>,
+}
+
+impl Census {
+    /// Count the blobs of a tick, returning the species that died out
+    /// since the last count.
+    pub fn update<'a>(&mut self, tick: u64, blobs: impl Iterator<Item=&'a Blob>) -> Vec<Extinction> {
+        let mut counts: BTreeMap<&str, (usize, u32)> = BTreeMap::new();
+        for blob in blobs {
+            if let Some(species) = &blob.species {
+                let (population, generations) = counts.entry(species).or_default();
+                *population += 1;
+                *generations = (*generations).max(blob.generation);
+            }
+        }
+        for (&species, &(population, generations)) in &counts {
+            match self.records.get_mut(species) {
+                Some(record) => {
+                    record.population = population;
+                    record.peak = record.peak.max(population);
+                    record.generations = record.generations.max(generations);
+                },
+                None => {
+                    self.records.insert(species.to_string(), Record { population, peak: population, first_tick: tick, generations });
+                },
+            }
+        }
+        let extinct: Vec<String> = self.records.keys().filter(|species| !counts.contains_key(species.as_str())).cloned().collect();
+        let mut ret = vec![];
+        for species in extinct {
+            let Record { first_tick, peak, generations, .. } = self.records.remove(&species).unwrap();
+            ret.push(Extinction { species, tick, first_tick, peak, generations });
+        }
+        self.extinctions.extend(ret.iter().cloned());
+        ret
+    }
+
+    /// The living species by name.
+    pub fn records(&self) -> impl Iterator<Item=(&str, &Record)> {
+        self.records.iter().map(|(species, record)| (species.as_str(), record))
+    }
+
+    /// The species that died out, in the order they did.
+    pub fn extinctions(&self) -> &[Extinction] { &self.extinctions }
+
+    /// Give a species another name, merging it with the species of
+    /// that name if there is one.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        let record = match self.records.remove(from) {
+            Some(record) => record,
+            None => return,
+        };
+        match self.records.get_mut(to) {
+            Some(other) => {
+                other.population += record.population;
+                other.peak = other.peak.max(record.peak).max(other.population);
+                other.first_tick = other.first_tick.min(record.first_tick);
+                other.generations = other.generations.max(record.generations);
+            },
+            None => {
+                self.records.insert(to.to_string(), record);
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::prelude::*;
+
+    use crate::simulation::{BlobConfig, Event, Simulation, SimulationConfig};
+
+    #[test]
+    fn test_census() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let species = |name: &str, x: f32| BlobConfig { species: Some(name.to_string()), ..BlobConfig::new(Vector2::new(x, 100.)) };
+        let grazers: Vec<_> = (0..3).map(|i| sim.insert_blob(species("Grazer", 20. + 30. * i as f32))).collect();
+        sim.insert_blob(species("Hunter", 200.));
+        sim.insert_blob(species("Stalker", 250.));
+        sim.step();
+        let population = |sim: &Simulation, name: &str| sim.census().records().find(|&(species, _)| species == name).map(|(_, record)| record.population);
+        assert_eq!((population(&sim, "Grazer"), population(&sim, "Hunter")), (Some(3), Some(1)));
+
+        for grazer in grazers {
+            sim.remove_blob(grazer);
+        }
+        sim.step();
+        assert_eq!(population(&sim, "Grazer"), None);
+        let extinction = &sim.census().extinctions()[0];
+        assert_eq!((extinction.species.as_str(), extinction.first_tick, extinction.peak), ("Grazer", 0, 3));
+        assert!(sim.events_since(0).any(|(_, event)| matches!(event, Event::Extinct(e) if e == extinction)));
+
+        //  renamed to a living species, a species merges with it
+        assert_eq!(sim.rename_species("Stalker", "Hunter"), 1);
+        sim.step();
+        assert_eq!((population(&sim, "Hunter"), population(&sim, "Stalker")), (Some(2), None));
+        assert_eq!(sim.census().extinctions().len(), 1);
+    }
+}
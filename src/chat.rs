@@ -0,0 +1,238 @@
+//! Chat commands that change the first world, for streams where the
+//! viewers drive evolution.
+//!
+//! `--chat <channel>` joins the chat of a Twitch channel, reading it
+//! without an account, or of a channel on another IRC server given
+//! with `--chat-server <host:port>`. Viewers change the world with:
+//!
+//! * `!spawn [name]` - a random blob, named as asked if the name is
+//!   letters, digits, `-` and `_`, with the viewer's name above it
+//! * `!feed` - a handful of food at a random place
+//! * `!smite` - a random blob struck down, leaving nothing
+//!
+//! Each viewer may give a command every `VIEWER_COOLDOWN`, and the
+//! chat as a whole `RATE` commands a second, in bursts of up to
+//! `BURST`; commands over the limits are ignored. Chat cannot change
+//! worlds whose input is recorded, replayed or shared in lockstep, as
+//! the other side would not see the commands.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut chat = Chat::connect("irc.chat.twitch.tv:6667".to_string(), "somechannel".to_string());
+//! for request in chat.poll(&sim, time::Instant::now())? {
+//!     if let Some(key) = apply(&mut sim, &request) {
+//!         chat.attribute(key, &request.viewer);
+//!     }
+//! }
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Write},
+    net::TcpStream,
+    sync::mpsc,
+    thread,
+    time,
+};
+
+use raylib::prelude::*;
+
+use crate::{fonts::Fonts, keyed_set::Key, simulation::{Simulation, Blob}, theme::Theme, window::Camera};
+
+/// The server of Twitch chat, without encryption.
+pub const TWITCH: &str = "irc.chat.twitch.tv:6667";
+/// How long a viewer waits between commands.
+pub const VIEWER_COOLDOWN: time::Duration = time::Duration::from_secs(10);
+/// How many commands a second the chat gives at most, in the long run.
+pub const RATE: f32 = 1.;
+/// How many commands the chat may give at once.
+pub const BURST: f32 = 5.;
+/// The longest name a blob may be given.
+const MAX_NAME: usize = 20;
+const FONT_SIZE: i32 = 14;
+
+/// What a viewer asked for.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Intervention {
+    Spawn(Option<String>),
+    Feed,
+    Smite,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub viewer: String,
+    pub intervention: Intervention,
+}
+
+/// The sender and text of a message to a channel, from a line sent by
+/// an IRC server such as `:ann!ann@host PRIVMSG #channel :hello`.
+pub fn parse_message(line: &str) -> Option<(&str, &str)> {
+    //  Twitch may start lines with tags
+    let line = if line.starts_with('@') { line.split_once(' ')?.1 } else { line };
+    let (prefix, rest) = line.strip_prefix(':')?.split_once(' ')?;
+    let (_, text) = rest.strip_prefix("PRIVMSG ")?.split_once(" :")?;
+    let viewer = prefix.split('!').next()?;
+    Some((viewer, text.trim_end()))
+}
+
+/// The intervention a message asks for, if it is a command.
+pub fn parse_command(text: &str) -> Option<Intervention> {
+    let mut words = text.split_whitespace();
+    match words.next()? {
+        "!spawn" => {
+            let valid = |name: &&str| name.len() <= MAX_NAME && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+            Some(Intervention::Spawn(words.next().filter(valid).map(str::to_string)))
+        },
+        "!feed" => Some(Intervention::Feed),
+        "!smite" => Some(Intervention::Smite),
+        _ => None,
+    }
+}
+
+/// Limits the commands of each viewer and of the chat as a whole.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    //  when each viewer last gave a command that was taken
+    last: HashMap<String, time::Instant>,
+    //  commands the chat may give now, refilled at `RATE` a second
+    tokens: f32,
+    refilled: Option<time::Instant>,
+}
+
+impl RateLimit {
+    pub fn new() -> Self {
+        Self { last: HashMap::new(), tokens: BURST, refilled: None }
+    }
+
+    /// Whether a viewer may give a command now, taking it if so.
+    pub fn allow(&mut self, viewer: &str, now: time::Instant) -> bool {
+        let elapsed = self.refilled.map_or(0., |refilled| now.duration_since(refilled).as_secs_f32());
+        self.tokens = (self.tokens + elapsed * RATE).min(BURST);
+        self.refilled = Some(now);
+        let waited = self.last.get(viewer).is_none_or(|&last| now.duration_since(last) >= VIEWER_COOLDOWN);
+        if !waited || self.tokens < 1. {
+            return false;
+        }
+        self.tokens -= 1.;
+        self.last.insert(viewer.to_string(), now);
+        true
+    }
+}
+
+impl Default for RateLimit {
+    fn default() -> Self { Self::new() }
+}
+
+pub struct Chat {
+    receiver: mpsc::Receiver<Result<Request, String>>,
+    limit: RateLimit,
+    //  the viewers who spawned blobs that are alive
+    attributions: HashMap<Key<Blob>, String>,
+}
+
+impl Chat {
+    /// Join a channel on a server, reading it on a thread of its own.
+    pub fn connect(server: String, channel: String) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let error = match read(&server, &channel, &sender) {
+                Ok(()) => return,
+                Err(e) => format!("{}: {}", server, e),
+            };
+            let _ = sender.send(Err(error));
+        });
+        Self { receiver, limit: RateLimit::new(), attributions: HashMap::new() }
+    }
+
+    /// The requests taken since the last poll, leaving out those over
+    /// the limits, or why the chat stopped.
+    pub fn poll(&mut self, sim: &Simulation, now: time::Instant) -> Result<Vec<Request>, String> {
+        self.attributions.retain(|&key, _| sim.contains_blob(key));
+        let mut ret = vec![];
+        loop {
+            match self.receiver.try_recv() {
+                Ok(Ok(request)) if self.limit.allow(&request.viewer, now) => ret.push(request),
+                Ok(Ok(_)) => (),
+                Ok(Err(e)) => return Err(e),
+                Err(mpsc::TryRecvError::Empty) => return Ok(ret),
+                Err(mpsc::TryRecvError::Disconnected) => return Err("the chat stopped".to_string()),
+            }
+        }
+    }
+
+    /// Show who spawned a blob above it.
+    pub fn attribute(&mut self, blob: Key<Blob>, viewer: &str) {
+        self.attributions.insert(blob, viewer.to_string());
+    }
+
+    /// Draw the names of the viewers above the blobs they spawned, in
+    /// a view on the window, in screen coordinates.
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, sim: &Simulation, view: Camera, rect: Rectangle, theme: &Theme, fonts: &Fonts) {
+        for (&key, viewer) in &self.attributions {
+            let blob = match sim.get_blob(key) {
+                Some(blob) => blob,
+                None => continue,
+            };
+            let pos = view.world_to_screen(blob.pos() - Vector2::new(0., blob.radius()));
+            if !rect.check_collision_point_rec(pos) { continue; }
+            let text = format!("@{}", viewer);
+            let x = pos.x as i32 - fonts.measure(&text, FONT_SIZE) / 2;
+            fonts.draw_text(draw, &text, x, pos.y as i32 - FONT_SIZE - 2, FONT_SIZE, theme.accent);
+        }
+    }
+}
+
+/// Read the commands in a channel until the connection or the chat
+/// closes.
+fn read(server: &str, channel: &str, sender: &mpsc::Sender<Result<Request, String>>) -> io::Result<()> {
+    let stream = TcpStream::connect(server)?;
+    let mut out = stream.try_clone()?;
+    //  a nick like this reads Twitch chat without an account
+    let nick = format!("justinfan{}", rand::random::<u32>() % 100_000);
+    write!(out, "NICK {}\r\nUSER {} 0 * :{}\r\nJOIN #{}\r\n", nick, nick, nick, channel.trim_start_matches('#').to_lowercase())?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if let Some(token) = line.strip_prefix("PING ") {
+            write!(out, "PONG {}\r\n", token)?;
+            continue;
+        }
+        let request = parse_message(&line).and_then(|(viewer, text)| {
+            Some(Request { viewer: viewer.to_string(), intervention: parse_command(text)? })
+        });
+        if let Some(request) = request {
+            if sender.send(Ok(request)).is_err() {
+                return Ok(());
+            }
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the server closed the connection"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chat() {
+        let line = "@badges=;color= :ann!ann@ann.tmi.twitch.tv PRIVMSG #blobs :!spawn Rex\r";
+        assert_eq!(parse_message(line), Some(("ann", "!spawn Rex")));
+        assert_eq!(parse_message(":tmi.twitch.tv 001 justinfan1 :Welcome"), None);
+        assert_eq!(parse_command("!spawn Rex"), Some(Intervention::Spawn(Some("Rex".to_string()))));
+        assert_eq!(parse_command("!spawn <b>Rex</b>"), Some(Intervention::Spawn(None)));
+        assert_eq!(parse_command("!feed please"), Some(Intervention::Feed));
+        assert_eq!(parse_command("smite"), None);
+
+        let mut limit = RateLimit::new();
+        let now = time::Instant::now();
+        assert!(limit.allow("ann", now));
+        assert!(!limit.allow("ann", now), "within the viewer's cooldown");
+        for viewer in ["b", "c", "d", "e"] {
+            assert!(limit.allow(viewer, now));
+        }
+        assert!(!limit.allow("f", now), "over the burst");
+        assert!(limit.allow("f", now + time::Duration::from_secs(1)));
+        assert!(limit.allow("ann", now + VIEWER_COOLDOWN));
+    }
+}
@@ -0,0 +1,229 @@
+//! Rectangles of worlds cut out to files, to be stitched into other
+//! worlds, so large maps can be built from smaller pieces by several
+//! people.
+//!
+//! A chunk keeps the lamps, foods and blobs of a rectangle, placed
+//! from its corner, each blob as it is exported, see `exchange`. In
+//! the scenario editor the chunk button arms cutting, and dragging
+//! over the first world then writes what is in the rectangle to a new
+//! file in `DIR`. Dropping a chunk on the window puts it in every world
+//! with its corner where it is dropped, leaving out what falls outside
+//! the world. Lakes follow from the seed and water of a world, and
+//! corpses are left behind, so neither is part of a chunk. The format
+//! is a line for each thing, with fields separated by tabs, and each
+//! blob followed by its exported file and `end`:
+//!
+//! ```text
+//! chunk    1
+//! size    200    150
+//! light    20    30    80    1
+//! food    10    12
+//! blob    50    60
+//! version = 1
+//! name = "Ada"
+//! end
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::chunk::{self, Chunk};
+//! # let mut other_sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! let chunk = Chunk::cut(&sim, Rectangle::new(0., 0., 200., 150.));
+//! let path = chunk.save(chunk::DIR)?;
+//! let added = Chunk::load(&path)?.paste(&mut other_sim, Vector2::new(400., 0.));
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    exchange::Exported,
+    light::Light,
+    simulation::Simulation,
+    toml,
+};
+
+/// Where cut chunks are written.
+pub const DIR: &str = "chunks";
+pub const EXTENSION: &str = "chunk";
+/// The version of the file format, raised when old files stop reading the same.
+pub const VERSION: u32 = 1;
+
+/// What is in a rectangle of a world, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chunk {
+    pub size: Vector2,
+    /// Lamps, placed from the corner.
+    pub lights: Vec<Light>,
+    /// Where foods lie, from the corner.
+    pub foods: Vec<Vector2>,
+    /// Blobs with where they are, from the corner.
+    pub blobs: Vec<(Vector2, Exported)>,
+}
+
+impl Chunk {
+    /// The lamps, foods and blobs in a rectangle of a world.
+    pub fn cut(sim: &Simulation, rect: Rectangle) -> Self {
+        let corner = Vector2::new(rect.x, rect.y);
+        let inside = |pos: Vector2| rect.check_collision_point_rec(pos);
+        Self {
+            size: Vector2::new(rect.width, rect.height),
+            lights: sim.lights().iter()
+                .filter(|light| inside(light.pos))
+                .map(|&light| Light { pos: light.pos - corner, ..light })
+                .collect(),
+            foods: sim.foods()
+                .filter(|(_, food)| !food.is_corpse() && inside(food.pos()))
+                .map(|(_, food)| food.pos() - corner)
+                .collect(),
+            blobs: sim.blobs()
+                .filter(|(_, blob)| inside(blob.pos()))
+                .map(|(_, blob)| (blob.pos() - corner, Exported::from_blob(blob, sim.parameters())))
+                .collect(),
+        }
+    }
+
+    /// Put what is in the chunk in a world with its corner at a place,
+    /// returning how many lamps, foods and blobs fit in the world.
+    pub fn paste(&self, sim: &mut Simulation, corner: Vector2) -> usize {
+        let size = sim.size();
+        let inside = |pos: Vector2| (0. ..=size.x).contains(&pos.x) && (0. ..=size.y).contains(&pos.y);
+        let mut added = 0;
+        for light in &self.lights {
+            let pos = corner + light.pos;
+            if inside(pos) {
+                sim.add_light(Light { pos, ..*light });
+                added += 1;
+            }
+        }
+        for &food in &self.foods {
+            if inside(corner + food) {
+                sim.insert_food(corner + food);
+                added += 1;
+            }
+        }
+        for (pos, blob) in &self.blobs {
+            if inside(corner + *pos) {
+                blob.insert(sim, corner + *pos);
+                added += 1;
+            }
+        }
+        added
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "chunk\t{}", VERSION)?;
+        writeln!(out, "size\t{}\t{}", self.size.x, self.size.y)?;
+        for light in &self.lights {
+            writeln!(out, "light\t{}\t{}\t{}\t{}", light.pos.x, light.pos.y, light.radius, light.brightness)?;
+        }
+        for food in &self.foods {
+            writeln!(out, "food\t{}\t{}", food.x, food.y)?;
+        }
+        for (pos, blob) in &self.blobs {
+            writeln!(out, "blob\t{}\t{}", pos.x, pos.y)?;
+            write!(out, "{}", blob.to_toml())?;
+            writeln!(out, "end")?;
+        }
+        Ok(())
+    }
+
+    pub fn read(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().enumerate();
+        match lines.next().map(|(_, line)| line.split('\t').collect::<Vec<_>>()).as_deref() {
+            Some(["chunk", version]) if version.parse() == Ok(VERSION) => (),
+            Some(["chunk", version]) => return Err(format!("chunks of version {} cannot be read, only {}", version, VERSION)),
+            _ => return Err("a chunk must start with `chunk` and its version".to_string()),
+        }
+        let (mut size, mut lights, mut foods, mut blobs) = (None, vec![], vec![], vec![]);
+        while let Some((i, line)) = lines.next() {
+            let invalid = || format!("line {}: invalid chunk line `{}`", i + 1, line);
+            let fields: Vec<_> = line.split('\t').collect();
+            let numbers = fields[1..].iter()
+                .map(|field| field.parse::<f32>().ok().filter(|number| number.is_finite()))
+                .collect::<Option<Vec<_>>>()
+                .ok_or_else(invalid)?;
+            match (fields[0], &numbers[..]) {
+                ("size", &[width, height]) if width > 0. && height > 0. => size = Some(Vector2::new(width, height)),
+                ("light", &[x, y, radius, brightness]) if radius >= 0. => {
+                    lights.push(Light { pos: Vector2::new(x, y), radius, brightness });
+                },
+                ("food", &[x, y]) => foods.push(Vector2::new(x, y)),
+                ("blob", &[x, y]) => {
+                    let source: Vec<_> = lines.by_ref().map(|(_, line)| line).take_while(|&line| line != "end").collect();
+                    let table = toml::parse(&source.join("\n")).map_err(|e| format!("line {}: {}", i + 1, e))?;
+                    let blob = Exported::from_table(&table).map_err(|e| format!("line {}: {}", i + 1, e))?;
+                    blobs.push((Vector2::new(x, y), blob));
+                },
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(Self { size: size.ok_or("a chunk needs its size")?, lights, foods, blobs })
+    }
+
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::read(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Write the chunk to a new file in a directory, named by its size.
+    pub fn save(&self, dir: &str) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let path = (1..)
+            .map(|i| Path::new(dir).join(format!("chunk-{}x{}-{}.{}", self.size.x as u32, self.size.y as u32, i, EXTENSION)))
+            .find(|path| !path.exists())
+            .unwrap();
+        let mut file = io::BufWriter::new(fs::File::create(&path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_chunk() {
+        let config = SimulationConfig {
+            lights: vec![Light { pos: Vector2::new(60., 40.), radius: 50., brightness: 1. }],
+            ..SimulationConfig::new(Vector2::new(300., 200.))
+        };
+        let mut sim = Simulation::new(config);
+        sim.insert_food(Vector2::new(30., 30.));
+        sim.insert_food(Vector2::new(250., 150.));
+        sim.insert_blob(BlobConfig { name: Some("Ada".to_string()), ..BlobConfig::new(Vector2::new(80., 70.)) });
+        sim.insert_blob(BlobConfig::new(Vector2::new(200., 100.)));
+        let chunk = Chunk::cut(&sim, Rectangle::new(20., 20., 100., 80.));
+        assert_eq!((chunk.lights.len(), chunk.foods.len(), chunk.blobs.len()), (1, 1, 1));
+        assert_eq!((chunk.foods[0], chunk.blobs[0].0), (Vector2::new(10., 10.), Vector2::new(60., 50.)));
+
+        let mut text = vec![];
+        chunk.write(&mut text).unwrap();
+        let read = Chunk::read(&String::from_utf8(text).unwrap()).unwrap();
+        assert_eq!(read, chunk);
+        assert!(Chunk::read("chunk\t9\nsize\t1\t1\n").unwrap_err().contains("version 9"));
+        assert!(Chunk::read("chunk\t1\nfood\t1\n").unwrap_err().contains("line 2"));
+
+        //  stitched beside itself, what falls outside the world is left out
+        let mut other = Simulation::new(SimulationConfig::new(Vector2::new(150., 200.)));
+        assert_eq!(read.paste(&mut other, Vector2::new(0., 0.)), 3);
+        assert_eq!(read.paste(&mut other, Vector2::new(100., 0.)), 2);
+        assert_eq!((other.foods().count(), other.blobs().count(), other.lights().len()), (2, 1, 2));
+        let (_, ada) = other.blobs().next().unwrap();
+        assert_eq!((ada.name.as_deref(), ada.pos()), (Some("Ada"), Vector2::new(60., 50.)));
+    }
+}
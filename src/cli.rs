@@ -0,0 +1,819 @@
+//! Command line options.
+
+use raylib::prelude::{Color, Rectangle, Vector2};
+
+use crate::{mutators::Mutator, simulation::{Parameters, Simulation, Timing}, light::Light, watch::Region, autopause::{self, Trigger}, theme::Theme, style, recorder::Recorder, memory::Budget, golden, tournament::Arena, foraging::Benchmark, bench::Bench, world_code::WorldCode, anomalies, quality, replay, observer::Interest, naming::NamingRule, power, placement, spawning, stream, report};
+
+/// How to take part in a lockstep session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Network {
+    /// Wait for a peer on a port.
+    Host(u16),
+    /// Join the peer at an address.
+    Connect(String),
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Options {
+    pub help: bool,
+    pub mutators: Vec<Mutator>,
+    /// A parameter name and value to run a second world with.
+    pub compare: Option<(String, String)>,
+    pub network: Option<Network>,
+    /// The size of each world, unless it is that of the window.
+    pub world_size: Option<Vector2>,
+    /// The seed of the world, unless a random one.
+    pub seed: Option<u64>,
+    /// The population below which random blobs spawn, if only below one.
+    pub min_population: Option<usize>,
+    /// Lamps to place in the world.
+    pub lights: Vec<Light>,
+    /// Regions to watch, see `watch`.
+    pub watches: Vec<Region>,
+    /// What pauses the run by itself, see `autopause`.
+    pub pause_on: Vec<Trigger>,
+    /// How many ticks to simulate before showing the world.
+    pub fast_forward: u64,
+    /// Whether to simulate without a window, then write the statistics and exit.
+    pub headless: bool,
+    /// How many ticks to simulate without a window.
+    pub steps: u64,
+    /// Whether a headless run reads commands instead, see `shell`.
+    pub shell: bool,
+    /// Where to write the statistics of a headless run.
+    pub stats_csv: String,
+    /// An SQLite database to add the statistics of a headless run to, see `sqlite`.
+    pub stats_sqlite: Option<String>,
+    /// Where to write the report of a run as it ends, if anywhere, see `report`.
+    pub report_dir: Option<String>,
+    /// Whether to open the report once it is written.
+    pub open_report: bool,
+    /// How many of the latest events each world keeps, see `history`.
+    pub event_history: usize,
+    /// A file to write every event of a world to, see `history`.
+    pub event_spill: Option<String>,
+    pub timing: Timing,
+    pub theme: Theme,
+    /// How blobs are drawn, see `style`.
+    pub style: style::Choice,
+    /// Whether to draw the world through the post-processing shader.
+    pub post_processing: bool,
+    /// How many of the last seconds a saved GIF shows.
+    pub gif_seconds: u32,
+    /// Where to write a text account of the world, `-` for the standard output.
+    pub narrate: Option<String>,
+    /// Where to write what changes in the world every tick, `-` for the standard output.
+    pub observe: Option<String>,
+    /// The blobs whose changes are written.
+    pub interest: Interest,
+    /// Where to keep the state of the world after every tick, see `shared_state`.
+    pub shared_state: Option<String>,
+    /// Where to write the family tree of each world as a run ends, see `genealogy`.
+    pub genealogy: Option<String>,
+    /// Whether to start with the sound of the world on.
+    pub sonify: bool,
+    pub budget: Budget,
+    /// Draw a fixed world offscreen and compare it with the golden images, then exit.
+    pub render_test: Option<golden::Mode>,
+    /// Where to record the input of every frame.
+    pub record_input: Option<String>,
+    /// A recording to replay, in a world with the seed it was made with.
+    pub play_input: Option<String>,
+    /// An `http://` endpoint to post an anonymous summary of the run to.
+    pub post_summary: Option<String>,
+    /// Whether to check every tick that energy is conserved.
+    pub audit_energy: bool,
+    /// Whether to compute floating point functions the same on every platform.
+    pub deterministic_math: bool,
+    /// How many standard deviations from usual a statistic must move to be flagged.
+    pub anomaly_sigma: f32,
+    /// The frame rate to lower the quality of drawing to hold, or 0 to keep it.
+    pub target_fps: u32,
+    /// Whether to keep the window responsive by doing less work, see `realtime`.
+    pub realtime: bool,
+    /// When to save power, see `power`.
+    pub low_power: power::Setting,
+    /// What happens while the window is unfocused, see `power`.
+    pub unfocused: power::Unfocused,
+    /// The monitor to open the window on, counted from 0, unless the saved one.
+    pub monitor: Option<usize>,
+    /// Where on its monitor the window's corner goes, unless centered.
+    pub window_position: Option<(i32, i32)>,
+    /// Whether to keep the window above the others.
+    pub on_top: bool,
+    /// Whether to run as a screensaver, see `screensaver`.
+    pub screensaver: bool,
+    /// A color to draw the world on for keying out, see `stream`.
+    pub chroma_key: Option<Color>,
+    /// Where to keep the statistics of the first world for streaming.
+    pub stream_stats: Option<String>,
+    /// The channel whose chat commands change the first world, see `chat`.
+    pub chat: Option<String>,
+    /// The IRC server of the chat, as `host:port`.
+    pub chat_server: Option<String>,
+    /// A camera tour to play from the start.
+    pub tour: Option<String>,
+    /// A grayscale image of where food grows.
+    pub fertility: Option<String>,
+    /// A CSV file of blobs every world starts with, see `roster`.
+    pub import_csv: Option<String>,
+    /// Where blobs and food are added, by the name of a spawner, see `spawning`.
+    pub blob_spawner: Option<String>,
+    pub food_spawner: Option<String>,
+    /// The name or path of a scenario, see `scenario`.
+    pub scenario: Option<String>,
+    /// How children are named.
+    pub naming: NamingRule,
+    /// The mod whose names fresh names of children are drawn from, or `base`.
+    pub name_pack: Option<String>,
+    /// A shared world to recreate instead of a new one.
+    pub world_code: Option<WorldCode>,
+    /// Exported blobs to play a tournament between instead of opening the window.
+    pub tournament: Option<(Vec<String>, Arena)>,
+    /// Exported blobs to run the foraging benchmark on instead of opening
+    /// the window, and where to write the scores as JSON.
+    pub forage: Option<(Vec<String>, Benchmark, Option<String>)>,
+    /// A speed benchmark to run instead of opening the window, and where
+    /// to write its report as JSON.
+    pub bench: Option<(Bench, Option<String>)>,
+    /// Whether to check the environment and print a diagnosis instead of opening the window.
+    pub doctor: bool,
+    /// Exported blobs whose brains to print as JSON instead of opening the window.
+    pub brain_json: Option<Vec<String>>,
+}
+
+impl Options {
+    pub const USAGE: &'static str = "\
+Usage: blobs [options]
+       blobs tournament [--ticks <ticks>] <blob file>...
+       blobs forage [--ticks <ticks>] [--json <path>] <blob file>...
+       blobs bench [--blobs <count>] [--ticks <ticks>] [--json <path>]
+       blobs brain-json <blob file>...
+       blobs doctor
+
+Options:
+    -h, --help            Print this message
+    --mutator <name>      Create the world with a mutator, may be repeated.
+                          One of: no-predation, double-mutation,
+                          scarce-food, fog, giant-blobs
+    --compare <name>=<value>
+                          Run a second world side by side, identical
+                          except for one parameter. One of: predation,
+                          mutation_rate, food_rate, sight_scale, size_scale,
+                          metabolism_scale, predation_efficiency,
+                          food_lifetime, reproduction, water, night,
+                          weather, speciation
+    --seed <number>       Start the world from this seed, so that the
+                          same seed and input give the same run
+                          (default a random seed, shown in the status bar)
+    --min-population <count>
+                          Spawn random blobs only while fewer than this
+                          many live, leaving the rest to reproduction
+    --light <x>,<y>,<radius>
+                          Place a lamp in the world, may be repeated;
+                          blobs see farther in its light at night, when
+                          the night parameter darkens the world
+    --watch <name>@<x>,<y>,<width>,<height>[ <alert>...]
+                          Watch a region, may be repeated, recording its
+                          population, food, predators, births and deaths
+                          and alerting when a measure crosses a
+                          threshold, as in predators>3 or food<10
+    --pause-on <trigger>[,<trigger>...]
+                          Pause by itself with a banner telling why at
+                          the first predation, when a selected blob is
+                          about to starve (dying), when the population
+                          falls below a count (population<N) or when a
+                          new species is counted (species)
+    --world-size <width>x<height>
+                          Make each world this large, from 100 to 20000
+                          units a side, and pan and zoom to see all of
+                          it (default the size of the window)
+    --fast-forward <ticks>
+                          Simulate this many ticks as fast as possible
+                          before showing the world
+    --headless            Simulate without a window, then write the
+                          statistics of each world as CSV and exit
+    --steps <ticks>       How many ticks to simulate headless
+                          (default 100000)
+    --shell               With --headless, read commands such as
+                          step 600, stats, spawn, save <path> and quit
+                          from the standard input instead of simulating
+                          --steps ticks; help lists them
+    --stats-csv <path>    Where to write the statistics of a headless
+                          run (default stats.csv), with the label of the
+                          world before the extension if there are two
+    --stats-sqlite <path> Also add the statistics, species and events of
+                          a headless run to an SQLite database, through
+                          the sqlite3 program
+    --report-dir <path>   Where to write the report of a run, with its
+                          graphs, as the window closes or a headless
+                          run ends (default reports)
+    --no-report           Write no report of the run
+    --open-report         Open the report once it is written
+    --event-history <count>
+                          How many of the latest events each world keeps
+                          to query (default 65536)
+    --event-spill <path>  Also write every event to a file as it happens,
+                          with the label of the world before the
+                          extension if there are two
+    --tick-rate <ticks>   Simulation ticks per simulated second, from 10
+                          to 240 (default 60)
+    --substeps <count>    Times movement and collisions are resolved in
+                          a tick, from 1 to 8 (default 1)
+    --brain-period <ticks>
+                          Ticks between the decisions of each brain,
+                          from 1 to 30 (default 1)
+    --theme <name>        The color theme, light or dark
+    --style <name>        How blobs are drawn: minimal, classic, detailed,
+                          or auto to pick by zoom (default auto)
+    --no-post-processing  Skip the bloom, vignette and lens effects,
+                          for machines that draw slowly
+    --gif-seconds <seconds>
+                          How much of the past C saves as a GIF,
+                          from 10 to 30 seconds (default 20)
+    --narrate <path>      Write a text summary of the world every 10
+                          seconds of simulation time to a file, or to
+                          the standard output if the path is -
+    --observe <path>      Write which blobs spawned, moved and died every
+                          tick to a file, or to the standard output if
+                          the path is -
+    --observe-region <x>,<y>,<width>,<height>
+                          Only write the changes of blobs in a rectangle
+    --observe-species <name>
+                          Only write the changes of blobs of a species
+    --shared-state <path> Keep the positions and traits of the blobs and
+                          foods of the first world after every tick in a
+                          file of fixed layout for other programs to map,
+                          best on a memory file system like /dev/shm
+    --genealogy <path>    Write the family tree of every blob of a world
+                          as the run ends, with their traits, lifespans
+                          and children, as GEXF for Gephi if the path
+                          ends in .gexf and as DOT for GraphViz otherwise
+    --tour <path>         Move the camera through the keyframes of a tour,
+                          as recorded with J, from the start
+    --fertility <path>    Grow food by a grayscale image stretched over
+                          the world, fastest where it is white and not
+                          at all where it is black, or by a grid of
+                          values from a .csv or ESRI ASCII .asc file,
+                          fastest where they are largest
+    --import-csv <path>   Start every world with the blobs of a CSV file
+                          as well, a column each for where they are, their
+                          name, species, diet and genes; Shift+I adds them
+                          again
+    --blob-spawner <spawner>
+                          Where blobs are added: uniform, edge (along the
+                          walls), kin (near their species), safe (away
+                          from hunters) or nests:x,y;x,y (around points)
+    --food-spawner <spawner>
+                          Where food is added, likewise, kin being near
+                          other food
+    --scenario <name>     Run the scenario in assets/scenarios/<name>.toml,
+                          or in a .toml file, whose schedules change how
+                          often food and blobs are added over time
+    --naming <rule>       How children are named, one of: fresh, a name
+                          drawn like those of added blobs; inherit, the
+                          parent's name with the generation as a
+                          numeral; unnamed, until selected
+                          (default fresh)
+    --name-pack <mod>     Draw fresh names of children only from the
+                          names of a mod, or of the base game for base
+    --sonify              Play ambient tones that follow the population,
+                          births and predation, also toggled with S
+    --memory-budget <MiB> Memory to use before warning and trimming the
+                          statistics and GIF history (default 64)
+    --entity-budget <count>
+                          Blobs and foods to allow before warning
+                          (default 5000)
+    --render-test         Draw a fixed seeded world offscreen in each
+                          theme, compare it with the golden images in
+                          tests/golden and exit, failing if they differ
+    --update-golden       Like --render-test, but replace the golden
+                          images with what is drawn
+    --record-input <path> Write the mouse and keys of every frame to a
+                          file, along with the seed of the world, or
+                          with the setup and mods too if the path ends
+                          in .blobreplay
+    --play-input <path>   Replay recorded input in a world with the
+                          recording's seed, or the bundled setup of a
+                          .blobreplay, then take input as usual
+    --post-summary <url>  Post an anonymous summary of the run (seed,
+                          configuration hash, largest population, ticks
+                          and longest life) to an http:// endpoint
+    --world-code <code>   Recreate the world of a code copied with F8,
+                          which decides the mutators and timing
+    --audit-energy        Check every tick that the energy stored in
+                          blobs and foods changes only by what is
+                          spawned, eaten, burned and removed, stopping
+                          if it does not, and graph the flows with G
+    --target-fps <fps>    Drop post-processing, then labels, then the
+                          heatmap while frames take too long for this
+                          frame rate, or never if it is 0 (default 60)
+    --realtime            Never let ticking stall the window: drop ticks
+                          that do not fit in half of each frame, and
+                          while they do not, have brains decide less
+                          often, then stop random blobs from spawning
+    --low-power <when>    Draw at most 30 frames a second, tick at most
+                          30 times a simulated second, drop post-
+                          processing and energy numbers, and sleep while
+                          the window is in the background: on, off or
+                          auto, while on battery where it can be told
+                          (default auto)
+    --unfocused <what>    What happens while the window is unfocused or
+                          minimized: keep running, pause, or run in the
+                          background without drawing and at fewer
+                          frames (default keep)
+    --monitor <n>         Open the window on a monitor counted from 1,
+                          by default the one it was last closed on
+    --window-position <x,y>
+                          Place the window's corner this far from its
+                          monitor's, instead of centering it
+    --on-top              Keep the window above other windows
+    --screensaver         Open fullscreen without the interface, drift
+                          between the places blobs crowd, seed the
+                          world anew when every blob dies, and exit on
+                          any input
+    --chroma-key <color>  Draw the world on green, blue, magenta or an
+                          r,g,b color to key out when streaming, without
+                          the interface or post-processing
+    --stream-stats <path> Keep the population, food, births and kills
+                          in a text file, rewritten every second, for a
+                          text source of a stream
+    --chat <channel>      Take !spawn, !feed and !smite from the chat of
+                          a Twitch channel, a few a second at most
+    --chat-server <host:port>
+                          Read the chat on another IRC server instead
+    --deterministic-math  Compute sines, exponentials and the like the
+                          same on every platform, as lockstep sessions
+                          always do, at some cost in speed
+    --anomaly-sigma <sigmas>
+                          How far from usual, in standard deviations,
+                          births and trait means must move to be
+                          flagged and bookmarked (default 4)
+    --host <port>         Wait for a peer to run a shared world in lockstep
+    --connect <address>   Join the world of a peer started with --host,
+                          which also decides the mutators
+
+Tournament:
+    Plays every pair of exported blobs against each other in the
+    same arena, on both sides, and prints them ranked by points for
+    survival, food eaten and kills.
+    --ticks <ticks>       How long each match lasts (default 3600)
+
+Forage:
+    Runs the foraging benchmark: each exported blob forages alone in
+    the same arena with the same food, from each of four starts, and
+    they are printed ranked by the food they ate.
+    --ticks <ticks>       How long each trial lasts (default 3600)
+    --json <path>         Also write the scores as JSON to this file
+
+Bench:
+    Runs a world of random blobs without a window as fast as it can
+    and prints the ticks a second, the time spent in each phase of a
+    tick and the most memory held.
+    --blobs <count>       How many blobs to start with (default 1000)
+    --ticks <ticks>       How many ticks to run (default 1000)
+    --json <path>         Also write the report as JSON to this file
+
+Brain JSON:
+    Prints the brain of each exported blob, its kind and the genes
+    that steer it, as a JSON object on a line of its own.
+
+Doctor:
+    Checks the assets, the settings kept between runs, the directories
+    written to, the threads of the machine and the renderer, and prints
+    what is wrong, exiting with 1 if anything failed.";
+
+    /// Parse the arguments that follow the program name.
+    pub fn parse<I: IntoIterator<Item=String>>(args: I) -> Result<Self, String> {
+        let mut ret = Self {
+            post_processing: true,
+            gif_seconds: Recorder::DEFAULT_SECONDS,
+            anomaly_sigma: anomalies::DEFAULT_SIGMA,
+            target_fps: quality::DEFAULT_TARGET_FPS,
+            steps: 100_000,
+            stats_csv: "stats.csv".to_string(),
+            report_dir: Some(report::DIR.to_string()),
+            event_history: Simulation::EVENT_LOG_LENGTH,
+            ..Self::default()
+        };
+        let mut args = args.into_iter().peekable();
+        if args.peek().map(String::as_str) == Some("tournament") {
+            args.next();
+            ret.tournament = Some(Self::parse_tournament(args)?);
+            return Ok(ret);
+        }
+        if args.peek().map(String::as_str) == Some("forage") {
+            args.next();
+            ret.forage = Some(Self::parse_forage(args)?);
+            return Ok(ret);
+        }
+        if args.peek().map(String::as_str) == Some("bench") {
+            args.next();
+            ret.bench = Some(Self::parse_bench(args)?);
+            return Ok(ret);
+        }
+        if args.peek().map(String::as_str) == Some("doctor") {
+            args.next();
+            if let Some(arg) = args.next() {
+                return Err(format!("unexpected argument `{}`", arg));
+            }
+            ret.doctor = true;
+            return Ok(ret);
+        }
+        if args.peek().map(String::as_str) == Some("brain-json") {
+            args.next();
+            let paths: Vec<_> = args.collect();
+            if paths.is_empty() {
+                return Err("brain-json needs a blob file".to_string());
+            }
+            ret.brain_json = Some(paths);
+            return Ok(ret);
+        }
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => ret.help = true,
+                "--mutator" => {
+                    let name = args.next().ok_or("--mutator needs a name")?;
+                    let mutator = Mutator::from_id(&name).ok_or_else(|| format!("unknown mutator `{}`", name))?;
+                    if !ret.mutators.contains(&mutator) {
+                        ret.mutators.push(mutator);
+                    }
+                },
+                "--compare" => {
+                    let assignment = args.next().ok_or("--compare needs <name>=<value>")?;
+                    let (name, value) = assignment.split_once('=')
+                        .ok_or_else(|| format!("expected <name>=<value>, got `{}`", assignment))?;
+                    Parameters::default().set(name, value)?;
+                    ret.compare = Some((name.to_string(), value.to_string()));
+                },
+                "--world-size" => {
+                    let size = args.next().ok_or("--world-size needs <width>x<height>")?;
+                    let side = |side: &str| side.parse().ok().filter(|side| (100. ..=20_000.).contains(side));
+                    let parsed = size.split_once('x').and_then(|(width, height)| Some(Vector2::new(side(width)?, side(height)?)));
+                    ret.world_size = Some(parsed.ok_or_else(|| format!("expected <width>x<height> from 100 to 20000, got `{}`", size))?);
+                },
+                "--seed" => {
+                    let seed = args.next().ok_or("--seed needs a number")?;
+                    ret.seed = Some(seed.parse().map_err(|_| format!("invalid seed `{}`", seed))?);
+                },
+                "--min-population" => {
+                    let count = args.next().ok_or("--min-population needs a number")?;
+                    ret.min_population = Some(count.parse().map_err(|_| format!("invalid number `{}`", count))?);
+                },
+                "--light" => {
+                    let light = args.next().ok_or("--light needs <x>,<y>,<radius>")?;
+                    let numbers: Vec<f32> = light.split(',').filter_map(|n| n.trim().parse().ok()).collect();
+                    match numbers[..] {
+                        [x, y, radius] if radius > 0. => ret.lights.push(Light::new(Vector2::new(x, y), radius)),
+                        _ => return Err(format!("expected <x>,<y>,<radius> with a positive radius, got `{}`", light)),
+                    }
+                },
+                "--watch" => {
+                    let spec = args.next().ok_or("--watch needs <name>@<x>,<y>,<width>,<height>")?;
+                    ret.watches.push(Region::parse(&spec, None)?);
+                },
+                "--pause-on" => {
+                    let triggers = args.next().ok_or("--pause-on needs a list of triggers")?;
+                    ret.pause_on.extend(autopause::parse(&triggers)?);
+                },
+                "--fast-forward" => {
+                    let ticks = args.next().ok_or("--fast-forward needs a number of ticks")?;
+                    ret.fast_forward = ticks.parse().map_err(|_| format!("invalid number of ticks `{}`", ticks))?;
+                },
+                "--headless" => ret.headless = true,
+                "--shell" => ret.shell = true,
+                "--steps" => {
+                    let ticks = args.next().ok_or("--steps needs a number of ticks")?;
+                    ret.steps = ticks.parse().map_err(|_| format!("invalid number of ticks `{}`", ticks))?;
+                },
+                "--stats-csv" => {
+                    let path = args.next().ok_or("--stats-csv needs a path")?;
+                    ret.stats_csv = path;
+                },
+                "--stats-sqlite" => ret.stats_sqlite = Some(args.next().ok_or("--stats-sqlite needs a path")?),
+                "--report-dir" => ret.report_dir = Some(args.next().ok_or("--report-dir needs a path")?),
+                "--no-report" => ret.report_dir = None,
+                "--open-report" => ret.open_report = true,
+                "--event-history" => {
+                    let count = args.next().ok_or("--event-history needs a number of events")?;
+                    ret.event_history = count.parse().ok()
+                        .filter(|&count: &usize| count > 0)
+                        .ok_or_else(|| format!("expected a positive number of events, got `{}`", count))?;
+                },
+                "--event-spill" => ret.event_spill = Some(args.next().ok_or("--event-spill needs a path")?),
+                "--tick-rate" | "--substeps" | "--brain-period" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a number", arg))?;
+                    let value = value.parse().map_err(|_| format!("invalid number `{}`", value))?;
+                    match arg.as_str() {
+                        "--tick-rate" => ret.timing.tick_rate = value,
+                        "--substeps" => ret.timing.substeps = value,
+                        _ => ret.timing.brain_period = value,
+                    }
+                    ret.timing.validate()?;
+                },
+                "--theme" => {
+                    let name = args.next().ok_or("--theme needs a name")?;
+                    ret.theme = Theme::from_name(&name).ok_or_else(|| format!("unknown theme `{}`", name))?;
+                },
+                "--style" => {
+                    let name = args.next().ok_or("--style needs a name")?;
+                    ret.style = style::Choice::parse(&name)?;
+                },
+                "--no-post-processing" => ret.post_processing = false,
+                "--gif-seconds" => {
+                    let seconds = args.next().ok_or("--gif-seconds needs a number of seconds")?;
+                    ret.gif_seconds = seconds.parse().ok()
+                        .filter(|seconds| (Recorder::MIN_SECONDS..=Recorder::MAX_SECONDS).contains(seconds))
+                        .ok_or_else(|| format!("expected {} to {} seconds, got `{}`", Recorder::MIN_SECONDS, Recorder::MAX_SECONDS, seconds))?;
+                },
+                "--narrate" => {
+                    let path = args.next().ok_or("--narrate needs a path")?;
+                    ret.narrate = Some(path);
+                },
+                "--observe" => {
+                    let path = args.next().ok_or("--observe needs a path")?;
+                    ret.observe = Some(path);
+                },
+                "--shared-state" => ret.shared_state = Some(args.next().ok_or("--shared-state needs a path")?),
+                "--genealogy" => ret.genealogy = Some(args.next().ok_or("--genealogy needs a path")?),
+                "--observe-region" => {
+                    let region = args.next().ok_or("--observe-region needs <x>,<y>,<width>,<height>")?;
+                    let numbers: Vec<f32> = region.split(',').filter_map(|n| n.trim().parse().ok()).collect();
+                    match numbers[..] {
+                        [x, y, width, height] if width > 0. && height > 0. => ret.interest.region = Some(Rectangle::new(x, y, width, height)),
+                        _ => return Err(format!("expected <x>,<y>,<width>,<height> with a positive size, got `{}`", region)),
+                    }
+                },
+                "--observe-species" => {
+                    let name = args.next().ok_or("--observe-species needs a name")?;
+                    ret.interest.species = Some(name);
+                },
+                "--sonify" => ret.sonify = true,
+                "--naming" => {
+                    let name = args.next().ok_or("--naming needs a rule")?;
+                    ret.naming = NamingRule::from_name(&name)
+                        .ok_or_else(|| format!("unknown naming rule `{}`, expected one of {}", name, NamingRule::NAMES.join(", ")))?;
+                },
+                "--name-pack" => {
+                    let pack = args.next().ok_or("--name-pack needs a mod name")?;
+                    ret.name_pack = Some(pack);
+                },
+                "--tour" => {
+                    let path = args.next().ok_or("--tour needs a path")?;
+                    ret.tour = Some(path);
+                },
+                "--fertility" => {
+                    let path = args.next().ok_or("--fertility needs a path")?;
+                    ret.fertility = Some(path);
+                },
+                "--import-csv" => {
+                    let path = args.next().ok_or("--import-csv needs a path")?;
+                    ret.import_csv = Some(path);
+                },
+                "--blob-spawner" | "--food-spawner" => {
+                    let name = args.next().ok_or_else(|| format!("{} needs a spawner", arg))?;
+                    spawning::parse(&name)?;
+                    *if arg == "--blob-spawner" { &mut ret.blob_spawner } else { &mut ret.food_spawner } = Some(name);
+                },
+                "--scenario" => ret.scenario = Some(args.next().ok_or("--scenario needs a name or path")?),
+                "--memory-budget" | "--entity-budget" => {
+                    let value = args.next().ok_or_else(|| format!("{} needs a number", arg))?;
+                    let value: usize = value.parse().ok()
+                        .filter(|&value| value > 0)
+                        .ok_or_else(|| format!("invalid number `{}`", value))?;
+                    match arg.as_str() {
+                        "--memory-budget" => ret.budget.bytes = value << 20,
+                        _ => ret.budget.entities = value,
+                    }
+                },
+                "--render-test" => ret.render_test = Some(golden::Mode::Compare),
+                "--update-golden" => ret.render_test = Some(golden::Mode::Update),
+                "--record-input" | "--play-input" => {
+                    let path = args.next().ok_or_else(|| format!("{} needs a path", arg))?;
+                    match arg.as_str() {
+                        "--record-input" => ret.record_input = Some(path),
+                        _ => ret.play_input = Some(path),
+                    }
+                },
+                "--post-summary" => {
+                    let url = args.next().ok_or("--post-summary needs a URL")?;
+                    if !url.starts_with("http://") {
+                        return Err(format!("only http:// endpoints are supported, got `{}`", url));
+                    }
+                    ret.post_summary = Some(url);
+                },
+                "--audit-energy" => ret.audit_energy = true,
+                "--deterministic-math" => ret.deterministic_math = true,
+                "--realtime" => ret.realtime = true,
+                "--low-power" => {
+                    let name = args.next().ok_or("--low-power needs on, off or auto")?;
+                    ret.low_power = power::Setting::from_name(&name)
+                        .ok_or_else(|| format!("unknown low-power setting `{}`, expected one of {}", name, power::Setting::NAMES.join(", ")))?;
+                },
+                "--monitor" => {
+                    let number = args.next().ok_or("--monitor needs a number")?;
+                    let number: usize = number.parse().ok().filter(|&number| number > 0)
+                        .ok_or_else(|| format!("invalid monitor `{}`, expected a number from 1", number))?;
+                    ret.monitor = Some(number - 1);
+                },
+                "--window-position" => {
+                    let position = args.next().ok_or("--window-position needs x,y")?;
+                    ret.window_position = Some(placement::parse_position(&position)?);
+                },
+                "--on-top" => ret.on_top = true,
+                "--screensaver" => ret.screensaver = true,
+                "--chroma-key" => {
+                    let key = args.next().ok_or("--chroma-key needs a color")?;
+                    ret.chroma_key = Some(stream::parse_key(&key)?);
+                },
+                "--stream-stats" => ret.stream_stats = Some(args.next().ok_or("--stream-stats needs a path")?),
+                "--chat" => ret.chat = Some(args.next().ok_or("--chat needs a channel")?),
+                "--chat-server" => ret.chat_server = Some(args.next().ok_or("--chat-server needs host:port")?),
+                "--unfocused" => {
+                    let name = args.next().ok_or("--unfocused needs keep, pause or background")?;
+                    ret.unfocused = power::Unfocused::from_name(&name)
+                        .ok_or_else(|| format!("unknown unfocused setting `{}`, expected one of {}", name, power::Unfocused::NAMES.join(", ")))?;
+                },
+                "--target-fps" => {
+                    let fps = args.next().ok_or("--target-fps needs a number")?;
+                    ret.target_fps = fps.parse().map_err(|_| format!("invalid number `{}`", fps))?;
+                },
+                "--anomaly-sigma" => {
+                    let sigma = args.next().ok_or("--anomaly-sigma needs a number")?;
+                    ret.anomaly_sigma = sigma.parse().ok()
+                        .filter(|&sigma: &f32| sigma > 0.)
+                        .ok_or_else(|| format!("expected a positive number of standard deviations, got `{}`", sigma))?;
+                },
+                "--world-code" => {
+                    let code = args.next().ok_or("--world-code needs a code")?;
+                    ret.world_code = Some(WorldCode::decode(&code)?);
+                },
+                "--host" => {
+                    let port = args.next().ok_or("--host needs a port")?;
+                    let port = port.parse().map_err(|_| format!("invalid port `{}`", port))?;
+                    ret.network = Some(Network::Host(port));
+                },
+                "--connect" => {
+                    let address = args.next().ok_or("--connect needs an address")?;
+                    ret.network = Some(Network::Connect(address));
+                },
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+        match ret.network {
+            Some(_) if ret.compare.is_some() => return Err("--compare cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.record_input.is_some() || ret.play_input.is_some() => {
+                return Err("input cannot be recorded or replayed in a lockstep session".to_string());
+            },
+            Some(_) if ret.fast_forward > 0 => return Err("--fast-forward cannot be used in a lockstep session".to_string()),
+            //  the peer has no copy of the image
+            Some(_) if ret.fertility.is_some() => return Err("a fertility map cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.import_csv.is_some() => return Err("--import-csv cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.blob_spawner.is_some() || ret.food_spawner.is_some() => {
+                return Err("--blob-spawner and --food-spawner cannot be used in a lockstep session".to_string());
+            },
+            Some(_) if ret.scenario.is_some() => return Err("a scenario cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.realtime => return Err("--realtime cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.headless => return Err("--headless cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.min_population.is_some() => return Err("--min-population cannot be used in a lockstep session".to_string()),
+            Some(_) if !ret.lights.is_empty() => return Err("--light cannot be used in a lockstep session".to_string()),
+            //  names are drawn from the world's generator
+            Some(_) if ret.naming != NamingRule::default() || ret.name_pack.is_some() => {
+                return Err("--naming and --name-pack cannot be used in a lockstep session".to_string());
+            },
+            //  the peer would wait for as long as the window is away
+            Some(_) if ret.screensaver => return Err("--screensaver cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.unfocused == power::Unfocused::Pause => return Err("--unfocused pause cannot be used in a lockstep session".to_string()),
+            //  the peer would not see the commands
+            Some(_) if ret.chat.is_some() => return Err("--chat cannot be used in a lockstep session".to_string()),
+            Some(Network::Connect(_)) if !ret.mutators.is_empty() => return Err("the host decides the mutators".to_string()),
+            Some(Network::Connect(_)) if ret.timing != Timing::default() => return Err("the host decides the timing".to_string()),
+            Some(Network::Connect(_)) if ret.world_size.is_some() => return Err("the host decides the world size".to_string()),
+            Some(Network::Connect(_)) if ret.seed.is_some() => return Err("the host decides the seed".to_string()),
+            _ => (),
+        }
+        match ret.world_code {
+            Some(_) if ret.network.is_some() => return Err("a world code cannot be used in a lockstep session".to_string()),
+            Some(_) if ret.play_input.is_some() => return Err("a replay runs in its own world, not that of a world code".to_string()),
+            Some(_) if !ret.lights.is_empty() => return Err("a world code cannot place lights".to_string()),
+            Some(_) if !ret.mutators.is_empty() || ret.timing != Timing::default() || ret.world_size.is_some() || ret.seed.is_some() => {
+                return Err("the world code decides the seed, mutators, timing and size".to_string());
+            },
+            _ => (),
+        }
+        if ret.observe.is_none() && ret.interest != Interest::default() {
+            return Err("--observe-region and --observe-species need --observe".to_string());
+        }
+        if ret.play_input.as_deref().is_some_and(replay::is_bundle) && (!ret.mutators.is_empty() || ret.timing != Timing::default()) {
+            return Err("a bundled replay decides the mutators and timing".to_string());
+        }
+        if ret.play_input.is_some() && ret.seed.is_some() {
+            return Err("a replay runs with the seed it was recorded with".to_string());
+        }
+        //  a recording keeps only the seed, so it is made and replayed in a world the size of the window
+        if (ret.record_input.is_some() || ret.play_input.is_some()) && ret.world_size.is_some() {
+            return Err("input can only be recorded or replayed in a world the size of the window".to_string());
+        }
+        if (ret.record_input.is_some() || ret.play_input.is_some()) && (ret.naming != NamingRule::default() || ret.name_pack.is_some()) {
+            return Err("input can only be recorded or replayed with the default naming".to_string());
+        }
+        if (ret.record_input.is_some() || ret.play_input.is_some()) && ret.scenario.is_some() {
+            return Err("input can only be recorded or replayed without a scenario".to_string());
+        }
+        if (ret.record_input.is_some() || ret.play_input.is_some()) && !ret.lights.is_empty() {
+            return Err("input can only be recorded or replayed in a world without lights".to_string());
+        }
+        //  reseeding draws a seed the recording does not keep
+        if (ret.record_input.is_some() || ret.play_input.is_some()) && ret.screensaver {
+            return Err("input cannot be recorded or replayed with --screensaver".to_string());
+        }
+        //  a recording keeps when the window was away, but not what that did
+        if (ret.record_input.is_some() || ret.play_input.is_some()) && ret.unfocused == power::Unfocused::Pause {
+            return Err("input cannot be recorded or replayed with --unfocused pause".to_string());
+        }
+        if (ret.record_input.is_some() || ret.play_input.is_some()) && ret.chat.is_some() {
+            return Err("input cannot be recorded or replayed with --chat".to_string());
+        }
+        if ret.shell && !ret.headless {
+            return Err("--shell needs --headless".to_string());
+        }
+        if ret.open_report && ret.report_dir.is_none() {
+            return Err("--open-report cannot be used with --no-report".to_string());
+        }
+        if ret.chat_server.is_some() && ret.chat.is_none() {
+            return Err("--chat-server needs --chat".to_string());
+        }
+        Ok(ret)
+    }
+
+    /// Parse the arguments that follow `tournament`.
+    fn parse_tournament<I: Iterator<Item=String>>(mut args: I) -> Result<(Vec<String>, Arena), String> {
+        let mut paths = vec![];
+        let mut arena = Arena::default();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ticks" => {
+                    let ticks = args.next().ok_or("--ticks needs a number of ticks")?;
+                    arena.ticks = ticks.parse().ok()
+                        .filter(|&ticks| ticks > 0)
+                        .ok_or_else(|| format!("invalid number of ticks `{}`", ticks))?;
+                },
+                _ if arg.starts_with('-') => return Err(format!("unexpected argument `{}`", arg)),
+                _ => paths.push(arg),
+            }
+        }
+        if paths.len() < 2 {
+            return Err("a tournament needs at least two blob files".to_string());
+        }
+        Ok((paths, arena))
+    }
+
+    /// Parse the arguments that follow `forage`.
+    fn parse_forage<I: Iterator<Item=String>>(mut args: I) -> Result<(Vec<String>, Benchmark, Option<String>), String> {
+        let mut paths = vec![];
+        let mut benchmark = Benchmark::default();
+        let mut json = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--ticks" => {
+                    let ticks = args.next().ok_or("--ticks needs a number of ticks")?;
+                    benchmark.ticks = ticks.parse().ok()
+                        .filter(|&ticks| ticks > 0)
+                        .ok_or_else(|| format!("invalid number of ticks `{}`", ticks))?;
+                },
+                "--json" => json = Some(args.next().ok_or("--json needs a path")?),
+                _ if arg.starts_with('-') => return Err(format!("unexpected argument `{}`", arg)),
+                _ => paths.push(arg),
+            }
+        }
+        if paths.is_empty() {
+            return Err("the foraging benchmark needs a blob file".to_string());
+        }
+        Ok((paths, benchmark, json))
+    }
+
+    /// Parse the arguments that follow `bench`.
+    fn parse_bench<I: Iterator<Item=String>>(mut args: I) -> Result<(Bench, Option<String>), String> {
+        let mut bench = Bench::default();
+        let mut json = None;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--blobs" => {
+                    let count = args.next().ok_or("--blobs needs a number of blobs")?;
+                    bench.blobs = count.parse().ok()
+                        .filter(|&count| count > 0)
+                        .ok_or_else(|| format!("invalid number of blobs `{}`", count))?;
+                },
+                "--ticks" => {
+                    let ticks = args.next().ok_or("--ticks needs a number of ticks")?;
+                    bench.ticks = ticks.parse().ok()
+                        .filter(|&ticks| ticks > 0)
+                        .ok_or_else(|| format!("invalid number of ticks `{}`", ticks))?;
+                },
+                "--json" => json = Some(args.next().ok_or("--json needs a path")?),
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+        Ok((bench, json))
+    }
+}
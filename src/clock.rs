@@ -0,0 +1,172 @@
+//! Simulation time.
+//!
+//! The simulation advances in fixed ticks, so its time is
+//! independent of the wall clock: pausing or speeding up the
+//! window does not change what happens inside the world.
+//! Ticks are grouped into an in-world calendar of days, seasons
+//! and years.
+
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub const ALL: [Self; 4] = [Self::Spring, Self::Summer, Self::Autumn, Self::Winter];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Spring => "Spring",
+            Self::Summer => "Summer",
+            Self::Autumn => "Autumn",
+            Self::Winter => "Winter",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Clock {
+    tick: u64,
+    tick_length: f32,
+    //  kept as a sum since the tick length can change
+    time: f64,
+}
+
+impl Clock {
+    /// Seconds of simulation time in a day.
+    pub const DAY_LENGTH: f64 = 30.;
+    pub const DAYS_PER_YEAR: u64 = 20;
+
+    pub fn new(tick_length: f32) -> Self {
+        debug_assert!(tick_length > 0.);
+        Self { tick: 0, tick_length, time: 0. }
+    }
+
+    /// A clock already at some tick, such as a saved one.
+    pub fn restore(tick_length: f32, tick: u64, time: f64) -> Self {
+        debug_assert!(tick_length > 0.);
+        Self { tick, tick_length, time }
+    }
+
+    /// The number of ticks since the simulation started.
+    pub fn tick(&self) -> u64 { self.tick }
+
+    pub fn tick_length(&self) -> f32 { self.tick_length }
+
+    /// Change the length of the ticks to come.
+    pub fn set_tick_length(&mut self, tick_length: f32) {
+        debug_assert!(tick_length > 0.);
+        self.tick_length = tick_length;
+    }
+
+    /// Seconds of simulation time since the simulation started.
+    pub fn time(&self) -> f64 { self.time }
+
+    pub fn advance(&mut self) {
+        self.tick += 1;
+        self.time += self.tick_length as f64;
+    }
+
+    /// Days since the simulation started.
+    pub fn days(&self) -> u64 { (self.time() / Self::DAY_LENGTH) as u64 }
+
+    /// The calendar year, starting from 1.
+    pub fn year(&self) -> u64 { self.days() / Self::DAYS_PER_YEAR + 1 }
+
+    /// The day of the year, starting from 1.
+    pub fn day_of_year(&self) -> u64 { self.days() % Self::DAYS_PER_YEAR + 1 }
+
+    /// How far into the year we are, in [0, 1).
+    pub fn year_fraction(&self) -> f64 {
+        let year_length = Self::DAY_LENGTH * Self::DAYS_PER_YEAR as f64;
+        (self.time() % year_length) / year_length
+    }
+
+    pub fn season(&self) -> Season {
+        Season::ALL[(self.year_fraction() * Season::ALL.len() as f64) as usize]
+    }
+}
+
+impl fmt::Display for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Year {}, Day {} ({})", self.year(), self.day_of_year(), self.season().name())
+    }
+}
+
+/// Fires once every `period` seconds of simulation time.
+#[derive(Debug, Clone)]
+pub struct Interval {
+    period: f64,
+    //  when it last fired, if it did
+    last: Option<f64>,
+}
+
+impl Interval {
+    pub fn new(period: f64) -> Self {
+        Self { period, last: None }
+    }
+
+    /// Change the period, counted from when the interval last fired.
+    /// An infinite period never fires.
+    pub fn set_period(&mut self, period: f64) {
+        self.period = period;
+    }
+
+    /// Returns true if the interval elapsed since it last fired.
+    pub fn poll(&mut self, clock: &Clock) -> bool {
+        if self.last.is_none_or(|last| clock.time() >= last + self.period) {
+            self.last = Some(clock.time());
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_calendar() {
+        let mut clock = Clock::new(1.);
+        assert_eq!((clock.year(), clock.day_of_year(), clock.season()), (1, 1, Season::Spring));
+
+        let ticks_per_year = (Clock::DAY_LENGTH * Clock::DAYS_PER_YEAR as f64) as u64;
+        for _ in 0..ticks_per_year / 2 {
+            clock.advance();
+        }
+        assert_eq!((clock.year(), clock.day_of_year(), clock.season()), (1, 11, Season::Autumn));
+
+        for _ in 0..ticks_per_year / 2 {
+            clock.advance();
+        }
+        assert_eq!((clock.year(), clock.day_of_year(), clock.season()), (2, 1, Season::Spring));
+    }
+
+    #[test]
+    fn test_interval() {
+        let mut clock = Clock::new(0.5);
+        let mut interval = Interval::new(1.);
+        let fired: Vec<bool> = (0..5).map(|_| {
+            let fired = interval.poll(&clock);
+            clock.advance();
+            fired
+        }).collect();
+        assert_eq!(fired, vec![true, false, true, false, true]);
+
+        //  a shorter period counts from the last firing, an infinite one stops it
+        interval.set_period(0.5);
+        assert!(interval.poll(&clock));
+        interval.set_period(f64::INFINITY);
+        clock.advance();
+        assert!(!interval.poll(&clock));
+        interval.set_period(0.5);
+        assert!(interval.poll(&clock));
+    }
+}
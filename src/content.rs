@@ -0,0 +1,444 @@
+//! A browser of the content found on disk, to choose what worlds are
+//! made with without editing files by hand.
+//!
+//! Shift and `M` opens a panel listing, each with a description and a
+//! toggle:
+//!
+//! * the mods in `mods/`, on or off over what their `mod.toml` says
+//! * the species of the base game and the enabled mods, see `species`
+//! * the name packs, the base names and those of each mod, of which
+//!   at most one is drawn from, see `naming`
+//! * the scenarios in `assets/scenarios`, of which at most one runs
+//!
+//! Clicking an entry toggles it, and the choices are kept in `PATH` a
+//! line each, so they last between runs. They apply to the next world
+//! made, which the new world button makes at once, with the same seed.
+//! `--name-pack` and `--scenario` win over what is chosen here, and
+//! lockstep sessions and recorded or replayed input leave the choices
+//! out, as the peer or the replay starts from the files alone.
+//!
+//! ```text
+//! mod    Example    on
+//! species    Wisp    off
+//! names    base
+//! scenario    famine
+//! ```
+//!
+//! # Example
+//!
+//! ```ignore
+//! let settings = Settings::load(content::PATH)?;
+//! let catalog = Catalog::discover()?;
+//! let (loaded_mods, species, names) = catalog.resolve(&settings);
+//! let mut browser = Browser::new(catalog, settings);
+//! if browser.update(&frame, screen_width) == Some(Outcome::Changed) {
+//!     browser.settings().save(content::PATH)?;
+//! }
+//! ```
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    io,
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    window::DrawingContext,
+    fonts::Fonts,
+    input::{Button, Frame},
+    mods::{self, Mod},
+    scenario::Scenario,
+    species::Species,
+    theme::Theme,
+};
+
+/// Where the choices are kept.
+pub const PATH: &str = "content.txt";
+/// The name pack of the names of the base game.
+pub const BASE: &str = "base";
+
+const WIDTH: f32 = 460.;
+const PADDING: f32 = 8.;
+const ROW_HEIGHT: f32 = 26.;
+/// How many rows the panel shows at once.
+const ROWS: usize = 16;
+const BUTTON_HEIGHT: f32 = 18.;
+const FONT_SIZE: i32 = 10;
+
+/// What kind of content an entry is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Mod,
+    Species,
+    NamePack,
+    Scenario,
+}
+
+impl Kind {
+    pub const ALL: [Self; 4] = [Self::Mod, Self::Species, Self::NamePack, Self::Scenario];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Self::Mod => "Mods",
+            Self::Species => "Species",
+            Self::NamePack => "Name packs",
+            Self::Scenario => "Scenarios",
+        }
+    }
+}
+
+/// A piece of content as listed, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub kind: Kind,
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+}
+
+/// What is chosen in the browser, over what the files say.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Settings {
+    mods: BTreeMap<String, bool>,
+    disabled_species: BTreeSet<String>,
+    name_pack: Option<String>,
+    scenario: Option<String>,
+}
+
+impl Settings {
+    /// The choices kept in a file, of which there are none while it is missing.
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map_err(|e| format!("{}: {}", path, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path, e)),
+        }
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut ret = Self::default();
+        for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            match line.split('\t').collect::<Vec<_>>()[..] {
+                ["mod", name, "on"] => { ret.mods.insert(name.to_string(), true); },
+                ["mod", name, "off"] => { ret.mods.insert(name.to_string(), false); },
+                ["species", name, "off"] => { ret.disabled_species.insert(name.to_string()); },
+                ["names", pack] => ret.name_pack = Some(pack.to_string()),
+                ["scenario", name] => ret.scenario = Some(name.to_string()),
+                _ => return Err(format!("line {}: invalid line `{}`", i + 1, line)),
+            }
+        }
+        Ok(ret)
+    }
+
+    fn to_text(&self) -> String {
+        let mut ret = String::new();
+        for (name, &on) in &self.mods {
+            ret += &format!("mod\t{}\t{}\n", name, if on { "on" } else { "off" });
+        }
+        for name in &self.disabled_species {
+            ret += &format!("species\t{}\toff\n", name);
+        }
+        if let Some(pack) = &self.name_pack {
+            ret += &format!("names\t{}\n", pack);
+        }
+        if let Some(name) = &self.scenario {
+            ret += &format!("scenario\t{}\n", name);
+        }
+        ret
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    /// The name pack chosen, if any.
+    pub fn name_pack(&self) -> Option<&str> { self.name_pack.as_deref() }
+
+    /// The name of the scenario chosen, if any, see `scenario::resolve`.
+    pub fn scenario(&self) -> Option<&str> { self.scenario.as_deref() }
+
+    /// Turn an entry on if it is off and off if it is on.
+    pub fn toggle(&mut self, entry: &Entry) {
+        match entry.kind {
+            Kind::Mod => { self.mods.insert(entry.name.clone(), !entry.enabled); },
+            Kind::Species if entry.enabled => { self.disabled_species.insert(entry.name.clone()); },
+            Kind::Species => { self.disabled_species.remove(&entry.name); },
+            Kind::NamePack => self.name_pack = Some(entry.name.clone()).filter(|_| !entry.enabled),
+            Kind::Scenario => self.scenario = Some(entry.name.clone()).filter(|_| !entry.enabled),
+        }
+    }
+}
+
+/// The content found on disk, as the files have it.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    pub mods: Vec<Mod>,
+    /// The species and names of the base game.
+    pub species: Vec<Species>,
+    pub names: Vec<String>,
+    /// The scenarios that could be read, by name.
+    pub scenarios: Vec<(String, Scenario)>,
+}
+
+impl Catalog {
+    /// Find the mods, species, names and scenarios, see the module.
+    pub fn discover() -> Result<Self, String> {
+        let mut scenarios = vec![];
+        if let Ok(entries) = fs::read_dir(Scenario::DIR) {
+            let mut paths: Vec<_> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+            paths.sort();
+            for path in paths.iter().filter(|path| path.extension().is_some_and(|extension| extension == "toml")) {
+                if let (Some(name), Ok(scenario)) = (path.file_stem(), Scenario::load(path)) {
+                    scenarios.push((name.to_string_lossy().into_owned(), scenario));
+                }
+            }
+        }
+        let names = fs::read_to_string("names.txt").map_err(|e| format!("names.txt: {}", e))?;
+        Ok(Self {
+            mods: Mod::load_dir(Mod::DIR).map_err(|e| e.to_string())?,
+            species: Species::load_dir(Species::ASSETS_DIR).map_err(|e| e.to_string())?,
+            names: names.split_whitespace().map(str::to_string).collect(),
+            scenarios,
+        })
+    }
+
+    /// The mods with the choices made, and the species and names they
+    /// give along with those of the base game.
+    pub fn resolve(&self, settings: &Settings) -> (Vec<Mod>, Vec<Species>, Vec<String>) {
+        let mut mods = self.mods.clone();
+        for m in &mut mods {
+            m.enabled = settings.mods.get(&m.name).copied().unwrap_or(m.enabled);
+        }
+        let (mut species, names) = mods::merge(&mods, self.species.clone(), self.names.clone());
+        species.retain(|species| !settings.disabled_species.contains(&species.name));
+        (mods, species, names)
+    }
+
+    /// Everything listed in the browser, kind by kind.
+    pub fn entries(&self, settings: &Settings) -> Vec<Entry> {
+        let (mods, _, _) = self.resolve(settings);
+        let (species, _) = mods::merge(&mods, self.species.clone(), vec![]);
+        let mut ret: Vec<_> = mods.iter().map(|m| Entry {
+            kind: Kind::Mod,
+            name: m.name.clone(),
+            description: format!("{} species, {} names. {}", m.species.len(), m.names.len(), m.description),
+            enabled: m.enabled,
+        }).collect();
+        ret.extend(species.iter().map(|species| Entry {
+            kind: Kind::Species,
+            name: species.name.clone(),
+            description: format!("{}, {} brain, spawn weight {}", species.diet.name(), species.brain.name(), species.spawn_weight),
+            enabled: !settings.disabled_species.contains(&species.name),
+        }));
+        let packs = std::iter::once((BASE, "the names of the base game", &self.names))
+            .chain(mods.iter().filter(|m| !m.names.is_empty()).map(|m| (m.name.as_str(), m.description.as_str(), &m.names)));
+        ret.extend(packs.map(|(name, description, names)| Entry {
+            kind: Kind::NamePack,
+            name: name.to_string(),
+            description: format!("{} names, {}", names.len(), description),
+            enabled: settings.name_pack() == Some(name),
+        }));
+        ret.extend(self.scenarios.iter().map(|(name, scenario)| Entry {
+            kind: Kind::Scenario,
+            name: name.clone(),
+            description: format!("{}: {}", scenario.name, scenario.description),
+            enabled: settings.scenario() == Some(name),
+        }));
+        ret
+    }
+}
+
+/// What a click in the browser did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// An entry was toggled.
+    Changed,
+    /// The new world button was pressed.
+    NewWorld,
+}
+
+/// A row of the browser, a kind's title or an entry.
+enum Row<'a> {
+    Title(Kind),
+    Entry(&'a Entry),
+}
+
+/// The panel, see the module.
+#[derive(Debug, Clone)]
+pub struct Browser {
+    catalog: Catalog,
+    settings: Settings,
+    entries: Vec<Entry>,
+    //  the first row shown
+    scroll: usize,
+}
+
+impl Browser {
+    pub fn new(catalog: Catalog, settings: Settings) -> Self {
+        let entries = catalog.entries(&settings);
+        Self { catalog, settings, entries, scroll: 0 }
+    }
+
+    pub fn catalog(&self) -> &Catalog { &self.catalog }
+
+    pub fn settings(&self) -> &Settings { &self.settings }
+
+    /// Where the panel is, at the top middle of the window.
+    pub fn rect(screen_width: i32) -> Rectangle {
+        let height = 3. * PADDING + 14. + ROWS as f32 * ROW_HEIGHT + BUTTON_HEIGHT;
+        Rectangle::new((screen_width as f32 - WIDTH) / 2., 60., WIDTH, height)
+    }
+
+    fn row_rect(i: usize, screen_width: i32) -> Rectangle {
+        let rect = Self::rect(screen_width);
+        Rectangle::new(rect.x + PADDING, rect.y + PADDING + 14. + i as f32 * ROW_HEIGHT, rect.width - 2. * PADDING, ROW_HEIGHT)
+    }
+
+    fn button(screen_width: i32) -> Rectangle {
+        let last = Self::row_rect(ROWS, screen_width);
+        Rectangle::new(last.x + last.width - 100., last.y + PADDING, 100., BUTTON_HEIGHT)
+    }
+
+    fn rows(&self) -> Vec<Row<'_>> {
+        let mut ret = vec![];
+        for kind in Kind::ALL {
+            ret.push(Row::Title(kind));
+            ret.extend(self.entries.iter().filter(|entry| entry.kind == kind).map(Row::Entry));
+        }
+        ret
+    }
+
+    /// Whether the panel takes the mouse in this frame, leaving it to no gesture.
+    pub fn captures(&self, frame: &Frame, screen_width: i32) -> bool {
+        frame.button == Button::Pressed && Self::rect(screen_width).check_collision_point_rec(frame.mouse)
+    }
+
+    /// Scroll the rows by the turn of the mouse wheel.
+    pub fn scroll(&mut self, wheel: f32) {
+        let most = self.rows().len().saturating_sub(ROWS);
+        self.scroll = (self.scroll as isize - (wheel * 3.) as isize).clamp(0, most as isize) as usize;
+    }
+
+    /// Read this frame's input, toggling the entry clicked.
+    pub fn update(&mut self, frame: &Frame, screen_width: i32) -> Option<Outcome> {
+        if frame.button != Button::Pressed {
+            return None;
+        }
+        if Self::button(screen_width).check_collision_point_rec(frame.mouse) {
+            return Some(Outcome::NewWorld);
+        }
+        let clicked = (0..ROWS).find(|&i| Self::row_rect(i, screen_width).check_collision_point_rec(frame.mouse))?;
+        let entry = match self.rows().into_iter().nth(self.scroll + clicked)? {
+            Row::Entry(entry) => entry.clone(),
+            Row::Title(_) => return None,
+        };
+        self.settings.toggle(&entry);
+        self.entries = self.catalog.entries(&self.settings);
+        Some(Outcome::Changed)
+    }
+
+    pub fn draw(&self, draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts) {
+        let screen_width = draw.get_screen_width();
+        let rect = Self::rect(screen_width);
+        draw.draw_rectangle_rec(rect, theme.panel);
+        draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+        let title = "Content, applied to the next world (shift M to close)";
+        fonts.draw_text(draw, title, (rect.x + PADDING) as i32, (rect.y + PADDING) as i32, FONT_SIZE, theme.text);
+        for (i, row) in self.rows().iter().skip(self.scroll).take(ROWS).enumerate() {
+            let row_rect = Self::row_rect(i, screen_width);
+            let (x, y) = (row_rect.x as i32, row_rect.y as i32);
+            match row {
+                Row::Title(kind) => fonts.draw_text(draw, kind.title(), x, y + 12, FONT_SIZE, theme.accent),
+                Row::Entry(entry) => {
+                    let toggle = Rectangle::new(row_rect.x, row_rect.y + 4., 10., 10.);
+                    if entry.enabled {
+                        draw.draw_rectangle_rec(toggle, theme.ok);
+                    }
+                    draw.draw_rectangle_lines_ex(toggle, 1, theme.text);
+                    fonts.draw_text(draw, &entry.name, x + 16, y + 4, FONT_SIZE, theme.text);
+                    let description = truncate(fonts, &entry.description, row_rect.width as i32 - 16);
+                    fonts.draw_text(draw, &description, x + 16, y + 15, FONT_SIZE, theme.muted_text);
+                },
+            }
+        }
+        let button = Self::button(screen_width);
+        draw.draw_rectangle_lines_ex(button, 1, theme.panel_border);
+        let label = "new world";
+        let x = button.x as i32 + (button.width as i32 - fonts.measure(label, FONT_SIZE)) / 2;
+        fonts.draw_text(draw, label, x, button.y as i32 + 4, FONT_SIZE, theme.text);
+    }
+}
+
+/// Text cut short with an ellipsis to fit a width.
+fn truncate(fonts: &Fonts, text: &str, width: i32) -> String {
+    if fonts.measure(text, FONT_SIZE) <= width {
+        return text.to_string();
+    }
+    let mut ret: String = text.to_string();
+    while !ret.is_empty() && fonts.measure(&format!("{}...", ret), FONT_SIZE) > width {
+        ret.pop();
+    }
+    format!("{}...", ret.trim_end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml;
+
+    #[test]
+    fn test_content() {
+        let text = "mod\tExample\ton\nspecies\tWisp\toff\n\nnames\tbase\nscenario\tfamine\n";
+        let settings = Settings::parse(text).unwrap();
+        assert_eq!(settings.to_text(), text.replace("\n\n", "\n"));
+        assert_eq!((settings.name_pack(), settings.scenario()), (Some("base"), Some("famine")));
+        assert!(Settings::parse("mod\tExample\tmaybe").unwrap_err().contains("line 1"));
+
+        let species = |name: &str| {
+            let table = toml::parse(&format!("name = \"{}\"", name)).unwrap();
+            Species::from_table(&table).unwrap()
+        };
+        let pack = Mod {
+            name: "Example".to_string(), description: "A pack".to_string(), load_order: 0, enabled: false,
+            species: vec![species("Wisp")], names: vec!["Zed".to_string()], unsupported: vec![],
+        };
+        let catalog = Catalog {
+            mods: vec![pack],
+            species: vec![species("Grazer")],
+            names: vec!["Ann".to_string(), "Bob".to_string()],
+            scenarios: vec![("famine".to_string(), Scenario { name: "Famine".to_string(), ..Scenario::default() })],
+        };
+        let mut settings = Settings::default();
+        let names = |entries: &[Entry]| entries.iter().map(|entry| (entry.kind, entry.name.clone(), entry.enabled)).collect::<Vec<_>>();
+        let entries = catalog.entries(&settings);
+        assert_eq!(names(&entries), [
+            (Kind::Mod, "Example".to_string(), false), (Kind::Species, "Grazer".to_string(), true),
+            (Kind::NamePack, "base".to_string(), false), (Kind::NamePack, "Example".to_string(), false),
+            (Kind::Scenario, "famine".to_string(), false),
+        ]);
+
+        //  turning the mod on brings its species and names, which can be turned off again
+        settings.toggle(&entries[0]);
+        let (mods, species, names) = catalog.resolve(&settings);
+        assert!(mods[0].enabled && species.len() == 2 && names.len() == 3);
+        let wisp = catalog.entries(&settings).into_iter().find(|entry| entry.name == "Wisp").unwrap();
+        settings.toggle(&wisp);
+        assert_eq!(catalog.resolve(&settings).1.iter().map(|species| species.name.as_str()).collect::<Vec<_>>(), ["Grazer"]);
+
+        //  clicking a pack chooses it, and clicking it again chooses none
+        let mut browser = Browser::new(catalog, settings);
+        let row = Browser::row_rect(6, 800);
+        let mut click = Frame::idle(0., Vector2::new(row.x + 20., row.y + 10.));
+        click.button = Button::Pressed;
+        assert!(browser.captures(&click, 800));
+        assert_eq!(browser.update(&click, 800), Some(Outcome::Changed));
+        assert_eq!(browser.settings().name_pack(), Some("base"));
+        browser.update(&click, 800);
+        assert_eq!(browser.settings().name_pack(), None);
+        let button = Browser::button(800);
+        click.mouse = Vector2::new(button.x + 1., button.y + 1.);
+        assert_eq!(browser.update(&click, 800), Some(Outcome::NewWorld));
+    }
+}
@@ -0,0 +1,104 @@
+//! Crash dumps, so a panic can be reproduced from the state it happened in.
+//!
+//! A panic hook keeps the message and where it was raised, and
+//! after the main loop unwinds the worlds are written to a new
+//! directory under `crashes/` while they are still as the panic
+//! left them:
+//!
+//! * `panic.txt` has the message, location and seed of each world
+//! * `world-<label>.txt` has the state of each world, see `Simulation::write_state`
+//! * `events-<label>.txt` has the last events of each world, oldest
+//!   first, in the `key=value` format of the narrator
+//!
+//! # Example
+//!
+//! ```ignore
+//! crash::install();
+//! if let Err(payload) = panic::catch_unwind(|| run(&mut sim)) {
+//!     crash::dump(crash::DIR, &[("A", &sim)])?;
+//!     panic::resume_unwind(payload);
+//! }
+//! ```
+
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    panic,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time,
+};
+
+use crate::{simulation::Simulation, history::Query, narrator};
+
+/// Where crash dumps are written.
+pub const DIR: &str = "crashes";
+/// How many of the latest events a dump has.
+pub const EVENTS: usize = 1000;
+
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// Keep the message of every panic for the dump, then report it as usual.
+pub fn install() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if let Ok(mut last) = LAST_PANIC.lock() {
+            *last = Some(info.to_string());
+        }
+        default_hook(info);
+    }));
+}
+
+/// Write the state of labeled worlds to a new directory in `dir`, returning its path.
+pub fn dump(dir: &str, worlds: &[(&str, &Simulation)]) -> io::Result<PathBuf> {
+    let since_epoch = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default();
+    let path = Path::new(dir).join(format!("crash-{}", since_epoch.as_millis()));
+    fs::create_dir_all(&path)?;
+
+    let message = LAST_PANIC.lock().ok().and_then(|last| last.clone());
+    let mut out = fs::File::create(path.join("panic.txt"))?;
+    writeln!(out, "{}", message.as_deref().unwrap_or("unknown panic"))?;
+    for (label, sim) in worlds {
+        writeln!(out, "world {} seed={} tick={}", label, sim.seed(), sim.clock().tick())?;
+    }
+
+    for (label, sim) in worlds {
+        let mut out = BufWriter::new(fs::File::create(path.join(format!("world-{}.txt", label)))?);
+        sim.write_state(&mut out)?;
+        out.flush()?;
+
+        let events: Vec<_> = sim.history().query(Query::all()).collect();
+        let mut out = BufWriter::new(fs::File::create(path.join(format!("events-{}.txt", label)))?);
+        for (tick, event) in &events[events.len().saturating_sub(EVENTS)..] {
+            narrator::write_event(&mut out, *tick, event)?;
+        }
+        out.flush()?;
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::prelude::*;
+
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_dump() {
+        let mut sim = Simulation::new(SimulationConfig { seed: 99, ..SimulationConfig::new(Vector2::new(300., 300.)) });
+        sim.insert_food(Vector2::new(10., 20.));
+        sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+
+        let dir = std::env::temp_dir().join("blobs-crash-test");
+        let path = dump(dir.to_str().unwrap(), &[("A", &sim)]).unwrap();
+        let panic = fs::read_to_string(path.join("panic.txt")).unwrap();
+        assert!(panic.ends_with("world A seed=99 tick=0\n"));
+        let world = fs::read_to_string(path.join("world-A.txt")).unwrap();
+        assert!(world.starts_with("world seed=99 tick=0"));
+        assert_eq!(world.lines().filter(|line| line.starts_with("blob 0 ")).count(), 1);
+        assert_eq!(world.lines().filter(|line| line.starts_with("food 0 ")).count(), 1);
+        assert_eq!(fs::read_to_string(path.join("events-A.txt")).unwrap(), "born tick=0 key=0\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
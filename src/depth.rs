@@ -0,0 +1,113 @@
+//! Telling overlapping blobs apart: which is on top and how crowded a
+//! pile is.
+//!
+//! Blobs are drawn from the largest to the smallest, those of the same
+//! size by their keys, so a small blob is never hidden under a large
+//! one and the order does not change from frame to frame. In the
+//! classic and detailed styles, see `style`, each blob casts a soft
+//! shadow down and to the right over the blobs drawn before it, which
+//! shows which of two is on top.
+//!
+//! With `Shift+\`, blobs are tinted toward the warning color of the
+//! theme the more others their bodies overlap, up to `CROWDED`, so the
+//! dense middle of a cluster stands out.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let crowding = depth::crowding(sim.blobs());
+//! for (key, blob) in depth::order(sim.blobs()) {
+//!     depth::draw_shadow(&mut draw, blob.pos(), blob.radius(), zoom);
+//!     blob.draw(&mut draw, &context);
+//!     depth::draw_crowding(&mut draw, blob.pos(), blob.radius(), zoom, crowding[&key], &theme);
+//! }
+//! ```
+
+use std::{cmp::Ordering, collections::BTreeMap};
+
+use raylib::prelude::*;
+
+use crate::{
+    keyed_set::Key,
+    shapes,
+    simulation::Blob,
+    theme::Theme,
+};
+
+/// How many overlapping blobs tint a blob the most.
+pub const CROWDED: usize = 6;
+/// The most a crowded blob is tinted.
+const MAX_TINT: f32 = 0.55;
+/// How far a shadow falls, as a part of the radius of its blob.
+const SHADOW_OFFSET: f32 = 0.2;
+/// The opacity of each of the circles a shadow is made of.
+const SHADOW_ALPHA: f32 = 0.09;
+
+/// The blobs in the order they are drawn, see the module.
+pub fn order<'a>(blobs: impl Iterator<Item=(Key<Blob>, &'a Blob)>) -> Vec<(Key<Blob>, &'a Blob)> {
+    let mut ret: Vec<_> = blobs.collect();
+    ret.sort_by(|(a_key, a), (b_key, b)| match b.radius().total_cmp(&a.radius()) {
+        Ordering::Equal => a_key.cmp(b_key),
+        ordering => ordering,
+    });
+    ret
+}
+
+/// How many other blobs the body of each blob overlaps.
+pub fn crowding<'a>(blobs: impl Iterator<Item=(Key<Blob>, &'a Blob)>) -> BTreeMap<Key<Blob>, usize> {
+    let mut blobs: Vec<_> = blobs.map(|(key, blob)| (key, blob.pos(), blob.radius())).collect();
+    blobs.sort_by(|(_, a, _), (_, b, _)| a.x.total_cmp(&b.x));
+    let largest = blobs.iter().map(|&(_, _, radius)| radius).fold(0., f32::max);
+    let mut ret: BTreeMap<_, _> = blobs.iter().map(|&(key, _, _)| (key, 0)).collect();
+    //  sorted by x, only those near enough along x can overlap
+    for (i, &(key, pos, radius)) in blobs.iter().enumerate() {
+        for &(other, other_pos, other_radius) in blobs[i + 1..].iter().take_while(|(_, other_pos, _)| other_pos.x - pos.x < radius + largest) {
+            if (other_pos - pos).length() < radius + other_radius {
+                *ret.get_mut(&key).unwrap() += 1;
+                *ret.get_mut(&other).unwrap() += 1;
+            }
+        }
+    }
+    ret
+}
+
+/// Draw the soft shadow of a blob of a radius at a place.
+pub fn draw_shadow<D: RaylibDraw>(draw: &mut D, pos: Vector2, radius: f32, zoom: f32) {
+    let center = pos + Vector2::one() * radius * SHADOW_OFFSET;
+    //  circles growing fainter outward, darkest under the blob
+    for spread in [1.15, 1.05, 0.95] {
+        shapes::draw_circle(draw, center, radius * spread, zoom, Color::BLACK.fade(SHADOW_ALPHA));
+    }
+}
+
+/// How much a blob overlapping some others is tinted, from 0 to `MAX_TINT`.
+pub fn tint(overlaps: usize) -> f32 {
+    overlaps.min(CROWDED) as f32 / CROWDED as f32 * MAX_TINT
+}
+
+/// Tint a blob of a radius at a place by how many others it overlaps.
+pub fn draw_crowding<D: RaylibDraw>(draw: &mut D, pos: Vector2, radius: f32, zoom: f32, overlaps: usize, theme: &Theme) {
+    if overlaps == 0 { return; }
+    shapes::draw_circle(draw, pos, radius, zoom, theme.warning.fade(tint(overlaps)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, Simulation, SimulationConfig};
+
+    #[test]
+    fn test_depth() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let small = sim.insert_blob(BlobConfig { radius: 3., ..BlobConfig::new(Vector2::new(100., 100.)) });
+        let large = sim.insert_blob(BlobConfig { radius: 10., ..BlobConfig::new(Vector2::new(108., 100.)) });
+        let alike = sim.insert_blob(BlobConfig { radius: 3., ..BlobConfig::new(Vector2::new(250., 250.)) });
+        let order: Vec<_> = order(sim.blobs()).into_iter().map(|(key, _)| key).collect();
+        assert_eq!(order, [large, small, alike]);
+
+        let crowding = crowding(sim.blobs());
+        assert_eq!((crowding[&small], crowding[&large], crowding[&alike]), (1, 1, 0));
+        assert_eq!(tint(0), 0.);
+        assert_eq!(tint(CROWDED * 2), MAX_TINT);
+    }
+}
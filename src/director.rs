@@ -0,0 +1,173 @@
+//! A director that keeps the camera on where the most is happening,
+//! toggled with `Q`, for watching or streaming without a hand on the
+//! mouse.
+//!
+//! The director notes every birth where the child appears and every
+//! kill where the victim died, each counting for less as it grows
+//! older and forgotten after `MEMORY` seconds. A place is as
+//! interesting as the blobs crowding within `REACH` of it, so large
+//! herds draw the eye, and the moments noted there. The view eases
+//! over to the most interesting place and stays at least `DWELL`
+//! seconds, then moves on only for a place clearly more interesting
+//! than where it is.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::director::Director;
+//! # let (camera_center, tick, frame_seconds) = (sim.size() / 2., 0, 1. / 60.);
+//! let mut director = Director::new(camera_center);
+//! //  after every tick
+//! director.collect(&sim, tick);
+//! //  every frame
+//! let (center, zoom) = director.update(&sim, frame_seconds);
+//! ```
+
+use std::collections::VecDeque;
+
+use raylib::prelude::*;
+
+use crate::{simulation::{Simulation, Event}, history::{Kind, Query}, screensaver, tour::{Tour, Keyframe}};
+
+/// Seconds a moment is remembered.
+const MEMORY: f32 = 20.;
+/// Seconds the view stays at a place at least.
+const DWELL: f32 = 8.;
+/// Seconds the camera takes to move to the next place.
+const TRAVEL: f32 = 3.;
+/// Seconds between looks for a more interesting place, once the view has dwelt.
+const LOOK_PERIOD: f32 = 1.;
+/// How far from a place what happens counts for it.
+const REACH: f32 = 150.;
+/// How many blobs a fresh birth counts as.
+const BIRTH: f32 = 3.;
+/// How many blobs a fresh kill counts as.
+const KILL: f32 = 6.;
+/// How many times as interesting another place must be to move on.
+const MARGIN: f32 = 1.5;
+/// How far in places are seen.
+const ZOOM: f32 = 1.5;
+/// How many moments are remembered at most.
+const MAX_MOMENTS: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Moment {
+    pos: Vector2,
+    weight: f32,
+    //  seconds since it happened
+    age: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Director {
+    //  oldest first
+    moments: VecDeque<Moment>,
+    //  the way to the current place, timed from when it was set out on
+    leg: Tour,
+    time: f32,
+}
+
+impl Director {
+    /// Start at a place, looking for a better one at once.
+    pub fn new(center: Vector2) -> Self {
+        let mut leg = Tour::new();
+        leg.push(Keyframe { time: 0., center, zoom: ZOOM }).unwrap();
+        Self { moments: VecDeque::new(), leg, time: TRAVEL + DWELL }
+    }
+
+    /// Note the births and kills of the ticks since one.
+    pub fn collect(&mut self, sim: &Simulation, since: u64) {
+        let births = sim.history().query(Query { kind: Some(Kind::Born), ..Query::ticks(since..u64::MAX) })
+            .filter_map(|(_, event)| match event { Event::Born(key) => sim.get_blob(*key), _ => None })
+            .map(|blob| Moment { pos: blob.pos(), weight: BIRTH, age: 0. });
+        //  a kill shows as the energy the victim had
+        let kills = sim.energy_changes().iter()
+            .filter(|change| change.amount < 0.)
+            .map(|change| Moment { pos: change.pos, weight: KILL, age: 0. });
+        for moment in births.chain(kills) {
+            if self.moments.len() == MAX_MOMENTS {
+                self.moments.pop_front();
+            }
+            self.moments.push_back(moment);
+        }
+    }
+
+    /// How interesting a place is, see the module documentation.
+    fn interest(&self, sim: &Simulation, place: Vector2) -> f32 {
+        let near = |pos: Vector2| (pos - place).length() <= REACH;
+        let crowd = sim.blobs().filter(|(_, blob)| near(blob.pos())).count() as f32;
+        let moments: f32 = self.moments.iter()
+            .filter(|moment| near(moment.pos))
+            .map(|moment| moment.weight * (1. - moment.age / MEMORY))
+            .sum();
+        crowd + moments
+    }
+
+    /// Move on by some seconds, returning the middle of the view and
+    /// the zoom.
+    pub fn update(&mut self, sim: &Simulation, seconds: f32) -> (Vector2, f32) {
+        for moment in &mut self.moments {
+            moment.age += seconds;
+        }
+        self.moments.retain(|moment| moment.age < MEMORY);
+        self.time += seconds;
+        let (center, zoom) = self.leg.sample(self.time).unwrap_or_else(|| {
+            let last = self.leg.keyframes().last().unwrap();
+            (last.center, last.zoom)
+        });
+        if self.time < TRAVEL + DWELL {
+            return (center, zoom);
+        }
+        let places = screensaver::hotspots(sim).into_iter().chain(self.moments.iter().map(|moment| moment.pos));
+        let best = places
+            .map(|place| (self.interest(sim, place), place))
+            .max_by(|a, b| a.0.total_cmp(&b.0));
+        match best {
+            Some((interest, place)) if interest > MARGIN * self.interest(sim, center) && (place - center).length() > REACH => {
+                self.leg = Tour::new();
+                self.leg.push(Keyframe { time: 0., center, zoom }).unwrap();
+                self.leg.push(Keyframe { time: TRAVEL, center: place, zoom: ZOOM }).unwrap();
+                self.time = 0.;
+            },
+            //  look again in a while
+            _ => self.time = TRAVEL + DWELL - LOOK_PERIOD,
+        }
+        (center, zoom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_director() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(1000., 1000.)));
+        for i in 0..3 {
+            sim.insert_blob(BlobConfig::new(Vector2::new(200. + i as f32, 200.)));
+        }
+        let mut director = Director::new(Vector2::new(800., 800.));
+        //  the herd is more interesting than the empty corner
+        assert_eq!(director.update(&sim, 0.), (Vector2::new(800., 800.), ZOOM));
+        let (center, _) = director.update(&sim, TRAVEL);
+        assert!((center - Vector2::new(201., 200.)).length() < REACH, "{:?}", center);
+
+        //  a kill far away is not enough to leave before the dwell is over
+        director.moments.push_back(Moment { pos: Vector2::new(800., 200.), weight: KILL, age: 0. });
+        let (center, _) = director.update(&sim, 1.);
+        assert!((center - Vector2::new(201., 200.)).length() < REACH);
+        //  but a few are once it is
+        for _ in 0..2 {
+            director.moments.push_back(Moment { pos: Vector2::new(800., 200.), weight: KILL, age: 0. });
+        }
+        director.update(&sim, DWELL);
+        let (center, _) = director.update(&sim, TRAVEL);
+        assert!((center - Vector2::new(800., 200.)).length() < 1., "{:?}", center);
+        assert_eq!(director.interest(&sim, Vector2::new(500., 900.)), 0.);
+    }
+}
@@ -0,0 +1,281 @@
+//! The `doctor` subcommand, which checks that the game has what it
+//! needs on this machine, and the warning shown in game when it does not.
+//!
+//! `blobs doctor` checks, and prints a line for each:
+//!
+//! * the assets: the font, the shader, the names and every species,
+//!   scenario and mod, each read as the game reads it
+//! * the settings kept between runs, see `content`, `benchmark`,
+//!   `placement`, `recent` and `leaderboard`, a missing one being fine
+//! * that the directories worlds, crashes, exports and chunks are
+//!   written to can be written to
+//! * the threads of the machine, which predictions and loading run on
+//!   beside the simulation
+//! * the `sqlite3` program, which `--stats-sqlite` writes through, a
+//!   missing one being a warning
+//! * the renderer, by opening a small window and drawing through the
+//!   post-processing shader, unless there is no display to open it on
+//!
+//! ```text
+//! ok       font       assets/fonts/DejaVuSans.ttf
+//! failed   settings   content.txt: line 2: invalid line `species`
+//! warning  threads    1 hardware thread, predictions and loading slow the simulation
+//! 1 failed, 1 warning
+//! ```
+//!
+//! The game checks the same but the renderer as it starts, and the
+//! post-processing as it loads, and shows a warning sign at the bottom
+//! right while anything failed or warns, listing what when the mouse
+//! is over it.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut checks = doctor::diagnose();
+//! checks.push(doctor::renderer(&mut window));
+//! doctor::write_report(&mut io::stdout(), &checks)?;
+//! ```
+
+use std::{
+    env,
+    fs,
+    io::{self, Write},
+    path::Path,
+    process,
+    thread,
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    window::{DrawingContext, PostProcessing, Window},
+    benchmark::{self, Recommendation},
+    chunk,
+    crash,
+    exchange,
+    fonts::Fonts,
+    content,
+    leaderboard,
+    mods::Mod,
+    placement,
+    recent::{self, Recent},
+    scenario::Scenario,
+    species::Species,
+    sqlite,
+    theme::Theme,
+};
+
+/// The size of the warning sign, in pixels.
+const SIGN: f32 = 22.;
+const FONT_SIZE: i32 = 16;
+
+/// How a check came out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Status {
+    Ok,
+    Warning,
+    Failed,
+}
+
+impl Status {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Warning => "warning",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// What was checked and what was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+}
+
+impl Check {
+    pub fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self { name, status, detail: detail.into() }
+    }
+
+    /// A check of something read, which failed with its error.
+    fn of<T>(name: &'static str, result: Result<T, String>, detail: impl FnOnce(T) -> String) -> Self {
+        match result {
+            Ok(value) => Self::new(name, Status::Ok, detail(value)),
+            Err(e) => Self::new(name, Status::Failed, e),
+        }
+    }
+}
+
+/// Everything but the renderer, see the module.
+pub fn diagnose() -> Vec<Check> {
+    let mut ret = vec![];
+    for (name, path) in [("font", Fonts::PATH), ("shader", PostProcessing::SHADER_PATH), ("names", "names.txt")] {
+        ret.push(match fs::metadata(path) {
+            Ok(_) => Check::new(name, Status::Ok, path),
+            Err(e) => Check::new(name, Status::Failed, format!("{}: {}", path, e)),
+        });
+    }
+    ret.push(Check::of("species", Species::load_dir(Species::ASSETS_DIR).map_err(|e| e.to_string()), |species| {
+        format!("{} in {}", species.len(), Species::ASSETS_DIR)
+    }));
+    ret.push(scenarios());
+    ret.push(Check::of("mods", Mod::load_dir(Mod::DIR).map_err(|e| e.to_string()), |mods| {
+        format!("{} in {}, {} enabled", mods.len(), Mod::DIR, mods.iter().filter(|m| m.enabled).count())
+    }));
+
+    ret.push(Check::of("settings", content::Settings::load(content::PATH), |_| content::PATH.to_string()));
+    ret.push(Check::of("benchmark", Recommendation::load(benchmark::PATH), |_| benchmark::PATH.to_string()));
+    ret.push(Check::of("window", placement::load(placement::PATH), |_| placement::PATH.to_string()));
+    ret.push(Check::of("recent", Recent::load(recent::PATH).map_err(|e| format!("{}: {}", recent::PATH, e)), |_| recent::PATH.to_string()));
+    ret.push(Check::of("records", leaderboard::load(leaderboard::PATH).map_err(|e| format!("{}: {}", leaderboard::PATH, e)), |_| leaderboard::PATH.to_string()));
+
+    for dir in [".", crash::DIR, exchange::DIR, chunk::DIR] {
+        ret.push(writable(dir));
+    }
+    ret.push(threads(thread::available_parallelism().map_or(1, |threads| threads.get())));
+    ret.push(sqlite3(sqlite::version()));
+    ret
+}
+
+/// Whether every scenario can be read.
+fn scenarios() -> Check {
+    let entries = match fs::read_dir(Scenario::DIR) {
+        Ok(entries) => entries,
+        Err(e) => return Check::new("scenarios", Status::Failed, format!("{}: {}", Scenario::DIR, e)),
+    };
+    let paths: Vec<_> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|extension| extension == "toml"))
+        .collect();
+    match paths.iter().find_map(|path| Scenario::load(path).err()) {
+        Some(e) => Check::new("scenarios", Status::Failed, e.to_string()),
+        None => Check::new("scenarios", Status::Ok, format!("{} in {}", paths.len(), Scenario::DIR)),
+    }
+}
+
+/// Whether a file can be made in a directory, or in the directory it
+/// would be made in while it does not exist yet.
+fn writable(dir: &str) -> Check {
+    let at = if Path::new(dir).is_dir() { dir } else { "." };
+    let path = Path::new(at).join(format!(".doctor-{}", process::id()));
+    let result = fs::write(&path, "").and_then(|()| fs::remove_file(&path));
+    let name = "writable";
+    match result {
+        Ok(()) if at == dir => Check::new(name, Status::Ok, dir),
+        Ok(()) => Check::new(name, Status::Ok, format!("{}, not made yet", dir)),
+        Err(e) => Check::new(name, Status::Failed, format!("{}: {}", dir, e)),
+    }
+}
+
+/// Whether the machine has threads to spare beside the simulation.
+fn threads(count: usize) -> Check {
+    match count {
+        1 => Check::new("threads", Status::Warning, "1 hardware thread, predictions and loading slow the simulation"),
+        count => Check::new("threads", Status::Ok, format!("{} hardware threads", count)),
+    }
+}
+
+/// Whether statistics can be written to SQLite, which only some runs do.
+fn sqlite3(version: Result<String, String>) -> Check {
+    match version {
+        Ok(version) => Check::new("sqlite3", Status::Ok, format!("version {}", version)),
+        Err(e) => Check::new("sqlite3", Status::Warning, format!("{}, --stats-sqlite needs it", e)),
+    }
+}
+
+/// Whether there is a display to open a window on, as far as the
+/// environment tells; other systems always have one.
+pub fn has_display() -> bool {
+    !cfg!(target_os = "linux") || env::var_os("DISPLAY").is_some() || env::var_os("WAYLAND_DISPLAY").is_some()
+}
+
+/// Whether the window draws through the post-processing shader.
+pub fn renderer(window: &mut Window) -> Check {
+    let drawn = window.load_post_processing()
+        .and_then(|_| window.render_offscreen(64, 64, |draw| draw.clear_background(Color::BLACK)));
+    Check::of("renderer", drawn, |_| "draws through the post-processing shader".to_string())
+}
+
+/// The checks that failed or warn.
+pub fn problems(checks: &[Check]) -> impl Iterator<Item=&Check> {
+    checks.iter().filter(|check| check.status != Status::Ok)
+}
+
+/// Write a line for each check and a count of the problems, see the module.
+pub fn write_report<W: Write>(out: &mut W, checks: &[Check]) -> io::Result<()> {
+    for check in checks {
+        writeln!(out, "{:8} {:10} {}", check.status.name(), check.name, check.detail)?;
+    }
+    let count = |status| checks.iter().filter(|check| check.status == status).count();
+    match (count(Status::Failed), count(Status::Warning)) {
+        (0, 0) => writeln!(out, "everything is in order"),
+        (failed, warnings) => writeln!(out, "{} failed, {} warning{}", failed, warnings, if warnings == 1 { "" } else { "s" }),
+    }
+}
+
+/// Draw a warning sign at the bottom right while there are problems,
+/// listing them above it while the mouse is over it.
+pub fn draw(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, checks: &[Check], mouse: Vector2) {
+    let worst = match problems(checks).map(|check| check.status).max() {
+        Some(worst) => worst,
+        None => return,
+    };
+    let color = if worst == Status::Failed { theme.error } else { theme.warning };
+    let (width, height) = (draw.get_screen_width() as f32, draw.get_screen_height() as f32);
+    let corner = Vector2::new(width - SIGN - 10., height - SIGN - 10.);
+    draw.draw_triangle(
+        Vector2::new(corner.x + SIGN / 2., corner.y),
+        Vector2::new(corner.x, corner.y + SIGN),
+        Vector2::new(corner.x + SIGN, corner.y + SIGN),
+        color,
+    );
+    let x = (corner.x + SIGN / 2.) as i32 - fonts.measure("!", FONT_SIZE) / 2;
+    fonts.draw_text(draw, "!", x, (corner.y + SIGN - FONT_SIZE as f32) as i32, FONT_SIZE, theme.background);
+    if !Rectangle::new(corner.x, corner.y, SIGN, SIGN).check_collision_point_rec(mouse) {
+        return;
+    }
+    let lines: Vec<_> = problems(checks).map(|check| format!("{}: {}", check.name, check.detail)).collect();
+    let padding = 6;
+    let box_width = lines.iter().map(|line| fonts.measure(line, FONT_SIZE)).max().unwrap_or(0) + 2 * padding;
+    let box_height = lines.len() as i32 * FONT_SIZE + 2 * padding;
+    let (left, top) = (width as i32 - 10 - box_width, corner.y as i32 - 6 - box_height);
+    draw.draw_rectangle(left, top, box_width, box_height, theme.panel);
+    draw.draw_rectangle_lines(left, top, box_width, box_height, theme.panel_border);
+    for (i, line) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, left + padding, top + padding + i as i32 * FONT_SIZE, FONT_SIZE, theme.text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_doctor() {
+        //  the tree has every asset, and the settings of the machine it runs on are its own
+        let checks = diagnose();
+        for name in ["font", "shader", "names", "species", "scenarios", "mods"] {
+            let check = checks.iter().find(|check| check.name == name).unwrap();
+            assert_eq!(check.status, Status::Ok, "{}", check.detail);
+        }
+        assert_eq!(threads(1).status, Status::Warning);
+        assert_eq!(threads(8).detail, "8 hardware threads");
+        assert_eq!(sqlite3(Ok("3.45.1".to_string())).detail, "version 3.45.1");
+        assert_eq!(sqlite3(Err("the sqlite3 program was not found".to_string())).status, Status::Warning);
+        assert!(writable(".").status == Status::Ok && writable("no-such-dir").detail.ends_with("not made yet"));
+
+        let checks = [
+            Check::new("font", Status::Ok, "assets/fonts/DejaVuSans.ttf"),
+            Check::new("settings", Status::Failed, "content.txt: line 2: invalid line `species`"),
+            Check::new("threads", Status::Warning, "1 hardware thread"),
+        ];
+        assert_eq!(problems(&checks).count(), 2);
+        let mut out = vec![];
+        write_report(&mut out, &checks).unwrap();
+        let report = String::from_utf8(out).unwrap();
+        assert!(report.starts_with("ok       font       assets/fonts/DejaVuSans.ttf\n"));
+        assert!(report.ends_with("1 failed, 1 warning\n"));
+    }
+}
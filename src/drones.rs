@@ -0,0 +1,130 @@
+//! Camera drones: observers that fly through a world without being
+//! part of it, to sample what happens there without disturbing it.
+//!
+//! A drone has no circle in the physics, so blobs never see, hear,
+//! touch or eat it. It flies straight toward its target, if it has
+//! one, and looks the way it last flew with a cone of vision, seeing
+//! the blobs and food in it, see `Simulation::drone_sightings`. The
+//! simulation records, with its other statistics, how many of each
+//! every drone sees, as the series `blobs seen by drone <key>` and
+//! `food seen by drone <key>`.
+//!
+//! In the Drone mode, `6`, clicking places a drone, clicking one
+//! removes it and dragging from one sends it where the drag ends.
+//! Embedders do the same with the commands `spawn-drone <x> <y>`,
+//! `steer-drone <key> <x> <y>` and `remove-drone <key>`, and
+//! read what a drone sees with `blobs_sim_drone_sightings`, see `ffi`.
+//! Drones are saved and loaded with the world.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::drones::Drone;
+//! let drone = sim.add_drone(Drone::new(Vector2::new(100., 100.)));
+//! sim.get_drone_mut(drone).unwrap().target = Some(Vector2::new(300., 200.));
+//! sim.step();
+//! let seen = sim.drone_sightings(drone).iter().filter(|sighting| !sighting.food).count();
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{simulation::Simulation, theme::Theme};
+
+/// How many lines the arc at the end of a cone of vision is drawn with.
+const ARC_SEGMENTS: usize = 12;
+/// The size of a drone as drawn, in pixels.
+const BODY_RADIUS: f32 = 6.;
+
+/// An observer of a world, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drone {
+    pub pos: Vector2,
+    /// Where it looks, of length 1.
+    pub direction: Vector2,
+    /// Half the angle of its cone of vision, in degrees.
+    pub pov: f32,
+    /// How far it sees.
+    pub range: f32,
+    /// How far it flies in a second.
+    pub speed: f32,
+    /// Where it flies to, until it is there.
+    pub target: Option<Vector2>,
+}
+
+impl Drone {
+    pub const POV: f32 = 45.;
+    pub const RANGE: f32 = 150.;
+    pub const SPEED: f32 = 80.;
+
+    /// A drone looking right, going nowhere.
+    pub fn new(pos: Vector2) -> Self {
+        Self { pos, direction: Vector2::new(1., 0.), pov: Self::POV, range: Self::RANGE, speed: Self::SPEED, target: None }
+    }
+
+    /// Fly toward the target for some seconds, turning to face it.
+    pub fn fly(&mut self, seconds: f32) {
+        let target = match self.target {
+            Some(target) => target,
+            None => return,
+        };
+        let to = target - self.pos;
+        let distance = to.length();
+        if distance > 0. {
+            self.direction = to / distance;
+        }
+        if distance <= self.speed * seconds {
+            self.pos = target;
+            self.target = None;
+        } else {
+            self.pos += self.direction * self.speed * seconds;
+        }
+    }
+
+    /// Whether a point is in its cone of vision.
+    pub fn sees(&self, pos: Vector2) -> bool {
+        let to = pos - self.pos;
+        let distance = to.length();
+        distance <= self.range && (distance == 0. || (to / distance).dot(self.direction) >= self.pov.to_radians().cos())
+    }
+}
+
+/// Draw the drones of a world with their cones of vision.
+pub fn draw<D: RaylibDraw>(draw: &mut D, sim: &Simulation, theme: &Theme, zoom: f32) {
+    for (_, drone) in sim.drones() {
+        let heading = drone.direction.y.atan2(drone.direction.x);
+        let point = |angle: f32| drone.pos + Vector2::new(angle.cos(), angle.sin()) * drone.range;
+        let (left, right) = (heading - drone.pov.to_radians(), heading + drone.pov.to_radians());
+        let width = 1. / zoom;
+        let color = theme.accent.fade(0.6);
+        draw.draw_line_ex(drone.pos, point(left), width, color);
+        draw.draw_line_ex(drone.pos, point(right), width, color);
+        for i in 0..ARC_SEGMENTS {
+            let angle = |i: usize| left + (right - left) * i as f32 / ARC_SEGMENTS as f32;
+            draw.draw_line_ex(point(angle(i)), point(angle(i + 1)), width, color);
+        }
+        if let Some(target) = drone.target {
+            draw.draw_line_ex(drone.pos, target, width, theme.accent.fade(0.3));
+        }
+        draw.draw_poly(drone.pos, 4, BODY_RADIUS / zoom, heading.to_degrees(), theme.accent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drones() {
+        let mut drone = Drone { target: Some(Vector2::new(100., 100.)), ..Drone::new(Vector2::new(100., 0.)) };
+        drone.fly(0.5);
+        assert_eq!((drone.pos, drone.direction), (Vector2::new(100., 40.), Vector2::new(0., 1.)));
+        assert!(drone.sees(Vector2::new(100., 150.)) && drone.sees(Vector2::new(120., 100.)));
+        //  behind it, beside it and too far
+        assert!(!drone.sees(Vector2::new(100., 20.)) && !drone.sees(Vector2::new(200., 40.)) && !drone.sees(Vector2::new(100., 200.)));
+        drone.fly(10.);
+        assert_eq!((drone.pos, drone.target), (Vector2::new(100., 100.), None));
+    }
+}
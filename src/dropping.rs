@@ -0,0 +1,89 @@
+//! Files dropped on the window, told apart by their extension:
+//!
+//! * a save, `.save`, is loaded into the first world like `F11`
+//! * a fixture, `.fixture`, is played back to tell whether it still
+//!   comes to what it did, see `fixture`
+//! * a scenario, `.toml`, starts the worlds over under it
+//! * a blob export, `.blob`, lands where it was dropped, see `exchange`
+//! * a chunk, `.chunk`, is stitched into every world with its corner
+//!   where it was dropped, see `chunk`
+//! * an image or a grid of values, `.png`, `.bmp`, `.tga`, `.jpg`,
+//!   `.gif`, `.csv` or `.asc`, becomes the fertility map of every world
+//!
+//! Drops are part of recorded input, see `input`, so a replay drops the
+//! same files at the same frames.
+//!
+//! # Example
+//!
+//! ```ignore
+//! for path in &frame.dropped {
+//!     match Dropped::of(path) {
+//!         Ok(Dropped::Blob) => imports.push((Exported::load(path)?, pos)),
+//!         Ok(_) => (),
+//!         Err(e) => eprintln!("{}", e),
+//!     }
+//! }
+//! ```
+
+use std::path::Path;
+
+use crate::{chunk, exchange, fixture};
+
+/// Extensions of the images raylib reads.
+pub const IMAGES: [&str; 6] = ["png", "bmp", "tga", "jpg", "jpeg", "gif"];
+
+/// What a dropped file is, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dropped {
+    World,
+    Fixture,
+    Scenario,
+    Blob,
+    FertilityMap,
+    Chunk,
+}
+
+impl Dropped {
+    /// What a file is by its extension.
+    pub fn of(path: &str) -> Result<Self, String> {
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("save") => Ok(Self::World),
+            Some(extension) if extension == fixture::EXTENSION => Ok(Self::Fixture),
+            Some("toml") => Ok(Self::Scenario),
+            Some(extension) if extension == exchange::EXTENSION => Ok(Self::Blob),
+            Some(extension) if extension == chunk::EXTENSION => Ok(Self::Chunk),
+            Some("csv" | "asc") => Ok(Self::FertilityMap),
+            Some(extension) if IMAGES.contains(&extension) => Ok(Self::FertilityMap),
+            _ => Err(format!("{} is not a save, fixture, scenario, blob, chunk or fertility map", path)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::World => "save",
+            Self::Fixture => "fixture",
+            Self::Scenario => "scenario",
+            Self::Blob => "blob",
+            Self::FertilityMap => "fertility map",
+            Self::Chunk => "chunk",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dropping() {
+        assert_eq!(Dropped::of("/home/ada/world.save"), Ok(Dropped::World));
+        assert_eq!(Dropped::of("assets/scenarios/boom.toml"), Ok(Dropped::Scenario));
+        assert_eq!(Dropped::of("tests/fixtures/repro-40-1.fixture"), Ok(Dropped::Fixture));
+        assert_eq!(Dropped::of("/tmp/a b.blob"), Ok(Dropped::Blob));
+        assert_eq!(Dropped::of("chunks/chunk-200x150-1.chunk"), Ok(Dropped::Chunk));
+        assert_eq!(Dropped::of("islands.PNG"), Ok(Dropped::FertilityMap));
+        assert_eq!(Dropped::of("ndvi.asc").map(|dropped| dropped.name()), Ok("fertility map"));
+        assert!(Dropped::of("notes.txt").is_err() && Dropped::of("README").is_err());
+    }
+}
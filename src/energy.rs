@@ -0,0 +1,119 @@
+//! An audit of the energy in a simulation, for debugging ecology.
+//!
+//! Energy is counted in the units of hunger. A blob stores the
+//! hunger it can still take before starving, `max_hunger - hunger`,
+//! and every food and corpse stores `FOOD_ENERGY`. Energy moves in
+//! four flows, each measured where it happens:
+//!
+//! * spawned: foods, corpses and blobs put in the world
+//! * absorbed: what a blob gains by eating
+//! * metabolism: what blobs burn as they grow hungry
+//! * consumed: what foods and blobs hold when they leave the world,
+//!   eaten or not
+//!
+//! After every tick the change in stored energy must equal the
+//! flows since the last check, so a change made without going
+//! through them, such as hunger set by hand, fails the audit.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::energy::{Audit, Flows, FOOD_ENERGY};
+//! let mut audit = Audit::new(sim.stored_energy());
+//! sim.insert_food(Vector2::new(100., 100.));
+//! audit.add(Flows { spawned: FOOD_ENERGY, ..Flows::default() });
+//! audit.check(sim.stored_energy())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+/// The energy stored in a food or a corpse.
+pub const FOOD_ENERGY: f32 = 5.;
+
+/// Energy that moved, into the world or out of it.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Flows {
+    pub spawned: f32,
+    pub absorbed: f32,
+    pub metabolism: f32,
+    pub consumed: f32,
+}
+
+impl Flows {
+    pub fn entered(&self) -> f32 { self.spawned + self.absorbed }
+
+    pub fn left(&self) -> f32 { self.metabolism + self.consumed }
+
+    fn add(&mut self, other: &Flows) {
+        self.spawned += other.spawned;
+        self.absorbed += other.absorbed;
+        self.metabolism += other.metabolism;
+        self.consumed += other.consumed;
+    }
+}
+
+/// Checks that stored energy changes only by its flows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Audit {
+    //  the energy stored at the last check, and what flowed since
+    stored: f32,
+    flows: Flows,
+    //  what flowed since the statistics were last sampled
+    sample: Flows,
+}
+
+impl Audit {
+    /// The largest mismatch allowed, as a part of the stored energy,
+    /// for the rounding of summing many floats.
+    pub const RELATIVE_TOLERANCE: f32 = 1e-4;
+    pub const ABSOLUTE_TOLERANCE: f32 = 1e-2;
+
+    pub fn new(stored: f32) -> Self {
+        Self { stored, flows: Flows::default(), sample: Flows::default() }
+    }
+
+    pub fn add(&mut self, flows: Flows) {
+        self.flows.add(&flows);
+        self.sample.add(&flows);
+    }
+
+    /// Compare the energy stored now with the flows since the last check.
+    pub fn check(&mut self, stored: f32) -> Result<(), String> {
+        let (before, flows) = (self.stored, self.flows);
+        let expected = before + flows.entered() - flows.left();
+        let tolerance = Self::ABSOLUTE_TOLERANCE + Self::RELATIVE_TOLERANCE * stored.abs().max(before.abs());
+        self.stored = stored;
+        self.flows = Flows::default();
+        if !stored.is_finite() || (stored - expected).abs() > tolerance {
+            return Err(format!("{} energy is stored, but {} stored before and {:?} since make {}",
+                stored, before, flows, expected,
+            ));
+        }
+        Ok(())
+    }
+
+    /// What flowed since the last call, for sampling into statistics.
+    pub fn take_sample(&mut self) -> Flows {
+        std::mem::take(&mut self.sample)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit() {
+        let mut audit = Audit::new(10.);
+        audit.add(Flows { spawned: FOOD_ENERGY, metabolism: 2., ..Flows::default() });
+        assert_eq!(audit.check(13.), Ok(()));
+        audit.add(Flows { consumed: 1., ..Flows::default() });
+        assert!(audit.check(13.).is_err());
+        //  a failed check starts over from what is stored
+        assert_eq!(audit.check(13.), Ok(()));
+        assert_eq!(audit.take_sample(), Flows { spawned: FOOD_ENERGY, metabolism: 2., consumed: 1., absorbed: 0. });
+        assert_eq!(audit.take_sample(), Flows::default());
+    }
+}
@@ -0,0 +1,364 @@
+//! Blobs exported to small files, to be shared and put in other worlds.
+//!
+//! An exported blob keeps its name, species, diet, brain, aura and
+//! every gene, along with how long it had lived. The file is written in
+//! the TOML subset of the asset files, so it can be read and edited
+//! by hand. Sizes are stored before the world's scaling, so a blob
+//! keeps its proportions in a world with other parameters.
+//!
+//! ```toml
+//! version = 1
+//! name = "Ada"
+//! species = "Grazer"
+//! diet = "herbivore"
+//! brain = "color_attraction"
+//!
+//! [genome]
+//! radius = 11.5
+//! speed = 64
+//! body_color = [40, 180, 60]
+//!
+//! [stats]
+//! age = 125.5
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::exchange::{self, Exported};
+//! # let (key, pos) = (sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.))), Vector2::new(200., 100.));
+//! # let mut other_sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! let exported = Exported::from_blob(sim.get_blob(key).unwrap(), sim.parameters());
+//! let path = exported.save(exchange::DIR)?;
+//! let key = Exported::load(&path)?.insert(&mut other_sim, pos);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+    time,
+};
+
+use raylib::prelude::*;
+
+use crate::{
+    keyed_set::Key,
+    simulation::{Blob, BlobConfig, Simulation, Parameters},
+    aura::Aura,
+    species::{Diet, BrainKind},
+    toml::{self, Value},
+    narrator::quote,
+};
+
+/// Where exported blobs are written, and the import menu looks.
+pub const DIR: &str = "exported";
+pub const EXTENSION: &str = "blob";
+/// The version of the file format, raised when old files stop reading the same.
+pub const VERSION: u32 = 1;
+
+/// Every heritable trait of a blob.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Genome {
+    pub radius: f32,
+    pub speed: f32,
+    pub rotation_speed: f32,
+    pub pov: f32,
+    pub sight_depth: f32,
+    pub color_attraction: f32,
+    pub color_repulsion: f32,
+    pub max_hunger: f32,
+    pub attack: f32,
+    pub defence: f32,
+    pub hunger_reduction: f32,
+    pub hunger_division: f32,
+    pub swimming: f32,
+    pub hearing: f32,
+    pub aura_strength: f32,
+    pub body_color: Color,
+    pub favorite_color: Color,
+}
+
+impl Genome {
+    fn numbers_mut(&mut self) -> [(&'static str, &mut f32); 15] {
+        [
+            ("radius", &mut self.radius),
+            ("speed", &mut self.speed),
+            ("rotation_speed", &mut self.rotation_speed),
+            ("pov", &mut self.pov),
+            ("sight_depth", &mut self.sight_depth),
+            ("color_attraction", &mut self.color_attraction),
+            ("color_repulsion", &mut self.color_repulsion),
+            ("max_hunger", &mut self.max_hunger),
+            ("attack", &mut self.attack),
+            ("defence", &mut self.defence),
+            ("hunger_reduction", &mut self.hunger_reduction),
+            ("hunger_division", &mut self.hunger_division),
+            ("swimming", &mut self.swimming),
+            ("hearing", &mut self.hearing),
+            ("aura_strength", &mut self.aura_strength),
+        ]
+    }
+}
+
+/// A blob taken out of its world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exported {
+    pub name: Option<String>,
+    pub species: Option<String>,
+    pub diet: Diet,
+    pub brain: BrainKind,
+    pub aura: Aura,
+    pub genome: Genome,
+    /// Seconds the blob had lived when it was exported.
+    pub age: f32,
+}
+
+impl Exported {
+    /// Export a blob of a world with some parameters.
+    pub fn from_blob(blob: &Blob, parameters: &Parameters) -> Self {
+        Self {
+            name: blob.name.clone(),
+            species: blob.species.clone(),
+            diet: blob.diet,
+            brain: blob.brain,
+            aura: blob.aura,
+            genome: Genome {
+                radius: blob.radius() / parameters.size_scale,
+                speed: blob.speed,
+                rotation_speed: blob.rotation_speed,
+                pov: blob.pov,
+                sight_depth: blob.sight_depth() / parameters.sight_scale,
+                color_attraction: blob.color_attraction,
+                color_repulsion: blob.color_repulsion,
+                max_hunger: blob.max_hunger,
+                attack: blob.attack,
+                defence: blob.defence,
+                hunger_reduction: blob.hunger_reduction,
+                hunger_division: blob.hunger_division,
+                swimming: blob.swimming,
+                hearing: blob.hearing,
+                aura_strength: blob.aura_strength,
+                body_color: blob.color,
+                favorite_color: blob.favorite_color,
+            },
+            age: blob.alive_time,
+        }
+    }
+
+    /// Put a newborn copy of the blob in a simulation.
+    pub fn insert(&self, sim: &mut Simulation, pos: Vector2) -> Key<Blob> {
+        let g = &self.genome;
+        sim.insert_blob(BlobConfig {
+            name: self.name.clone(),
+            species: self.species.clone(),
+            radius: g.radius,
+            color: g.body_color,
+            speed: g.speed,
+            rotation_speed: g.rotation_speed,
+            pov: g.pov,
+            sight_depth: g.sight_depth,
+            favorite_color: g.favorite_color,
+            color_attraction: g.color_attraction,
+            color_repulsion: g.color_repulsion,
+            max_hunger: g.max_hunger,
+            attack: g.attack,
+            defence: g.defence,
+            hunger_reduction: g.hunger_reduction,
+            hunger_division: g.hunger_division,
+            swimming: g.swimming,
+            hearing: g.hearing,
+            aura: self.aura,
+            aura_strength: g.aura_strength,
+            diet: self.diet,
+            brain: self.brain,
+            ..BlobConfig::new(pos)
+        })
+    }
+
+    /// The contents of the file the blob is exported to.
+    pub fn to_toml(&self) -> String {
+        let color = |c: Color| format!("[{}, {}, {}]", c.r, c.g, c.b);
+        let mut ret = format!("version = {}\n", VERSION);
+        if let Some(name) = &self.name {
+            ret += &format!("name = {}\n", quote(name));
+        }
+        if let Some(species) = &self.species {
+            ret += &format!("species = {}\n", quote(species));
+        }
+        ret += &format!("diet = \"{}\"\n", self.diet.name());
+        ret += &format!("aura = \"{}\"\n", self.aura.name());
+        ret += &self.brain.to_toml();
+        ret += "\n[genome]\n";
+        for (key, value) in self.genome.clone().numbers_mut().iter() {
+            ret += &format!("{} = {}\n", key, value);
+        }
+        ret += &format!("body_color = {}\nfavorite_color = {}\n", color(self.genome.body_color), color(self.genome.favorite_color));
+        ret += &format!("\n[stats]\nage = {}\n", self.age);
+        ret
+    }
+
+    /// The brain as a JSON object for tools outside the program:
+    /// its kind, the genes that steer it and its parameters.
+    pub fn brain_json(&self) -> String {
+        let g = &self.genome;
+        let color = |c: Color| format!("[{},{},{}]", c.r, c.g, c.b);
+        let name = self.name.as_deref().map_or_else(|| "null".to_string(), quote);
+        let parameters: Vec<_> = self.brain.parameters().iter().map(|(name, value)| format!(r#""{}":{}"#, name, value)).collect();
+        format!(r#"{{"name":{},"kind":"{}","genes":{{"favorite_color":{},"color_attraction":{},"color_repulsion":{},"pov":{},"sight_depth":{},"rotation_speed":{}}},"parameters":{{{}}}}}"#,
+            name, self.brain.name(), color(g.favorite_color), g.color_attraction, g.color_repulsion, g.pov, g.sight_depth, g.rotation_speed,
+            parameters.join(","),
+        )
+    }
+
+    /// Read a blob from a parsed file.
+    pub fn from_table(table: &toml::Table) -> Result<Self, String> {
+        match table.get("version").and_then(Value::as_f64) {
+            Some(version) if version == VERSION as f64 => (),
+            Some(version) => return Err(format!("version {} is not supported, only {}", version, VERSION)),
+            None => return Err("missing number `version`".to_string()),
+        }
+        let string = |key: &str| match table.get(key) {
+            None => Ok(None),
+            Some(value) => value.as_str()
+                .map(|s| Some(s.to_string()))
+                .ok_or_else(|| format!("`{}` must be a string", key)),
+        };
+        let diet = table.get("diet")
+            .and_then(Value::as_str)
+            .and_then(Diet::from_name)
+            .ok_or("`diet` must be \"herbivore\", \"carnivore\" or \"omnivore\"")?;
+        let brain = BrainKind::from_value(table.get("brain").ok_or("missing `brain`")?)?;
+        //  blobs exported before auras have none
+        let aura = match table.get("aura") {
+            None => Aura::None,
+            Some(value) => value.as_str().and_then(Aura::from_name).ok_or("`aura` must be \"none\", \"slow\", \"heal\" or \"repel\"")?,
+        };
+
+        let genes = table.get("genome").and_then(Value::as_table).ok_or("missing table `genome`")?;
+        let color = |key: &str| genes.get(key)
+            .and_then(parse_color)
+            .ok_or_else(|| format!("`genome.{}` must be [r, g, b]", key));
+        let mut genome = Genome {
+            radius: 0., speed: 0., rotation_speed: 0., pov: 0., sight_depth: 0.,
+            color_attraction: 0., color_repulsion: 0., max_hunger: 0.,
+            attack: 0., defence: 0., hunger_reduction: 0., hunger_division: 0., swimming: 0., hearing: 0., aura_strength: 0.,
+            body_color: color("body_color")?,
+            favorite_color: color("favorite_color")?,
+        };
+        for (key, gene) in genome.numbers_mut().iter_mut() {
+            //  blobs exported before swimming, hearing and auras were genes can neither swim nor hear and have no aura
+            if (*key == "swimming" || *key == "hearing" || *key == "aura_strength") && genes.get(*key).is_none() { continue; }
+            **gene = genes.get(*key)
+                .and_then(Value::as_f32)
+                .filter(|gene| gene.is_finite() && *gene >= 0.)
+                .ok_or_else(|| format!("`genome.{}` must be a non-negative number", key))?;
+        }
+        for key in genes.keys() {
+            let known = key == "body_color" || key == "favorite_color"
+                || genome.numbers_mut().iter().any(|(name, _)| name == key);
+            if !known {
+                return Err(format!("unknown gene `{}`", key));
+            }
+        }
+
+        let age = match table.get("stats").and_then(|stats| stats.get("age")) {
+            None => 0.,
+            Some(value) => value.as_f32()
+                .filter(|age| age.is_finite() && *age >= 0.)
+                .ok_or("`stats.age` must be a non-negative number")?,
+        };
+        Ok(Self { name: string("name")?, species: string("species")?, diet, brain, aura, genome, age })
+    }
+
+    /// Read a blob from a file.
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let table = toml::parse(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::from_table(&table).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Write the blob to a new file in a directory, named after it, returning its path.
+    pub fn save(&self, dir: &str) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let name: String = self.name.as_deref().unwrap_or("blob").chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+            .collect();
+        let since_epoch = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default();
+        let path = Path::new(dir).join(format!("{}-{}.{}", name, since_epoch.as_millis(), EXTENSION));
+        fs::write(&path, self.to_toml())?;
+        Ok(path)
+    }
+}
+
+fn parse_color(value: &Value) -> Option<Color> {
+    let channels = value.as_array()?;
+    let channel = |i: usize| channels.get(i)?.as_f64().filter(|c| (0. ..=255.).contains(c)).map(|c| c as u8);
+    match channels.len() {
+        3 => Some(Color::new(channel(0)?, channel(1)?, channel(2)?, 255)),
+        _ => None,
+    }
+}
+
+/// The exported blobs in a directory, sorted by file name.
+///
+/// A missing directory has none.
+pub fn list(dir: &str) -> io::Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == EXTENSION));
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+
+    #[test]
+    fn test_round_trip() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let key = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let blob = sim.get_blob_mut(key).unwrap();
+        blob.name = Some("Ada \"the\" Great".to_string());
+        blob.diet = Diet::Omnivore;
+        blob.brain = BrainKind::StateMachine { hungry: 0.3, fear: 25. };
+        blob.alive_time = 12.5;
+        blob.aura = Aura::Heal;
+        blob.aura_strength = 0.25;
+        let exported = Exported::from_blob(sim.get_blob(key).unwrap(), sim.parameters());
+
+        let table = toml::parse(&exported.to_toml()).unwrap();
+        assert_eq!(Exported::from_table(&table).unwrap(), exported);
+
+        //  sizes follow the scale of the world the blob is put in
+        let mut config = SimulationConfig::new(Vector2::new(300., 300.));
+        config.parameters.size_scale *= 2.;
+        let mut other = Simulation::new(config);
+        let copy = exported.insert(&mut other, Vector2::new(50., 50.));
+        let copy = other.get_blob(copy).unwrap();
+        assert_eq!(copy.radius(), 2. * sim.get_blob(key).unwrap().radius());
+        assert_eq!((copy.name.as_deref(), copy.diet, copy.alive_time), (Some("Ada \"the\" Great"), Diet::Omnivore, 0.));
+
+        assert_eq!((copy.brain, copy.aura, copy.aura_strength), (exported.brain, Aura::Heal, 0.25));
+
+        assert!(exported.brain_json().starts_with(r#"{"name":"Ada \"the\" Great","kind":"state_machine","genes":{"favorite_color":[0,228,48],"color_attraction":0.5,"#));
+        assert!(exported.brain_json().ends_with(r#""parameters":{"hungry":0.3,"fear":25}}"#));
+
+        let mut table = table;
+        table.remove("diet");
+        assert!(Exported::from_table(&table).is_err());
+    }
+}
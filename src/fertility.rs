@@ -0,0 +1,152 @@
+//! Fertility maps, for designing environments in an image editor.
+//!
+//! A fertility map is a grayscale image stretched over the world.
+//! How bright a pixel is decides how fast food grows at its place:
+//! white grows food at the full food rate, black grows none and gray
+//! in between. Colored images are read by their mean channel.
+//!
+//! Maps can also come from real data, such as vegetation indices, as
+//! a grid of numbers: a `.csv` file of rows of values separated by
+//! commas or spaces, or an ESRI ASCII grid (`.asc`), the plain text
+//! export of GeoTIFF rasters in GIS tools, whose header is read for
+//! its `NODATA_value`. The first row is the north of the world.
+//! Values are stretched from the smallest to the largest, cells
+//! without data, empty or not numbers, grow nothing.
+//!
+//! ```text
+//! # vegetation, rows from north to south
+//! 0.1, 0.4, 0.8
+//! 0.2, , 0.9
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::fertility::FertilityMap;
+//! # use rand::Rng;
+//! # let (mut rng, pos) = (rand::thread_rng(), Vector2::new(100., 100.));
+//! let map = FertilityMap::load("islands.png")?;
+//! if rng.gen::<f32>() < map.at(pos, sim.size()) {
+//!     sim.insert_food(pos);
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fs, path::Path};
+
+use raylib::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FertilityMap {
+    width: usize,
+    height: usize,
+    //  from 0 to 1, row by row
+    values: Vec<f32>,
+}
+
+impl FertilityMap {
+    /// A map of pixels row by row.
+    pub fn from_pixels(width: usize, height: usize, pixels: &[Color]) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err("the image is empty".to_string());
+        }
+        if pixels.len() != width * height {
+            return Err(format!("expected {} pixels for {}x{}, got {}", width * height, width, height, pixels.len()));
+        }
+        let values = pixels.iter().map(|pixel| (pixel.r as f32 + pixel.g as f32 + pixel.b as f32) / (3. * 255.)).collect();
+        Ok(Self { width, height, values })
+    }
+
+    /// A map of rows of values, see the module documentation.
+    pub fn from_grid(text: &str) -> Result<Self, String> {
+        let mut no_data = None;
+        let mut rows: Vec<Vec<Option<f32>>> = vec![];
+        for (i, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() { continue; }
+            //  the header of an ASCII grid, of which only the value for no data matters
+            let mut words = line.split_whitespace();
+            let key = words.next().unwrap();
+            if key.starts_with(|c: char| c.is_ascii_alphabetic()) && rows.is_empty() && !key.eq_ignore_ascii_case("nan") {
+                if key.eq_ignore_ascii_case("nodata_value") {
+                    let value = words.next().ok_or_else(|| format!("line {}: NODATA_value needs a value", i + 1))?;
+                    no_data = Some(value.parse::<f32>().map_err(|_| format!("line {}: invalid value `{}`", i + 1, value))?);
+                }
+                continue;
+            }
+            let separator = if line.contains(',') { ',' } else { ' ' };
+            let row = line.split(separator)
+                .filter(|cell| separator == ',' || !cell.is_empty())
+                .map(|cell| cell.trim().parse::<f32>().ok().filter(|value| value.is_finite() && Some(*value) != no_data))
+                .collect::<Vec<_>>();
+            if let Some(first) = rows.first().filter(|first| first.len() != row.len()) {
+                return Err(format!("line {}: expected {} values like the first row, got {}", i + 1, first.len(), row.len()));
+            }
+            rows.push(row);
+        }
+        let (width, height) = (rows.first().map_or(0, Vec::len), rows.len());
+        if width == 0 {
+            return Err("the grid is empty".to_string());
+        }
+        let values: Vec<_> = rows.into_iter().flatten().collect();
+        let known = values.iter().flatten();
+        let (min, max) = known.fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), &value| (min.min(value), max.max(value)));
+        if min > max {
+            return Err("the grid has no values".to_string());
+        }
+        //  a grid of one value grows food everywhere
+        let stretch = |value: f32| if max > min { (value - min) / (max - min) } else { 1. };
+        let values = values.into_iter().map(|value| value.map_or(0., stretch)).collect();
+        Ok(Self { width, height, values })
+    }
+
+    /// Load a map from an image, or from a grid of values for `.csv`
+    /// and `.asc` paths.
+    pub fn load(path: &str) -> Result<Self, String> {
+        let extension = Path::new(path).extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase);
+        if let Some("csv" | "asc") = extension.as_deref() {
+            let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+            return Self::from_grid(&text).map_err(|e| format!("{}: {}", path, e));
+        }
+        let image = Image::load_image(path)?;
+        Self::from_pixels(image.width() as usize, image.height() as usize, &image.get_image_data())
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// The fertility at a place of a world of some size, from 0 to 1.
+    pub fn at(&self, pos: Vector2, size: Vector2) -> f32 {
+        let x = ((pos.x / size.x * self.width as f32) as usize).min(self.width - 1);
+        let y = ((pos.y / size.y * self.height as f32) as usize).min(self.height - 1);
+        self.values[y * self.width + x]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fertility() {
+        let pixels = [Color::BLACK, Color::WHITE, Color::new(51, 51, 51, 255), Color::new(255, 0, 0, 255)];
+        let map = FertilityMap::from_pixels(2, 2, &pixels).unwrap();
+        let size = Vector2::new(100., 50.);
+        assert_eq!(map.at(Vector2::new(10., 10.), size), 0.);
+        assert_eq!(map.at(Vector2::new(60., 10.), size), 1.);
+        assert_eq!(map.at(Vector2::new(10., 40.), size), 0.2);
+        assert!((map.at(Vector2::new(99., 49.), size) - 1. / 3.).abs() < 1e-6);
+        //  the far edges belong to the last pixels
+        assert_eq!(map.at(size, size), map.at(Vector2::new(99., 49.), size));
+        assert!(FertilityMap::from_pixels(3, 2, &pixels).is_err());
+        assert!(FertilityMap::from_pixels(0, 0, &[]).is_err());
+
+        let csv = FertilityMap::from_grid("# a comment\n2, 4\n, 6\n").unwrap();
+        assert_eq!(csv, FertilityMap { width: 2, height: 2, values: vec![0., 0.5, 0., 1.] });
+        let asc = "ncols 2\nnrows 1\nxllcorner 0\nyllcorner 0\ncellsize 30\nNODATA_value -9999\n-9999 3\n";
+        assert_eq!(FertilityMap::from_grid(asc).unwrap().values, vec![0., 1.]);
+        assert!(FertilityMap::from_grid("1, 2\n3\n").is_err());
+        assert!(FertilityMap::from_grid("ncols 2\n").is_err());
+    }
+}
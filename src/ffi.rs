@@ -0,0 +1,255 @@
+//! A C interface to the simulation, for hosts not written in Rust,
+//! like game engines, declared in `include/blobs.h`.
+//!
+//! A host creates a simulation, steps it, pushes commands in the text
+//! form of `lockstep::Command`, like `spawn-food 120 80`, and copies
+//! out arrays of what it draws: a function filling an array takes
+//! room for `capacity` blobs or foods and returns how many it filled,
+//! in the same order across the functions until the next step. The
+//! functions are exported from the shared library the crate builds,
+//! `libblobs.so` or its like, which a host links against.
+//!
+//! Every pointer a function takes must be null or valid for what it
+//! is used for, a simulation one returned by `blobs_sim_create` and
+//! not yet destroyed, and no two calls may use one simulation at the
+//! same time.
+//!
+//! A panic never unwinds into the host: a function that panics returns
+//! as it does for a null simulation, and -1 from a command. The
+//! simulation may then be left half stepped, and is best destroyed.
+//!
+//! # Example
+//!
+//! ```c
+//! BlobsSim *sim = blobs_sim_create(800, 600, 42);
+//! for (int i = 0; i < 20; i++) blobs_sim_push_command(sim, "spawn-blob");
+//! blobs_sim_step(sim, 60);
+//! float positions[2 * 256];
+//! size_t count = blobs_sim_blob_positions(sim, positions, 256);
+//! blobs_sim_destroy(sim);
+//! ```
+
+use std::{ffi::CStr, os::raw::{c_char, c_int}, panic, ptr, slice};
+
+use raylib::prelude::Vector2;
+
+use crate::{
+    keyed_set::Key,
+    lockstep::Command,
+    simulation::{Simulation, SimulationConfig, Source},
+};
+
+/// Copy arrays of values into room for `capacity` of them, returning
+/// how many were copied.
+unsafe fn fill<T: Copy, const N: usize>(out: *mut T, capacity: usize, values: impl Iterator<Item = [T; N]>) -> usize {
+    if out.is_null() { return 0; }
+    let out = slice::from_raw_parts_mut(out, capacity * N);
+    out.chunks_exact_mut(N).zip(values).map(|(chunk, value)| chunk.copy_from_slice(&value)).count()
+}
+
+/// Run the body of a function, returning what it does if it fails
+/// instead of unwinding into the host.
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(panic::AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+/// Create an empty world of a size, from a seed.
+#[no_mangle]
+pub extern "C" fn blobs_sim_create(width: f32, height: f32, seed: u64) -> *mut Simulation {
+    if !(width > 0. && height > 0. && width.is_finite() && height.is_finite()) {
+        return ptr::null_mut();
+    }
+    guard(ptr::null_mut(), || {
+        let config = SimulationConfig { seed, ..SimulationConfig::new(Vector2::new(width, height)) };
+        Box::into_raw(Box::new(Simulation::new(config)))
+    })
+}
+
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_destroy(sim: *mut Simulation) {
+    if !sim.is_null() {
+        guard((), || drop(Box::from_raw(sim)));
+    }
+}
+
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_step(sim: *mut Simulation, ticks: u32) {
+    if let Some(sim) = sim.as_mut() {
+        guard((), || {
+            for _ in 0..ticks {
+                sim.step();
+            }
+        });
+    }
+}
+
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_tick(sim: *const Simulation) -> u64 {
+    sim.as_ref().map_or(0, |sim| guard(0, || sim.clock().tick()))
+}
+
+/// Apply a command, returning 0, or -1 for one that is not valid,
+/// placing something outside the world or not finite.
+///
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_push_command(sim: *mut Simulation, command: *const c_char) -> c_int {
+    let sim = match sim.as_mut() {
+        Some(sim) => sim,
+        None => return -1,
+    };
+    if command.is_null() {
+        return -1;
+    }
+    let command = CStr::from_ptr(command).to_str().map_err(|e| e.to_string())
+        .and_then(str::parse::<Command>)
+        .and_then(|command| command.validate(sim.size()).map(|()| command));
+    match command {
+        Ok(command) => guard(-1, || {
+            sim.spawning_as(Source::Embedder, |sim| crate::apply_command(sim, command, &[], &[]));
+            0
+        }),
+        Err(_) => -1,
+    }
+}
+
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_blob_count(sim: *const Simulation) -> usize {
+    sim.as_ref().map_or(0, |sim| guard(0, || sim.blobs().count()))
+}
+
+/// The keys of the blobs, which stay the same while a blob lives.
+///
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_blob_keys(sim: *const Simulation, out: *mut u64, capacity: usize) -> usize {
+    let sim = match sim.as_ref() { Some(sim) => sim, None => return 0 };
+    guard(0, || fill(out, capacity, sim.blobs().map(|(key, _)| [key.index()])))
+}
+
+/// The positions of the blobs, as x and y.
+///
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_blob_positions(sim: *const Simulation, out: *mut f32, capacity: usize) -> usize {
+    let sim = match sim.as_ref() { Some(sim) => sim, None => return 0 };
+    guard(0, || fill(out, capacity, sim.blobs().map(|(_, blob)| [blob.pos().x, blob.pos().y])))
+}
+
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_blob_radii(sim: *const Simulation, out: *mut f32, capacity: usize) -> usize {
+    let sim = match sim.as_ref() { Some(sim) => sim, None => return 0 };
+    guard(0, || fill(out, capacity, sim.blobs().map(|(_, blob)| [blob.radius()])))
+}
+
+/// The colors of the blobs, as red, green, blue and alpha.
+///
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_blob_colors(sim: *const Simulation, out: *mut u8, capacity: usize) -> usize {
+    let sim = match sim.as_ref() { Some(sim) => sim, None => return 0 };
+    guard(0, || fill(out, capacity, sim.blobs().map(|(_, blob)| [blob.color.r, blob.color.g, blob.color.b, blob.color.a])))
+}
+
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_food_count(sim: *const Simulation) -> usize {
+    sim.as_ref().map_or(0, |sim| guard(0, || sim.foods().count()))
+}
+
+/// The positions of the foods, as x and y.
+///
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_food_positions(sim: *const Simulation, out: *mut f32, capacity: usize) -> usize {
+    let sim = match sim.as_ref() { Some(sim) => sim, None => return 0 };
+    guard(0, || fill(out, capacity, sim.foods().map(|(_, food)| [food.pos().x, food.pos().y])))
+}
+
+/// The keys of the drones, see `drones`, which stay the same while a
+/// drone is there.
+///
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_drone_keys(sim: *const Simulation, out: *mut u64, capacity: usize) -> usize {
+    let sim = match sim.as_ref() { Some(sim) => sim, None => return 0 };
+    guard(0, || fill(out, capacity, sim.drones().map(|(key, _)| [key.index()])))
+}
+
+/// What a drone sees now, as x, y, radius and 1 for food or 0 for a
+/// blob, or nothing for a drone that is not there.
+///
+/// # Safety
+/// See the module.
+#[no_mangle]
+pub unsafe extern "C" fn blobs_sim_drone_sightings(sim: *const Simulation, drone: u64, out: *mut f32, capacity: usize) -> usize {
+    let sim = match sim.as_ref() { Some(sim) => sim, None => return 0 };
+    guard(0, || fill(out, capacity, sim.drone_sightings(Key::from_index(drone)).iter().map(|sighting| [sighting.pos.x, sighting.pos.y, sighting.radius, sighting.food as u8 as f32])))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+
+    #[test]
+    fn test_ffi() {
+        //  the header declares every function
+        let header = include_str!("../include/blobs.h");
+        for line in include_str!("ffi.rs").lines().filter(|line| line.starts_with("pub ") && line.contains("extern \"C\" fn ")) {
+            let name = line.split("fn ").nth(1).unwrap().split('(').next().unwrap();
+            assert!(header.contains(&format!("{}(", name)), "{} is not in the header", name);
+        }
+
+        unsafe {
+            let sim = blobs_sim_create(300., 200., 7);
+            assert!(blobs_sim_create(0., 200., 7).is_null());
+            for command in &["spawn-blob", "spawn-blob", "spawn-food 10 20"] {
+                assert_eq!(blobs_sim_push_command(sim, CString::new(*command).unwrap().as_ptr()), 0);
+            }
+            assert_eq!(blobs_sim_push_command(sim, CString::new("fly").unwrap().as_ptr()), -1);
+            //  nothing is put outside the world, or at no place at all
+            for command in &["spawn-food nan 0", "spawn-food 301 0", "move 0 inf 5", "impulse 0 nan 1", "repel 10 10 inf"] {
+                assert_eq!(blobs_sim_push_command(sim, CString::new(*command).unwrap().as_ptr()), -1, "{}", command);
+            }
+            assert!(blobs_sim_create(f32::INFINITY, 200., 7).is_null());
+            assert_eq!(guard(-1, || panic!("a bug")), -1);
+            assert_eq!((blobs_sim_blob_count(sim), blobs_sim_food_count(sim)), (2, 1));
+            let mut positions = [0.; 2];
+            assert_eq!(blobs_sim_food_positions(sim, positions.as_mut_ptr(), 1), 1);
+            assert_eq!(positions, [10., 20.]);
+            let mut colors = [0; 4];
+            //  room for one of the two blobs
+            assert_eq!(blobs_sim_blob_colors(sim, colors.as_mut_ptr(), 1), 1);
+            blobs_sim_step(sim, 3);
+            assert_eq!(blobs_sim_tick(sim), 3);
+
+            //  a drone looking right sees the food ahead of it
+            assert_eq!(blobs_sim_push_command(sim, CString::new("spawn-drone 0 20").unwrap().as_ptr()), 0);
+            let mut drones = [0; 2];
+            assert_eq!(blobs_sim_drone_keys(sim, drones.as_mut_ptr(), 2), 1);
+            let mut sighting = [0.; 4];
+            assert_eq!(blobs_sim_drone_sightings(sim, drones[0], sighting.as_mut_ptr(), 1), 1);
+            assert_eq!(sighting, [10., 20., 5., 1.]);
+            blobs_sim_destroy(sim);
+        }
+    }
+}
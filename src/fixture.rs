@@ -0,0 +1,270 @@
+//! Reproductions of bugs as test fixtures: a saved world, the input
+//! given to it over some ticks, and what it came to.
+//!
+//! Shift and `F8` starts capturing the first world: it is saved as it
+//! is, and the interventions made on it by hand over the next `TICKS`
+//! ticks are kept with the tick they were made at. Then the fixture
+//! is played back from the save to find what the world comes to, and
+//! written to `DIR` to be attached to a bug report or checked in.
+//!
+//! Playing a fixture back loads its world, makes each intervention
+//! at its tick and steps the simulation, without the blobs and food
+//! the program adds at random around it, see `World::tick`, so it
+//! depends on the simulation alone. Blobs spawned by hand are drawn
+//! without names or species. `test_fixture` plays back every fixture
+//! in `DIR` and fails if any comes to something else, and so does
+//! dropping one on the window, see `dropping`. The format is
+//! a header, with fields separated by tabs, followed by the save:
+//!
+//! ```text
+//! fixture    blobs stuck in the corner
+//! ticks    600
+//! input    12    impulse 3 40 0
+//! expect    1812    14    22    5853632968524632296
+//! world
+//! version    14
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::{fixture::{self, Capture}, lockstep::Command};
+//! # let command = Command::SpawnFood(Vector2::new(100., 100.));
+//! let mut capture = Capture::start(&sim, fixture::TICKS);
+//! capture.input(sim.clock().tick(), command);
+//! let fixture = capture.finish("blobs stuck in the corner")?;
+//! fixture.check()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{lockstep::Command, simulation::{Simulation, Source}};
+
+/// Where fixtures are written and checked from.
+pub const DIR: &str = "tests/fixtures";
+pub const EXTENSION: &str = "fixture";
+/// How many ticks a capture lasts.
+pub const TICKS: u64 = 600;
+
+/// What a fixture's world came to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Outcome {
+    pub tick: u64,
+    pub blobs: usize,
+    pub foods: usize,
+    /// See `Simulation::state_hash`.
+    pub state: u64,
+}
+
+impl Outcome {
+    pub fn of(sim: &Simulation) -> Self {
+        Self { tick: sim.clock().tick(), blobs: sim.blobs().count(), foods: sim.foods().count(), state: sim.state_hash() }
+    }
+}
+
+/// Make an intervention as fixtures do, by hand but without names or species.
+pub fn apply(sim: &mut Simulation, command: Command) {
+    sim.spawning_as(Source::User, |sim| crate::apply_command(sim, command, &[], &[]));
+}
+
+/// A reproduction, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fixture {
+    pub note: String,
+    pub ticks: u64,
+    /// Interventions with how many ticks after the save they were made.
+    pub inputs: Vec<(u64, Command)>,
+    pub expected: Outcome,
+    /// The world as saved, see `Simulation::save`.
+    pub world: String,
+}
+
+impl Fixture {
+    /// Play the fixture back, failing on an input the world would not take.
+    pub fn run(&self) -> Result<Outcome, String> {
+        let mut sim = Simulation::load(self.world.as_bytes())?;
+        for (i, (_, command)) in self.inputs.iter().enumerate() {
+            //  inputs are written after the note and ticks, see `write`
+            command.validate(sim.size()).map_err(|e| format!("line {}: {}", i + 3, e))?;
+        }
+        let mut inputs = self.inputs.iter().peekable();
+        for tick in 0..self.ticks {
+            while let Some(&(_, command)) = inputs.next_if(|(at, _)| *at <= tick) {
+                apply(&mut sim, command);
+            }
+            sim.step();
+        }
+        Ok(Outcome::of(&sim))
+    }
+
+    /// Play the fixture back and tell how it differs from what was expected.
+    pub fn check(&self) -> Result<(), String> {
+        let outcome = self.run()?;
+        if outcome == self.expected {
+            return Ok(());
+        }
+        let Outcome { tick, blobs, foods, state } = self.expected;
+        Err(format!("`{}` expected tick {} with {} blobs, {} foods and state {}, got tick {} with {} blobs, {} foods and state {}",
+            self.note, tick, blobs, foods, state, outcome.tick, outcome.blobs, outcome.foods, outcome.state))
+    }
+
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "fixture\t{}", self.note)?;
+        writeln!(out, "ticks\t{}", self.ticks)?;
+        for (at, command) in &self.inputs {
+            writeln!(out, "input\t{}\t{}", at, command)?;
+        }
+        let Outcome { tick, blobs, foods, state } = self.expected;
+        writeln!(out, "expect\t{}\t{}\t{}\t{}", tick, blobs, foods, state)?;
+        writeln!(out, "world")?;
+        out.write_all(self.world.as_bytes())
+    }
+
+    pub fn read(text: &str) -> Result<Self, String> {
+        let (header, world) = text.split_once("\nworld\n").ok_or("a fixture needs a world")?;
+        let (mut note, mut ticks, mut inputs, mut expected) = (None, None, vec![], None);
+        for (i, line) in header.lines().enumerate() {
+            let invalid = || format!("line {}: invalid fixture line `{}`", i + 1, line);
+            let fields: Vec<_> = line.split('\t').collect();
+            let number = |field: &str| field.parse::<u64>().map_err(|_| invalid());
+            match fields[..] {
+                ["fixture", text] => note = Some(text.to_string()),
+                ["ticks", count] => ticks = Some(number(count)?),
+                ["input", at, command] => inputs.push((number(at)?, command.parse().map_err(|e| format!("line {}: {}", i + 1, e))?)),
+                ["expect", tick, blobs, foods, state] => expected = Some(Outcome {
+                    tick: number(tick)?, blobs: number(blobs)? as usize, foods: number(foods)? as usize, state: number(state)?,
+                }),
+                _ => return Err(invalid()),
+            }
+        }
+        inputs.sort_by_key(|&(at, _)| at);
+        Ok(Self {
+            note: note.ok_or("a fixture needs a note")?,
+            ticks: ticks.ok_or("a fixture needs its ticks")?,
+            inputs,
+            expected: expected.ok_or("a fixture needs its expected outcome")?,
+            world: world.to_string(),
+        })
+    }
+
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::read(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Write the fixture to a new file in a directory, named by the tick it starts at.
+    pub fn save(&self, dir: &str) -> io::Result<PathBuf> {
+        fs::create_dir_all(dir)?;
+        let start = self.expected.tick - self.ticks;
+        let path = (1..)
+            .map(|i| Path::new(dir).join(format!("repro-{}-{}.{}", start, i, EXTENSION)))
+            .find(|path| !path.exists())
+            .unwrap();
+        let mut file = io::BufWriter::new(fs::File::create(&path)?);
+        self.write(&mut file)?;
+        file.flush()?;
+        Ok(path)
+    }
+}
+
+/// A fixture being captured, see the module.
+#[derive(Debug, Clone)]
+pub struct Capture {
+    world: String,
+    start: u64,
+    ticks: u64,
+    inputs: Vec<(u64, Command)>,
+}
+
+impl Capture {
+    /// Save a world to capture it over some ticks from now.
+    pub fn start(sim: &Simulation, ticks: u64) -> Self {
+        let mut world = vec![];
+        sim.save(&mut world).expect("writing to memory cannot fail");
+        let world = String::from_utf8(world).expect("saves are text");
+        Self { world, start: sim.clock().tick(), ticks, inputs: vec![] }
+    }
+
+    /// Keep an intervention made at a tick, before it is stepped.
+    pub fn input(&mut self, tick: u64, command: Command) {
+        self.inputs.push((tick.saturating_sub(self.start), command));
+    }
+
+    /// Whether the world has reached the end of the capture.
+    pub fn done(&self, tick: u64) -> bool {
+        tick >= self.start + self.ticks
+    }
+
+    /// Play the capture back to find its outcome, and again to check
+    /// it plays back the same.
+    pub fn finish(self, note: &str) -> Result<Fixture, String> {
+        let mut fixture = Fixture {
+            note: note.to_string(),
+            ticks: self.ticks,
+            inputs: self.inputs,
+            expected: Outcome { tick: 0, blobs: 0, foods: 0, state: 0 },
+            world: self.world,
+        };
+        fixture.expected = fixture.run()?;
+        fixture.check()?;
+        Ok(fixture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::Vector2;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    /// The fixtures in a directory, by name, none if it is missing.
+    fn list(dir: &str) -> io::Result<Vec<PathBuf>> {
+        let mut paths = match fs::read_dir(dir) {
+            Ok(entries) => entries.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => vec![],
+            Err(e) => return Err(e),
+        };
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == EXTENSION));
+        paths.sort();
+        Ok(paths)
+    }
+
+    #[test]
+    fn test_fixture() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 200.)));
+        let blob = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.insert_food(Vector2::new(150., 100.));
+        sim.step();
+        let mut capture = Capture::start(&sim, 30);
+        capture.input(1, Command::ApplyImpulse(blob, Vector2::new(40., 0.)));
+        capture.input(10, Command::SpawnFood(Vector2::new(20., 20.)));
+        assert!(!capture.done(30) && capture.done(31));
+        let fixture = capture.finish("pushed toward food").unwrap();
+        assert_eq!((fixture.expected.tick, fixture.inputs[1].0), (31, 9));
+        fixture.check().unwrap();
+
+        let mut text = vec![];
+        fixture.write(&mut text).unwrap();
+        let read = Fixture::read(&String::from_utf8(text).unwrap()).unwrap();
+        assert_eq!(read, fixture);
+        let wrong = Fixture { inputs: vec![], ..read };
+        assert!(wrong.check().unwrap_err().contains("pushed toward food"));
+        assert!(Fixture::read("fixture\tno world\n").is_err());
+        let outside = Fixture { inputs: vec![(0, Command::SpawnFood(Vector2::new(f32::NAN, 0.)))], ..fixture.clone() };
+        assert!(outside.run().unwrap_err().starts_with("line 3: "));
+
+        //  every checked-in reproduction still comes to what it did
+        for path in list(DIR).unwrap() {
+            Fixture::load(&path).and_then(|fixture| fixture.check()).unwrap();
+        }
+    }
+}
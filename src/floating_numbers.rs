@@ -0,0 +1,92 @@
+//! Small numbers rising from blobs as their energy changes.
+//!
+//! With `D`, every meal shows the energy it gave as a green `+12`
+//! rising from the eater, and every kill the energy the victim had
+//! stored as a red `-8` where it died, each fading out within a
+//! second or so. They follow the energy changes a world lists after
+//! every tick, so none are missed while several ticks run in a frame.
+//! Numbers too small to read, off the view or in views zoomed out far
+//! are not drawn, and the oldest give way once too many are shown.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut numbers = FloatingNumbers::new();
+//! sim.step();
+//! numbers.collect(&sim);
+//! numbers.update(frame_seconds);
+//! numbers.draw(&mut draw, view, rect, &theme, &fonts);
+//! ```
+
+use std::collections::VecDeque;
+
+use raylib::prelude::*;
+
+use crate::{fonts::Fonts, simulation::Simulation, theme::Theme, window::Camera};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FloatingNumber {
+    //  the world position it rose from
+    pos: Vector2,
+    amount: f32,
+    //  seconds since it appeared
+    age: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FloatingNumbers {
+    //  oldest first
+    numbers: VecDeque<FloatingNumber>,
+}
+
+impl FloatingNumbers {
+    /// How many seconds a number is shown.
+    const LIFETIME: f32 = 1.2;
+    /// How many screen pixels a number rises in its lifetime.
+    const RISE: f32 = 30.;
+    /// How many numbers are shown at most.
+    const MAX_NUMBERS: usize = 200;
+    /// The zoom below which numbers are not drawn.
+    const MIN_ZOOM: f32 = 0.5;
+    /// Changes of less energy round to nothing and are not shown.
+    const MIN_AMOUNT: f32 = 0.5;
+    const FONT_SIZE: i32 = 16;
+
+    pub fn new() -> Self { Self::default() }
+
+    /// Add the energy changes of the world's last tick.
+    pub fn collect(&mut self, sim: &Simulation) {
+        for change in sim.energy_changes().iter().filter(|change| change.amount.abs() >= Self::MIN_AMOUNT) {
+            if self.numbers.len() == Self::MAX_NUMBERS {
+                self.numbers.pop_front();
+            }
+            self.numbers.push_back(FloatingNumber { pos: change.pos, amount: change.amount, age: 0. });
+        }
+    }
+
+    /// Age the numbers by some seconds, dropping those that faded out.
+    pub fn update(&mut self, seconds: f32) {
+        for number in &mut self.numbers {
+            number.age += seconds;
+        }
+        self.numbers.retain(|number| number.age < Self::LIFETIME);
+    }
+
+    /// Draw the numbers in a view on the window, in screen coordinates.
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, view: Camera, rect: Rectangle, theme: &Theme, fonts: &Fonts) {
+        if view.zoom < Self::MIN_ZOOM { return; }
+        for number in &self.numbers {
+            let progress = number.age / Self::LIFETIME;
+            let pos = view.world_to_screen(number.pos) - Vector2::new(0., Self::RISE * progress);
+            if !rect.check_collision_point_rec(pos) { continue; }
+            let (text, color) = if number.amount > 0. {
+                (format!("+{:.0}", number.amount), theme.ok)
+            } else {
+                (format!("-{:.0}", -number.amount), theme.error)
+            };
+            let color = color.fade(1. - progress);
+            let x = pos.x as i32 - fonts.measure(&text, Self::FONT_SIZE) / 2;
+            fonts.draw_text(draw, &text, x, pos.y as i32 - Self::FONT_SIZE, Self::FONT_SIZE, color);
+        }
+    }
+}
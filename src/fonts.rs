@@ -0,0 +1,75 @@
+//! Text drawn with the bundled TrueType font.
+//!
+//! The font is rasterized once, into a glyph atlas, for every size
+//! the interface uses, so text is drawn at its native size instead
+//! of scaled up from raylib's small bitmap font. Sizes without an
+//! atlas are drawn from the nearest larger one with smooth filtering.
+//! When the font cannot be loaded raylib's default font is used.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let fonts = window.load_fonts(Fonts::PATH)?;
+//! let width = fonts.measure("Hello", 20);
+//! fonts.draw_text(&mut draw, "Hello", 10, 10, 20, Color::BLACK);
+//! ```
+
+use std::collections::BTreeMap;
+
+use raylib::prelude::*;
+
+pub struct Fonts {
+    //  glyph atlases by pixel size
+    atlases: BTreeMap<i32, Font>,
+}
+
+impl Fonts {
+    pub const PATH: &'static str = "assets/fonts/DejaVuSans.ttf";
+    /// The sizes used by the interface, which are rasterized up front.
+    pub const SIZES: [i32; 4] = [10, 16, 20, 30];
+
+    /// Fonts that draw with raylib's default font.
+    pub fn new() -> Self {
+        Self { atlases: BTreeMap::new() }
+    }
+
+    /// Rasterize the font in a file at every size in `SIZES`.
+    pub fn load(handle: &mut RaylibHandle, thread: &RaylibThread, path: &str) -> Result<Self, String> {
+        let mut atlases = BTreeMap::new();
+        for &size in &Self::SIZES {
+            //  the printable ASCII characters
+            let font = handle.load_font_ex(thread, path, size, FontLoadEx::Default(0))?;
+            unsafe {
+                raylib::ffi::SetTextureFilter(font.texture, TextureFilterMode::FILTER_BILINEAR as i32);
+            }
+            atlases.insert(size, font);
+        }
+        Ok(Self { atlases })
+    }
+
+    /// The atlas to draw text of a size with.
+    fn atlas(&self, size: i32) -> Option<&Font> {
+        self.atlases.range(size..).next()
+            .or_else(|| self.atlases.iter().next_back())
+            .map(|(_, font)| font)
+    }
+
+    /// The width of a text drawn at a size.
+    pub fn measure(&self, text: &str, size: i32) -> i32 {
+        match self.atlas(size) {
+            Some(font) => measure_text_ex(font, text, size as f32, 0.).x.ceil() as i32,
+            None => measure_text(text, size),
+        }
+    }
+
+    pub fn draw_text<D: RaylibDraw>(&self, draw: &mut D, text: &str, x: i32, y: i32, size: i32, color: Color) {
+        match self.atlas(size) {
+            Some(font) => draw.draw_text_ex(font, text, Vector2::new(x as f32, y as f32), size as f32, 0., color),
+            None => draw.draw_text(text, x, y, size, color),
+        }
+    }
+}
+
+impl Default for Fonts {
+    fn default() -> Self { Self::new() }
+}
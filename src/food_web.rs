@@ -0,0 +1,248 @@
+//! A live diagram of who eats whom.
+//!
+//! Every species is a node, and so are foods and corpses, sized by
+//! how many of them are in the world. An edge leads from prey to
+//! eater, weighted by the meals of the last `WINDOW` ticks. Nodes
+//! keep their places between updates and are moved by a simple
+//! force-directed layout, a few iterations every frame, so the
+//! diagram settles smoothly as the world changes:
+//!
+//! * every two nodes push each other apart
+//! * every edge pulls its nodes together, harder the more was eaten
+//! * a weak pull keeps all nodes near the middle
+//!
+//! Positions are in a unit square, scaled to wherever it is drawn.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::food_web::{self, FoodWeb};
+//! let mut web = FoodWeb::new();
+//! web.update(&sim);
+//! web.layout(food_web::ITERATIONS);
+//! for edge in web.edges() {
+//!     let (from, to) = (&web.nodes()[edge.from], &web.nodes()[edge.to]);
+//! }
+//! ```
+
+use raylib::prelude::*;
+
+use crate::simulation::{Simulation, Food, Prey};
+
+/// How many of the latest ticks of meals the edges count.
+pub const WINDOW: u64 = 600;
+/// Layout iterations to run each frame.
+pub const ITERATIONS: usize = 5;
+/// The farthest a node moves in an iteration.
+const STEP: f32 = 0.01;
+/// How hard nodes are pulled to the middle.
+const GRAVITY: f32 = 0.05;
+/// How close to the border nodes may go.
+const MARGIN: f32 = 0.1;
+
+/// A species, foods or corpses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// What the node stands for, as eaten.
+    pub prey: Prey,
+    /// How many of it are in the world.
+    pub population: usize,
+    pub color: Color,
+    pub pos: Vector2,
+}
+
+impl Node {
+    pub fn label(&self) -> &str {
+        match &self.prey {
+            Prey::Food => "food",
+            Prey::Corpse => "corpses",
+            Prey::Blob(Some(species)) => species,
+            Prey::Blob(None) => "no species",
+        }
+    }
+}
+
+/// Meals from the node at one index to the node at another.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub meals: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FoodWeb {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl FoodWeb {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn nodes(&self) -> &[Node] { &self.nodes }
+
+    pub fn edges(&self) -> &[Edge] { &self.edges }
+
+    /// Count the populations and recent meals of a world.
+    ///
+    /// Nodes with nothing in the world and no recent meals are dropped.
+    pub fn update(&mut self, sim: &Simulation) {
+        let mut nodes: Vec<Node> = self.nodes.iter()
+            .map(|node| Node { population: 0, ..node.clone() })
+            .collect();
+        //  colors are summed, then divided by the population
+        let mut colors: Vec<[f32; 3]> = vec![[0.; 3]; nodes.len()];
+        let mut add = |nodes: &mut Vec<Node>, prey: Prey, color: Color| {
+            let i = index(nodes, &prey);
+            if i == colors.len() {
+                colors.push([0.; 3]);
+            }
+            nodes[i].population += 1;
+            for (sum, channel) in colors[i].iter_mut().zip(&[color.r, color.g, color.b]) {
+                *sum += *channel as f32;
+            }
+            i
+        };
+        for (_, blob) in sim.blobs() {
+            add(&mut nodes, Prey::Blob(blob.species.clone()), blob.color);
+        }
+        for (_, food) in sim.foods() {
+            let prey = if food.is_corpse() { Prey::Corpse } else { Prey::Food };
+            add(&mut nodes, prey, Color::BLANK);
+        }
+        let mut edges: Vec<Edge> = vec![];
+        for (_, meal) in sim.meals_since(sim.clock().tick().saturating_sub(WINDOW)) {
+            let from = index(&mut nodes, &meal.prey);
+            let to = index(&mut nodes, &Prey::Blob(meal.eater.clone()));
+            match edges.iter_mut().find(|edge| (edge.from, edge.to) == (from, to)) {
+                Some(edge) => edge.meals += 1,
+                None => edges.push(Edge { from, to, meals: 1 }),
+            }
+        }
+
+        colors.resize(nodes.len(), [0.; 3]);
+        for (node, sum) in nodes.iter_mut().zip(&colors) {
+            node.color = match node.prey {
+                Prey::Food => Food::COLOR,
+                Prey::Corpse => Color::BROWN,
+                Prey::Blob(_) if node.population > 0 => {
+                    let channel = |sum: f32| (sum / node.population as f32) as u8;
+                    Color::new(channel(sum[0]), channel(sum[1]), channel(sum[2]), 255)
+                },
+                Prey::Blob(_) => node.color,
+            };
+        }
+
+        //  drop forgotten nodes, renumbering the edges
+        let kept: Vec<bool> = (0..nodes.len())
+            .map(|i| nodes[i].population > 0 || edges.iter().any(|edge| edge.from == i || edge.to == i))
+            .collect();
+        let mut renumbered = vec![0; nodes.len()];
+        let mut count = 0;
+        for (i, &keep) in kept.iter().enumerate() {
+            renumbered[i] = count;
+            count += keep as usize;
+        }
+        let mut kept = kept.into_iter();
+        nodes.retain(|_| kept.next().unwrap());
+        for edge in &mut edges {
+            edge.from = renumbered[edge.from];
+            edge.to = renumbered[edge.to];
+        }
+        self.nodes = nodes;
+        self.edges = edges;
+    }
+
+    /// Move the nodes by some iterations of the force-directed layout.
+    pub fn layout(&mut self, iterations: usize) {
+        //  the distance at which pushing and pulling balance
+        let ideal = 0.6 / (self.nodes.len().max(1) as f32).sqrt();
+        let middle = Vector2::new(0.5, 0.5);
+        for _ in 0..iterations {
+            let mut moves = vec![Vector2::zero(); self.nodes.len()];
+            for a in 0..self.nodes.len() {
+                for b in a + 1..self.nodes.len() {
+                    let d = self.nodes[a].pos - self.nodes[b].pos;
+                    let distance = d.length().max(0.01);
+                    let push = d / distance * (ideal * ideal / distance);
+                    moves[a] += push;
+                    moves[b] -= push;
+                }
+            }
+            for edge in self.edges.iter().filter(|edge| edge.from != edge.to) {
+                let d = self.nodes[edge.to].pos - self.nodes[edge.from].pos;
+                let distance = d.length();
+                let pull = d * (distance / ideal * (1. + edge.meals as f32).ln());
+                moves[edge.from] += pull;
+                moves[edge.to] -= pull;
+            }
+            for (node, mut movement) in self.nodes.iter_mut().zip(moves) {
+                movement += (middle - node.pos) * GRAVITY;
+                let length = movement.length();
+                if length > STEP {
+                    movement *= STEP / length;
+                }
+                node.pos += movement;
+                node.pos.x = node.pos.x.clamp(MARGIN, 1. - MARGIN);
+                node.pos.y = node.pos.y.clamp(MARGIN, 1. - MARGIN);
+            }
+        }
+    }
+}
+
+/// The index of the node of some prey, adding it if it is new.
+fn index(nodes: &mut Vec<Node>, prey: &Prey) -> usize {
+    if let Some(i) = nodes.iter().position(|node| &node.prey == prey) {
+        return i;
+    }
+    //  new nodes start spread around the middle, the same way every time
+    let angle = nodes.len() as f32 * 2.4;
+    let pos = Vector2::new(0.5 + 0.3 * angle.cos(), 0.5 + 0.3 * angle.sin());
+    nodes.push(Node { prey: prey.clone(), population: 0, color: Color::GRAY, pos });
+    nodes.len() - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_food_web() {
+        let mut config = SimulationConfig::new(Vector2::new(300., 300.));
+        config.parameters.food_rate = 0.;
+        let mut sim = Simulation::new(config);
+        let blob = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.get_blob_mut(blob).unwrap().species = Some("grazer".to_string());
+        sim.insert_food(Vector2::new(100., 100.));
+        sim.insert_food(Vector2::new(250., 250.));
+        sim.step();
+
+        let mut web = FoodWeb::new();
+        web.update(&sim);
+        let labels: Vec<_> = web.nodes().iter().map(|node| (node.label(), node.population)).collect();
+        assert_eq!(labels, vec![("grazer", 1), ("food", 1)]);
+        assert_eq!(web.nodes()[0].color, Color::RED);
+        assert_eq!(web.edges(), &[Edge { from: 1, to: 0, meals: 1 }]);
+
+        //  nodes keep their places, and the layout keeps them apart and inside
+        web.layout(100);
+        let positions: Vec<_> = web.nodes().iter().map(|node| node.pos).collect();
+        web.update(&sim);
+        assert_eq!(web.nodes().iter().map(|node| node.pos).collect::<Vec<_>>(), positions);
+        assert!((positions[0] - positions[1]).length() > 0.1);
+        assert!(positions.iter().all(|pos| pos.x >= MARGIN && pos.y <= 1. - MARGIN));
+
+        //  meals are forgotten after the window
+        sim.remove_blob(blob);
+        for _ in 0..WINDOW {
+            sim.step();
+        }
+        web.update(&sim);
+        assert_eq!(web.nodes().len(), 1);
+        assert!(web.edges().is_empty());
+    }
+}
@@ -0,0 +1,247 @@
+//! The foraging benchmark, a standard test of how well a blob finds
+//! food, so brains and genomes can be compared on equal footing.
+//!
+//! Each blob forages alone, without children, in the same arena from
+//! each of `STARTS` in turn. The food lies in a fixed layout of
+//! patches and strays, see `layout`, and grows back at the next place
+//! of the layout every `Benchmark::food_period` ticks, so every blob
+//! meets the same food and every run of the benchmark scores the same.
+//! A trial ends when its ticks run out or the blob starves, and blobs
+//! are ranked by the food they ate a trial:
+//!
+//! ```text
+//! rank  blob   food/trial  survived  first food
+//!    1  hardy        12.5      100%        212
+//!    2  frail         1.0       18%        340
+//! ```
+//!
+//! The scores can be written as JSON for tools outside the program,
+//! see `write_json`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::io;
+//! # use blobs::{foraging::{self, Benchmark}, tournament::Contestant};
+//! let contestants = vec![Contestant::load("ada.blob")?, Contestant::load("bob.blob")?];
+//! let scores = foraging::run(&Benchmark::default(), &contestants);
+//! foraging::write_table(&mut io::stdout(), &Benchmark::default(), &contestants, &scores)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{self, Write};
+
+use raylib::prelude::*;
+
+use crate::{
+    exchange::Exported,
+    narrator::quote,
+    simulation::{Simulation, SimulationConfig, Parameters},
+    tournament::Contestant,
+};
+
+/// Where the blob starts each trial, as fractions of the arena.
+pub const STARTS: [(f32, f32); 4] = [(0.5, 0.5), (0.15, 0.15), (0.85, 0.2), (0.3, 0.85)];
+/// Patches of food, as their center in fractions of the arena and how many foods they have.
+const PATCHES: [(f32, f32, usize); 4] = [(0.25, 0.3, 8), (0.75, 0.25, 6), (0.7, 0.75, 10), (0.2, 0.7, 4)];
+/// Foods strewn between the patches.
+const STRAYS: usize = 12;
+
+/// The arena and length of the benchmark, the same for every blob.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Benchmark {
+    pub size: Vector2,
+    pub seed: u64,
+    /// How long each trial lasts.
+    pub ticks: u64,
+    /// Ticks between foods growing back.
+    pub food_period: u64,
+}
+
+impl Default for Benchmark {
+    fn default() -> Self {
+        Self { size: Vector2::new(600., 600.), seed: 1, ticks: 3600, food_period: 40 }
+    }
+}
+
+/// Where food lies in an arena: patches around fixed centers, then
+/// strays evenly between them, the same every time.
+pub fn layout(size: Vector2) -> Vec<Vector2> {
+    let mut ret = vec![];
+    for &(x, y, count) in &PATCHES {
+        let center = Vector2::new(x, y) * size;
+        let radius = size.x.min(size.y) * 0.06;
+        for i in 0..count {
+            //  the golden angle spreads them evenly in a disc
+            let angle = i as f32 * 2.39996;
+            let distance = radius * ((i as f32 + 0.5) / count as f32).sqrt();
+            ret.push(center + Vector2::new(angle.cos(), angle.sin()) * distance);
+        }
+    }
+    for i in 0..STRAYS {
+        let t = (i as f32 + 0.5) / STRAYS as f32;
+        //  a low-discrepancy sequence, so strays neither clump nor line up
+        let u = (i as f32 * 0.618034).fract();
+        ret.push(Vector2::new(0.05 + 0.9 * u, 0.05 + 0.9 * t) * size);
+    }
+    ret
+}
+
+/// What a blob achieved over its trials.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Score {
+    pub trials: u32,
+    /// Ticks survived, over all trials.
+    pub survived: u64,
+    pub foods: u32,
+    /// Ticks until the first food, over the trials it was found in.
+    pub first_food: u64,
+    /// Trials the blob ate anything in.
+    pub fed: u32,
+}
+
+impl Score {
+    pub fn foods_per_trial(&self) -> f32 {
+        self.foods as f32 / self.trials.max(1) as f32
+    }
+
+    /// The share of the trials' ticks survived.
+    pub fn survival(&self, benchmark: &Benchmark) -> f32 {
+        self.survived as f32 / (benchmark.ticks * self.trials.max(1) as u64) as f32
+    }
+
+    /// The mean ticks until the first food, none if it found none.
+    pub fn mean_first_food(&self) -> Option<f32> {
+        Some(self.first_food as f32 / self.fed as f32).filter(|_| self.fed > 0)
+    }
+
+    fn add(&mut self, other: &Score) {
+        self.trials += other.trials;
+        self.survived += other.survived;
+        self.foods += other.foods;
+        self.first_food += other.first_food;
+        self.fed += other.fed;
+    }
+}
+
+/// Let a blob forage from a start, see `STARTS`.
+pub fn trial(benchmark: &Benchmark, blob: &Exported, start: usize) -> Score {
+    let parameters = Parameters { reproduction: false, predation: false, ..Parameters::default() };
+    let config = SimulationConfig { seed: benchmark.seed + start as u64, parameters, ..SimulationConfig::new(benchmark.size) };
+    let mut sim = Simulation::new(config);
+    let layout = layout(benchmark.size);
+    for &pos in &layout {
+        sim.insert_food(pos);
+    }
+    let (x, y) = STARTS[start];
+    let key = blob.insert(&mut sim, Vector2::new(x, y) * benchmark.size);
+
+    let mut score = Score { trials: 1, ..Score::default() };
+    let mut regrown = 0;
+    for tick in 1..=benchmark.ticks {
+        sim.step();
+        if tick % benchmark.food_period == 0 {
+            sim.insert_food(layout[regrown % layout.len()]);
+            regrown += 1;
+        }
+        match sim.get_blob(key) {
+            Some(blob) => {
+                if blob.foods_eaten > 0 && score.fed == 0 {
+                    score.first_food = tick;
+                    score.fed = 1;
+                }
+                score.survived = tick;
+                score.foods = blob.foods_eaten;
+            },
+            None => break,
+        }
+    }
+    score
+}
+
+/// Let every contestant forage from every start, returning their total scores.
+pub fn run(benchmark: &Benchmark, contestants: &[Contestant]) -> Vec<Score> {
+    contestants.iter().map(|contestant| {
+        let mut score = Score::default();
+        for start in 0..STARTS.len() {
+            score.add(&trial(benchmark, &contestant.blob, start));
+        }
+        score
+    }).collect()
+}
+
+/// The contestants with their scores, best foragers first.
+fn ranked<'a>(contestants: &'a [Contestant], scores: &'a [Score]) -> Vec<(&'a Contestant, &'a Score)> {
+    let mut ret: Vec<_> = contestants.iter().zip(scores).collect();
+    ret.sort_by(|(_, a), (_, b)| b.foods_per_trial().partial_cmp(&a.foods_per_trial()).unwrap()
+        .then(b.survived.cmp(&a.survived)));
+    ret
+}
+
+/// Write the contestants as a table ranked by food, see the module.
+pub fn write_table<W: Write>(out: &mut W, benchmark: &Benchmark, contestants: &[Contestant], scores: &[Score]) -> io::Result<()> {
+    let width = contestants.iter().map(|c| c.label.len()).max().unwrap_or(0).max("blob".len());
+    writeln!(out, "rank  {:width$}  food/trial  survived  first food", "blob", width = width)?;
+    for (i, (contestant, score)) in ranked(contestants, scores).into_iter().enumerate() {
+        let first_food = score.mean_first_food().map_or_else(|| "never".to_string(), |ticks| format!("{:.0}", ticks));
+        writeln!(out, "{:>4}  {:width$}  {:>10.1}  {:>7.0}%  {:>10}",
+            i + 1, contestant.label, score.foods_per_trial(), 100. * score.survival(benchmark), first_food,
+            width = width,
+        )?;
+    }
+    Ok(())
+}
+
+/// Write the benchmark and the ranked scores as a JSON object.
+pub fn write_json<W: Write>(out: &mut W, benchmark: &Benchmark, contestants: &[Contestant], scores: &[Score]) -> io::Result<()> {
+    let entries: Vec<_> = ranked(contestants, scores).into_iter().enumerate().map(|(i, (contestant, score))| {
+        let first_food = score.mean_first_food().map_or_else(|| "null".to_string(), |ticks| ticks.to_string());
+        format!(r#"{{"rank":{},"blob":{},"trials":{},"foods":{},"foods_per_trial":{},"survival":{},"first_food":{}}}"#,
+            i + 1, quote(&contestant.label), score.trials, score.foods, score.foods_per_trial(), score.survival(benchmark), first_food,
+        )
+    }).collect();
+    writeln!(out, r#"{{"benchmark":{{"seed":{},"ticks":{},"food_period":{},"starts":{}}},"scores":[{}]}}"#,
+        benchmark.seed, benchmark.ticks, benchmark.food_period, STARTS.len(), entries.join(","),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::BlobConfig;
+
+    fn contestant(label: &str, speed: f32, max_hunger: f32) -> Contestant {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let key = sim.insert_blob(BlobConfig { speed, max_hunger, ..BlobConfig::new(Vector2::new(100., 100.)) });
+        Contestant { label: label.to_string(), blob: Exported::from_blob(sim.get_blob(key).unwrap(), sim.parameters()) }
+    }
+
+    #[test]
+    fn test_foraging() {
+        let size = Vector2::new(600., 600.);
+        let layout = layout(size);
+        assert_eq!(layout.len(), PATCHES.iter().map(|&(_, _, count)| count).sum::<usize>() + STRAYS);
+        assert!(layout.iter().all(|pos| pos.x > 0. && pos.y > 0. && pos.x < size.x && pos.y < size.y));
+
+        let benchmark = Benchmark { ticks: 600, ..Benchmark::default() };
+        let contestants = vec![contestant("still", 0., 1000.), contestant("roaming", 80., 1000.)];
+        let scores = run(&benchmark, &contestants);
+        assert!(scores.iter().all(|score| score.trials == STARTS.len() as u32 && score.survival(&benchmark) == 1.));
+        //  the benchmark scores the same every time
+        assert_eq!(run(&benchmark, &contestants), scores);
+
+        let mut out = vec![];
+        write_table(&mut out, &benchmark, &contestants, &scores).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        assert!(table.starts_with("rank  blob "));
+        assert_eq!(table.lines().count(), 3);
+        //  a blob that never moves finds no food
+        assert!(table.lines().nth(1).unwrap().contains("roaming") && table.lines().nth(2).unwrap().ends_with("never"));
+
+        let mut out = vec![];
+        write_json(&mut out, &benchmark, &contestants, &scores).unwrap();
+        let json = String::from_utf8(out).unwrap();
+        assert!(json.starts_with(r#"{"benchmark":{"seed":1,"ticks":600,"food_period":40,"starts":4},"scores":[{"rank":1,"#));
+        assert!(json.contains(r#""blob":"still""#) && json.trim_end().ends_with("]}"));
+    }
+}
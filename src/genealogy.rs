@@ -0,0 +1,273 @@
+//! The family tree of a world, exported to explore in GraphViz or Gephi.
+//!
+//! A genealogy records every blob of a world from when it starts
+//! following it: after every tick those alive, and from a removal hook
+//! those that die or are removed, so a child born and eaten within a
+//! tick is kept too. Each blob is a node with an edge from its parent,
+//! if the parent was recorded; blobs already there when recording
+//! started have no recorded parent. Nodes carry the generation,
+//! species, when the blob was born and died, how long it lived, how
+//! many children it had and its traits.
+//!
+//! `--genealogy` writes the tree of each world as a run ends, as GEXF
+//! for a path ending in `.gexf` and as DOT otherwise:
+//!
+//! ```text
+//! digraph genealogy {
+//!   12 [label="Ada", generation="1", species="Grazer", born="0", died="1830", lifespan="30.5", children="2", ...];
+//!   270 [label="270", generation="2", ...];
+//!   12 -> 270;
+//! }
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use std::sync::{Arc, Mutex};
+//! # use blobs::genealogy::Genealogy;
+//! # let mut file = Vec::new();
+//! let genealogy = Arc::new(Mutex::new(Genealogy::default()));
+//! let noted = genealogy.clone();
+//! sim.on_entity_removed(0, Box::new(move |sim, removed| noted.lock().unwrap().note_removed(sim, removed)));
+//! sim.step();
+//! genealogy.lock().unwrap().record(&sim);
+//! genealogy.lock().unwrap().write_dot(&mut file)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{collections::BTreeMap, fs, io::{self, Write}, path::Path};
+
+use crate::{hooks::Removed, simulation::{Blob, Simulation}, svg};
+
+/// The attributes of nodes besides their labels, with their types in
+/// GEXF. `died` is -1 for blobs alive.
+const ATTRIBUTES: [(&str, &str); 10] = [
+    ("generation", "integer"),
+    ("species", "string"),
+    ("born", "long"),
+    ("died", "long"),
+    ("lifespan", "float"),
+    ("children", "integer"),
+    ("speed", "float"),
+    ("radius", "float"),
+    ("sight_depth", "float"),
+    ("pov", "float"),
+];
+
+/// A blob as recorded, by the index of its key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub name: Option<String>,
+    pub species: Option<String>,
+    pub parent: Option<u64>,
+    pub generation: u32,
+    /// The tick it was first recorded at, which for a blob born while
+    /// recording is the tick of its birth.
+    pub born: u64,
+    /// The tick it was removed at, if it was.
+    pub died: Option<u64>,
+    /// How many seconds it lived, so far if it lives.
+    pub lifespan: f32,
+    pub children: u32,
+    pub speed: f32,
+    pub radius: f32,
+    pub sight_depth: f32,
+    pub pov: f32,
+}
+
+impl Node {
+    fn of(blob: &Blob, tick: u64) -> Self {
+        Self {
+            name: blob.name.clone(),
+            species: blob.species.clone(),
+            parent: blob.parent.map(|parent| parent.index()),
+            generation: blob.generation,
+            born: tick,
+            died: None,
+            lifespan: blob.alive_time,
+            children: blob.children,
+            speed: blob.speed,
+            radius: blob.radius(),
+            sight_depth: blob.sight_depth(),
+            pov: blob.pov,
+        }
+    }
+
+    /// Bring what changes over a life up to date.
+    fn update(&mut self, blob: &Blob) {
+        self.name = blob.name.clone();
+        self.lifespan = blob.alive_time;
+        self.children = blob.children;
+        self.radius = blob.radius();
+    }
+
+    /// The values of the node's attributes, in the order of `ATTRIBUTES`.
+    fn values(&self) -> [String; ATTRIBUTES.len()] {
+        [
+            self.generation.to_string(),
+            self.species.clone().unwrap_or_default(),
+            self.born.to_string(),
+            self.died.map_or("-1".to_string(), |tick| tick.to_string()),
+            self.lifespan.to_string(),
+            self.children.to_string(),
+            self.speed.to_string(),
+            self.radius.to_string(),
+            self.sight_depth.to_string(),
+            self.pov.to_string(),
+        ]
+    }
+}
+
+/// The blobs of a world as recorded, see the module.
+#[derive(Debug, Clone, Default)]
+pub struct Genealogy {
+    nodes: BTreeMap<u64, Node>,
+}
+
+impl Genealogy {
+    /// Record the blobs alive now, after a tick.
+    pub fn record(&mut self, sim: &Simulation) {
+        let tick = sim.clock().tick();
+        for (key, blob) in sim.blobs() {
+            self.nodes.entry(key.index())
+                .and_modify(|node| node.update(blob))
+                .or_insert_with(|| Node::of(blob, tick));
+        }
+    }
+
+    /// Record a blob as it is removed, from a removal hook.
+    pub fn note_removed(&mut self, sim: &Simulation, removed: &Removed) {
+        if let Removed::Blob(key, blob) = removed {
+            let tick = sim.clock().tick();
+            let node = self.nodes.entry(key.index()).or_insert_with(|| Node::of(blob, tick));
+            node.update(blob);
+            node.died = Some(tick);
+        }
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item=(u64, &Node)> {
+        self.nodes.iter().map(|(&key, node)| (key, node))
+    }
+
+    /// The edges from parents to children, of parents recorded.
+    fn edges(&self) -> impl Iterator<Item=(u64, u64)> + '_ {
+        self.nodes().filter_map(move |(key, node)| node.parent.filter(|parent| self.nodes.contains_key(parent)).map(|parent| (parent, key)))
+    }
+
+    /// Write the tree in the DOT language of GraphViz.
+    pub fn write_dot<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        writeln!(w, "digraph genealogy {{")?;
+        for (key, node) in self.nodes() {
+            let label = node.name.clone().unwrap_or_else(|| key.to_string());
+            write!(w, "  {} [label={}", key, quote(&label))?;
+            for ((name, _), value) in ATTRIBUTES.iter().zip(&node.values()) {
+                write!(w, ", {}={}", name, quote(value))?;
+            }
+            writeln!(w, "];")?;
+        }
+        for (parent, child) in self.edges() {
+            writeln!(w, "  {} -> {};", parent, child)?;
+        }
+        writeln!(w, "}}")
+    }
+
+    /// Write the tree as a GEXF document, the format of Gephi.
+    pub fn write_gexf<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let attribute = |text: &str| svg::escape(text).replace('"', "&quot;");
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, r#"<gexf xmlns="http://gexf.net/1.3" version="1.3">"#)?;
+        writeln!(w, r#"<graph defaultedgetype="directed">"#)?;
+        writeln!(w, r#"<attributes class="node">"#)?;
+        for (id, (name, kind)) in ATTRIBUTES.iter().enumerate() {
+            writeln!(w, r#"<attribute id="{}" title="{}" type="{}"/>"#, id, name, kind)?;
+        }
+        writeln!(w, "</attributes>")?;
+        writeln!(w, "<nodes>")?;
+        for (key, node) in self.nodes() {
+            let label = node.name.clone().unwrap_or_else(|| key.to_string());
+            writeln!(w, r#"<node id="{}" label="{}"><attvalues>"#, key, attribute(&label))?;
+            for (id, value) in node.values().iter().enumerate() {
+                writeln!(w, r#"<attvalue for="{}" value="{}"/>"#, id, attribute(value))?;
+            }
+            writeln!(w, "</attvalues></node>")?;
+        }
+        writeln!(w, "</nodes>")?;
+        writeln!(w, "<edges>")?;
+        for (id, (parent, child)) in self.edges().enumerate() {
+            writeln!(w, r#"<edge id="{}" source="{}" target="{}"/>"#, id, parent, child)?;
+        }
+        writeln!(w, "</edges>")?;
+        writeln!(w, "</graph>")?;
+        writeln!(w, "</gexf>")
+    }
+
+    /// Write the tree to a file, as GEXF if its extension is `gexf`
+    /// and as DOT otherwise.
+    pub fn write_to(&self, path: &Path) -> io::Result<()> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        if path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("gexf")) {
+            self.write_gexf(&mut out)?;
+        } else {
+            self.write_dot(&mut out)?;
+        }
+        out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use raylib::prelude::Vector2;
+
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_genealogy() {
+        let mut config = SimulationConfig::new(Vector2::new(300., 300.));
+        config.parameters.food_rate = 0.;
+        let mut sim = Simulation::new(config);
+        let genealogy = Arc::new(Mutex::new(Genealogy::default()));
+        let noted = genealogy.clone();
+        sim.on_entity_removed(0, Box::new(move |sim, removed| noted.lock().unwrap().note_removed(sim, removed)));
+        let recorded = genealogy.clone();
+        sim.on_post_tick(0, Box::new(move |sim| recorded.lock().unwrap().record(sim)));
+
+        //  a parent has a child, and is then removed
+        let parent = sim.insert_blob(BlobConfig { name: Some("Ada \"A\"".to_string()), species: Some("Grazer".to_string()), max_hunger: 20., ..BlobConfig::new(Vector2::new(100., 100.)) });
+        sim.step();
+        sim.get_blob_mut(parent).unwrap().alive_time = Blob::REPRODUCTION_AGE;
+        sim.insert_food(sim.get_blob(parent).unwrap().pos());
+        sim.step();
+        let child = sim.blobs().map(|(key, _)| key).find(|&key| key != parent).unwrap();
+        sim.remove_blob(parent);
+        sim.step();
+
+        let genealogy = genealogy.lock().unwrap();
+        let nodes: BTreeMap<_, _> = genealogy.nodes().collect();
+        assert_eq!(nodes.len(), 2);
+        let (parent_node, child_node) = (nodes[&parent.index()], nodes[&child.index()]);
+        assert_eq!((parent_node.born, parent_node.died, parent_node.children), (1, Some(2), 1));
+        assert_eq!((child_node.parent, child_node.generation, child_node.died), (Some(parent.index()), 2, None));
+        assert_eq!(child_node.species.as_deref(), Some("Grazer"));
+        assert_eq!(genealogy.edges().collect::<Vec<_>>(), vec![(parent.index(), child.index())]);
+
+        let mut dot = vec![];
+        genealogy.write_dot(&mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.contains(&format!("  {} [label=\"Ada \\\"A\\\"\", generation=\"1\", species=\"Grazer\"", parent.index())), "{}", dot);
+        assert!(dot.contains(&format!("  {} -> {};", parent.index(), child.index())));
+
+        let mut gexf = vec![];
+        genealogy.write_gexf(&mut gexf).unwrap();
+        let gexf = String::from_utf8(gexf).unwrap();
+        assert!(gexf.contains(&format!(r#"<node id="{}" label="Ada &quot;A&quot;">"#, parent.index())), "{}", gexf);
+        assert!(gexf.contains(&format!(r#"<edge id="0" source="{}" target="{}"/>"#, parent.index(), child.index())));
+        assert_eq!(gexf.matches("<attvalue ").count(), 2 * ATTRIBUTES.len());
+    }
+}
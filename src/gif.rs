@@ -0,0 +1,118 @@
+//! A minimal encoder for animated GIFs.
+//!
+//! Frames are quantized to a fixed palette and compressed as soon as
+//! they are encoded, so many of them can be kept in memory and
+//! written out later without any more work.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::gif;
+//! # use raylib::prelude::Color;
+//! # let (width, height, mut file) = (2, 2, Vec::new());
+//! # let pixels = vec![Color::WHITE; 4];
+//! let frames = vec![gif::Frame::encode(width, height, &pixels)];
+//! gif::write(&mut file, &frames, 10)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{self, Write};
+
+use raylib::prelude::Color;
+
+use crate::lzw;
+
+//  levels of each channel in the palette, a 6x7x6 color cube
+const RED_LEVELS: u32 = 6;
+const GREEN_LEVELS: u32 = 7;
+const BLUE_LEVELS: u32 = 6;
+
+/// An image quantized and compressed, ready to be written.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    width: u16,
+    height: u16,
+    //  the LZW data, in sub-blocks and terminated
+    data: Vec<u8>,
+}
+
+impl Frame {
+    /// Quantize and compress pixels given row by row.
+    pub fn encode(width: u16, height: u16, pixels: &[Color]) -> Self {
+        assert_eq!(pixels.len(), width as usize * height as usize);
+        let indices: Vec<u8> = pixels.iter().map(|&color| palette_index(color)).collect();
+        let mut data = vec![];
+        for block in lzw::compress(&indices).chunks(255) {
+            data.push(block.len() as u8);
+            data.extend_from_slice(block);
+        }
+        data.push(0);
+        Self { width, height, data }
+    }
+
+    /// The estimated bytes held by the frame.
+    pub fn bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.data.capacity()
+    }
+}
+
+/// Write frames as a looping animation, each shown for a delay in
+/// hundredths of a second. The animation is as large as the first frame.
+pub fn write<W: Write>(out: &mut W, frames: &[Frame], delay: u16) -> io::Result<()> {
+    let (width, height) = frames.first().map_or((1, 1), |frame| (frame.width, frame.height));
+    out.write_all(b"GIF89a")?;
+    //  logical screen with a global color table of 256 entries
+    out.write_all(&width.to_le_bytes())?;
+    out.write_all(&height.to_le_bytes())?;
+    out.write_all(&[0xf7, 0, 0])?;
+    out.write_all(&palette())?;
+    //  loop forever
+    out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+    for frame in frames {
+        //  graphic control: leave the frame in place, no transparency
+        out.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+        out.write_all(&delay.to_le_bytes())?;
+        out.write_all(&[0, 0])?;
+        //  image descriptor at the top left, without a local color table
+        out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        out.write_all(&frame.width.to_le_bytes())?;
+        out.write_all(&frame.height.to_le_bytes())?;
+        out.write_all(&[0, lzw::MIN_CODE_SIZE])?;
+        out.write_all(&frame.data)?;
+    }
+    out.write_all(&[0x3b])
+}
+
+/// The 256 colors of the global color table as RGB triples.
+fn palette() -> Vec<u8> {
+    let level = |value: u32, levels: u32| (value * 255 / (levels - 1)) as u8;
+    let mut ret = Vec::with_capacity(3 * 256);
+    for r in 0..RED_LEVELS {
+        for g in 0..GREEN_LEVELS {
+            for b in 0..BLUE_LEVELS {
+                ret.extend_from_slice(&[level(r, RED_LEVELS), level(g, GREEN_LEVELS), level(b, BLUE_LEVELS)]);
+            }
+        }
+    }
+    //  pad the unused entries with black
+    ret.resize(3 * 256, 0);
+    ret
+}
+
+/// The palette entry closest to a color.
+fn palette_index(color: Color) -> u8 {
+    let level = |value: u8, levels: u32| (value as u32 * (levels - 1) + 127) / 255;
+    let (r, g, b) = (level(color.r, RED_LEVELS), level(color.g, GREEN_LEVELS), level(color.b, BLUE_LEVELS));
+    ((r * GREEN_LEVELS + g) * BLUE_LEVELS + b) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette() {
+        assert_eq!(palette_index(Color::new(255, 255, 255, 255)), 251);
+        assert_eq!(&palette()[3 * 251..3 * 252], &[255, 255, 255]);
+    }
+}
@@ -0,0 +1,119 @@
+//! Golden image tests of the renderer.
+//!
+//! A fixed seeded world is drawn offscreen in each theme and
+//! compared with images stored in `tests/golden`, so changes to
+//! drawing code that alter what is on screen are noticed. GPUs
+//! and drivers rasterize slightly differently, so pixels may
+//! differ a little, and a few may differ a lot.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let image = window.render_offscreen(golden::WIDTH, golden::HEIGHT, |draw| draw_scene(draw))?;
+//! let tolerance = Tolerance::default();
+//! let difference = golden::check(&image, "tests/golden/world-light.png", &tolerance)?;
+//! assert!(difference.within(&tolerance));
+//! ```
+
+use std::fmt;
+
+use raylib::prelude::*;
+
+/// Where the golden images are kept.
+pub const DIR: &str = "tests/golden";
+pub const WIDTH: u32 = 640;
+pub const HEIGHT: u32 = 360;
+/// The seed of the drawn world.
+pub const SEED: u64 = 1234;
+/// Ticks simulated before drawing, so blobs have moved, eaten and died.
+pub const TICKS: u64 = 600;
+
+/// Whether a render test compares with the golden images or replaces them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Compare,
+    Update,
+}
+
+/// How far a rendered image may stray from its golden image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// The largest difference in any channel that still counts as the same.
+    pub channel: u8,
+    /// The part of the pixels that may differ by more than that.
+    pub fraction: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self { channel: 8, fraction: 0.002 }
+    }
+}
+
+/// How two images of the same size differ.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difference {
+    /// Pixels with a channel that differs by more than the tolerance.
+    pub differing: usize,
+    pub total: usize,
+    /// The largest difference in any channel of any pixel.
+    pub max_channel: u8,
+}
+
+impl Difference {
+    pub fn within(&self, tolerance: &Tolerance) -> bool {
+        self.differing as f32 <= tolerance.fraction * self.total as f32
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} pixels differ, by up to {}", self.differing, self.total, self.max_channel)
+    }
+}
+
+/// Compare pixels row by row, counting those that differ by more than a channel tolerance.
+pub fn compare(actual: &[Color], expected: &[Color], channel: u8) -> Result<Difference, String> {
+    if actual.len() != expected.len() {
+        return Err(format!("{} pixels instead of {}", actual.len(), expected.len()));
+    }
+    let mut ret = Difference { differing: 0, total: actual.len(), max_channel: 0 };
+    for (a, b) in actual.iter().zip(expected) {
+        let difference = [(a.r, b.r), (a.g, b.g), (a.b, b.b), (a.a, b.a)].iter()
+            .map(|&(a, b)| (a as i16 - b as i16).unsigned_abs() as u8)
+            .max()
+            .unwrap_or(0);
+        ret.max_channel = ret.max_channel.max(difference);
+        if difference > channel {
+            ret.differing += 1;
+        }
+    }
+    Ok(ret)
+}
+
+/// Compare an image with the golden image in a file.
+pub fn check(image: &Image, path: &str, tolerance: &Tolerance) -> Result<Difference, String> {
+    let golden = Image::load_image(path)?;
+    if (golden.width(), golden.height()) != (image.width(), image.height()) {
+        return Err(format!("the image is {}x{} but {} is {}x{}", image.width(), image.height(), path, golden.width(), golden.height()));
+    }
+    compare(&image.get_image_data(), &golden.get_image_data(), tolerance.channel)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare() {
+        let expected = vec![Color::new(10, 20, 30, 255); 1000];
+        let mut actual = expected.clone();
+        actual[0].r = 15;
+        actual[1].b = 90;
+        let difference = compare(&actual, &expected, Tolerance::default().channel).unwrap();
+        assert_eq!(difference, Difference { differing: 1, total: 1000, max_channel: 60 });
+        assert!(difference.within(&Tolerance::default()));
+        assert!(!difference.within(&Tolerance { channel: 8, fraction: 0. }));
+        assert!(compare(&actual[1..], &expected, 0).is_err());
+    }
+}
@@ -0,0 +1,162 @@
+//! Statistics of a group of blobs, such as the selected ones.
+//!
+//! Rather than listing every blob, a selection of several is summed
+//! up as its size, the mean, least and greatest of each gene of
+//! `inspector::genome`, and how many of it belong to each species.
+//! The blobs themselves can be written out as CSV, a row each.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let blobs: Vec<&Blob> = selected.iter().filter_map(|&key| sim.get_blob(key)).collect();
+//! let stats = GroupStats::of(&blobs);
+//! group::draw(&mut draw, &theme, &fonts, &stats, Vector2::new(10., 10.));
+//! ```
+
+use std::{collections::BTreeMap, io::{self, Write}};
+
+use raylib::prelude::*;
+
+use crate::{
+    window::DrawingContext,
+    simulation::Blob,
+    theme::Theme,
+    fonts::Fonts,
+    inspector,
+};
+
+/// The spread of a gene over a group.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Spread {
+    pub name: &'static str,
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupStats {
+    pub count: usize,
+    /// In the order of `inspector::genome`.
+    pub genes: Vec<Spread>,
+    /// Blobs by species, with those of none under "none".
+    pub species: BTreeMap<String, usize>,
+}
+
+impl GroupStats {
+    pub fn of(blobs: &[&Blob]) -> Self {
+        let mut genes: Vec<Spread> = vec![];
+        let mut species = BTreeMap::new();
+        for blob in blobs {
+            let genome = inspector::genome(blob);
+            if genes.is_empty() {
+                genes = genome.iter().map(|gene| Spread { name: gene.name, mean: 0., min: gene.value, max: gene.value }).collect();
+            }
+            for (spread, gene) in genes.iter_mut().zip(&genome) {
+                spread.mean += gene.value / blobs.len() as f32;
+                spread.min = spread.min.min(gene.value);
+                spread.max = spread.max.max(gene.value);
+            }
+            *species.entry(blob.species.clone().unwrap_or_else(|| "none".to_string())).or_insert(0) += 1;
+        }
+        Self { count: blobs.len(), genes, species }
+    }
+}
+
+/// Write blobs as CSV, a row each under a header, labeled by their world.
+pub fn write_csv<W: Write>(w: &mut W, blobs: &[(&str, &Blob)]) -> io::Result<()> {
+    let genes: Vec<_> = match blobs.first() {
+        Some((_, blob)) => inspector::genome(blob).iter().map(|gene| gene.name).collect(),
+        None => vec![],
+    };
+    writeln!(w, "world,name,species,diet,age,{}", genes.iter().map(|name| field(name)).collect::<Vec<_>>().join(","))?;
+    for &(world, blob) in blobs {
+        let values: Vec<_> = inspector::genome(blob).iter().map(|gene| gene.value.to_string()).collect();
+        writeln!(w, "{},{},{},{},{},{}",
+            field(world),
+            field(blob.name.as_deref().unwrap_or("")),
+            field(blob.species.as_deref().unwrap_or("")),
+            blob.diet.name(),
+            blob.alive_time,
+            values.join(","),
+        )?;
+    }
+    Ok(())
+}
+
+/// A CSV field, quoted if it holds a comma, quote or line break.
+fn field(text: &str) -> String {
+    if text.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+const WIDTH: f32 = 320.;
+const PADDING: f32 = 10.;
+const FONT_SIZE: i32 = 16;
+const SMALL_FONT_SIZE: i32 = 10;
+const ROW_HEIGHT: i32 = 14;
+
+/// Draw the statistics of a group with the panel's top left corner at a position.
+pub fn draw(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, stats: &GroupStats, pos: Vector2) {
+    let rows = 1 + stats.genes.len() + stats.species.len();
+    let height = 3. * PADDING + FONT_SIZE as f32 + (rows as i32 * ROW_HEIGHT) as f32 + PADDING;
+    let rect = Rectangle::new(pos.x, pos.y, WIDTH, height);
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+
+    let (x, mut y) = ((pos.x + PADDING) as i32, (pos.y + PADDING) as i32);
+    fonts.draw_text(draw, &format!("{} blobs selected", stats.count), x, y, FONT_SIZE, theme.text);
+    y += FONT_SIZE + PADDING as i32;
+
+    //  a column each for the mean, least and greatest
+    let columns = [x + 120, x + 180, x + 240];
+    for (&column, heading) in columns.iter().zip(&["mean", "min", "max"]) {
+        fonts.draw_text(draw, heading, column, y, SMALL_FONT_SIZE, theme.muted_text);
+    }
+    y += ROW_HEIGHT;
+    for spread in &stats.genes {
+        fonts.draw_text(draw, spread.name, x, y, SMALL_FONT_SIZE, theme.text);
+        for (&column, value) in columns.iter().zip(&[spread.mean, spread.min, spread.max]) {
+            fonts.draw_text(draw, &format!("{:.2}", value), column, y, SMALL_FONT_SIZE, theme.text);
+        }
+        y += ROW_HEIGHT;
+    }
+
+    y += PADDING as i32;
+    for (species, &count) in &stats.species {
+        let text = format!("{}: {} ({:.0}%)", species, count, 100. * count as f32 / stats.count as f32);
+        fonts.draw_text(draw, &text, x, y, SMALL_FONT_SIZE, theme.muted_text);
+        y += ROW_HEIGHT;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, Simulation, SimulationConfig};
+
+    #[test]
+    fn test_group_stats() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(200., 200.)));
+        let a = sim.insert_blob(BlobConfig { speed: 40., species: Some("Grazer".to_string()), ..BlobConfig::new(Vector2::new(50., 50.)) });
+        let b = sim.insert_blob(BlobConfig { speed: 80., name: Some("Bob, Jr.".to_string()), ..BlobConfig::new(Vector2::new(150., 50.)) });
+        let blobs = [sim.get_blob(a).unwrap(), sim.get_blob(b).unwrap()];
+        let stats = GroupStats::of(&blobs);
+        assert_eq!(stats.count, 2);
+        let speed = stats.genes.iter().find(|spread| spread.name == "Speed").unwrap();
+        assert_eq!((speed.mean, speed.min, speed.max), (60., 40., 80.));
+        assert_eq!(stats.species.get("Grazer"), Some(&1));
+        assert_eq!(stats.species.get("none"), Some(&1));
+
+        let mut out = vec![];
+        write_csv(&mut out, &[("A", blobs[0]), ("A", blobs[1])]).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with("world,name,species,diet,age,Radius,Speed,"));
+        assert!(lines[2].starts_with("A,\"Bob, Jr.\",,herbivore,0,5,80,"));
+    }
+}
@@ -0,0 +1,157 @@
+//! A handle other threads control a world through, such as a server,
+//! a shell or workers running scripts, while one thread steps it.
+//!
+//! A world owns a `CommandQueue`, and hands out `SimulationHandle`s,
+//! which can be cloned and sent to any thread. Through a handle a
+//! thread sends commands, in the form of `lockstep::Command`, and
+//! subscribes to the events of the world. Nothing a handle sends
+//! touches the world at once: the thread stepping it takes the
+//! commands queued at the start of the next tick, applies them in the
+//! order they were sent, steps and then sends the events of the tick
+//! to every subscriber, so the world has a single writer and a tick is
+//! never seen half done.
+//!
+//! Commands outside the world or not finite, see `Command::validate`,
+//! are dropped by the thread stepping it rather than applied.
+//!
+//! Sending fails once the world is gone. A subscriber that dropped its
+//! receiver is forgotten at the next tick. A world in a lockstep
+//! session must not be sent commands, which its peer would not see.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let handle = world.commands.handle();
+//! let events = handle.subscribe()?;
+//! thread::spawn(move || handle.send(Command::SpawnFood(Vector2::new(120., 80.))));
+//! for command in world.commands.take() {
+//!     apply_command(&mut sim, command);
+//! }
+//! sim.step();
+//! world.commands.publish(&sim, tick);
+//! for (tick, event) in events.try_iter() { ... }
+//! ```
+
+use std::sync::mpsc;
+
+use crate::{
+    lockstep::Command,
+    simulation::{Event, Simulation},
+};
+
+/// The events of a world with their ticks, as a subscriber receives them.
+pub type Events = mpsc::Receiver<(u64, Event)>;
+
+enum Message {
+    Command(Command),
+    Subscribe(mpsc::Sender<(u64, Event)>),
+}
+
+/// What other threads control a world through, see the module.
+#[derive(Clone)]
+pub struct SimulationHandle {
+    sender: mpsc::Sender<Message>,
+}
+
+impl SimulationHandle {
+    /// Queue a command for the start of the next tick.
+    pub fn send(&self, command: Command) -> Result<(), String> {
+        self.sender.send(Message::Command(command)).map_err(|_| "the world is gone".to_string())
+    }
+
+    /// Receive the events of the world, with their ticks, from the
+    /// next tick on.
+    pub fn subscribe(&self) -> Result<Events, String> {
+        let (sender, receiver) = mpsc::channel();
+        self.sender.send(Message::Subscribe(sender)).map_err(|_| "the world is gone".to_string())?;
+        Ok(receiver)
+    }
+}
+
+/// The commands sent to a world and who listens to it, see the module.
+pub struct CommandQueue {
+    //  kept to make handles from
+    sender: mpsc::Sender<Message>,
+    receiver: mpsc::Receiver<Message>,
+    subscribers: Vec<mpsc::Sender<(u64, Event)>>,
+}
+
+impl Default for CommandQueue {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver, subscribers: vec![] }
+    }
+}
+
+impl CommandQueue {
+    pub fn handle(&self) -> SimulationHandle {
+        SimulationHandle { sender: self.sender.clone() }
+    }
+
+    /// The commands sent since the last time, in the order they were
+    /// sent, to apply before the next tick.
+    pub fn take(&mut self) -> Vec<Command> {
+        let mut ret = vec![];
+        for message in self.receiver.try_iter() {
+            match message {
+                Message::Command(command) => ret.push(command),
+                Message::Subscribe(subscriber) => self.subscribers.push(subscriber),
+            }
+        }
+        ret
+    }
+
+    /// Send the events of a world since a tick to the subscribers.
+    pub fn publish(&mut self, sim: &Simulation, since: u64) {
+        if self.subscribers.is_empty() { return; }
+        let events: Vec<_> = sim.events_since(since).cloned().collect();
+        self.subscribers.retain(|subscriber| events.iter().all(|event| subscriber.send(event.clone()).is_ok()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use raylib::prelude::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_handle() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let mut queue = CommandQueue::default();
+        let handle = queue.handle();
+        let events = handle.subscribe().unwrap();
+        let senders: Vec<_> = (0..4).map(|i| {
+            let handle = handle.clone();
+            thread::spawn(move || handle.send(Command::SpawnFood(Vector2::new(10. * i as f32, 10.))).unwrap())
+        }).collect();
+        for sender in senders {
+            sender.join().unwrap();
+        }
+
+        //  nothing changes until the owner applies the commands
+        assert_eq!(sim.foods().count(), 0);
+        let commands = queue.take();
+        assert_eq!(commands.len(), 4);
+        for command in commands {
+            if let Command::SpawnFood(pos) = command {
+                sim.insert_food(pos);
+            }
+        }
+        let tick = sim.clock().tick();
+        sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.step();
+        queue.publish(&sim, tick);
+        assert!(matches!(events.try_recv(), Ok((0, Event::Born(_)))));
+        assert_eq!(sim.foods().count(), 4);
+
+        //  a subscriber that hung up is forgotten, and the world gone fails to send
+        drop(events);
+        sim.insert_blob(BlobConfig::new(Vector2::new(200., 100.)));
+        queue.publish(&sim, tick);
+        assert!(queue.subscribers.is_empty());
+        drop(queue);
+        assert!(handle.send(Command::SpawnBlob).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+//! How a trait is spread over the population, and how the spread
+//! shifted over a run.
+//!
+//! The simulation records, with its other statistics, how many blobs
+//! have each of `TRAITS` in each of `BINS` equal bins of its usual
+//! range, as the series `<trait> histogram <bin>`, the last bin also
+//! counting the blobs beyond the range. A histogram of any recorded
+//! tick is read back from the statistics, and the view plays them
+//! from the start of the run to the tick the world is at over
+//! `PLAY_SECONDS`, then follows the world. The scrubber under the
+//! bars seeks to a tick and pauses, and the button beside it plays
+//! or pauses.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let speed = &histogram::TRAITS[0];
+//! let counts = histogram::counts(sim.stats(), speed, sim.clock().tick());
+//! let mut view = HistogramView::new();
+//! view.update(&frame, sim.stats(), rect);
+//! view.draw(&mut draw, &theme, &fonts, sim.stats(), rect);
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    window::DrawingContext,
+    fonts::Fonts,
+    input::{Button, Frame},
+    simulation::{Blob, Simulation},
+    stats::StatsStore,
+    theme::Theme,
+};
+
+/// How many bins a trait's range is split into.
+pub const BINS: usize = 10;
+/// How long playing the whole recorded history takes.
+pub const PLAY_SECONDS: f64 = 10.;
+
+const PADDING: f32 = 8.;
+const FONT_SIZE: i32 = 10;
+const SCRUBBER_HEIGHT: f32 = 12.;
+const BUTTON_WIDTH: f32 = 40.;
+
+/// A trait whose spread is recorded, and its usual range from 0.
+#[derive(Clone, Copy)]
+pub struct Trait {
+    pub name: &'static str,
+    pub max: f32,
+    pub value: fn(&Blob) -> f32,
+}
+
+pub const TRAITS: [Trait; 4] = [
+    Trait { name: "speed", max: 120., value: |blob| blob.speed },
+    Trait { name: "size", max: 20., value: Blob::radius },
+    Trait { name: "sight", max: 170., value: Blob::sight_depth },
+    Trait { name: "field of view", max: 180., value: |blob| blob.pov },
+];
+
+impl Trait {
+    /// The name of the series of a bin.
+    pub fn series(&self, bin: usize) -> String {
+        format!("{} histogram {}", self.name, bin)
+    }
+
+    /// The bin a value falls in.
+    pub fn bin(&self, value: f32) -> usize {
+        ((value / self.max * BINS as f32).max(0.) as usize).min(BINS - 1)
+    }
+}
+
+/// The statistics sampled with the others, by name.
+pub fn samples(sim: &Simulation) -> Vec<(String, f32)> {
+    let mut ret = vec![];
+    for spread in &TRAITS {
+        let mut counts = [0; BINS];
+        for (_, blob) in sim.blobs() {
+            counts[spread.bin((spread.value)(blob))] += 1;
+        }
+        ret.extend(counts.iter().enumerate().map(|(bin, &count)| (spread.series(bin), count as f32)));
+    }
+    ret
+}
+
+/// How many blobs were in each bin of a trait at the last sample at
+/// or before a tick, all 0 before the first.
+pub fn counts(stats: &StatsStore, spread: &Trait, tick: u64) -> [f32; BINS] {
+    let mut ret = [0.; BINS];
+    for (bin, count) in ret.iter_mut().enumerate() {
+        if let Some(value) = stats.id(&spread.series(bin)).and_then(|id| stats.at(id, tick)) {
+            *count = value;
+        }
+    }
+    ret
+}
+
+/// The first and last recorded ticks of a trait's spread.
+fn recorded(stats: &StatsStore, spread: &Trait) -> Option<(u64, u64)> {
+    let id = stats.id(&spread.series(0))?;
+    Some((stats.first(id)?, stats.latest(id)?.tick))
+}
+
+/// The histogram of a trait, played over the run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistogramView {
+    /// The index of the trait in `TRAITS`.
+    pub spread: usize,
+    /// The tick shown, or the latest while it follows the world.
+    playhead: Option<f64>,
+    playing: bool,
+    //  when the last frame was, for how far to play
+    last_time: Option<f64>,
+    scrubbing: bool,
+}
+
+impl HistogramView {
+    /// A view of the first trait, playing from the start.
+    pub fn new() -> Self {
+        Self { spread: 0, playhead: Some(0.), playing: true, last_time: None, scrubbing: false }
+    }
+
+    /// The view of the next trait, played from the start, or none
+    /// after the last.
+    pub fn next(&self) -> Option<Self> {
+        Some(Self { spread: self.spread + 1, ..Self::new() }).filter(|view| view.spread < TRAITS.len())
+    }
+
+    fn scrubber(rect: Rectangle) -> Rectangle {
+        let y = rect.y + rect.height - PADDING - SCRUBBER_HEIGHT;
+        Rectangle::new(rect.x + PADDING + BUTTON_WIDTH + PADDING, y, rect.width - 3. * PADDING - BUTTON_WIDTH, SCRUBBER_HEIGHT)
+    }
+
+    fn button(rect: Rectangle) -> Rectangle {
+        let scrubber = Self::scrubber(rect);
+        Rectangle::new(rect.x + PADDING, scrubber.y, BUTTON_WIDTH, SCRUBBER_HEIGHT)
+    }
+
+    /// The tick shown, within what is recorded.
+    pub fn tick(&self, stats: &StatsStore) -> Option<u64> {
+        let (first, last) = recorded(stats, &TRAITS[self.spread])?;
+        Some(self.playhead.map_or(last, |playhead| (playhead as u64).clamp(first, last)))
+    }
+
+    /// Whether the view takes the mouse in this frame, leaving it to no gesture.
+    pub fn captures(&self, frame: &Frame, rect: Rectangle) -> bool {
+        self.scrubbing || (frame.button == Button::Pressed && rect.check_collision_point_rec(frame.mouse))
+    }
+
+    /// Read this frame's input and play on, in a rectangle.
+    pub fn update(&mut self, frame: &Frame, stats: &StatsStore, rect: Rectangle) {
+        let elapsed = self.last_time.map_or(0., |last| (frame.time - last).max(0.));
+        self.last_time = Some(frame.time);
+        let (first, last) = match recorded(stats, &TRAITS[self.spread]) {
+            Some(range) => range,
+            None => return,
+        };
+
+        let scrubber = Self::scrubber(rect);
+        if frame.button == Button::Pressed && Self::button(rect).check_collision_point_rec(frame.mouse) {
+            self.playing = !self.playing;
+            //  playing from the latest tick plays again from the start
+            if self.playing && self.playhead.is_none() {
+                self.playhead = Some(first as f64);
+            }
+        } else if frame.button == Button::Pressed && scrubber.check_collision_point_rec(frame.mouse) {
+            self.scrubbing = true;
+        }
+        if self.scrubbing {
+            let t = ((frame.mouse.x - scrubber.x) / scrubber.width).clamp(0., 1.) as f64;
+            self.playhead = Some(first as f64 + t * (last - first) as f64);
+            self.playing = false;
+            self.scrubbing = frame.is_button_down();
+        } else if self.playing {
+            if let Some(playhead) = &mut self.playhead {
+                *playhead = playhead.max(first as f64) + elapsed / PLAY_SECONDS * (last - first) as f64;
+                if *playhead >= last as f64 {
+                    self.playhead = None;
+                    self.playing = false;
+                }
+            }
+        }
+    }
+
+    pub fn draw(&self, draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, stats: &StatsStore, rect: Rectangle) {
+        let spread = &TRAITS[self.spread];
+        draw.draw_rectangle_rec(rect, theme.panel);
+        draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+        let (x, y) = ((rect.x + PADDING) as i32, (rect.y + PADDING) as i32);
+        let tick = self.tick(stats);
+        let title = match (tick, self.playhead) {
+            (Some(tick), Some(_)) => format!("{} at tick {} (shift G for the next trait)", spread.name, tick),
+            (Some(tick), None) => format!("{} now, tick {} (shift G for the next trait)", spread.name, tick),
+            (None, _) => format!("{}: nothing recorded yet", spread.name),
+        };
+        fonts.draw_text(draw, &title, x, y, FONT_SIZE, theme.text);
+        let (first, last) = match (tick, recorded(stats, spread)) {
+            (Some(_), Some(range)) => range,
+            _ => return,
+        };
+
+        //  bars on the scale of the fullest bin of the whole run, so they do not jump as it plays
+        let top = (0..BINS)
+            .filter_map(|bin| stats.id(&spread.series(bin)))
+            .filter_map(|id| stats.max(id))
+            .fold(1., f32::max);
+        let counts = counts(stats, spread, tick.unwrap_or(last));
+        let chart = Rectangle::new(
+            rect.x + PADDING, rect.y + 2. * PADDING + FONT_SIZE as f32,
+            rect.width - 2. * PADDING, rect.height - 5. * PADDING - 2. * FONT_SIZE as f32 - SCRUBBER_HEIGHT,
+        );
+        let width = chart.width / BINS as f32;
+        for (bin, &count) in counts.iter().enumerate() {
+            let height = chart.height * count / top;
+            let bar = Rectangle::new(chart.x + bin as f32 * width + 1., chart.y + chart.height - height, width - 2., height);
+            draw.draw_rectangle_rec(bar, theme.accent);
+        }
+        draw.draw_line_v(Vector2::new(chart.x, chart.y + chart.height), Vector2::new(chart.x + chart.width, chart.y + chart.height), theme.panel_border);
+        let axis_y = (chart.y + chart.height) as i32 + 2;
+        fonts.draw_text(draw, "0", chart.x as i32, axis_y, FONT_SIZE, theme.muted_text);
+        let max = format!("{}+", spread.max);
+        fonts.draw_text(draw, &max, (chart.x + chart.width) as i32 - fonts.measure(&max, FONT_SIZE), axis_y, FONT_SIZE, theme.muted_text);
+
+        let button = Self::button(rect);
+        draw.draw_rectangle_lines_ex(button, 1, theme.panel_border);
+        let label = if self.playing { "pause" } else { "play" };
+        let label_x = button.x as i32 + (button.width as i32 - fonts.measure(label, FONT_SIZE)) / 2;
+        fonts.draw_text(draw, label, label_x, button.y as i32 + 1, FONT_SIZE, theme.text);
+        let scrubber = Self::scrubber(rect);
+        draw.draw_rectangle_rec(Rectangle { y: scrubber.y + scrubber.height / 2. - 1., height: 2., ..scrubber }, theme.muted_text);
+        let t = if last > first { (tick.unwrap_or(last) - first) as f32 / (last - first) as f32 } else { 1. };
+        draw.draw_circle_v(Vector2::new(scrubber.x + t * scrubber.width, scrubber.y + scrubber.height / 2.), scrubber.height / 2., theme.accent);
+    }
+}
+
+impl Default for HistogramView {
+    fn default() -> Self { Self::new() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_histogram() {
+        let speed = &TRAITS[0];
+        assert_eq!((speed.bin(0.), speed.bin(59.), speed.bin(500.)), (0, 4, BINS - 1));
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        sim.insert_blob(BlobConfig { speed: 5., ..BlobConfig::new(Vector2::new(50., 50.)) });
+        sim.insert_blob(BlobConfig { speed: 115., ..BlobConfig::new(Vector2::new(150., 50.)) });
+        for _ in 0..Simulation::STATS_PERIOD + 1 {
+            sim.step();
+        }
+        let counts = counts(sim.stats(), speed, sim.clock().tick());
+        assert_eq!((counts[0], counts[BINS - 1], counts.iter().sum::<f32>()), (1., 1., 2.));
+
+        //  playing reaches the latest tick and then follows the world
+        let mut view = HistogramView::new();
+        let rect = Rectangle::new(0., 0., 320., 160.);
+        view.update(&Frame::idle(0., Vector2::zero()), sim.stats(), rect);
+        assert_eq!(view.tick(sim.stats()), Some(0));
+        view.update(&Frame::idle(PLAY_SECONDS, Vector2::zero()), sim.stats(), rect);
+        assert_eq!((view.playhead, view.tick(sim.stats())), (None, Some(Simulation::STATS_PERIOD)));
+        assert!(view.next().unwrap().next().unwrap().next().unwrap().next().is_none());
+    }
+}
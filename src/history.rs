@@ -0,0 +1,227 @@
+//! The events of a world, the latest of them kept in a ring buffer
+//! and all of them optionally written through to a file.
+//!
+//! A world keeps its last `Simulation::EVENT_LOG_LENGTH` events
+//! unless told to keep more, with `--event-history <count>`, which
+//! can be millions: the oldest are dropped as new ones come. Events
+//! are kept in order of their tick, so a query for a span of ticks
+//! finds its first and last by bisection before looking at any, and
+//! may ask for events of one kind, or involving one blob, region or
+//! species.
+//!
+//! With `--event-spill <path>` every event is also written to a
+//! file as it happens, in the format of the narrator, so the whole
+//! history of a long run is kept while memory holds the recent part.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::history::{Entity, Kind, Query};
+//! let kills = sim.history().query(Query { kind: Some(Kind::Killed), ..Query::ticks(600..1200) }).count();
+//! let of_ann: Vec<_> = sim.history().query(Query { entity: Some(Entity::Named("Ann")), ..Query::all() }).collect();
+//! sim.history_mut().spill_to("events.txt")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fs,
+    io::{self, BufWriter},
+    mem,
+    ops::Range,
+};
+
+use crate::{
+    keyed_set::Key,
+    narrator,
+    simulation::{Blob, Event},
+};
+
+/// What an event is, without what it is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Born,
+    Killed,
+    Starved,
+    Quarantined,
+    Alert,
+    Extinct,
+    Weather,
+    Record,
+}
+
+impl Kind {
+    pub fn of(event: &Event) -> Self {
+        match event {
+            Event::Born(_) => Self::Born,
+            Event::Killed { .. } => Self::Killed,
+            Event::Starved(_) => Self::Starved,
+            Event::Quarantined { .. } => Self::Quarantined,
+            Event::Alert { .. } => Self::Alert,
+            Event::Extinct(_) => Self::Extinct,
+            Event::Weather(_) => Self::Weather,
+            Event::Record(_) => Self::Record,
+        }
+    }
+}
+
+/// What an event may be about: a blob born, by its key, or a blob,
+/// region or species by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity<'a> {
+    Blob(Key<Blob>),
+    Named(&'a str),
+}
+
+impl Entity<'_> {
+    fn in_event(self, event: &Event) -> bool {
+        let named = |name: &Option<String>| matches!(self, Entity::Named(wanted) if name.as_deref() == Some(wanted));
+        match event {
+            Event::Born(key) => self == Entity::Blob(*key),
+            Event::Killed { victim, killer } => named(victim) || named(killer),
+            Event::Starved(blob) | Event::Quarantined { blob, .. } => named(blob),
+            Event::Alert { region, .. } => self == Entity::Named(region),
+            Event::Extinct(extinction) => self == Entity::Named(&extinction.species),
+            Event::Weather(_) => false,
+            Event::Record(record) => record.holder.as_deref().is_some_and(|holder| self == Entity::Named(holder)),
+        }
+    }
+}
+
+/// Which events to find: those in a span of ticks, and of a kind and
+/// about an entity if they are given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query<'a> {
+    pub ticks: Range<u64>,
+    pub kind: Option<Kind>,
+    pub entity: Option<Entity<'a>>,
+}
+
+impl Query<'_> {
+    /// Every event in a span of ticks.
+    pub fn ticks(ticks: Range<u64>) -> Self {
+        Self { ticks, kind: None, entity: None }
+    }
+
+    /// Every event.
+    pub fn all() -> Self {
+        Self::ticks(0..u64::MAX)
+    }
+
+    fn matches(&self, event: &Event) -> bool {
+        self.kind.is_none_or(|kind| kind == Kind::of(event)) && self.entity.is_none_or(|entity| entity.in_event(event))
+    }
+}
+
+/// The latest events of a world, see the module.
+pub struct History {
+    events: VecDeque<(u64, Event)>,
+    capacity: usize,
+    spill: Option<BufWriter<fs::File>>,
+    /// Why the last write to the spill file failed, after which it was
+    /// given up on.
+    spill_error: Option<String>,
+}
+
+impl Clone for History {
+    /// The kept events, but not the file, which has one writer.
+    fn clone(&self) -> Self {
+        Self { events: self.events.clone(), capacity: self.capacity, spill: None, spill_error: None }
+    }
+}
+
+impl History {
+    pub fn new(capacity: usize) -> Self {
+        Self { events: VecDeque::new(), capacity: capacity.max(1), spill: None, spill_error: None }
+    }
+
+    /// Write every event from now on to a new file.
+    pub fn spill_to(&mut self, path: &str) -> io::Result<()> {
+        self.spill = Some(BufWriter::new(fs::File::create(path)?));
+        self.spill_error = None;
+        Ok(())
+    }
+
+    /// Why writing to the spill file stopped, if it did, once.
+    pub fn take_spill_error(&mut self) -> Option<String> {
+        self.spill_error.take()
+    }
+
+    pub fn push(&mut self, tick: u64, event: Event) {
+        if let Some(spill) = &mut self.spill {
+            if let Err(e) = narrator::write_event(spill, tick, &event) {
+                self.spill = None;
+                self.spill_error = Some(e.to_string());
+            }
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back((tick, event));
+    }
+
+    /// The kept events that happened at or after a tick, oldest first.
+    pub fn since(&self, tick: u64) -> impl Iterator<Item=&(u64, Event)> {
+        self.events.range(self.events.partition_point(|&(t, _)| t < tick)..)
+    }
+
+    /// The kept events a query asks for, oldest first.
+    pub fn query<'a>(&'a self, query: Query<'a>) -> impl Iterator<Item=&'a (u64, Event)> + 'a {
+        let start = self.events.partition_point(|&(t, _)| t < query.ticks.start);
+        let end = self.events.partition_point(|&(t, _)| t < query.ticks.end).max(start);
+        self.events.range(start..end).filter(move |(_, event)| query.matches(event))
+    }
+
+    /// About how many bytes the kept events take, see `memory`.
+    pub fn bytes(&self) -> usize {
+        let text = |text: &Option<String>| text.as_ref().map_or(0, String::capacity);
+        self.events.capacity() * mem::size_of::<(u64, Event)>() + self.events.iter().map(|(_, event)| match event {
+            Event::Born(_) | Event::Weather(_) => 0,
+            Event::Killed { victim, killer } => text(victim) + text(killer),
+            Event::Starved(blob) => text(blob),
+            Event::Quarantined { blob, problem, .. } => text(blob) + problem.capacity(),
+            Event::Alert { region, .. } => region.capacity(),
+            Event::Extinct(extinction) => extinction.species.capacity(),
+            Event::Record(record) => text(&record.holder),
+        }).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history() {
+        let name = |name: &str| Some(name.to_string());
+        let mut history = History::new(4);
+        history.push(1, Event::Born(Key::from_index(7)));
+        history.push(2, Event::Starved(name("Ann")));
+        history.push(2, Event::Killed { victim: name("Bob"), killer: name("Ann") });
+        history.push(5, Event::Starved(name("Cid")));
+        history.push(9, Event::Born(Key::from_index(8)));
+        //  the oldest was dropped for the latest
+        assert_eq!(history.since(0).map(|&(tick, _)| tick).collect::<Vec<_>>(), [2, 2, 5, 9]);
+
+        let ticks = |query: Query| history.query(query).map(|&(tick, _)| tick).collect::<Vec<_>>();
+        assert_eq!(ticks(Query::ticks(2..6)), [2, 2, 5]);
+        assert_eq!(ticks(Query::ticks(3..5)), [] as [u64; 0]);
+        assert_eq!(ticks(Query { kind: Some(Kind::Starved), ..Query::all() }), [2, 5]);
+        assert_eq!(ticks(Query { entity: Some(Entity::Named("Ann")), ..Query::all() }), [2, 2]);
+        assert_eq!(ticks(Query { entity: Some(Entity::Blob(Key::from_index(8))), ..Query::ticks(0..9) }), [] as [u64; 0]);
+
+        //  a spill file has every event, kept or not
+        let path = std::env::temp_dir().join(format!("blobs-history-{}", std::process::id()));
+        let mut history = History::new(1);
+        history.spill_to(path.to_str().unwrap()).unwrap();
+        history.push(3, Event::Born(Key::from_index(1)));
+        history.push(4, Event::Starved(name("Dan")));
+        assert_eq!(history.clone().since(0).count(), 1);
+        drop(history);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "born tick=3 key=1\nstarved tick=4 blob=\"Dan\"\n");
+        fs::remove_file(&path).unwrap();
+    }
+}
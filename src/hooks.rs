@@ -0,0 +1,126 @@
+//! Functions embedders run around the ticks of a simulation.
+//!
+//! A simulation runs its pre-tick hooks before each tick, its
+//! post-tick hooks after it, and its removal hooks whenever a blob or
+//! food is removed, during the tick for those eaten, killed, spoiled
+//! or starved. Hooks of a kind run from the highest priority to the
+//! lowest, those of the same priority in the order they were
+//! registered. A hook registered while hooks of its kind run first
+//! runs the next time, and one unregistered then does not run again.
+//! Forks start without hooks.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! let id = sim.on_post_tick(0, Box::new(|sim| println!("{} blobs", sim.blobs().count())));
+//! sim.step();
+//! sim.unregister_hook(id);
+//! ```
+
+use raylib::prelude::Vector2;
+
+use crate::{
+    keyed_set::Key,
+    simulation::{Blob, CircleObject, Food, Simulation},
+};
+
+/// A hook run before or after a tick.
+pub type TickHook = Box<dyn FnMut(&mut Simulation) + Send>;
+/// A hook run when an entity is removed.
+pub type RemovalHook = Box<dyn FnMut(&mut Simulation, &Removed) + Send>;
+
+/// Names a registered hook, to unregister it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HookId(u64);
+
+/// An entity just removed from a simulation.
+#[derive(Debug, Clone)]
+pub enum Removed {
+    Blob(Key<Blob>, Box<Blob>),
+    Food(Key<Food>, Food),
+}
+
+impl Removed {
+    /// What it was in the simulation, with its key there.
+    pub fn object(&self) -> CircleObject {
+        match self {
+            Self::Blob(key, _) => CircleObject::Blob(*key),
+            Self::Food(key, _) => CircleObject::Food(*key),
+        }
+    }
+
+    /// Where it was when it was removed.
+    pub fn pos(&self) -> Vector2 {
+        match self {
+            Self::Blob(_, blob) => blob.pos(),
+            Self::Food(_, food) => food.pos(),
+        }
+    }
+}
+
+/// Hooks of a kind, by priority.
+pub struct Hooks<F> {
+    //  from the highest priority, taken out while running
+    entries: Vec<(HookId, i32, Option<F>)>,
+}
+
+impl<F> Default for Hooks<F> {
+    fn default() -> Self { Self { entries: vec![] } }
+}
+
+impl<F> Hooks<F> {
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    pub fn insert(&mut self, id: HookId, priority: i32, hook: F) {
+        let index = self.entries.iter().position(|&(_, p, _)| p < priority).unwrap_or(self.entries.len());
+        self.entries.insert(index, (id, priority, Some(hook)));
+    }
+
+    pub fn remove(&mut self, id: HookId) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|&(i, _, _)| i != id);
+        self.entries.len() < len
+    }
+
+    /// The hooks in the order they run.
+    pub fn ids(&self) -> Vec<HookId> {
+        self.entries.iter().map(|&(id, _, _)| id).collect()
+    }
+
+    /// Take a hook out to run it, unless it was unregistered.
+    pub fn take(&mut self, id: HookId) -> Option<F> {
+        self.entries.iter_mut().find(|(i, _, _)| *i == id)?.2.take()
+    }
+
+    /// Put back a hook taken out, unless it was unregistered since.
+    pub fn put_back(&mut self, id: HookId, hook: F) {
+        if let Some(entry) = self.entries.iter_mut().find(|(i, _, _)| *i == id) {
+            entry.2 = Some(hook);
+        }
+    }
+}
+
+/// The hooks of a simulation.
+#[derive(Default)]
+pub struct SimulationHooks {
+    pub pre_tick: Hooks<TickHook>,
+    pub post_tick: Hooks<TickHook>,
+    pub removed: Hooks<RemovalHook>,
+    next: u64,
+}
+
+impl SimulationHooks {
+    /// An id no hook of the simulation had.
+    pub fn next_id(&mut self) -> HookId {
+        self.next += 1;
+        HookId(self.next)
+    }
+
+    /// Unregister a hook, returning whether it was registered.
+    pub fn remove(&mut self, id: HookId) -> bool {
+        self.pre_tick.remove(id) || self.post_tick.remove(id) || self.removed.remove(id)
+    }
+}
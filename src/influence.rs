@@ -0,0 +1,123 @@
+//! Soft tools that push, pull or calm the blobs around the cursor,
+//! most near it and less farther out, like a soft brush.
+//!
+//! Each tool puts a `physics::Field` in the world while the button is
+//! held, and the simulation applies it to the velocity of every blob
+//! in its disc at each tick, falling off with the square of the
+//! distance to its edge:
+//!
+//! * Repel, `7`, accelerates blobs away from the cursor
+//! * Attract, `8`, accelerates them toward it
+//! * Calm, `9`, damps their velocities
+//!
+//! A field lasts `TICKS` ticks after it was last put, so the tools are
+//! felt as long as they are held and a little after, and one held in
+//! place replaces itself instead of adding up. The radius is a number
+//! of screen pixels, and reaches farther the farther the view is
+//! zoomed out. Tools are sent to a lockstep peer as the commands
+//! `repel <x> <y> <radius>`, `attract <x> <y> <radius>` and
+//! `calm <x> <y> <radius>`, and fields are saved with the world.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::influence::{self, Influence};
+//! sim.add_field(Influence::Repel.field(Vector2::new(200., 150.), 80.), influence::TICKS);
+//! sim.step();
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{physics::{Field, Force}, shapes, simulation::Simulation, theme::Theme};
+
+/// How fast repelling and attracting change a velocity at the center,
+/// in units per second each second.
+pub const ACCELERATION: f32 = 400.;
+/// How fast calming damps a velocity at the center.
+pub const CALMING: f32 = 8.;
+/// For how many ticks a field lasts after it was put.
+pub const TICKS: u64 = 6;
+
+/// A soft tool, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Influence {
+    Repel,
+    Attract,
+    Calm,
+}
+
+impl Influence {
+    pub const ALL: [Self; 3] = [Self::Repel, Self::Attract, Self::Calm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Repel => "repel",
+            Self::Attract => "attract",
+            Self::Calm => "calm",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|influence| influence.name() == name)
+    }
+
+    /// The field the tool puts around a point.
+    pub fn field(&self, center: Vector2, radius: f32) -> Field {
+        let force = match self {
+            Self::Repel => Force::Radial(ACCELERATION),
+            Self::Attract => Force::Radial(-ACCELERATION),
+            Self::Calm => Force::Damping(CALMING),
+        };
+        Field { center, radius, force }
+    }
+}
+
+/// Draw the fields of a world as faint discs.
+pub fn draw<D: RaylibDraw>(draw: &mut D, sim: &Simulation, theme: &Theme, zoom: f32) {
+    for field in sim.fields() {
+        let color = match field.force {
+            Force::Radial(_) => theme.accent,
+            Force::Damping(_) => theme.text,
+        };
+        shapes::draw_circle(draw, field.center, field.radius, zoom, color.fade(0.08));
+        shapes::draw_circle_outline(draw, field.center, field.radius, 1., zoom, color.fade(0.4));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{math::FloatMode, simulation::{BlobConfig, SimulationConfig}};
+
+    #[test]
+    fn test_influence() {
+        let (center, near, far) = (Vector2::new(100., 100.), Vector2::new(110., 100.), Vector2::new(160., 100.));
+        let repel = Influence::Repel.field(center, 50.);
+        let pushed = |pos| repel.apply(pos, Vector2::zero(), 0.1, FloatMode::Native);
+        assert!(pushed(near).x > pushed(Vector2::new(130., 100.)).x && pushed(near).x > 0.);
+        assert_eq!(pushed(far), Vector2::zero());
+        assert!(Influence::Attract.field(center, 50.).apply(near, Vector2::zero(), 0.1, FloatMode::Native).x < 0.);
+        let calmed = Influence::Calm.field(center, 50.).apply(near, Vector2::new(0., 30.), 0.1, FloatMode::Native);
+        assert!(calmed.y < 30. && calmed.y > 0.);
+
+        //  a field lasts its ticks, and put again replaces itself
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        sim.add_field(repel, TICKS);
+        sim.add_field(Influence::Repel.field(Vector2::new(200., 200.), 50.), TICKS);
+        sim.add_field(Influence::Calm.field(center, 50.), TICKS);
+        assert_eq!(sim.fields().count(), 2);
+        for _ in 0..TICKS {
+            sim.step();
+        }
+        assert_eq!(sim.fields().count(), 0);
+
+        let blob = sim.insert_blob(BlobConfig::new(Vector2::new(230., 200.)));
+        sim.add_field(Influence::Repel.field(Vector2::new(200., 200.), 50.), TICKS);
+        sim.step();
+        assert!(sim.get_blob(blob).unwrap().velocity().x > 0.);
+        assert!(Influence::from_name("calm") == Some(Influence::Calm) && Influence::from_name("push").is_none());
+    }
+}
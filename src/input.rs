@@ -0,0 +1,313 @@
+//! Input as plain data, so it can be recorded and played back.
+//!
+//! Every frame the mouse and the keys the program uses are read
+//! into a `Frame`, and everything that reacts to input reads the
+//! frame rather than the window. Frames can be written to a file
+//! as they happen and read back to replay a session against a
+//! world with the same seed, which is also how interactions are
+//! tested without a window.
+//!
+//! A recording starts with a `seed=` line, followed by a line for
+//! each frame, a line for each file dropped on the window in it and
+//! one for any text typed in it. Frames in which the window is not
+//! focused, or is minimized, end in `focused=false`. A recording ends
+//! with the metadata of the run, see `runs`:
+//!
+//! ```text
+//! seed=1234
+//! frame time=2.5 mouse=300,200 wheel=0 button=pressed down=KEY_LEFT_SHIFT pressed=
+//! drop /home/ada/champion.blob
+//! type more food
+//! run time=1760000000 seed=1234 config=9c1e4f0a5b2d3e77 version=0.1.0+fa634ec notes=more food
+//! ```
+//!
+//! # Example
+//!
+//! ```ignore
+//! input::write_header(&mut out, seed)?;
+//! //  every frame
+//! let frame = Frame::capture(&handle);
+//! frame.write(&mut out)?;
+//! ```
+
+use std::io::{self, BufRead, Write};
+
+use raylib::prelude::*;
+
+use crate::runs::Metadata;
+
+/// Every key the program reacts to, which are the ones recorded.
+pub const KEYS: [KeyboardKey; 67] = [
+    KeyboardKey::KEY_F, KeyboardKey::KEY_P, KeyboardKey::KEY_EQUAL, KeyboardKey::KEY_MINUS,
+    KeyboardKey::KEY_M, KeyboardKey::KEY_G, KeyboardKey::KEY_T, KeyboardKey::KEY_U,
+    KeyboardKey::KEY_S, KeyboardKey::KEY_C, KeyboardKey::KEY_B, KeyboardKey::KEY_V,
+    KeyboardKey::KEY_L, KeyboardKey::KEY_E, KeyboardKey::KEY_I, KeyboardKey::KEY_H, KeyboardKey::KEY_W, KeyboardKey::KEY_O,
+    KeyboardKey::KEY_N, KeyboardKey::KEY_R, KeyboardKey::KEY_A, KeyboardKey::KEY_K, KeyboardKey::KEY_J, KeyboardKey::KEY_X, KeyboardKey::KEY_Y, KeyboardKey::KEY_D, KeyboardKey::KEY_Q, KeyboardKey::KEY_BACKSPACE,
+    KeyboardKey::KEY_SPACE,
+    KeyboardKey::KEY_UP, KeyboardKey::KEY_DOWN, KeyboardKey::KEY_LEFT, KeyboardKey::KEY_RIGHT, KeyboardKey::KEY_ENTER, KeyboardKey::KEY_Z,
+    KeyboardKey::KEY_COMMA, KeyboardKey::KEY_PERIOD,
+    KeyboardKey::KEY_LEFT_BRACKET, KeyboardKey::KEY_RIGHT_BRACKET,
+    KeyboardKey::KEY_SEMICOLON, KeyboardKey::KEY_APOSTROPHE,
+    KeyboardKey::KEY_F1, KeyboardKey::KEY_F2, KeyboardKey::KEY_F3, KeyboardKey::KEY_F4,
+    KeyboardKey::KEY_F5, KeyboardKey::KEY_F6, KeyboardKey::KEY_F7, KeyboardKey::KEY_F8, KeyboardKey::KEY_F9,
+    KeyboardKey::KEY_F10, KeyboardKey::KEY_F11,
+    KeyboardKey::KEY_ONE, KeyboardKey::KEY_TWO, KeyboardKey::KEY_THREE, KeyboardKey::KEY_FOUR,
+    KeyboardKey::KEY_FIVE, KeyboardKey::KEY_SIX, KeyboardKey::KEY_SEVEN, KeyboardKey::KEY_EIGHT, KeyboardKey::KEY_NINE,
+    KeyboardKey::KEY_LEFT_SHIFT, KeyboardKey::KEY_RIGHT_SHIFT,
+    KeyboardKey::KEY_LEFT_CONTROL, KeyboardKey::KEY_RIGHT_CONTROL,
+    KeyboardKey::KEY_LEFT_ALT, KeyboardKey::KEY_RIGHT_ALT,
+];
+
+/// The state of the left mouse button in a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Button {
+    Up,
+    /// Held down since an earlier frame.
+    Down,
+    /// Pressed in this frame.
+    Pressed,
+}
+
+/// The input of a single frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// Seconds since the window opened.
+    pub time: f64,
+    pub mouse: Vector2,
+    pub wheel: f32,
+    pub button: Button,
+    /// Paths of the files dropped on the window.
+    pub dropped: Vec<String>,
+    /// Text typed, for text boxes.
+    pub typed: String,
+    /// Whether the window is focused and not minimized.
+    pub focused: bool,
+    //  bits of the keys held and pressed, by their index in `KEYS`
+    down: u128,
+    pressed: u128,
+}
+
+impl Frame {
+    /// A frame where nothing is touched and the mouse rests at a position.
+    pub fn idle(time: f64, mouse: Vector2) -> Self {
+        Self { time, mouse, wheel: 0., button: Button::Up, dropped: vec![], typed: String::new(), focused: true, down: 0, pressed: 0 }
+    }
+
+    /// Read this frame's input from the window.
+    pub fn capture(handle: &RaylibHandle) -> Self {
+        let button = if handle.is_mouse_button_pressed(MouseButton::MOUSE_LEFT_BUTTON) {
+            Button::Pressed
+        } else if handle.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
+            Button::Down
+        } else {
+            Button::Up
+        };
+        let mut ret = Self { button, wheel: handle.get_mouse_wheel_move(), ..Self::idle(handle.get_time(), handle.get_mouse_position()) };
+        if handle.is_file_dropped() {
+            ret.dropped = handle.get_dropped_files();
+        }
+        ret.focused = handle.is_window_focused() && !handle.is_window_minimized();
+        for &key in &KEYS {
+            ret.set_key(key, handle.is_key_down(key), handle.is_key_pressed(key));
+        }
+        //  raylib queues the characters typed, with no safe way to read them
+        loop {
+            let c = unsafe { raylib::ffi::GetCharPressed() };
+            match std::char::from_u32(c as u32) {
+                Some(c) if c != '\0' => ret.typed.push(c),
+                _ => break,
+            }
+        }
+        ret
+    }
+
+    /// Forget the keys pressed in this frame, once a text box has taken them.
+    pub fn clear_keys(&mut self) {
+        self.pressed = 0;
+    }
+
+    /// Set whether a key is held, and whether it was pressed in this frame.
+    pub fn set_key(&mut self, key: KeyboardKey, down: bool, pressed: bool) {
+        let bit = 1u128 << index(key);
+        self.down = if down || pressed { self.down | bit } else { self.down & !bit };
+        self.pressed = if pressed { self.pressed | bit } else { self.pressed & !bit };
+    }
+
+    pub fn is_key_down(&self, key: KeyboardKey) -> bool {
+        self.down & 1u128 << index(key) != 0
+    }
+
+    pub fn is_key_pressed(&self, key: KeyboardKey) -> bool {
+        self.pressed & 1u128 << index(key) != 0
+    }
+
+    pub fn is_button_down(&self) -> bool { self.button != Button::Up }
+
+    pub fn is_any_key_pressed(&self) -> bool { self.pressed != 0 }
+
+    pub fn is_shift_down(&self) -> bool {
+        self.is_key_down(KeyboardKey::KEY_LEFT_SHIFT) || self.is_key_down(KeyboardKey::KEY_RIGHT_SHIFT)
+    }
+
+    pub fn is_ctrl_down(&self) -> bool {
+        self.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || self.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL)
+    }
+
+    pub fn is_alt_down(&self) -> bool {
+        self.is_key_down(KeyboardKey::KEY_LEFT_ALT) || self.is_key_down(KeyboardKey::KEY_RIGHT_ALT)
+    }
+
+    /// Write the frame as lines of a recording.
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let keys = |bits: u128| KEYS.iter().enumerate()
+            .filter(|(i, _)| bits & 1u128 << i != 0)
+            .map(|(_, key)| format!("{:?}", key))
+            .collect::<Vec<_>>()
+            .join(",");
+        let button = match self.button {
+            Button::Up => "up",
+            Button::Down => "down",
+            Button::Pressed => "pressed",
+        };
+        write!(out, "frame time={} mouse={},{} wheel={} button={} down={} pressed={}",
+            self.time, self.mouse.x, self.mouse.y, self.wheel, button, keys(self.down), keys(self.pressed),
+        )?;
+        //  left out while focused, as recordings made before it were
+        if !self.focused {
+            write!(out, " focused=false")?;
+        }
+        writeln!(out)?;
+        for path in &self.dropped {
+            writeln!(out, "drop {}", path)?;
+        }
+        if !self.typed.is_empty() {
+            writeln!(out, "type {}", self.typed)?;
+        }
+        Ok(())
+    }
+
+    /// Parse the first line written by `write`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut fields = line.split(' ');
+        if fields.next() != Some("frame") {
+            return Err(format!("expected a frame, got `{}`", line));
+        }
+        let mut ret = Self::idle(0., Vector2::zero());
+        for field in fields {
+            let (name, value) = field.split_once('=').ok_or_else(|| format!("invalid field `{}`", field))?;
+            let number = |value: &str| value.parse::<f32>().map_err(|_| format!("invalid number `{}`", value));
+            match name {
+                "time" => ret.time = value.parse().map_err(|_| format!("invalid time `{}`", value))?,
+                "mouse" => {
+                    let (x, y) = value.split_once(',').ok_or_else(|| format!("invalid position `{}`", value))?;
+                    ret.mouse = Vector2::new(number(x)?, number(y)?);
+                },
+                "wheel" => ret.wheel = number(value)?,
+                "focused" => ret.focused = value.parse().map_err(|_| format!("invalid flag `{}`", value))?,
+                "button" => ret.button = match value {
+                    "up" => Button::Up,
+                    "down" => Button::Down,
+                    "pressed" => Button::Pressed,
+                    _ => return Err(format!("invalid button state `{}`", value)),
+                },
+                "down" | "pressed" => {
+                    for key_name in value.split(',').filter(|key_name| !key_name.is_empty()) {
+                        let key = *KEYS.iter()
+                            .find(|key| format!("{:?}", key) == key_name)
+                            .ok_or_else(|| format!("unknown key `{}`", key_name))?;
+                        let bits = if name == "down" { &mut ret.down } else { &mut ret.pressed };
+                        *bits |= 1u128 << index(key);
+                    }
+                },
+                //  fields added later are skipped
+                _ => (),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+/// The position of a key in `KEYS`.
+fn index(key: KeyboardKey) -> usize {
+    KEYS.iter().position(|&k| k == key).unwrap_or_else(|| panic!("{:?} is not in input::KEYS", key))
+}
+
+/// Start a recording of a world with a seed.
+pub fn write_header<W: Write>(out: &mut W, seed: u64) -> io::Result<()> {
+    writeln!(out, "seed={}", seed)
+}
+
+/// A recording read back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recording {
+    /// The seed of the world it was recorded in.
+    pub seed: u64,
+    pub frames: Vec<Frame>,
+    /// What is known about the run, unless it ended before it was written.
+    pub metadata: Option<Metadata>,
+}
+
+/// End a recording with the metadata of its run.
+pub fn write_metadata<W: Write>(out: &mut W, metadata: &Metadata) -> io::Result<()> {
+    writeln!(out, "{}", metadata.to_line())
+}
+
+/// Read a recording.
+pub fn read<R: BufRead>(r: R) -> io::Result<Recording> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut lines = r.lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    let seed = header.strip_prefix("seed=")
+        .and_then(|seed| seed.parse().ok())
+        .ok_or_else(|| invalid(format!("expected seed=<number>, got `{}`", header)))?;
+    let mut frames: Vec<Frame> = vec![];
+    let mut metadata = None;
+    for line in lines {
+        let line = line?;
+        if line.is_empty() { continue; }
+        if line.starts_with("run ") {
+            metadata = Some(Metadata::parse(&line).map_err(invalid)?);
+            continue;
+        }
+        let (drop, typed) = (line.strip_prefix("drop "), line.strip_prefix("type "));
+        match (frames.last_mut(), drop, typed) {
+            (Some(frame), Some(path), _) => frame.dropped.push(path.to_string()),
+            (Some(frame), _, Some(text)) => frame.typed.push_str(text),
+            (None, Some(_), _) | (None, _, Some(_)) => return Err(invalid(format!("`{}` before the first frame", line))),
+            _ => frames.push(Frame::parse(&line).map_err(invalid)?),
+        }
+    }
+    Ok(Recording { seed, frames, metadata })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let mut frame = Frame { button: Button::Pressed, wheel: -1., ..Frame::idle(2.5, Vector2::new(300., 200.5)) };
+        frame.set_key(KeyboardKey::KEY_LEFT_SHIFT, true, false);
+        frame.set_key(KeyboardKey::KEY_ONE, true, true);
+        frame.dropped.push("/tmp/a b.blob".to_string());
+        frame.typed.push_str("no food ");
+        assert!(frame.is_key_down(KeyboardKey::KEY_ONE) && frame.is_key_pressed(KeyboardKey::KEY_ONE));
+        assert!(!frame.is_key_pressed(KeyboardKey::KEY_LEFT_SHIFT));
+
+        let mut out = vec![];
+        write_header(&mut out, 42).unwrap();
+        frame.write(&mut out).unwrap();
+        let away = Frame { focused: false, ..Frame::idle(3., Vector2::zero()) };
+        away.write(&mut out).unwrap();
+        let metadata = Metadata { time: 1, seed: 42, config_hash: 3, version: "1.0".to_string(), notes: "a b".to_string() };
+        write_metadata(&mut out, &metadata).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert_eq!(text.lines().nth(1), Some("frame time=2.5 mouse=300,200.5 wheel=-1 button=pressed down=KEY_ONE,KEY_LEFT_SHIFT pressed=KEY_ONE"));
+        assert_eq!(text.lines().nth(2), Some("drop /tmp/a b.blob"));
+        assert_eq!(text.lines().nth(3), Some("type no food "));
+        assert!(text.lines().nth(4).unwrap().ends_with(" focused=false"));
+        let recording = Recording { seed: 42, frames: vec![frame, away], metadata: Some(metadata) };
+        assert_eq!(read(&out[..]).unwrap(), recording);
+        assert!(Frame::parse("frame down=KEY_F12").is_err());
+    }
+}
@@ -0,0 +1,367 @@
+//! A close look at a single blob.
+//!
+//! The inspector shows a large portrait of the blob, with its field
+//! of view, heading and favorite color, badges for its current
+//! state, its genome as bars and a radar chart of its `profile`
+//! over the mean of its species, so a blob can be followed as a
+//! character rather than a dot.
+//!
+//! While its parent lives, the parent's genes are ghosted behind the
+//! bars, and the genes that changed by more than `MUTATED` on the way
+//! down are highlighted with how much. Two selected blobs get a diff
+//! of their genomes side by side instead.
+//!
+//! # Example
+//!
+//! ```ignore
+//! if let Some(blob) = sim.get_blob(selected) {
+//!     let mean = inspector::mean_profile(sim.blobs().map(|(_, other)| other).filter(|other| other.species == blob.species));
+//!     let parent = blob.parent.and_then(|parent| sim.get_blob(parent));
+//!     inspector::draw(&mut draw, &theme, &fonts, blob, parent, mean.as_ref(), Vector2::new(10., 10.));
+//! }
+//! inspector::draw_diff(&mut draw, &theme, &fonts, [a, b], Vector2::new(300., 10.));
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    window::DrawingContext,
+    simulation::Blob,
+    theme::Theme,
+    fonts::Fonts,
+    shapes,
+    species::BrainKind,
+};
+
+/// A heritable trait of a blob and the range it usually lies in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gene {
+    pub name: &'static str,
+    pub value: f32,
+    /// The largest value blobs are usually born with.
+    pub max: f32,
+}
+
+impl Gene {
+    /// Where the value lies in the usual range, clamped to [0,1].
+    pub fn fraction(&self) -> f32 {
+        (self.value / self.max).clamp(0., 1.)
+    }
+
+    /// How much the gene differs from another of the same name, as a
+    /// part of the other's value, or of the usual range where it is 0.
+    pub fn change(&self, from: &Gene) -> f32 {
+        let base = if from.value.abs() > f32::EPSILON { from.value.abs() } else { self.max };
+        (self.value - from.value) / base
+    }
+}
+
+/// How much a gene changes from its parent's before it counts as
+/// mutated rather than jittered.
+pub const MUTATED: f32 = 0.05;
+
+/// The genes of a blob, with the ranges random blobs are drawn from.
+pub fn genome(blob: &Blob) -> Vec<Gene> {
+    let gene = |name, value, max| Gene { name, value, max };
+    vec![
+        gene("Radius", blob.radius(), 20.),
+        gene("Speed", blob.speed, 120.),
+        gene("Turning", blob.rotation_speed, 5.),
+        gene("Field of view", blob.pov, 180.),
+        gene("Sight", blob.sight_depth(), 170.),
+        gene("Attraction", blob.color_attraction, 1.),
+        gene("Repulsion", blob.color_repulsion, 1.),
+        gene("Max hunger", blob.max_hunger, 25.),
+        gene("Attack", blob.attack, 1.),
+        gene("Defence", blob.defence, 2.),
+        gene("Satiation", blob.hunger_reduction, 0.5),
+        gene("Digestion", blob.hunger_division, 1.),
+        gene("Swimming", blob.swimming, 1.),
+        gene("Hearing", blob.hearing, 1.),
+        gene("Aura", blob.aura_strength, 1.),
+    ]
+}
+
+/// The axes of the radar chart, see `profile`.
+pub const PROFILE: [&str; 5] = ["Speed", "Size", "Vision", "Metabolism", "Aggression"];
+/// About the greatest upkeep of a blob born with random genes.
+const MAX_UPKEEP: f32 = 2.5;
+
+/// What sets a blob apart at a glance, each from 0 to 1, in the order of `PROFILE`.
+pub fn profile(blob: &Blob) -> [f32; 5] {
+    let fraction = |value: f32, max: f32| (value / max).clamp(0., 1.);
+    [
+        fraction(blob.speed, 120.),
+        fraction(blob.radius(), 20.),
+        fraction(blob.sight_depth(), 170.),
+        fraction(blob.upkeep(), MAX_UPKEEP),
+        fraction(blob.attack, 1.),
+    ]
+}
+
+/// The mean profile of some blobs, or nothing for none.
+pub fn mean_profile<'a, I: IntoIterator<Item=&'a Blob>>(blobs: I) -> Option<[f32; 5]> {
+    let mut sum = [0.; 5];
+    let mut count = 0;
+    for blob in blobs {
+        for (total, value) in sum.iter_mut().zip(&profile(blob)) {
+            *total += value;
+        }
+        count += 1;
+    }
+    if count == 0 { return None; }
+    Some(sum.map(|total| total / count as f32))
+}
+
+const WIDTH: f32 = 280.;
+const PADDING: f32 = 10.;
+const PORTRAIT_SIZE: f32 = 140.;
+/// The radius of the blob in the portrait, whatever its real size.
+const BODY_RADIUS: f32 = 30.;
+const FONT_SIZE: i32 = 16;
+const SMALL_FONT_SIZE: i32 = 10;
+const BAR_HEIGHT: f32 = 14.;
+const RADAR_SIZE: f32 = 150.;
+
+/// Draw the inspector panel for a blob with its top left corner at a
+/// position, comparing it with its parent and the mean profile of its
+/// species if given.
+pub fn draw(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, blob: &Blob, parent: Option<&Blob>, mean: Option<&[f32; 5]>, pos: Vector2) {
+    let genome = genome(blob);
+    let inherited = parent.map(self::genome);
+    let orphan = blob.parent.is_some() && parent.is_none();
+    let chips = if blob.tags.is_empty() { 0 } else { SMALL_FONT_SIZE + 10 };
+    let height = 5. * PADDING + 2. * FONT_SIZE as f32 + chips as f32 + PORTRAIT_SIZE + genome.len() as f32 * BAR_HEIGHT + RADAR_SIZE
+        + if orphan { BAR_HEIGHT } else { 0. };
+    let rect = Rectangle::new(pos.x, pos.y, WIDTH, height);
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+
+    //  name and what it is
+    let (x, mut y) = ((pos.x + PADDING) as i32, (pos.y + PADDING) as i32);
+    fonts.draw_text(draw, blob.name.as_deref().unwrap_or("Unnamed"), x, y, FONT_SIZE, theme.text);
+    y += FONT_SIZE;
+    let description = format!("{}{}, {}{:.1}s old, generation {}",
+        blob.species.as_ref().map_or(String::new(), |species| format!("{} ", species)),
+        blob.diet.name(),
+        if blob.brain == BrainKind::ColorAttraction { String::new() } else { format!("{} brain, ", blob.brain.name().replace('_', " ")) },
+        blob.alive_time,
+        blob.generation,
+    );
+    fonts.draw_text(draw, &description, x, y, FONT_SIZE, theme.muted_text);
+    y += FONT_SIZE + PADDING as i32;
+
+    //  tags, each in its color
+    let mut chip_x = x;
+    for tag in &blob.tags {
+        let width = fonts.measure(&tag.text, SMALL_FONT_SIZE) + 8;
+        draw.draw_rectangle(chip_x, y, width, SMALL_FONT_SIZE + 6, tag.color);
+        fonts.draw_text(draw, &tag.text, chip_x + 4, y + 3, SMALL_FONT_SIZE, Color::BLACK);
+        chip_x += width + 4;
+    }
+    y += chips;
+
+    let portrait = Rectangle::new(pos.x + PADDING, y as f32, WIDTH - 2. * PADDING, PORTRAIT_SIZE);
+    draw_portrait(draw, theme, blob, portrait);
+    draw_status(draw, theme, fonts, blob, portrait);
+    y += PORTRAIT_SIZE as i32 + PADDING as i32;
+
+    //  genome, over the parent's where it is known
+    let label_width = 90.;
+    for (i, gene) in genome.iter().enumerate() {
+        let bar = Rectangle::new(pos.x + PADDING + label_width, y as f32 + 2., WIDTH - 2. * PADDING - label_width, BAR_HEIGHT - 4.);
+        let from = inherited.as_ref().map(|inherited| &inherited[i]);
+        let change = from.map(|from| gene.change(from)).filter(|change| change.abs() > MUTATED);
+        let color = if change.is_some() { theme.warning } else { theme.accent };
+        fonts.draw_text(draw, gene.name, x, y + 2, SMALL_FONT_SIZE, if change.is_some() { theme.warning } else { theme.text });
+        draw.draw_rectangle_rec(Rectangle { width: bar.width * gene.fraction(), ..bar }, color);
+        if let Some(from) = from {
+            let ghost = Rectangle { width: bar.width * from.fraction(), ..bar };
+            draw.draw_rectangle_rec(ghost, theme.text.fade(0.2));
+            draw.draw_line_v(Vector2::new(ghost.x + ghost.width, bar.y - 1.), Vector2::new(ghost.x + ghost.width, bar.y + bar.height + 1.), theme.text);
+        }
+        draw.draw_rectangle_lines_ex(bar, 1, theme.panel_border);
+        let value = match change {
+            Some(change) => format!("{:+.0}% {:.2}", change * 100., gene.value),
+            None => format!("{:.2}", gene.value),
+        };
+        fonts.draw_text(draw, &value, (bar.x + bar.width) as i32 - fonts.measure(&value, SMALL_FONT_SIZE) - 2, y + 2, SMALL_FONT_SIZE, theme.text);
+        y += BAR_HEIGHT as i32;
+    }
+    if orphan {
+        fonts.draw_text(draw, "Its parent is gone, so nothing is ghosted", x, y + 2, SMALL_FONT_SIZE, theme.muted_text);
+        y += BAR_HEIGHT as i32;
+    }
+    y += PADDING as i32;
+
+    let radar = Rectangle::new(pos.x + PADDING, y as f32, WIDTH - 2. * PADDING, RADAR_SIZE);
+    draw_radar(draw, theme, fonts, &profile(blob), mean, radar);
+}
+
+/// Draw the genomes of two blobs side by side, each gene as a bar to
+/// the left for the first and to the right for the second, with the
+/// genes that differ by more than `MUTATED` highlighted.
+pub fn draw_diff(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, blobs: [&Blob; 2], pos: Vector2) {
+    let genomes = blobs.map(genome);
+    let height = 3. * PADDING + FONT_SIZE as f32 + genomes[0].len() as f32 * BAR_HEIGHT;
+    let rect = Rectangle::new(pos.x, pos.y, WIDTH, height);
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+
+    let name = |blob: &Blob| blob.name.clone().unwrap_or_else(|| "Unnamed".to_string());
+    let (x, mut y) = ((pos.x + PADDING) as i32, (pos.y + PADDING) as i32);
+    fonts.draw_text(draw, &format!("{} vs {}", name(blobs[0]), name(blobs[1])), x, y, FONT_SIZE, theme.text);
+    y += FONT_SIZE + PADDING as i32;
+
+    let label_width = 90.;
+    //  bars grow out from the middle, the first blob's to the left
+    let middle = pos.x + PADDING + label_width + (WIDTH - 2. * PADDING - label_width) / 2.;
+    let half = (WIDTH - 2. * PADDING - label_width) / 2. - 1.;
+    for (a, b) in genomes[0].iter().zip(&genomes[1]) {
+        let differs = b.change(a).abs() > MUTATED;
+        let color = if differs { theme.warning } else { theme.accent };
+        fonts.draw_text(draw, a.name, x, y + 2, SMALL_FONT_SIZE, if differs { theme.warning } else { theme.text });
+        let (top, bar_height) = (y as f32 + 2., BAR_HEIGHT - 4.);
+        draw.draw_rectangle_rec(Rectangle::new(middle - 1. - half * a.fraction(), top, half * a.fraction(), bar_height), color);
+        draw.draw_rectangle_rec(Rectangle::new(middle + 1., top, half * b.fraction(), bar_height), color);
+        let (left, right) = (format!("{:.2}", a.value), format!("{:.2}", b.value));
+        let label_x = middle as i32 - half as i32;
+        fonts.draw_text(draw, &left, label_x, y + 2, SMALL_FONT_SIZE, theme.text);
+        fonts.draw_text(draw, &right, (middle + half) as i32 - fonts.measure(&right, SMALL_FONT_SIZE), y + 2, SMALL_FONT_SIZE, theme.text);
+        y += BAR_HEIGHT as i32;
+    }
+    draw.draw_line_v(Vector2::new(middle, pos.y + 2. * PADDING + FONT_SIZE as f32), Vector2::new(middle, y as f32), theme.panel_border);
+}
+
+/// Draw a radar chart of a profile over a mean one in a rectangle,
+/// with a legend in its top left corner.
+fn draw_radar(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, profile: &[f32; 5], mean: Option<&[f32; 5]>, rect: Rectangle) {
+    let center = Vector2::new(rect.x + rect.width / 2., rect.y + rect.height / 2.);
+    let radius = rect.height / 2. - SMALL_FONT_SIZE as f32 - 2.;
+    //  the first axis points up, the rest follow clockwise
+    let axis = |i: usize| {
+        let angle = (i as f32 / PROFILE.len() as f32 - 0.25) * std::f32::consts::TAU;
+        Vector2::new(angle.cos(), angle.sin())
+    };
+    let points = |values: &[f32; 5]| -> Vec<Vector2> {
+        values.iter().enumerate().map(|(i, &value)| center + axis(i) * radius * value).collect()
+    };
+    let outline = |draw: &mut DrawingContext, points: &[Vector2], thickness: f32, color: Color| {
+        for i in 0..points.len() {
+            draw.draw_line_ex(points[i], points[(i + 1) % points.len()], thickness, color);
+        }
+    };
+
+    //  rings at half and all of each range, and the axes with their names
+    for &ring in &[0.5, 1.] {
+        outline(draw, &points(&[ring; 5]), 1., theme.panel_border);
+    }
+    for (i, name) in PROFILE.iter().enumerate() {
+        let tip = center + axis(i) * radius;
+        draw.draw_line_v(center, tip, theme.panel_border);
+        let label = center + axis(i) * (radius + 4.);
+        let width = fonts.measure(name, SMALL_FONT_SIZE) as f32;
+        //  labels sit beside the tip, to the left of it on the left side
+        let x = if axis(i).x < -0.1 { label.x - width } else if axis(i).x > 0.1 { label.x } else { label.x - width / 2. };
+        let y = if axis(i).y < 0. { label.y - SMALL_FONT_SIZE as f32 } else { label.y };
+        fonts.draw_text(draw, name, x as i32, y as i32, SMALL_FONT_SIZE, theme.muted_text);
+    }
+
+    //  the blob filled, the mean as an outline over it
+    let blob = points(profile);
+    for i in 0..blob.len() {
+        draw.draw_triangle(center, blob[(i + 1) % blob.len()], blob[i], theme.accent.fade(0.35));
+    }
+    outline(draw, &blob, 2., theme.accent);
+    let (x, y) = (rect.x as i32, rect.y as i32);
+    fonts.draw_text(draw, "This blob", x, y, SMALL_FONT_SIZE, theme.accent);
+    if let Some(mean) = mean {
+        outline(draw, &points(mean), 1.5, theme.text);
+        fonts.draw_text(draw, "Species mean", x, y + SMALL_FONT_SIZE + 2, SMALL_FONT_SIZE, theme.text);
+    }
+}
+
+/// Draw the blob scaled up in the middle of a rectangle.
+fn draw_portrait(draw: &mut DrawingContext, theme: &Theme, blob: &Blob, rect: Rectangle) {
+    draw.draw_rectangle_rec(rect, theme.background);
+    let center = Vector2::new(rect.x + rect.width / 2., rect.y + rect.height / 2.);
+    let direction = if blob.direction().length_sqr() > 0. { blob.direction().normalized() } else { Vector2::new(0., 1.) };
+
+    //  field of view, reaching further for blobs that see further
+    let angle = direction.x.atan2(direction.y).to_degrees();
+    let sight = BODY_RADIUS + (blob.sight_depth() / 170.).min(1.) * (rect.height / 2. - BODY_RADIUS - 2.);
+    draw.draw_circle_sector(center, sight, (angle - blob.pov / 2.) as i32, (angle + blob.pov / 2.) as i32,
+        shapes::circle_segments(sight), theme.contrast(blob.favorite_color).fade(0.15));
+    shapes::draw_sector_outline(draw, center, sight, angle - blob.pov / 2., angle + blob.pov / 2., 1., theme.contrast(blob.favorite_color));
+
+    //  the body, faded as it starves, ringed by its favorite color
+    let fullness = 1. - blob.hunger / blob.max_hunger;
+    shapes::draw_circle(draw, center, BODY_RADIUS, 1., theme.contrast(blob.color).fade(fullness.max(0.2)));
+    shapes::draw_circle_outline(draw, center, BODY_RADIUS, 3., 1., theme.contrast(blob.favorite_color));
+
+    //  heading
+    let tip = center + direction * (BODY_RADIUS + 14.);
+    let side = Vector2::new(-direction.y, direction.x) * 5.;
+    draw.draw_line_ex(center + direction * BODY_RADIUS, tip, 2., theme.text);
+    draw.draw_triangle(tip + direction * 4., tip - side, tip + side, theme.text);
+}
+
+/// Draw badges for the state of the blob along the bottom of the portrait.
+fn draw_status(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, blob: &Blob, portrait: Rectangle) {
+    let mut badges = vec![];
+    if blob.diet.eats_blobs() {
+        badges.push(("Predator", theme.error));
+    }
+    if blob.hunger > 0.75 * blob.max_hunger {
+        badges.push(("Starving", theme.warning));
+    } else if blob.hunger < 0.25 * blob.max_hunger {
+        badges.push(("Fed", theme.ok));
+    }
+    if blob.velocity().length() > 1. {
+        badges.push(("Thrown", theme.accent));
+    }
+
+    let height = SMALL_FONT_SIZE + 6;
+    let mut x = portrait.x as i32 + 4;
+    let y = (portrait.y + portrait.height) as i32 - height - 4;
+    for (label, color) in badges {
+        let width = fonts.measure(label, SMALL_FONT_SIZE) + 8;
+        draw.draw_rectangle(x, y, width, height, color);
+        fonts.draw_text(draw, label, x + 4, y + 3, SMALL_FONT_SIZE, theme.background);
+        x += width + 4;
+    }
+
+    //  hunger, filling up as the blob starves
+    let bar = Rectangle::new(portrait.x + 4., portrait.y + 4., 60., 6.);
+    draw.draw_rectangle_rec(Rectangle { width: bar.width * (blob.hunger / blob.max_hunger).clamp(0., 1.), ..bar }, theme.warning);
+    draw.draw_rectangle_lines_ex(bar, 1, theme.panel_border);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, Simulation, SimulationConfig};
+
+    #[test]
+    fn test_profile() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(200., 200.)));
+        let slow = sim.insert_blob(BlobConfig { speed: 30., attack: 2., ..BlobConfig::new(Vector2::new(50., 50.)) });
+        let fast = sim.insert_blob(BlobConfig { speed: 90., attack: 0., ..BlobConfig::new(Vector2::new(150., 50.)) });
+        let (slow, fast) = (sim.get_blob(slow).unwrap(), sim.get_blob(fast).unwrap());
+        assert_eq!(profile(slow)[0], 0.25);
+        //  values past the usual range are drawn at its edge
+        assert_eq!(profile(slow)[4], 1.);
+        let mean = mean_profile(vec![slow, fast]).unwrap();
+        assert_eq!((mean[0], mean[4]), (0.5, 0.5));
+        assert_eq!(mean_profile(vec![]), None);
+    }
+
+    #[test]
+    fn test_mutations() {
+        let gene = |value| Gene { name: "Speed", value, max: 120. };
+        assert_eq!(gene(66.).change(&gene(60.)), 0.1);
+        assert!(gene(61.).change(&gene(60.)).abs() < MUTATED);
+        //  from nothing, the change is of the usual range
+        assert_eq!(gene(12.).change(&gene(0.)), 0.1);
+    }
+}
@@ -0,0 +1,285 @@
+//! Keyboard-only operation: a cursor that stands in for the mouse, and
+//! a palette to find and run every action by name.
+//!
+//! `Z` turns the keyboard cursor on and off. While it is on, it is
+//! where the mouse would be and everything the mouse does is done
+//! with keys, the tools, toolbar and panels included:
+//!
+//! * the arrow keys move it, `FAST` times as fast with shift
+//! * `Enter` clicks, and held drags like the left button
+//! * Ctrl and the arrows pan the camera
+//! * Alt and up or down zoom in and out around it
+//!
+//! Ctrl and `P` opens the command palette. Typing narrows the actions
+//! to those whose names have the typed letters in order, closest first,
+//! up and down choose one and `Enter` runs it as if its keys were
+//! pressed, so the palette does exactly what the keys do. Ctrl and `P`
+//! again closes it.
+//!
+//! Both read the keys of each frame and rewrite it, see `input`, so
+//! recorded input replays the same.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let pan = cursor.apply(&mut frame, delta_time, Vector2::new(800., 600.));
+//! camera.pan(pan);
+//! if let Some(action) = palette.update(&frame, &actions) {
+//!     keyboard::run(&action, &mut frame);
+//! }
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{input::{Button, Frame}, window::{InteractionMode, RenderLayer}};
+
+/// An action of the palette, run by pressing its key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+    pub name: String,
+    pub key: KeyboardKey,
+    pub shift: bool,
+    pub ctrl: bool,
+}
+
+impl Action {
+    fn new(name: &str, key: KeyboardKey, shift: bool) -> Self {
+        Self { name: name.to_string(), key, shift, ctrl: false }
+    }
+
+    /// The keys that run it, as shown in the palette.
+    pub fn keys(&self) -> String {
+        let key = format!("{:?}", self.key);
+        let key = key.trim_start_matches("KEY_");
+        format!("{}{}{}", if self.ctrl { "Ctrl+" } else { "" }, if self.shift { "Shift+" } else { "" }, key)
+    }
+}
+
+/// Every action of the palette.
+pub fn actions() -> Vec<Action> {
+    use KeyboardKey::*;
+    let mut ret: Vec<_> = [
+        ("Pause or resume", KEY_P, false),
+        ("Speed up", KEY_EQUAL, false),
+        ("Slow down", KEY_MINUS, false),
+        ("Fast-forward", KEY_F, false),
+        ("Halve the tick rate", KEY_COMMA, false),
+        ("Double the tick rate", KEY_PERIOD, false),
+        ("Fewer substeps", KEY_LEFT_BRACKET, false),
+        ("More substeps", KEY_RIGHT_BRACKET, false),
+        ("Brains decide more often", KEY_SEMICOLON, false),
+        ("Brains decide less often", KEY_APOSTROPHE, false),
+        ("Spawn a blob", KEY_SPACE, false),
+        ("Edit the notes", KEY_N, false),
+        ("Rename the selected blobs", KEY_N, true),
+        ("Bookmark this tick", KEY_B, false),
+        ("Tag the selected blobs", KEY_T, true),
+        ("Rename a species", KEY_S, true),
+        ("Override a species parameter", KEY_O, true),
+        ("Add a tour keyframe", KEY_J, false),
+        ("Play the tour", KEY_J, true),
+        ("Director camera", KEY_Q, false),
+        ("Show the mods", KEY_M, false),
+        ("Browse the content", KEY_M, true),
+        ("Show the graph", KEY_G, false),
+        ("Next trait histogram", KEY_G, true),
+        ("Next theme", KEY_T, false),
+        ("Show memory use", KEY_U, false),
+        ("Next overlay", KEY_O, false),
+        ("Tune parameters", KEY_K, false),
+        ("Edit the scenario", KEY_K, true),
+        ("Predict what comes next", KEY_X, false),
+        ("Predict without the selected blobs", KEY_X, true),
+        ("Next brain view", KEY_A, false),
+        ("Energy numbers", KEY_D, false),
+        ("Speech bubbles", KEY_D, true),
+        ("Next blob style", KEY_BACKSLASH, false),
+        ("Crowding tint", KEY_BACKSLASH, true),
+        ("Food web and energy flows", KEY_W, false),
+        ("Hall of fame", KEY_H, false),
+        ("Hall of records", KEY_H, true),
+        ("Browse past runs", KEY_R, false),
+        ("Export the selected blobs", KEY_E, false),
+        ("Export the selected blobs as a table", KEY_E, true),
+        ("Trace the selected blob", KEY_L, false),
+        ("Save the trace", KEY_L, true),
+        ("Select kin near the cursor", KEY_Y, false),
+        ("Import a blob", KEY_I, false),
+        ("Import blobs from CSV", KEY_I, true),
+        ("Copy the world code", KEY_F8, false),
+        ("Capture a fixture", KEY_F8, true),
+        ("Paste a world code", KEY_F9, false),
+        ("Save the world", KEY_F10, false),
+        ("Save the world as", KEY_F10, true),
+        ("Load the world", KEY_F11, false),
+        ("Load the world from", KEY_F11, true),
+        ("Sound", KEY_S, false),
+        ("Record a GIF", KEY_C, false),
+        ("Snapshot as SVG", KEY_C, true),
+        ("Vignette", KEY_V, false),
+        ("Lens", KEY_V, true),
+        ("Bloom", KEY_B, true),
+        ("Keyboard cursor", KEY_Z, false),
+    ].iter().map(|&(name, key, shift)| Action::new(name, key, shift)).collect();
+    ret.extend(InteractionMode::ALL.iter().map(|mode| Action::new(&format!("{} mode", mode.name()), mode.hotkey(), false)));
+    ret.extend(RenderLayer::ALL.iter().map(|layer| Action::new(&format!("{} layer", layer.name()), layer.toggle_key(), false)));
+    ret
+}
+
+/// How well a name matches what was typed, lower being closer: the
+/// letters skipped between those typed, or none if they are not all
+/// in it in order. Case is ignored.
+pub fn score(name: &str, typed: &str) -> Option<usize> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let mut at = 0;
+    let mut skipped = 0;
+    for c in typed.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = name[at..].iter().position(|&n| n == c)?;
+        //  letters skipped before the first typed are cheap, so prefixes and words match well
+        skipped += if at == 0 { found.min(1) } else { found };
+        at += found + 1;
+    }
+    Some(skipped)
+}
+
+/// The actions whose names match what was typed, closest first.
+pub fn search<'a>(actions: &'a [Action], typed: &str) -> Vec<&'a Action> {
+    let mut ret: Vec<_> = actions.iter().filter_map(|action| score(&action.name, typed).map(|score| (score, action))).collect();
+    ret.sort_by_key(|&(score, action)| (score, action.name.len()));
+    ret.into_iter().map(|(_, action)| action).collect()
+}
+
+/// Run an action in a frame, by pressing its keys in it instead of
+/// those that were.
+pub fn run(action: &Action, frame: &mut Frame) {
+    frame.clear_keys();
+    for (down, keys) in [(action.shift, [KeyboardKey::KEY_LEFT_SHIFT, KeyboardKey::KEY_RIGHT_SHIFT]), (action.ctrl, [KeyboardKey::KEY_LEFT_CONTROL, KeyboardKey::KEY_RIGHT_CONTROL])] {
+        for key in keys {
+            frame.set_key(key, down, false);
+        }
+    }
+    frame.set_key(action.key, true, true);
+}
+
+/// The command palette, see the module.
+#[derive(Debug, Clone, Default)]
+pub struct Palette {
+    pub typed: String,
+    /// The chosen of the matching actions.
+    pub chosen: usize,
+}
+
+impl Palette {
+    /// How many matching actions are shown.
+    pub const SHOWN: usize = 12;
+
+    /// Type into the palette, returning the action chosen with `Enter`.
+    pub fn update(&mut self, frame: &Frame, actions: &[Action]) -> Option<Action> {
+        let before = self.typed.len();
+        self.typed.push_str(&frame.typed);
+        if frame.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+            self.typed.pop();
+        }
+        let found = search(actions, &self.typed);
+        if self.typed.len() != before {
+            self.chosen = 0;
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_UP) {
+            self.chosen = self.chosen.saturating_sub(1);
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_DOWN) {
+            self.chosen = (self.chosen + 1).min(found.len().min(Self::SHOWN).saturating_sub(1));
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return found.get(self.chosen).map(|&action| action.clone());
+        }
+        None
+    }
+}
+
+/// The keyboard cursor, see the module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cursor {
+    /// Where it is on the screen.
+    pub pos: Vector2,
+}
+
+impl Cursor {
+    /// How fast the cursor moves, in pixels a second.
+    pub const SPEED: f32 = 300.;
+    /// How many times as fast it moves with shift.
+    pub const FAST: f32 = 4.;
+    /// How fast Ctrl and the arrows pan, in pixels a second.
+    pub const PAN_SPEED: f32 = 600.;
+
+    pub fn new(pos: Vector2) -> Self {
+        Self { pos }
+    }
+
+    /// Stand in for the mouse in a frame, returning how far to pan the
+    /// camera, see `Camera::pan`.
+    pub fn apply(&mut self, frame: &mut Frame, delta_time: f32, screen: Vector2) -> Vector2 {
+        let held = |key| if frame.is_key_down(key) { 1. } else { 0. };
+        let direction = Vector2::new(
+            held(KeyboardKey::KEY_RIGHT) - held(KeyboardKey::KEY_LEFT),
+            held(KeyboardKey::KEY_DOWN) - held(KeyboardKey::KEY_UP),
+        );
+        let mut pan = Vector2::zero();
+        if frame.is_ctrl_down() {
+            pan = direction * -Self::PAN_SPEED * delta_time;
+        } else if frame.is_alt_down() {
+            for (key, wheel) in [(KeyboardKey::KEY_UP, 1.), (KeyboardKey::KEY_DOWN, -1.)] {
+                if frame.is_key_pressed(key) {
+                    frame.wheel += wheel;
+                }
+            }
+        } else {
+            let speed = if frame.is_shift_down() { Self::SPEED * Self::FAST } else { Self::SPEED };
+            let pos = self.pos + direction * speed * delta_time;
+            self.pos = Vector2::new(pos.x.clamp(0., screen.x), pos.y.clamp(0., screen.y));
+        }
+        frame.mouse = self.pos;
+        frame.button = if frame.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            Button::Pressed
+        } else if frame.is_key_down(KeyboardKey::KEY_ENTER) {
+            Button::Down
+        } else {
+            Button::Up
+        };
+        pan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyboard() {
+        let actions = actions();
+        assert_eq!(search(&actions, "pause")[0].name, "Pause or resume");
+        assert_eq!(search(&actions, "svg")[0].key, KeyboardKey::KEY_C);
+        assert!(search(&actions, "zzzq").is_empty() && score("Save the world as", "swa").is_some());
+
+        //  the palette runs the chosen action by pressing its keys
+        let mut palette = Palette::default();
+        let mut frame = Frame::idle(0., Vector2::zero());
+        frame.typed.push_str("rename");
+        assert!(palette.update(&frame, &actions).is_none());
+        let mut frame = Frame::idle(0., Vector2::zero());
+        frame.set_key(KeyboardKey::KEY_ENTER, true, true);
+        let action = palette.update(&frame, &actions).unwrap();
+        run(&action, &mut frame);
+        assert!(frame.is_key_pressed(action.key) && frame.is_shift_down() && !frame.is_key_pressed(KeyboardKey::KEY_ENTER));
+
+        //  the cursor moves with the arrows and clicks with enter
+        let mut cursor = Cursor::new(Vector2::new(100., 100.));
+        let mut frame = Frame::idle(0., Vector2::zero());
+        frame.set_key(KeyboardKey::KEY_RIGHT, true, true);
+        frame.set_key(KeyboardKey::KEY_ENTER, true, true);
+        assert_eq!(cursor.apply(&mut frame, 0.5, Vector2::new(800., 600.)), Vector2::zero());
+        assert_eq!((frame.mouse, frame.button), (Vector2::new(250., 100.), Button::Pressed));
+        frame.set_key(KeyboardKey::KEY_LEFT_CONTROL, true, false);
+        assert!(cursor.apply(&mut frame, 0.5, Vector2::new(800., 600.)).x < 0. && cursor.pos.x == 250.);
+    }
+}
@@ -1,54 +1,85 @@
 //! A set of elements that are given unique keys.
 //!
 //! A `KeyedSet<T>` has all the properties of a `HashMap<Key, T>`
-//! but the keys are generated by the data structure.
+//! but the keys are generated by the data structure. It is a
+//! generational slot map: a key is a slot and a generation, the
+//! elements are stored densely, and inserting and removing are O(1).
+//!
+//! The slot of a removed element is given out again, under the next
+//! generation, so a key held past the removal of its element refers to
+//! nothing rather than to the element now in its slot. A key is never
+//! given out twice. Keys are numbers that can be saved and sent to
+//! peers, see `Key::index`.
+//!
+//! Iteration is namespace by namespace and, within each, in the order
+//! the elements are stored: the order they were inserted, except that
+//! a removed element is replaced by the last one. It depends only on
+//! what was inserted and removed, so it is the same on every run.
+//!
+//! Elements can be inserted in namespaces, each with `Namespace::SIZE`
+//! slots of its own, so that the keys elements of one source get do
+//! not depend on how many another source added or removed before.
 //!
 //! # Example
-//! 
+//!
 //! ```
-//! use keyed_set::prelude::{KeyedSet, Key};
-//! 
+//! use blobs::keyed_set::prelude::KeyedSet;
+//!
 //! let mut set = KeyedSet::new();
 //! let hi_key = set.insert("Hi!");
-//! assert_eq!(set.get(hi_key), Some("Hi!"));
+//! assert_eq!(set.get(hi_key), Some(&"Hi!"));
 //! set.remove(hi_key);
 //! assert_eq!(set.get(hi_key), None);
 //! ```
 
 use std::{
-    collections::{
-        HashMap,
-        hash_map,
-    },
     fmt::Display,
     marker::PhantomData,
 };
 
 /// A key is like a reference to an element of a keyed set.
 ///
-/// The lifetime of the key is the lifetime of the creating 
+/// The lifetime of the key is the lifetime of the creating
 /// `KeyedSet`. The time parameter is the element type of
 /// the `KeyedSet`.
 #[derive(Debug)]
-pub struct Key<T>(usize, PhantomData<*const T>);
+pub struct Key<T> {
+    slot: u32,
+    generation: u32,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Key<T> {
+    fn new(slot: u32, generation: u32) -> Self {
+        Self { slot, generation, marker: PhantomData }
+    }
+
+    /// The number identifying the key within its set, its generation
+    /// in the high 32 bits and its slot in the low.
+    pub fn index(self) -> u64 { (self.generation as u64) << 32 | self.slot as u64 }
+
+    /// The key with the given index, for keys that were sent elsewhere
+    /// by their index. It refers to nothing if no such key was generated.
+    pub fn from_index(index: u64) -> Self { Self::new(index as u32, (index >> 32) as u32) }
+}
 
 impl<T> PartialEq for Key<T> {
     fn eq(&self, other: &Self) -> bool {
-        self.0.eq(&other.0)
+        self.index().eq(&other.index())
     }
-} 
+}
 
 impl<T> Eq for Key<T> {}
 
 impl<T> std::hash::Hash for Key<T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.0.hash(state)
+        self.index().hash(state)
     }
 }
 
 impl<T> Clone for Key<T> {
     fn clone(&self) -> Self {
-        Self(self.0, self.1)
+        *self
     }
 }
 
@@ -56,64 +87,361 @@ impl<T> Copy for Key<T> {}
 
 impl<T> PartialOrd for Key<T> {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.0.partial_cmp(&other.0)
+        Some(self.cmp(other))
     }
 }
 
 impl<T> Ord for Key<T> {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.0.cmp(&other.0)
+        self.index().cmp(&other.index())
+    }
+}
+
+/// A range of slots for one source of elements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Namespace(u8);
+
+impl Namespace {
+    /// Where plain inserts go.
+    pub const DEFAULT: Self = Self(0);
+    /// How many namespaces there are.
+    pub const COUNT: u8 = 16;
+    /// How many slots a namespace has, so that every slot fits in 32 bits.
+    pub const SIZE: u32 = 1 << 28;
+
+    pub const fn new(index: u8) -> Self {
+        assert!(index < Self::COUNT, "there are only 16 namespaces");
+        Self(index)
+    }
+
+    pub fn index(self) -> u8 { self.0 }
+
+    /// The namespace a key was generated in.
+    pub fn of<T>(key: Key<T>) -> Self {
+        Self((key.slot / Self::SIZE) as u8)
+    }
+
+    /// The first slot of the namespace.
+    pub fn start(self) -> u32 { self.0 as u32 * Self::SIZE }
+}
+
+/// Where the element of a slot is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Place {
+    /// At this index of the dense storage.
+    Taken(usize),
+    /// Nowhere, and the slot is in the free list.
+    Free,
+    /// Nowhere, and the slot is never given out again.
+    Retired,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    //  the generation of the last key it gave out
+    generation: u32,
+    place: Place,
+}
+
+/// The elements of one namespace.
+///
+/// Slots past those it has, up to `end`, gave out their first key and
+/// are retired, as those of a loaded set whose keys were not all saved.
+#[derive(Clone)]
+struct Slots<T> {
+    //  the slots by their index within the namespace
+    slots: Vec<Slot>,
+    //  how many slots gave out a key
+    end: u32,
+    //  the free slots, the last given out first
+    free: Vec<u32>,
+    dense: Vec<(Key<T>, T)>,
+    start: u32,
+}
+
+impl<T> Slots<T> {
+    fn new(namespace: Namespace) -> Self {
+        Self { slots: vec![], end: 0, free: vec![], dense: vec![], start: namespace.start() }
+    }
+
+    fn len(&self) -> usize { self.dense.len() }
+
+    /// The slot of a key, if the namespace has it.
+    fn slot(&self, key: Key<T>) -> Option<&Slot> {
+        self.slots.get((key.slot - self.start) as usize)
+    }
+
+    /// Have the slots up to that of a key, those added retired.
+    fn slot_mut(&mut self, key: Key<T>) -> &mut Slot {
+        let i = (key.slot - self.start) as usize;
+        if i >= self.slots.len() {
+            self.slots.resize(i + 1, Slot { generation: 0, place: Place::Retired });
+            self.end = self.end.max(i as u32 + 1);
+        }
+        &mut self.slots[i]
+    }
+
+    fn get(&self, key: Key<T>) -> Option<&T> {
+        match self.slot(key)? {
+            &Slot { generation, place: Place::Taken(i) } if generation == key.generation => Some(&self.dense[i].1),
+            _ => None,
+        }
+    }
+
+    fn get_mut(&mut self, key: Key<T>) -> Option<&mut T> {
+        match *self.slot(key)? {
+            Slot { generation, place: Place::Taken(i) } if generation == key.generation => Some(&mut self.dense[i].1),
+            _ => None,
+        }
+    }
+
+    fn was_generated(&self, key: Key<T>) -> bool {
+        let i = key.slot - self.start;
+        i < self.end && key.generation <= self.slot(key).map_or(0, |slot| slot.generation)
+    }
+
+    fn next_key(&self) -> Key<T> {
+        match self.free.last() {
+            Some(&i) => Key::new(self.start + i, self.slots[i as usize].generation + 1),
+            None => Key::new(self.start + self.end, 0),
+        }
+    }
+
+    fn insert(&mut self, value: T) -> Key<T> {
+        let key = self.next_key();
+        assert!(key.slot - self.start < Namespace::SIZE, "namespace {} is out of slots", self.start / Namespace::SIZE);
+        if self.free.last() == Some(&(key.slot - self.start)) {
+            self.free.pop();
+        }
+        let place = Place::Taken(self.dense.len());
+        *self.slot_mut(key) = Slot { generation: key.generation, place };
+        self.dense.push((key, value));
+        key
+    }
+
+    fn insert_at(&mut self, key: Key<T>, value: T) -> Option<T> {
+        let (i, len) = (key.slot - self.start, self.dense.len());
+        let slot = self.slot_mut(key);
+        let place = std::mem::replace(&mut slot.place, Place::Taken(len));
+        slot.generation = key.generation;
+        match place {
+            Place::Taken(taken) => {
+                slot.place = place;
+                self.dense[taken].0 = key;
+                return Some(std::mem::replace(&mut self.dense[taken].1, value));
+            },
+            //  only keys of broken saves are put in free slots
+            Place::Free => self.free.retain(|&free| free != i),
+            Place::Retired => (),
+        }
+        self.dense.push((key, value));
+        None
+    }
+
+    /// Free the slot of an element, which must be the key's.
+    fn free(&mut self, key: Key<T>) {
+        let slot = &mut self.slots[(key.slot - self.start) as usize];
+        //  a slot out of generations is never given out again
+        if slot.generation == u32::MAX {
+            slot.place = Place::Retired;
+        } else {
+            slot.place = Place::Free;
+            self.free.push(key.slot - self.start);
+        }
+    }
+
+    fn remove(&mut self, key: Key<T>) -> Option<T> {
+        let i = match *self.slot(key)? {
+            Slot { generation, place: Place::Taken(i) } if generation == key.generation => i,
+            _ => return None,
+        };
+        self.free(key);
+        let (_, value) = self.dense.swap_remove(i);
+        if let Some(&(moved, _)) = self.dense.get(i) {
+            self.slots[(moved.slot - self.start) as usize].place = Place::Taken(i);
+        }
+        Some(value)
+    }
+
+    fn vacate(&mut self, key: Key<T>) -> bool {
+        if key.slot - self.start >= self.end || key.generation == u32::MAX || matches!(self.slot(key), Some(Slot { place: Place::Taken(_) | Place::Free, .. })) {
+            return false;
+        }
+        *self.slot_mut(key) = Slot { generation: key.generation, place: Place::Free };
+        self.free.push(key.slot - self.start);
+        true
+    }
+
+    /// Keep the elements a predicate holds for, in their order.
+    fn retain<F: FnMut(Key<T>, &mut T) -> bool>(&mut self, f: &mut F) {
+        let mut kept = 0;
+        for i in 0..self.dense.len() {
+            let key = self.dense[i].0;
+            if !f(key, &mut self.dense[i].1) {
+                self.free(key);
+                continue;
+            }
+            self.dense.swap(kept, i);
+            self.slots[(key.slot - self.start) as usize].place = Place::Taken(kept);
+            kept += 1;
+        }
+        self.dense.truncate(kept);
+    }
+
+    fn drain(&mut self) -> std::vec::IntoIter<(Key<T>, T)> {
+        let dense = std::mem::take(&mut self.dense);
+        for &(key, _) in &dense {
+            self.free(key);
+        }
+        dense.into_iter()
     }
 }
 
 /// A set of elements that are given unique keys.
 ///
 /// A `KeyedSet<T>` has all the properties of a `HashMap<Key, T>`
-/// but the keys are generated by the data structure.
+/// but the keys are generated by the data structure. Elements are
+/// stored densely in a `Vec` for each namespace, found through the
+/// slot of their key.
 ///
 /// # Example
-/// 
+///
 /// ```
-/// use keyed_set::prelude::{KeyedSet, Key};
-/// 
+/// use blobs::keyed_set::prelude::KeyedSet;
+///
 /// let mut set = KeyedSet::new();
 /// let hi_key = set.insert("Hi!");
-/// assert_eq!(set.get(hi_key), Some("Hi!"));
+/// assert_eq!(set.get(hi_key), Some(&"Hi!"));
 /// set.remove(hi_key);
 /// assert_eq!(set.get(hi_key), None);
 /// ```
+#[derive(Clone)]
 pub struct KeyedSet<T> {
-    map: HashMap<Key<T>, T>,
-    next: Key<T>,
+    //  the elements of each namespace, by namespace
+    slots: [Slots<T>; Namespace::COUNT as usize],
 }
 
 impl<T> KeyedSet<T> {
     pub fn new() -> Self {
-        Self { map: HashMap::new(), next: Key(0, PhantomData) }
+        Self { slots: std::array::from_fn(|i| Slots::new(Namespace(i as u8))) }
     }
 
-    fn generate_key(&mut self) -> Key<T> {
-        let key = self.next;
-        self.next.0 += 1;
-        key
+    /// The elements of the namespace a key is in.
+    fn slots_of(&self, key: Key<T>) -> &Slots<T> {
+        &self.slots[Namespace::of(key).0 as usize]
+    }
+
+    fn slots_of_mut(&mut self, key: Key<T>) -> &mut Slots<T> {
+        &mut self.slots[Namespace::of(key).0 as usize]
     }
-    
+
     pub fn insert(&mut self, value: T) -> Key<T> {
-        let key = self.generate_key();
-        self.map.insert(key, value);
-        key
+        self.insert_in(Namespace::DEFAULT, value)
+    }
+
+    /// Insert an element under the next key of a namespace.
+    pub fn insert_in(&mut self, namespace: Namespace, value: T) -> Key<T> {
+        self.slots[namespace.0 as usize].insert(value)
+    }
+
+    /// Insert an element under a key of another set, such as a saved
+    /// one, replacing what was in its slot.
+    pub fn insert_at(&mut self, key: Key<T>, value: T) -> Option<T> {
+        self.slots_of_mut(key).insert_at(key, value)
+    }
+
+    /// Whether a key was generated, even if its element was removed
+    /// since, rather than one the set never gave out.
+    pub fn was_generated(&self, key: Key<T>) -> bool {
+        self.slots_of(key).was_generated(key)
+    }
+
+    /// The key the next element inserted in the default namespace is given.
+    pub fn next_key(&self) -> Key<T> { self.next_key_in(Namespace::DEFAULT) }
+
+    /// The key the next element inserted in a namespace is given.
+    pub fn next_key_in(&self, namespace: Namespace) -> Key<T> { self.slots[namespace.0 as usize].next_key() }
+
+    /// The first key of the first slot of the default namespace that
+    /// never gave out a key.
+    pub fn fresh_key(&self) -> Key<T> { self.fresh_key_in(Namespace::DEFAULT) }
+
+    /// The first key of the first slot of a namespace that never gave out a key.
+    pub fn fresh_key_in(&self, namespace: Namespace) -> Key<T> {
+        let slots = &self.slots[namespace.0 as usize];
+        Key::new(slots.start + slots.end, 0)
+    }
+
+    /// The last keys the free slots of a namespace gave out, in the
+    /// order they were freed, the last of which is given out again first.
+    pub fn vacant_in(&self, namespace: Namespace) -> impl Iterator<Item=Key<T>> + '_ {
+        let slots = &self.slots[namespace.0 as usize];
+        slots.free.iter().map(move |&i| Key::new(slots.start + i, slots.slots[i as usize].generation))
+    }
+
+    /// Have the slots of the default namespace before that of the
+    /// given key, as `fresh_key` tells, each give out its first key.
+    pub fn reserve(&mut self, fresh: Key<T>) { self.reserve_in(Namespace::DEFAULT, fresh) }
+
+    /// Have the slots of a namespace before that of the given key, as
+    /// `fresh_key_in` tells, each give out its first key. Those not
+    /// taken or vacated after are never given out again.
+    pub fn reserve_in(&mut self, namespace: Namespace, fresh: Key<T>) {
+        let slots = &mut self.slots[namespace.0 as usize];
+        let end = fresh.slot.clamp(namespace.start(), namespace.start() + Namespace::SIZE) - namespace.start();
+        slots.end = slots.end.max(end);
+    }
+
+    /// Free the slot of a key, as the last it gave out, for `vacant_in`
+    /// to list after those freed before. False for a slot that is taken
+    /// or free or was never given out, or for a key of its last generation.
+    pub fn vacate(&mut self, key: Key<T>) -> bool {
+        self.slots_of_mut(key).vacate(key)
     }
 
     pub fn get(&self, key: Key<T>) -> Option<&T> {
-        self.map.get(&key)
+        self.slots_of(key).get(key)
     }
-    
+
     pub fn get_mut(&mut self, key: Key<T>) -> Option<&mut T> {
-        self.map.get_mut(&key)
+        self.slots_of_mut(key).get_mut(key)
     }
 
     pub fn remove(&mut self, key: Key<T>) -> Option<T> {
-        self.map.remove(&key)
+        self.slots_of_mut(key).remove(key)
+    }
+
+    pub fn contains_key(&self, key: Key<T>) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Keep only the elements a predicate holds for, visiting them in
+    /// order, which those kept stay in.
+    pub fn retain<F: FnMut(Key<T>, &mut T) -> bool>(&mut self, mut f: F) {
+        for slots in &mut self.slots {
+            slots.retain(&mut f);
+        }
+    }
+
+    /// Remove every element, in order.
+    pub fn drain(&mut self) -> IntoIter<T> {
+        let mut drained = vec![];
+        for slots in &mut self.slots {
+            drained.extend(slots.drain());
+        }
+        IntoIter { entries: drained.into_iter() }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item=Key<T>> + '_ {
+        self.iter().map(|(&key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item=&T> + '_ {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item=&mut T> + '_ {
+        self.iter_mut().map(|(_, value)| value)
     }
 
     pub fn iter(&self) -> <&Self as IntoIterator>::IntoIter {
@@ -124,44 +452,102 @@ impl<T> KeyedSet<T> {
         self.into_iter()
     }
 
-    pub fn len(&self) -> usize { self.map.len() }
+    pub fn len(&self) -> usize { self.slots.iter().map(Slots::len).sum() }
+
+    pub fn is_empty(&self) -> bool { self.slots.iter().all(|slots| slots.len() == 0) }
+}
+
+impl<T> Default for KeyedSet<T> {
+    fn default() -> Self { Self::new() }
+}
+
+/// The elements of a set, in order.
+pub struct IntoIter<T> {
+    entries: std::vec::IntoIter<(Key<T>, T)>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = (Key<T>, T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next()
+    }
+}
+
+/// References to the elements of a set, in order.
+pub struct Iter<'a, T> {
+    slots: std::slice::Iter<'a, Slots<T>>,
+    entries: std::slice::Iter<'a, (Key<T>, T)>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (&'a Key<T>, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.entries.next() {
+                Some((key, value)) => return Some((key, value)),
+                None => self.entries = self.slots.next()?.dense.iter(),
+            }
+        }
+    }
+}
+
+/// Mutable references to the elements of a set, in order.
+pub struct IterMut<'a, T> {
+    slots: std::slice::IterMut<'a, Slots<T>>,
+    entries: std::slice::IterMut<'a, (Key<T>, T)>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (&'a Key<T>, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.entries.next() {
+                Some((key, value)) => return Some((&*key, value)),
+                None => self.entries = self.slots.next()?.dense.iter_mut(),
+            }
+        }
+    }
 }
 
 impl<T> IntoIterator for KeyedSet<T> {
     type Item = (Key<T>, T);
-    type IntoIter = hash_map::IntoIter<Key<T>, T>;
+    type IntoIter = IntoIter<T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.into_iter()
+        let entries: Vec<_> = IntoIterator::into_iter(self.slots).flat_map(|slots| slots.dense).collect();
+        IntoIter { entries: entries.into_iter() }
     }
 }
 
 impl<'a, T> IntoIterator for &'a KeyedSet<T> {
     type Item = (&'a Key<T>, &'a T);
-    type IntoIter = hash_map::Iter<'a, Key<T>, T>;
+    type IntoIter = Iter<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.iter()
+        Iter { slots: self.slots.iter(), entries: [].iter() }
     }
 }
 
 impl<'a, T> IntoIterator for &'a mut KeyedSet<T> {
     type Item = (&'a Key<T>, &'a mut T);
-    type IntoIter = hash_map::IterMut<'a, Key<T>, T>;
+    type IntoIter = IterMut<'a, T>;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.map.iter_mut()
+        IterMut { slots: self.slots.iter_mut(), entries: [].iter_mut() }
     }
 }
 
 impl<T> Display for Key<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("#{}{}", std::any::type_name::<T>(), self.0))
+        f.write_fmt(format_args!("#{}{}", std::any::type_name::<T>(), self.index()))
     }
 }
 
 pub mod prelude {
-    pub use super::{Key, KeyedSet};
+    pub use super::{Key, KeyedSet, Namespace};
 }
 
 #[cfg(test)]
@@ -176,9 +562,63 @@ mod tests {
 
         assert_eq!(a.get(hello), Some(&"Hello!"));
         assert_eq!(a.get_mut(bye), Some(&mut "Bye!"));
-        
+
         a.remove(hello);
         assert_eq!(a.get(hello), None);
         assert_eq!(a.get(bye), Some(&"Bye!"));
+
+        //  a removed element's slot is given out again, under a key of its own
+        let hi = a.insert("Hi!");
+        assert!(hi != hello && Key::from_index(hi.index()) == hi);
+        assert_eq!((a.get(hello), a.get(hi)), (None, Some(&"Hi!")));
+        assert!(a.was_generated(hello) && a.was_generated(hi) && !a.was_generated(a.next_key()));
+        assert!(!a.was_generated(Key::from_index(u64::MAX)));
+
+        //  a set restored from the keys of another goes on as it would
+        a.remove(bye);
+        let mut b = KeyedSet::new();
+        b.reserve(a.fresh_key());
+        b.insert_at(hi, "Hi!");
+        for key in a.vacant_in(Namespace::DEFAULT) {
+            assert!(b.vacate(key) && !b.vacate(key));
+        }
+        assert!(!b.vacate(hi) && !b.vacate(b.fresh_key()));
+        assert_eq!((b.get(bye), b.get(hi)), (None, Some(&"Hi!")));
+        assert_eq!((b.insert("Yo!"), b.insert("Hey!")), (a.insert("Yo!"), a.insert("Hey!")));
+
+        //  slots reserved and not taken or vacated are never given out again
+        let mut d = KeyedSet::new();
+        d.reserve(a.fresh_key());
+        assert_eq!(d.insert("Yo!"), a.fresh_key());
+
+        let mut c = KeyedSet::new();
+        let keys: Vec<_> = (0..6).map(|i| c.insert(i)).collect();
+        c.retain(|_, value| *value % 2 == 0);
+        assert!(!c.contains_key(keys[1]) && c.contains_key(keys[2]));
+        assert_eq!(c.keys().collect::<Vec<_>>(), vec![keys[0], keys[2], keys[4]]);
+        c.values_mut().for_each(|value| *value *= 10);
+        assert_eq!(c.values().copied().collect::<Vec<_>>(), vec![0, 20, 40]);
+        assert_eq!(c.drain().map(|(_, value)| value).collect::<Vec<_>>(), vec![0, 20, 40]);
+        assert_eq!(c.len(), 0);
+        let new = c.insert(6);
+        assert!(keys.iter().all(|&key| key != new && c.get(key).is_none()));
+
+        //  a removed element is replaced by the last
+        let mut d = KeyedSet::new();
+        let keys: Vec<_> = (0..4).map(|i| d.insert(i)).collect();
+        d.remove(keys[1]);
+        assert_eq!(d.values().copied().collect::<Vec<_>>(), vec![0, 3, 2]);
+        assert_eq!(d.get(keys[3]), Some(&3));
+
+        //  a namespace's keys do not depend on what was inserted in another
+        let other = Namespace::new(3);
+        let mut e = KeyedSet::new();
+        let first = e.insert_in(other, "a");
+        e.insert("b");
+        assert_eq!((Namespace::of(first), e.insert_in(other, "c").index()), (other, first.index() + 1));
+        assert_eq!(e.values().copied().collect::<Vec<_>>(), vec!["b", "a", "c"]);
+        let mut f = KeyedSet::new();
+        f.insert_at(first, "a");
+        assert_eq!((f.next_key_in(other).index(), f.next_key().index()), (first.index() + 1, 0));
     }
 }
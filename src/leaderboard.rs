@@ -0,0 +1,260 @@
+//! Summaries of finished runs, kept in a local hall of fame and
+//! optionally posted to a leaderboard.
+//!
+//! Every run appends a line for each of its worlds to
+//! `hall-of-fame.txt`, its metadata (see `runs`) and how it fared:
+//!
+//! ```text
+//! run time=1760000000 seed=1234 config=9c1e4f0a5b2d3e77 version=0.1.0+fa634ec max_population=48 ticks=36000 longest_life=412.5 notes=
+//! ```
+//!
+//! Posting is opt-in. A summary is sent as a small JSON object over
+//! plain HTTP, and holds nothing about who ran it: only the seed, a
+//! hash of the configuration, the version and how the world fared,
+//! but not the notes.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::leaderboard::{self, Summary};
+//! # let now = 0;
+//! let summary = Summary::of(&sim, now);
+//! leaderboard::append(leaderboard::PATH, &summary)?;
+//! leaderboard::post("http://example.com/runs", &summary)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    cmp::Reverse,
+    fs,
+    io::{self, BufRead, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{math::Fnv, simulation::Simulation, runs::Metadata};
+
+/// Where the hall of fame is kept.
+pub const PATH: &str = "hall-of-fame.txt";
+/// How many of the best runs the hall of fame shows.
+pub const SHOWN: usize = 10;
+/// How long posting may wait on the network at each step.
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How a world fared over a run.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub metadata: Metadata,
+    pub max_population: u32,
+    pub ticks: u64,
+    /// The longest a blob lived, in seconds.
+    pub longest_life: f32,
+}
+
+impl Summary {
+    /// Summarize a world at the end of a run.
+    pub fn of(sim: &Simulation, time: u64) -> Self {
+        let stats = sim.stats();
+        let live = sim.blobs().count() as f32;
+        let max_population = stats.id("population")
+            .and_then(|id| stats.max(id))
+            .map_or(live, |max| max.max(live));
+        Self {
+            metadata: Metadata::of(sim, time),
+            max_population: max_population as u32,
+            ticks: sim.clock().tick(),
+            longest_life: sim.longest_life(),
+        }
+    }
+
+    /// The line of the summary in the hall of fame.
+    pub fn to_line(&self) -> String {
+        format!("run {} max_population={} ticks={} longest_life={} notes={}",
+            self.metadata.fields(), self.max_population, self.ticks, self.longest_life, self.metadata.notes,
+        )
+    }
+
+    /// Parse a line written by `to_line`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let (mut max_population, mut ticks, mut longest_life) = (0, 0, 0.);
+        let metadata = Metadata::parse_with(line, |name, value| {
+            let invalid = || format!("invalid {} `{}`", name, value);
+            match name {
+                "max_population" => max_population = value.parse().map_err(|_| invalid())?,
+                "ticks" => ticks = value.parse().map_err(|_| invalid())?,
+                "longest_life" => longest_life = value.parse().map_err(|_| invalid())?,
+                //  fields added later are skipped
+                _ => (),
+            }
+            Ok(())
+        })?;
+        Ok(Self { metadata, max_population, ticks, longest_life })
+    }
+
+    /// The anonymous JSON object posted to a leaderboard.
+    pub fn to_json(&self) -> String {
+        format!(r#"{{"seed":{},"config_hash":"{:016x}","version":"{}","max_population":{},"ticks":{},"longest_life":{}}}"#,
+            self.metadata.seed, self.metadata.config_hash, self.metadata.version, self.max_population, self.ticks, self.longest_life,
+        )
+    }
+}
+
+/// A hash of everything that shapes a world besides its seed, the
+/// same on every machine, so runs of the same setup can be compared.
+pub fn config_hash(sim: &Simulation) -> u64 {
+    let p = sim.parameters();
+    let t = sim.timing();
+    let mutators: Vec<_> = sim.mutators().iter().map(|mutator| mutator.id()).collect();
+    let mut description = format!(
+        "size={}x{} predation={} mutation_rate={} food_rate={} sight_scale={} size_scale={} metabolism_scale={} predation_efficiency={} mutators={} tick_rate={} substeps={} brain_period={}",
+        sim.size().x, sim.size().y, p.predation, p.mutation_rate, p.food_rate, p.sight_scale, p.size_scale, p.metabolism_scale, p.predation_efficiency,
+        mutators.join(","), t.tick_rate, t.substeps, t.brain_period,
+    );
+    //  only when changed, so setups from before these parameters hash as they did
+    if p.food_lifetime > 0. {
+        description += &format!(" food_lifetime={}", p.food_lifetime);
+    }
+    if !p.reproduction {
+        description += " reproduction=false";
+    }
+    if p.water > 0. {
+        description += &format!(" water={}", p.water);
+    }
+    if p.night > 0. {
+        description += &format!(" night={}", p.night);
+    }
+    if p.weather > 0. {
+        description += &format!(" weather={}", p.weather);
+    }
+    if p.speciation > 0. {
+        description += &format!(" speciation={}", p.speciation);
+    }
+    for lamp in sim.lights() {
+        description += &format!(" light={},{},{},{}", lamp.pos.x, lamp.pos.y, lamp.radius, lamp.brightness);
+    }
+    let mut hash = Fnv::default();
+    hash.write(description.as_bytes());
+    hash.finish()
+}
+
+/// Add a summary to the end of the hall of fame.
+pub fn append(path: &str, summary: &Summary) -> io::Result<()> {
+    let mut out = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(out, "{}", summary.to_line())
+}
+
+/// Read every summary in the hall of fame, oldest first.
+///
+/// A missing file has none.
+pub fn load(path: &str) -> io::Result<Vec<Summary>> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let mut ret = vec![];
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        if line.is_empty() { continue; }
+        ret.push(Summary::parse(&line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?);
+    }
+    Ok(ret)
+}
+
+/// The best runs, by their largest population and then by how long they ran.
+pub fn best(summaries: &[Summary], count: usize) -> Vec<&Summary> {
+    let mut ret: Vec<_> = summaries.iter().collect();
+    ret.sort_by_key(|summary| Reverse((summary.max_population, summary.ticks)));
+    ret.truncate(count);
+    ret
+}
+
+/// Post a summary to an `http://` endpoint, failing unless it answers with success.
+pub fn post(endpoint: &str, summary: &Summary) -> Result<(), String> {
+    let rest = endpoint.strip_prefix("http://")
+        .ok_or_else(|| format!("only http:// endpoints are supported, got `{}`", endpoint))?;
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+    let address = address.to_socket_addrs()
+        .map_err(|e| format!("failed to resolve {}: {}", authority, e))?
+        .next()
+        .ok_or_else(|| format!("no address for {}", authority))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, TIMEOUT).map_err(|e| format!("failed to connect to {}: {}", authority, e))?;
+    stream.set_read_timeout(Some(TIMEOUT)).and_then(|()| stream.set_write_timeout(Some(TIMEOUT))).map_err(|e| e.to_string())?;
+    let body = summary.to_json();
+    let request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        path, authority, body.len(), body,
+    );
+    stream.write_all(request.as_bytes()).map_err(|e| format!("failed to send to {}: {}", authority, e))?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).map_err(|e| format!("failed to read the answer of {}: {}", authority, e))?;
+    let status = response.lines().next().unwrap_or("");
+    match status.split(' ').nth(1) {
+        Some(code) if code.starts_with('2') => Ok(()),
+        _ => Err(format!("{} answered `{}`", authority, status)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{net::TcpListener, thread};
+
+    use raylib::prelude::*;
+
+    use super::*;
+    use crate::{simulation::SimulationConfig, mutators::Mutator};
+
+    #[test]
+    fn test_summaries() {
+        let config = SimulationConfig { seed: 5, ..SimulationConfig::new(Vector2::new(300., 300.)) };
+        let sim = Simulation::new(config.clone());
+        let mut summary = Summary { max_population: 3, longest_life: 12.5, ..Summary::of(&sim, 100) };
+        summary.metadata.notes = "no predators".to_string();
+        assert_eq!(Summary::parse(&summary.to_line()), Ok(summary.clone()));
+        assert_eq!(Metadata::parse(&summary.to_line()), Ok(summary.metadata.clone()));
+        assert_eq!(config_hash(&Simulation::new(SimulationConfig { seed: 6, ..config.clone() })), summary.metadata.config_hash);
+        assert_ne!(config_hash(&Simulation::new(SimulationConfig { mutators: vec![Mutator::Fog], ..config })), summary.metadata.config_hash);
+
+        let runs = vec![
+            Summary { ticks: 10, ..summary.clone() },
+            Summary { max_population: 9, ..summary.clone() },
+            Summary { ticks: 20, ..summary.clone() },
+        ];
+        let best: Vec<_> = best(&runs, 2).into_iter().map(|run| (run.max_population, run.ticks)).collect();
+        assert_eq!(best, vec![(9, 0), (3, 20)]);
+    }
+
+    #[test]
+    fn test_post() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/runs", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            //  read until the body has arrived, as the request is not closed
+            let mut request = vec![];
+            let mut buffer = [0; 1024];
+            while !request.ends_with(b"}") {
+                let length = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..length]);
+            }
+            stream.write_all(b"HTTP/1.1 201 Created\r\nContent-Length: 0\r\n\r\n").unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        let metadata = Metadata { time: 1, seed: 42, config_hash: 255, version: "1.0".to_string(), notes: "private".to_string() };
+        let summary = Summary { metadata, max_population: 7, ticks: 600, longest_life: 9.5 };
+        post(&endpoint, &summary).unwrap();
+        let request = server.join().unwrap();
+        assert!(request.starts_with("POST /runs HTTP/1.1\r\n"));
+        assert!(request.ends_with(r#"{"seed":42,"config_hash":"00000000000000ff","version":"1.0","max_population":7,"ticks":600,"longest_life":9.5}"#));
+        assert!(post("https://example.com", &summary).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! The simulation and everything around it, as a library: the game
+//! in `main.rs` is built on it, and hosts link against the C functions
+//! of `ffi` in its shared build.
+
+pub mod keyed_set;
+pub mod window;
+pub mod physics;
+pub mod simulation;
+pub mod math;
+pub mod clock;
+pub mod toml;
+pub mod species;
+pub mod mods;
+pub mod mutators;
+pub mod cli;
+pub mod stats;
+pub mod lockstep;
+pub mod theme;
+pub mod fonts;
+pub mod shapes;
+pub mod gif;
+pub mod lzw;
+pub mod recorder;
+pub mod inspector;
+pub mod narrator;
+pub mod sonification;
+pub mod memory;
+pub mod shutdown;
+pub mod crash;
+pub mod golden;
+pub mod input;
+pub mod exchange;
+pub mod leaderboard;
+pub mod tournament;
+pub mod energy;
+pub mod food_web;
+pub mod spatial;
+pub mod runs;
+pub mod world_code;
+pub mod ai_view;
+pub mod tuning;
+pub mod anomalies;
+pub mod tour;
+pub mod whatif;
+pub mod fertility;
+pub mod svg;
+pub mod quality;
+pub mod benchmark;
+pub mod realtime;
+pub mod group;
+pub mod replay;
+pub mod observer;
+pub mod hooks;
+pub mod light;
+pub mod viewpoints;
+pub mod floating_numbers;
+pub mod naming;
+pub mod tags;
+pub mod watch;
+pub mod drones;
+pub mod influence;
+pub mod trace;
+pub mod aura;
+pub mod power;
+pub mod placement;
+pub mod screensaver;
+pub mod director;
+pub mod stream;
+pub mod chat;
+pub mod vision;
+pub mod sankey;
+pub mod histogram;
+pub mod sqlite;
+pub mod scenario;
+pub mod scenario_editor;
+pub mod shared_state;
+pub mod ffi;
+pub mod loading;
+pub mod history;
+pub mod census;
+pub mod spawning;
+pub mod recent;
+pub mod dropping;
+pub mod overrides;
+pub mod keyboard;
+pub mod fixture;
+pub mod foraging;
+pub mod zones;
+pub mod scripts;
+pub mod profile;
+pub mod bench;
+pub mod memorial;
+pub mod weather;
+pub mod chunk;
+pub mod autopause;
+pub mod content;
+pub mod doctor;
+pub mod bubbles;
+pub mod records;
+pub mod throttle;
+pub mod style;
+pub mod shell;
+pub mod roster;
+pub mod taxonomy;
+pub mod handle;
+pub mod depth;
+pub mod senses;
+pub mod png;
+pub mod report;
+pub mod genealogy;
+
+use rand::{seq::SliceRandom, rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    simulation::prelude::*,
+    species::Species,
+    lockstep::Command,
+    drones::Drone,
+    fertility::FertilityMap,
+    spawning::{Spawner, Uniform},
+    zones::Zone,
+};
+
+/// Add a random blob, of one of the species if there are any.
+pub fn add_random_blob(sim: &mut Simulation, names: &[String], species: &[Species], spawner: &dyn Spawner) -> keyed_set::Key<Blob> {
+    //  draw from the simulation's generator so that runs with the same seed match
+    let mut rng = StdRng::from_rng(sim.rng()).unwrap();
+    //  the species is chosen first, for spawners placing blobs near their kin
+    let key = match species::choose(species, &mut rng) {
+        Some(chosen) => {
+            let pos = spawner.place(sim, spawning::Entity::Blob(Some(&chosen.name)), &mut rng);
+            chosen.spawn(sim, pos, &mut rng)
+        },
+        None => {
+            let pos = spawner.place(sim, spawning::Entity::Blob(None), &mut rng);
+            sim.insert_blob(BlobConfig { pos, ..BlobConfig::randomized(sim.size(), &mut rng) })
+        },
+    };
+    //  embedders may give no names
+    sim.get_blob_mut(key).unwrap().name = names.choose(&mut rng).cloned();
+    //  drawn last, so that worlds without water spawn the blobs they did before
+    if !sim.lakes().is_empty() {
+        sim.get_blob_mut(key).unwrap().swimming = rng.gen();
+    }
+    sim.get_blob_mut(key).unwrap().hearing = rng.gen();
+    //  blobs of species have the aura of their species
+    if species.is_empty() {
+        let blob = sim.get_blob_mut(key).unwrap();
+        blob.aura = *aura::Aura::ALL.choose(&mut rng).unwrap();
+        blob.aura_strength = if blob.aura == aura::Aura::None { 0. } else { rng.gen() };
+    }
+    key
+}
+
+/// Add food at a random place the zones allow, or on a fertility map
+/// maybe at none, the likelier the more fertile the place.
+pub fn add_random_food(sim: &mut Simulation, fertility: Option<&FertilityMap>, spawner: &dyn Spawner, zones: &[Zone]) -> Option<keyed_set::Key<Food>> {
+    let pos = spawning::place_food(spawner, sim, zones)?;
+    //  droughts keep most food from growing
+    let growth = sim.food_growth(pos);
+    if growth < 1. && sim.rng().gen::<f32>() >= growth {
+        return None;
+    }
+    if let Some(map) = fertility {
+        if sim.rng().gen::<f32>() >= map.at(pos, sim.size()) {
+            return None;
+        }
+    }
+    Some(sim.insert_food(pos))
+}
+
+/// Apply a user intervention to a simulation.
+pub fn apply_command(sim: &mut Simulation, command: Command, names: &[String], species: &[Species]) {
+    match command {
+        Command::SpawnBlob => { add_random_blob(sim, names, species, &Uniform); },
+        Command::SpawnFood(pos) => { sim.insert_food(pos); },
+        Command::MoveBlob(blob_key, pos) => sim.set_blob_pos(blob_key, pos),
+        Command::SetVelocity(blob_key, velocity) => {
+            if let Some(blob) = sim.get_blob_mut(blob_key) {
+                blob.set_velocity(velocity);
+            }
+        },
+        Command::ApplyImpulse(blob_key, impulse) => sim.apply_impulse(blob_key, impulse),
+        Command::SpawnDrone(pos) => { sim.add_drone(Drone::new(pos)); },
+        Command::SteerDrone(drone_key, target) => {
+            if let Some(drone) = sim.get_drone_mut(drone_key) {
+                drone.target = Some(target);
+            }
+        },
+        Command::RemoveDrone(drone_key) => { sim.remove_drone(drone_key); },
+        Command::Influence(influence, pos, radius) => sim.add_field(influence.field(pos, radius), influence::TICKS),
+    }
+}
@@ -0,0 +1,117 @@
+//! Light, which decides how far blobs see.
+//!
+//! The sun lights the whole world, dimming toward midnight as far as
+//! the `night` parameter says, and point lights brighten the ground
+//! around them, fading to nothing at their radius. A blob sees what
+//! is in full light as far as its sight reaches, and what is in the
+//! dark only from nearer, down to `DARK_SIGHT` of its reach.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::light;
+//! # let (food, blob) = (sim.insert_food(Vector2::new(100., 100.)), sim.insert_blob(BlobConfig::new(Vector2::new(120., 100.))));
+//! # let (food, blob) = (sim.get_food(food).unwrap(), sim.get_blob(blob).unwrap());
+//! let light = sim.light_at(food.pos());
+//! let reach = blob.sight_depth() * light::sight_factor(light);
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    clock::Clock,
+    math::FloatMode,
+    simulation::Simulation,
+};
+
+/// The part of its reach a blob sees in complete darkness.
+pub const DARK_SIGHT: f32 = 0.25;
+/// The side of the squares the light map is drawn in.
+const CELL: f32 = 20.;
+
+/// A lamp lighting the ground around it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub pos: Vector2,
+    pub radius: f32,
+    /// The light added at its center, where 1 is full daylight.
+    pub brightness: f32,
+}
+
+impl Light {
+    pub const COLOR: Color = Color::GOLD;
+
+    pub fn new(pos: Vector2, radius: f32) -> Self {
+        Self { pos, radius, brightness: 1. }
+    }
+
+    /// The light it adds at a position.
+    pub fn at(&self, pos: Vector2) -> f32 {
+        self.brightness * (1. - pos.distance_to(self.pos) / self.radius).max(0.)
+    }
+}
+
+/// The light of the sun at a time, from 1 at noon to `1 - night` at midnight.
+pub fn sunlight(clock: &Clock, night: f32, mode: FloatMode) -> f32 {
+    //  days start at dawn
+    let day = (clock.time() % Clock::DAY_LENGTH / Clock::DAY_LENGTH) as f32;
+    let darkness = 0.5 - 0.5 * mode.sin(2. * std::f32::consts::PI * day);
+    1. - night * darkness
+}
+
+/// The light at a position, from 0 for darkness to 1.
+pub fn illumination(sunlight: f32, lights: &[Light], pos: Vector2) -> f32 {
+    lights.iter().fold(sunlight, |light, lamp| light + lamp.at(pos)).min(1.)
+}
+
+/// The part of its reach a blob sees in some light.
+pub fn sight_factor(light: f32) -> f32 {
+    DARK_SIGHT + (1. - DARK_SIGHT) * light
+}
+
+/// Draw how dark the world is, shading squares by their light and
+/// ringing the reach of every lamp.
+pub fn draw_light_map<D: RaylibDraw>(draw: &mut D, sim: &Simulation) {
+    let (columns, rows) = ((sim.size().x / CELL).ceil() as i32, (sim.size().y / CELL).ceil() as i32);
+    for row in 0..rows {
+        for column in 0..columns {
+            let corner = Vector2::new(column as f32, row as f32) * CELL;
+            let light = sim.light_at(corner + Vector2::one() * (CELL / 2.));
+            if light >= 1. { continue; }
+            draw.draw_rectangle_v(corner, Vector2::new(CELL, CELL), Color::BLACK.fade(0.7 * (1. - light)));
+        }
+    }
+    for lamp in sim.lights() {
+        draw.draw_circle_lines(lamp.pos.x as i32, lamp.pos.y as i32, lamp.radius, Light::COLOR.fade(0.5));
+        draw.draw_circle_v(lamp.pos, 3., Light::COLOR);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_light() {
+        let mut clock = Clock::new(0.5);
+        assert_eq!(sunlight(&clock, 0.8, FloatMode::Native), 0.6);
+        //  noon, then midnight
+        let quarter = (Clock::DAY_LENGTH / 2.) as u32;
+        (0..quarter).for_each(|_| clock.advance());
+        assert!((sunlight(&clock, 0.8, FloatMode::Native) - 1.).abs() < 1e-6);
+        (0..2 * quarter).for_each(|_| clock.advance());
+        assert!((sunlight(&clock, 0.8, FloatMode::Native) - 0.2).abs() < 1e-6);
+        assert_eq!(sunlight(&clock, 0., FloatMode::Native), 1.);
+
+        let lamp = Light::new(Vector2::new(100., 100.), 50.);
+        assert_eq!(illumination(0., &[lamp], Vector2::new(100., 100.)), 1.);
+        assert_eq!(illumination(0.2, &[lamp], Vector2::new(125., 100.)), 0.7);
+        assert_eq!(illumination(0.2, &[lamp], Vector2::new(200., 100.)), 0.2);
+        assert_eq!(illumination(0.9, &[lamp, lamp], Vector2::new(100., 100.)), 1.);
+        assert_eq!(sight_factor(1.), 1.);
+        assert_eq!(sight_factor(0.), DARK_SIGHT);
+    }
+}
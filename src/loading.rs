@@ -0,0 +1,168 @@
+//! Worlds loaded from a save on a thread of their own, so that the
+//! window keeps drawing while a large save is read.
+//!
+//! The save is parsed line by line as it is read, counting how much
+//! of the file was for a progress bar, and checked and repaired, see
+//! `Simulation::load_repairing`, before it is handed over whole: the
+//! world it is to replace carries on until then, and stays if the
+//! load fails or is cancelled. Cancelling stops the reading at the
+//! next block of the file.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::{loading::Loading, simulation::Simulation};
+//! let loading = Loading::start(Simulation::SAVE_PATH)?;
+//! //  every frame
+//! if let Some(loaded) = loading.poll() {
+//!     let (sim, warnings) = loaded?;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs,
+    io::{self, BufReader, Read},
+    sync::{Arc, mpsc, atomic::{AtomicBool, AtomicU64, Ordering}},
+    thread,
+};
+
+use raylib::prelude::*;
+
+use crate::{fonts::Fonts, simulation::Simulation, theme::Theme};
+
+/// How much of the file is read at a time, and so how often the
+/// progress moves and a cancel is noticed.
+const BLOCK_SIZE: usize = 1 << 16;
+const FONT_SIZE: i32 = 20;
+const BAR_WIDTH: f32 = 400.;
+const BAR_HEIGHT: f32 = 24.;
+const BUTTON_WIDTH: f32 = 90.;
+
+/// A world loaded and checked, with what was repaired in it.
+pub type Loaded = Result<(Simulation, Vec<String>), String>;
+
+/// A reader counting what was read, that fails once cancelled.
+struct Counting<R> {
+    inner: R,
+    read: Arc<AtomicU64>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl<R: Read> Read for Counting<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(io::Error::other("cancelled"));
+        }
+        let count = self.inner.read(buf)?;
+        self.read.fetch_add(count as u64, Ordering::Relaxed);
+        Ok(count)
+    }
+}
+
+/// A save being loaded.
+pub struct Loading {
+    pub path: String,
+    size: u64,
+    read: Arc<AtomicU64>,
+    cancelled: Arc<AtomicBool>,
+    receiver: mpsc::Receiver<Loaded>,
+}
+
+impl Loading {
+    /// Start loading a save on another thread.
+    pub fn start(path: &str) -> io::Result<Self> {
+        let file = fs::File::open(path)?;
+        let size = file.metadata()?.len();
+        let (read, cancelled) = (Arc::new(AtomicU64::new(0)), Arc::new(AtomicBool::new(false)));
+        let reader = Counting { inner: file, read: read.clone(), cancelled: cancelled.clone() };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let loaded = Simulation::load_repairing(BufReader::with_capacity(BLOCK_SIZE, reader)).and_then(|(sim, warnings)| {
+                match sim.check_invariants().first() {
+                    Some(violation) => Err(violation.clone()),
+                    None => Ok((sim, warnings)),
+                }
+            });
+            //  the loading may have been cancelled and dropped by the time it is done
+            let _ = sender.send(loaded);
+        });
+        Ok(Self { path: path.to_string(), size, read, cancelled, receiver })
+    }
+
+    /// The part of the file read so far, from 0 to 1.
+    pub fn progress(&self) -> f32 {
+        if self.size == 0 { 1. } else { (self.read.load(Ordering::Relaxed) as f64 / self.size as f64).min(1.) as f32 }
+    }
+
+    /// Stop reading, leaving the thread to finish on its own.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// The world, once it is loaded and checked.
+    pub fn poll(&self) -> Option<Loaded> {
+        match self.receiver.try_recv() {
+            Ok(loaded) => Some(loaded),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err("the loading stopped".to_string())),
+        }
+    }
+
+    fn bar(screen_width: i32, screen_height: i32) -> Rectangle {
+        Rectangle::new((screen_width as f32 - BAR_WIDTH) / 2., (screen_height as f32 - BAR_HEIGHT) / 2., BAR_WIDTH, BAR_HEIGHT)
+    }
+
+    /// The button that cancels the loading, right of the progress bar.
+    pub fn cancel_button(screen_width: i32, screen_height: i32) -> Rectangle {
+        let bar = Self::bar(screen_width, screen_height);
+        Rectangle::new(bar.x + bar.width + 8., bar.y, BUTTON_WIDTH, bar.height)
+    }
+
+    /// Draw the progress over the middle of the window.
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, theme: &Theme, fonts: &Fonts, screen_width: i32, screen_height: i32) {
+        let bar = Self::bar(screen_width, screen_height);
+        draw.draw_rectangle_rec(bar, theme.panel);
+        draw.draw_rectangle_rec(Rectangle { width: bar.width * self.progress(), ..bar }, theme.accent);
+        draw.draw_rectangle_lines_ex(bar, 1, theme.panel_border);
+        let text = format!("Loading {}: {:.0}% (F11 to cancel)", self.path, 100. * self.progress());
+        fonts.draw_text(draw, &text, bar.x as i32, bar.y as i32 - FONT_SIZE - 6, FONT_SIZE, theme.text);
+        let button = Self::cancel_button(screen_width, screen_height);
+        draw.draw_rectangle_rec(button, theme.panel);
+        draw.draw_rectangle_lines_ex(button, 1, theme.panel_border);
+        fonts.draw_text(draw, "Cancel", button.x as i32 + 12, button.y as i32 + 2, FONT_SIZE, theme.text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_loading() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 200.)));
+        for i in 0..20 {
+            sim.insert_blob(BlobConfig::new(Vector2::new(10. * i as f32, 50.)));
+        }
+        let mut saved = vec![];
+        sim.save(&mut saved).unwrap();
+        let path = std::env::temp_dir().join(format!("blobs-loading-{}", std::process::id()));
+        fs::write(&path, &saved).unwrap();
+
+        let loading = Loading::start(path.to_str().unwrap()).unwrap();
+        let loaded = loop {
+            match loading.poll() {
+                Some(loaded) => break loaded,
+                None => thread::yield_now(),
+            }
+        };
+        fs::remove_file(&path).unwrap();
+        let (loaded, warnings) = loaded.unwrap();
+        assert_eq!((loaded.blobs().count(), warnings.len(), loading.progress()), (20, 0, 1.));
+
+        //  a cancelled load fails at its next read
+        let reader = Counting { inner: &saved[..], read: Arc::new(AtomicU64::new(0)), cancelled: Arc::new(AtomicBool::new(true)) };
+        assert!(Simulation::load_repairing(BufReader::new(reader)).err().unwrap().contains("cancelled"));
+    }
+}
@@ -0,0 +1,538 @@
+//! Lockstep multiplayer between two peers over TCP.
+//!
+//! Instead of streaming the world, the peers share the seed the
+//! simulation is created from and then only exchange commands,
+//! the interventions of their users, each stamped with the tick
+//! it takes effect at. Both peers run the same simulation locally
+//! and apply the same commands at the same ticks, so they stay
+//! identical. A command is scheduled `DELAY` ticks in the future
+//! to give it time to reach the other peer, and a peer only steps
+//! a tick once the other peer has confirmed it sent every command
+//! for it.
+//!
+//! Every `HASH_PERIOD` ticks the peers exchange a hash of their
+//! state, which tells when they desynchronized.
+//!
+//! Messages are lines of text:
+//!
+//! * `hello <seed> <width> <height> <mutators> <tick rate> <substeps>
+//!   <brain period> <content>` - sent by the host on connection,
+//!   mutators are comma separated or `-` and content is the host's
+//!   `fingerprint`; the client refuses a size or timing out of range
+//!   or content other than its own
+//! * `cmd <tick> <command>` - a command to apply before stepping
+//!   the tick
+//! * `ready <tick>` - every command up to and including the tick
+//!   was sent
+//! * `hash <tick> <hash>` - the state hash after stepping the tick
+//!
+//! # Example
+//!
+//! ```ignore
+//! let listener = TcpListener::bind(("0.0.0.0", 7700))?;
+//! let content = lockstep::fingerprint(&mods, &species, &names);
+//! let mut session = Session::accept(&listener, &setup, content)?;
+//! session.schedule(Command::SpawnBlob, sim.clock().tick());
+//! session.poll(sim.clock().tick())?;
+//! if session.can_step(sim.clock().tick()) {
+//!     for command in session.take_commands(sim.clock().tick()) { ... }
+//!     sim.step();
+//! }
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    str::FromStr,
+};
+
+use raylib::prelude::Vector2;
+
+use crate::{
+    keyed_set::Key,
+    math::Fnv,
+    mods::Mod,
+    mutators::Mutator,
+    simulation::{Blob, Timing},
+    species::Species,
+    drones::Drone,
+    influence::Influence,
+};
+
+/// What both peers need to create the same simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setup {
+    pub seed: u64,
+    pub size: Vector2,
+    pub mutators: Vec<Mutator>,
+    pub timing: Timing,
+}
+
+/// A hash of the content a peer loaded, its species, enabled mods and
+/// names, which blobs are spawned and named from, so peers whose
+/// content differs can be told apart before their worlds do.
+pub fn fingerprint(mods: &[Mod], species: &[Species], names: &[String]) -> u64 {
+    let enabled: Vec<_> = mods.iter().filter(|m| m.enabled).map(|m| &m.name).collect();
+    let mut hash = Fnv::default();
+    hash.write(format!("{:?} {:?} {:?}", enabled, species, names).as_bytes());
+    hash.finish()
+}
+
+/// A user intervention in the simulation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    /// Add a random blob.
+    SpawnBlob,
+    SpawnFood(Vector2),
+    MoveBlob(Key<Blob>, Vector2),
+    SetVelocity(Key<Blob>, Vector2),
+    ApplyImpulse(Key<Blob>, Vector2),
+    /// Place a drone, see `drones`.
+    SpawnDrone(Vector2),
+    /// Send a drone somewhere.
+    SteerDrone(Key<Drone>, Vector2),
+    RemoveDrone(Key<Drone>),
+    /// Put the field of a soft tool at a point with a radius, see `influence`.
+    Influence(Influence, Vector2, f32),
+}
+
+impl Command {
+    /// Check that what a command places is in a world of a size and
+    /// that its numbers are finite, as those a host or a peer sends
+    /// may not be.
+    pub fn validate(&self, size: Vector2) -> Result<(), String> {
+        let inside = |pos: Vector2| (0. ..=size.x).contains(&pos.x) && (0. ..=size.y).contains(&pos.y);
+        let valid = match *self {
+            Self::SpawnBlob | Self::RemoveDrone(_) => true,
+            Self::SpawnFood(pos) | Self::MoveBlob(_, pos) | Self::SpawnDrone(pos) | Self::SteerDrone(_, pos) => inside(pos),
+            Self::SetVelocity(_, v) | Self::ApplyImpulse(_, v) => v.x.is_finite() && v.y.is_finite(),
+            Self::Influence(_, pos, radius) => inside(pos) && radius > 0. && radius <= size.length(),
+        };
+        if valid { Ok(()) } else { Err(format!("`{}` is outside the world or not finite", self)) }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        //  floats are written with the shortest representation that reads back exactly
+        match self {
+            Self::SpawnBlob => write!(f, "spawn-blob"),
+            Self::SpawnFood(pos) => write!(f, "spawn-food {} {}", pos.x, pos.y),
+            Self::MoveBlob(key, pos) => write!(f, "move {} {} {}", key.index(), pos.x, pos.y),
+            Self::SetVelocity(key, v) => write!(f, "velocity {} {} {}", key.index(), v.x, v.y),
+            Self::ApplyImpulse(key, v) => write!(f, "impulse {} {} {}", key.index(), v.x, v.y),
+            Self::SpawnDrone(pos) => write!(f, "spawn-drone {} {}", pos.x, pos.y),
+            Self::SteerDrone(key, pos) => write!(f, "steer-drone {} {} {}", key.index(), pos.x, pos.y),
+            Self::RemoveDrone(key) => write!(f, "remove-drone {}", key.index()),
+            Self::Influence(influence, pos, radius) => write!(f, "{} {} {} {}", influence.name(), pos.x, pos.y, radius),
+        }
+    }
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid command `{}`", s);
+        let parts: Vec<&str> = s.split(' ').collect();
+        let vector = |x: &str, y: &str| Some(Vector2::new(x.parse().ok()?, y.parse().ok()?));
+        fn key<T>(index: &str) -> Option<Key<T>> { index.parse().ok().map(Key::from_index) }
+        let command = match parts[..] {
+            ["spawn-blob"] => Some(Self::SpawnBlob),
+            ["spawn-food", x, y] => vector(x, y).map(Self::SpawnFood),
+            ["move", k, x, y] => key(k).zip(vector(x, y)).map(|(k, v)| Self::MoveBlob(k, v)),
+            ["velocity", k, x, y] => key(k).zip(vector(x, y)).map(|(k, v)| Self::SetVelocity(k, v)),
+            ["impulse", k, x, y] => key(k).zip(vector(x, y)).map(|(k, v)| Self::ApplyImpulse(k, v)),
+            ["spawn-drone", x, y] => vector(x, y).map(Self::SpawnDrone),
+            ["steer-drone", k, x, y] => key(k).zip(vector(x, y)).map(|(k, v)| Self::SteerDrone(k, v)),
+            ["remove-drone", k] => key(k).map(Self::RemoveDrone),
+            [name, x, y, r] => Influence::from_name(name).zip(vector(x, y)).zip(r.parse().ok()).map(|((i, v), r)| Self::Influence(i, v, r)),
+            _ => None,
+        };
+        command.ok_or_else(invalid)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Message {
+    /// The setup and the host's content `fingerprint`.
+    Hello(Setup, u64),
+    Command(u64, Command),
+    Ready(u64),
+    Hash(u64, u64),
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hello(setup, content) => {
+                let mutators: Vec<_> = setup.mutators.iter().map(Mutator::id).collect();
+                write!(f, "hello {} {} {} {} {} {} {} {}",
+                    setup.seed, setup.size.x, setup.size.y,
+                    if mutators.is_empty() { "-".to_string() } else { mutators.join(",") },
+                    setup.timing.tick_rate, setup.timing.substeps, setup.timing.brain_period,
+                    content,
+                )
+            },
+            Self::Command(tick, command) => write!(f, "cmd {} {}", tick, command),
+            Self::Ready(tick) => write!(f, "ready {}", tick),
+            Self::Hash(tick, hash) => write!(f, "hash {} {}", tick, hash),
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid message `{}`", s);
+        let (kind, rest) = s.split_once(' ').ok_or_else(invalid)?;
+        let parts: Vec<&str> = rest.split(' ').collect();
+        let number = |part: &str| part.parse::<u64>().map_err(|_| invalid());
+        match (kind, &parts[..]) {
+            ("hello", [seed, width, height, mutators, tick_rate, substeps, brain_period, content]) => {
+                let mutators = match *mutators {
+                    "-" => vec![],
+                    mutators => mutators.split(',')
+                        .map(|id| Mutator::from_id(id).ok_or_else(invalid))
+                        .collect::<Result<_, _>>()?,
+                };
+                Ok(Self::Hello(Setup {
+                    seed: number(seed)?,
+                    size: Vector2::new(
+                        width.parse().map_err(|_| invalid())?,
+                        height.parse().map_err(|_| invalid())?,
+                    ),
+                    mutators,
+                    timing: Timing {
+                        tick_rate: tick_rate.parse().map_err(|_| invalid())?,
+                        substeps: substeps.parse().map_err(|_| invalid())?,
+                        brain_period: brain_period.parse().map_err(|_| invalid())?,
+                    },
+                }, number(content)?))
+            },
+            ("cmd", [tick, ..]) => {
+                let command = rest.split_once(' ').ok_or_else(invalid)?.1;
+                Ok(Self::Command(number(tick)?, command.parse()?))
+            },
+            ("ready", [tick]) => Ok(Self::Ready(number(tick)?)),
+            ("hash", [tick, hash]) => Ok(Self::Hash(number(tick)?, number(hash)?)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// A connection to the other peer and the commands of both peers
+/// that were not applied yet.
+pub struct Session {
+    stream: TcpStream,
+    is_host: bool,
+    //  of the world, which the commands of the other peer must be in
+    size: Vector2,
+    incoming: Vec<u8>,
+    outgoing: Vec<u8>,
+    //  commands by tick, the host's before the client's
+    commands: BTreeMap<u64, [Vec<Command>; 2]>,
+    sent_ready: Option<u64>,
+    remote_ready: Option<u64>,
+    local_hashes: BTreeMap<u64, u64>,
+    remote_hashes: BTreeMap<u64, u64>,
+    desync: Option<u64>,
+}
+
+impl Session {
+    /// How many ticks after being issued a command takes effect.
+    pub const DELAY: u64 = 6;
+    /// How many ticks pass between state hash exchanges.
+    pub const HASH_PERIOD: u64 = 60;
+    /// The longest line a peer may send, so that one never ending its
+    /// line cannot fill the memory.
+    const MAX_LINE: usize = 4096;
+
+    /// Wait for a client and send it the setup of the simulation and
+    /// the `fingerprint` of the content loaded.
+    pub fn accept(listener: &TcpListener, setup: &Setup, content: u64) -> io::Result<Self> {
+        let (mut stream, _) = listener.accept()?;
+        writeln!(stream, "{}", Message::Hello(setup.clone(), content))?;
+        Self::new(stream, true, setup.size)
+    }
+
+    /// Connect to a host and receive the setup of the simulation,
+    /// refusing a host whose content has another `fingerprint`.
+    pub fn connect<A: ToSocketAddrs>(address: A, content: u64) -> io::Result<(Self, Setup)> {
+        let stream = TcpStream::connect(address)?;
+        let mut line = String::new();
+        //  read byte by byte so that nothing after the hello is buffered away
+        BufReader::with_capacity(1, (&stream).take(Self::MAX_LINE as u64)).read_line(&mut line)?;
+        let (setup, host_content) = match line.trim_end().parse().map_err(invalid_data)? {
+            Message::Hello(setup, host_content) => (setup, host_content),
+            message => return Err(invalid_data(format!("expected hello, got `{}`", message))),
+        };
+        if !(setup.size.x > 0. && setup.size.y > 0. && setup.size.x.is_finite() && setup.size.y.is_finite()) {
+            return Err(invalid_data(format!("invalid world size {}x{}", setup.size.x, setup.size.y)));
+        }
+        setup.timing.validate().map_err(invalid_data)?;
+        if host_content != content {
+            return Err(invalid_data("the host loaded other species, mods or names than these".to_string()));
+        }
+        Ok((Self::new(stream, false, setup.size)?, setup))
+    }
+
+    fn new(stream: TcpStream, is_host: bool, size: Vector2) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            stream,
+            is_host,
+            size,
+            incoming: vec![],
+            outgoing: vec![],
+            commands: BTreeMap::new(),
+            sent_ready: None,
+            remote_ready: None,
+            local_hashes: BTreeMap::new(),
+            remote_hashes: BTreeMap::new(),
+            desync: None,
+        })
+    }
+
+    pub fn is_host(&self) -> bool { self.is_host }
+
+    /// The first tick the peers' states were found to differ at.
+    pub fn desync(&self) -> Option<u64> { self.desync }
+
+    fn send(&mut self, message: Message) {
+        self.outgoing.extend(format!("{}\n", message).bytes());
+    }
+
+    fn side(&self, local: bool) -> usize {
+        if local == self.is_host { 0 } else { 1 }
+    }
+
+    /// Issue a command at the current tick, to be applied by both peers
+    /// `DELAY` ticks later.
+    pub fn schedule(&mut self, command: Command, tick: u64) {
+        let tick = tick + Self::DELAY;
+        let side = self.side(true);
+        self.commands.entry(tick).or_default()[side].push(command);
+        self.send(Message::Command(tick, command));
+    }
+
+    /// Exchange messages with the other peer. A peer that sends what
+    /// cannot be read, a command outside the world or for a tick it
+    /// said was ready, or a line too long, is an error, as applying it
+    /// would crash both peers or desync them.
+    pub fn poll(&mut self, tick: u64) -> io::Result<()> {
+        //  nothing more can be scheduled before the delay
+        let ready = tick + Self::DELAY - 1;
+        if self.sent_ready.is_none_or(|sent| ready > sent) {
+            self.send(Message::Ready(ready));
+            self.sent_ready = Some(ready);
+        }
+
+        //  write
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(n) => { self.outgoing.drain(..n); },
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        //  read
+        let mut buffer = [0; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => {
+                    self.incoming.extend_from_slice(&buffer[..n]);
+                    self.receive_lines(tick)?;
+                },
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Receive the whole lines read, leaving the last unless it is
+    /// already too long.
+    fn receive_lines(&mut self, tick: u64) -> io::Result<()> {
+        while let Some(end) = self.incoming.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.incoming.drain(..=end).collect();
+            if end > Self::MAX_LINE {
+                return Err(invalid_data(format!("a line of {} bytes", end)));
+            }
+            let line = String::from_utf8_lossy(&line[..end]).into_owned();
+            self.receive(line.parse().map_err(invalid_data)?, tick)?;
+        }
+        if self.incoming.len() > Self::MAX_LINE {
+            return Err(invalid_data(format!("a line of more than {} bytes", Self::MAX_LINE)));
+        }
+        Ok(())
+    }
+
+    fn receive(&mut self, message: Message, tick: u64) -> io::Result<()> {
+        match message {
+            Message::Hello(..) => return Err(invalid_data("unexpected hello".to_string())),
+            Message::Command(at, command) => {
+                //  the peer said it had no more commands up to the tick it was ready for, and
+                //  those before the tick being stepped were taken already
+                if at < tick || self.remote_ready.is_some_and(|ready| at <= ready) {
+                    return Err(invalid_data(format!("a command for tick {}, which is past", at)));
+                }
+                command.validate(self.size).map_err(invalid_data)?;
+                let side = self.side(false);
+                self.commands.entry(at).or_default()[side].push(command);
+            },
+            Message::Ready(tick) => self.remote_ready = Some(tick),
+            Message::Hash(tick, hash) => {
+                self.remote_hashes.insert(tick, hash);
+                self.compare_hashes();
+            },
+        }
+        Ok(())
+    }
+
+    /// Whether every command for the tick is known, so it can be stepped.
+    pub fn can_step(&self, tick: u64) -> bool {
+        self.remote_ready.is_some_and(|ready| ready >= tick)
+    }
+
+    /// Remove and return the commands to apply before stepping the tick,
+    /// in the same order on both peers.
+    pub fn take_commands(&mut self, tick: u64) -> Vec<Command> {
+        let [host, client] = self.commands.remove(&tick).unwrap_or_default();
+        host.into_iter().chain(client).collect()
+    }
+
+    /// Record the state hash after stepping a tick, sharing it with the
+    /// other peer if it is time to.
+    pub fn record_hash(&mut self, tick: u64, hash: u64) {
+        if !tick.is_multiple_of(Self::HASH_PERIOD) { return; }
+        self.local_hashes.insert(tick, hash);
+        self.send(Message::Hash(tick, hash));
+        self.compare_hashes();
+    }
+
+    fn compare_hashes(&mut self) {
+        let matched: Vec<u64> = self.local_hashes.keys()
+            .filter(|tick| self.remote_hashes.contains_key(tick))
+            .cloned()
+            .collect();
+        for tick in matched {
+            let local = self.local_hashes.remove(&tick);
+            let remote = self.remote_hashes.remove(&tick);
+            if local != remote && self.desync.is_none() {
+                self.desync = Some(tick);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_round_trip() {
+        let messages = [
+            Message::Hello(Setup { seed: 42, size: Vector2::new(650., 680.), mutators: vec![Mutator::Fog, Mutator::ScarceFood], timing: Timing::default() }, 7),
+            Message::Hello(Setup { seed: 1, size: Vector2::new(0.1, 2.5), mutators: vec![], timing: Timing { tick_rate: 30, substeps: 4, brain_period: 3 } }, u64::MAX),
+            Message::Command(7, Command::SpawnBlob),
+            Message::Command(8, Command::ApplyImpulse(Key::from_index(3), Vector2::new(-0.1, 1e-7))),
+            Message::Command(9, Command::SteerDrone(Key::from_index(2), Vector2::new(40., 0.5))),
+            Message::Command(10, Command::Influence(Influence::Calm, Vector2::new(3.5, 80.), 12.25)),
+            Message::Ready(12),
+            Message::Hash(60, u64::MAX),
+        ];
+        for message in &messages {
+            assert_eq!(&message.to_string().parse::<Message>().unwrap(), message);
+        }
+        assert!("cmd 3 teleport 1".parse::<Message>().is_err());
+        assert_ne!(fingerprint(&[], &[], &["Ada".to_string()]), fingerprint(&[], &[], &[]));
+    }
+
+    #[test]
+    fn test_session() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        let setup = Setup { seed: 5, size: Vector2::new(10., 20.), mutators: vec![], timing: Timing::default() };
+        let client = std::thread::spawn(move || Session::connect(address, 9).unwrap());
+        let mut host = Session::accept(&listener, &setup, 9).unwrap();
+        let (mut client, client_setup) = client.join().unwrap();
+        assert_eq!(client_setup, setup);
+
+        client.schedule(Command::SpawnFood(Vector2::new(1., 2.)), 0);
+        host.schedule(Command::SpawnBlob, 0);
+        let tick = Session::DELAY;
+        while !(host.can_step(tick - 1) && client.can_step(tick - 1)) {
+            host.poll(0).unwrap();
+            client.poll(0).unwrap();
+        }
+        assert!(!host.can_step(tick));
+        let expected = vec![Command::SpawnBlob, Command::SpawnFood(Vector2::new(1., 2.))];
+        assert_eq!(host.take_commands(tick), expected);
+        assert_eq!(client.take_commands(tick), expected);
+
+        host.record_hash(0, 1);
+        client.record_hash(0, 2);
+        while host.desync().is_none() || client.desync().is_none() {
+            host.poll(tick).unwrap();
+            client.poll(tick).unwrap();
+        }
+        assert_eq!(host.desync(), Some(0));
+
+        //  a peer sending a command outside the world is dropped before it is applied
+        client.schedule(Command::SpawnFood(Vector2::new(f32::NAN, 0.)), tick);
+        let error = loop {
+            client.poll(tick).unwrap();
+            if let Err(e) = host.poll(tick) {
+                break e;
+            }
+        };
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(host.take_commands(tick + Session::DELAY).is_empty());
+
+        //  as is one sending a command for a tick it said it had none for, or a line without end
+        let past = |client: &mut Session| client.send(Message::Command(Session::DELAY - 1, Command::SpawnBlob));
+        let endless = |client: &mut Session| client.outgoing.extend(vec![b'0'; 2 * Session::MAX_LINE]);
+        for misbehave in [&past as &dyn Fn(&mut Session), &endless] {
+            let client = std::thread::spawn(move || Session::connect(address, 9).unwrap().0);
+            let mut host = Session::accept(&listener, &setup, 9).unwrap();
+            let mut client = client.join().unwrap();
+            client.poll(0).unwrap();
+            misbehave(&mut client);
+            let error = loop {
+                client.poll(0).unwrap();
+                if let Err(e) = host.poll(0) {
+                    break e;
+                }
+            };
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(host.take_commands(Session::DELAY - 1).is_empty());
+        }
+
+        //  a host with a timing out of range is refused before anything is stepped
+        let timing = Timing { tick_rate: 0, ..Timing::default() };
+        let client = std::thread::spawn(move || Session::connect(address, 9).err().unwrap());
+        let _host = Session::accept(&listener, &Setup { timing, ..setup.clone() }, 9).unwrap();
+        let error = client.join().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("tick rate"), "{}", error);
+
+        //  as is one with other content, which would spawn other blobs
+        let client = std::thread::spawn(move || Session::connect(address, 9).err().unwrap());
+        let _host = Session::accept(&listener, &setup, 10).unwrap();
+        let error = client.join().unwrap();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("species"), "{}", error);
+    }
+}
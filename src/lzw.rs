@@ -0,0 +1,164 @@
+//! The variable width LZW compression of GIF, for bytes of any kind.
+//!
+//! Codes start a bit wider than a byte and widen as the table of
+//! strings grows, up to 12 bits, after which the table is cleared and
+//! built again. GIF images are compressed this way, and so are other
+//! files that are mostly the same few lines over and over, such as
+//! bundled replays.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::lzw;
+//! let text = "tick 1\ntick 2\ntick 3\n";
+//! let compressed = lzw::compress(text.as_bytes());
+//! assert_eq!(lzw::decompress(&compressed)?, text.as_bytes());
+//! # Ok::<(), String>(())
+//! ```
+
+use std::collections::HashMap;
+
+/// Bits per symbol, as a GIF image header gives it.
+pub const MIN_CODE_SIZE: u8 = 8;
+/// The largest code LZW may use.
+const MAX_CODE: u16 = 4095;
+const CLEAR: u16 = 1 << MIN_CODE_SIZE;
+const END: u16 = CLEAR + 1;
+
+/// Packs variable width codes into bytes, least significant bit first.
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// Compress bytes, ending with the end code.
+pub fn compress(bytes: &[u8]) -> Vec<u8> {
+    let mut out = BitWriter { bytes: vec![], buffer: 0, bits: 0 };
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut size = MIN_CODE_SIZE + 1;
+    let mut next = END + 1;
+    out.write(CLEAR, size);
+
+    let mut bytes = bytes.iter();
+    let mut current = match bytes.next() {
+        Some(&byte) => byte as u16,
+        None => {
+            out.write(END, size);
+            return out.finish();
+        },
+    };
+    for &byte in bytes {
+        if let Some(&code) = table.get(&(current, byte)) {
+            current = code;
+            continue;
+        }
+        out.write(current, size);
+        if next <= MAX_CODE {
+            table.insert((current, byte), next);
+            next += 1;
+            //  the decoder adds each entry a code later, and widens its codes then
+            if next > 1 << size && size < 12 {
+                size += 1;
+            }
+        } else {
+            out.write(CLEAR, size);
+            table.clear();
+            size = MIN_CODE_SIZE + 1;
+            next = END + 1;
+        }
+        current = byte as u16;
+    }
+    out.write(current, size);
+    //  the decoder adds an entry for the last code too
+    if next <= MAX_CODE && next + 1 > 1 << size && size < 12 {
+        size += 1;
+    }
+    out.write(END, size);
+    out.finish()
+}
+
+/// Decompress what `compress` wrote, failing on anything else.
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut pos = 0;
+    let mut read = |size: u8| -> Result<u16, String> {
+        if pos + size as usize > 8 * bytes.len() {
+            return Err("the data ends before its end code".to_string());
+        }
+        let mut code = 0;
+        for i in 0..size as usize {
+            let bit = (bytes[(pos + i) / 8] >> ((pos + i) % 8)) & 1;
+            code |= (bit as u16) << i;
+        }
+        pos += size as usize;
+        Ok(code)
+    };
+    let mut out = vec![];
+    //  strings by code, with empty ones for the clear and end codes
+    let mut table: Vec<Vec<u8>> = vec![];
+    let mut size = MIN_CODE_SIZE + 1;
+    let mut previous: Option<Vec<u8>> = None;
+    loop {
+        let code = read(size)?;
+        if code == CLEAR {
+            table = (0..CLEAR).map(|i| vec![i as u8]).collect();
+            table.push(vec![]);
+            table.push(vec![]);
+            size = MIN_CODE_SIZE + 1;
+            previous = None;
+            continue;
+        }
+        if code == END { return Ok(out); }
+        let entry = match (table.get(code as usize), &previous) {
+            (Some(entry), _) if !entry.is_empty() => entry.clone(),
+            (None, Some(previous)) if code as usize == table.len() => [&previous[..], &previous[..1]].concat(),
+            _ => return Err(format!("unexpected code {}", code)),
+        };
+        if let Some(previous) = previous {
+            if table.len() <= MAX_CODE as usize {
+                table.push([&previous[..], &entry[..1]].concat());
+            }
+            if table.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+        }
+        out.extend_from_slice(&entry);
+        previous = Some(entry);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lzw() {
+        let noise: Vec<u8> = (0..20_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+        let stripes: Vec<u8> = (0..20_000u32).map(|i| (i / 37 % 5) as u8).collect();
+        for bytes in &[vec![], vec![7], vec![1; 1000], noise, stripes] {
+            assert_eq!(&decompress(&compress(bytes)).unwrap(), bytes);
+        }
+        let compressed = compress(b"frame time=1 mouse=0,0\n");
+        assert!(decompress(&compressed[..compressed.len() - 2]).is_err());
+        assert!(decompress(&[0xff; 4]).is_err());
+    }
+}
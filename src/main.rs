@@ -1,54 +1,697 @@
-mod keyed_set;
-mod window;
-mod physics;
-mod simulation;
-mod math;
-
 use std::{
+    env,
+    panic,
+    process,
     time,
     io,
     fs,
+    mem,
     path,
-    collections::HashMap,
+    thread,
+    net::TcpListener,
+    sync::{Arc, Mutex, mpsc},
+    collections::{BTreeSet, HashMap, HashSet, VecDeque},
 };
 
-use rand::{random, seq::SliceRandom};
+use rand::{random, seq::SliceRandom, Rng};
 
 use raylib::prelude::*;
 
-use crate::{
-    window::prelude::*,
+use blobs::{
+    keyed_set, window, lockstep, shapes, inspector, narrator, memory, shutdown, crash, golden,
+    input, exchange, leaderboard, tournament, food_web, spatial, runs, ai_view, anomalies, tour,
+    whatif, svg, quality, benchmark, realtime, group, replay, light, naming, tags, watch,
+    drones, influence, trace, aura, power, placement, stream, chat, vision, sankey, sqlite,
+    scenario, scenario_editor, spawning, recent, overrides, keyboard, fixture, foraging, zones,
+    bench, memorial, weather, chunk, content, doctor, bubbles, records, shell, roster, handle,
+    senses, report,
+    add_random_blob, add_random_food, apply_command,
+    window::{prelude::*, Camera},
     simulation::prelude::*,
+    clock::Interval,
+    species::Species,
+    mods::Mod,
+    mutators::Mutator,
+    cli::{Options, Network},
+    stats::{StatsStore, MarkKind},
+    lockstep::{Command, Session, Setup},
+    theme::Theme,
+    fonts::Fonts,
+    recorder::Recorder,
+    narrator::Narrator,
+    sonification::Sonification,
+    memory::{Usage, Budget},
+    input::Frame,
+    exchange::Exported,
+    leaderboard::Summary,
+    tournament::{Arena, Contestant},
+    foraging::Benchmark,
+    bench::Bench,
+    zones::Zone,
+    scripts::{Runner, Step},
+    food_web::FoodWeb,
+    spawning::{Spawner, Uniform},
+    sankey::Sankey,
+    histogram::HistogramView,
+    loading::Loading,
+    recent::Recent,
+    dropping::Dropped,
+    keyboard::{Cursor, Palette},
+    fixture::{Capture, Fixture},
+    chunk::Chunk,
+    autopause::AutoPause,
+    content::{Browser, Catalog, Settings},
+    doctor::{Check, Status},
+    spatial::Overlay,
+    world_code::WorldCode,
+    tuning::TuningPanel,
+    anomalies::Detector,
+    tour::{Tour, Keyframe},
+    viewpoints::{Viewpoint, Viewpoints},
+    influence::Influence,
+    floating_numbers::FloatingNumbers,
+    bubbles::{Bubbles, Pack},
+    throttle::Throttle,
+    handle::CommandQueue,
+    senses::Senses,
+    style::{Choice, Style},
+    naming::{Naming, NamingRule},
+    power::{LowPower, Unfocused},
+    screensaver::Screensaver,
+    director::Director,
+    stream::StatsFile,
+    shared_state::SharedState,
+    chat::{Chat, Intervention},
+    whatif::{Prediction, Outcome},
+    fertility::FertilityMap,
+    genealogy::Genealogy,
+    scenario::{Scenario, Action},
+    scenario_editor::ScenarioEditor,
+    math::FloatMode,
+    quality::{AutoQuality, Tier},
+    benchmark::{Measurement, Recommendation},
+    realtime::Governor,
+    group::GroupStats,
+    replay::Bundle,
+    observer::{Changes, Interest, Observer},
+    hooks::HookId,
 };
 
-fn random_vector2() -> Vector2 { Vector2::new(random(), random()) }
-fn random_color() -> Color { Color::new(random(), random(), random(), 255) }
-
-fn add_random_blob(sim: &mut Simulation, names: &mut Vec<String>) -> keyed_set::Key<Blob> {
-    let key = sim.insert_blob(
-        random_vector2() * sim.size(),
-        20. * random::<f32>(),
-        random_color(),
-        120. * random::<f32>(),
-        5. * random::<f32>(),
-        180f32 * random::<f32>(),
-        170f32 * random::<f32>(),
-        random_color(),
-        random(),
-        random(),
-        25. * random::<f32>(),
-        random::<f32>(),
-        2. * random::<f32>(),
-        0.5 * random::<f32>(),
-        random::<f32>(),
-    );
-    let name = names.choose(&mut rand::thread_rng()).unwrap().to_string();
-    sim.get_blob_mut(key).unwrap().name = Some(name);
-    key
+fn random_vector2<R: Rng + ?Sized>(rng: &mut R) -> Vector2 { Vector2::new(rng.gen(), rng.gen()) }
+
+/// Where recorded input goes: straight to a file, or kept to be
+/// bundled with its setup when the run ends, see `replay`.
+enum InputLog {
+    File(io::BufWriter<fs::File>),
+    Bundle(String, Vec<u8>),
+}
+
+impl InputLog {
+    /// End the recording with what the run came to, writing the bundle
+    /// with the setup and content it was recorded with if it is one.
+    fn finish(&mut self, metadata: &runs::Metadata, world: WorldCode, compare: Option<(String, String)>, mods: Vec<(String, u64)>) -> io::Result<()> {
+        input::write_metadata(self, metadata)?;
+        match self {
+            Self::File(out) => io::Write::flush(out),
+            Self::Bundle(path, recording) => {
+                let recording = mem::take(recording);
+                let bundle = Bundle { program: runs::VERSION.to_string(), world, compare, mods, recording };
+                fs::File::create(path).map(io::BufWriter::new).and_then(|mut out| bundle.write(&mut out))
+            },
+        }
+    }
+}
+
+impl io::Write for InputLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::File(out) => out.write(buf),
+            Self::Bundle(_, recording) => recording.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::File(out) => out.flush(),
+            Self::Bundle(..) => Ok(()),
+        }
+    }
+}
+
+/// Apply what a viewer asked for in chat to a simulation, returning
+/// the blob spawned, if one was.
+fn apply_intervention(sim: &mut Simulation, intervention: &Intervention, names: &[String], species: &[Species]) -> Option<keyed_set::Key<Blob>> {
+    const FEED_FOODS: usize = 10;
+    const FEED_SPREAD: f32 = 40.;
+    match intervention {
+        Intervention::Spawn(name) => {
+            let key = add_random_blob(sim, names, species, &Uniform);
+            if let Some(name) = name {
+                sim.get_blob_mut(key).unwrap().name = Some(name.clone());
+            }
+            Some(key)
+        },
+        Intervention::Feed => {
+            let center = random_vector2(sim.rng()) * sim.size();
+            for _ in 0..FEED_FOODS {
+                let offset = (random_vector2(sim.rng()) * 2. - Vector2::one()) * FEED_SPREAD;
+                let pos = center + offset;
+                let pos = Vector2::new(pos.x.max(0.).min(sim.size().x), pos.y.max(0.).min(sim.size().y));
+                sim.insert_food(pos);
+            }
+            None
+        },
+        Intervention::Smite => {
+            let keys: Vec<_> = sim.blobs().map(|(key, _)| key).collect();
+            if let Some(&key) = keys.choose(sim.rng()) {
+                sim.remove_blob(key);
+            }
+            None
+        },
+    }
+}
+
+/// A simulation and the timers that keep adding to it.
+struct World {
+    sim: Simulation,
+    food_interval: Interval,
+    blob_interval: Interval,
+    /// Shown over the world when several are on screen.
+    label: String,
+    anomalies: Detector,
+    /// Where food grows, shared with forks.
+    fertility: Option<Arc<FertilityMap>>,
+    /// Whether random blobs spawn, see `realtime`.
+    spawning: bool,
+    /// The population below which random blobs spawn, if only below one.
+    min_population: Option<usize>,
+    /// The hook of an observer following what changes every tick, see
+    /// `observer`, and what it saw in the ticks since they were last written.
+    observer: Option<([HookId; 2], mpsc::Receiver<Changes>)>,
+    /// The family tree recorded by hooks while asked for, see `genealogy`.
+    genealogy: Option<Arc<Mutex<Genealogy>>>,
+    /// The energy changes rising from blobs, while they are shown.
+    numbers: Option<FloatingNumbers>,
+    /// What blobs say as they start something else, while it is shown.
+    bubbles: Option<Bubbles>,
+    /// The events shown as notices and written to the log, thinned
+    /// for each, see `throttle`.
+    toasts: Throttle,
+    log: Throttle,
+    /// What other threads sent it and who listens, see `handle`.
+    commands: CommandQueue,
+    /// How children are named, see `naming`.
+    naming: Naming,
+    /// What moves the camera while it is on, see `director`.
+    director: Option<Director>,
+    /// The scenario scheduling what is added, shared with forks.
+    scenario: Option<Arc<Scenario>>,
+    /// Where blobs and food are added, see `spawning`.
+    blob_spawner: Arc<dyn Spawner>,
+    food_spawner: Arc<dyn Spawner>,
+    /// What runs the scripts of the scenario, see `scripts`.
+    scripts: Runner,
+    /// The latest message of a script, until it is shown.
+    message: Option<String>,
+    /// What pauses the run by itself, see `autopause`.
+    autopause: AutoPause,
+}
+
+impl World {
+    const FOOD_ADD_DELAY: f64 = 0.2;
+    const BLOB_ADD_DELAY: f64 = 0.5;
+    const START_BLOBS: usize = 10;
+    const START_FOODS: usize = 100;
+
+    fn new(config: SimulationConfig, label: String) -> Self {
+        let sim = Simulation::new(config);
+        Self {
+            food_interval: Interval::new(Self::FOOD_ADD_DELAY / sim.parameters().food_rate as f64),
+            blob_interval: Interval::new(Self::BLOB_ADD_DELAY),
+            sim,
+            label,
+            anomalies: Detector::new(anomalies::DEFAULT_SIGMA),
+            fertility: None,
+            spawning: true,
+            min_population: None,
+            observer: None,
+            genealogy: None,
+            numbers: None,
+            bubbles: None,
+            toasts: Throttle::default(),
+            log: Throttle::default(),
+            commands: CommandQueue::default(),
+            naming: Naming::default(),
+            director: None,
+            scenario: None,
+            blob_spawner: Arc::new(Uniform),
+            food_spawner: Arc::new(Uniform),
+            scripts: Runner::default(),
+            message: None,
+            autopause: AutoPause::default(),
+        }
+    }
+
+    /// Populate the simulation as every world starts.
+    fn populate(&mut self, names: &[String], species: &[Species]) {
+        for _ in 0..Self::START_BLOBS {
+            add_random_blob(&mut self.sim, names, species, self.blob_spawner.as_ref());
+        }
+        let zones = self.scenario.as_ref().map_or(&[][..], |scenario| &scenario.zones);
+        for _ in 0..Self::START_FOODS {
+            add_random_food(&mut self.sim, self.fertility.as_deref(), self.food_spawner.as_ref(), zones);
+        }
+    }
+
+    /// An independent copy of the world, see `Simulation::fork`.
+    fn fork(&self) -> Self {
+        Self {
+            sim: self.sim.fork(),
+            food_interval: self.food_interval.clone(),
+            blob_interval: self.blob_interval.clone(),
+            label: self.label.clone(),
+            anomalies: self.anomalies.clone(),
+            fertility: self.fertility.clone(),
+            spawning: self.spawning,
+            min_population: self.min_population,
+            observer: None,
+            genealogy: None,
+            numbers: None,
+            bubbles: None,
+            toasts: Throttle::default(),
+            log: Throttle::default(),
+            commands: CommandQueue::default(),
+            naming: self.naming.clone(),
+            director: None,
+            scenario: self.scenario.clone(),
+            blob_spawner: self.blob_spawner.clone(),
+            food_spawner: self.food_spawner.clone(),
+            scripts: self.scripts.clone(),
+            message: None,
+            autopause: self.autopause.clone(),
+        }
+    }
+
+    /// Advance the simulation by one tick and add what is due.
+    fn tick(&mut self, names: &[String], species: &[Species]) {
+        let tick = self.sim.clock().tick();
+        //  what other threads sent, between ticks, see `handle`
+        for command in self.commands.take() {
+            //  a NaN position or an infinite radius would break the tick
+            if let Err(e) = command.validate(self.sim.size()) {
+                eprintln!("{}: dropped {}: {}", self.label, command, e);
+                continue;
+            }
+            self.sim.spawning_as(Source::Embedder, |sim| apply_command(sim, command, names, species));
+        }
+        self.sim.step();
+        self.commands.publish(&self.sim, tick);
+        //  a blob a bug broke is set straight by the world, but the bug is worth knowing of, as are alerts
+        for passed in self.log.filter(&self.sim, tick, self.sim.clock().time(), None) {
+            let more = if passed.held > 0 { format!(", and {} more", passed.held) } else { String::new() };
+            match passed.event {
+                Event::Quarantined { blob, problem, removed } => {
+                    let fate = if *removed { "removed" } else { "set straight" };
+                    eprintln!("{}: tick {}: blob {} {} for {}{}", self.label, passed.tick, blob.as_deref().unwrap_or("unnamed"), fate, problem, more);
+                },
+                Event::Alert { region, alert, value } => eprintln!("{}: tick {}: {} in {} at {}{}", self.label, passed.tick, alert, region, value, more),
+                _ => (),
+            }
+        }
+        if let Some(e) = self.sim.history_mut().take_spill_error() {
+            eprintln!("{}: stopped writing the events: {}", self.label, e);
+        }
+        if let Some(numbers) = &mut self.numbers {
+            numbers.collect(&self.sim);
+        }
+        if let Some(bubbles) = &mut self.bubbles {
+            bubbles.collect(&self.sim);
+        }
+        if let Some(director) = &mut self.director {
+            director.collect(&self.sim, tick);
+        }
+        //  children are born nameless, and named by the rule
+        let born: Vec<_> = self.sim.events_since(tick)
+            .filter_map(|(_, event)| match event { Event::Born(key) => Some(*key), _ => None })
+            .filter_map(|key| self.sim.get_blob(key).filter(|blob| blob.name.is_none()).map(|blob| (key, blob.parent, blob.generation)))
+            .collect();
+        for (key, parent, generation) in born {
+            let parent = parent.and_then(|parent| self.sim.get_blob(parent)).and_then(|parent| Some((parent.name.clone()?, parent.generation)));
+            let parent = parent.as_ref().map(|(name, generation)| (name.as_str(), *generation));
+            let name = self.naming.child_name(parent, generation, names, self.sim.rng());
+            self.sim.get_blob_mut(key).unwrap().name = name;
+        }
+        //  a scenario makes random blobs and food come more or less often, and events and scripts happen
+        if let Some(scenario) = self.scenario.clone() {
+            for step in self.scripts.fire(&scenario.scripts, &self.sim, tick) {
+                match step {
+                    Step::Message(text) => {
+                        self.sim.mark(&format!("script: {}", text));
+                        self.message = Some(text);
+                    },
+                    Step::Act(action) => {
+                        self.apply(&action, names, species);
+                        self.sim.mark(&format!("script: {} {}", action.name(), action.amount()));
+                    },
+                }
+            }
+            let tick = self.sim.clock().tick();
+            for event in scenario.events_at(tick) {
+                self.apply(&event.action, names, species);
+                self.sim.mark(&event.label());
+            }
+            self.blob_interval.set_period(Self::BLOB_ADD_DELAY / scenario.blobs.rate(tick) as f64);
+            self.food_interval.set_period(Self::FOOD_ADD_DELAY / self.sim.parameters().food_rate as f64 / scenario.food.rate(tick) as f64);
+        }
+        let short = self.min_population.is_none_or(|min| self.sim.blobs().count() < min);
+        if self.blob_interval.poll(self.sim.clock()) && self.spawning && short {
+            add_random_blob(&mut self.sim, names, species, self.blob_spawner.as_ref());
+        }
+        if self.food_interval.poll(self.sim.clock()) {
+            let zones = self.scenario.as_ref().map_or(&[][..], |scenario| &scenario.zones);
+            add_random_food(&mut self.sim, self.fertility.as_deref(), self.food_spawner.as_ref(), zones);
+        }
+    }
+
+    /// Do what a timed event of the scenario does.
+    fn apply(&mut self, action: &Action, names: &[String], species: &[Species]) {
+        let fertility = self.fertility.as_deref();
+        let (blob_spawner, food_spawner) = (self.blob_spawner.as_ref(), self.food_spawner.as_ref());
+        let zones = self.scenario.as_ref().map_or(&[][..], |scenario| &scenario.zones);
+        match *action {
+            Action::Feed(count) => self.sim.spawning_as(Source::Scenario, |sim| {
+                for _ in 0..count {
+                    add_random_food(sim, fertility, food_spawner, zones);
+                }
+            }),
+            Action::Spawn(count) => self.sim.spawning_as(Source::Scenario, |sim| {
+                for _ in 0..count {
+                    add_random_blob(sim, names, species, blob_spawner);
+                }
+            }),
+            Action::Cull(fraction) => {
+                let mut keys: Vec<_> = self.sim.blobs().map(|(key, _)| key).collect();
+                let count = (keys.len() as f32 * fraction).round() as usize;
+                keys.shuffle(self.sim.rng());
+                for key in keys.into_iter().take(count) {
+                    self.sim.remove_blob(key);
+                }
+            },
+        }
+    }
+
+    /// Predict the world without the blobs removed on a thread of its
+    /// own, see `whatif`.
+    fn predict(&self, intervention: String, removed: &[keyed_set::Key<Blob>], names: &[String], species: &[Species]) -> Prediction {
+        let mut fork = self.fork();
+        for &key in removed {
+            fork.sim.remove_blob(key);
+        }
+        let ticks = whatif::SECONDS as u64 * fork.sim.timing().tick_rate as u64;
+        let (names, species) = (names.to_vec(), species.to_vec());
+        Prediction::start(intervention, Outcome::of(&self.sim), move || {
+            for _ in 0..ticks {
+                fork.tick(&names, &species);
+            }
+            Outcome::of(&fork.sim)
+        })
+    }
+
+    /// Carry on from a loaded simulation, following it as the options
+    /// say, since hooks stay with the simulation they were registered on.
+    fn replace(&mut self, sim: Simulation, options: &Options) {
+        self.sim = sim;
+        self.anomalies = Detector::new(options.anomaly_sigma);
+        if options.observe.is_some() {
+            self.observe(options.interest.clone());
+        }
+        if options.genealogy.is_some() {
+            self.record_genealogy();
+        }
+    }
+
+    /// Follow the blobs of an interest after every tick of the simulation.
+    fn observe(&mut self, interest: Interest) {
+        let (sender, receiver) = mpsc::channel();
+        let observer = Arc::new(Mutex::new(Observer::new(interest)));
+        let noted = observer.clone();
+        let removal = self.sim.on_entity_removed(0, Box::new(move |_, removed| noted.lock().unwrap().note_removed(removed)));
+        let tick = self.sim.on_post_tick(0, Box::new(move |sim| {
+            let changes = observer.lock().unwrap().observe(sim);
+            if !changes.is_empty() {
+                //  the world may have stopped listening
+                let _ = sender.send(changes);
+            }
+        }));
+        self.observer = Some(([removal, tick], receiver));
+    }
+
+    /// Record the family tree of the world from now on, see `genealogy`.
+    fn record_genealogy(&mut self) {
+        let genealogy = Arc::new(Mutex::new(Genealogy::default()));
+        let noted = genealogy.clone();
+        self.sim.on_entity_removed(0, Box::new(move |sim, removed| noted.lock().unwrap().note_removed(sim, removed)));
+        let recorded = genealogy.clone();
+        self.sim.on_post_tick(0, Box::new(move |sim| recorded.lock().unwrap().record(sim)));
+        self.genealogy = Some(genealogy);
+    }
+
+    /// Write the state after every tick to shared memory, see `shared_state`.
+    fn share(&mut self, shared: SharedState) {
+        let mut shared = Some(shared);
+        self.sim.on_post_tick(0, Box::new(move |sim| {
+            if let Some(Err(e)) = shared.as_mut().map(|shared| shared.write(sim)) {
+                eprintln!("failed to write the shared state, stopping: {}", e);
+                shared = None;
+            }
+        }));
+    }
+
+    /// Stop following what changes.
+    fn stop_observing(&mut self) {
+        if let Some((hooks, _)) = self.observer.take() {
+            for hook in hooks {
+                self.sim.unregister_hook(hook);
+            }
+        }
+    }
+
+    /// Write what the observer saw since the last call.
+    fn write_changes(&mut self, out: &mut dyn io::Write) -> io::Result<()> {
+        if let Some((_, receiver)) = &self.observer {
+            for changes in receiver.try_iter() {
+                changes.write(out)?;
+            }
+        }
+        out.flush()
+    }
+}
+
+/// Create and populate the worlds of a setup, a second one if a parameter is compared.
+fn create_worlds(
+    setup: &Setup,
+    options: &Options,
+    fertility: Option<&Arc<FertilityMap>>,
+    scenario: Option<&Arc<Scenario>>,
+    naming: &Naming,
+    names: &[String],
+    species: &[Species],
+) -> Vec<World> {
+    let config = SimulationConfig {
+        seed: setup.seed,
+        mutators: setup.mutators.clone(),
+        timing: setup.timing,
+        audit_energy: options.audit_energy,
+        //  peers may run on different platforms
+        float_mode: if options.deterministic_math || options.network.is_some() { FloatMode::Deterministic } else { FloatMode::Native },
+        lights: options.lights.clone(),
+        event_capacity: options.event_history,
+        ..SimulationConfig::new(setup.size)
+    };
+    let mut worlds = vec![World::new(config.clone(), "A".to_string())];
+    if let Some((name, value)) = &options.compare {
+        let mut config = config;
+        config.parameters.set(name, value).unwrap();
+        worlds.push(World::new(config, format!("B: {}={}", name, value)));
+    }
+    //  read again each time, so the worlds start over with the file as it is
+    let roster = options.import_csv.as_ref()
+        .map(|path| roster::load(path).unwrap_or_else(|e| panic!("failed to import the blobs: {}", e)));
+    //  checked when the options were parsed
+    let spawner = |text: &Option<String>| text.as_deref().map_or_else(|| Arc::new(Uniform) as Arc<dyn Spawner>, |text| spawning::parse(text).unwrap());
+    for world in &mut worlds {
+        world.fertility = fertility.cloned();
+        world.scenario = scenario.cloned();
+        world.blob_spawner = spawner(&options.blob_spawner);
+        world.food_spawner = spawner(&options.food_spawner);
+        world.naming = naming.clone();
+        world.populate(names, species);
+        if let Some(roster) = &roster {
+            roster::insert(&mut world.sim, roster);
+        }
+        world.anomalies = Detector::new(options.anomaly_sigma);
+        world.min_population = options.min_population;
+        world.autopause = AutoPause::new(options.pause_on.clone());
+        for region in &options.watches {
+            world.sim.watch(region.clone());
+        }
+    }
+    if let Some(path) = &options.event_spill {
+        let count = worlds.len();
+        for world in &mut worlds {
+            let path = labeled_path(path, &world.label, count);
+            if let Err(e) = world.sim.history_mut().spill_to(&path.to_string_lossy()) {
+                eprintln!("failed to write the events to {}: {}", path.display(), e);
+            }
+        }
+    }
+    //  only the first world is observed and shared, as it is narrated
+    if options.observe.is_some() {
+        worlds[0].observe(options.interest.clone());
+    }
+    if options.genealogy.is_some() {
+        for world in &mut worlds {
+            world.record_genealogy();
+        }
+    }
+    if let Some(path) = &options.shared_state {
+        let shared = SharedState::create(path, setup.size).unwrap_or_else(|e| panic!("failed to create {}: {}", path, e));
+        worlds[0].share(shared);
+    }
+    worlds
+}
+
+/// A file to write to, or the standard output for `-`.
+/// A file for each world when there are several, told apart by the
+/// first letter of their labels before the extension.
+fn labeled_path(path: &str, label: &str, worlds: usize) -> path::PathBuf {
+    let path = path::Path::new(path);
+    if worlds == 1 {
+        return path.to_path_buf();
+    }
+    let stem = format!("{}-{}", path.file_stem().unwrap_or_default().to_string_lossy(), &label[..1]);
+    match path.extension() {
+        Some(extension) => path.with_file_name(format!("{}.{}", stem, extension.to_string_lossy())),
+        None => path.with_file_name(stem),
+    }
+}
+
+fn create_output(path: &str) -> Box<dyn io::Write> {
+    match path {
+        "-" => Box::new(io::stdout()),
+        path => Box::new(fs::File::create(path).map(io::BufWriter::new).unwrap_or_else(|e| panic!("failed to create {}: {}", path, e))),
+    }
+}
+
+/// The part of the window the world with the given index is drawn on.
+fn viewport(index: usize, count: usize, screen_width: i32, screen_height: i32) -> Rectangle {
+    let width = screen_width as f32 / count as f32;
+    Rectangle::new(index as f32 * width, 0., width, screen_height as f32)
+}
+
+/// The naming of the options, with the names of its pack, or of the
+/// pack chosen in the content browser if it is still there.
+fn load_naming(options: &Options, settings: &Settings, loaded_mods: &[Mod]) -> Naming {
+    let chosen = settings.name_pack()
+        .filter(|&pack| pack == content::BASE || loaded_mods.iter().any(|m| m.name == pack && !m.names.is_empty()));
+    let pack = options.name_pack.as_deref().or(chosen).map(|pack| {
+        let names = match pack {
+            content::BASE => read_names("names.txt").unwrap(),
+            pack => loaded_mods.iter().find(|m| m.name == pack)
+                .unwrap_or_else(|| panic!("no mod named {} to draw names from", pack))
+                .names.clone(),
+        };
+        if names.is_empty() {
+            panic!("the name pack {} has no names", pack);
+        }
+        names.into()
+    });
+    Naming { rule: options.naming, pack }
+}
+
+/// The scenario of the options, or the one chosen in the content
+/// browser if it can still be read.
+fn load_scenario(options: &Options, settings: &Settings) -> Option<Arc<Scenario>> {
+    match (&options.scenario, settings.scenario()) {
+        (Some(scenario), _) => Some(Arc::new(Scenario::load(&scenario::resolve(scenario)).unwrap_or_else(|e| panic!("failed to load the scenario: {}", e)))),
+        (None, Some(chosen)) => Scenario::load(&scenario::resolve(chosen))
+            .map_err(|e| eprintln!("failed to load the scenario chosen, leaving it out: {}", e))
+            .ok()
+            .map(Arc::new),
+        (None, None) => None,
+    }
+}
+
+/// The content settings of a run, none where it must start from the
+/// files alone, see `content`.
+fn content_settings(options: &Options) -> Settings {
+    if options.network.is_some() || options.record_input.is_some() || options.play_input.is_some() {
+        return Settings::default();
+    }
+    Settings::load(content::PATH).unwrap_or_else(|e| {
+        eprintln!("failed to read the content settings, leaving them out: {}", e);
+        Settings::default()
+    })
+}
+
+/// Start the lockstep session of the options, taking the setup of the
+/// host when connecting to one.
+fn open_session(network: &Network, setup: &mut Setup, content: u64) -> Session {
+    match network {
+        Network::Host(port) => {
+            let listener = TcpListener::bind(("0.0.0.0", *port))
+                .unwrap_or_else(|e| panic!("failed to listen on port {}: {}", port, e));
+            println!("Waiting for a peer on port {}", port);
+            Session::accept(&listener, setup, content).unwrap_or_else(|e| panic!("failed to accept a peer: {}", e))
+        },
+        Network::Connect(address) => {
+            let (session, host_setup) = Session::connect(address.as_str(), content)
+                .unwrap_or_else(|e| panic!("failed to connect to {}: {}", address, e));
+            *setup = host_setup;
+            session
+        },
+    }
 }
 
-fn add_random_food(sim: &mut Simulation) -> keyed_set::Key<Food> {
-    sim.insert_food(random_vector2() * sim.size())
+/// Read recorded input to replay, taking the setup it was recorded in,
+/// along with the bundle it came in if it did.
+fn read_playback(path: &str, setup: &mut Setup, code_ticks: &mut u64) -> (VecDeque<Frame>, Option<Bundle>) {
+    let mut bundle = None;
+    let recording = if replay::is_bundle(path) {
+        let read = fs::read(path).map_err(|e| e.to_string()).and_then(|bytes| Bundle::read(&bytes))
+            .unwrap_or_else(|e| panic!("failed to read the replay {}: {}", path, e));
+        *setup = read.world.setup.clone();
+        *code_ticks = read.world.tick;
+        let recording = input::read(&read.recording[..])
+            .unwrap_or_else(|e| panic!("failed to read input from {}: {}", path, e));
+        bundle = Some(read);
+        recording
+    } else {
+        fs::File::open(path)
+            .and_then(|file| input::read(io::BufReader::new(file)))
+            .unwrap_or_else(|e| panic!("failed to read input from {}: {}", path, e))
+    };
+    match &recording.metadata {
+        Some(metadata) if metadata.version != runs::VERSION => {
+            eprintln!("{} was recorded with version {}, and may play out differently in {}", path, metadata.version, runs::VERSION);
+        },
+        _ => (),
+    }
+    setup.seed = recording.seed;
+    (recording.frames.into(), bundle)
+}
+
+/// Start recording input to a file, or to a bundle written as the run
+/// ends.
+fn open_input_log(path: &str, seed: u64) -> InputLog {
+    let mut out = if replay::is_bundle(path) {
+        InputLog::Bundle(path.to_string(), vec![])
+    } else {
+        InputLog::File(fs::File::create(path).map(io::BufWriter::new)
+            .unwrap_or_else(|e| panic!("failed to create {}: {}", path, e)))
+    };
+    input::write_header(&mut out, seed).unwrap_or_else(|e| panic!("failed to write to {}: {}", path, e));
+    out
 }
 
 fn read_names<P: AsRef<path::Path> + ?Sized>(path: &P) -> io::Result<Vec<String>> {
@@ -56,94 +699,3167 @@ fn read_names<P: AsRef<path::Path> + ?Sized>(path: &P) -> io::Result<Vec<String>
     Ok(content.split_whitespace().map(|x| x.to_string()).collect())
 }  
 
-struct Selection {
+/// Blobs grabbed by a drag gesture and where they were grabbed.
+struct Drag {
+    world: usize,
     start_mouse_pos: Vector2,
     blobs: HashMap<keyed_set::Key<Blob>, Vector2>,
 }
 
-fn main() {
-    //  options
-    let food_add_delay = time::Duration::from_secs_f32(0.2);
-    let blob_add_delay = time::Duration::from_secs_f32(0.5);
-    let start_blobs = 10;
-    let start_foods = 100;
-    let window_config = WindowConfig {
-        width: 1300,
-        height: 680,
-        title: "Blobs",
-    }; 
+/// The state of mouse gestures between frames.
+struct Interaction {
+    //  selected blobs along with the index of their world
+    selected: HashSet<(usize, keyed_set::Key<Blob>)>,
+    drag: Option<Drag>,
+    last_paint_pos: Vector2,
+    //  a region drawn to watch, in a world, with its name and alerts while they are typed
+    watching: Option<(usize, Rectangle, String)>,
+}
 
-    //  allocate resources
-    let mut window = Window::new(&window_config);
-    let mut sim = Simulation::new(Vector2::new(window.width() as f32, window.height() as f32));
-    let mut food_add_time = time::Instant::now(); 
-    let mut blob_add_time = time::Instant::now(); 
-    let mut names = read_names("names.txt").unwrap();
-    
-    //  initialize simulation
-    for _ in 0..start_blobs {
-        let blob_key = add_random_blob(&mut sim, &mut names);
-    }
-    //  initialize simulation
-    for _ in 0..start_foods {
-        add_random_food(&mut sim);
-    }
-
-    let mut last_frame_time = time::Instant::now();
-    let mut selection: Option<Selection> = None;
-    window.draw_loop(|mut draw| {
-        //  record time and calculate delta
-        let frame_time = time::Instant::now();
-        let delta_time = (frame_time - last_frame_time).as_secs_f32();
-        last_frame_time = frame_time;
-        //  draw and simulate
-        draw.clear_background(Color::WHITE);
-        sim.draw(&mut draw);
-        sim.step(delta_time);
+impl Interaction {
+    /// The distance between food painted in one stroke.
+    const PAINT_SPACING: f32 = 3. * Food::RADIUS;
+    /// How near, in screen pixels, a click must come to a blob to pick it.
+    const PICK_DISTANCE: f32 = 3.;
+    /// How near, in screen pixels, a drag must start to a drone to send it.
+    const DRONE_PICK_DISTANCE: f32 = 8.;
+    /// How many blobs selecting similar ones picks.
+    const SIMILAR_COUNT: usize = 10;
+    /// How far, in screen pixels, the soft tools reach.
+    const INFLUENCE_RADIUS: f32 = 80.;
+
+    fn new() -> Self {
+        Self { selected: HashSet::new(), drag: None, last_paint_pos: Vector2::zero(), watching: None }
+    }
+
+    /// Act on a gesture, returning the interventions it makes in the worlds.
+    /// While paused, dropped blobs stay where they are put.
+    fn handle(&mut self, gesture: Gesture, worlds: &mut [World], views: &[(Rectangle, Camera)], camera: &mut Camera, paused: bool) -> Vec<(usize, Command)> {
+        let mut commands = vec![];
+        //  a gesture acts on the world it started in
+        let focus = views.iter()
+            .position(|(rect, _)| rect.check_collision_point_rec(gesture.start))
+            .unwrap_or(0);
+        let view = views[focus].1;
+        let sim = &mut worlds[focus].sim;
+        let world_pos = view.screen_to_world(gesture.pos);
+        let pick_radius = Self::PICK_DISTANCE / view.zoom;
+        //  what a gesture puts in the world is kept in it, as a peer takes nothing outside, see `Command::validate`
+        let size = sim.size();
+        let inside = |pos: Vector2| Vector2::new(pos.x.clamp(0., size.x), pos.y.clamp(0., size.y));
+        match (gesture.mode, gesture.phase) {
+            (InteractionMode::Select, GesturePhase::End) => {
+                let start = view.screen_to_world(gesture.start);
+                let rect = rect_from_corners(start, world_pos);
+                let keys = if rect.width < 2. && rect.height < 2. {
+                    sim.select_circle(world_pos, pick_radius, &BlobFilter::default())
+                } else {
+                    sim.select_rect(rect, &BlobFilter::default())
+                };
+                self.selected = keys.into_iter().map(|key| (focus, key)).collect();
+            },
+            (InteractionMode::Select, _) => (),
+            (InteractionMode::Drag, GesturePhase::Begin) => {
+                let blobs = sim.select_circle(world_pos, pick_radius, &BlobFilter::default());
+                //  grabbing a selected blob moves the whole selection
+                let grabbed: Vec<_> = if blobs.iter().any(|&blob| self.selected.contains(&(focus, blob))) {
+                    self.selected.iter().filter(|(world, _)| *world == focus).map(|&(_, key)| key).collect()
+                } else {
+                    blobs
+                };
+                //  grabbing a flying blob catches it
+                for &blob_key in &grabbed {
+                    commands.push((focus, Command::SetVelocity(blob_key, Vector2::zero())));
+                }
+                self.drag = Some(Drag {
+                    world: focus,
+                    start_mouse_pos: world_pos,
+                    blobs: grabbed.iter()
+                        .filter_map(|&blob_key| Some((blob_key, sim.get_blob(blob_key)?.pos())))
+                        .collect(),
+                });
+            },
+            (InteractionMode::Drag, GesturePhase::Update) => {
+                if let Some(drag) = self.drag.as_ref().filter(|drag| drag.world == focus) {
+                    for (&blob_key, start_pos) in &drag.blobs {
+                        commands.push((focus, Command::MoveBlob(blob_key, inside(*start_pos + world_pos - drag.start_mouse_pos))));
+                    }
+                }
+            },
+            (InteractionMode::Drag, GesturePhase::End) => {
+                //  released blobs fly off with the mouse's velocity, measured on screen and so scaled by the zoom
+                if let Some(drag) = self.drag.take().filter(|drag| drag.world == focus && !paused) {
+                    for &blob_key in drag.blobs.keys() {
+                        if let Some(mass) = sim.get_blob(blob_key).map(Blob::mass) {
+                            commands.push((focus, Command::ApplyImpulse(blob_key, gesture.velocity / view.zoom * mass)));
+                        }
+                    }
+                }
+            },
+            //  the camera is shared, so every world pans together
+            (InteractionMode::Pan, _) => camera.pan(gesture.delta),
+            (InteractionMode::Paint, phase) => {
+                if phase == GesturePhase::Begin || (world_pos - self.last_paint_pos).length() >= Self::PAINT_SPACING {
+                    commands.push((focus, Command::SpawnFood(inside(world_pos))));
+                    self.last_paint_pos = world_pos;
+                }
+            },
+            (InteractionMode::Watch, GesturePhase::End) => {
+                let rect = rect_from_corners(view.screen_to_world(gesture.start), world_pos);
+                if rect.width >= 1. && rect.height >= 1. {
+                    self.watching = Some((focus, rect, String::new()));
+                }
+            },
+            (InteractionMode::Watch, _) => (),
+            //  a click places a drone or removes one, and a drag from one sends it where the drag ends
+            (InteractionMode::Drone, GesturePhase::End) => {
+                let start = view.screen_to_world(gesture.start);
+                let pick_radius = Self::DRONE_PICK_DISTANCE / view.zoom;
+                let dragged = (world_pos - start).length() > pick_radius;
+                match sim.drones().find(|(_, drone)| (drone.pos - start).length() <= pick_radius) {
+                    Some((drone, _)) if dragged => commands.push((focus, Command::SteerDrone(drone, inside(world_pos)))),
+                    Some((drone, _)) => commands.push((focus, Command::RemoveDrone(drone))),
+                    None if !dragged => commands.push((focus, Command::SpawnDrone(inside(world_pos)))),
+                    None => (),
+                }
+            },
+            (InteractionMode::Drone, _) => (),
+            (mode @ (InteractionMode::Repel | InteractionMode::Attract | InteractionMode::Calm), GesturePhase::Begin | GesturePhase::Update) => {
+                let influence = match mode {
+                    InteractionMode::Repel => Influence::Repel,
+                    InteractionMode::Attract => Influence::Attract,
+                    _ => Influence::Calm,
+                };
+                commands.push((focus, Command::Influence(influence, inside(world_pos), (Self::INFLUENCE_RADIUS / view.zoom).min(size.length()))));
+            },
+            (InteractionMode::Repel | InteractionMode::Attract | InteractionMode::Calm, GesturePhase::End) => (),
+        }
+        commands
+    }
+}
+
+/// How stepping the worlds in a frame ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stepped {
+    /// The pending time was all simulated.
+    Done,
+    /// Stopped to wait for the peer.
+    Waiting,
+    /// Stopped at the deadline, dropping the rest of the pending time.
+    OutOfTime,
+}
+
+/// Simulate as many ticks as the pending time covers, keeping the worlds
+/// in step, and the peer too in lockstep. Past a deadline, if any, the
+/// rest is dropped after at least one tick.
+fn step_worlds(
+    worlds: &mut [World],
+    mut session: Option<&mut Session>,
+    pending_time: &mut f32,
+    deadline: Option<time::Instant>,
+    names: &[String],
+    species: &[Species],
+) -> Stepped {
+    let tick_length = worlds[0].sim.clock().tick_length();
+    let mut ticked = false;
+    while *pending_time >= tick_length {
+        if ticked && deadline.is_some_and(|deadline| time::Instant::now() >= deadline) {
+            *pending_time = 0.;
+            return Stepped::OutOfTime;
+        }
+        ticked = true;
+        let tick = worlds[0].sim.clock().tick();
+        if let Some(session) = &mut session {
+            //  wait for the peer instead of running ahead of it
+            if !session.can_step(tick) {
+                *pending_time = 0.;
+                return Stepped::Waiting;
+            }
+            for command in session.take_commands(tick) {
+                worlds[0].sim.spawning_as(Source::User, |sim| apply_command(sim, command, names, species));
+            }
+        }
+        *pending_time -= tick_length;
+        for world in worlds.iter_mut() {
+            world.tick(names, species);
+        }
+        if let Some(session) = &mut session {
+            session.record_hash(tick, worlds[0].sim.state_hash());
+        }
+    }
+    Stepped::Done
+}
+
+/// The selected blobs that are still alive, labeled by their world, in
+/// the order of their worlds and keys.
+fn selected_blobs<'a>(worlds: &'a [World], selected: &HashSet<(usize, keyed_set::Key<Blob>)>) -> Vec<(&'a str, &'a Blob)> {
+    let mut selected: Vec<_> = selected.iter().cloned().collect();
+    selected.sort();
+    selected.into_iter()
+        .filter_map(|(world, key)| Some((&worlds[world].label[..1], worlds[world].sim.get_blob(key)?)))
+        .collect()
+}
+
+/// A rectangle spanning two corners given in any order.
+fn rect_from_corners(a: Vector2, b: Vector2) -> Rectangle {
+    Rectangle::new(a.x.min(b.x), a.y.min(b.y), (a.x - b.x).abs(), (a.y - b.y).abs())
+}
+
+/// The timing changed by this frame's keys, if any: `,` and `.`
+/// halve and double the tick rate, `[` and `]` change the substeps
+/// and `;` and `'` the ticks between brain decisions.
+fn adjust_timing(input: &Frame, timing: Timing) -> Option<Timing> {
+    let clamp = |value: u32, range: std::ops::RangeInclusive<u32>| value.max(*range.start()).min(*range.end());
+    let mut adjusted = timing;
+    if input.is_key_pressed(KeyboardKey::KEY_COMMA) {
+        adjusted.tick_rate = clamp(timing.tick_rate / 2, Timing::TICK_RATES);
+    }
+    if input.is_key_pressed(KeyboardKey::KEY_PERIOD) {
+        adjusted.tick_rate = clamp(timing.tick_rate * 2, Timing::TICK_RATES);
+    }
+    if input.is_key_pressed(KeyboardKey::KEY_LEFT_BRACKET) {
+        adjusted.substeps = clamp(timing.substeps - 1, Timing::SUBSTEPS);
+    }
+    if input.is_key_pressed(KeyboardKey::KEY_RIGHT_BRACKET) {
+        adjusted.substeps = clamp(timing.substeps + 1, Timing::SUBSTEPS);
+    }
+    if input.is_key_pressed(KeyboardKey::KEY_SEMICOLON) {
+        adjusted.brain_period = clamp(timing.brain_period - 1, Timing::BRAIN_PERIODS);
+    }
+    if input.is_key_pressed(KeyboardKey::KEY_APOSTROPHE) {
+        adjusted.brain_period = clamp(timing.brain_period + 1, Timing::BRAIN_PERIODS);
+    }
+    Some(adjusted).filter(|&adjusted| adjusted != timing)
+}
+
+/// Draw the simulation time at the bottom left of the window, along
+/// with the quality while it is lowered.
+fn draw_clock(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, sim: &Simulation, speed: f32, paused: bool, tier: Tier) {
+    let font_size = 20;
+    let (clock, timing) = (sim.clock(), sim.timing());
+    let mut text = format!("{}  tick {}  seed {}  x{}{}  {} Hz, {} substeps, brains every {} ticks",
+        clock, clock.tick(), sim.seed(), speed,
+        if paused { "  (paused)" } else { "" },
+        timing.tick_rate, timing.substeps, timing.brain_period,
+    );
+    if tier != Tier::Full {
+        text += &format!("  quality: {}", tier.name());
+    }
+    fonts.draw_text(draw, &text, 10, draw.get_screen_height() - font_size - 10, font_size, theme.muted_text);
+}
+
+/// Draw the active mutators above the clock.
+fn draw_mutators(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, mutators: &[Mutator]) {
+    if mutators.is_empty() { return; }
+    let font_size = 20;
+    let text = format!("Mutators: {}", mutators.iter().map(Mutator::to_string).collect::<Vec<_>>().join(", "));
+    fonts.draw_text(draw, &text, 10, draw.get_screen_height() - 2 * font_size - 14, font_size, theme.warning);
+}
+
+/// A statistic to draw on the graph.
+struct GraphSeries<'a> {
+    stats: &'a StatsStore,
+    name: String,
+    label: String,
+    color: Color,
+}
+
+/// Draw the whole history of some statistics as lines on a common
+/// scale, with the changes and bookmarks of the first one's store as
+/// vertical lines.
+fn draw_graph(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, series: &[GraphSeries], rect: Rectangle) {
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    let font_size = 10;
+    let lines: Vec<_> = series.iter()
+        .filter_map(|s| {
+            let id = s.stats.id(&s.name)?;
+            Some((s, id, s.stats.query(id, 0..u64::MAX, rect.width as usize / 2)))
+        })
+        .collect();
+    let max = lines.iter().flat_map(|(_, _, points)| points).map(|p| p.mean).fold(1., f32::max);
+    let top = series.len() as f32 * font_size as f32 + 4.;
+    for (i, (s, id, points)) in lines.iter().enumerate() {
+        let step = rect.width / points.len().max(2) as f32;
+        let to_screen = |i: usize, value: f32| Vector2::new(
+            rect.x + i as f32 * step,
+            rect.y + rect.height - value / max * (rect.height - top),
+        );
+        for (j, pair) in points.windows(2).enumerate() {
+            draw.draw_line_v(to_screen(j, pair[0].mean), to_screen(j + 1, pair[1].mean), theme.contrast(s.color));
+        }
+        let latest = s.stats.latest(*id).map_or(0., |s| s.value);
+        fonts.draw_text(draw, &format!("{}: {}", s.label, latest), rect.x as i32 + 4, rect.y as i32 + 2 + font_size * i as i32, font_size, theme.contrast(s.color));
+    }
+    if let Some((s, _, points)) = lines.first() {
+        //  a mark goes at the point its tick falls in
+        let step = rect.width / points.len().max(2) as f32;
+        for mark in s.stats.marks() {
+            let x = rect.x + points.partition_point(|p| p.tick <= mark.tick).saturating_sub(1) as f32 * step;
+            let (color, y) = match mark.kind {
+                MarkKind::Change => (theme.warning, rect.y + rect.height - font_size as f32 - 2.),
+                MarkKind::Bookmark => (theme.accent, rect.y + top),
+            };
+            draw.draw_line_v(Vector2::new(x, rect.y + top), Vector2::new(x, rect.y + rect.height), color);
+            fonts.draw_text(draw, &mark.label, x as i32 + 2, y as i32, font_size, color);
+        }
+    }
+}
+
+/// Draw who eats whom: species, foods and corpses sized by their
+/// population, with arrows from prey to eater as thick as the meals.
+fn draw_food_web(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, web: &FoodWeb, rect: Rectangle) {
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    let font_size = 10;
+    fonts.draw_text(draw, &format!("Food web, meals of the last {} ticks", food_web::WINDOW), rect.x as i32 + 4, rect.y as i32 + 2, font_size, theme.text);
+    let to_screen = |pos: Vector2| Vector2::new(rect.x + pos.x * rect.width, rect.y + pos.y * rect.height);
+    let radius = |population: usize| (3. + 2. * (population as f32).sqrt()).min(20.);
+    for edge in web.edges() {
+        let (from, to) = (&web.nodes()[edge.from], &web.nodes()[edge.to]);
+        let thickness = 1. + (edge.meals as f32).ln();
+        let color = theme.muted_text;
+        if edge.from == edge.to {
+            //  a species eating its own kind
+            let r = radius(to.population);
+            draw.draw_circle_lines((to_screen(to.pos).x + r) as i32, (to_screen(to.pos).y - r) as i32, r, color);
+            continue;
+        }
+        let (start, end) = (to_screen(from.pos), to_screen(to.pos));
+        let dir = (end - start).normalized();
+        let tip = end - dir * radius(to.population);
+        let side = Vector2::new(-dir.y, dir.x) * (3. + thickness);
+        let back = tip - dir * (6. + 2. * thickness);
+        draw.draw_line_ex(start, back, thickness, color);
+        draw.draw_triangle(tip, back - side, back + side, color);
+    }
+    for node in web.nodes() {
+        let center = to_screen(node.pos);
+        let r = radius(node.population);
+        draw.draw_circle_v(center, r, theme.contrast(node.color));
+        let label = format!("{} {}", node.label(), node.population);
+        let x = center.x as i32 - fonts.measure(&label, font_size) / 2;
+        fonts.draw_text(draw, &label, x, (center.y + r) as i32 + 2, font_size, theme.text);
+    }
+}
 
-        //  add blob
-        if frame_time > blob_add_time {
-            blob_add_time = frame_time + blob_add_delay;
-            let blob_key = add_random_blob(&mut sim, &mut names);
+/// Draw where energy went: food, species and decay in columns, with
+/// bands between them as wide as the energy that flowed.
+fn draw_sankey(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, sankey: &Sankey, rect: Rectangle) {
+    //  the most width of the slices bands are drawn in, in pixels
+    const SLICE: f32 = 2.;
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    let font_size = 10;
+    fonts.draw_text(draw, &format!("Energy flows of the last {} ticks", sankey::WINDOW), rect.x as i32 + 4, rect.y as i32 + 2, font_size, theme.text);
+    let area = Rectangle::new(rect.x + 10., rect.y + 10. + font_size as f32, rect.width - 20., rect.height - 20. - 2. * font_size as f32);
+    let to_screen = |node: Rectangle| Rectangle::new(
+        area.x + node.x * area.width, area.y + node.y * area.height, node.width * area.width, node.height * area.height,
+    );
+    for link in sankey.links() {
+        let (from, to) = (to_screen(sankey.nodes()[link.from].rect), to_screen(sankey.nodes()[link.to].rect));
+        let (start, end) = (from.x + from.width, to.x);
+        let width = link.width * area.height;
+        let color = sankey.nodes()[link.from].color.fade(0.5);
+        let slices = ((end - start).abs() / SLICE).ceil().max(1.) as usize;
+        for slice in 0..slices {
+            let t = (slice as f32 + 0.5) / slices as f32;
+            let (x0, x1) = (start + (end - start) * slice as f32 / slices as f32, start + (end - start) * (slice + 1) as f32 / slices as f32);
+            let y = sankey::band(area.y + link.start * area.height, area.y + link.end * area.height, t);
+            draw.draw_rectangle_rec(Rectangle::new(x0.min(x1), y, (x1 - x0).abs(), width.max(1.)), color);
         }
-        //  add food
-        if frame_time > food_add_time {
-            food_add_time = frame_time + food_add_delay;
-            add_random_food(&mut sim);
+    }
+    for node in sankey.nodes() {
+        let rect = to_screen(node.rect);
+        draw.draw_rectangle_rec(Rectangle { height: rect.height.max(1.), ..rect }, theme.contrast(node.color));
+        let label = format!("{} {:.0}", node.label(), node.value());
+        let x = (rect.x as i32).min((area.x + area.width) as i32 - fonts.measure(&label, font_size));
+        fonts.draw_text(draw, &label, x, (rect.y + rect.height) as i32 + 1, font_size, theme.text);
+    }
+}
+
+/// Ticks left to simulate without rendering the world.
+struct FastForward {
+    done: u64,
+    total: u64,
+}
+
+/// Draw the progress of a fast-forward in the middle of the window.
+fn draw_fast_forward(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, fast_forward: &FastForward) {
+    let font_size = 20;
+    let (width, height) = (400., 24.);
+    let rect = Rectangle::new(
+        (draw.get_screen_width() as f32 - width) / 2.,
+        (draw.get_screen_height() as f32 - height) / 2.,
+        width, height,
+    );
+    let progress = fast_forward.done as f32 / fast_forward.total as f32;
+    draw.draw_rectangle_rec(Rectangle { width: rect.width * progress, ..rect }, theme.accent);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    let text = format!("Fast-forwarding {} / {} ticks (F to stop)", fast_forward.done, fast_forward.total);
+    fonts.draw_text(draw, &text, rect.x as i32, rect.y as i32 - font_size - 6, font_size, theme.text);
+}
+
+/// The exported blobs that can be imported, with the one under the cursor.
+struct ImportMenu {
+    //  blobs that fail to load are listed with why
+    entries: Vec<(path::PathBuf, Result<Exported, String>)>,
+    cursor: usize,
+}
+
+impl ImportMenu {
+    fn open(dir: &str) -> io::Result<Self> {
+        let entries = exchange::list(dir)?.into_iter()
+            .map(|path| {
+                let exported = Exported::load(&path);
+                (path, exported)
+            })
+            .collect();
+        Ok(Self { entries, cursor: 0 })
+    }
+}
+
+/// Draw the import menu at the left of the window, below the selection list.
+fn draw_import_menu(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, menu: &ImportMenu) {
+    let font_size = 20;
+    let mut lines = vec![(format!("Import a blob from {}/ (up, down, enter)", exchange::DIR), theme.text)];
+    if menu.entries.is_empty() {
+        lines.push(("No exported blobs, select some and press E".to_string(), theme.text));
+    }
+    for (i, (path, exported)) in menu.entries.iter().enumerate() {
+        let file = path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let (text, color) = match exported {
+            Ok(exported) => (format!("{}: {} ({}, lived {:.0} s)", file,
+                exported.name.as_deref().unwrap_or("unnamed"),
+                exported.species.as_deref().unwrap_or(exported.diet.name()),
+                exported.age,
+            ), theme.text),
+            Err(e) => (e.clone(), theme.error),
+        };
+        let marker = if i == menu.cursor { "> " } else { "  " };
+        lines.push((format!("{}{}", marker, text), color));
+    }
+    let rect = Rectangle::new(
+        10., 46.,
+        lines.iter().map(|(line, _)| fonts.measure(line, font_size)).max().unwrap_or(0) as f32 + 12.,
+        (lines.len() as i32 * font_size) as f32 + 8.,
+    );
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    for (i, (line, color)) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, rect.x as i32 + 6, rect.y as i32 + 4 + i as i32 * font_size, font_size, *color);
+    }
+}
+
+/// Draw the best past runs in the middle of the window.
+fn draw_hall_of_fame(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, runs: &[Summary]) {
+    let font_size = 20;
+    let mut lines = vec![format!("Hall of fame: the best {} of {} runs", leaderboard::SHOWN.min(runs.len()), runs.len())];
+    for (i, run) in leaderboard::best(runs, leaderboard::SHOWN).into_iter().enumerate() {
+        lines.push(format!("{}. {} blobs at most, {} ticks, longest life {:.0} s (seed {}, config {:016x})",
+            i + 1, run.max_population, run.ticks, run.longest_life, run.metadata.seed, run.metadata.config_hash,
+        ));
+    }
+    let width = lines.iter().map(|line| fonts.measure(line, font_size)).max().unwrap_or(0) + 12;
+    let height = lines.len() as i32 * font_size + 8;
+    let rect = Rectangle::new(
+        ((draw.get_screen_width() - width) / 2) as f32,
+        ((draw.get_screen_height() - height) / 2) as f32,
+        width as f32,
+        height as f32,
+    );
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    for (i, line) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, rect.x as i32 + 6, rect.y as i32 + 4 + i as i32 * font_size, font_size, theme.text);
+    }
+}
+
+/// Draw the records of every world at the top right of the window.
+fn draw_records(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, worlds: &[World]) {
+    let font_size = 18;
+    let mut lines = vec![];
+    for world in worlds {
+        lines.push((format!("Records of {}", world.label), theme.text));
+        let tick_length = world.sim.clock().tick_length();
+        for &kind in &records::Kind::ALL {
+            lines.push(match world.sim.records().get(kind) {
+                Some(record) => (format!("  {} (at {:.0} s)", record.describe(), record.tick as f32 * tick_length), theme.text),
+                None => (format!("  {}: none yet", kind.title()), theme.muted_text),
+            });
         }
+    }
+    let width = lines.iter().map(|(line, _)| fonts.measure(line, font_size)).max().unwrap_or(0) + 12;
+    let height = lines.len() as i32 * font_size + 8;
+    let rect = Rectangle::new((draw.get_screen_width() - width - 10) as f32, 40., width as f32, height as f32);
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    for (i, (line, color)) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, rect.x as i32 + 6, rect.y as i32 + 4 + i as i32 * font_size, font_size, *color);
+    }
+}
+
+/// Draw the trace of a blob along the bottom of the window.
+fn draw_trace(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, trace: &trace::Trace, view: &trace::View) {
+    let font_size = trace::View::FONT_SIZE;
+    let rect = trace::View::rect(draw.get_screen_width(), draw.get_screen_height());
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    let (title, lines) = view.lines(trace);
+    fonts.draw_text(draw, &title, rect.x as i32 + 6, rect.y as i32 + 4, font_size, theme.muted_text);
+    for (i, line) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, rect.x as i32 + 6, rect.y as i32 + 4 + (i as i32 + 1) * font_size, font_size, theme.text);
+    }
+}
 
-        if draw.is_key_down(KeyboardKey::KEY_SPACE) {
-            add_random_blob(&mut sim, &mut names);
+/// Draw the latest runs with their metadata in the middle of the window.
+fn draw_runs(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, runs: &[Summary]) {
+    let font_size = 20;
+    let mut lines = vec![(format!("The latest {} of {} runs", runs::SHOWN.min(runs.len()), runs.len()), theme.text)];
+    for run in runs.iter().take(runs::SHOWN) {
+        let metadata = &run.metadata;
+        lines.push((format!("{}  seed {}  config {:016x}  version {}  {} blobs at most, {} ticks",
+            runs::format_time(metadata.time), metadata.seed, metadata.config_hash, metadata.version, run.max_population, run.ticks,
+        ), theme.text));
+        if !metadata.notes.is_empty() {
+            lines.push((format!("    {}", metadata.notes), theme.muted_text));
         }
+    }
+    let width = lines.iter().map(|(line, _)| fonts.measure(line, font_size)).max().unwrap_or(0) + 12;
+    let height = lines.len() as i32 * font_size + 8;
+    let rect = Rectangle::new(
+        ((draw.get_screen_width() - width) / 2) as f32,
+        ((draw.get_screen_height() - height) / 2) as f32,
+        width as f32,
+        height as f32,
+    );
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    for (i, (line, color)) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, rect.x as i32 + 6, rect.y as i32 + 4 + i as i32 * font_size, font_size, *color);
+    }
+}
+
+/// Draw text as it is typed after a label, at the bottom middle of the window.
+/// Draw the command palette at the top middle of the window, with the
+/// matching actions and their keys below what was typed.
+fn draw_palette(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, palette: &Palette, actions: &[keyboard::Action]) {
+    let font_size = 20;
+    let found = keyboard::search(actions, &palette.typed);
+    let mut lines = vec![(format!("> {}_", palette.typed), theme.text)];
+    if found.is_empty() {
+        lines.push(("No action matches".to_string(), theme.muted_text));
+    }
+    for (i, action) in found.iter().take(Palette::SHOWN).enumerate() {
+        let color = if i == palette.chosen { theme.accent } else { theme.text };
+        lines.push((format!("{}  ({})", action.name, action.keys()), color));
+    }
+    let width = lines.iter().map(|(text, _)| fonts.measure(text, font_size)).max().unwrap_or(0).max(400) + 12;
+    let rect = Rectangle::new(((draw.get_screen_width() - width) / 2) as f32, 40., width as f32, (lines.len() as i32 * font_size + 8) as f32);
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.accent);
+    for (i, (text, color)) in lines.iter().enumerate() {
+        fonts.draw_text(draw, text, rect.x as i32 + 6, rect.y as i32 + 4 + i as i32 * font_size, font_size, *color);
+    }
+}
+
+/// Make a file the latest saved or loaded, and keep the list for the next run.
+fn remember(recent: &mut Recent, path: &str) {
+    recent.push(path);
+    if let Err(e) = recent.save(recent::PATH) {
+        eprintln!("failed to write the recent files to {}: {}", recent::PATH, e);
+    }
+}
+
+fn draw_text_box(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, label: &str, typed: &str) {
+    let font_size = 20;
+    let text = format!("{}: {}_", label, typed);
+    let hint = "Enter to finish";
+    let width = fonts.measure(&text, font_size).max(fonts.measure(hint, font_size)).max(300) + 12;
+    let rect = Rectangle::new(
+        ((draw.get_screen_width() - width) / 2) as f32,
+        (draw.get_screen_height() - 2 * font_size - 60) as f32,
+        width as f32,
+        (2 * font_size + 8) as f32,
+    );
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.accent);
+    fonts.draw_text(draw, &text, rect.x as i32 + 6, rect.y as i32 + 4, font_size, theme.text);
+    fonts.draw_text(draw, hint, rect.x as i32 + 6, rect.y as i32 + 4 + font_size, font_size, theme.muted_text);
+}
+
+/// Draw how a prediction compares with the world when it started, at the top middle.
+fn draw_prediction(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, prediction: &Prediction) {
+    let font_size = 20;
+    let mut lines = vec![(format!("What if, {} (X to close)", prediction.intervention), theme.text)];
+    let start = &prediction.start;
+    match prediction.outcome() {
+        None => lines.push((format!("Predicting {} seconds ahead...", whatif::SECONDS), theme.muted_text)),
+        Some(outcome) => {
+            let compare = |name: &str, now: usize, then: usize| {
+                let color = if then < now { theme.error } else if then > now { theme.ok } else { theme.text };
+                (format!("{}: {} now, {} in {} seconds", name, now, then, whatif::SECONDS), color)
+            };
+            lines.push(compare("Blobs", start.population, outcome.population));
+            lines.push(compare("Food", start.foods, outcome.foods));
+            let names: BTreeSet<_> = start.species.keys().chain(outcome.species.keys()).collect();
+            for name in names {
+                let count = |outcome: &Outcome| outcome.species.get(name).cloned().unwrap_or(0);
+                lines.push(compare(name, count(start), count(outcome)));
+            }
+        },
+    }
+    let width = lines.iter().map(|(line, _)| fonts.measure(line, font_size)).max().unwrap() + 12;
+    let rect = Rectangle::new(
+        ((draw.get_screen_width() - width) / 2) as f32,
+        40.,
+        width as f32,
+        (lines.len() as i32 * font_size + 8) as f32,
+    );
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    for (i, (line, color)) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, rect.x as i32 + 6, rect.y as i32 + 4 + i as i32 * font_size, font_size, *color);
+    }
+}
+
+/// Type a frame's text into a string, taking the frame's keys.
+/// Returns whether Enter finished the text.
+fn type_into(text: &mut String, frame: &mut Frame) -> bool {
+    text.push_str(&frame.typed);
+    if frame.is_key_pressed(KeyboardKey::KEY_BACKSPACE) {
+        text.pop();
+    }
+    let done = frame.is_key_pressed(KeyboardKey::KEY_ENTER);
+    frame.clear_keys();
+    done
+}
 
-        if draw.is_mouse_button_down(MouseButton::MOUSE_LEFT_BUTTON) {
-            if let Some(selection) = &mut selection {
-                for (&blob_key, start_pos) in &selection.blobs {
-                    sim.set_blob_pos(blob_key, *start_pos + draw.get_mouse_position() - selection.start_mouse_pos);
+/// Draw the state of the lockstep session above the mutators.
+fn draw_network(draw: &mut DrawingContext, fonts: &Fonts, text: &str, color: Color) {
+    let font_size = 20;
+    fonts.draw_text(draw, text, 10, draw.get_screen_height() - 3 * font_size - 18, font_size, color);
+}
+
+/// Draw the outcome of an action at the top middle of the window.
+fn draw_notice(draw: &mut DrawingContext, fonts: &Fonts, text: &str, color: Color) {
+    let font_size = 20;
+    let x = (draw.get_screen_width() - fonts.measure(text, font_size)) / 2;
+    fonts.draw_text(draw, text, x, 10, font_size, color);
+}
+
+/// Draw why the run paused by itself across the window, below the notices.
+fn draw_banner(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, text: &str) {
+    let font_size = 20;
+    let y = 40;
+    draw.draw_rectangle(0, y, draw.get_screen_width(), font_size + 16, theme.warning.fade(0.85));
+    let x = (draw.get_screen_width() - fonts.measure(text, font_size)) / 2;
+    fonts.draw_text(draw, text, x, y + 8, font_size, theme.background);
+}
+
+/// How the worlds are shown, besides where: what is selected, the
+/// layers and overlays on and how detailed they are drawn.
+#[derive(Clone, Copy)]
+struct ViewState<'a> {
+    selected: &'a HashSet<(usize, keyed_set::Key<Blob>)>,
+    layers: LayerVisibility,
+    overlay: Overlay,
+    /// Every how many blobs the brain view shows, if it is on.
+    ai_stride: Option<usize>,
+    tier: Tier,
+    style: Choice,
+    crowding: bool,
+    zones: Option<&'a [Zone]>,
+}
+
+/// Draw every world in its view, one layer at a time.
+fn draw_worlds<D: RaylibDraw>(draw: &mut D, worlds: &[World], views: &[(Rectangle, Camera)], state: &ViewState, theme: &Theme, fonts: &Fonts) {
+    let ViewState { selected, layers, overlay, ai_stride, tier, style, crowding, zones } = *state;
+    draw.clear_background(theme.background);
+    for (i, (world, (rect, view))) in worlds.iter().zip(views).enumerate() {
+        let mut scissor = draw.begin_scissor_mode(rect.x as i32, rect.y as i32, rect.width as i32, rect.height as i32);
+        {
+            let mut world_draw = scissor.begin_mode2D(view.camera2d());
+            let context = RenderContext { theme, fonts, zoom: view.zoom, labels: tier.labels(), style: style.style(view.zoom), crowding };
+            for layer in layers.visible().filter(|&layer| layer != RenderLayer::Heatmap || tier.heatmap()) {
+                world.sim.draw(&mut world_draw, layer, &context);
+                match (layer, overlay) {
+                    (RenderLayer::Heatmap, Overlay::Density) => spatial::draw_density(&mut world_draw, &world.sim, theme),
+                    (RenderLayer::Heatmap, Overlay::Light) => light::draw_light_map(&mut world_draw, &world.sim),
+                    (RenderLayer::Effects, Overlay::Territories) => spatial::draw_territories(&mut world_draw, &world.sim, theme, view.zoom),
+                    (RenderLayer::Effects, Overlay::Memorial) => memorial::draw(&mut world_draw, &world.sim, theme, view.zoom),
+                    _ => (),
+                }
+                if layer == RenderLayer::Effects {
+                    if let Some(stride) = ai_stride {
+                        ai_view::draw(&mut world_draw, &world.sim, stride, theme, view.zoom);
+                    }
+                    weather::draw(&mut world_draw, &world.sim, theme, view.zoom);
+                    watch::draw(&mut world_draw, &world.sim, theme, fonts, view.zoom);
+                    drones::draw(&mut world_draw, &world.sim, theme, view.zoom);
+                    influence::draw(&mut world_draw, &world.sim, theme, view.zoom);
+                    aura::draw(&mut world_draw, &world.sim, view.zoom);
+                    if let Some(zones) = zones {
+                        zones::draw(&mut world_draw, zones, theme, fonts, view.zoom);
+                    }
+                    for &(_, blob_key) in selected.iter().filter(|(world, _)| *world == i) {
+                        if let Some(blob) = world.sim.get_blob(blob_key) {
+                            shapes::draw_circle_outline(&mut world_draw, blob.pos(), blob.radius() + 4. / view.zoom, 1., view.zoom, theme.accent);
+                        }
+                    }
                 }
-            } else {
-                let (blobs, _) = sim.select(draw.get_mouse_position());
-                selection = Some(Selection {
-                    start_mouse_pos: draw.get_mouse_position(),
-                    blobs: blobs.iter().map(|&blob_key| (blob_key, sim.get_blob(blob_key).unwrap().pos())).collect(),
-                });
             }
-        } else {
-            selection = None;
         }
-
-        if let Some(selection) = &selection {
-            let mut y = 10;
-            for (&blob_key, _) in &selection.blobs {
-                if let Some(blob) = sim.get_blob(blob_key) {
-                    let font_size = 20;
-                    draw.draw_text(
-                        &format!("Speed: {} Pov: {} Depth: {}", blob.speed, blob.pov, blob.sight_depth()), 
-                        10, y, font_size, Color::BLACK
-                    );
-                    y += font_size;
+        if layers.is_visible(RenderLayer::Effects) {
+            if let Some(numbers) = &world.numbers {
+                numbers.draw(&mut scissor, *view, *rect, theme, fonts);
+            }
+            if let Some(bubbles) = &world.bubbles {
+                bubbles.draw(&mut scissor, &world.sim, *view, *rect, theme, fonts);
+            }
+            //  point from the edge of the view toward selected blobs off it
+            for &(_, blob_key) in selected.iter().filter(|(world, _)| *world == i) {
+                let edge = world.sim.get_blob(blob_key).and_then(|blob| view.edge_toward(*rect, blob.pos(), 12.));
+                if let Some((pos, direction)) = edge {
+                    let rotation = direction.y.atan2(direction.x).to_degrees();
+                    scissor.draw_poly(pos, 3, 8., rotation, theme.accent);
                 }
             }
         }
-    });
-}
\ No newline at end of file
+        if worlds.len() > 1 && layers.is_visible(RenderLayer::Ui) {
+            let bottom = (rect.y + rect.height) as i32;
+            fonts.draw_text(&mut scissor, &world.label, rect.x as i32 + 10, bottom - 90, 30, theme.muted_text);
+            scissor.draw_line(rect.x as i32, rect.y as i32, rect.x as i32, bottom, theme.panel_border);
+        }
+    }
+}
+
+/// Draw the render layers that are toggled off above the lockstep state.
+fn draw_hidden_layers(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, layers: LayerVisibility) {
+    let hidden: Vec<_> = layers.hidden().map(|layer| layer.name()).collect();
+    if hidden.is_empty() { return; }
+    let font_size = 20;
+    let text = format!("Hidden layers: {}", hidden.join(", "));
+    fonts.draw_text(draw, &text, 10, draw.get_screen_height() - 4 * font_size - 22, font_size, theme.muted_text);
+}
+
+/// Draw the list of enabled mods at the top right of the window.
+fn draw_mods(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, loaded_mods: &[Mod]) {
+    let font_size = 20;
+    let enabled: Vec<&Mod> = loaded_mods.iter().filter(|m| m.enabled).collect();
+    let mut lines = vec![format!("Mods ({} of {} enabled)", enabled.len(), loaded_mods.len())];
+    for m in enabled {
+        let mut line = format!("{}: {} species, {} names", m.name, m.species.len(), m.names.len());
+        if !m.unsupported.is_empty() {
+            line += &format!(", {} unsupported files", m.unsupported.len());
+        }
+        lines.push(line);
+    }
+    let x = draw.get_screen_width() - 10 - lines.iter().map(|l| fonts.measure(l, font_size)).max().unwrap_or(0);
+    for (i, line) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, x, 46 + i as i32 * font_size, font_size, theme.text);
+    }
+}
+
+/// Draw the memory held by each store at the right of the window, above the graph.
+fn draw_memory(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, usage: &Usage, budget: &Budget, entities: usize, warnings: &[String]) {
+    let font_size = 16;
+    let mut lines: Vec<(String, Color)> = usage.stores().iter()
+        .map(|(name, bytes)| (format!("{}: {}", name, memory::format_bytes(*bytes)), theme.text))
+        .collect();
+    let total_color = if usage.total() > budget.bytes { theme.warning } else { theme.text };
+    lines.push((format!("Total: {} of {}", memory::format_bytes(usage.total()), memory::format_bytes(budget.bytes)), total_color));
+    let entities_color = if entities > budget.entities { theme.warning } else { theme.text };
+    lines.push((format!("Entities: {} of {}", entities, budget.entities), entities_color));
+    lines.extend(warnings.iter().map(|warning| (warning.clone(), theme.warning)));
+
+    let padding = 6;
+    let width = lines.iter().map(|(line, _)| fonts.measure(line, font_size)).max().unwrap_or(0) + 2 * padding;
+    let height = lines.len() as i32 * font_size + 2 * padding;
+    let rect = Rectangle::new(
+        (draw.get_screen_width() - 10 - width) as f32,
+        (draw.get_screen_height() - 180 - height) as f32,
+        width as f32, height as f32,
+    );
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    for (i, (line, color)) in lines.iter().enumerate() {
+        fonts.draw_text(draw, line, rect.x as i32 + padding, rect.y as i32 + padding + i as i32 * font_size, font_size, *color);
+    }
+}
+
+/// Print what happened in each world over the run.
+fn print_summary(worlds: &[World], elapsed: time::Duration) {
+    println!("Ran for {:.0} seconds with seed {}", elapsed.as_secs_f64(), worlds[0].sim.seed());
+    for world in worlds {
+        let sim = &world.sim;
+        let stats = sim.stats();
+        let peak = stats.id("population")
+            .and_then(|id| stats.max(id))
+            .unwrap_or(0.);
+        let prefix = if worlds.len() > 1 { format!("{}: ", world.label) } else { String::new() };
+        println!("{}{} ticks, reached {}, {} blobs (at most {:.0}) and {} foods",
+            prefix, sim.clock().tick(), sim.clock(), sim.blobs().count(), peak, sim.foods().count(),
+        );
+        for extinction in sim.census().extinctions() {
+            println!("{}{} died out at tick {} after {} generations, having been {} at most",
+                prefix, extinction.species, extinction.tick, extinction.generations, extinction.peak,
+            );
+        }
+    }
+}
+
+/// Write the report of a run to where the options say, and open it
+/// if they ask to, see `report`.
+fn write_report(worlds: &[World], options: &Options, elapsed: time::Duration) -> Result<(), String> {
+    let dir = match &options.report_dir {
+        Some(dir) => dir,
+        None => return Ok(()),
+    };
+    let labeled: Vec<_> = worlds.iter().map(|world| (world.label.as_str(), &world.sim)).collect();
+    let path = report::write(dir, &labeled, elapsed).map_err(|e| format!("failed to write the report: {}", e))?;
+    println!("Wrote the report of the run to {}", path.display());
+    if options.open_report {
+        report::open(&path.join(report::PAGE))?;
+    }
+    Ok(())
+}
+
+/// Write the family tree of each world to where the options say, see
+/// `genealogy`.
+fn write_genealogies(worlds: &[World], options: &Options) -> Result<(), String> {
+    let path = match &options.genealogy {
+        Some(path) => path,
+        None => return Ok(()),
+    };
+    for world in worlds {
+        let path = labeled_path(path, &world.label, worlds.len());
+        if let Some(genealogy) = &world.genealogy {
+            genealogy.lock().unwrap().write_to(&path).map_err(|e| format!("failed to write {}: {}", path.display(), e))?;
+            println!("Wrote the family tree to {}", path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Play a tournament between exported blobs without a window and
+/// print the ranking. Returns whether it could be played.
+fn run_tournament(paths: &[String], arena: &Arena) -> bool {
+    let contestants = match paths.iter().map(Contestant::load).collect::<Result<Vec<_>, _>>() {
+        Ok(contestants) => contestants,
+        Err(e) => {
+            eprintln!("failed to load a contestant: {}", e);
+            return false;
+        },
+    };
+    println!("Playing {} matches of {} ticks", contestants.len() * (contestants.len() - 1), arena.ticks);
+    let scores = tournament::run(arena, &contestants);
+    tournament::write_table(&mut io::stdout(), arena, &contestants, &scores)
+        .map_err(|e| eprintln!("failed to write the ranking: {}", e))
+        .is_ok()
+}
+
+/// Run the foraging benchmark on exported blobs without a window, print
+/// the ranking and write it as JSON if asked. Returns whether it could be run.
+fn run_foraging(paths: &[String], benchmark: &Benchmark, json: Option<&str>) -> bool {
+    let contestants = match paths.iter().map(Contestant::load).collect::<Result<Vec<_>, _>>() {
+        Ok(contestants) => contestants,
+        Err(e) => {
+            eprintln!("failed to load a blob: {}", e);
+            return false;
+        },
+    };
+    println!("Running {} trials of {} ticks each", contestants.len() * foraging::STARTS.len(), benchmark.ticks);
+    let scores = foraging::run(benchmark, &contestants);
+    if let Err(e) = foraging::write_table(&mut io::stdout(), benchmark, &contestants, &scores) {
+        eprintln!("failed to write the ranking: {}", e);
+        return false;
+    }
+    match json {
+        Some(path) => fs::File::create(path)
+            .and_then(|mut file| foraging::write_json(&mut file, benchmark, &contestants, &scores))
+            .map_err(|e| eprintln!("failed to write {}: {}", path, e))
+            .is_ok(),
+        None => true,
+    }
+}
+
+/// Run the speed benchmark without a window, print the report and write
+/// it as JSON if asked. Returns whether it could be written.
+fn run_bench(bench: &Bench, json: Option<&str>) -> bool {
+    println!("Running {} blobs for {} ticks", bench.blobs, bench.ticks);
+    let report = bench::run(bench);
+    if let Err(e) = bench::write_report(&mut io::stdout(), &report) {
+        eprintln!("failed to write the report: {}", e);
+        return false;
+    }
+    match json {
+        Some(path) => fs::File::create(path)
+            .and_then(|mut file| bench::write_json(&mut file, &report))
+            .map_err(|e| eprintln!("failed to write {}: {}", path, e))
+            .is_ok(),
+        None => true,
+    }
+}
+
+/// Check the environment and print the diagnosis, see `doctor`.
+/// Returns whether nothing failed.
+fn run_doctor() -> bool {
+    let mut checks = doctor::diagnose();
+    checks.push(if doctor::has_display() {
+        let mut window = Window::new(&WindowConfig { width: 64, height: 64, title: "Blobs doctor", antialiasing: false, monitor: None, position: None, on_top: false, fullscreen: false });
+        doctor::renderer(&mut window)
+    } else {
+        Check::new("renderer", Status::Warning, "no display to open a window on, not checked")
+    });
+    if let Err(e) = doctor::write_report(&mut io::stdout(), &checks) {
+        eprintln!("failed to write the diagnosis: {}", e);
+        return false;
+    }
+    checks.iter().all(|check| check.status != Status::Failed)
+}
+
+/// Simulate the worlds of the options without a window and write their
+/// statistics as CSV. Returns whether they could be written.
+fn run_headless(options: &Options) -> bool {
+    shutdown::install();
+    //  rather than find out once the run is over
+    if options.stats_sqlite.is_some() {
+        if let Err(e) = sqlite::version() {
+            eprintln!("cannot write the statistics to SQLite: {}", e);
+            return false;
+        }
+    }
+    let settings = content_settings(options);
+    let catalog = Catalog::discover().unwrap_or_else(|e| panic!("failed to load the content: {}", e));
+    let (loaded_mods, species, names) = catalog.resolve(&settings);
+    let fertility = options.fertility.as_ref()
+        .map(|path| Arc::new(FertilityMap::load(path).unwrap_or_else(|e| panic!("failed to read the fertility map: {}", e))));
+    let scenario = load_scenario(options, &settings);
+    let naming = load_naming(options, &settings, &loaded_mods);
+    let setup = match &options.world_code {
+        Some(code) => code.setup.clone(),
+        //  the size of the window's world
+        None => Setup { seed: options.seed.unwrap_or_else(random), size: options.world_size.unwrap_or_else(|| Vector2::new(1300., 680.)), mutators: options.mutators.clone(), timing: options.timing },
+    };
+    let mut worlds = create_worlds(&setup, options, fertility.as_ref(), scenario.as_ref(), &naming, &names, &species);
+
+    let start = time::Instant::now();
+    let mut observed = options.observe.as_deref().map(create_output);
+    let mut telemetry: Vec<_> = worlds.iter().map(|_| sqlite::Telemetry::new()).filter(|_| options.stats_sqlite.is_some()).collect();
+    let mut tick_worlds = |worlds: &mut [World]| {
+        for world in worlds.iter_mut() {
+            world.tick(&names, &species);
+        }
+        for (world, telemetry) in worlds.iter().zip(&mut telemetry) {
+            telemetry.collect(&world.sim);
+        }
+        if let Some(out) = &mut observed {
+            worlds[0].write_changes(out).unwrap_or_else(|e| panic!("failed to write what changed: {}", e));
+        }
+    };
+    let populations = |worlds: &[World]| worlds.iter().map(|world| world.sim.blobs().count().to_string()).collect::<Vec<_>>().join(" and ");
+    if options.shell {
+        println!("Seed {}, type help for the commands", setup.seed);
+        let mut lines = io::BufRead::lines(io::stdin().lock());
+        //  the labels of the worlds, and the events they send, see `handle`
+        let mut events: Option<Vec<(String, handle::Events)>> = None;
+        while !shutdown::requested() {
+            print!("{}", shell::PROMPT);
+            //  the prompt shows before the line is typed
+            let _ = io::Write::flush(&mut io::stdout());
+            let line = match lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    eprintln!("failed to read a command: {}", e);
+                    break;
+                },
+                None => break,
+            };
+            match shell::Command::parse(&line) {
+                Ok(None) => (),
+                Ok(Some(shell::Command::Step(ticks))) => {
+                    for _ in 0..ticks {
+                        tick_worlds(&mut worlds);
+                        for (label, receiver) in events.iter().flatten() {
+                            for (tick, event) in receiver.try_iter() {
+                                print!("{}: ", label);
+                                narrator::write_event(&mut io::stdout(), tick, &event).unwrap_or_else(|e| panic!("failed to write an event: {}", e));
+                            }
+                        }
+                        if shutdown::requested() { break; }
+                    }
+                    println!("tick {}: population {}", worlds[0].sim.clock().tick(), populations(&worlds));
+                },
+                Ok(Some(shell::Command::Stats)) => for world in &worlds {
+                    shell::write_stats(&mut io::stdout(), &world.sim, &world.label).unwrap_or_else(|e| panic!("failed to write the statistics: {}", e));
+                },
+                Ok(Some(shell::Command::Spawn(count))) => {
+                    for world in &mut worlds {
+                        for _ in 0..count {
+                            add_random_blob(&mut world.sim, &names, &species, world.blob_spawner.as_ref());
+                        }
+                    }
+                    println!("population {}", populations(&worlds));
+                },
+                Ok(Some(shell::Command::Save(path))) => for world in &worlds {
+                    let path = labeled_path(&path, &world.label, worlds.len());
+                    let saved = fs::File::create(&path).map(io::BufWriter::new)
+                        .and_then(|mut file| world.sim.save(&mut file).and_then(|()| io::Write::flush(&mut file)));
+                    match saved {
+                        Ok(()) => println!("Saved the world to {}", path.display()),
+                        Err(e) => println!("failed to save {}: {}", path.display(), e),
+                    }
+                },
+                Ok(Some(shell::Command::Do(command))) => match worlds.iter().find_map(|world| command.validate(world.sim.size()).err()) {
+                    Some(e) => println!("{}", e),
+                    None => {
+                        for world in &worlds {
+                            //  the world is not gone while it is here
+                            world.commands.handle().send(command).unwrap();
+                        }
+                        println!("sent {}, applied at the next tick", command);
+                    },
+                },
+                Ok(Some(shell::Command::Events)) => {
+                    events = match events {
+                        Some(_) => None,
+                        None => Some(worlds.iter().map(|world| (world.label.clone(), world.commands.handle().subscribe().unwrap())).collect()),
+                    };
+                    println!("{} printing the events", if events.is_some() { "Started" } else { "Stopped" });
+                },
+                Ok(Some(shell::Command::Help)) => println!("{}", shell::HELP),
+                Ok(Some(shell::Command::Quit)) => break,
+                Err(e) => println!("{}", e),
+            }
+        }
+    } else {
+        println!("Simulating {} ticks of seed {}", options.steps, setup.seed);
+        let report_every = (options.steps / 10).max(1);
+        for tick in 1..=options.steps {
+            tick_worlds(&mut worlds);
+            if tick % report_every == 0 || shutdown::requested() {
+                println!("tick {}: population {}, {:.0} s", tick, populations(&worlds), start.elapsed().as_secs_f32());
+            }
+            if shutdown::requested() {
+                break;
+            }
+        }
+    }
+
+    let mut ok = true;
+    for world in &worlds {
+        let path = labeled_path(&options.stats_csv, &world.label, worlds.len());
+        let written = fs::File::create(&path).map(io::BufWriter::new)
+            .and_then(|mut file| world.sim.stats().write_csv(&mut file).and_then(|()| io::Write::flush(&mut file)));
+        match written {
+            Ok(()) => println!("Wrote the statistics to {}", path.display()),
+            Err(e) => {
+                eprintln!("failed to write {}: {}", path.display(), e);
+                ok = false;
+            },
+        }
+    }
+    if let Some(path) = &options.stats_sqlite {
+        let runs: Vec<_> = worlds.iter().zip(&telemetry).map(|(world, telemetry)| (&world.sim, world.label.as_str(), telemetry)).collect();
+        match sqlite::write(path, &runs) {
+            Ok(()) => println!("Added the statistics to {}", path),
+            Err(e) => {
+                eprintln!("failed to write the statistics to SQLite: {}", e);
+                ok = false;
+            },
+        }
+    }
+    if let Err(e) = write_genealogies(&worlds, options) {
+        eprintln!("{}", e);
+        ok = false;
+    }
+    if let Err(e) = write_report(&worlds, options, start.elapsed()) {
+        eprintln!("{}", e);
+        ok = false;
+    }
+    ok
+}
+
+/// Draw a fixed seeded world offscreen in each theme and compare it
+/// with the golden images, or replace them. Returns whether all matched.
+fn render_test(mode: golden::Mode) -> bool {
+    let (width, height) = (golden::WIDTH, golden::HEIGHT);
+    let mut window = Window::new(&WindowConfig { width, height, title: "Blobs render test", antialiasing: false, monitor: None, position: None, on_top: false, fullscreen: false });
+    let fonts = window.load_fonts(Fonts::PATH).unwrap_or_else(|e| panic!("failed to load the font: {}", e));
+    //  without mods, which differ between machines
+    let species = Species::load_dir(Species::ASSETS_DIR).unwrap_or_else(|e| panic!("failed to load species: {}", e));
+    let names = read_names("names.txt").unwrap();
+
+    let config = SimulationConfig { seed: golden::SEED, ..SimulationConfig::new(Vector2::new(width as f32, height as f32)) };
+    let mut world = World::new(config, "A".to_string());
+    for _ in 0..10 {
+        add_random_blob(&mut world.sim, &names, &species, &Uniform);
+    }
+    for _ in 0..100 {
+        add_random_food(&mut world.sim, None, &Uniform, &[]);
+    }
+    for _ in 0..golden::TICKS {
+        world.tick(&names, &species);
+    }
+
+    let views = [(Rectangle::new(0., 0., width as f32, height as f32), Camera::new())];
+    let tolerance = golden::Tolerance::default();
+    let mut ok = true;
+    for theme in &Theme::ALL {
+        let path = format!("{}/world-{}.png", golden::DIR, theme.name);
+        let image = window.render_offscreen(width, height, |draw| {
+            let state = ViewState {
+                selected: &HashSet::new(), layers: LayerVisibility::new(), overlay: Overlay::None, ai_stride: None,
+                tier: Tier::Full, style: Choice::Fixed(Style::Classic), crowding: false, zones: None,
+            };
+            draw_worlds(draw, std::slice::from_ref(&world), &views, &state, theme, &fonts);
+        });
+        let image = match image {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("{}: failed to draw: {}", path, e);
+                ok = false;
+                continue;
+            },
+        };
+        match mode {
+            golden::Mode::Update => {
+                if let Err(e) = fs::create_dir_all(golden::DIR) {
+                    eprintln!("failed to create {}: {}", golden::DIR, e);
+                    return false;
+                }
+                image.export_image(&path);
+                println!("{}: updated", path);
+            },
+            golden::Mode::Compare => match golden::check(&image, &path, &tolerance) {
+                Ok(difference) if difference.within(&tolerance) => println!("{}: ok, {}", path, difference),
+                Ok(difference) => {
+                    eprintln!("{}: changed, {}", path, difference);
+                    ok = false;
+                },
+                Err(e) => {
+                    eprintln!("{}: {}", path, e);
+                    ok = false;
+                },
+            },
+        }
+    }
+    ok
+}
+
+/// The recommendation for this machine, measured on the first launch and
+/// kept for the next ones.
+fn recommend(window: &mut Window, fonts: &Fonts, options: &Options, names: &[String], species: &[Species]) -> Recommendation {
+    match Recommendation::load(benchmark::PATH) {
+        Ok(Some(recommendation)) => recommendation,
+        result => {
+            if let Err(e) = result {
+                eprintln!("failed to read the benchmark, measuring again: {}", e);
+            }
+            let target_fps = if options.target_fps > 0 { options.target_fps } else { quality::DEFAULT_TARGET_FPS };
+            let recommendation = benchmark::recommend(&measure(window, fonts, names, species), target_fps);
+            println!("Recommended for this machine: {} entities, {} quality, saved to {}", recommendation.entities, recommendation.tier.name(), benchmark::PATH);
+            if let Err(e) = recommendation.save(benchmark::PATH) {
+                eprintln!("failed to save the benchmark: {}", e);
+            }
+            recommendation
+        },
+    }
+}
+
+/// Time ticking and drawing a seeded scene at each tier but the full
+/// one, which only adds post-processing.
+fn measure(window: &mut Window, fonts: &Fonts, names: &[String], species: &[Species]) -> Measurement {
+    let (width, height) = (800, 600);
+    let config = SimulationConfig { seed: golden::SEED, ..SimulationConfig::new(Vector2::new(width as f32, height as f32)) };
+    let mut world = World::new(config, "A".to_string());
+    for _ in 0..200 {
+        add_random_blob(&mut world.sim, names, species, &Uniform);
+    }
+    for _ in 0..800 {
+        add_random_food(&mut world.sim, None, &Uniform, &[]);
+    }
+    let entities = world.sim.entity_count();
+    let ticks = 120;
+    let start = time::Instant::now();
+    for _ in 0..ticks {
+        world.tick(names, species);
+    }
+    let tick = start.elapsed().as_secs_f32() / ticks as f32;
+
+    let views = [(Rectangle::new(0., 0., width as f32, height as f32), Camera::new())];
+    let theme = Theme::default();
+    let frames = Tier::ALL.iter().cloned().filter(|&tier| tier != Tier::Full).map(|tier| {
+        let start = time::Instant::now();
+        let drawn = window.render_offscreen(width, height, |draw| {
+            let state = ViewState {
+                selected: &HashSet::new(), layers: LayerVisibility::new(), overlay: Overlay::Density, ai_stride: None,
+                tier, style: Choice::Auto, crowding: false, zones: None,
+            };
+            draw_worlds(draw, std::slice::from_ref(&world), &views, &state, &theme, fonts);
+        });
+        if let Err(e) = drawn {
+            eprintln!("failed to draw the benchmark: {}", e);
+        }
+        (tier, start.elapsed().as_secs_f32())
+    }).collect();
+    Measurement { entities, tick, frames }
+}
+
+/// Export the selected blobs to files for importing them again, see
+/// `exchange`, or their data as a table, returning the notice.
+fn export_selected(worlds: &[World], selected: &HashSet<(usize, keyed_set::Key<Blob>)>, table: bool, theme: &Theme) -> (String, Color) {
+    if table {
+        let blobs = selected_blobs(worlds, selected);
+        if blobs.is_empty() {
+            return ("Select blobs to export their data".to_string(), theme.warning);
+        }
+        let since_epoch = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default();
+        let path = format!("selection-{}.csv", since_epoch.as_secs());
+        let saved = fs::File::create(&path).map(io::BufWriter::new)
+            .and_then(|mut file| group::write_csv(&mut file, &blobs).and_then(|()| io::Write::flush(&mut file)));
+        return match saved {
+            Ok(()) => (format!("Saved the data of {} blobs to {}", blobs.len(), path), theme.ok),
+            Err(e) => (format!("Failed to save {}: {}", path, e), theme.error),
+        };
+    }
+    let mut exported = 0;
+    let mut error = None;
+    for &(world, blob_key) in selected {
+        let sim = &worlds[world].sim;
+        if let Some(blob) = sim.get_blob(blob_key) {
+            match Exported::from_blob(blob, sim.parameters()).save(exchange::DIR) {
+                Ok(_) => exported += 1,
+                Err(e) => error = Some(e),
+            }
+        }
+    }
+    match error {
+        Some(e) => (format!("Failed to export to {}/: {}", exchange::DIR, e), theme.error),
+        None if exported == 0 => ("Select blobs to export them".to_string(), theme.warning),
+        None => (format!("Exported {} blobs to {}/", exported, exchange::DIR), theme.ok),
+    }
+}
+
+/// Point the camera at a center from half a view away, zoomed as far as
+/// it may be.
+fn aim(camera: &mut Camera, center: Vector2, zoom: f32, half_view: Vector2) {
+    camera.zoom = zoom.clamp(Camera::MIN_ZOOM, Camera::MAX_ZOOM);
+    camera.target = center - half_view / camera.zoom;
+}
+
+/// Add where the camera looks to a tour at a time into it, saving the
+/// tour.
+fn add_keyframe(tour: &mut Tour, time: f32, camera: &Camera, half_view: Vector2) -> Result<String, String> {
+    let keyframe = Keyframe { time, center: camera.target + half_view / camera.zoom, zoom: camera.zoom };
+    tour.push(keyframe).map_err(|e| format!("Failed to add a keyframe: {}", e))?;
+    tour.save(tour::PATH).map_err(|e| format!("Failed to save {}: {}", tour::PATH, e))?;
+    Ok(format!("Added keyframe {} at {:.1} seconds to {}", tour.keyframes().len(), keyframe.time, tour::PATH))
+}
+
+/// Save the last seconds of the worlds as a GIF, or a snapshot of a
+/// world as shapes, which scale without blurring, returning the notice.
+fn save_capture(sim: &Simulation, recorder: &Recorder, snapshot: bool, theme: &Theme) -> (String, Color) {
+    let since_epoch = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default();
+    if snapshot {
+        let path = format!("blobs-{}.svg", since_epoch.as_secs());
+        let saved = fs::File::create(&path).map(io::BufWriter::new)
+            .and_then(|mut file| svg::write(&mut file, sim, theme).and_then(|()| io::Write::flush(&mut file)));
+        return match saved {
+            Ok(()) => (format!("Saved a snapshot to {}", path), theme.ok),
+            Err(e) => (format!("Failed to save {}: {}", path, e), theme.error),
+        };
+    }
+    let path = format!("blobs-{}.gif", since_epoch.as_secs());
+    match recorder.save(&path) {
+        Ok(()) => (format!("Saved the last {:.0} seconds to {}", recorder.seconds(), path), theme.ok),
+        Err(e) => (format!("Failed to save {}: {}", path, e), theme.error),
+    }
+}
+
+/// Show or hide the energy numbers of every world, or their speech
+/// bubbles.
+fn toggle_floating(worlds: &mut [World], bubbles: bool) -> Result<String, String> {
+    if bubbles {
+        let shown = worlds[0].bubbles.is_none();
+        let pack = Pack::load(bubbles::PATH)?;
+        for world in worlds.iter_mut() {
+            world.bubbles = Some(Bubbles::new(pack.clone())).filter(|_| shown);
+        }
+        return Ok(format!("Speech bubbles: {}", if shown { "on" } else { "off" }));
+    }
+    let shown = worlds[0].numbers.is_none();
+    for world in worlds.iter_mut() {
+        world.numbers = Some(FloatingNumbers::new()).filter(|_| shown);
+    }
+    Ok(format!("Energy numbers: {}", if shown { "on" } else { "off" }))
+}
+
+/// Save the trace of the blob traced, returning the notice.
+fn save_trace(worlds: &[World], theme: &Theme) -> (String, Color) {
+    match worlds.iter().find_map(|world| world.sim.trace().map(|trace| (trace, world.sim.clock().tick()))) {
+        Some((trace, tick)) => {
+            let path = trace::path(trace.blob(), tick);
+            match trace.save(&path) {
+                Ok(()) => (format!("Saved {} ticks of the trace to {}", trace.entries().len(), path), theme.ok),
+                Err(e) => (format!("Failed to save {}: {}", path, e), theme.error),
+            }
+        },
+        None => ("Trace a blob with L to save its trace".to_string(), theme.warning),
+    }
+}
+
+/// Stop tracing, or trace the first blob selected, returning whether a
+/// trace started.
+fn toggle_trace(worlds: &mut [World], selected: &HashSet<(usize, keyed_set::Key<Blob>)>) -> Result<bool, String> {
+    let tracing = worlds.iter().any(|world| world.sim.trace().is_some());
+    for world in worlds.iter_mut() {
+        world.sim.trace_blob(None);
+    }
+    if tracing {
+        return Ok(false);
+    }
+    let first = selected.iter().min().copied();
+    let (world, key) = first.filter(|&(world, key)| worlds[world].sim.get_blob(key).is_some())
+        .ok_or_else(|| "Select a blob to trace it".to_string())?;
+    worlds[world].sim.trace_blob(Some(key));
+    Ok(true)
+}
+
+/// Insert the blobs of a CSV file in every world, see `roster`.
+fn import_roster(worlds: &mut [World], path: &str) -> Result<String, String> {
+    let blobs = roster::load(path).map_err(|e| format!("Failed to import {}", e))?;
+    for world in worlds.iter_mut() {
+        roster::insert(&mut world.sim, &blobs);
+    }
+    Ok(format!("Imported {} blobs from {}", blobs.len(), path))
+}
+
+/// Insert exported blobs in every world, so compared worlds stay alike,
+/// returning the notice.
+fn insert_imports(worlds: &mut [World], imports: &[(Exported, Vector2)]) -> String {
+    for (exported, pos) in imports {
+        for world in worlds.iter_mut() {
+            let size = world.sim.size();
+            exported.insert(&mut world.sim, Vector2::new(pos.x.clamp(0., size.x), pos.y.clamp(0., size.y)));
+        }
+    }
+    let names: Vec<_> = imports.iter().map(|(exported, _)| exported.name.as_deref().unwrap_or("unnamed")).collect();
+    format!("Imported {}", names.join(", "))
+}
+
+/// Slow the brains and stop spawning in every world as the level of the
+/// governor says, keeping the brain period the worlds had before.
+fn apply_level(worlds: &mut [World], level: realtime::Level, slowed_brains: &mut Option<u32>) {
+    //  the worlds keep the same timing
+    let timing = *worlds[0].sim.timing();
+    let brain_period = match (level.slow_brains(), *slowed_brains) {
+        (true, None) => {
+            *slowed_brains = Some(timing.brain_period);
+            (timing.brain_period * realtime::BRAIN_FACTOR).min(*Timing::BRAIN_PERIODS.end())
+        },
+        (false, Some(brain_period)) => {
+            *slowed_brains = None;
+            brain_period
+        },
+        _ => timing.brain_period,
+    };
+    for world in worlds.iter_mut() {
+        world.sim.set_timing(Timing { brain_period, ..timing }).unwrap();
+        world.spawning = level.spawns();
+    }
+}
+
+/// Check the triggers of every world since a tick, bookmarking the
+/// moments they wait for and returning why to pause.
+fn check_autopause(worlds: &mut [World], selected: &HashSet<(usize, keyed_set::Key<Blob>)>, first_tick: u64) -> Option<String> {
+    let several = worlds.len() > 1;
+    let mut reason_shown = None;
+    for (i, world) in worlds.iter_mut().enumerate().filter(|(_, world)| !world.autopause.is_empty()) {
+        let selected: Vec<_> = selected.iter().filter(|(world, _)| *world == i).map(|&(_, key)| key).collect();
+        if let Some(reason) = world.autopause.check(&world.sim, first_tick, &selected) {
+            let tick = world.sim.clock().tick();
+            world.sim.bookmark(tick, &format!("Paused at {}", reason));
+            let prefix = if several { format!("{}: ", &world.label[..1]) } else { String::new() };
+            reason_shown = Some(format!("{}Paused at {}, P to resume", prefix, reason));
+        }
+    }
+    reason_shown
+}
+
+/// The keyboard cursor, a crosshair in a ring.
+fn draw_cursor(draw: &mut DrawingContext, theme: &Theme, pos: Vector2) {
+    draw.draw_circle_lines(pos.x as i32, pos.y as i32, 8., theme.accent);
+    draw.draw_line_v(pos - Vector2::new(12., 0.), pos + Vector2::new(12., 0.), theme.accent);
+    draw.draw_line_v(pos - Vector2::new(0., 12.), pos + Vector2::new(0., 12.), theme.accent);
+}
+
+/// What a frame shows of the selection: a close look at a single
+/// selected blob, and the group and how two differ for more.
+fn draw_selection(draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, worlds: &[World], selected: &HashSet<(usize, keyed_set::Key<Blob>)>) {
+    let single = selected.iter().next().filter(|_| selected.len() == 1);
+    if let Some(&(world, blob_key)) = single {
+        let sim = &worlds[world].sim;
+        if let Some(blob) = sim.get_blob(blob_key) {
+            //  blobs of no species are compared with the others of none
+            let kin = sim.blobs().map(|(_, other)| other).filter(|other| other.species == blob.species);
+            let mean = inspector::mean_profile(kin);
+            let parent = blob.parent.and_then(|parent| sim.get_blob(parent));
+            inspector::draw(draw, theme, fonts, blob, parent, mean.as_ref(), Vector2::new(10., 10.));
+            vision::draw(draw, theme, fonts, blob, &sim.sightings(blob_key), Vector2::new(300., 10.));
+            senses::draw(draw, theme, fonts, blob, &Senses::of(sim, blob_key), Vector2::new(300., 16. + vision::HEIGHT));
+        }
+    }
+    let blobs: Vec<_> = selected_blobs(worlds, selected).into_iter().map(|(_, blob)| blob).collect();
+    if blobs.len() > 1 {
+        group::draw(draw, theme, fonts, &GroupStats::of(&blobs), Vector2::new(10., 10.));
+    }
+    if let [a, b] = blobs[..] {
+        inspector::draw_diff(draw, theme, fonts, [a, b], Vector2::new(340., 10.));
+    }
+}
+
+/// What is shown of the lockstep session, or of its loss, or else of
+/// the governor holding back the simulation, if anything.
+fn network_status(session: Option<&Session>, error: Option<&str>, waiting: bool, governor: Option<&Governor>, theme: &Theme) -> Option<(String, Color)> {
+    match (session, error) {
+        (Some(session), _) => {
+            let role = if session.is_host() { "host" } else { "client" };
+            Some(match session.desync() {
+                Some(tick) => (format!("Lockstep {}: desynchronized at tick {}", role, tick), theme.error),
+                None if waiting => (format!("Lockstep {}: waiting for peer", role), theme.warning),
+                None => (format!("Lockstep {}", role), theme.ok),
+            })
+        },
+        (None, Some(error)) => Some((error.to_string(), theme.error)),
+        (None, None) => governor.and_then(Governor::degraded).map(|degraded| (format!("Degraded: {}", degraded), theme.warning)),
+    }
+}
+
+/// Account for the memory the worlds and the GIF history take, thinning
+/// the statistics, or else trimming the history, when over the budget.
+fn account_memory(worlds: &mut [World], recorder: &mut Recorder, budget: &Budget) -> Usage {
+    let mut usage = Usage::new();
+    let several = worlds.len() > 1;
+    for world in worlds.iter() {
+        let prefix = if several { format!("{} ", &world.label[..1]) } else { String::new() };
+        world.sim.account(&mut usage, &prefix);
+    }
+    usage.add("recorder".to_string(), recorder.bytes());
+    if usage.total() > budget.bytes {
+        let excess = usage.total() - budget.bytes;
+        let mut thinned = false;
+        for world in worlds.iter_mut() {
+            thinned |= world.sim.stats_mut().thin();
+        }
+        if !thinned {
+            recorder.trim(recorder.bytes().saturating_sub(excess));
+        }
+    }
+    usage
+}
+
+/// The notice of the latest event let through as a toast in the ticks
+/// since `first_tick`, those nearest the middle of each view first.
+fn toast(worlds: &mut [World], views: &[(Rectangle, Camera)], first_tick: u64, seconds: f64, theme: &Theme) -> Option<(String, Color)> {
+    let several = worlds.len() > 1;
+    let mut latest = None;
+    for (world, (rect, view)) in worlds.iter_mut().zip(views) {
+        let center = view.screen_to_world(Vector2::new(rect.x + rect.width / 2., rect.y + rect.height / 2.));
+        let prefix = if several { format!("{}: ", &world.label[..1]) } else { String::new() };
+        for passed in world.toasts.filter(&world.sim, first_tick, seconds, Some(center)) {
+            let more = if passed.held > 0 { format!(" (and {} more)", passed.held) } else { String::new() };
+            latest = match passed.event {
+                Event::Alert { region, alert, value } => Some((format!("{}{} in {} at {}{}", prefix, alert, region, value, more), theme.warning)),
+                Event::Extinct(extinction) => Some((format!("{}{} died out, having been {} at most", prefix, extinction.species, extinction.peak), theme.warning)),
+                Event::Weather(weather) => Some((format!("{}{} began", prefix, weather.kind.name()), theme.accent)),
+                Event::Record(record) => Some((format!("{}New record! {}", prefix, record.describe()), theme.accent)),
+                _ => latest,
+            };
+        }
+    }
+    latest
+}
+
+/// The panels that show something of the worlds or of past runs,
+/// toggled by keys.
+#[derive(Default)]
+struct Panels {
+    mods: bool,
+    graph: bool,
+    histogram: Option<HistogramView>,
+    /// Who eats whom in the first world, kept while it is shown.
+    food_web: Option<FoodWeb>,
+    sankey: Option<Sankey>,
+    /// Whether the records of the worlds are shown.
+    records: bool,
+    /// Past runs, read when the hall of fame is opened.
+    hall_of_fame: Option<Vec<Summary>>,
+    /// Past runs newest first, read when the browser is opened.
+    runs: Option<Vec<Summary>>,
+}
+
+impl Panels {
+    /// Where the histogram is, above the graph.
+    fn histogram_rect(screen_width: i32, screen_height: i32) -> Rectangle {
+        Rectangle::new(screen_width as f32 - 330., screen_height as f32 - 340., 320., 160.)
+    }
+
+    /// Open and close panels by the keys pressed, failing if past runs
+    /// cannot be read.
+    fn toggle(&mut self, frame: &Frame) -> Result<(), String> {
+        let past_runs = || leaderboard::load(leaderboard::PATH).map_err(|e| format!("Failed to read {}: {}", leaderboard::PATH, e));
+        if frame.is_key_pressed(KeyboardKey::KEY_M) && !frame.is_shift_down() {
+            self.mods = !self.mods;
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_G) && frame.is_shift_down() {
+            self.histogram = match &self.histogram {
+                Some(view) => view.next(),
+                None => Some(HistogramView::new()),
+            };
+        } else if frame.is_key_pressed(KeyboardKey::KEY_G) {
+            self.graph = !self.graph;
+        }
+        //  the food web, then the energy flows, then neither
+        if frame.is_key_pressed(KeyboardKey::KEY_W) {
+            (self.food_web, self.sankey) = match (&self.food_web, &self.sankey) {
+                (Some(_), _) => (None, Some(Sankey::default())),
+                (None, Some(_)) => (None, None),
+                (None, None) => (Some(FoodWeb::new()), None),
+            };
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_H) && frame.is_shift_down() {
+            self.records = !self.records;
+        } else if frame.is_key_pressed(KeyboardKey::KEY_H) {
+            self.hall_of_fame = match self.hall_of_fame {
+                Some(_) => None,
+                None => Some(past_runs()?),
+            };
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_R) {
+            self.runs = match self.runs {
+                Some(_) => None,
+                None => Some(past_runs()?.into_iter().rev().collect()),
+            };
+        }
+        Ok(())
+    }
+
+    /// Measure the first world again for the panels shown.
+    fn update(&mut self, sim: &Simulation) {
+        if let Some(web) = &mut self.food_web {
+            web.update(sim);
+            web.layout(food_web::ITERATIONS);
+        }
+        if let Some(sankey) = &mut self.sankey {
+            *sankey = Sankey::measure(sim, sankey::WINDOW);
+        }
+    }
+
+    fn draw(&self, draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, worlds: &[World], loaded_mods: &[Mod], audit_energy: bool) {
+        let (screen_width, screen_height) = (draw.get_screen_width(), draw.get_screen_height());
+        if self.records {
+            draw_records(draw, theme, fonts, worlds);
+        }
+        if let Some(runs) = &self.hall_of_fame {
+            draw_hall_of_fame(draw, theme, fonts, runs);
+        }
+        if let Some(runs) = &self.runs {
+            draw_runs(draw, theme, fonts, runs);
+        }
+        if self.mods {
+            draw_mods(draw, theme, fonts, loaded_mods);
+        }
+        if let Some(web) = &self.food_web {
+            let rect = Rectangle::new(screen_width as f32 - 270., 10., 260., 260.);
+            draw_food_web(draw, theme, fonts, web, rect);
+        }
+        if let Some(sankey) = &self.sankey {
+            let rect = Rectangle::new(screen_width as f32 - 410., 10., 400., 260.);
+            draw_sankey(draw, theme, fonts, sankey, rect);
+        }
+        if let Some(view) = &self.histogram {
+            view.draw(draw, theme, fonts, worlds[0].sim.stats(), Self::histogram_rect(screen_width, screen_height));
+        }
+        if self.graph {
+            let rect = Rectangle::new(screen_width as f32 - 330., screen_height as f32 - 170., 320., 160.);
+            draw_graph(draw, theme, fonts, &graph_series(worlds, audit_energy), rect);
+        }
+    }
+}
+
+/// What the graph shows of each world: its population and food, its
+/// energy if audited and how many live in each watched region.
+fn graph_series(worlds: &[World], audit_energy: bool) -> Vec<GraphSeries<'_>> {
+    let colors = [(Color::BLUE, Color::DARKGREEN), (Color::RED, Color::ORANGE)];
+    worlds.iter().zip(&colors)
+        .flat_map(|(world, &(population, food))| {
+            let prefix = if worlds.len() > 1 { format!("{} ", &world.label[..1]) } else { String::new() };
+            let mut ret = vec![
+                GraphSeries { stats: world.sim.stats(), name: "population".to_string(), label: prefix.clone() + "population", color: population },
+                GraphSeries { stats: world.sim.stats(), name: "food".to_string(), label: prefix.clone() + "food", color: food },
+            ];
+            if audit_energy {
+                ret.push(GraphSeries { stats: world.sim.stats(), name: "energy in".to_string(), label: prefix.clone() + "energy in", color: Color::GOLD });
+                ret.push(GraphSeries { stats: world.sim.stats(), name: "energy out".to_string(), label: prefix.clone() + "energy out", color: Color::PURPLE });
+            }
+            let region_colors = [Color::SKYBLUE, Color::LIME, Color::PINK, Color::VIOLET];
+            for (region, &color) in world.sim.regions().iter().zip(region_colors.iter().cycle()) {
+                let name = region.series(watch::Measure::Population);
+                ret.push(GraphSeries { stats: world.sim.stats(), label: prefix.clone() + &name, name, color });
+            }
+            ret
+        })
+        .collect()
+}
+
+/// Apply interventions to their worlds right away, or in lockstep
+/// schedule them to take effect after a delay on both peers. Those on
+/// the first world go into the fixture being captured, if one is.
+fn route_commands(commands: Vec<(usize, Command)>, worlds: &mut [World], mut session: Option<&mut Session>, mut capture: Option<&mut Capture>, names: &[String], species: &[Species]) {
+    let tick = worlds[0].sim.clock().tick();
+    for (world, command) in commands {
+        match session.as_deref_mut() {
+            Some(session) => session.schedule(command, tick),
+            None => worlds[world].sim.spawning_as(Source::User, |sim| apply_command(sim, command, names, species)),
+        }
+        if let (0, Some(capture)) = (world, capture.as_deref_mut()) {
+            capture.input(tick, command);
+        }
+    }
+}
+
+/// Apply what viewers asked for in chat since the last frame to a
+/// simulation, returning the notice of the latest, if any.
+fn take_chat(chat: &mut Chat, sim: &mut Simulation, now: time::Instant, names: &[String], species: &[Species]) -> Result<Option<String>, String> {
+    let mut latest = None;
+    for request in chat.poll(sim, now)? {
+        let text = match &request.intervention {
+            Intervention::Spawn(_) => format!("{} spawned a blob", request.viewer),
+            Intervention::Feed => format!("{} scattered food", request.viewer),
+            Intervention::Smite => format!("{} smote a blob", request.viewer),
+        };
+        let spawned = sim.spawning_as(Source::Chat, |sim| apply_intervention(sim, &request.intervention, names, species));
+        if let Some(key) = spawned {
+            chat.attribute(key, &request.viewer);
+        }
+        latest = Some(text);
+    }
+    Ok(latest)
+}
+
+/// The notice of what typed text did, a warning if it did nothing.
+fn noted(result: Result<String, String>, theme: &Theme, time: time::Instant) -> (String, Color, time::Instant) {
+    match result {
+        Ok(text) => (text, theme.ok, time),
+        Err(text) => (text, theme.warning, time),
+    }
+}
+
+/// What a line typed into a text box is for, see `type_into`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Prompt {
+    /// A note on the tick it was opened at.
+    Bookmark(u64),
+    /// A pattern the selected blobs are renamed by.
+    Rename,
+    /// A tag put on, taken off or looked for.
+    Tag,
+    /// A species and its new name.
+    RenameSpecies,
+    /// A species, parameter and change.
+    Override,
+    /// The file the first world is saved to or loaded from.
+    SaveAs,
+    LoadFrom,
+}
+
+impl Prompt {
+    /// The prompt the keys of a frame open at a tick, if any.
+    fn opened(frame: &Frame, tick: u64, loading: bool) -> Option<Self> {
+        let shift = frame.is_shift_down();
+        let pressed = |key| frame.is_key_pressed(key);
+        if pressed(KeyboardKey::KEY_F10) && shift {
+            Some(Self::SaveAs)
+        } else if pressed(KeyboardKey::KEY_F11) && shift && !loading {
+            Some(Self::LoadFrom)
+        } else if pressed(KeyboardKey::KEY_T) && shift {
+            Some(Self::Tag)
+        } else if pressed(KeyboardKey::KEY_S) && shift {
+            Some(Self::RenameSpecies)
+        } else if pressed(KeyboardKey::KEY_O) && shift {
+            Some(Self::Override)
+        } else if pressed(KeyboardKey::KEY_N) && shift {
+            Some(Self::Rename)
+        } else if pressed(KeyboardKey::KEY_B) && !shift {
+            Some(Self::Bookmark(tick))
+        } else {
+            None
+        }
+    }
+
+    /// Why the prompt cannot be opened now, if it cannot.
+    fn refused(self, lockstep: bool, nothing_selected: bool) -> Option<&'static str> {
+        match self {
+            //  tags, species, overrides and names are not sent to the peer
+            Self::Tag if lockstep => Some("Blobs cannot be tagged in a lockstep session"),
+            Self::RenameSpecies if lockstep => Some("Species cannot be renamed in a lockstep session"),
+            Self::Override if lockstep => Some("Species parameters cannot be overridden in a lockstep session"),
+            Self::Rename if lockstep => Some("Blobs cannot be renamed in a lockstep session"),
+            Self::Rename if nothing_selected => Some("Select blobs to rename them"),
+            _ => None,
+        }
+    }
+
+    /// The title of its text box.
+    fn title(self, sim: &Simulation, selected: usize, recent: &Recent) -> String {
+        match self {
+            Self::Bookmark(tick) => format!("Bookmark tick {}", tick),
+            Self::Rename => format!("Rename {} blobs, # counting and * for the name", selected),
+            Self::Tag => format!("Tag {} blobs with name or name:color, -name to take it off, ?name to find", selected),
+            Self::RenameSpecies => {
+                let living: Vec<_> = sim.census().records().map(|(species, _)| species).collect();
+                format!("Rename a species as old=new, merging it into a species of that name: {}", living.join(", "))
+            },
+            Self::Override => {
+                let overrides = sim.overrides();
+                let now = if overrides.is_empty() { "none yet".to_string() } else { overrides.describe() };
+                format!("Override a species as Grazer mutation_rate x2 or -20% ({}), now {}", overrides::NAMES.join(", "), now)
+            },
+            Self::SaveAs => format!("Save the world as, recently {}", recent.paths().join(", ")),
+            Self::LoadFrom => format!("Load the world from, recently {}", recent.paths().join(", ")),
+        }
+    }
+
+    /// Do what was typed to the worlds, but for saving and loading,
+    /// which the caller does.
+    fn apply(self, typed: &str, worlds: &mut [World], selected: &mut HashSet<(usize, keyed_set::Key<Blob>)>) -> Result<String, String> {
+        match self {
+            Self::Bookmark(tick) => {
+                let note = runs::clean_notes(typed);
+                for world in worlds.iter_mut() {
+                    world.sim.bookmark(tick, &note);
+                }
+                Ok(format!("Bookmarked tick {}", tick))
+            },
+            Self::Rename => rename_blobs(worlds, selected, typed),
+            Self::Tag => edit_tags(worlds, selected, typed),
+            Self::RenameSpecies => rename_species(worlds, typed),
+            Self::Override => override_species(worlds, typed),
+            Self::SaveAs | Self::LoadFrom => unreachable!("saving and loading are done by the caller"),
+        }
+    }
+}
+
+/// Rename the selected blobs by a pattern, see `naming::rename`,
+/// numbering them in key order.
+fn rename_blobs(worlds: &mut [World], selected: &HashSet<(usize, keyed_set::Key<Blob>)>, pattern: &str) -> Result<String, String> {
+    let mut selected: Vec<_> = selected.iter().copied().collect();
+    selected.sort();
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("Renamed nothing, the pattern was empty".to_string());
+    }
+    for (i, &(world, key)) in selected.iter().enumerate() {
+        if let Some(blob) = worlds[world].sim.get_blob_mut(key) {
+            blob.name = Some(naming::rename(pattern, blob.name.as_deref(), i + 1));
+        }
+    }
+    Ok(format!("Renamed {} blobs by {}", selected.len(), pattern))
+}
+
+/// Watch a region of a world as typed, see `watch::Region::parse`, or
+/// stop watching one typed as -name.
+fn watch_region(sim: &mut Simulation, rect: Rectangle, typed: &str) -> Result<String, String> {
+    match typed.trim().strip_prefix('-') {
+        Some(name) if sim.unwatch(name) => Ok(format!("Stopped watching {}", name)),
+        Some(name) => Err(format!("No region named {} is watched", name)),
+        None => {
+            let region = watch::Region::parse(typed, Some(rect))?;
+            let text = format!("Watching {} with {} alerts", region.name, region.alerts.len());
+            sim.watch(region);
+            Ok(text)
+        },
+    }
+}
+
+/// Put a tag on the selected blobs, take one off or select the blobs
+/// with one, as typed, see `tags::parse_edit`.
+fn edit_tags(worlds: &mut [World], selected: &mut HashSet<(usize, keyed_set::Key<Blob>)>, typed: &str) -> Result<String, String> {
+    let mut sorted: Vec<_> = selected.iter().copied().collect();
+    sorted.sort();
+    Ok(match tags::parse_edit(typed)? {
+        tags::Edit::Add(tag) => {
+            for &(world, key) in &sorted {
+                if let Some(blob) = worlds[world].sim.get_blob_mut(key) {
+                    blob.tag(tag.clone());
+                }
+            }
+            format!("Tagged {} blobs {}", sorted.len(), tag.text)
+        },
+        tags::Edit::Remove(text) => {
+            let untagged = sorted.iter()
+                .filter(|&&(world, key)| worlds[world].sim.get_blob_mut(key).is_some_and(|blob| blob.untag(&text)))
+                .count();
+            format!("Took {} off {} blobs", text, untagged)
+        },
+        tags::Edit::Find(text) => {
+            *selected = worlds.iter().enumerate()
+                .flat_map(|(i, world)| world.sim.blobs().filter(|(_, blob)| tags::has(blob, &text)).map(move |(key, _)| (i, key)))
+                .collect();
+            format!("Selected {} blobs tagged {}", selected.len(), text)
+        },
+    })
+}
+
+/// Rename a species in every world as typed as old=new, merging it
+/// into a species of the new name if there is one.
+fn rename_species(worlds: &mut [World], typed: &str) -> Result<String, String> {
+    match typed.split_once('=').map(|(from, to)| (from.trim(), to.trim())) {
+        Some((from, to)) if !from.is_empty() && !to.is_empty() => {
+            let merges = worlds.iter().any(|world| world.sim.census().records().any(|(species, _)| species == to));
+            let renamed: usize = worlds.iter_mut().map(|world| world.sim.rename_species(from, to)).sum();
+            let verb = if merges { "Merged" } else { "Renamed" };
+            Ok(format!("{} {} into {}, {} blobs", verb, from, to, renamed))
+        },
+        _ => Err("Type the species and its new name as old=new".to_string()),
+    }
+}
+
+/// Override a parameter of a species as typed, see `overrides::parse`,
+/// in every world so compared worlds stay alike, marking it.
+fn override_species(worlds: &mut [World], typed: &str) -> Result<String, String> {
+    let (name, parameter, factor) = overrides::parse(typed)?;
+    for world in worlds {
+        world.sim.set_override(&name, &parameter, factor)?;
+        world.sim.mark(&format!("{} {} x{}", name, parameter, factor));
+    }
+    Ok(format!("{} of {} is now multiplied by {}", parameter, name, factor))
+}
+
+/// Read the input of a frame, replaying it if there is any left and
+/// recording it if asked, with the seconds since the last frame's.
+fn read_frame(handle: &RaylibHandle, playback: &mut Option<VecDeque<Frame>>, input_log: &mut Option<InputLog>, last_input_time: &mut Option<f64>) -> (Frame, f32) {
+    let frame = match playback.as_mut().and_then(VecDeque::pop_front) {
+        Some(frame) => frame,
+        None => {
+            //  the window's clock differs from the recording's
+            if playback.take().is_some() {
+                *last_input_time = None;
+            }
+            Frame::capture(handle)
+        },
+    };
+    if let Some(Err(e)) = input_log.as_mut().map(|out| frame.write(out)) {
+        eprintln!("failed to record input, stopping: {}", e);
+        *input_log = None;
+    }
+    let delta_time = (frame.time - last_input_time.unwrap_or(frame.time)) as f32;
+    *last_input_time = Some(frame.time);
+    (frame, delta_time)
+}
+
+fn main() {
+    let options = Options::parse(env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, Options::USAGE);
+        process::exit(2);
+    });
+    if options.help {
+        println!("{}", Options::USAGE);
+        return;
+    }
+    if let Some(mode) = options.render_test {
+        process::exit(if render_test(mode) { 0 } else { 1 });
+    }
+    if let Some((paths, arena)) = &options.tournament {
+        process::exit(if run_tournament(paths, arena) { 0 } else { 1 });
+    }
+    if let Some((paths, benchmark, json)) = &options.forage {
+        process::exit(if run_foraging(paths, benchmark, json.as_deref()) { 0 } else { 1 });
+    }
+    if let Some((bench, json)) = &options.bench {
+        process::exit(if run_bench(bench, json.as_deref()) { 0 } else { 1 });
+    }
+    if options.headless {
+        process::exit(if run_headless(&options) { 0 } else { 1 });
+    }
+    if let Some(paths) = &options.brain_json {
+        for path in paths {
+            match Exported::load(path) {
+                Ok(exported) => println!("{}", exported.brain_json()),
+                Err(e) => {
+                    eprintln!("failed to load a blob: {}", e);
+                    process::exit(1);
+                },
+            }
+        }
+        return;
+    }
+    if options.doctor {
+        process::exit(if run_doctor() { 0 } else { 1 });
+    }
+    shutdown::install();
+    crash::install();
+    let start_time = time::Instant::now();
+
+    //  options
+    let max_speed = 64.;
+    let zoom_step = 1.2f32;
+    let notice_duration = time::Duration::from_secs(4);
+    let fast_forward_ticks = 10_000;
+    //  how much real time a frame of fast-forwarding may take
+    let fast_forward_budget = time::Duration::from_millis(30);
+    let window_config = WindowConfig {
+        width: 1300,
+        height: 680,
+        title: "Blobs",
+        antialiasing: true,
+        //  a saved monitor that cannot be read is not worth stopping for
+        monitor: options.monitor.or_else(|| placement::load(placement::PATH).unwrap_or_else(|e| {
+            eprintln!("failed to read the saved monitor: {}", e);
+            None
+        })),
+        position: options.window_position,
+        on_top: options.on_top,
+        fullscreen: options.screensaver,
+    };
+
+    //  connect to the peer, who must start from the same setup
+    let world_count = if options.compare.is_some() { 2 } else { 1 };
+    let mut setup = Setup {
+        seed: options.seed.unwrap_or_else(random),
+        size: options.world_size
+            .unwrap_or_else(|| Vector2::new(window_config.width as f32 / world_count as f32, window_config.height as f32)),
+        mutators: options.mutators.clone(),
+        timing: options.timing,
+    };
+    //  a shared world is grown again to the tick it was shared at
+    let mut code_ticks = 0;
+    if let Some(code) = &options.world_code {
+        setup = code.setup.clone();
+        code_ticks = code.tick;
+    }
+    //  the content, which a peer must have loaded too
+    let settings = content_settings(&options);
+    let catalog = Catalog::discover().unwrap_or_else(|e| panic!("failed to load the content: {}", e));
+    let (mut loaded_mods, mut species, mut names) = catalog.resolve(&settings);
+    let content = lockstep::fingerprint(&loaded_mods, &species, &names);
+    let mut session = options.network.as_ref().map(|network| open_session(network, &mut setup, content));
+    let mut network_error: Option<String> = None;
+
+    //  replayed input runs in the world it was recorded in
+    let mut bundle = None;
+    let mut playback: Option<VecDeque<Frame>> = options.play_input.as_ref().map(|path| {
+        let (frames, read) = read_playback(path, &mut setup, &mut code_ticks);
+        bundle = read;
+        frames
+    });
+    let mut input_log: Option<InputLog> = options.record_input.as_ref().map(|path| open_input_log(path, setup.seed));
+
+    //  allocate resources
+    let mut window = Window::new(&window_config);
+    let fonts = window.load_fonts(Fonts::PATH).unwrap_or_else(|e| {
+        eprintln!("failed to load the font, using the default one: {}", e);
+        Fonts::new()
+    });
+    if let (Some(bundle), Some(path)) = (&bundle, &options.play_input) {
+        bundle.check(&replay::fingerprints(&loaded_mods), &options.compare)
+            .unwrap_or_else(|e| panic!("cannot play {}: {}", path, e));
+    }
+    let mut fertility = options.fertility.as_ref()
+        .map(|path| Arc::new(FertilityMap::load(path).unwrap_or_else(|e| panic!("failed to read the fertility map: {}", e))));
+    let mut scenario = load_scenario(&options, &settings);
+    let mut naming = load_naming(&options, &settings, &loaded_mods);
+    let recommendation = recommend(&mut window, &fonts, &options, &names, &species);
+    let mut worlds = create_worlds(&setup, &options, fertility.as_ref(), scenario.as_ref(), &naming, &names, &species);
+    let mut panels = Panels::default();
+    let mut loading: Option<Loading> = None;
+    let mut show_memory = false;
+    let mut overlay = Overlay::None;
+    //  how many blobs to skip between those drawn by the AI debug view, if it is shown
+    let mut ai_stride: Option<usize> = None;
+    let mut tuning: Option<TuningPanel> = None;
+    let mut scenario_editor: Option<ScenarioEditor> = None;
+    //  where the scenario is saved, once it has a file
+    let mut scenario_path = options.scenario.as_deref().or(settings.scenario()).map(scenario::resolve).filter(|_| scenario.is_some());
+    //  the content browser, while it is open
+    let mut browser: Option<Browser> = None;
+    let mut prediction: Option<Prediction> = None;
+    let mut governor = Some(Governor::new(if options.target_fps > 0 { options.target_fps } else { quality::DEFAULT_TARGET_FPS }))
+        .filter(|_| options.realtime);
+    //  the brain period before the governor slowed brains down, while it does
+    let mut slowed_brains: Option<u32> = None;
+    let mut low_power = LowPower::new(options.low_power);
+    let unfocused = options.unfocused;
+    //  the tick rate before low-power mode lowered it, while it does
+    let mut full_tick_rate: Option<u32> = None;
+    let mut quality = Some(AutoQuality::new(options.target_fps, recommendation.tier)).filter(|_| options.target_fps > 0);
+    let mut import_menu: Option<ImportMenu> = None;
+    let mut blob_style = options.style;
+    let mut crowding = false;
+    let mut trace_view = trace::View::default();
+    let mut notes = String::new();
+    let mut editing_notes = false;
+    //  what is typed into a text box and for what, while it is open
+    let mut prompt: Option<(Prompt, String)> = None;
+    //  keyboard-only operation, see `keyboard`
+    let mut keyboard_cursor: Option<Cursor> = None;
+    let mut palette: Option<Palette> = None;
+    let actions = keyboard::actions();
+    //  the first world and the interventions on it, while a fixture is captured
+    let mut capture: Option<Capture> = None;
+    let mut recent = Recent::load(recent::PATH).unwrap_or_else(|e| {
+        eprintln!("failed to read the recent files in {}: {}", recent::PATH, e);
+        Recent::default()
+    });
+    let mut budget = options.budget;
+    if budget.entities == Budget::DEFAULT_ENTITIES {
+        budget.entities = recommendation.entities;
+    }
+    //  whether anything was over the budget last frame
+    let mut over_budget = false;
+    let mut layers = LayerVisibility::new();
+    //  post-processing would tint a key color
+    //  what is wrong with the environment, shown as a warning sign, see `doctor`
+    let mut diagnosis = doctor::diagnose();
+    let mut post_processing = if options.post_processing && options.chroma_key.is_none() {
+        window.load_post_processing()
+            .map_err(|e| {
+                eprintln!("failed to load post-processing, drawing without it: {}", e);
+                diagnosis.push(Check::new("renderer", Status::Failed, format!("post-processing: {}", e)));
+            })
+            .ok()
+    } else {
+        None
+    };
+    for check in doctor::problems(&diagnosis) {
+        eprintln!("{} {}: {}", check.status.name(), check.name, check.detail);
+    }
+    let keyed = |theme: Theme| options.chroma_key.map_or(theme, |key| stream::keyed(theme, key));
+    let mut theme = keyed(options.theme);
+    if options.chroma_key.is_some() {
+        layers.toggle(RenderLayer::Ui);
+    }
+    let mut stream_stats = options.stream_stats.as_deref().map(StatsFile::new);
+    let mut chat = options.chat.clone().map(|channel| {
+        Chat::connect(options.chat_server.clone().unwrap_or_else(|| chat::TWITCH.to_string()), channel)
+    });
+    let mut recorder = Recorder::new(options.gif_seconds);
+    //  opened on the first frame, which has the thread it needs
+    let mut sonify = options.sonify;
+    let mut sonification: Option<Sonification> = None;
+    let mut narrator: Option<Narrator<Box<dyn io::Write>>> = options.narrate.as_deref().map(create_output).map(Narrator::new);
+    let mut observed: Option<Box<dyn io::Write>> = options.observe.as_deref().map(create_output);
+    //  the outcome of the last action, with when it happened
+    let mut notice: Option<(String, Color, time::Instant)> = None;
+    let mut input = InputController::new(InteractionMode::Drag);
+    let mut camera = Camera::new();
+    //  zoom out as far as needed, and allowed, to show all of a world larger than its view
+    let first_view = viewport(0, worlds.len(), window_config.width as i32, window_config.height as i32);
+    let fit = (first_view.width / setup.size.x).min(first_view.height / setup.size.y);
+    if fit < 1. {
+        camera.zoom = fit.max(Camera::MIN_ZOOM);
+    }
+    let mut tour = options.tour.as_ref()
+        .map(|path| Tour::load(path).unwrap_or_else(|e| panic!("failed to read the tour {}: {}", path, e)))
+        .unwrap_or_default();
+    //  seconds into the tour while it plays, and when the recording of one started
+    let mut tour_time: Option<f32> = options.tour.as_ref().map(|_| 0.);
+    let mut recording_start: Option<f64> = None;
+    //  the screensaver hides the interface and moves the camera itself
+    let mut screensaver = options.screensaver.then(|| Screensaver::new(setup.size / 2.));
+    if screensaver.is_some() {
+        layers.toggle(RenderLayer::Ui);
+    }
+
+    //  the time of the last frame's input, which paces the simulation
+    let mut last_input_time: Option<f64> = None;
+    let mut interaction = Interaction::new();
+    let mut paused = false;
+    //  why the run paused by itself, shown until it is resumed
+    let mut pause_reason: Option<String> = None;
+    let mut speed = 1f32;
+    let mut pending_time = 0f32;
+    //  whether the first world was changed in ways a world code leaves out
+    let mut by_hand = fertility.is_some();
+    let mut fast_forward = Some(FastForward { done: 0, total: options.fast_forward + code_ticks })
+        .filter(|fast_forward| fast_forward.total > 0);
+    //  a panic leaves the worlds as they were, to be dumped before unwinding further
+    let outcome = panic::catch_unwind(panic::AssertUnwindSafe(|| window.draw_loop(|mut draw, thread| {
+        //  read input, replaying it if there is any left, and calculate delta
+        let frame_time = time::Instant::now();
+        let (mut frame, delta_time) = read_frame(&draw, &mut playback, &mut input_log, &mut last_input_time);
+        if screensaver.as_mut().is_some_and(|screensaver| screensaver.woken(&frame)) {
+            shutdown::request();
+            return;
+        }
+
+        //  save power on battery, but keep what a peer or a replay depends on
+        if let Some(active) = low_power.update(frame_time) {
+            window::set_target_fps(if active { power::FPS } else { 0 });
+            if active {
+                for world in &mut worlds {
+                    world.numbers = None;
+                    world.bubbles = None;
+                }
+            }
+            notice = Some((format!("Low-power mode: {}", if active { "on" } else { "off" }), theme.muted_text, frame_time));
+        }
+        let steady = session.is_none() && input_log.is_none() && playback.is_none();
+        let timing = *worlds[0].sim.timing();
+        //  worlds made anew, such as by pasting a world code, are lowered again
+        let tick_rate = match full_tick_rate {
+            _ if low_power.active() && steady && timing.tick_rate > power::TICK_RATE => {
+                full_tick_rate = Some(timing.tick_rate);
+                Some(power::TICK_RATE)
+            },
+            Some(tick_rate) if !low_power.active() => {
+                full_tick_rate = None;
+                Some(tick_rate)
+            },
+            _ => None,
+        };
+        if let Some(tick_rate) = tick_rate {
+            for world in &mut worlds {
+                world.sim.set_timing(Timing { tick_rate, ..timing }).unwrap();
+            }
+        }
+        //  the focus is part of the input, so replays do the same while away
+        let away = !frame.focused;
+        let background = low_power.active() && steady && away;
+        let hidden = away && unfocused == Unfocused::Background;
+        if background || hidden {
+            thread::sleep(power::BACKGROUND_SLEEP);
+        }
+        let standing = background || away && unfocused == Unfocused::Pause;
+
+        //  the command palette takes the keys while it is open, and runs an action by pressing its keys
+        if frame.is_key_pressed(KeyboardKey::KEY_P) && frame.is_ctrl_down() {
+            palette = match palette {
+                Some(_) => None,
+                None => Some(Palette::default()),
+            };
+            frame.clear_keys();
+        } else if let Some(open) = &mut palette {
+            match open.update(&frame, &actions) {
+                Some(action) => {
+                    palette = None;
+                    keyboard::run(&action, &mut frame);
+                },
+                None => frame.clear_keys(),
+            }
+        }
+
+        //  the file to save the first world to or load it from this frame
+        let mut save_to: Option<String> = None;
+        let mut load_from: Option<String> = None;
+        //  the notes, bookmarks and renaming patterns take the keys while they are typed
+        if editing_notes {
+            if type_into(&mut notes, &mut frame) {
+                notes = runs::clean_notes(&notes);
+                editing_notes = false;
+            }
+        } else if let Some((kind, mut typed)) = prompt.take() {
+            if !type_into(&mut typed, &mut frame) {
+                prompt = Some((kind, typed));
+            } else if kind == Prompt::SaveAs {
+                save_to = Some(typed.trim().to_string()).filter(|path| !path.is_empty());
+            } else if kind == Prompt::LoadFrom {
+                load_from = Some(typed.trim().to_string()).filter(|path| !path.is_empty());
+            } else {
+                let result = kind.apply(&typed, &mut worlds, &mut interaction.selected);
+                by_hand |= kind == Prompt::Override && result.is_ok();
+                notice = Some(noted(result, &theme, frame_time));
+            }
+        } else if let Some((world, rect, mut typed)) = interaction.watching.take() {
+            if type_into(&mut typed, &mut frame) {
+                notice = Some(noted(watch_region(&mut worlds[world].sim, rect, &typed), &theme, frame_time));
+            } else {
+                interaction.watching = Some((world, rect, typed));
+            }
+        } else if frame.is_key_pressed(KeyboardKey::KEY_N) && !frame.is_shift_down() {
+            editing_notes = true;
+        } else if let Some(kind) = Prompt::opened(&frame, worlds[0].sim.clock().tick(), loading.is_some()) {
+            match kind.refused(session.is_some(), interaction.selected.is_empty()) {
+                Some(refused) => notice = Some((refused.to_string(), theme.warning, frame_time)),
+                None => {
+                    let typed = match kind {
+                        Prompt::SaveAs | Prompt::LoadFrom => recent.latest().unwrap_or(Simulation::SAVE_PATH).to_string(),
+                        _ => String::new(),
+                    };
+                    prompt = Some((kind, typed));
+                },
+            }
+        }
+
+        //  fast-forward without drawing the world, until done or stopped
+        if frame.is_key_pressed(KeyboardKey::KEY_F) && session.is_none() {
+            fast_forward = match fast_forward {
+                Some(_) => None,
+                None => Some(FastForward { done: 0, total: fast_forward_ticks }),
+            };
+        }
+        if let Some(progress) = &mut fast_forward {
+            while progress.done < progress.total && frame_time.elapsed() < fast_forward_budget {
+                for world in &mut worlds {
+                    world.tick(&names, &species);
+                }
+                progress.done += 1;
+            }
+            if progress.done < progress.total {
+                draw.clear_background(theme.background);
+                draw_fast_forward(&mut draw, &theme, &fonts, progress);
+                return;
+            }
+            fast_forward = None;
+            pending_time = 0.;
+        }
+        let (screen_width, screen_height) = (draw.get_screen_width(), draw.get_screen_height());
+
+        //  the keyboard cursor stands in for the mouse while it is on, leaving the arrows to the import menu
+        if frame.is_key_pressed(KeyboardKey::KEY_Z) {
+            keyboard_cursor = match keyboard_cursor {
+                Some(_) => None,
+                None => Some(Cursor::new(frame.mouse)),
+            };
+            let text = if keyboard_cursor.is_some() { "Keyboard cursor: arrows move, Enter clicks, Ctrl pans, Alt zooms" } else { "Keyboard cursor: off" };
+            notice = Some((text.to_string(), theme.ok, frame_time));
+        }
+        if let Some(cursor) = keyboard_cursor.as_mut().filter(|_| import_menu.is_none()) {
+            let pan = cursor.apply(&mut frame, delta_time, Vector2::new(screen_width as f32, screen_height as f32));
+            if pan != Vector2::zero() {
+                camera.pan(pan);
+                tour_time = None;
+                worlds[0].director = None;
+            }
+        }
+
+        //  zoom around the cursor, within the view it is over, or scroll a trace under it
+        let wheel = frame.wheel;
+        let trace_rect = trace::View::rect(screen_width, screen_height);
+        let shown_trace = worlds.iter().find_map(|world| world.sim.trace());
+        if let Some(browser) = browser.as_mut().filter(|_| wheel != 0. && Browser::rect(screen_width).check_collision_point_rec(frame.mouse)) {
+            browser.scroll(wheel);
+        } else if let Some(trace) = shown_trace.filter(|_| wheel != 0. && trace_rect.check_collision_point_rec(frame.mouse)) {
+            trace_view.scroll(wheel, trace);
+        } else if wheel != 0. {
+            let mouse = frame.mouse;
+            let rect = (0..worlds.len())
+                .map(|i| viewport(i, worlds.len(), screen_width, screen_height))
+                .find(|rect| rect.check_collision_point_rec(mouse))
+                .unwrap_or_else(|| viewport(0, worlds.len(), screen_width, screen_height));
+            camera.zoom_at(mouse - Vector2::new(rect.x, rect.y), zoom_step.powf(wheel));
+        }
+
+        //  camera tours, recorded a keyframe at a time, with the middle of the first view
+        let first = viewport(0, worlds.len(), screen_width, screen_height);
+        let half_view = Vector2::new(first.width, first.height) / 2.;
+        if frame.is_key_pressed(KeyboardKey::KEY_J) && frame.is_shift_down() {
+            tour_time = match tour_time {
+                Some(_) => None,
+                None if tour.keyframes().is_empty() => {
+                    notice = Some(("There is no tour to play, add keyframes with J".to_string(), theme.warning, frame_time));
+                    None
+                },
+                None => {
+                    notice = Some((format!("Playing the tour of {:.1} seconds", tour.duration()), theme.ok, frame_time));
+                    Some(0.)
+                },
+            };
+        } else if frame.is_key_pressed(KeyboardKey::KEY_J) {
+            let start = *recording_start.get_or_insert_with(|| {
+                tour = Tour::new();
+                frame.time
+            });
+            let time = (frame.time - start) as f32;
+            let (text, color) = match add_keyframe(&mut tour, time, &camera, half_view) {
+                Ok(text) => (text, theme.ok),
+                Err(e) => (e, theme.error),
+            };
+            notice = Some((text, color, frame_time));
+        }
+        //  numbered viewpoints of the first world, saved with Ctrl+Shift and recalled with Ctrl
+        if frame.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || frame.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL) {
+            if let Some(number) = Viewpoints::KEYS.iter().position(|&key| frame.is_key_pressed(key)).map(|i| i + 1) {
+                notice = Some(if frame.is_shift_down() {
+                    let viewpoint = Viewpoint { center: camera.target + half_view / camera.zoom, zoom: camera.zoom };
+                    match worlds[0].sim.viewpoints_mut().set(number, viewpoint) {
+                        Ok(()) => (format!("Saved viewpoint {}, recall it with Ctrl+{}", number, number), theme.ok, frame_time),
+                        Err(e) => (format!("Failed to save viewpoint {}: {}", number, e), theme.error, frame_time),
+                    }
+                } else if let Some(viewpoint) = worlds[0].sim.viewpoints().get(number) {
+                    aim(&mut camera, viewpoint.center, viewpoint.zoom, half_view);
+                    tour_time = None;
+                    worlds[0].director = None;
+                    (format!("Viewpoint {}", number), theme.ok, frame_time)
+                } else {
+                    (format!("There is no viewpoint {}, save one with Ctrl+Shift+{}", number, number), theme.warning, frame_time)
+                });
+            }
+        }
+        //  the director follows what happens in the first world, until the camera is taken over
+        if frame.is_key_pressed(KeyboardKey::KEY_Q) {
+            let on = worlds[0].director.is_none();
+            worlds[0].director = Some(Director::new(camera.target + half_view / camera.zoom)).filter(|_| on);
+            tour_time = None;
+            notice = Some((format!("Director: {}", if on { "on" } else { "off" }), theme.ok, frame_time));
+        }
+        let panning = input.active_gesture().is_some_and(|(mode, _)| mode == InteractionMode::Pan);
+        if tour_time.is_some() || wheel != 0. || panning {
+            worlds[0].director = None;
+        }
+        let World { sim, director, .. } = &mut worlds[0];
+        if let Some(director) = director {
+            let (center, zoom) = director.update(sim, delta_time);
+            aim(&mut camera, center, zoom, half_view);
+        }
+        if let Some(screensaver) = &mut screensaver {
+            let (center, zoom) = screensaver.update(&worlds[0].sim, delta_time);
+            aim(&mut camera, center, zoom, half_view);
+        }
+        if let Some(time) = tour_time {
+            match tour.sample(time) {
+                Some((center, zoom)) => {
+                    aim(&mut camera, center, zoom, half_view);
+                    tour_time = Some(time + delta_time);
+                },
+                None => tour_time = None,
+            }
+        }
+
+        let views: Vec<(Rectangle, Camera)> = (0..worlds.len())
+            .map(|i| viewport(i, worlds.len(), screen_width, screen_height))
+            .map(|rect| (rect, camera.with_offset(Vector2::new(rect.x, rect.y))))
+            .collect();
+
+        //  sliders change the parameters of every world, and a gesture starting on them is theirs
+        let histogram_rect = Panels::histogram_rect(screen_width, screen_height);
+        let captured = tuning.as_ref().is_some_and(|panel| panel.captures(&frame, screen_height))
+            || browser.as_ref().is_some_and(|browser| browser.captures(&frame, screen_width))
+            || scenario_editor.as_ref().is_some_and(|editor| editor.captures(&frame, screen_height))
+            || panels.histogram.as_ref().is_some_and(|view| view.captures(&frame, histogram_rect))
+            || loading.is_some() && frame.button == input::Button::Pressed && Loading::cancel_button(screen_width, screen_height).check_collision_point_rec(frame.mouse);
+        if let Some(view) = &mut panels.histogram {
+            view.update(&frame, worlds[0].sim.stats(), histogram_rect);
+        }
+        if let Some(change) = tuning.as_mut().and_then(|panel| panel.update(&frame, worlds[0].sim.parameters(), screen_height)) {
+            for world in &mut worlds {
+                world.sim.set_parameter(change.name, &change.value).unwrap();
+                if change.done {
+                    world.sim.mark(&change.label());
+                }
+            }
+            by_hand = true;
+        }
+
+        //  the content chosen is kept for the next world, made now with the new world button
+        match browser.as_mut().and_then(|browser| browser.update(&frame, screen_width)) {
+            Some(content::Outcome::Changed) => if let Err(e) = browser.as_ref().unwrap().settings().save(content::PATH) {
+                notice = Some((format!("Failed to save the content chosen: {}", e), theme.error, frame_time));
+            },
+            Some(content::Outcome::NewWorld) => {
+                let browser = browser.as_ref().unwrap();
+                (loaded_mods, species, names) = browser.catalog().resolve(browser.settings());
+                naming = load_naming(&options, browser.settings(), &loaded_mods);
+                scenario = load_scenario(&options, browser.settings());
+                scenario_path = options.scenario.as_deref().or(browser.settings().scenario()).map(scenario::resolve).filter(|_| scenario.is_some());
+                scenario_editor = None;
+                worlds = create_worlds(&setup, &options, fertility.as_ref(), scenario.as_ref(), &naming, &names, &species);
+                interaction = Interaction::new();
+                pending_time = 0.;
+                by_hand = true;
+                notice = Some((format!("Made a new world with {} species and {} names", species.len(), names.len()), theme.ok, frame_time));
+            },
+            None => (),
+        }
+
+        //  the scenario's events change in every world, and in the worlds a reset creates
+        //  zones are the same in every world, marked in whichever the mouse is over
+        let (_, mouse_view) = views.iter()
+            .find(|(rect, _)| rect.check_collision_point_rec(frame.mouse))
+            .unwrap_or(&views[0]);
+        let mouse_world = mouse_view.screen_to_world(frame.mouse);
+        match scenario_editor.as_mut().and_then(|editor| editor.update(&frame, worlds[0].sim.clock().tick(), screen_height, mouse_world)) {
+            Some(scenario_editor::Outcome::Changed) => {
+                scenario = scenario_editor.as_ref().map(|editor| Arc::new(editor.scenario().clone()));
+                for world in &mut worlds {
+                    world.scenario = scenario.clone();
+                }
+                by_hand = true;
+            },
+            Some(scenario_editor::Outcome::Saved(Ok(path))) => {
+                notice = Some((format!("Saved the scenario to {}", path.display()), theme.ok, frame_time));
+                scenario_path = Some(path);
+            },
+            Some(scenario_editor::Outcome::Saved(Err(e))) => {
+                notice = Some((format!("Failed to save the scenario: {}", e), theme.error, frame_time));
+            },
+            Some(scenario_editor::Outcome::Cut(rect)) => {
+                let chunk = Chunk::cut(&worlds[0].sim, rect);
+                let count = chunk.lights.len() + chunk.foods.len() + chunk.blobs.len();
+                notice = Some(match chunk.save(chunk::DIR) {
+                    Ok(path) => (format!("Cut {} things out to {}", count, path.display()), theme.ok, frame_time),
+                    Err(e) => (format!("Failed to write the chunk: {}", e), theme.error, frame_time),
+                });
+            },
+            None => (),
+        }
+
+        //  handle mouse gestures, collecting the interventions they make
+        let gesture = if captured { None } else { input.update(&frame, screen_width) };
+        let mut commands: Vec<(usize, Command)> = match gesture {
+            Some(gesture) => interaction.handle(gesture, &mut worlds, &views, &mut camera, paused),
+            None => vec![],
+        };
+        if frame.is_key_down(KeyboardKey::KEY_SPACE) {
+            commands.extend((0..worlds.len()).map(|world| (world, Command::SpawnBlob)));
+        }
+
+        by_hand |= !commands.is_empty();
+        route_commands(commands, &mut worlds, session.as_mut(), capture.as_mut(), &names, &species);
+        //  viewers' commands change the first world
+        match chat.as_mut().map(|open| take_chat(open, &mut worlds[0].sim, frame_time, &names, &species)) {
+            Some(Ok(Some(text))) => notice = Some((text, theme.accent, frame_time)),
+            Some(Ok(None)) | None => (),
+            Some(Err(e)) => {
+                eprintln!("failed to read the chat, stopping: {}", e);
+                notice = Some((format!("Chat stopped: {}", e), theme.error, frame_time));
+                chat = None;
+            },
+        }
+        if let Some(Err(e)) = session.as_mut().map(|session| session.poll(worlds[0].sim.clock().tick())) {
+            network_error = Some(format!("Lockstep connection lost: {}", e));
+            session = None;
+        }
+
+        //  blobs left unnamed by the naming rule are named once selected
+        for &(world, key) in &interaction.selected {
+            let world = &mut worlds[world];
+            if world.naming.rule == NamingRule::Unnamed && world.sim.get_blob(key).is_some_and(|blob| blob.name.is_none()) {
+                let name = world.naming.fresh_name(&names, world.sim.rng());
+                world.sim.get_blob_mut(key).unwrap().name = Some(name);
+            }
+        }
+
+        //  time controls
+        if frame.is_key_pressed(KeyboardKey::KEY_P) {
+            paused = !paused;
+        }
+        if !paused {
+            pause_reason = None;
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_EQUAL) {
+            speed = f32::min(speed * 2., max_speed);
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_MINUS) {
+            speed = f32::max(speed / 2., 1. / max_speed);
+        }
+        //  both peers must keep the timing they started with
+        if let Some(timing) = adjust_timing(&frame, *worlds[0].sim.timing()).filter(|_| session.is_none()) {
+            for world in &mut worlds {
+                world.sim.set_timing(timing).unwrap();
+            }
+            by_hand = true;
+        }
+
+        //  panels and layers
+        layers.update(&frame);
+        if let Err(e) = panels.toggle(&frame) {
+            notice = Some((e, theme.error, frame_time));
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_M) && frame.is_shift_down() {
+            if session.is_some() || input_log.is_some() || playback.is_some() {
+                //  the peer and the replay start from the files alone
+                notice = Some(("Content cannot be chosen in a lockstep session or while input is recorded or replayed".to_string(), theme.warning, frame_time));
+            } else {
+                browser = match browser {
+                    Some(_) => None,
+                    None => match Catalog::discover() {
+                        Ok(catalog) => Some(Browser::new(catalog, content_settings(&options))),
+                        Err(e) => {
+                            notice = Some((format!("Failed to load the content: {}", e), theme.error, frame_time));
+                            None
+                        },
+                    },
+                };
+            }
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_T) && !frame.is_shift_down() {
+            theme = keyed(theme.next());
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_U) {
+            show_memory = !show_memory;
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_O) && !frame.is_shift_down() {
+            overlay = overlay.next();
+            notice = Some((format!("Overlay: {}", overlay.name()), theme.ok, frame_time));
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_K) && frame.is_shift_down() {
+            if session.is_some() {
+                notice = Some(("A scenario cannot be edited in a lockstep session".to_string(), theme.warning, frame_time));
+            } else {
+                scenario_editor = match scenario_editor {
+                    Some(_) => None,
+                    None => {
+                        let path = scenario_path.clone().unwrap_or_else(scenario::untitled);
+                        let edited = scenario.as_deref().cloned().unwrap_or_else(|| Scenario { name: "Untitled".to_string(), ..Scenario::default() });
+                        Some(ScenarioEditor::new(path, edited))
+                    },
+                };
+            }
+        } else if frame.is_key_pressed(KeyboardKey::KEY_K) {
+            if session.is_some() {
+                //  both peers must keep the parameters they started with
+                notice = Some(("Parameters cannot be tuned in a lockstep session".to_string(), theme.warning, frame_time));
+            } else {
+                tuning = match tuning {
+                    Some(_) => None,
+                    None => Some(TuningPanel::new()),
+                };
+            }
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_X) {
+            //  without shift the prediction is of the world as it is, and is hidden again the next time
+            let removed: Vec<_> = interaction.selected.iter().filter(|(world, _)| *world == 0).map(|&(_, key)| key).collect();
+            let intervention = if !frame.is_shift_down() {
+                Some("as it is".to_string()).filter(|_| prediction.is_none())
+            } else if removed.is_empty() {
+                notice = Some(("Select blobs to predict the world without them".to_string(), theme.warning, frame_time));
+                None
+            } else {
+                Some(format!("without the {} selected blobs", removed.len()))
+            };
+            let removed = if frame.is_shift_down() { &removed[..] } else { &[] };
+            prediction = intervention.map(|intervention| worlds[0].predict(intervention, removed, &names, &species));
+        }
+        if let Some(prediction) = &mut prediction {
+            prediction.poll();
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_A) {
+            ai_stride = ai_view::next(ai_stride);
+            let text = match ai_stride {
+                Some(1) => "AI view: every blob".to_string(),
+                Some(stride) => format!("AI view: every {}th blob", stride),
+                None => "AI view: off".to_string(),
+            };
+            notice = Some((text, theme.ok, frame_time));
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_D) && low_power.active() {
+            let what = if frame.is_shift_down() { "Speech bubbles" } else { "Energy numbers" };
+            notice = Some((format!("{} are off in low-power mode", what), theme.warning, frame_time));
+        } else if frame.is_key_pressed(KeyboardKey::KEY_D) {
+            notice = Some(noted(toggle_floating(&mut worlds, frame.is_shift_down()), &theme, frame_time));
+        }
+        for numbers in worlds.iter_mut().filter_map(|world| world.numbers.as_mut()) {
+            numbers.update(delta_time);
+        }
+        for bubbles in worlds.iter_mut().filter_map(|world| world.bubbles.as_mut()) {
+            bubbles.update(delta_time);
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_BACKSLASH) && frame.is_shift_down() {
+            crowding = !crowding;
+            notice = Some((format!("Crowding tint {}", if crowding { "on" } else { "off" }), theme.ok, frame_time));
+        } else if frame.is_key_pressed(KeyboardKey::KEY_BACKSLASH) {
+            blob_style = blob_style.next();
+            notice = Some((format!("Blob style: {}", blob_style.name()), theme.ok, frame_time));
+        }
+
+        //  export the data of the selected blobs as a table, or the blobs themselves to files
+        if frame.is_key_pressed(KeyboardKey::KEY_E) {
+            let (text, color) = export_selected(&worlds, &interaction.selected, frame.is_shift_down(), &theme);
+            notice = Some((text, color, frame_time));
+        }
+
+        //  trace a selected blob, or stop, or save its trace
+        if frame.is_key_pressed(KeyboardKey::KEY_L) && frame.is_shift_down() {
+            let (text, color) = save_trace(&worlds, &theme);
+            notice = Some((text, color, frame_time));
+        } else if frame.is_key_pressed(KeyboardKey::KEY_L) {
+            match toggle_trace(&mut worlds, &interaction.selected) {
+                Ok(true) => trace_view = trace::View::default(),
+                Ok(false) => {},
+                Err(e) => notice = Some((e, theme.warning, frame_time)),
+            }
+        }
+
+        //  select the blobs nearest the cursor of the species of a selected one
+        if frame.is_key_pressed(KeyboardKey::KEY_Y) {
+            if let Some(focus) = views.iter().position(|(rect, _)| rect.check_collision_point_rec(frame.mouse)) {
+                let sim = &worlds[focus].sim;
+                let first = interaction.selected.iter().filter(|(world, _)| *world == focus).map(|&(_, key)| key).min();
+                let species = first.and_then(|key| sim.get_blob(key)).and_then(|blob| blob.species.clone());
+                let pos = views[focus].1.screen_to_world(frame.mouse);
+                let nearest = sim.nearest_blobs(pos, Interaction::SIMILAR_COUNT, &BlobFilter::species(species.as_deref()));
+                let kind = species.map_or_else(|| "blobs".to_string(), |species| format!("{} blobs", species));
+                notice = Some((format!("Selected the {} {} nearest the cursor", nearest.len(), kind), theme.ok, frame_time));
+                interaction.selected = nearest.into_iter().map(|key| (focus, key)).collect();
+            }
+        }
+
+        //  import blobs picked from the menu or dropped on the window
+        if frame.is_key_pressed(KeyboardKey::KEY_I) && frame.is_shift_down() {
+            //  the blobs of a CSV file, see `roster`
+            let path = options.import_csv.as_deref().unwrap_or(roster::PATH);
+            notice = Some(if session.is_some() {
+                ("Blobs cannot be imported in a lockstep session".to_string(), theme.warning, frame_time)
+            } else {
+                let result = import_roster(&mut worlds, path);
+                by_hand |= result.is_ok();
+                noted(result, &theme, frame_time)
+            });
+        } else if frame.is_key_pressed(KeyboardKey::KEY_I) {
+            import_menu = match import_menu {
+                Some(_) => None,
+                None => match ImportMenu::open(exchange::DIR) {
+                    Ok(menu) => Some(menu),
+                    Err(e) => {
+                        notice = Some((format!("Failed to list {}/: {}", exchange::DIR, e), theme.error, frame_time));
+                        None
+                    },
+                },
+            };
+        }
+        let mut imports: Vec<(Exported, Vector2)> = vec![];
+        if let Some(menu) = &mut import_menu {
+            if frame.is_key_pressed(KeyboardKey::KEY_UP) {
+                menu.cursor = menu.cursor.saturating_sub(1);
+            }
+            if frame.is_key_pressed(KeyboardKey::KEY_DOWN) {
+                menu.cursor = (menu.cursor + 1).min(menu.entries.len().saturating_sub(1));
+            }
+            if frame.is_key_pressed(KeyboardKey::KEY_ENTER) {
+                if let Some((_, Ok(exported))) = menu.entries.get(menu.cursor) {
+                    //  menu imports land in the middle of the first view
+                    let (rect, view) = views[0];
+                    imports.push((exported.clone(), view.screen_to_world(Vector2::new(rect.x + rect.width / 2., rect.y + rect.height / 2.))));
+                    import_menu = None;
+                }
+            }
+        }
+        //  other dropped files load a world, check a fixture, start a scenario or map fertility
+        for path in &frame.dropped {
+            match Dropped::of(path) {
+                Ok(Dropped::Blob) => match Exported::load(path) {
+                    Ok(exported) => {
+                        let (_, view) = views.iter()
+                            .find(|(rect, _)| rect.check_collision_point_rec(frame.mouse))
+                            .unwrap_or(&views[0]);
+                        imports.push((exported, view.screen_to_world(frame.mouse)));
+                    },
+                    Err(e) => notice = Some((format!("Failed to import {}", e), theme.error, frame_time)),
+                },
+                Ok(Dropped::World) => load_from = Some(path.clone()),
+                Ok(Dropped::Fixture) => notice = Some(match Fixture::load(path).and_then(|fixture| fixture.check()) {
+                    Ok(()) => (format!("{} still comes to what it did", path), theme.ok, frame_time),
+                    Err(e) => (e, theme.warning, frame_time),
+                }),
+                Ok(dropped) if session.is_some() => {
+                    //  the peer started from the same setup and keeps it
+                    notice = Some((format!("A {} cannot be dropped in a lockstep session", dropped.name()), theme.warning, frame_time));
+                },
+                Ok(Dropped::Scenario) => notice = Some(match Scenario::load(path) {
+                    Ok(started) => {
+                        let name = started.name.clone();
+                        scenario = Some(Arc::new(started));
+                        scenario_path = Some(path::PathBuf::from(path));
+                        scenario_editor = None;
+                        worlds = create_worlds(&setup, &options, fertility.as_ref(), scenario.as_ref(), &naming, &names, &species);
+                        interaction = Interaction::new();
+                        pending_time = 0.;
+                        by_hand = true;
+                        (format!("Started the scenario {}", name), theme.ok, frame_time)
+                    },
+                    Err(e) => (format!("Failed to load the scenario: {}", e), theme.error, frame_time),
+                }),
+                Ok(Dropped::Chunk) => notice = Some(match Chunk::load(path) {
+                    Ok(chunk) => {
+                        //  its corner lands where it is dropped, in every world so compared worlds stay alike
+                        let (_, view) = views.iter()
+                            .find(|(rect, _)| rect.check_collision_point_rec(frame.mouse))
+                            .unwrap_or(&views[0]);
+                        let corner = view.screen_to_world(frame.mouse);
+                        let mut added = 0;
+                        for world in &mut worlds {
+                            added = world.sim.spawning_as(Source::User, |sim| chunk.paste(sim, corner));
+                        }
+                        by_hand = true;
+                        (format!("Stitched in {} things of {}", added, path), theme.ok, frame_time)
+                    },
+                    Err(e) => (format!("Failed to read the chunk: {}", e), theme.error, frame_time),
+                }),
+                Ok(Dropped::FertilityMap) => notice = Some(match FertilityMap::load(path) {
+                    Ok(map) => {
+                        fertility = Some(Arc::new(map));
+                        for world in &mut worlds {
+                            world.fertility = fertility.clone();
+                        }
+                        by_hand = true;
+                        (format!("Food grows by the fertility map {}", path), theme.ok, frame_time)
+                    },
+                    Err(e) => (format!("Failed to read the fertility map: {}", e), theme.error, frame_time),
+                }),
+                Err(e) => notice = Some((e, theme.warning, frame_time)),
+            }
+        }
+        if !imports.is_empty() && session.is_some() {
+            notice = Some(("Blobs cannot be imported in a lockstep session".to_string(), theme.warning, frame_time));
+        } else if !imports.is_empty() {
+            notice = Some((insert_imports(&mut worlds, &imports), theme.ok, frame_time));
+            by_hand = true;
+        }
+
+        //  capture the first world and what is done to it as a fixture, see `fixture`
+        if frame.is_key_pressed(KeyboardKey::KEY_F8) && frame.is_shift_down() {
+            notice = Some(if capture.take().is_some() {
+                ("Stopped capturing a fixture".to_string(), theme.warning, frame_time)
+            } else if session.is_some() {
+                ("A fixture cannot be captured in a lockstep session".to_string(), theme.warning, frame_time)
+            } else {
+                capture = Some(Capture::start(&worlds[0].sim, fixture::TICKS));
+                (format!("Capturing a fixture of the next {} ticks", fixture::TICKS), theme.ok, frame_time)
+            });
+        }
+        if capture.as_ref().is_some_and(|capture| capture.done(worlds[0].sim.clock().tick())) {
+            let note = format!("seed {} until tick {}, describe the bug here", setup.seed, worlds[0].sim.clock().tick());
+            let saved = capture.take().unwrap().finish(&note)
+                .and_then(|fixture| fixture.save(fixture::DIR).map_err(|e| e.to_string()));
+            notice = Some(match saved {
+                Ok(path) => (format!("Captured a fixture to {}", path.display()), theme.ok, frame_time),
+                Err(e) => (format!("Failed to capture a fixture: {}", e), theme.error, frame_time),
+            });
+        }
+        //  share the first world as a code, or grow the world of a shared one
+        if frame.is_key_pressed(KeyboardKey::KEY_F8) && !frame.is_shift_down() {
+            let code = WorldCode { setup: setup.clone(), tick: worlds[0].sim.clock().tick() }.encode();
+            window::set_clipboard_text(&code);
+            notice = Some(if by_hand {
+                (format!("Copied {}, which leaves out what was changed by hand", code), theme.warning, frame_time)
+            } else {
+                (format!("Copied {}", code), theme.ok, frame_time)
+            });
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_F9) {
+            let pasted = draw.get_clipboard_text()
+                .map_err(|e| e.to_string())
+                .and_then(|text| WorldCode::decode(&text));
+            notice = Some(match pasted {
+                //  the clipboard is not part of recorded input
+                _ if session.is_some() || input_log.is_some() || playback.is_some() => {
+                    ("A world code cannot be pasted in a lockstep session or while input is recorded or replayed".to_string(), theme.warning, frame_time)
+                },
+                Ok(code) => {
+                    setup = code.setup;
+                    worlds = create_worlds(&setup, &options, fertility.as_ref(), scenario.as_ref(), &naming, &names, &species);
+                    interaction = Interaction::new();
+                    pending_time = 0.;
+                    by_hand = fertility.is_some();
+                    fast_forward = Some(FastForward { done: 0, total: code.tick }).filter(|fast_forward| fast_forward.total > 0);
+                    (format!("Growing the shared world to tick {}", code.tick), theme.ok, frame_time)
+                },
+                Err(e) => (format!("Failed to paste: {}", e), theme.error, frame_time),
+            });
+        }
+
+        //  save the first world to come back to later, or load it back
+        if frame.is_key_pressed(KeyboardKey::KEY_F10) && !frame.is_shift_down() {
+            save_to = Some(recent.latest().unwrap_or(Simulation::SAVE_PATH).to_string());
+        }
+        if let Some(path) = save_to {
+            let saved = fs::File::create(&path).map(io::BufWriter::new)
+                .and_then(|mut file| worlds[0].sim.save(&mut file).and_then(|()| io::Write::flush(&mut file)));
+            notice = Some(match saved {
+                Ok(()) => {
+                    remember(&mut recent, &path);
+                    (format!("Saved the world to {}", path), theme.ok, frame_time)
+                },
+                Err(e) => (format!("Failed to save {}: {}", path, e), theme.error, frame_time),
+            });
+        }
+        //  a save loads on its own thread while the world it replaces carries on, until cancelled
+        let cancel_clicked = frame.button == input::Button::Pressed && Loading::cancel_button(screen_width, screen_height).check_collision_point_rec(frame.mouse);
+        if frame.is_key_pressed(KeyboardKey::KEY_F11) && !frame.is_shift_down() && loading.is_none() {
+            load_from = Some(recent.latest().unwrap_or(Simulation::SAVE_PATH).to_string());
+        }
+        if load_from.is_some() || loading.is_some() && (cancel_clicked || frame.is_key_pressed(KeyboardKey::KEY_F11)) {
+            notice = Some(match loading.take() {
+                Some(cancelled) => {
+                    cancelled.cancel();
+                    (format!("Stopped loading {}", cancelled.path), theme.warning, frame_time)
+                },
+                //  the file is not part of recorded input
+                None if session.is_some() || input_log.is_some() || playback.is_some() => {
+                    ("A world cannot be loaded in a lockstep session or while input is recorded or replayed".to_string(), theme.warning, frame_time)
+                },
+                None => {
+                    let path = load_from.take().unwrap();
+                    match Loading::start(&path) {
+                        Ok(started) => {
+                            loading = Some(started);
+                            remember(&mut recent, &path);
+                            (format!("Loading {}", path), theme.accent, frame_time)
+                        },
+                        Err(e) => (format!("Failed to load {}: {}", path, e), theme.error, frame_time),
+                    }
+                },
+            });
+        }
+        if let Some(loaded) = loading.as_ref().and_then(Loading::poll) {
+            let path = loading.take().unwrap().path;
+            notice = Some(match loaded {
+                Ok((sim, warnings)) => {
+                    worlds[0].replace(sim, &options);
+                    interaction = Interaction::new();
+                    pending_time = 0.;
+                    by_hand = true;
+                    for warning in &warnings {
+                        eprintln!("{}: {}", path, warning);
+                    }
+                    match warnings.len() {
+                        0 => (format!("Loaded the world saved at tick {}", worlds[0].sim.clock().tick()), theme.ok, frame_time),
+                        count => (format!("Loaded the world saved at tick {}, repairing {} problems listed in the console", worlds[0].sim.clock().tick(), count), theme.warning, frame_time),
+                    }
+                },
+                Err(e) => (format!("Failed to load {}: {}", path, e), theme.error, frame_time),
+            });
+        }
+
+        //  everything above works the same while paused, only the worlds stand still
+        let mut waiting = false;
+        //  worlds also stand still while the window is away, if they are to
+        if !paused && !standing {
+            //  after a stall, or while ticking cannot keep up, the worlds slow down instead of bursting ahead
+            let (owed, dropped) = worlds[0].sim.timing().owed(pending_time, delta_time, speed);
+            pending_time = owed;
+            if dropped {
+                notice = Some(("Ticking falls behind, the worlds slow down".to_string(), theme.muted_text, frame_time));
+            }
+            let first_tick = worlds[0].sim.clock().tick();
+            let tick_start = time::Instant::now();
+            let deadline = governor.as_ref().map(|governor| governor.deadline(tick_start));
+            let stepped = step_worlds(&mut worlds, session.as_mut(), &mut pending_time, deadline, &names, &species);
+            waiting = stepped == Stepped::Waiting;
+            //  the latest alert of a watched region is shown until another action, nearest the camera first
+            let seconds = frame_time.duration_since(start_time).as_secs_f64();
+            if let Some((text, color)) = toast(&mut worlds, &views, first_tick, seconds, &theme) {
+                notice = Some((text, color, frame_time));
+            }
+            //  the dead leave the selection
+            interaction.selected.retain(|&(world, key)| worlds[world].sim.contains_blob(key));
+            //  stop at the moments the triggers wait for, bookmarking them
+            if let Some(reason) = check_autopause(&mut worlds, &interaction.selected, first_tick) {
+                pause_reason = Some(reason);
+                paused = true;
+            }
+            //  do less work while ticking takes too long, and more again once it does not
+            let ticking = tick_start.elapsed().as_secs_f32();
+            if let Some(level) = governor.as_mut().and_then(|governor| governor.update(ticking, stepped == Stepped::OutOfTime)) {
+                apply_level(&mut worlds, level, &mut slowed_brains);
+                notice = Some((format!("Simulation: {}, to keep the window responsive", level.name()), theme.muted_text, frame_time));
+            }
+        }
+
+        //  the screensaver never runs out of blobs to watch
+        if screensaver.is_some() && worlds.iter().any(|world| world.sim.blobs().next().is_none()) {
+            setup.seed = random();
+            worlds = create_worlds(&setup, &options, fertility.as_ref(), scenario.as_ref(), &naming, &names, &species);
+            interaction = Interaction::new();
+            pending_time = 0.;
+        }
+
+        //  flag unusual moments, bookmarking them to find them again
+        let several = worlds.len() > 1;
+        for world in &mut worlds {
+            for anomaly in world.anomalies.update(world.sim.stats()) {
+                world.sim.bookmark(anomaly.tick, &anomaly.description);
+                let text = if several { format!("{}: {}", &world.label[..1], anomaly.description) } else { anomaly.description };
+                println!("tick {}: {}", anomaly.tick, text);
+                notice = Some((text, theme.warning, frame_time));
+            }
+            //  and tell what the scenario's scripts say
+            if let Some(message) = world.message.take() {
+                let text = if several { format!("{}: {}", &world.label[..1], message) } else { message };
+                notice = Some((text, theme.accent, frame_time));
+            }
+        }
+
+        //  narrate the first world, along with its selected blobs
+        let mut keys: Vec<_> = interaction.selected.iter().filter(|(world, _)| *world == 0).map(|&(_, key)| key).collect();
+        keys.sort();
+        let blobs: Vec<_> = keys.into_iter().filter_map(|key| worlds[0].sim.get_blob(key)).collect();
+        if let Some(Err(e)) = narrator.as_mut().map(|narrator| narrator.update(&worlds[0].sim, &blobs)) {
+            eprintln!("failed to narrate, stopping: {}", e);
+            narrator = None;
+        }
+        if let Some(Err(e)) = stream_stats.as_mut().map(|file| file.update(&worlds[0].sim, frame_time)) {
+            eprintln!("failed to write the statistics for streaming, stopping: {}", e);
+            stream_stats = None;
+        }
+        if let Some(Err(e)) = observed.as_mut().map(|out| worlds[0].write_changes(out)) {
+            eprintln!("failed to write what changed, stopping: {}", e);
+            observed = None;
+            worlds[0].stop_observing();
+        }
+
+        //  sound of the first world
+        if frame.is_key_pressed(KeyboardKey::KEY_S) && !frame.is_shift_down() {
+            sonify = !sonify;
+        }
+        if sonify && sonification.is_none() {
+            match Sonification::new(thread) {
+                Ok(opened) => sonification = Some(opened),
+                Err(e) => {
+                    eprintln!("failed to open the audio device: {}", e);
+                    sonify = false;
+                },
+            }
+        } else if !sonify {
+            sonification = None;
+        }
+        if let Some(sonification) = &mut sonification {
+            sonification.update(&worlds[0].sim);
+        }
+
+        //  draw worlds, through the post-processing pass if there is one and the quality allows it
+        let tier = quality.as_ref().map_or(Tier::Full, AutoQuality::tier);
+        //  the zones of the scenario are shown while it is edited
+        let zones = scenario_editor.as_ref().map(|editor| editor.zones(mouse_world));
+        if let Some(post_processing) = &mut post_processing {
+            post_processing.update(&frame);
+        }
+        let state = ViewState { selected: &interaction.selected, layers, overlay, ai_stride, tier, style: blob_style, crowding, zones: zones.as_deref() };
+        match post_processing.as_mut().filter(|_| tier.post_processing() && !low_power.active()) {
+            //  nothing of the worlds is drawn while they run in the background
+            _ if hidden => {
+                draw.clear_background(theme.background);
+                fonts.draw_text(&mut draw, "Running in the background", 10, 10, 20, theme.muted_text);
+            },
+            Some(post_processing) => {
+                {
+                    let mut target = post_processing.begin(&mut draw, thread);
+                    draw_worlds(&mut target, &worlds, &views, &state, &theme, &fonts);
+                }
+                post_processing.present(&mut draw);
+            },
+            None => draw_worlds(&mut draw, &worlds, &views, &state, &theme, &fonts),
+        }
+        if let (Some(chat), false) = (&chat, hidden) {
+            let (rect, view) = views[0];
+            chat.draw(&mut draw, &worlds[0].sim, view, rect, &theme, &fonts);
+        }
+
+        //  keep the worlds, without the interface, for saving as a GIF
+        if !hidden {
+            recorder.capture(&draw);
+        }
+        if frame.is_key_pressed(KeyboardKey::KEY_C) {
+            let (text, color) = save_capture(&worlds[0].sim, &recorder, frame.is_shift_down(), &theme);
+            notice = Some((text, color, frame_time));
+        }
+
+        //  account for memory, trimming the statistics before the GIF history when over the budget
+        let usage = account_memory(&mut worlds, &mut recorder, &budget);
+        let entities = worlds.iter().map(|world| world.sim.entity_count()).sum();
+        let warnings = usage.warnings(&budget, entities);
+        if !warnings.is_empty() && !over_budget {
+            for warning in &warnings {
+                eprintln!("{}", warning);
+            }
+            notice = Some((format!("{} (U for details)", warnings[0]), theme.warning, frame_time));
+        }
+        over_budget = !warnings.is_empty();
+
+        panels.update(&worlds[0].sim);
+
+        //  draw interface
+        if !layers.is_visible(RenderLayer::Ui) || hidden { return; }
+        if let Some((mode, start)) = input.active_gesture() {
+            if mode == InteractionMode::Select || mode == InteractionMode::Watch {
+                let rect = rect_from_corners(start, frame.mouse);
+                draw.draw_rectangle_rec(rect, theme.accent.fade(0.3));
+                draw.draw_rectangle_lines_ex(rect, 1, theme.accent);
+            }
+        }
+        draw_selection(&mut draw, &theme, &fonts, &worlds, &interaction.selected);
+        input.draw_toolbar(&mut draw, &theme, &fonts);
+        if let Some(menu) = &import_menu {
+            draw_import_menu(&mut draw, &theme, &fonts, menu);
+        }
+        panels.draw(&mut draw, &theme, &fonts, &worlds, &loaded_mods, options.audit_energy);
+        if editing_notes {
+            draw_text_box(&mut draw, &theme, &fonts, "Notes", &notes);
+        }
+        if let Some((kind, typed)) = &prompt {
+            let title = kind.title(&worlds[0].sim, interaction.selected.len(), &recent);
+            draw_text_box(&mut draw, &theme, &fonts, &title, typed);
+        }
+        if let Some((_, _, typed)) = &interaction.watching {
+            draw_text_box(&mut draw, &theme, &fonts, "Watch the region as name, then alerts like predators>3 or food<10, -name to stop", typed);
+        }
+        if let Some(open) = &palette {
+            draw_palette(&mut draw, &theme, &fonts, open, &actions);
+        }
+        if let Some(cursor) = &keyboard_cursor {
+            draw_cursor(&mut draw, &theme, cursor.pos);
+        }
+        if let Some(trace) = worlds.iter().find_map(|world| world.sim.trace()) {
+            draw_trace(&mut draw, &theme, &fonts, trace, &trace_view);
+        }
+        if let Some(prediction) = &prediction {
+            draw_prediction(&mut draw, &theme, &fonts, prediction);
+        }
+        if let Some(panel) = &tuning {
+            panel.draw(&mut draw, &theme, &fonts, worlds[0].sim.parameters());
+        }
+        if let Some(editor) = &scenario_editor {
+            editor.draw(&mut draw, &theme, &fonts, worlds[0].sim.clock().tick());
+        }
+        if let Some(browser) = &browser {
+            browser.draw(&mut draw, &theme, &fonts);
+        }
+        if show_memory {
+            draw_memory(&mut draw, &theme, &fonts, &usage, &budget, entities, &warnings);
+        }
+        draw_clock(&mut draw, &theme, &fonts, &worlds[0].sim, speed, paused, tier);
+        draw_mutators(&mut draw, &theme, &fonts, worlds[0].sim.mutators());
+        if let Some((text, color)) = network_status(session.as_ref(), network_error.as_deref(), waiting, governor.as_ref(), &theme) {
+            draw_network(&mut draw, &fonts, &text, color);
+        }
+        draw_hidden_layers(&mut draw, &theme, &fonts, layers);
+        if let Some(loading) = &loading {
+            loading.draw(&mut draw, &theme, &fonts, screen_width, screen_height);
+        }
+        doctor::draw(&mut draw, &theme, &fonts, &diagnosis, frame.mouse);
+        if let Some((text, color, _)) = notice.as_ref().filter(|(_, _, time)| time.elapsed() < notice_duration) {
+            draw_notice(&mut draw, &fonts, text, *color);
+        }
+        if let Some(reason) = &pause_reason {
+            draw_banner(&mut draw, &theme, &fonts, reason);
+        }
+
+        //  what the frame took besides waiting for the display decides the next frame's quality
+        if let Some(tier) = quality.as_mut().and_then(|quality| quality.update(frame_time.elapsed().as_secs_f32())) {
+            notice = Some((format!("Quality: {}, to hold {} frames a second", tier.name(), options.target_fps), theme.muted_text, frame_time));
+        }
+    })));
+    if let Err(payload) = outcome {
+        let labeled: Vec<_> = worlds.iter().map(|world| (&world.label[..1], &world.sim)).collect();
+        match crash::dump(crash::DIR, &labeled) {
+            Ok(path) => eprintln!("wrote a crash dump to {}", path.display()),
+            Err(e) => eprintln!("failed to write a crash dump: {}", e),
+        }
+        panic::resume_unwind(payload);
+    }
+
+    //  finish what is in progress before the resources are dropped
+    let since_epoch = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default();
+    let notes = runs::clean_notes(&notes);
+    let summaries: Vec<_> = worlds.iter()
+        .map(|world| {
+            let mut summary = Summary::of(&world.sim, since_epoch.as_secs());
+            summary.metadata.notes = notes.clone();
+            summary
+        })
+        .collect();
+    let mut hooks = shutdown::Hooks::new();
+    if let Some(narrator) = &mut narrator {
+        let sim = &worlds[0].sim;
+        hooks.register("narrator", move || narrator.report(sim, &[]).map_err(|e| e.to_string()));
+    }
+    if let Some(log) = &mut input_log {
+        let metadata = &summaries[0].metadata;
+        let world = WorldCode { setup: setup.clone(), tick: code_ticks };
+        let mods = replay::fingerprints(&loaded_mods);
+        let compare = options.compare.clone();
+        hooks.register("input recording", move || log.finish(metadata, world, compare, mods).map_err(|e| e.to_string()));
+    }
+    //  the window opens where it was left next time
+    if let Some(monitor) = window.monitor() {
+        hooks.register("window placement", move || placement::save(placement::PATH, monitor).map_err(|e| e.to_string()));
+    }
+    hooks.register("hall of fame", || {
+        summaries.iter().try_for_each(|summary| leaderboard::append(leaderboard::PATH, summary)).map_err(|e| e.to_string())
+    });
+    if let Some(endpoint) = &options.post_summary {
+        let summaries = &summaries;
+        hooks.register("leaderboard", move || summaries.iter().try_for_each(|summary| leaderboard::post(endpoint, summary)));
+    }
+    hooks.register("family tree", || write_genealogies(&worlds, &options));
+    hooks.register("report", || write_report(&worlds, &options, start_time.elapsed()));
+    hooks.register("summary", || {
+        print_summary(&worlds, start_time.elapsed());
+        Ok(())
+    });
+    if !hooks.run() {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::Button;
+
+    /// Replay frames through the input handling of the main loop, applying what they do.
+    fn replay(world: &mut World, frames: &[Frame], paused: bool) -> Interaction {
+        let size = world.sim.size();
+        let mut input = InputController::new(InteractionMode::Drag);
+        let mut interaction = Interaction::new();
+        let mut camera = Camera::new();
+        let views = [(Rectangle::new(0., 0., size.x, size.y), camera)];
+        for frame in frames {
+            if let Some(gesture) = input.update(frame, size.x as i32) {
+                for (_, command) in interaction.handle(gesture, std::slice::from_mut(world), &views, &mut camera, paused) {
+                    world.sim.spawning_as(Source::User, |sim| apply_command(sim, command, &[], &[]));
+                }
+            }
+        }
+        interaction
+    }
+
+    #[test]
+    fn test_replay() {
+        let mut world = World::new(SimulationConfig { seed: 7, ..SimulationConfig::new(Vector2::new(400., 300.)) }, "A".to_string());
+        let blob = world.sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let mouse = |time: f64, x: f32, y: f32, button: Button| {
+            let mut frame = Frame::idle(time, Vector2::new(x, y));
+            frame.button = button;
+            frame
+        };
+
+        //  drag the blob, then select it with a rectangle, going through a recording
+        let mut select = Frame::idle(0.5, Vector2::new(150., 120.));
+        select.set_key(KeyboardKey::KEY_ONE, true, true);
+        let frames = [
+            mouse(0., 100., 100., Button::Pressed),
+            mouse(0.1, 120., 110., Button::Down),
+            mouse(0.2, 150., 120., Button::Down),
+            mouse(0.3, 150., 120., Button::Up),
+            select,
+            mouse(0.6, 120., 80., Button::Pressed),
+            mouse(0.7, 180., 160., Button::Up),
+        ];
+        let mut out = vec![];
+        input::write_header(&mut out, world.sim.seed()).unwrap();
+        for frame in &frames {
+            frame.write(&mut out).unwrap();
+        }
+        let recording = input::read(&out[..]).unwrap();
+        assert_eq!(recording.seed, 7);
+        let frames = recording.frames;
+
+        let interaction = replay(&mut world, &frames, false);
+        let pos = world.sim.get_blob(blob).unwrap().pos();
+        assert!((pos - Vector2::new(150., 120.)).length() < 0.01, "{:?}", pos);
+        assert!(interaction.drag.is_none());
+        assert_eq!(interaction.selected, vec![(0, blob)].into_iter().collect());
+
+        //  thrown blobs fly off, but only if the world runs
+        let throw = [mouse(1., 150., 120., Button::Pressed), mouse(1.1, 200., 120., Button::Down), mouse(1.15, 250., 120., Button::Up)];
+        replay(&mut world, &throw, true);
+        assert_eq!(world.sim.get_blob(blob).unwrap().velocity(), Vector2::zero());
+        let pos = world.sim.get_blob(blob).unwrap().pos();
+        replay(&mut world, &[mouse(2., pos.x, pos.y, Button::Pressed), mouse(2.1, pos.x + 50., pos.y, Button::Down), mouse(2.15, pos.x + 100., pos.y, Button::Up)], false);
+        assert!(world.sim.get_blob(blob).unwrap().velocity().x > 0.);
+    }
+}
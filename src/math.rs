@@ -1,14 +1,107 @@
+//! Vector math for steering, along with the floating point functions
+//! the simulation depends on.
+//!
+//! Addition, multiplication, division and square roots are rounded
+//! the same everywhere, but functions such as `sin` and `exp` come
+//! from the platform and may differ in their last bits between Linux,
+//! Windows and macOS, which is enough for two worlds in lockstep to
+//! drift apart. In `FloatMode::Deterministic` they are computed here
+//! from those basic operations alone, in double precision so that
+//! rounding to single precision gives the same result everywhere.
+//!
+//! Random numbers drawn in turn from one generator depend on the
+//! order they are drawn in, which threads do not keep. A `random::Stream`
+//! is instead derived from the seed, a system, an entity and a tick,
+//! so each entity draws the same numbers however the work is split.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::math::{self, random, FloatMode};
+//! # use rand::Rng;
+//! # use raylib::prelude::Vector2;
+//! # let (direction, target, seed, key, tick) = (Vector2::new(1., 0.), Vector2::new(0., 1.), 42, 7, 100);
+//! # const STEERING: u64 = 1;
+//! let mode = FloatMode::Deterministic;
+//! let direction = math::slerp(direction, target, 0.5, mode);
+//! let wander: f32 = random::Stream::new(seed, STEERING, key, tick).gen();
+//! ```
+
+use std::f64::consts::{FRAC_PI_2, LN_2, PI};
+
 use raylib::prelude::*;
 
 pub use raylib::prelude::Vector3;
 
-pub fn unsigned_angle_vector2(a: Vector2, b: Vector2) -> f32 {
-    let mut angle = a.angle_to(b).to_degrees().abs();
+/// How the simulation computes the functions the platform provides.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatMode {
+    /// The platform's functions, which are faster.
+    #[default]
+    Native,
+    /// The same results on every platform, for lockstep sessions.
+    Deterministic,
+}
+
+impl FloatMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Native => "native",
+            Self::Deterministic => "deterministic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Native, Self::Deterministic].iter().cloned().find(|mode| mode.name() == name)
+    }
+
+    pub fn sin(self, x: f32) -> f32 {
+        match self {
+            Self::Native => x.sin(),
+            Self::Deterministic => sin(x as f64) as f32,
+        }
+    }
+
+    pub fn cos(self, x: f32) -> f32 {
+        match self {
+            Self::Native => x.cos(),
+            Self::Deterministic => cos(x as f64) as f32,
+        }
+    }
+
+    pub fn exp(self, x: f32) -> f32 {
+        match self {
+            Self::Native => x.exp(),
+            Self::Deterministic => exp(x as f64) as f32,
+        }
+    }
+
+    pub fn atan2(self, y: f32, x: f32) -> f32 {
+        match self {
+            Self::Native => y.atan2(x),
+            Self::Deterministic => atan2(y as f64, x as f64) as f32,
+        }
+    }
+
+    /// Hue in degrees, saturation and value of a color.
+    pub fn color_to_hsv(self, color: Color) -> Vector3 {
+        match self {
+            Self::Native => color.color_to_hsv(),
+            Self::Deterministic => color_to_hsv(color),
+        }
+    }
+}
+
+pub fn unsigned_angle_vector2(a: Vector2, b: Vector2, mode: FloatMode) -> f32 {
+    //  as Vector2::angle_to, from 0 to 2π
+    let mut angle = mode.atan2(b.y - a.y, b.x - a.x);
+    if angle < 0. { angle += 2. * std::f32::consts::PI }
+    let mut angle = angle.to_degrees().abs();
     if angle > 180. { angle -= 180. }
     angle
 }
 
-pub fn slerp(start: Vector2, end: Vector2, time: f32) -> Vector2 {
+pub fn slerp(start: Vector2, end: Vector2, time: f32, mode: FloatMode) -> Vector2 {
     //  https://en.wikipedia.org/wiki/Slerp
     //  slerp(p0, p1, t) = sin((1-t)a) / sin a * p0 + sin ta / sin a * p1
 
@@ -17,8 +110,225 @@ pub fn slerp(start: Vector2, end: Vector2, time: f32) -> Vector2 {
     let p0 = start;
     let p1 = end;
     let t = time;
-    let a = unsigned_angle_vector2(start, end).to_radians();
-    let sa = a.sin();
+    let a = unsigned_angle_vector2(start, end, mode).to_radians();
+    let sa = mode.sin(a);
 
-    (p0 * (((1. - t) * a).sin() / sa) + p1 * ((t * a).sin() / sa)).normalized()
-}
\ No newline at end of file
+    (p0 * (mode.sin((1. - t) * a) / sa) + p1 * (mode.sin(t * a) / sa)).normalized()
+}
+
+fn sin(x: f64) -> f64 {
+    if !x.is_finite() { return f64::NAN; }
+    //  bring x to [-π, π], then to [-π/2, π/2] where the series converges quickly
+    let x = x - (x / (2. * PI)).round() * 2. * PI;
+    let x = if x > FRAC_PI_2 { PI - x } else if x < -FRAC_PI_2 { -PI - x } else { x };
+    let x2 = x * x;
+    let mut term = x;
+    let mut sum = x;
+    for n in 1..12 {
+        term *= -x2 / ((2 * n) * (2 * n + 1)) as f64;
+        sum += term;
+    }
+    sum
+}
+
+fn cos(x: f64) -> f64 {
+    sin(x + FRAC_PI_2)
+}
+
+fn exp(x: f64) -> f64 {
+    if x.is_nan() { return x; }
+    if x > 709. { return f64::INFINITY; }
+    if x < -745. { return 0.; }
+    //  e^x = 2^k e^r with |r| <= ln 2 / 2
+    let k = (x / LN_2).round();
+    let r = x - k * LN_2;
+    let mut term = 1.;
+    let mut sum = 1.;
+    for n in 1..16 {
+        term *= r / n as f64;
+        sum += term;
+    }
+    //  2^k in two halves, as either alone may not be a normal number
+    let half = |k: i64| f64::from_bits(((k + 1023) as u64) << 52);
+    let k = k as i64;
+    sum * half(k / 2) * half(k - k / 2)
+}
+
+/// The arctangent of a number from -1 to 1.
+fn atan(z: f64) -> f64 {
+    //  halve the angle twice, atan z = 2 atan(z / (1 + √(1 + z²))), leaving |z| < 0.2
+    let halve = |z: f64| z / (1. + (1. + z * z).sqrt());
+    let z = halve(halve(z));
+    let z2 = z * z;
+    let mut power = z;
+    let mut sum = z;
+    for n in 1..14 {
+        power *= -z2;
+        sum += power / (2 * n + 1) as f64;
+    }
+    4. * sum
+}
+
+fn atan2(y: f64, x: f64) -> f64 {
+    if x.is_nan() || y.is_nan() { return f64::NAN; }
+    if x == 0. && y == 0. { return 0.; }
+    if y.abs() > x.abs() {
+        return FRAC_PI_2.copysign(y) - atan(x / y);
+    }
+    let angle = atan(y / x);
+    match (x < 0., y < 0.) {
+        (false, _) => angle,
+        (true, false) => angle + PI,
+        (true, true) => angle - PI,
+    }
+}
+
+/// Raylib's conversion, written out so no compiler fuses its operations.
+fn color_to_hsv(color: Color) -> Vector3 {
+    let (r, g, b) = (color.r as f32 / 255., color.g as f32 / 255., color.b as f32 / 255.);
+    let min = r.min(g).min(b);
+    let max = r.max(g).max(b);
+    let delta = max - min;
+    if delta < 0.00001 || max <= 0. {
+        return Vector3::new(0., 0., max);
+    }
+    let mut hue = if r >= max {
+        (g - b) / delta
+    } else if g >= max {
+        2. + (b - r) / delta
+    } else {
+        4. + (r - g) / delta
+    };
+    hue *= 60.;
+    if hue < 0. { hue += 360. }
+    Vector3::new(hue, delta / max, max)
+}
+
+/// An FNV-1a hash, which unlike the standard library's hasher comes
+/// out the same in every version, for hashes peers compare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self { Self(0xcbf29ce484222325) }
+}
+
+impl Fnv {
+    pub fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+
+    /// Write a number as 64 little-endian bits, the same on every target.
+    pub fn write_u64(&mut self, value: u64) { self.write(&value.to_le_bytes()) }
+
+    pub fn finish(self) -> u64 { self.0 }
+}
+
+/// For maps keyed by small numbers, which it hashes faster than the
+/// standard library's hasher, with no defense against keys picked to
+/// collide.
+impl std::hash::Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) { Fnv::write(self, bytes) }
+
+    fn finish(&self) -> u64 { self.0 }
+}
+
+/// Counter-based random numbers, see the module.
+pub mod random {
+    use rand::{RngCore, Error};
+
+    /// SplitMix64's finalizer, spreading every bit of a number over all of them.
+    fn mix(mut z: u64) -> u64 {
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// The numbers of one system for one entity in one tick, the nth
+    /// of which is a hash of where it is rather than of those before it.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Stream {
+        key: u64,
+        counter: u64,
+    }
+
+    impl Stream {
+        /// 2⁶⁴ over the golden ratio, which SplitMix64 steps by.
+        const GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+        pub fn new(seed: u64, system: u64, entity: u64, tick: u64) -> Self {
+            let key = [system, entity, tick].iter().fold(mix(seed), |key, &part| mix(key.wrapping_add(Self::GAMMA) ^ part));
+            Self { key, counter: 0 }
+        }
+    }
+
+    impl RngCore for Stream {
+        fn next_u32(&mut self) -> u32 {
+            (self.next_u64() >> 32) as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.counter += 1;
+            mix(self.key ^ self.counter.wrapping_mul(Self::GAMMA))
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            for chunk in dest.chunks_mut(8) {
+                let bytes = self.next_u64().to_le_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_deterministic_functions() {
+        let close = |a: f64, b: f64| (a - b).abs() <= 1e-12 * b.abs().max(1.);
+        for i in -200..=200 {
+            let x = i as f64 * 0.137;
+            assert!(close(sin(x), x.sin()), "sin {}", x);
+            assert!(close(cos(x), x.cos()), "cos {}", x);
+            assert!(close(exp(x), x.exp()), "exp {}", x);
+            assert!(close(atan2(x, 1.7), x.atan2(1.7)), "atan2 {}", x);
+            assert!(close(atan2(-1.3, x), (-1.3f64).atan2(x)), "atan2 {}", x);
+        }
+        assert_eq!(exp(-1000.), 0.);
+        assert!(sin(f64::INFINITY).is_nan());
+        for &color in &[Color::RED, Color::GOLD, Color::SKYBLUE, Color::GRAY, Color::BLACK] {
+            assert_eq!(color_to_hsv(color), color.color_to_hsv(), "{:?}", color);
+        }
+    }
+
+    #[test]
+    fn test_random() {
+        use random::Stream;
+        let draw = |entity: u64| Stream::new(42, 1, entity, 7).gen::<u64>();
+        //  entities drawn on threads in any order draw what they do in turn
+        let serial: Vec<_> = (0..64).map(draw).collect();
+        let threads: Vec<_> = (0..64).rev().map(|entity| std::thread::spawn(move || draw(entity))).collect();
+        let parallel: Vec<_> = threads.into_iter().rev().map(|thread| thread.join().unwrap()).collect();
+        assert_eq!(serial, parallel);
+        let mut unique = serial.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), serial.len());
+        //  each part of the derivation changes the stream
+        let parts = [(42, 1, 3, 7), (43, 1, 3, 7), (42, 2, 3, 7), (42, 1, 3, 8)];
+        let firsts: Vec<u64> = parts.iter().map(|&(seed, system, entity, tick)| Stream::new(seed, system, entity, tick).gen()).collect();
+        assert!(firsts.iter().enumerate().all(|(i, a)| firsts[i + 1..].iter().all(|b| a != b)));
+        let mut stream = Stream::new(42, 1, 3, 7);
+        assert!((0..100).map(|_| stream.gen_range(-1. ..1.)).all(|x: f32| (-1. ..1.).contains(&x)));
+    }
+}
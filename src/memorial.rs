@@ -0,0 +1,114 @@
+//! Where blobs died lately, drawn as faint marks in the memorial
+//! overlay so that hunting grounds and deadly places stand out.
+//!
+//! Every death in a tick leaves a grave at the place of the blob,
+//! with the tick and the cause, see `Cause`. A world keeps its graves
+//! for `LIFETIME` ticks, and no more than `MAX_GRAVES` of them, see
+//! `Simulation::graves`. Cycling the overlays with `O` to memorial
+//! draws each as a small cross in the color of its cause, fading as
+//! it ages:
+//!
+//! * killed by a hunter, in the error color of the theme
+//! * starved, in the warning color
+//! * drowned, starved in water, in the accent color
+//!
+//! Blobs removed by hand, by a scenario or by a peer did not die and
+//! leave no grave.
+//!
+//! # Example
+//!
+//! ```ignore
+//! sim.step();
+//! let killed = sim.graves().filter(|grave| grave.cause == Cause::Killed).count();
+//! memorial::draw(&mut draw, &sim, &theme, zoom);
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{simulation::Simulation, theme::Theme};
+
+/// How many ticks a grave is kept and drawn.
+pub const LIFETIME: u64 = 1800;
+/// The most graves a world keeps, the oldest going first.
+pub const MAX_GRAVES: usize = 4096;
+/// The half width of a grave as drawn, in pixels.
+const SIZE: f32 = 4.;
+/// How opaque a fresh grave is drawn.
+const OPACITY: f32 = 0.5;
+
+/// What a blob died of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    Killed,
+    Starved,
+    /// Starved while in water.
+    Drowned,
+}
+
+impl Cause {
+    fn color(&self, theme: &Theme) -> Color {
+        match self {
+            Self::Killed => theme.error,
+            Self::Starved => theme.warning,
+            Self::Drowned => theme.accent,
+        }
+    }
+}
+
+/// Where and when a blob died, and of what.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grave {
+    pub pos: Vector2,
+    pub tick: u64,
+    pub cause: Cause,
+}
+
+impl Grave {
+    /// How much of its lifetime a grave has left at a tick, from 1 when
+    /// it is dug to 0 when it is gone.
+    pub fn freshness(&self, tick: u64) -> f32 {
+        1. - tick.saturating_sub(self.tick).min(LIFETIME) as f32 / LIFETIME as f32
+    }
+}
+
+/// Draw the graves of a world in world coordinates, see the module.
+pub fn draw<D: RaylibDraw>(draw: &mut D, sim: &Simulation, theme: &Theme, zoom: f32) {
+    let tick = sim.clock().tick();
+    let size = SIZE / zoom;
+    for grave in sim.graves() {
+        let color = grave.cause.color(theme).fade(OPACITY * grave.freshness(tick));
+        let Vector2 { x, y } = grave.pos;
+        draw.draw_line_ex(Vector2::new(x - size, y - size), Vector2::new(x + size, y + size), 1.5 / zoom, color);
+        draw.draw_line_ex(Vector2::new(x - size, y + size), Vector2::new(x + size, y - size), 1.5 / zoom, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_memorial() {
+        let grave = Grave { pos: Vector2::zero(), tick: 100, cause: Cause::Killed };
+        assert_eq!(grave.freshness(100), 1.);
+        assert_eq!(grave.freshness(100 + LIFETIME / 2), 0.5);
+        assert_eq!(grave.freshness(100 + 2 * LIFETIME), 0.);
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let starving = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let removed = sim.insert_blob(BlobConfig::new(Vector2::new(200., 200.)));
+        let blob = sim.get_blob_mut(starving).unwrap();
+        blob.hunger = blob.max_hunger * 2.;
+        sim.remove_blob(removed);
+        sim.step();
+        let graves: Vec<_> = sim.graves().collect();
+        assert_eq!(graves.len(), 1);
+        assert_eq!((graves[0].cause, graves[0].tick), (Cause::Starved, 0));
+        assert!(graves[0].pos.distance_to(Vector2::new(100., 100.)) < 1.);
+        for _ in 0..LIFETIME {
+            sim.step();
+        }
+        assert_eq!(sim.graves().count(), 0);
+    }
+}
@@ -0,0 +1,106 @@
+//! Accounting of the memory held by the larger stores.
+//!
+//! Each store estimates the bytes it holds from the sizes of its
+//! elements and the capacity of its buffers, without asking the
+//! allocator, which is close enough to see what grows. A budget
+//! limits the total and the number of entities, and the stores
+//! that only keep history are trimmed when the total goes over it.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::{memory::{Budget, Usage}, recorder::Recorder};
+//! # let recorder = Recorder::new(Recorder::DEFAULT_SECONDS);
+//! let mut usage = Usage::new();
+//! sim.account(&mut usage, "");
+//! usage.add("recorder".to_string(), recorder.bytes());
+//! for warning in usage.warnings(&Budget::default(), sim.entity_count()) {
+//!     eprintln!("{}", warning);
+//! }
+//! ```
+
+/// Limits past which memory use and entities are warned about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Budget {
+    pub bytes: usize,
+    /// Blobs and foods in all worlds together.
+    pub entities: usize,
+}
+
+impl Budget {
+    pub const DEFAULT_MEBIBYTES: usize = 64;
+    pub const DEFAULT_ENTITIES: usize = 5000;
+}
+
+impl Default for Budget {
+    fn default() -> Self {
+        Self { bytes: Self::DEFAULT_MEBIBYTES << 20, entities: Self::DEFAULT_ENTITIES }
+    }
+}
+
+/// The estimated bytes held by each store.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Usage {
+    stores: Vec<(String, usize)>,
+}
+
+impl Usage {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn add(&mut self, name: String, bytes: usize) {
+        self.stores.push((name, bytes));
+    }
+
+    pub fn stores(&self) -> &[(String, usize)] { &self.stores }
+
+    pub fn total(&self) -> usize {
+        self.stores.iter().map(|(_, bytes)| bytes).sum()
+    }
+
+    /// What is over the budget, as sentences.
+    pub fn warnings(&self, budget: &Budget, entities: usize) -> Vec<String> {
+        let mut ret = vec![];
+        if self.total() > budget.bytes {
+            ret.push(format!("Using {} of memory, over the budget of {}", format_bytes(self.total()), format_bytes(budget.bytes)));
+        }
+        if entities > budget.entities {
+            ret.push(format!("{} entities, over the budget of {}", entities, budget.entities));
+        }
+        ret
+    }
+}
+
+/// A number of bytes in the largest unit that keeps it above one.
+pub fn format_bytes(bytes: usize) -> String {
+    let units = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024. && unit + 1 < units.len() {
+        value /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} B", bytes) } else { format!("{:.1} {}", value, units[unit]) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings() {
+        assert_eq!(format_bytes(12), "12 B");
+        assert_eq!(format_bytes(3 << 19), "1.5 MiB");
+
+        let mut usage = Usage::new();
+        usage.add("blobs".to_string(), 600);
+        usage.add("stats".to_string(), 500);
+        let budget = Budget { bytes: 1000, entities: 10 };
+        assert_eq!(usage.total(), 1100);
+        assert_eq!(usage.warnings(&budget, 10), vec!["Using 1.1 KiB of memory, over the budget of 1000 B".to_string()]);
+        assert_eq!(usage.warnings(&Budget { bytes: 2000, ..budget }, 11).len(), 1);
+        assert!(usage.warnings(&Budget::default(), 0).is_empty());
+    }
+}
@@ -0,0 +1,172 @@
+//! Content packs loaded from the mods directory.
+//!
+//! Every sub-directory of `mods/` is a pack. A pack may contain:
+//!
+//! * `mod.toml` - the pack's `name`, `description`, `load_order`
+//!   and whether it is `enabled`
+//! * `species/*.toml` - species definitions
+//! * `names.txt` - names given to spawned blobs
+//! * `scenarios/`, `sprites/` and `scripts/` - content the engine
+//!   cannot use yet, which is listed but otherwise ignored
+//!
+//! Packs are applied by ascending load order, then by name. A
+//! species defined by a later pack replaces the species of the
+//! same name defined before it, and names are added to the pool.
+
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    species::{LoadError, Species},
+    toml,
+};
+
+#[derive(Debug, Clone)]
+pub struct Mod {
+    pub name: String,
+    pub description: String,
+    pub load_order: i32,
+    pub enabled: bool,
+    pub species: Vec<Species>,
+    pub names: Vec<String>,
+    /// Files of content kinds the engine does not support yet.
+    pub unsupported: Vec<PathBuf>,
+}
+
+impl Mod {
+    pub const DIR: &'static str = "mods";
+    const UNSUPPORTED_DIRS: [&'static str; 3] = ["scenarios", "sprites", "scripts"];
+
+    /// Load the pack in a directory.
+    pub fn load<P: AsRef<Path> + ?Sized>(dir: &P) -> Result<Self, LoadError> {
+        let dir = dir.as_ref();
+        let mut ret = Self {
+            name: dir.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned()),
+            description: String::new(),
+            load_order: 0,
+            enabled: true,
+            species: vec![],
+            names: vec![],
+            unsupported: vec![],
+        };
+
+        //  manifest
+        let manifest_path = dir.join("mod.toml");
+        if manifest_path.exists() {
+            let source = fs::read_to_string(&manifest_path).map_err(|e| LoadError::Io(manifest_path.clone(), e))?;
+            let manifest = toml::parse(&source).map_err(|e| LoadError::Parse(manifest_path.clone(), e))?;
+            let invalid = |message: &str| LoadError::Invalid(manifest_path.clone(), message.to_string());
+            if let Some(value) = manifest.get("name") {
+                ret.name = value.as_str().ok_or_else(|| invalid("`name` must be a string"))?.to_string();
+            }
+            if let Some(value) = manifest.get("description") {
+                ret.description = value.as_str().ok_or_else(|| invalid("`description` must be a string"))?.to_string();
+            }
+            if let Some(value) = manifest.get("load_order") {
+                ret.load_order = value.as_f64()
+                    .filter(|n| n.fract() == 0.)
+                    .ok_or_else(|| invalid("`load_order` must be an integer"))? as i32;
+            }
+            if let Some(value) = manifest.get("enabled") {
+                ret.enabled = value.as_bool().ok_or_else(|| invalid("`enabled` must be true or false"))?;
+            }
+        }
+
+        //  content
+        ret.species = Species::load_dir(&dir.join("species"))?;
+        let names_path = dir.join("names.txt");
+        if names_path.exists() {
+            let names = fs::read_to_string(&names_path).map_err(|e| LoadError::Io(names_path.clone(), e))?;
+            ret.names = names.split_whitespace().map(str::to_string).collect();
+        }
+        for kind in &Self::UNSUPPORTED_DIRS {
+            ret.unsupported.extend(list_dir(&dir.join(kind))?);
+        }
+
+        Ok(ret)
+    }
+
+    /// Load every pack in a directory, sorted by load order.
+    ///
+    /// A missing directory has no packs.
+    pub fn load_dir<P: AsRef<Path> + ?Sized>(dir: &P) -> Result<Vec<Self>, LoadError> {
+        let mut mods = list_dir(dir.as_ref())?
+            .into_iter()
+            .filter(|path| path.is_dir())
+            .map(|path| Self::load(&path))
+            .collect::<Result<Vec<_>, _>>()?;
+        mods.sort_by(|a, b| (a.load_order, &a.name).cmp(&(b.load_order, &b.name)));
+        Ok(mods)
+    }
+}
+
+/// Apply the enabled packs, in order, on top of the base species and names.
+pub fn merge(mods: &[Mod], mut species: Vec<Species>, mut names: Vec<String>) -> (Vec<Species>, Vec<String>) {
+    for m in mods.iter().filter(|m| m.enabled) {
+        for s in &m.species {
+            match species.iter_mut().find(|existing| existing.name == s.name) {
+                Some(existing) => *existing = s.clone(),
+                None => species.push(s.clone()),
+            }
+        }
+        names.extend(m.names.iter().cloned());
+    }
+    (species, names)
+}
+
+/// The sorted paths in a directory, or none if it does not exist.
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>, LoadError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(LoadError::Io(dir.to_path_buf(), e)),
+    };
+    let mut paths = entries
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()
+        .map_err(|e| LoadError::Io(dir.to_path_buf(), e))?;
+    paths.sort();
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml::Value;
+
+    fn species(name: &str, spawn_weight: f32) -> Species {
+        let mut table = toml::Table::new();
+        table.insert("name".to_string(), Value::String(name.to_string()));
+        table.insert("spawn_weight".to_string(), Value::Number(spawn_weight as f64));
+        Species::from_table(&table).unwrap()
+    }
+
+    fn pack(name: &str, enabled: bool, species: Vec<Species>, names: &[&str]) -> Mod {
+        Mod {
+            name: name.to_string(),
+            description: String::new(),
+            load_order: 0,
+            enabled,
+            species,
+            names: names.iter().map(|n| n.to_string()).collect(),
+            unsupported: vec![],
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let mods = vec![
+            pack("a", true, vec![species("Grazer", 5.), species("Wisp", 1.)], &["Ann"]),
+            pack("b", false, vec![species("Ghost", 1.)], &["Bob"]),
+            pack("c", true, vec![species("Wisp", 2.)], &["Cid"]),
+        ];
+        let (species, names) = merge(&mods, vec![species("Grazer", 1.)], vec!["Dan".to_string()]);
+
+        let summary: Vec<_> = species.iter().map(|s| (s.name.as_str(), s.spawn_weight)).collect();
+        assert_eq!(summary, vec![("Grazer", 5.), ("Wisp", 2.)]);
+        assert_eq!(names, vec!["Dan", "Ann", "Cid"]);
+    }
+}
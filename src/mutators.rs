@@ -0,0 +1,87 @@
+//! World modifiers chosen when a world is created.
+//!
+//! Every mutator is an overlay on the simulation's parameters,
+//! so any combination of them can be applied together.
+
+use std::fmt;
+
+use crate::simulation::Parameters;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mutator {
+    /// Blobs never kill each other.
+    NoPredation,
+    /// Offspring traits drift twice as far from their parents'.
+    DoubleMutation,
+    /// Food appears at a fraction of the usual rate.
+    ScarceFood,
+    /// Blobs see half as far.
+    Fog,
+    /// Blobs are twice as large.
+    GiantBlobs,
+}
+
+impl Mutator {
+    pub const ALL: [Self; 5] = [
+        Self::NoPredation,
+        Self::DoubleMutation,
+        Self::ScarceFood,
+        Self::Fog,
+        Self::GiantBlobs,
+    ];
+
+    /// The name used on the command line.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::NoPredation => "no-predation",
+            Self::DoubleMutation => "double-mutation",
+            Self::ScarceFood => "scarce-food",
+            Self::Fog => "fog",
+            Self::GiantBlobs => "giant-blobs",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().cloned().find(|m| m.id() == id)
+    }
+
+    /// Apply the mutator on top of the given parameters.
+    pub fn apply(&self, parameters: &mut Parameters) {
+        match self {
+            Self::NoPredation => parameters.predation = false,
+            Self::DoubleMutation => parameters.mutation_rate *= 2.,
+            Self::ScarceFood => parameters.food_rate *= 0.4,
+            Self::Fog => parameters.sight_scale *= 0.5,
+            Self::GiantBlobs => parameters.size_scale *= 2.,
+        }
+    }
+}
+
+impl fmt::Display for Mutator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::NoPredation => "No predation",
+            Self::DoubleMutation => "Double mutation",
+            Self::ScarceFood => "Scarce food",
+            Self::Fog => "Fog",
+            Self::GiantBlobs => "Giant blobs",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_composes() {
+        let mut parameters = Parameters::default();
+        for mutator in &[Mutator::Fog, Mutator::GiantBlobs, Mutator::Fog] {
+            mutator.apply(&mut parameters);
+        }
+        assert_eq!(parameters.sight_scale, 0.25);
+        assert_eq!(parameters.size_scale, 2.);
+        assert!(parameters.predation);
+        assert_eq!(Mutator::from_id("scarce-food"), Some(Mutator::ScarceFood));
+    }
+}
@@ -0,0 +1,149 @@
+//! How blobs born in a world are named, and renaming many at once.
+//!
+//! Blobs that are added always get a name from the names of the base
+//! game and the enabled mods. Children are named by a rule, given with
+//! `--naming`:
+//!
+//! * `fresh` - a name drawn from a pack, the names of one mod or of
+//!   the base game given with `--name-pack`, or all of them (default)
+//! * `inherit` - the parent's name with the child's generation as a
+//!   numeral, so a child of `Ada` is `Ada II` and a grandchild
+//!   `Ada III`, or a fresh name if the parent has none
+//! * `unnamed` - no name, until the blob is selected
+//!
+//! Shift and `N` renames the selected blobs by a pattern, where each
+//! run of `#` stands for a counter from 1, padded to the run's length,
+//! and `*` for the blob's current name: `Scout ##` names them
+//! `Scout 01`, `Scout 02` and so on.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::naming::{rename, Naming, NamingRule};
+//! # let names = vec!["Ada".to_string()];
+//! let naming = Naming { rule: NamingRule::Inherit, pack: None };
+//! let name = naming.child_name(Some(("Ada", 1)), 2, &names, sim.rng());
+//! assert_eq!(name.as_deref(), Some("Ada II"));
+//! assert_eq!(rename("Scout ##", None, 3), "Scout 03");
+//! ```
+
+use std::sync::Arc;
+
+use rand::prelude::*;
+
+/// How children are named.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NamingRule {
+    #[default]
+    Fresh,
+    Inherit,
+    Unnamed,
+}
+
+impl NamingRule {
+    pub const NAMES: [&'static str; 3] = ["fresh", "inherit", "unnamed"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fresh" => Some(Self::Fresh),
+            "inherit" => Some(Self::Inherit),
+            "unnamed" => Some(Self::Unnamed),
+            _ => None,
+        }
+    }
+}
+
+/// The naming of a world's children.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Naming {
+    pub rule: NamingRule,
+    /// The names fresh ones are drawn from, if not all of them.
+    pub pack: Option<Arc<[String]>>,
+}
+
+impl Naming {
+    /// The name of a child of some generation, if it has one, given
+    /// the name and generation of its parent if it has a name.
+    pub fn child_name<R: Rng + ?Sized>(&self, parent: Option<(&str, u32)>, generation: u32, names: &[String], rng: &mut R) -> Option<String> {
+        match (self.rule, parent) {
+            (NamingRule::Unnamed, _) => None,
+            (NamingRule::Inherit, Some((name, parent_generation))) => Some(inherited(name, parent_generation, generation)),
+            _ => Some(self.fresh_name(names, rng)),
+        }
+    }
+
+    /// A name drawn from the pack, or from all names.
+    pub fn fresh_name<R: Rng + ?Sized>(&self, names: &[String], rng: &mut R) -> String {
+        self.pack.as_deref().unwrap_or(names).choose(rng).unwrap().to_string()
+    }
+}
+
+/// A parent's name with the numeral of its generation replaced by
+/// that of a child's.
+pub fn inherited(parent: &str, parent_generation: u32, generation: u32) -> String {
+    let suffix = format!(" {}", roman(parent_generation));
+    let base = parent.strip_suffix(suffix.as_str()).filter(|_| parent_generation > 1).unwrap_or(parent);
+    format!("{} {}", base, roman(generation))
+}
+
+/// A number from 1 in Roman numerals, or in digits from 4000 on.
+pub fn roman(mut number: u32) -> String {
+    if number == 0 || number >= 4000 {
+        return number.to_string();
+    }
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"), (900, "CM"), (500, "D"), (400, "CD"), (100, "C"), (90, "XC"),
+        (50, "L"), (40, "XL"), (10, "X"), (9, "IX"), (5, "V"), (4, "IV"), (1, "I"),
+    ];
+    let mut ret = String::new();
+    for &(value, numeral) in &NUMERALS {
+        while number >= value {
+            ret += numeral;
+            number -= value;
+        }
+    }
+    ret
+}
+
+/// The name a pattern gives the blob with a counter, see the module
+/// documentation.
+pub fn rename(pattern: &str, name: Option<&str>, counter: usize) -> String {
+    let mut ret = String::new();
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '#' => {
+                let mut width = 1;
+                while chars.next_if_eq(&'#').is_some() {
+                    width += 1;
+                }
+                ret += &format!("{:0width$}", counter, width = width);
+            },
+            '*' => ret += name.unwrap_or("unnamed"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_naming() {
+        assert_eq!(inherited("Ada", 1, 2), "Ada II");
+        assert_eq!(inherited("Ada II", 2, 3), "Ada III");
+        assert_eq!(inherited("Ada VIII", 8, 9), "Ada IX");
+        //  a numeral that is not the generation's is part of the name
+        assert_eq!(inherited("Henry VIII", 1, 2), "Henry VIII II");
+        assert_eq!(roman(1994), "MCMXCIV");
+
+        assert_eq!(rename("Scout ##", None, 3), "Scout 03");
+        assert_eq!(rename("* the #", Some("Ada"), 12), "Ada the 12");
+        assert_eq!(rename("#-#", None, 7), "7-7");
+    }
+}
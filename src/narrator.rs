@@ -0,0 +1,162 @@
+//! A plain text account of a world, for screen readers and other programs.
+//!
+//! Every few seconds of simulation time the narrator writes a
+//! summary of the world, a line for each death, each blob set
+//! straight after a bug and each alert of a watched region since the
+//! last summary and the status of the selected blobs. Each line starts
+//! with its kind, followed by `key=value` fields separated by
+//! spaces. Text values are quoted, with `"` and `\` escaped by a
+//! backslash. New fields are only ever added at the end of a line,
+//! so readers can rely on the ones they know.
+//!
+//! ```text
+//! summary tick=600 time="Year 1, Day 1 (Spring)" population=12 food=87 births=2 kills=1 starvations=0
+//! killed tick=590 victim="Ann" killer="Bob"
+//! starved tick=595 blob="Cid"
+//! quarantined tick=597 blob="Eve" problem="velocity Vector2 { x: NaN, y: 0.0 }" removed=false
+//! alert tick=600 region="nest" alert="predators>3" value=4
+//! extinct tick=600 species="Hunter" since=0 peak=9 generations=4
+//! selected name="Dan" species="grazer" diet=herbivore age=12.5 hunger=0.40 x=120 y=300
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use std::io;
+//! # use blobs::narrator::Narrator;
+//! let mut narrator = Narrator::new(io::stdout());
+//! //  after every tick
+//! narrator.update(&sim, &[])?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{self, Write};
+
+use crate::{
+    simulation::{Simulation, Blob, Event},
+    clock::Interval,
+};
+
+pub struct Narrator<W: Write> {
+    out: W,
+    interval: Interval,
+    //  the first tick whose events have not been reported
+    next_tick: u64,
+}
+
+impl<W: Write> Narrator<W> {
+    /// Seconds of simulation time between reports.
+    pub const PERIOD: f64 = 10.;
+
+    pub fn new(out: W) -> Self {
+        Self { out, interval: Interval::new(Self::PERIOD), next_tick: 0 }
+    }
+
+    /// Write a report if one is due.
+    pub fn update(&mut self, sim: &Simulation, selected: &[&Blob]) -> io::Result<()> {
+        if self.interval.poll(sim.clock()) {
+            self.report(sim, selected)?;
+        }
+        Ok(())
+    }
+
+    /// Write a report of what happened since the last one.
+    pub fn report(&mut self, sim: &Simulation, selected: &[&Blob]) -> io::Result<()> {
+        let tick = sim.clock().tick();
+        let events: Vec<_> = sim.events_since(self.next_tick).collect();
+        let count = |f: fn(&Event) -> bool| events.iter().filter(|(_, event)| f(event)).count();
+        writeln!(self.out, "summary tick={} time={} population={} food={} births={} kills={} starvations={}",
+            tick,
+            quote(&sim.clock().to_string()),
+            sim.blobs().count(),
+            sim.foods().count(),
+            count(|event| matches!(event, Event::Born(_))),
+            count(|event| matches!(event, Event::Killed { .. })),
+            count(|event| matches!(event, Event::Starved(_))),
+        )?;
+        for (tick, event) in events {
+            if !matches!(event, Event::Born(_)) {
+                write_event(&mut self.out, *tick, event)?;
+            }
+        }
+        for blob in selected {
+            writeln!(self.out, "selected name={} species={} diet={} age={:.1} hunger={:.2} x={:.0} y={:.0}",
+                quote_name(&blob.name),
+                quote_name(&blob.species),
+                blob.diet.name(),
+                blob.alive_time,
+                blob.hunger / blob.max_hunger,
+                blob.pos().x,
+                blob.pos().y,
+            )?;
+        }
+        self.next_tick = tick + 1;
+        self.out.flush()
+    }
+}
+
+/// Write the line of an event. Births, which a report only counts,
+/// are written with the key of the blob.
+pub fn write_event<W: Write>(out: &mut W, tick: u64, event: &Event) -> io::Result<()> {
+    match event {
+        Event::Born(key) => writeln!(out, "born tick={} key={}", tick, key.index()),
+        Event::Killed { victim, killer } => writeln!(out, "killed tick={} victim={} killer={}",
+            tick, quote_name(victim), quote_name(killer),
+        ),
+        Event::Starved(name) => writeln!(out, "starved tick={} blob={}", tick, quote_name(name)),
+        Event::Quarantined { blob, problem, removed } => writeln!(out, "quarantined tick={} blob={} problem={} removed={}",
+            tick, quote_name(blob), quote(problem), removed,
+        ),
+        Event::Alert { region, alert, value } => writeln!(out, "alert tick={} region={} alert={} value={}",
+            tick, quote(region), quote(&alert.to_string()), value,
+        ),
+        Event::Extinct(extinction) => writeln!(out, "extinct tick={} species={} since={} peak={} generations={}",
+            tick, quote(&extinction.species), extinction.first_tick, extinction.peak, extinction.generations,
+        ),
+        Event::Weather(weather) => writeln!(out, "weather tick={} kind={} x={} y={} radius={} until={}",
+            tick, weather.kind.name(), weather.center.x, weather.center.y, weather.radius, weather.until,
+        ),
+        Event::Record(record) => writeln!(out, "record tick={} kind={} value={} holder={}",
+            tick, record.kind.name(), record.value, quote_name(&record.holder),
+        ),
+    }
+}
+
+/// A text value as it is written in a field.
+pub fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A name that may be missing, which is written empty.
+pub fn quote_name(name: &Option<String>) -> String {
+    quote(name.as_deref().unwrap_or(""))
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::prelude::*;
+
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_report() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let key = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.get_blob_mut(key).unwrap().name = Some("Say \"hi\"".to_string());
+
+        let mut out = vec![];
+        let mut narrator = Narrator::new(&mut out);
+        narrator.update(&sim, &[sim.get_blob(key).unwrap()]).unwrap();
+        //  nothing is due until a period passes
+        narrator.update(&sim, &[]).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("summary tick=0 time=\"Year 1, Day 1 (Spring)\" population=1 food=0 births=1 kills=0"));
+        assert!(lines[1].starts_with("selected name=\"Say \\\"hi\\\"\" species=\"\" diet=herbivore age=0.0"));
+    }
+}
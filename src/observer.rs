@@ -0,0 +1,172 @@
+//! A read-only view of a world for external visualizers.
+//!
+//! An observer is made with an interest, the blobs in a region, of a
+//! species or all of them, and after every tick lists what changed
+//! among those blobs rather than the whole world: the ones spawned,
+//! moved and died, and the ones that came into or went out of its
+//! interest. Lists are small when little happens, so they are cheap
+//! to stream too. Observers attached with hooks, see `hooks`, also
+//! know where blobs died rather than where they were a tick before.
+//! `--observe` writes the lists to a file:
+//!
+//! ```text
+//! tick 120
+//! spawned 41 310.5 220
+//! moved 7 100.25 98
+//! died 12 64 301.75
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::observer::{Interest, Observer};
+//! # let rect = Rectangle::new(0., 0., 200., 200.);
+//! let mut observer = Observer::new(Interest { region: Some(rect), species: None });
+//! sim.step();
+//! let changes = observer.observe(&sim);
+//! for (key, pos) in &changes.moved {
+//!     println!("{} moved to {:?}", key.index(), pos);
+//! }
+//! ```
+
+use std::{collections::{BTreeMap, BTreeSet}, io::{self, Write}};
+
+use raylib::prelude::*;
+
+use crate::{keyed_set::Key, simulation::{Blob, CircleObject, Simulation}, hooks::Removed};
+
+/// The blobs an observer follows, those matching every filter given.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Interest {
+    pub region: Option<Rectangle>,
+    pub species: Option<String>,
+}
+
+impl Interest {
+    pub fn matches(&self, blob: &Blob) -> bool {
+        self.region.is_none_or(|region| region.check_collision_point_rec(blob.pos()))
+            && self.species.as_ref().is_none_or(|species| blob.species.as_ref() == Some(species))
+    }
+}
+
+/// What changed among the observed blobs in a tick.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Changes {
+    pub tick: u64,
+    /// Blobs added to the world since the last tick.
+    pub spawned: Vec<(Key<Blob>, Vector2)>,
+    /// Blobs that moved into the interest.
+    pub entered: Vec<(Key<Blob>, Vector2)>,
+    pub moved: Vec<(Key<Blob>, Vector2)>,
+    pub died: Vec<(Key<Blob>, Vector2)>,
+    /// Blobs still alive that moved out of the interest.
+    pub left: Vec<Key<Blob>>,
+}
+
+impl Changes {
+    pub fn is_empty(&self) -> bool {
+        self.spawned.is_empty() && self.entered.is_empty() && self.moved.is_empty() && self.died.is_empty() && self.left.is_empty()
+    }
+
+    /// Write the changes as lines of text, see the module documentation.
+    pub fn write<W: Write + ?Sized>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "tick {}", self.tick)?;
+        let placed = [("spawned", &self.spawned), ("entered", &self.entered), ("moved", &self.moved), ("died", &self.died)];
+        for (name, blobs) in &placed {
+            for (key, pos) in blobs.iter() {
+                writeln!(out, "{} {} {} {}", name, key.index(), pos.x, pos.y)?;
+            }
+        }
+        for key in &self.left {
+            writeln!(out, "left {}", key.index())?;
+        }
+        Ok(())
+    }
+}
+
+/// Follows the blobs of an interest from tick to tick.
+#[derive(Debug, Clone)]
+pub struct Observer {
+    interest: Interest,
+    //  where the observed blobs were when last observed
+    known: BTreeMap<Key<Blob>, Vector2>,
+    //  every blob there when last observed, to tell those born since
+    alive: BTreeSet<Key<Blob>>,
+    //  where observed blobs were removed since the last observation
+    removed: BTreeMap<Key<Blob>, Vector2>,
+}
+
+impl Observer {
+    pub fn new(interest: Interest) -> Self {
+        Self { interest, known: BTreeMap::new(), alive: BTreeSet::new(), removed: BTreeMap::new() }
+    }
+
+    /// Note where an observed blob was removed, from a removal hook.
+    pub fn note_removed(&mut self, removed: &Removed) {
+        if let CircleObject::Blob(key) = removed.object() {
+            if self.known.contains_key(&key) {
+                self.removed.insert(key, removed.pos());
+            }
+        }
+    }
+
+    /// What changed among the observed blobs since the last call.
+    pub fn observe(&mut self, sim: &Simulation) -> Changes {
+        let mut changes = Changes { tick: sim.clock().tick(), ..Changes::default() };
+        let mut known = BTreeMap::new();
+        let mut alive = BTreeSet::new();
+        for (key, blob) in sim.blobs() {
+            alive.insert(key);
+            if !self.interest.matches(blob) { continue; }
+            let pos = blob.pos();
+            match self.known.get(&key) {
+                Some(&last) if last != pos => changes.moved.push((key, pos)),
+                Some(_) => (),
+                None if !self.alive.contains(&key) => changes.spawned.push((key, pos)),
+                None => changes.entered.push((key, pos)),
+            }
+            known.insert(key, pos);
+        }
+        for (&key, &last) in self.known.iter().filter(|(key, _)| !known.contains_key(key)) {
+            if sim.get_blob(key).is_some() {
+                changes.left.push(key);
+            } else {
+                changes.died.push((key, self.removed.get(&key).copied().unwrap_or(last)));
+            }
+        }
+        self.removed.clear();
+        self.known = known;
+        self.alive = alive;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_observer() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(400., 400.)));
+        let inside = sim.insert_blob(BlobConfig::new(Vector2::new(50., 50.)));
+        let outside = sim.insert_blob(BlobConfig::new(Vector2::new(300., 300.)));
+        let mut observer = Observer::new(Interest { region: Some(Rectangle::new(0., 0., 200., 200.)), species: None });
+        let first = observer.observe(&sim);
+        assert_eq!(first.spawned, vec![(inside, Vector2::new(50., 50.))]);
+        assert!(observer.observe(&sim).is_empty());
+
+        sim.set_blob_pos(outside, Vector2::new(100., 100.));
+        sim.remove_blob(inside);
+        let changes = observer.observe(&sim);
+        assert_eq!(changes.entered, vec![(outside, Vector2::new(100., 100.))]);
+        assert_eq!(changes.died, vec![(inside, Vector2::new(50., 50.))]);
+
+        let mut out = vec![];
+        changes.write(&mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("tick 0\nentered {} 100 100\ndied {} 50 50\n", outside.index(), inside.index()));
+    }
+}
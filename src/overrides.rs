@@ -0,0 +1,132 @@
+//! Parameters changed for one species only, to compare species under
+//! different rules within one world.
+//!
+//! An override multiplies a parameter, see `Parameters`, for the blobs
+//! of a species, on top of its value for the whole world:
+//!
+//! * `mutation_rate` for the children they have
+//! * `metabolism_scale` for how fast they grow hungry
+//! * `predation_efficiency` for what they get from the blobs they eat
+//!
+//! Shift and `O` asks for one as the species, the parameter and the
+//! change, `x2` doubling it and `-20%` taking a fifth off, and `x1`
+//! takes it off again. Overrides are saved with the world, and cannot
+//! be changed in a lockstep session, whose peer would not know of them.
+//!
+//! ```text
+//! Grazer mutation_rate x2
+//! Hunter metabolism_scale -20%
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::overrides;
+//! let (species, parameter, factor) = overrides::parse("Grazer mutation_rate x2")?;
+//! sim.set_override(&species, &parameter, factor)?;
+//! assert_eq!(sim.overrides().factor(Some("Grazer"), "mutation_rate"), 2.);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::collections::BTreeMap;
+
+/// The parameters that can be overridden.
+pub const NAMES: [&str; 3] = ["mutation_rate", "metabolism_scale", "predation_efficiency"];
+
+/// The factors of the overridden parameters of each species.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Overrides {
+    factors: BTreeMap<(String, &'static str), f32>,
+}
+
+impl Overrides {
+    /// Multiply a parameter for a species, a factor of 1 taking the override off.
+    pub fn set(&mut self, species: &str, parameter: &str, factor: f32) -> Result<(), String> {
+        let parameter = NAMES.iter().copied().find(|&name| name == parameter)
+            .ok_or_else(|| format!("`{}` cannot be overridden, only {}", parameter, NAMES.join(", ")))?;
+        if !factor.is_finite() || factor < 0. {
+            return Err(format!("`{}` cannot be multiplied by {}", parameter, factor));
+        }
+        if factor == 1. {
+            self.factors.remove(&(species.to_string(), parameter));
+        } else {
+            self.factors.insert((species.to_string(), parameter), factor);
+        }
+        Ok(())
+    }
+
+    /// What a parameter is multiplied by for a species, 1 for blobs of none.
+    pub fn factor(&self, species: Option<&str>, parameter: &str) -> f32 {
+        species.and_then(|species| self.factors.iter().find(|((s, p), _)| s == species && *p == parameter))
+            .map_or(1., |(_, &factor)| factor)
+    }
+
+    /// Every override as its species, parameter and factor.
+    pub fn iter(&self) -> impl Iterator<Item=(&str, &'static str, f32)> {
+        self.factors.iter().map(|((species, parameter), &factor)| (species.as_str(), *parameter, factor))
+    }
+
+    pub fn is_empty(&self) -> bool { self.factors.is_empty() }
+
+    /// The overrides as `Grazer mutation_rate x2`, separated by commas.
+    pub fn describe(&self) -> String {
+        self.iter().map(|(species, parameter, factor)| format!("{} {} x{}", species, parameter, factor)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// Read an override as typed, see the module, into its species,
+/// parameter and factor.
+pub fn parse(text: &str) -> Result<(String, String, f32), String> {
+    let mut words = text.trim().rsplitn(3, ' ');
+    let (change, parameter, species) = match (words.next(), words.next(), words.next()) {
+        (Some(change), Some(parameter), Some(species)) if !species.trim().is_empty() => (change, parameter, species.trim()),
+        _ => return Err("Type the species, the parameter and the change, as Grazer mutation_rate x2".to_string()),
+    };
+    let factor = if let Some(factor) = change.strip_prefix('x').or_else(|| change.strip_prefix('*')) {
+        factor.parse::<f32>().ok()
+    } else if let Some(percent) = change.strip_suffix('%') {
+        percent.parse::<f32>().ok().map(|percent| 1. + percent / 100.)
+    } else {
+        None
+    };
+    let factor = factor.ok_or_else(|| format!("`{}` is not a change like x2 or -20%", change))?;
+    Ok((species.to_string(), parameter.to_string(), factor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::Vector2;
+    use crate::simulation::{BlobConfig, Simulation, SimulationConfig};
+
+    #[test]
+    fn test_overrides() {
+        let mut overrides = Overrides::default();
+        let (species, parameter, factor) = parse("Deep Sea mutation_rate x2").unwrap();
+        overrides.set(&species, &parameter, factor).unwrap();
+        let (species, parameter, factor) = parse("Hunter metabolism_scale -20%").unwrap();
+        overrides.set(&species, &parameter, factor).unwrap();
+        assert_eq!(overrides.factor(Some("Deep Sea"), "mutation_rate"), 2.);
+        assert!((overrides.factor(Some("Hunter"), "metabolism_scale") - 0.8).abs() < 1e-6);
+        assert_eq!((overrides.factor(Some("Hunter"), "mutation_rate"), overrides.factor(None, "mutation_rate")), (1., 1.));
+        assert_eq!(overrides.describe(), "Deep Sea mutation_rate x2, Hunter metabolism_scale x0.8");
+
+        overrides.set("Deep Sea", "mutation_rate", 1.).unwrap();
+        assert_eq!(overrides.iter().count(), 1);
+        assert!(overrides.set("Hunter", "water", 2.).is_err() && overrides.set("Hunter", "mutation_rate", -1.).is_err());
+        assert!(parse("mutation_rate x2").is_err() && parse("Grazer mutation_rate twice").is_err());
+
+        //  a species whose metabolism is stopped grows no hungrier than it was
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let blob = |species: &str, x: f32| BlobConfig { species: Some(species.to_string()), ..BlobConfig::new(Vector2::new(x, 100.)) };
+        let (resting, grazer) = (sim.insert_blob(blob("Resting", 50.)), sim.insert_blob(blob("Grazer", 250.)));
+        sim.set_override("Resting", "metabolism_scale", 0.).unwrap();
+        let hunger = |sim: &Simulation, key| sim.get_blob(key).unwrap().hunger;
+        let before = hunger(&sim, resting);
+        sim.step();
+        assert!(hunger(&sim, resting) == before && hunger(&sim, grazer) > before);
+    }
+}
@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::{collections::{HashMap, HashSet}, hash::BuildHasherDefault};
 
 use raylib::prelude::*;
 
-use crate::keyed_set::prelude::*;
+use crate::{keyed_set::prelude::*, math::{FloatMode, Fnv}};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -51,17 +51,94 @@ pub type CircleCollisions = HashMap<Key<Circle>, Vec<Key<Circle>>>;
 
 pub type CollisionMatrix = HashMap<Layer, LayerMask>;
 
+/// Circles, and a grid of those others collide with kept as they
+/// change, so that collisions and queries look only at circles near
+/// them.
+#[derive(Clone)]
 pub struct World {
-    pub circles: KeyedSet<Circle>,    
+    circles: KeyedSet<Circle>,
     collision_matrix: CollisionMatrix,
+    //  the layers some circle may collide with, whose circles the grid lists
+    targets: LayerMask,
+    grid: Grid,
+    //  the total of the finite radii of the circles listed, which sizes the cells
+    listed_radius: f64,
 }
 
+/// The velocity left after `timestep` seconds of exponential damping.
+pub fn damp(velocity: Vector2, damping: f32, timestep: f32, mode: FloatMode) -> Vector2 {
+    velocity * mode.exp(-damping * timestep)
+}
 
-impl Circle {
-    pub fn intersects_x_axis(&self, other: &Self) -> bool {
-        (other.center.x - self.center.x).abs() <= self.radius + other.radius
+/// What a field does to the velocities in it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Force {
+    /// An acceleration away from the center, toward it if negative.
+    Radial(f32),
+    /// Exponential damping at a rate, as `damp`.
+    Damping(f32),
+}
+
+impl Force {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Radial(_) => "radial",
+            Self::Damping(_) => "damping",
+        }
+    }
+
+    /// How strong it is, its acceleration or damping.
+    pub fn amount(&self) -> f32 {
+        match *self {
+            Self::Radial(amount) | Self::Damping(amount) => amount,
+        }
+    }
+
+    pub fn from_name(name: &str, amount: f32) -> Option<Self> {
+        match name {
+            "radial" => Some(Self::Radial(amount)),
+            "damping" => Some(Self::Damping(amount)),
+            _ => None,
+        }
+    }
+}
+
+/// A force over a disc, whole at its center and fading to nothing at
+/// its edge.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Field {
+    pub center: Vector2,
+    pub radius: f32,
+    pub force: Force,
+}
+
+impl Field {
+    /// How much of the force there is at a point, from 1 to 0.
+    pub fn falloff(&self, pos: Vector2) -> f32 {
+        let distance = (pos - self.center).length();
+        if distance >= self.radius { return 0.; }
+        let near = 1. - distance / self.radius;
+        near * near
+    }
+
+    /// The velocity of a body at a point after `timestep` seconds in the field.
+    pub fn apply(&self, pos: Vector2, velocity: Vector2, timestep: f32, mode: FloatMode) -> Vector2 {
+        let falloff = self.falloff(pos);
+        if falloff == 0. { return velocity; }
+        match self.force {
+            Force::Radial(acceleration) => {
+                let away = pos - self.center;
+                let length = away.length();
+                //  the center has no away, and is left alone
+                if length == 0. { return velocity; }
+                velocity + away / length * acceleration * falloff * timestep
+            },
+            Force::Damping(damping) => damp(velocity, damping * falloff, timestep, mode),
+        }
     }
+}
 
+impl Circle {
     pub fn intersects(&self, other: &Self) -> bool {
         (other.center - self.center).length_sqr() <= (self.radius + other.radius) * (self.radius + other.radius)
     }
@@ -69,7 +146,8 @@ impl Circle {
 
 impl World {
     pub fn new(collision_matrix: CollisionMatrix) -> Self {
-        Self { circles: KeyedSet::new(), collision_matrix }
+        let targets = LayerMask(collision_matrix.values().fold(0, |bits, mask| bits | mask.0));
+        Self { circles: KeyedSet::new(), collision_matrix, targets, grid: Grid::new(MIN_CELL_SIZE), listed_radius: 0. }
     }
 
     fn layers_collide(collision_matrix: &CollisionMatrix, left: &Circle, right: &Circle) -> bool {
@@ -79,62 +157,311 @@ impl World {
         }
     }
 
-    fn collisions_naive<'a>(collision_matrix: &CollisionMatrix, circles: &Vec<(Key<Circle>, &'a Circle)>) -> CircleCollisions {
-        let mut ret = CircleCollisions::new();
-        for &(key, circle) in circles {
-            let mut collided = vec![];
-            for &(other_key, other_circle) in circles {
-                if other_key != key 
-                && circle.intersects(other_circle)
-                && Self::layers_collide(collision_matrix, circle, other_circle) {
-                    collided.push(other_key);
-                }
-            }
-            if collided.len() > 0 { 
-                ret.insert(key, collided);
+    pub fn circles(&self) -> &KeyedSet<Circle> { &self.circles }
+
+    pub fn insert(&mut self, circle: Circle) -> Key<Circle> {
+        let key = self.circles.insert(circle);
+        //  a layer missing from the matrix collides with every layer
+        if !self.collision_matrix.contains_key(&circle.layer) && self.targets != LayerMask::full() {
+            self.targets = LayerMask::full();
+            self.rebuild();
+        } else {
+            self.list(key, &circle);
+            self.resize();
+        }
+        key
+    }
+
+    pub fn remove(&mut self, key: Key<Circle>) -> Option<Circle> {
+        let circle = self.circles.remove(key)?;
+        self.unlist(key, &circle);
+        self.resize();
+        Some(circle)
+    }
+
+    pub fn set_center(&mut self, key: Key<Circle>, center: Vector2) {
+        if let Some(circle) = self.circles.get_mut(key) {
+            circle.center = center;
+            let circle = *circle;
+            if self.targets.contains(&circle.layer) {
+                self.grid.update(key, &circle);
             }
         }
-        ret    
     }
 
+    pub fn set_radius(&mut self, key: Key<Circle>, radius: f32) {
+        if let Some(circle) = self.circles.get_mut(key) {
+            let old = *circle;
+            circle.radius = radius;
+            let new = *circle;
+            self.unlist(key, &old);
+            self.list(key, &new);
+            self.resize();
+        }
+    }
+
+    /// List a circle in the grid if others may collide with it.
+    fn list(&mut self, key: Key<Circle>, circle: &Circle) {
+        if !self.targets.contains(&circle.layer) { return; }
+        self.grid.insert(key, circle);
+        if circle.radius.is_finite() {
+            self.listed_radius += circle.radius as f64;
+        }
+    }
+
+    fn unlist(&mut self, key: Key<Circle>, circle: &Circle) {
+        if !self.grid.remove(key) { return; }
+        if circle.radius.is_finite() {
+            self.listed_radius -= circle.radius as f64;
+        }
+    }
+
+    /// Cells as wide as two of the circles listed, as small as bodies
+    /// are, so a body is tested against few others and a sight looks
+    /// up few cells.
+    fn cell_size(&self, listed: usize) -> f32 {
+        if listed == 0 { MIN_CELL_SIZE } else { (4. * self.listed_radius / listed as f64) as f32 }
+    }
+
+    /// Size cells anew once the circles listed have grown or shrunk to
+    /// twice or half what they were sized by.
+    fn resize(&mut self) {
+        let listed = self.grid.listed.len();
+        if listed == 0 { return; }
+        let cell_size = self.cell_size(listed);
+        if cell_size > 2. * self.grid.cell_size || (cell_size < self.grid.cell_size / 2. && self.grid.cell_size > MIN_CELL_SIZE) {
+            self.rebuild();
+        }
+    }
+
+    /// List every circle others may collide with in a grid of new cells.
+    fn rebuild(&mut self) {
+        let listed: Vec<_> = self.circles.iter().filter(|(_, circle)| self.targets.contains(&circle.layer)).collect();
+        let finite = listed.iter().map(|(_, circle)| circle.radius).filter(|radius| radius.is_finite());
+        self.listed_radius = finite.map(|radius| radius as f64).sum();
+        let mut grid = Grid::new(self.cell_size(listed.len()));
+        for (&key, circle) in listed {
+            grid.insert(key, circle);
+        }
+        self.grid = grid;
+    }
+
+    /// The circles whose bounding boxes overlap a box, some more than
+    /// once, and those too large to list.
+    fn near(&self, center: Vector2, half: Vector2) -> impl Iterator<Item=(Key<Circle>, &Circle)> + '_ {
+        self.grid.candidates(self.grid.span(center, half)).filter_map(move |key| Some((key, self.circles.get(key)?)))
+    }
+
+    #[cfg(test)]
     pub fn collisions(&self) -> CircleCollisions {
-        //  use the sweep and prune algorithm
+        self.collisions_where(|_| true)
+    }
 
-        //  edge case - no circles
-        if self.circles.len() == 0 { return CircleCollisions::new() }
+    /// The collisions of only the circles a predicate picks, with any
+    /// circle, each listed from left to right.
+    pub fn collisions_where<F>(&self, is_query: F) -> CircleCollisions
+    where F: Fn(Key<Circle>) -> bool {
+        let mut ret = CircleCollisions::new();
+        for (&key, circle) in self.circles.iter().filter(|(&key, _)| is_query(key)) {
+            let mut collided: Vec<_> = self.near(circle.center, Vector2::new(circle.radius, circle.radius))
+                .filter(|&(other, other_circle)| other != key
+                    && circle.intersects(other_circle)
+                    && Self::layers_collide(&self.collision_matrix, circle, other_circle))
+                .collect();
+            if !collided.is_empty() {
+                collided.sort_by(|(a, a_circle), (b, b_circle)| a_circle.center.x.total_cmp(&b_circle.center.x).then(a.cmp(b)));
+                //  a circle may share several cells with another
+                collided.dedup_by_key(|(other, _)| *other);
+                ret.insert(key, collided.into_iter().map(|(other, _)| other).collect());
+            }
+        }
+        ret
+    }
+
+    /// The circles on some layers that touch a circle, in key order.
+    /// Only circles others collide with are found.
+    pub fn touching(&self, center: Vector2, radius: f32, layers: LayerMask) -> Vec<Key<Circle>> {
+        let query = Circle { center, radius, layer: Layer(0) };
+        let mut found: Vec<_> = self.near(center, Vector2::new(radius, radius))
+            .filter(|(_, circle)| layers.contains(&circle.layer) && query.intersects(circle))
+            .map(|(key, _)| key)
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
 
-        //  sort by x axis
-        let mut circles: Vec<(Key<Circle>, &Circle)> = self.circles
-            .iter()
-            .map(|tuple| (*tuple.0, tuple.1))
+    /// The circles on some layers whose centers lie in a rectangle, in
+    /// key order. Only circles others collide with are found.
+    pub fn centers_in(&self, rect: Rectangle, layers: LayerMask) -> Vec<Key<Circle>> {
+        let half = Vector2::new(rect.width, rect.height) / 2.;
+        let inside = |pos: Vector2| pos.x >= rect.x && pos.x <= rect.x + rect.width && pos.y >= rect.y && pos.y <= rect.y + rect.height;
+        let mut found: Vec<_> = self.near(Vector2::new(rect.x, rect.y) + half, half)
+            .filter(|(_, circle)| layers.contains(&circle.layer) && inside(circle.center))
+            .map(|(key, _)| key)
             .collect();
-        //  this line will not work because the sort-key is a vector
-        //circles.sort_by_key(|circle| circle.center.x);
-        circles.sort_by(|a, b| a.1.center.x.partial_cmp(&b.1.center.x).unwrap());
-
-        //  check for x-axis intersection between neighbors
-        let mut x_axis_collisions = vec![];
-        let mut active_interval = vec![circles[0]]; //   edge case where no 0th element is handled earlier
-        for (key, circle) in circles.into_iter().skip(1) {
-            if active_interval.iter().any(|other| other.1.intersects_x_axis(circle)) {
-                active_interval.push((key, circle));
-            } else {
-                //  only report collisions between more than 1 circles
-                if active_interval.len() > 1 {
-                    x_axis_collisions.push(active_interval);
+        found.sort_unstable();
+        found.dedup();
+        found
+    }
+
+    /// Up to `count` of the circles a predicate picks whose centers are
+    /// nearest a position, nearest first. Only circles others collide
+    /// with are found.
+    ///
+    /// Cells are looked through in rings around the position until the
+    /// circles found within the rings are enough.
+    pub fn nearest<F>(&self, pos: Vector2, count: usize, pick: F) -> Vec<Key<Circle>>
+    where F: Fn(Key<Circle>, &Circle) -> bool {
+        let mut found: Vec<(f32, Key<Circle>)> = vec![];
+        let mut seen = HashSet::new();
+        let mut take = |keys: &[Key<Circle>], found: &mut Vec<_>| {
+            for &key in keys {
+                if let Some(circle) = self.circles.get(key).filter(|&circle| seen.insert(key) && pick(key, circle)) {
+                    found.push(((circle.center - pos).length(), key));
+                }
+            }
+        };
+        take(&self.grid.large, &mut found);
+        let (x, y) = self.grid.cell(pos);
+        let ring_of = |&(cell_x, cell_y): &(i32, i32)| (cell_x as i64 - x as i64).abs().max((cell_y as i64 - y as i64).abs());
+        let mut ring = 0;
+        loop {
+            //  a ring of more cells than are listed looks through those listed instead, all at once
+            if (2 * ring + 1) * (2 * ring + 1) > self.grid.cells.len() as i64 {
+                for (_, keys) in self.grid.cells.iter().filter(|(cell, _)| ring_of(cell) >= ring) {
+                    take(keys, &mut found);
                 }
-                active_interval = vec![(key, circle)];
+                break;
             }
+            for cell_x in x as i64 - ring..=x as i64 + ring {
+                for cell_y in y as i64 - ring..=y as i64 + ring {
+                    let on_ring = (cell_x - x as i64).abs() == ring || (cell_y - y as i64).abs() == ring;
+                    if let Some(keys) = self.grid.cells.get(&(cell_x as i32, cell_y as i32)).filter(|_| on_ring) {
+                        take(keys, &mut found);
+                    }
+                }
+            }
+            //  every center within the rings has been found
+            let within = ring as f32 * self.grid.cell_size;
+            if found.iter().filter(|&&(distance, _)| distance <= within).count() >= count { break; }
+            ring += 1;
         }
-        x_axis_collisions.push(active_interval);
-        
-        let mut ret = HashMap::new();
-        for interval in &x_axis_collisions {
-            for (key, value) in Self::collisions_naive(&self.collision_matrix, interval) {
-                ret.insert(key, value);
+        found.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        found.into_iter().take(count).map(|(_, key)| key).collect()
+    }
+}
+
+/// The smallest cells a grid is split into.
+const MIN_CELL_SIZE: f32 = 1.;
+/// The most cells a circle is listed in, larger ones being candidates
+/// of every circle instead.
+const MAX_CELLS: i64 = 1024;
+
+/// The first and last cells a bounding box overlaps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Span {
+    first: (i32, i32),
+    last: (i32, i32),
+}
+
+impl Span {
+    /// How many cells it is.
+    fn count(&self) -> i64 {
+        (self.last.0 as i64 - self.first.0 as i64 + 1).max(0).saturating_mul((self.last.1 as i64 - self.first.1 as i64 + 1).max(0))
+    }
+
+    fn contains(&self, (x, y): (i32, i32)) -> bool {
+        (self.first.0..=self.last.0).contains(&x) && (self.first.1..=self.last.1).contains(&y)
+    }
+
+    fn cells(self) -> impl Iterator<Item=(i32, i32)> {
+        (self.first.0..=self.last.0).flat_map(move |x| (self.first.1..=self.last.1).map(move |y| (x, y)))
+    }
+}
+
+/// A uniform grid over the plane, each cell listing the circles
+/// whose bounding boxes overlap it, so a circle is only tested
+/// against those sharing a cell with it.
+#[derive(Clone)]
+struct Grid {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<Key<Circle>>, BuildHasherDefault<Fnv>>,
+    //  circles over more than `MAX_CELLS` cells, such as one of an infinite radius
+    large: Vec<Key<Circle>>,
+    //  the cells each circle is listed in
+    listed: HashMap<Key<Circle>, Span, BuildHasherDefault<Fnv>>,
+}
+
+impl Grid {
+    fn new(cell_size: f32) -> Self {
+        Self { cell_size: cell_size.max(MIN_CELL_SIZE), cells: HashMap::default(), large: vec![], listed: HashMap::default() }
+    }
+
+    fn cell(&self, pos: Vector2) -> (i32, i32) {
+        ((pos.x / self.cell_size).floor() as i32, (pos.y / self.cell_size).floor() as i32)
+    }
+
+    /// The cells a box overlaps, by its center and half its size.
+    fn span(&self, center: Vector2, half: Vector2) -> Span {
+        Span { first: self.cell(center - half), last: self.cell(center + half) }
+    }
+
+    fn insert(&mut self, key: Key<Circle>, circle: &Circle) {
+        let span = self.span(circle.center, Vector2::new(circle.radius, circle.radius));
+        self.listed.insert(key, span);
+        if span.count() > MAX_CELLS {
+            self.large.push(key);
+            return;
+        }
+        for cell in span.cells() {
+            self.cells.entry(cell).or_default().push(key);
+        }
+    }
+
+    /// Take a circle out, returning whether it was listed.
+    fn remove(&mut self, key: Key<Circle>) -> bool {
+        let span = match self.listed.remove(&key) {
+            Some(span) => span,
+            None => return false,
+        };
+        if span.count() > MAX_CELLS {
+            self.large.retain(|&large| large != key);
+            return true;
+        }
+        for cell in span.cells() {
+            if let Some(keys) = self.cells.get_mut(&cell) {
+                keys.retain(|&listed| listed != key);
+                if keys.is_empty() {
+                    self.cells.remove(&cell);
+                }
             }
         }
-        ret
+        true
+    }
+
+    /// List a circle where it is now, if it moved to other cells.
+    fn update(&mut self, key: Key<Circle>, circle: &Circle) {
+        let span = self.span(circle.center, Vector2::new(circle.radius, circle.radius));
+        if self.listed.get(&key) != Some(&span) {
+            self.remove(key);
+            self.insert(key, circle);
+        }
+    }
+
+    /// The circles sharing a cell with a span, some more than once.
+    fn candidates(&self, span: Span) -> impl Iterator<Item=Key<Circle>> + '_ {
+        //  a span over more cells than are listed looks through those listed instead
+        let few = span.count() <= self.cells.len() as i64;
+        let spanned = few
+            .then(|| span.cells().filter_map(move |cell| self.cells.get(&cell)))
+            .into_iter()
+            .flatten();
+        let listed = (!few)
+            .then(|| self.cells.iter().filter(move |&(&cell, _)| span.contains(cell)).map(|(_, keys)| keys))
+            .into_iter()
+            .flatten();
+        self.large.iter().chain(spanned.chain(listed).flatten()).cloned()
     }
 }
 
@@ -145,15 +472,15 @@ mod tests {
     #[test]
     fn test_2_body_collision() {
         let mut w = World::new(CollisionMatrix::new());
-        let a = w.circles.insert(Circle { center: Vector2::new(5., 4.), radius: 2., layer: Layer::new(0) } );
-        let b = w.circles.insert(Circle { center: Vector2::new(6., 6.), radius: 1., layer: Layer::new(0) } );
+        let a = w.insert(Circle { center: Vector2::new(5., 4.), radius: 2., layer: Layer::new(0) } );
+        let b = w.insert(Circle { center: Vector2::new(6., 6.), radius: 1., layer: Layer::new(0) } );
         
         assert_eq!(w.collisions(), [
             (a, vec![b]),
             (b, vec![a]),
         ].iter().cloned().collect());
 
-        w.circles.get_mut(b).unwrap().center.x += 2.;
+        w.set_center(b, Vector2::new(8., 6.));
         
         assert_eq!(w.collisions(), [].iter().cloned().collect());
     }
@@ -161,9 +488,9 @@ mod tests {
     #[test]
     fn test_3_body_collision() {
         let mut w = World::new(CollisionMatrix::new());
-        let a = w.circles.insert(Circle { center: Vector2::new(5., 4.), radius: 2., layer: Layer::new(0) } );
-        let b = w.circles.insert(Circle { center: Vector2::new(7., 6.), radius: 1., layer: Layer::new(0) } );
-        let c = w.circles.insert(Circle { center: Vector2::new(3., 7.), radius: 2., layer: Layer::new(0) } );
+        let a = w.insert(Circle { center: Vector2::new(5., 4.), radius: 2., layer: Layer::new(0) } );
+        let b = w.insert(Circle { center: Vector2::new(7., 6.), radius: 1., layer: Layer::new(0) } );
+        let c = w.insert(Circle { center: Vector2::new(3., 7.), radius: 2., layer: Layer::new(0) } );
         
         assert_eq!(w.collisions(), [
             (a, vec![c, b]),
@@ -171,7 +498,7 @@ mod tests {
             (c, vec![a]),
         ].iter().cloned().collect());
 
-        w.circles.get_mut(c).unwrap().radius += 2.;
+        w.set_radius(c, 4.);
         
         assert_eq!(w.collisions(), [
             (a, vec![c, b]),
@@ -179,6 +506,93 @@ mod tests {
             (c, vec![a, b]),
         ].iter().cloned().collect());
     }
+
+    #[test]
+    fn test_grid_matches_all_pairs() {
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut collision_matrix = CollisionMatrix::new();
+        collision_matrix.insert(Layer::new(0), LayerMask::new(vec![Layer::new(0)]));
+        collision_matrix.insert(Layer::new(1), LayerMask::new(vec![Layer::new(0)]));
+        let mut w = World::new(collision_matrix);
+        //  small bodies among large sights, as in the simulation
+        for i in 0..300 {
+            let (radius, layer) = if i % 3 == 0 { (rng.gen_range(20. ..80.), Layer::new(1)) } else { (rng.gen_range(1. ..6.), Layer::new(0)) };
+            let center = Vector2::new(rng.gen_range(-200. ..400.), rng.gen_range(0. ..300.));
+            w.insert(Circle { center, radius, layer });
+        }
+
+        let check = |w: &World| {
+            let collisions = w.collisions();
+            for (key, circle) in w.circles.iter() {
+                let mut expected: Vec<_> = w.circles.iter()
+                    .filter(|&(other, other_circle)| other != key && circle.intersects(other_circle)
+                        && World::layers_collide(&w.collision_matrix, circle, other_circle))
+                    .map(|(other, _)| *other)
+                    .collect();
+                let mut found = collisions.get(key).cloned().unwrap_or_default();
+                expected.sort_by_key(|other| other.index());
+                found.sort_by_key(|other| other.index());
+                assert_eq!(found, expected);
+            }
+        };
+        check(&w);
+
+        //  the grid follows circles as they move, grow and go
+        let keys: Vec<_> = w.circles().keys().collect();
+        for (i, &key) in keys.iter().enumerate() {
+            match i % 4 {
+                0 => w.set_center(key, Vector2::new(rng.gen_range(-200. ..400.), rng.gen_range(0. ..300.))),
+                1 => w.set_radius(key, rng.gen_range(1. ..120.)),
+                2 => { w.remove(key); },
+                _ => (),
+            }
+        }
+        check(&w);
+
+        //  circles over most of the plane, or far from the rest, neither hang nor go missed
+        w.insert(Circle { center: Vector2::new(0., 0.), radius: 1e7, layer: Layer::new(0) });
+        w.insert(Circle { center: Vector2::new(-1e9, 1e9), radius: 1., layer: Layer::new(0) });
+        check(&w);
+        w.insert(Circle { center: Vector2::new(100., 100.), radius: f32::INFINITY, layer: Layer::new(1) });
+        check(&w);
+    }
+
+    #[test]
+    fn test_queries_match_scans() {
+        use rand::{Rng, SeedableRng, rngs::StdRng};
+
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut collision_matrix = CollisionMatrix::new();
+        collision_matrix.insert(Layer::new(0), LayerMask::new(vec![Layer::new(0), Layer::new(1)]));
+        let mut w = World::new(collision_matrix);
+        for i in 0..300 {
+            let center = Vector2::new(rng.gen_range(-200. ..400.), rng.gen_range(0. ..300.));
+            w.insert(Circle { center, radius: rng.gen_range(1. ..6.), layer: Layer::new((i % 2) as u8) });
+        }
+        let bodies = LayerMask::new(vec![Layer::new(0)]);
+        let on_bodies = |circle: &Circle| circle.layer == Layer::new(0);
+        for _ in 0..20 {
+            let pos = Vector2::new(rng.gen_range(-300. ..500.), rng.gen_range(-100. ..400.));
+            let query = Circle { center: pos, radius: rng.gen_range(0. ..100.), layer: Layer::new(0) };
+            let mut touching: Vec<_> = w.circles().iter().filter(|(_, c)| on_bodies(c) && query.intersects(c)).map(|(&k, _)| k).collect();
+            touching.sort();
+            assert_eq!(w.touching(query.center, query.radius, bodies), touching);
+
+            let rect = Rectangle::new(pos.x, pos.y, rng.gen_range(0. ..200.), rng.gen_range(0. ..200.));
+            let inside = |p: Vector2| p.x >= rect.x && p.x <= rect.x + rect.width && p.y >= rect.y && p.y <= rect.y + rect.height;
+            let mut in_rect: Vec<_> = w.circles().iter().filter(|(_, c)| on_bodies(c) && inside(c.center)).map(|(&k, _)| k).collect();
+            in_rect.sort();
+            assert_eq!(w.centers_in(rect, bodies), in_rect);
+
+            let count = rng.gen_range(0..200);
+            let mut nearest: Vec<_> = w.circles().iter().filter(|(_, c)| on_bodies(c)).map(|(&k, c)| ((c.center - pos).length(), k)).collect();
+            nearest.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+            let nearest: Vec<_> = nearest.into_iter().take(count).map(|(_, k)| k).collect();
+            assert_eq!(w.nearest(pos, count, |_, c| on_bodies(c)), nearest);
+        }
+    }
 }
 
 pub mod prelude {
@@ -0,0 +1,98 @@
+//! Where the window opens, such as to run the world as an ambient
+//! display on a second screen.
+//!
+//! `--monitor <n>` opens the window on a monitor counted from 1,
+//! centered on it unless `--window-position <x,y>` places its corner
+//! that far from the monitor's, and `--on-top` keeps the window above
+//! the others. The monitor the window is on when it closes is saved to
+//! `PATH`, and later launches open on it unless given another one.
+//! Deleting the file, or unplugging the monitor, opens on the primary
+//! monitor again.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let monitor = options.monitor.or(placement::load(placement::PATH)?);
+//! //  the window is 1300 by 680, centered on the second monitor
+//! let corner = placement::corner(&monitors, 1, None, (1300, 680));
+//! ```
+
+use std::{fs, io};
+
+use raylib::prelude::{Rectangle, Vector2};
+
+/// Where the monitor is kept between launches.
+pub const PATH: &str = "window.txt";
+
+/// The monitor saved when the window last closed, counted from 0.
+pub fn load(path: &str) -> Result<Option<usize>, String> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path, e)),
+    };
+    let mut monitor = None;
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+        let (key, value) = line.split_once('=').ok_or_else(|| format!("{}: expected `key = value`, got `{}`", path, line))?;
+        let value = value.trim();
+        match key.trim() {
+            "monitor" => {
+                let number: usize = value.parse().ok().filter(|&number| number > 0)
+                    .ok_or_else(|| format!("{}: invalid monitor `{}`", path, value))?;
+                monitor = Some(number - 1);
+            },
+            key => return Err(format!("{}: unknown key `{}`", path, key)),
+        }
+    }
+    Ok(monitor)
+}
+
+pub fn save(path: &str, monitor: usize) -> io::Result<()> {
+    fs::write(path, format!("# the monitor the window was last on, delete to open on the primary one\nmonitor = {}\n", monitor + 1))
+}
+
+/// Parse a position like `100,50`.
+pub fn parse_position(text: &str) -> Result<(i32, i32), String> {
+    let (x, y) = text.split_once(',').ok_or_else(|| format!("expected `x,y`, got `{}`", text))?;
+    let number = |value: &str| value.trim().parse().map_err(|_| format!("invalid coordinate `{}`", value));
+    Ok((number(x)?, number(y)?))
+}
+
+/// Where the corner of a window of some size goes on a monitor, by
+/// its place among the monitors' rectangles, at a position from the
+/// monitor's corner or centered, if there is such a monitor.
+pub fn corner(monitors: &[Rectangle], monitor: usize, position: Option<(i32, i32)>, (width, height): (i32, i32)) -> Option<(i32, i32)> {
+    let rect = monitors.get(monitor)?;
+    let (x, y) = position.unwrap_or(((rect.width as i32 - width) / 2, (rect.height as i32 - height) / 2));
+    Some((rect.x as i32 + x, rect.y as i32 + y))
+}
+
+/// The monitor a point on the desktop is on, by its place among the
+/// monitors' rectangles.
+pub fn monitor_at(monitors: &[Rectangle], point: Vector2) -> Option<usize> {
+    monitors.iter().position(|rect| rect.check_collision_point_rec(point))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_placement() {
+        let monitors = [Rectangle::new(0., 0., 1920., 1080.), Rectangle::new(1920., 0., 1280., 1024.)];
+        assert_eq!(corner(&monitors, 1, None, (1000, 600)), Some((2060, 212)));
+        assert_eq!(corner(&monitors, 1, Some((10, 20)), (1000, 600)), Some((1930, 20)));
+        assert_eq!(corner(&monitors, 2, None, (1000, 600)), None);
+        assert_eq!(monitor_at(&monitors, Vector2::new(2500., 500.)), Some(1));
+        assert_eq!(monitor_at(&monitors, Vector2::new(-5., 500.)), None);
+        assert_eq!(parse_position("100, -50"), Ok((100, -50)));
+        assert!(parse_position("100").is_err());
+
+        let path = std::env::temp_dir().join(format!("blobs-window-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(load(path), Ok(None));
+        save(path, 1).unwrap();
+        assert_eq!(load(path), Ok(Some(1)));
+        fs::remove_file(path).unwrap();
+    }
+}
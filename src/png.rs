@@ -0,0 +1,123 @@
+//! A minimal encoder for PNG images, for pictures written without a
+//! window, such as the graphs of a run report.
+//!
+//! Pixels are written as 8 bit RGB, unfiltered and stored in
+//! uncompressed deflate blocks, so the encoder needs no compressor.
+//! Files are larger than those of an image editor, but graphs of a few
+//! colors are small anyway, and any viewer opens them.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::png;
+//! # use raylib::prelude::Color;
+//! # let mut file = Vec::new();
+//! let pixels = vec![Color::WHITE; 640 * 240];
+//! png::write(&mut file, 640, 240, &pixels)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{self, Write};
+
+use raylib::prelude::Color;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// The most bytes a stored deflate block holds.
+const BLOCK: usize = 0xffff;
+
+/// The CRC-32 of some bytes, as PNG chunks end with.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// The Adler-32 checksum of some bytes, as zlib streams end with.
+fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in bytes {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    b << 16 | a
+}
+
+/// A zlib stream of stored blocks holding some bytes.
+fn zlib(bytes: &[u8]) -> Vec<u8> {
+    //  deflate with a 32K window and no dictionary
+    let mut ret = vec![0x78, 0x01];
+    let mut blocks = bytes.chunks(BLOCK).peekable();
+    if blocks.peek().is_none() {
+        ret.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        ret.push(last as u8);
+        ret.extend_from_slice(&len.to_le_bytes());
+        ret.extend_from_slice(&(!len).to_le_bytes());
+        ret.extend_from_slice(block);
+    }
+    ret.extend_from_slice(&adler32(bytes).to_be_bytes());
+    ret
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    let mut checked = kind.to_vec();
+    checked.extend_from_slice(data);
+    out.write_all(&checked)?;
+    out.write_all(&crc32(&checked).to_be_bytes())
+}
+
+/// Write pixels given row by row as an image. Their alpha is dropped.
+pub fn write<W: Write>(out: &mut W, width: u32, height: u32, pixels: &[Color]) -> io::Result<()> {
+    assert_eq!(pixels.len(), width as usize * height as usize);
+    out.write_all(&SIGNATURE)?;
+    let mut header = vec![];
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    //  8 bits a channel, RGB, and the only compression, filtering and no interlacing
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(out, b"IHDR", &header)?;
+    let mut rows = Vec::with_capacity(height as usize * (1 + 3 * width as usize));
+    for row in pixels.chunks(width.max(1) as usize) {
+        //  each row starts with its filter, none
+        rows.push(0);
+        for color in row {
+            rows.extend_from_slice(&[color.r, color.g, color.b]);
+        }
+    }
+    write_chunk(out, b"IDAT", &zlib(&rows))?;
+    write_chunk(out, b"IEND", &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_png() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        let mut out = vec![];
+        write(&mut out, 2, 1, &[Color::new(255, 0, 0, 255), Color::new(0, 0, 255, 128)]).unwrap();
+        assert_eq!(&out[..8], &SIGNATURE);
+        assert_eq!(&out[12..16], b"IHDR");
+        assert_eq!(&out[out.len() - 12..], &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+        //  the one stored block holds the filter and the channels of the row
+        let stream = zlib(&[0, 255, 0, 0, 0, 0, 255]);
+        assert_eq!(&stream[2..7], &[1, 7, 0, 0xf8, 0xff]);
+        assert!(out.windows(stream.len()).any(|window| window == stream.as_slice()));
+
+        //  larger images take more than one block, only the last marked so
+        let stream = zlib(&vec![0; BLOCK + 1]);
+        assert_eq!((stream[2], stream[7 + BLOCK]), (0, 1));
+    }
+}
@@ -0,0 +1,171 @@
+//! A low-power mode for laptops, to run longer on battery.
+//!
+//! In low-power mode the window draws at most `FPS` frames a second,
+//! worlds tick at most `TICK_RATE` times a simulated second, taking
+//! longer ticks to keep the same pace, and post-processing and the
+//! rising energy numbers are off. While the window is unfocused or
+//! minimized the process sleeps `BACKGROUND_SLEEP` a frame and the
+//! worlds stand still.
+//!
+//! The mode is on or off with `--low-power`, or by default follows
+//! the power source where it can be told, turning on while running on
+//! battery. Lockstep sessions and recorded or replayed input keep
+//! their tick rate and keep running in the background, as the peer or
+//! the replay depend on it.
+//!
+//! Outside low-power mode, what happens while the window is unfocused
+//! or minimized is given with `--unfocused`: the worlds keep running
+//! and being drawn (`keep`, the default), stand still (`pause`), or
+//! keep running without being drawn while the process sleeps
+//! `BACKGROUND_SLEEP` a frame (`background`).
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut low_power = LowPower::new(Setting::Auto);
+//! if let Some(active) = low_power.update(time::Instant::now()) {
+//!     window::set_target_fps(if active { power::FPS } else { 0 });
+//! }
+//! ```
+
+use std::{fs, path::Path, time};
+
+/// The most frames a second drawn in low-power mode.
+pub const FPS: u32 = 30;
+/// The most ticks a simulated second in low-power mode.
+pub const TICK_RATE: u32 = 30;
+/// How long to sleep a frame while the window is in the background.
+pub const BACKGROUND_SLEEP: time::Duration = time::Duration::from_millis(200);
+/// How often the power source is looked at.
+const CHECK_PERIOD: time::Duration = time::Duration::from_secs(10);
+/// Where Linux lists power supplies.
+const SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+/// When low-power mode is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Setting {
+    /// While running on battery.
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+impl Setting {
+    pub const NAMES: [&'static str; 3] = ["auto", "on", "off"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::Auto),
+            "on" => Some(Self::On),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+}
+
+/// What happens while the window is unfocused or minimized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Unfocused {
+    /// The worlds keep running and being drawn.
+    #[default]
+    Keep,
+    /// The worlds stand still.
+    Pause,
+    /// The worlds keep running without being drawn, at fewer frames.
+    Background,
+}
+
+impl Unfocused {
+    pub const NAMES: [&'static str; 3] = ["keep", "pause", "background"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "keep" => Some(Self::Keep),
+            "pause" => Some(Self::Pause),
+            "background" => Some(Self::Background),
+            _ => None,
+        }
+    }
+}
+
+/// Whether the machine runs on battery, if it can be told.
+pub fn on_battery() -> Option<bool> {
+    on_battery_in(Path::new(SUPPLY_DIR))
+}
+
+/// Whether the supplies listed in a directory like `SUPPLY_DIR` run
+/// the machine on battery: none is plugged in and a battery discharges.
+fn on_battery_in(dir: &Path) -> Option<bool> {
+    let read = |path: &Path, name: &str| fs::read_to_string(path.join(name)).ok().map(|text| text.trim().to_string());
+    let mut discharging = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        match read(&path, "type").as_deref() {
+            Some("Mains" | "USB") if read(&path, "online").as_deref() == Some("1") => return Some(false),
+            Some("Battery") => {
+                let status = read(&path, "status");
+                discharging = Some(discharging.unwrap_or(false) || status.as_deref() == Some("Discharging"));
+            },
+            _ => (),
+        }
+    }
+    discharging
+}
+
+/// Follows whether low-power mode is on.
+#[derive(Debug, Clone)]
+pub struct LowPower {
+    setting: Setting,
+    active: bool,
+    //  when the power source was last looked at, if it was
+    checked: Option<time::Instant>,
+}
+
+impl LowPower {
+    pub fn new(setting: Setting) -> Self {
+        Self { setting, active: false, checked: None }
+    }
+
+    pub fn active(&self) -> bool { self.active }
+
+    /// Look at the power source if it is time to, returning whether
+    /// the mode is on if that changed.
+    pub fn update(&mut self, now: time::Instant) -> Option<bool> {
+        if self.checked.is_some_and(|checked| now.duration_since(checked) < CHECK_PERIOD) {
+            return None;
+        }
+        self.checked = Some(now);
+        let active = match self.setting {
+            Setting::Auto => on_battery().unwrap_or(false),
+            Setting::On => true,
+            Setting::Off => false,
+        };
+        let changed = active != self.active;
+        self.active = active;
+        Some(active).filter(|_| changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_battery() {
+        let dir = std::env::temp_dir().join(format!("blobs-power-{}", std::process::id()));
+        let supply = |name: &str, fields: &[(&str, &str)]| {
+            fs::create_dir_all(dir.join(name)).unwrap();
+            for (field, value) in fields {
+                fs::write(dir.join(name).join(field), format!("{}\n", value)).unwrap();
+            }
+        };
+        assert_eq!(on_battery_in(&dir), None);
+        supply("BAT0", &[("type", "Battery"), ("status", "Discharging")]);
+        supply("AC", &[("type", "Mains"), ("online", "0")]);
+        assert_eq!(on_battery_in(&dir), Some(true));
+        supply("AC", &[("online", "1")]);
+        assert_eq!(on_battery_in(&dir), Some(false));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+//! Where the time of a tick goes, phase by phase, for the `bench`
+//! subcommand and anyone else who asks the simulation to keep it.
+//!
+//! A profiled simulation times each phase of its ticks with the wall
+//! clock and adds it up over the ticks, in the order the phases run:
+//!
+//! * `hooks` the code embedders run before and after a tick
+//! * `collisions` finding the bodies that touch
+//! * `brains` perception and the decisions of the blobs that think
+//! * `forces` force fields and auras
+//! * `physics` movement and eating, over every substep
+//! * `lifecycle` noises, spoiling, deaths and children
+//! * `upkeep` statistics, audits, invariants, drones and traces
+//!
+//! Timing is kept out of the state of the world, so profiling changes
+//! nothing of how it runs.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! sim.set_profiling(true);
+//! sim.step();
+//! for (phase, time) in sim.profile().unwrap().phases() {
+//!     println!("{} {:?}", phase, time);
+//! }
+//! ```
+
+use std::time::{Duration, Instant};
+
+/// The time spent in each phase of the ticks profiled, see the module.
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    ticks: u64,
+    phases: Vec<(&'static str, Duration)>,
+    //  when the phase being timed began
+    lap: Option<Instant>,
+}
+
+impl Profile {
+    pub fn new() -> Self { Self::default() }
+
+    /// Start timing a tick.
+    pub fn start(&mut self) {
+        self.lap = Some(Instant::now());
+    }
+
+    /// Count the time since the last phase ended toward a phase.
+    pub fn lap(&mut self, phase: &'static str) {
+        let now = Instant::now();
+        if let Some(from) = self.lap {
+            match self.phases.iter_mut().find(|(name, _)| *name == phase) {
+                Some((_, time)) => *time += now - from,
+                None => self.phases.push((phase, now - from)),
+            }
+        }
+        self.lap = Some(now);
+    }
+
+    /// Count the last phase of a tick and stop timing.
+    pub fn end(&mut self, phase: &'static str) {
+        self.lap(phase);
+        self.lap = None;
+        self.ticks += 1;
+    }
+
+    pub fn ticks(&self) -> u64 { self.ticks }
+
+    /// The phases in the order they first ran, with their time over all ticks.
+    pub fn phases(&self) -> &[(&'static str, Duration)] { &self.phases }
+
+    pub fn total(&self) -> Duration {
+        self.phases.iter().map(|&(_, time)| time).sum()
+    }
+}
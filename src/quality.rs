@@ -0,0 +1,146 @@
+//! Lowering the quality of what is drawn to hold a frame rate.
+//!
+//! The time each frame takes to simulate and draw is averaged, and
+//! while the average is over the budget of the target frame rate
+//! the next tier drops another expensive visual:
+//!
+//! * `NoEffects` skips the post-processing pass, bloom, vignette and lens
+//! * `NoLabels` also skips the names and ages over blobs
+//! * `NoHeatmap` also hides the heatmap layer, such as the density overlay
+//!
+//! Once frames take well under the budget again, the visuals come
+//! back one tier at a time. Lowering waits `LOWER_FRAMES` after a
+//! change and raising waits longer, `RAISE_FRAMES`, so that a world
+//! just fast enough at a tier does not flicker between two.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::quality::{AutoQuality, Tier};
+//! # let frame_start = std::time::Instant::now();
+//! let mut quality = AutoQuality::new(60, Tier::Full);
+//! if let Some(tier) = quality.update(frame_start.elapsed().as_secs_f32()) {
+//!     println!("quality: {}", tier.name());
+//! }
+//! ```
+
+/// How much of what is drawn is drawn, from the most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Tier {
+    Full,
+    NoEffects,
+    NoLabels,
+    NoHeatmap,
+}
+
+impl Tier {
+    pub const ALL: [Self; 4] = [Self::Full, Self::NoEffects, Self::NoLabels, Self::NoHeatmap];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::NoEffects => "no effects",
+            Self::NoLabels => "no labels",
+            Self::NoHeatmap => "no heatmap",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().cloned().find(|tier| tier.name() == name)
+    }
+
+    pub fn post_processing(self) -> bool { self < Self::NoEffects }
+
+    pub fn labels(self) -> bool { self < Self::NoLabels }
+
+    pub fn heatmap(self) -> bool { self < Self::NoHeatmap }
+
+    fn lower(self) -> Option<Self> {
+        match self {
+            Self::Full => Some(Self::NoEffects),
+            Self::NoEffects => Some(Self::NoLabels),
+            Self::NoLabels => Some(Self::NoHeatmap),
+            Self::NoHeatmap => None,
+        }
+    }
+
+    fn raise(self) -> Option<Self> {
+        match self {
+            Self::Full => None,
+            Self::NoEffects => Some(Self::Full),
+            Self::NoLabels => Some(Self::NoEffects),
+            Self::NoHeatmap => Some(Self::NoLabels),
+        }
+    }
+}
+
+/// The target frame rate, unless given.
+pub const DEFAULT_TARGET_FPS: u32 = 60;
+/// Frames after a change before the quality is lowered further.
+const LOWER_FRAMES: u32 = 30;
+/// Frames after a change before the quality is raised.
+const RAISE_FRAMES: u32 = 300;
+/// The part of the budget frames must take under to raise the quality.
+const HEADROOM: f32 = 0.6;
+/// How much of the average each new frame makes.
+const WEIGHT: f32 = 0.05;
+
+#[derive(Debug, Clone)]
+pub struct AutoQuality {
+    //  seconds a frame may take
+    budget: f32,
+    tier: Tier,
+    mean: f32,
+    //  since the last change
+    frames: u32,
+}
+
+impl AutoQuality {
+    /// Hold a frame rate, starting from a tier.
+    pub fn new(target_fps: u32, tier: Tier) -> Self {
+        let budget = 1. / target_fps as f32;
+        Self { budget, tier, mean: budget * HEADROOM, frames: 0 }
+    }
+
+    pub fn tier(&self) -> Tier { self.tier }
+
+    /// Account for the seconds a frame took, returning the new tier if it changes.
+    pub fn update(&mut self, seconds: f32) -> Option<Tier> {
+        self.mean += WEIGHT * (seconds - self.mean);
+        self.frames += 1;
+        let next = if self.mean > self.budget && self.frames >= LOWER_FRAMES {
+            self.tier.lower()
+        } else if self.mean < HEADROOM * self.budget && self.frames >= RAISE_FRAMES {
+            self.tier.raise()
+        } else {
+            None
+        }?;
+        self.tier = next;
+        self.frames = 0;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_quality() {
+        let mut quality = AutoQuality::new(50, Tier::Full);
+        let run = |quality: &mut AutoQuality, frames: u32, seconds: f32| -> Vec<Tier> {
+            (0..frames).filter_map(|_| quality.update(seconds)).collect()
+        };
+        assert_eq!(run(&mut quality, 1000, 0.01), vec![]);
+        //  slow frames drop a tier at a time, down to the lowest
+        assert_eq!(run(&mut quality, 200, 0.05), vec![Tier::NoEffects, Tier::NoLabels, Tier::NoHeatmap]);
+        assert!(!quality.tier().heatmap() && !quality.tier().labels());
+        //  fast frames bring them back, more slowly
+        assert_eq!(run(&mut quality, 400, 0.005), vec![Tier::NoLabels]);
+        assert_eq!(run(&mut quality, 800, 0.005), vec![Tier::NoEffects, Tier::Full]);
+        assert!(quality.tier().post_processing());
+        //  frames within the budget but without headroom change nothing
+        assert_eq!(run(&mut quality, 1000, 0.015), vec![]);
+        assert_eq!(Tier::from_name(Tier::NoLabels.name()), Some(Tier::NoLabels));
+    }
+}
@@ -0,0 +1,155 @@
+//! A soft real-time mode for exhibitions, where the window never stalls.
+//!
+//! Ticking may take `TICK_SHARE` of each frame at the target frame
+//! rate. Ticks left over once that time is up are dropped, slowing
+//! the world down rather than the window. While ticking takes longer
+//! than its share on average, the simulation does less work, one
+//! level at a time:
+//!
+//! * `SlowBrains` has brains decide `BRAIN_FACTOR` times less often
+//! * `NoSpawns` also stops random blobs from spawning
+//!
+//! Levels come back as `quality` tiers do, more slowly than they go.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut governor = Governor::new(60);
+//! let deadline = governor.deadline(frame_start);
+//! let stepped = step_worlds(&mut worlds, None, &mut pending_time, Some(deadline), &names, &species);
+//! if let Some(level) = governor.update(frame_start.elapsed().as_secs_f32(), stepped == Stepped::OutOfTime) {
+//!     println!("degraded: {}", level.name());
+//! }
+//! ```
+
+use std::time;
+
+/// How much less often brains decide at `SlowBrains`.
+pub const BRAIN_FACTOR: u32 = 4;
+/// The part of a frame ticking may take.
+const TICK_SHARE: f32 = 0.5;
+/// Frames after a change before less work is done.
+const LOWER_FRAMES: u32 = 30;
+/// Frames after a change before more work is done.
+const RAISE_FRAMES: u32 = 300;
+/// The part of the share ticking must take under to do more work.
+const HEADROOM: f32 = 0.6;
+/// How much of the average each new frame makes.
+const WEIGHT: f32 = 0.05;
+/// Frames dropped ticks are shown for.
+const DROPPED_FRAMES: u32 = 60;
+
+/// How much work the simulation leaves out, from none.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Normal,
+    SlowBrains,
+    NoSpawns,
+}
+
+impl Level {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::SlowBrains => "slower brains",
+            Self::NoSpawns => "no spawns",
+        }
+    }
+
+    pub fn slow_brains(self) -> bool { self >= Self::SlowBrains }
+
+    pub fn spawns(self) -> bool { self < Self::NoSpawns }
+
+    fn lower(self) -> Option<Self> {
+        match self {
+            Self::Normal => Some(Self::SlowBrains),
+            Self::SlowBrains => Some(Self::NoSpawns),
+            Self::NoSpawns => None,
+        }
+    }
+
+    fn raise(self) -> Option<Self> {
+        match self {
+            Self::Normal => None,
+            Self::SlowBrains => Some(Self::Normal),
+            Self::NoSpawns => Some(Self::SlowBrains),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Governor {
+    //  seconds ticking may take in a frame
+    budget: f32,
+    level: Level,
+    mean: f32,
+    //  since the last change
+    frames: u32,
+    //  since ticks were last dropped
+    since_dropped: u32,
+}
+
+impl Governor {
+    pub fn new(target_fps: u32) -> Self {
+        let budget = TICK_SHARE / target_fps as f32;
+        Self { budget, level: Level::Normal, mean: 0., frames: 0, since_dropped: DROPPED_FRAMES }
+    }
+
+    /// When ticking must stop in a frame that started at some instant.
+    pub fn deadline(&self, frame_start: time::Instant) -> time::Instant {
+        frame_start + time::Duration::from_secs_f32(self.budget)
+    }
+
+    /// What is left out, if anything, to show while it is.
+    pub fn degraded(&self) -> Option<&'static str> {
+        if self.level != Level::Normal {
+            Some(self.level.name())
+        } else if self.since_dropped < DROPPED_FRAMES {
+            Some("dropping ticks")
+        } else {
+            None
+        }
+    }
+
+    /// Account for the seconds ticking took in a frame and whether
+    /// ticks were dropped, returning the new level if it changes.
+    pub fn update(&mut self, seconds: f32, dropped: bool) -> Option<Level> {
+        self.since_dropped = if dropped { 0 } else { self.since_dropped.saturating_add(1) };
+        //  the dropped ticks would have taken at least as long again
+        let seconds = if dropped { 2. * seconds } else { seconds };
+        self.mean += WEIGHT * (seconds - self.mean);
+        self.frames += 1;
+        let next = if self.mean > self.budget && self.frames >= LOWER_FRAMES {
+            self.level.lower()
+        } else if self.mean < HEADROOM * self.budget && self.frames >= RAISE_FRAMES {
+            self.level.raise()
+        } else {
+            None
+        }?;
+        self.level = next;
+        self.frames = 0;
+        Some(next)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_governor() {
+        //  ticking may take 10ms of each frame
+        let mut governor = Governor::new(50);
+        let mut run = |frames: u32, seconds: f32, dropped: bool| -> Vec<Level> {
+            (0..frames).filter_map(|_| governor.update(seconds, dropped)).collect()
+        };
+        assert_eq!(run(1000, 0.002, false), vec![]);
+        assert_eq!(run(100, 0.01, true), vec![Level::SlowBrains, Level::NoSpawns]);
+        assert_eq!(run(400, 0.002, false), vec![Level::SlowBrains]);
+        assert_eq!(run(400, 0.002, false), vec![Level::Normal]);
+        assert_eq!(run(1000, 0.008, false), vec![]);
+        assert_eq!(governor.degraded(), None);
+        governor.update(0.008, true);
+        assert_eq!(governor.degraded(), Some("dropping ticks"));
+    }
+}
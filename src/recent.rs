@@ -0,0 +1,93 @@
+//! The files worlds were last saved to or loaded from, kept between
+//! runs so the next save or load starts from them.
+//!
+//! `F10` saves the first world to the latest of them, and `F11` loads
+//! it, or `Simulation::SAVE_PATH` if there is none yet. Shift and `F10`
+//! or `F11` ask for the file instead, starting from the latest and
+//! listing the others. Every file saved or loaded becomes the latest,
+//! and the list is written to `PATH` a line each, latest first, so it
+//! can be edited by hand.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::{recent::{self, Recent}, simulation::Simulation};
+//! let mut recent = Recent::load(recent::PATH)?;
+//! let path = recent.latest().unwrap_or(Simulation::SAVE_PATH).to_string();
+//! recent.push(&path);
+//! recent.save(recent::PATH)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fs, io};
+
+/// Where the recent files are kept.
+pub const PATH: &str = "recent.txt";
+/// How many files are kept.
+pub const LENGTH: usize = 8;
+
+/// Recent files, latest first, see the module.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Recent {
+    paths: Vec<String>,
+}
+
+impl Recent {
+    /// The files kept in a file, of which there are none while it is missing.
+    pub fn load(path: &str) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Self::parse(&text)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut ret = Self::default();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()).rev() {
+            ret.push(line);
+        }
+        ret
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.paths.iter().map(|path| format!("{}\n", path)).collect::<String>())
+    }
+
+    /// Make a file the latest, keeping it once and forgetting the oldest.
+    pub fn push(&mut self, path: &str) {
+        self.paths.retain(|kept| kept != path);
+        self.paths.insert(0, path.to_string());
+        self.paths.truncate(LENGTH);
+    }
+
+    pub fn latest(&self) -> Option<&str> {
+        self.paths.first().map(String::as_str)
+    }
+
+    pub fn paths(&self) -> &[String] { &self.paths }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recent() {
+        let mut recent = Recent::parse("b.save\n\na.save\n");
+        assert_eq!(recent.latest(), Some("b.save"));
+        recent.push("a.save");
+        assert_eq!(recent.paths(), ["a.save", "b.save"]);
+        for i in 0..LENGTH {
+            recent.push(&format!("{}.save", i));
+        }
+        assert_eq!((recent.paths().len(), recent.latest()), (LENGTH, Some("7.save")));
+
+        let path = std::env::temp_dir().join(format!("blobs-recent-{}", std::process::id()));
+        let path = path.to_str().unwrap();
+        assert_eq!(Recent::load(path).unwrap(), Recent::default());
+        recent.save(path).unwrap();
+        assert_eq!(Recent::load(path).unwrap(), recent);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,114 @@
+//! Keeps the last seconds drawn so they can be saved after the fact.
+//!
+//! Every few frames the window is captured, scaled down and
+//! compressed into a ring buffer, so saving an animated GIF of
+//! something that just happened needs no recording to be started
+//! in advance.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut recorder = Recorder::new(Recorder::DEFAULT_SECONDS);
+//! //  after drawing each frame
+//! recorder.capture(&draw);
+//! //  when asked to
+//! recorder.save("blobs.gif")?;
+//! ```
+
+use std::{collections::VecDeque, fs, io::{self, BufWriter}};
+
+use raylib::prelude::*;
+
+use crate::gif;
+
+pub struct Recorder {
+    frames: VecDeque<gif::Frame>,
+    capacity: usize,
+    //  when the last frame was captured, in seconds since the window opened
+    last_capture: Option<f64>,
+}
+
+impl Recorder {
+    /// Captured frames per second.
+    pub const FRAME_RATE: u32 = 10;
+    /// How many times smaller than the window the frames are.
+    pub const SCALE: usize = 4;
+    pub const DEFAULT_SECONDS: u32 = 20;
+    pub const MIN_SECONDS: u32 = 10;
+    pub const MAX_SECONDS: u32 = 30;
+
+    /// A recorder that keeps the last `seconds` seconds.
+    pub fn new(seconds: u32) -> Self {
+        let capacity = (seconds * Self::FRAME_RATE) as usize;
+        Self { frames: VecDeque::with_capacity(capacity), capacity, last_capture: None }
+    }
+
+    /// The length of what would be saved, in seconds.
+    pub fn seconds(&self) -> f32 {
+        self.frames.len() as f32 / Self::FRAME_RATE as f32
+    }
+
+    /// The estimated bytes held by the kept frames.
+    pub fn bytes(&self) -> usize {
+        self.frames.iter().map(gif::Frame::bytes).sum()
+    }
+
+    /// Drop the oldest frames until at most `bytes` are held.
+    pub fn trim(&mut self, bytes: usize) {
+        let mut held = self.bytes();
+        while held > bytes {
+            match self.frames.pop_front() {
+                Some(frame) => held -= frame.bytes(),
+                None => break,
+            }
+        }
+    }
+
+    /// Capture what has been drawn so far this frame, if a frame is due.
+    pub fn capture(&mut self, handle: &RaylibHandle) {
+        let now = handle.get_time();
+        if self.last_capture.is_some_and(|last| now - last < 1. / Self::FRAME_RATE as f64) {
+            return;
+        }
+        self.last_capture = Some(now);
+
+        //  the screen is read back upright, unlike render textures
+        let image = unsafe { Image::from_raw(raylib::ffi::GetScreenData()) };
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let (pixels, width, height) = downscale(&image.get_image_data(), width, height, Self::SCALE);
+        if width == 0 || height == 0 { return; }
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(gif::Frame::encode(width as u16, height as u16, &pixels));
+    }
+
+    /// Write the kept frames to a file as an animated GIF.
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let frames: Vec<_> = self.frames.iter().cloned().collect();
+        let mut out = BufWriter::new(fs::File::create(path)?);
+        gif::write(&mut out, &frames, (100 / Self::FRAME_RATE) as u16)
+    }
+}
+
+/// Shrink an image by averaging each square of `scale` by `scale` pixels.
+fn downscale(pixels: &[Color], width: usize, height: usize, scale: usize) -> (Vec<Color>, usize, usize) {
+    let (new_width, new_height) = (width / scale, height / scale);
+    let count = (scale * scale) as u32;
+    let mut ret = Vec::with_capacity(new_width * new_height);
+    for y in 0..new_height {
+        for x in 0..new_width {
+            let mut sum = [0u32; 3];
+            for dy in 0..scale {
+                let row = (y * scale + dy) * width + x * scale;
+                for color in &pixels[row..row + scale] {
+                    sum[0] += color.r as u32;
+                    sum[1] += color.g as u32;
+                    sum[2] += color.b as u32;
+                }
+            }
+            ret.push(Color::new((sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8, 255));
+        }
+    }
+    (ret, new_width, new_height)
+}
@@ -0,0 +1,189 @@
+//! The records of a world, kept over its whole run and saved with it.
+//!
+//! A world keeps four records: its oldest blob ever, its largest
+//! population, its longest lineage, as the most generations a blob
+//! came after the first, and the most children one blob had. Each
+//! holds its value, who set it if a blob did and the tick it was last
+//! raised at.
+//!
+//! Records are raised after every tick. One that is raised after it
+//! stood for `STOOD` ticks is broken, and logged as `Event::Record`
+//! for the game to announce; one raised tick after tick, such as the
+//! age of the oldest blob while it lives or a population growing, is
+//! only broken once it had stood. The records first set are not
+//! broken either, there being nothing to break.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::records::Records;
+//! # let tick = sim.clock().tick();
+//! let mut records = Records::default();
+//! for record in records.update(tick, sim.blobs().map(|(_, blob)| blob)) {
+//!     println!("{}", record.describe());
+//! }
+//! ```
+
+use crate::simulation::Blob;
+
+/// How many ticks a record stands before raising it breaks it.
+pub const STOOD: u64 = 600;
+
+/// What a record is of, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Oldest,
+    Population,
+    Lineage,
+    Offspring,
+}
+
+impl Kind {
+    pub const ALL: [Self; 4] = [Self::Oldest, Self::Population, Self::Lineage, Self::Offspring];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Oldest => "oldest",
+            Self::Population => "population",
+            Self::Lineage => "lineage",
+            Self::Offspring => "offspring",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Oldest => "Oldest blob",
+            Self::Population => "Largest population",
+            Self::Lineage => "Longest lineage",
+            Self::Offspring => "Most offspring",
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&kind| kind == self).unwrap()
+    }
+}
+
+/// The best a world did at something, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub kind: Kind,
+    pub value: f32,
+    /// The name of the blob that set it, for records a blob sets.
+    pub holder: Option<String>,
+    /// The tick it was last raised at.
+    pub tick: u64,
+}
+
+impl Record {
+    /// The record in words, as in `Oldest blob: Ada, 412 s`.
+    pub fn describe(&self) -> String {
+        let value = match self.kind {
+            Kind::Oldest => format!("{:.0} s", self.value),
+            Kind::Population => format!("{} blobs", self.value),
+            Kind::Lineage => format!("{} generations", self.value),
+            Kind::Offspring => format!("{} children", self.value),
+        };
+        match (self.kind, &self.holder) {
+            (Kind::Population, _) => format!("{}: {}", self.kind.title(), value),
+            (_, holder) => format!("{}: {}, {}", self.kind.title(), holder.as_deref().unwrap_or("a nameless blob"), value),
+        }
+    }
+}
+
+/// The records of a world, see the module.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Records {
+    records: [Option<Record>; 4],
+}
+
+impl Records {
+    pub fn get(&self, kind: Kind) -> Option<&Record> { self.records[kind.index()].as_ref() }
+
+    /// The records set so far, in the order of `Kind::ALL`.
+    pub fn iter(&self) -> impl Iterator<Item=&Record> { self.records.iter().flatten() }
+
+    /// Put back a record, as a world is loaded.
+    pub fn restore(&mut self, record: Record) {
+        let index = record.kind.index();
+        self.records[index] = Some(record);
+    }
+
+    /// Raise the records the blobs of a world beat after a tick,
+    /// returning those broken.
+    pub fn update<'a>(&mut self, tick: u64, blobs: impl Iterator<Item=&'a Blob>) -> Vec<Record> {
+        let mut best: [Option<(f32, Option<&String>)>; 4] = Default::default();
+        let mut population = 0;
+        for blob in blobs {
+            population += 1;
+            for (kind, value) in [(Kind::Oldest, blob.alive_time), (Kind::Lineage, blob.generation as f32), (Kind::Offspring, blob.children as f32)] {
+                let best = &mut best[kind.index()];
+                if best.is_none_or(|(most, _)| value > most) {
+                    *best = Some((value, blob.name.as_ref()));
+                }
+            }
+        }
+        best[Kind::Population.index()] = Some((population as f32, None));
+
+        let mut ret = vec![];
+        for (kind, best) in Kind::ALL.iter().zip(best) {
+            let (value, holder) = match best {
+                Some(best) => best,
+                None => continue,
+            };
+            let record = &mut self.records[kind.index()];
+            let broken = match record {
+                Some(record) if value > record.value => tick >= record.tick + STOOD,
+                Some(_) => continue,
+                None if value > 0. => false,
+                None => continue,
+            };
+            *record = Some(Record { kind: *kind, value, holder: holder.cloned(), tick });
+            if broken {
+                ret.extend(record.clone());
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::*;
+    use crate::simulation::{BlobConfig, Simulation, SimulationConfig};
+
+    #[test]
+    fn test_records() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let ada = sim.insert_blob(BlobConfig { name: Some("Ada".to_string()), ..BlobConfig::new(Vector2::new(100., 100.)) });
+        sim.insert_blob(BlobConfig::new(Vector2::new(200., 200.)));
+        let mut records = Records::default();
+
+        //  the first records are set, not broken, and none is set of nothing
+        sim.get_blob_mut(ada).unwrap().alive_time = 10.;
+        assert!(records.update(0, sim.blobs().map(|(_, blob)| blob)).is_empty());
+        assert_eq!(records.get(Kind::Oldest).unwrap().describe(), "Oldest blob: Ada, 10 s");
+        assert_eq!(records.get(Kind::Population).unwrap().describe(), "Largest population: 2 blobs");
+        assert_eq!(records.get(Kind::Offspring), None);
+
+        //  raised right away, a record is only raised, and once it stood it is broken
+        sim.get_blob_mut(ada).unwrap().alive_time = 11.;
+        assert!(records.update(1, sim.blobs().map(|(_, blob)| blob)).is_empty());
+        assert_eq!(records.get(Kind::Oldest).unwrap().value, 11.);
+        sim.get_blob_mut(ada).unwrap().children = 3;
+        assert!(records.update(2, sim.blobs().map(|(_, blob)| blob)).is_empty());
+        sim.insert_blob(BlobConfig::new(Vector2::new(50., 50.)));
+        let broken = records.update(1 + STOOD, sim.blobs().map(|(_, blob)| blob));
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0].describe(), "Largest population: 3 blobs");
+        assert_eq!(records.iter().count(), 4);
+    }
+}
@@ -0,0 +1,191 @@
+//! Input recordings bundled with what they need to play back elsewhere.
+//!
+//! A recording alone keeps only the seed, so it plays out the same
+//! only on a machine set up like the one it was made on. A bundle,
+//! written when the recording path ends in `.blobreplay`, also keeps
+//! the world code of the setup, the parameter compared if any, the
+//! version of the program and the enabled mods, each with a hash of
+//! its content. It is a header followed by text compressed with
+//! `lzw`, with fields separated by tabs:
+//!
+//! ```text
+//! program    0.1.0+fa634ec
+//! world    blobs:AdIEAAAAAAAAAICiRAAAKkQ8AQEAoJkC9Q
+//! compare    food_rate=2
+//! mod    deep sea    5f2a9c01d3e4b677
+//! recording
+//! seed=1234
+//! frame time=2.5 mouse=300,200 wheel=0 button=pressed down= pressed=
+//! ```
+//!
+//! Before a bundle plays, `check` compares it with this program and
+//! its mods and explains any difference that would change the run.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let bundle = Bundle::read(&fs::read(path)?)?;
+//! bundle.check(&replay::fingerprints(&mods), &options.compare)?;
+//! let recording = input::read(&bundle.recording[..])?;
+//! ```
+
+use std::io::{self, Write};
+
+use crate::{lzw, math::Fnv, mods::Mod, runs, world_code::WorldCode};
+
+/// The extension of bundled replays.
+pub const EXTENSION: &str = ".blobreplay";
+/// What every bundle starts with, followed by the format version.
+const MAGIC: &[u8] = b"BLOBREPLAY";
+const VERSION: u8 = 1;
+
+/// A recording and everything it was recorded with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bundle {
+    /// The version of the program it was recorded with.
+    pub program: String,
+    /// The setup of the world, and the tick it was grown to before
+    /// the recording started.
+    pub world: WorldCode,
+    /// The parameter compared in a second world, if any.
+    pub compare: Option<(String, String)>,
+    /// The enabled mods, in load order, by name and fingerprint.
+    pub mods: Vec<(String, u64)>,
+    /// The recording as `input` writes it.
+    pub recording: Vec<u8>,
+}
+
+impl Bundle {
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let mut text = vec![];
+        writeln!(text, "program\t{}", self.program)?;
+        writeln!(text, "world\t{}", self.world.encode())?;
+        if let Some((name, value)) = &self.compare {
+            writeln!(text, "compare\t{}={}", name, value)?;
+        }
+        for (name, fingerprint) in &self.mods {
+            writeln!(text, "mod\t{}\t{:016x}", name, fingerprint)?;
+        }
+        writeln!(text, "recording")?;
+        text.extend_from_slice(&self.recording);
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+        out.write_all(&lzw::compress(&text))
+    }
+
+    /// Read a bundle, failing with what is wrong with it.
+    pub fn read(bytes: &[u8]) -> Result<Self, String> {
+        let rest = bytes.strip_prefix(MAGIC).ok_or("it is not a bundled replay")?;
+        let (&version, rest) = rest.split_first().ok_or("it is empty")?;
+        if version != VERSION {
+            return Err(format!("it was bundled in format {}, this version of blobs reads format {}", version, VERSION));
+        }
+        let text = lzw::decompress(rest).map_err(|e| format!("it is damaged: {}", e))?;
+        let split = text.windows(b"recording\n".len()).position(|window| window == b"recording\n")
+            .ok_or("it has no recording")?;
+        let header = std::str::from_utf8(&text[..split]).map_err(|_| "its header is not text")?;
+        let mut program = None;
+        let mut world = None;
+        let mut compare = None;
+        let mut mods = vec![];
+        for line in header.lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            match fields[..] {
+                ["program", version] => program = Some(version.to_string()),
+                ["world", code] => world = Some(WorldCode::decode(code)?),
+                ["compare", assignment] => {
+                    let (name, value) = assignment.split_once('=').ok_or_else(|| format!("invalid comparison `{}`", assignment))?;
+                    compare = Some((name.to_string(), value.to_string()));
+                },
+                ["mod", name, fingerprint] => {
+                    let fingerprint = u64::from_str_radix(fingerprint, 16).map_err(|_| format!("invalid fingerprint of mod `{}`", name))?;
+                    mods.push((name.to_string(), fingerprint));
+                },
+                _ => return Err(format!("unexpected `{}`", line)),
+            }
+        }
+        Ok(Self {
+            program: program.ok_or("it has no program version")?,
+            world: world.ok_or("it has no world")?,
+            compare,
+            mods,
+            recording: text[split + b"recording\n".len()..].to_vec(),
+        })
+    }
+
+    /// Check that the replay plays out here as it was recorded, with
+    /// the enabled mods and compared parameter of this run.
+    pub fn check(&self, mods: &[(String, u64)], compare: &Option<(String, String)>) -> Result<(), String> {
+        if self.program != runs::VERSION {
+            return Err(format!("it was recorded with blobs {}, this is blobs {}", self.program, runs::VERSION));
+        }
+        let names = |mods: &[(String, u64)], of: &[(String, u64)]| -> Vec<String> {
+            mods.iter().filter(|m| !of.contains(m)).map(|(name, _)| name.clone()).collect()
+        };
+        let (missing, extra) = (names(&self.mods, mods), names(mods, &self.mods));
+        if !missing.is_empty() {
+            return Err(format!("it needs these mods, or these versions of them: {}", missing.join(", ")));
+        }
+        if !extra.is_empty() {
+            return Err(format!("it was recorded without these mods, disable them to play it: {}", extra.join(", ")));
+        }
+        if self.mods != mods {
+            return Err("it was recorded with the same mods in another load order".to_string());
+        }
+        if &self.compare != compare {
+            return Err(match &self.compare {
+                Some((name, value)) => format!("it was recorded comparing worlds, play it with --compare {}={}", name, value),
+                None => "it was recorded without --compare".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Whether a path names a bundle rather than a plain recording.
+pub fn is_bundle(path: &str) -> bool { path.ends_with(EXTENSION) }
+
+/// The enabled mods, in load order, by name and a hash of what they add.
+pub fn fingerprints(mods: &[Mod]) -> Vec<(String, u64)> {
+    mods.iter()
+        .filter(|m| m.enabled)
+        .map(|m| {
+            let mut hash = Fnv::default();
+            hash.write(format!("{:?} {:?}", m.species, m.names).as_bytes());
+            (m.name.clone(), hash.finish())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::Vector2;
+    use crate::{lockstep::Setup, simulation::Timing};
+
+    #[test]
+    fn test_bundle() {
+        let setup = Setup { seed: 42, size: Vector2::new(650., 680.), mutators: vec![], timing: Timing::default() };
+        let mods = vec![("deep sea".to_string(), 7)];
+        let compare = Some(("food_rate".to_string(), "2".to_string()));
+        let bundle = Bundle {
+            program: runs::VERSION.to_string(),
+            world: WorldCode { setup, tick: 0 },
+            compare: compare.clone(),
+            mods: mods.clone(),
+            recording: b"seed=42\nframe time=0 mouse=0,0 wheel=0 button=up down= pressed=\n".to_vec(),
+        };
+        let mut out = vec![];
+        bundle.write(&mut out).unwrap();
+        let read = Bundle::read(&out).unwrap();
+        assert_eq!(read, bundle);
+        assert_eq!(read.check(&mods, &compare), Ok(()));
+
+        assert!(read.check(&[], &compare).unwrap_err().contains("deep sea"));
+        assert!(read.check(&mods, &None).unwrap_err().contains("--compare food_rate=2"));
+        let old = Bundle { program: "0.0.1".to_string(), ..bundle };
+        assert!(old.check(&mods, &compare).unwrap_err().contains("0.0.1"));
+        assert_eq!(Bundle::read(b"seed=42\n"), Err("it is not a bundled replay".to_string()));
+        assert!(Bundle::read(&out[..out.len() - 3]).unwrap_err().contains("damaged"));
+    }
+}
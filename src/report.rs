@@ -0,0 +1,267 @@
+//! A report of a run, written as it ends, to look back on or share.
+//!
+//! When the window closes, or a headless run finishes, a new directory
+//! under `reports/` gets a page, `index.html`, and a graph of each of
+//! the main statistics of each world, as `A-population.png`. The page
+//! has how long the run took and its seed, and for each world:
+//!
+//! * the ticks it ran and its date
+//! * its peak population, and its population and food at the end
+//! * how many species lived and died out, the living ones with their
+//!   peaks, see `census`
+//! * its records, see `records`
+//! * the graphs, over the whole run, each with its highest and its
+//!   last value
+//!
+//! A graph shows the mean of each stretch of ticks as a line over the
+//! band of its lowest to highest, from 0 up, with the bookmarks of the
+//! run as faint lines across.
+//!
+//! `--no-report` writes no report, `--report-dir` writes it elsewhere,
+//! and `--open-report` opens the page once it is written.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::report;
+//! # let start = std::time::Instant::now();
+//! let path = report::write(report::DIR, &[("A", &sim)], start.elapsed())?;
+//! report::open(&path.join(report::PAGE))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    time,
+};
+
+use raylib::prelude::Color;
+
+use crate::{
+    png,
+    simulation::Simulation,
+    stats::{MarkKind, SeriesId, StatsStore},
+    svg::escape,
+};
+
+/// Where reports are written unless `--report-dir` says otherwise.
+pub const DIR: &str = "reports";
+/// The page of a report, in its directory.
+pub const PAGE: &str = "index.html";
+/// The statistics graphed, by the names of their series.
+pub const GRAPHS: [&str; 6] = ["population", "food", "births", "deaths", "mean speed", "mean size"];
+pub const WIDTH: u32 = 640;
+pub const HEIGHT: u32 = 180;
+//  between the edges of a graph and what is drawn
+const MARGIN: u32 = 6;
+
+const BACKGROUND: Color = Color { r: 255, g: 255, b: 255, a: 255 };
+const AXIS: Color = Color { r: 170, g: 170, b: 170, a: 255 };
+const BOOKMARK: Color = Color { r: 250, g: 220, b: 170, a: 255 };
+const BAND: Color = Color { r: 190, g: 215, b: 245, a: 255 };
+const LINE: Color = Color { r: 20, g: 80, b: 160, a: 255 };
+
+/// A picture drawn without a window, row by row.
+struct Canvas {
+    pixels: Vec<Color>,
+}
+
+impl Canvas {
+    fn new() -> Self {
+        Self { pixels: vec![BACKGROUND; (WIDTH * HEIGHT) as usize] }
+    }
+
+    fn set(&mut self, x: i32, y: i32, color: Color) {
+        if (0..WIDTH as i32).contains(&x) && (0..HEIGHT as i32).contains(&y) {
+            self.pixels[(y as u32 * WIDTH + x as u32) as usize] = color;
+        }
+    }
+
+    fn vertical(&mut self, x: i32, from: i32, to: i32, color: Color) {
+        for y in from.min(to)..=from.max(to) {
+            self.set(x, y, color);
+        }
+    }
+
+    /// A line two pixels thick, stepping along its longer side.
+    fn line(&mut self, from: (f32, f32), to: (f32, f32), color: Color) {
+        let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1.) as i32;
+        for step in 0..=steps {
+            let t = step as f32 / steps as f32;
+            let (x, y) = (from.0 + (to.0 - from.0) * t, from.1 + (to.1 - from.1) * t);
+            self.set(x.round() as i32, y.round() as i32, color);
+            self.set(x.round() as i32, y.round() as i32 + 1, color);
+        }
+    }
+}
+
+/// The graph of a series over the whole run, see the module, as pixels
+/// row by row, `WIDTH` by `HEIGHT`.
+pub fn graph(stats: &StatsStore, id: SeriesId) -> Vec<Color> {
+    let mut canvas = Canvas::new();
+    let (left, right) = (MARGIN as f32, (WIDTH - MARGIN) as f32);
+    let (top, bottom) = (MARGIN as f32, (HEIGHT - MARGIN) as f32);
+    let first = stats.first(id).unwrap_or(0);
+    let last = stats.latest(id).map_or(first, |sample| sample.tick);
+    let points = stats.query(id, first..last + 1, (right - left) as usize);
+    let highest = points.iter().map(|point| point.max).fold(0., f32::max);
+    let lowest = points.iter().map(|point| point.min).fold(0., f32::min);
+    let x = |tick: u64| left + (right - left) * (tick - first) as f32 / (last - first).max(1) as f32;
+    let y = |value: f32| bottom - (bottom - top) * (value - lowest) / (highest - lowest).max(f32::EPSILON);
+
+    for mark in stats.marks().iter().filter(|mark| mark.kind == MarkKind::Bookmark && (first..=last).contains(&mark.tick)) {
+        canvas.vertical(x(mark.tick) as i32, top as i32, bottom as i32, BOOKMARK);
+    }
+    for point in &points {
+        canvas.vertical(x(point.tick) as i32, y(point.min) as i32, y(point.max) as i32, BAND);
+    }
+    canvas.line((left, y(0.)), (right, y(0.)), AXIS);
+    for pair in points.windows(2) {
+        canvas.line((x(pair[0].tick), y(pair[0].mean)), (x(pair[1].tick), y(pair[1].mean)), LINE);
+    }
+    if let [point] = points[..] {
+        canvas.line((left, y(point.mean)), (right, y(point.mean)), LINE);
+    }
+    canvas.pixels
+}
+
+/// A length of time in words, as in `1 h 5 min 12 s`.
+pub fn format_duration(duration: time::Duration) -> String {
+    let seconds = duration.as_secs();
+    match (seconds / 3600, seconds / 60 % 60, seconds % 60) {
+        (0, 0, s) => format!("{} s", s),
+        (0, m, s) => format!("{} min {} s", m, s),
+        (h, m, s) => format!("{} h {} min {} s", h, m, s),
+    }
+}
+
+/// The file a graph of a world is written to, as in `A-mean-speed.png`.
+fn graph_file(label: &str, series: &str) -> String {
+    format!("{}-{}.png", &label[..1], series.replace(' ', "-"))
+}
+
+/// Write the page of a report on labeled worlds that ran for a while.
+pub fn write_page<W: Write>(out: &mut W, worlds: &[(&str, &Simulation)], elapsed: time::Duration) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Blobs run report</title>")?;
+    writeln!(out, "<style>body {{ font-family: sans-serif; max-width: 700px; margin: auto; }} td {{ padding-right: 1em; }} img {{ border: 1px solid #ccc; }}</style>")?;
+    writeln!(out, "</head>\n<body>\n<h1>Blobs run report</h1>")?;
+    writeln!(out, "<p>Ran for {} with seed {}.</p>", format_duration(elapsed), worlds.first().map_or(0, |(_, sim)| sim.seed()))?;
+    for &(label, sim) in worlds {
+        let stats = sim.stats();
+        let series = |name: &str| stats.id(name);
+        let live = sim.blobs().count();
+        let peak = series("population").and_then(|id| stats.max(id)).map_or(live, |max| (max as usize).max(live));
+        let census = sim.census();
+        writeln!(out, "<h2>World {}</h2>\n<table>", escape(label))?;
+        let rows = [
+            ("Ticks", format!("{}, reaching {}", sim.clock().tick(), sim.clock())),
+            ("Peak population", peak.to_string()),
+            ("At the end", format!("{} blobs and {} foods", live, sim.foods().count())),
+            ("Species", format!("{} living, {} died out", census.records().count(), census.extinctions().len())),
+        ];
+        for (name, value) in &rows {
+            writeln!(out, "<tr><td>{}</td><td>{}</td></tr>", name, escape(value))?;
+        }
+        writeln!(out, "</table>")?;
+
+        writeln!(out, "<h3>Records</h3>\n<ul>")?;
+        for record in sim.records().iter() {
+            writeln!(out, "<li>{} (tick {})</li>", escape(&record.describe()), record.tick)?;
+        }
+        writeln!(out, "</ul>")?;
+        if census.records().next().is_some() {
+            writeln!(out, "<h3>Living species</h3>\n<table>\n<tr><th>Species</th><th>Blobs</th><th>Peak</th><th>Generations</th></tr>")?;
+            for (species, record) in census.records() {
+                writeln!(out, "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>", escape(species), record.population, record.peak, record.generations)?;
+            }
+            writeln!(out, "</table>")?;
+        }
+
+        writeln!(out, "<h3>Graphs</h3>")?;
+        for (name, id) in GRAPHS.iter().filter_map(|&name| Some((name, series(name)?))) {
+            let last = stats.latest(id).map_or(0., |sample| sample.value);
+            writeln!(out, "<p>{}, at most {:.1} and {:.1} at the end<br><img src=\"{}\" width=\"{}\" height=\"{}\"></p>",
+                name, stats.max(id).unwrap_or(0.), last, graph_file(label, name), WIDTH, HEIGHT,
+            )?;
+        }
+    }
+    writeln!(out, "</body>\n</html>")
+}
+
+/// Write a report on labeled worlds that ran for a while to a new
+/// directory in `dir`, see the module, returning its path.
+pub fn write(dir: &str, worlds: &[(&str, &Simulation)], elapsed: time::Duration) -> io::Result<PathBuf> {
+    let since_epoch = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default();
+    let path = Path::new(dir).join(format!("report-{}", since_epoch.as_millis()));
+    fs::create_dir_all(&path)?;
+    for &(label, sim) in worlds {
+        let stats = sim.stats();
+        for (name, id) in GRAPHS.iter().filter_map(|&name| Some((name, stats.id(name)?))) {
+            let mut out = BufWriter::new(fs::File::create(path.join(graph_file(label, name)))?);
+            png::write(&mut out, WIDTH, HEIGHT, &graph(stats, id))?;
+            out.flush()?;
+        }
+    }
+    let mut out = BufWriter::new(fs::File::create(path.join(PAGE))?);
+    write_page(&mut out, worlds, elapsed)?;
+    out.flush()?;
+    Ok(path)
+}
+
+/// Open a file with the program the system opens its kind with, such
+/// as a browser for a page.
+pub fn open(path: &Path) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path).spawn()
+        .map(|_| ())
+        .map_err(|e| format!("failed to open {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::Vector2;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_report() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        sim.insert_blob(BlobConfig { name: Some("<Ada>".to_string()), species: Some("Grazer".to_string()), ..BlobConfig::new(Vector2::new(100., 100.)) });
+        for _ in 0..120 {
+            sim.step();
+        }
+        let mut page = vec![];
+        write_page(&mut page, &[("A", &sim)], time::Duration::from_secs(3725)).unwrap();
+        let page = String::from_utf8(page).unwrap();
+        assert!(page.contains("Ran for 1 h 2 min 5 s"));
+        assert!(page.contains("<td>Peak population</td><td>1</td>"));
+        assert!(page.contains("1 living, 0 died out"));
+        assert!(page.contains("<td>Grazer</td>"));
+        assert!(page.contains("&lt;Ada&gt;"), "{}", page);
+        assert!(page.contains("src=\"A-mean-speed.png\""));
+
+        //  the population, steady at one, is a flat line over the axis
+        let id = sim.stats().id("population").unwrap();
+        let pixels = graph(sim.stats(), id);
+        assert_eq!(pixels.len(), (WIDTH * HEIGHT) as usize);
+        let column: Vec<_> = (0..HEIGHT).map(|y| pixels[(y * WIDTH + WIDTH / 2) as usize]).collect();
+        assert_eq!(column[MARGIN as usize], LINE);
+        assert_eq!(column[(HEIGHT - MARGIN) as usize], AXIS);
+        assert_eq!(column[HEIGHT as usize / 2], BACKGROUND);
+        assert_eq!(graph_file("B: speed=2", "mean size"), "B-mean-size.png");
+    }
+}
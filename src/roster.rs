@@ -0,0 +1,230 @@
+//! Blobs read from a CSV file, to start a world with a population made
+//! to measure, such as by a script of an experiment.
+//!
+//! `--import-csv <path>` adds the blobs of a file to every world as it
+//! starts, next to the random ones, and `Shift+I` adds them again in
+//! game, from `PATH` unless the option gave another file.
+//!
+//! The first line names the columns, in any order, and each line after
+//! it is a blob. `x` and `y`, where the blob is in the world, must be
+//! given; every other column may be left out, or a field left empty,
+//! for the blob to have what `BlobConfig::new` gives it:
+//!
+//! * `name` and `species`
+//! * `diet`, as in `herbivore`, and `brain`, as in `greedy`
+//! * `aura`, as in `heal`
+//! * the genes, named as in a blob export, see `exchange`: `radius`,
+//!   `speed`, `rotation_speed` and so on, non-negative numbers
+//! * `body_color` and `favorite_color`, as in `#ff8000`
+//! * `hunger`, how hungry the blob starts, from 0 for full
+//!
+//! Fields holding a comma are quoted as in `"Smith, Ada"`, with a quote
+//! in them doubled, as `group::write_csv` writes them; a field cannot
+//! hold a line break. Blank lines and those starting with `#` are
+//! skipped. Blobs outside the world are moved to its nearest edge.
+//!
+//! ```text
+//! x,y,name,species,diet,speed,radius
+//! 100,100,Ada,Grazer,herbivore,60,6
+//! 400,250,Bo,Hunter,carnivore,90,
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::roster;
+//! let blobs = roster::load("population.csv")?;
+//! roster::insert(&mut sim, &blobs);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fs;
+
+use raylib::prelude::*;
+
+use crate::{
+    aura::Aura,
+    simulation::{BlobConfig, Simulation},
+    species::{BrainKind, Diet},
+};
+
+/// The file `Shift+I` reads unless `--import-csv` gave another.
+pub const PATH: &str = "blobs.csv";
+
+/// The genes a file may give, by the names of a blob export.
+const GENES: [&str; 15] = [
+    "radius", "speed", "rotation_speed", "pov", "sight_depth",
+    "color_attraction", "color_repulsion", "max_hunger", "attack", "defence",
+    "hunger_reduction", "hunger_division", "swimming", "hearing", "aura_strength",
+];
+
+fn gene_mut<'a>(config: &'a mut BlobConfig, name: &str) -> Option<&'a mut f32> {
+    Some(match name {
+        "radius" => &mut config.radius,
+        "speed" => &mut config.speed,
+        "rotation_speed" => &mut config.rotation_speed,
+        "pov" => &mut config.pov,
+        "sight_depth" => &mut config.sight_depth,
+        "color_attraction" => &mut config.color_attraction,
+        "color_repulsion" => &mut config.color_repulsion,
+        "max_hunger" => &mut config.max_hunger,
+        "attack" => &mut config.attack,
+        "defence" => &mut config.defence,
+        "hunger_reduction" => &mut config.hunger_reduction,
+        "hunger_division" => &mut config.hunger_division,
+        "swimming" => &mut config.swimming,
+        "hearing" => &mut config.hearing,
+        "aura_strength" => &mut config.aura_strength,
+        "hunger" => &mut config.hunger,
+        _ => return None,
+    })
+}
+
+/// The fields of a line, unquoted.
+fn split(line: &str) -> Result<Vec<String>, String> {
+    let mut ret = vec![];
+    let mut chars = line.chars().peekable();
+    loop {
+        let mut field = String::new();
+        if chars.next_if_eq(&'"').is_some() {
+            loop {
+                match chars.next() {
+                    Some('"') if chars.next_if_eq(&'"').is_some() => field.push('"'),
+                    Some('"') => break,
+                    Some(c) => field.push(c),
+                    None => return Err("unterminated quote".to_string()),
+                }
+            }
+            if !matches!(chars.peek(), None | Some(',')) {
+                return Err(format!("unexpected text after the quoted `{}`", field));
+            }
+        }
+        while let Some(c) = chars.next_if(|&c| c != ',') {
+            field.push(c);
+        }
+        ret.push(field.trim().to_string());
+        if chars.next().is_none() {
+            return Ok(ret);
+        }
+    }
+}
+
+fn parse_color(text: &str) -> Option<Color> {
+    let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::new(channel(0)?, channel(2)?, channel(4)?, 255))
+}
+
+/// The blobs of the contents of a file, see the module.
+pub fn parse(text: &str) -> Result<Vec<BlobConfig>, String> {
+    let mut lines = text.lines().enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    let (number, header) = lines.next().ok_or("missing the line naming the columns")?;
+    let columns = split(header).map_err(|e| format!("line {}: {}", number, e))?;
+    for (i, column) in columns.iter().enumerate() {
+        let known = ["x", "y", "name", "species", "diet", "brain", "aura", "body_color", "favorite_color", "hunger"].contains(&column.as_str())
+            || GENES.contains(&column.as_str());
+        if !known {
+            return Err(format!("unknown column `{}`", column));
+        }
+        if columns[..i].contains(column) {
+            return Err(format!("column `{}` is given twice", column));
+        }
+    }
+    for required in ["x", "y"] {
+        if !columns.iter().any(|column| column == required) {
+            return Err(format!("missing column `{}`", required));
+        }
+    }
+
+    let mut ret = vec![];
+    for (number, line) in lines {
+        let fields = split(line).map_err(|e| format!("line {}: {}", number, e))?;
+        if fields.len() != columns.len() {
+            return Err(format!("line {}: {} fields, expected {}", number, fields.len(), columns.len()));
+        }
+        let mut config = BlobConfig::default();
+        for (column, field) in columns.iter().zip(&fields) {
+            let invalid = || format!("line {}: invalid {} `{}`", number, column, field);
+            if field.is_empty() {
+                if column == "x" || column == "y" {
+                    return Err(format!("line {}: missing {}", number, column));
+                }
+                continue;
+            }
+            match column.as_str() {
+                "x" => config.pos.x = field.parse().ok().filter(|x: &f32| x.is_finite()).ok_or_else(invalid)?,
+                "y" => config.pos.y = field.parse().ok().filter(|y: &f32| y.is_finite()).ok_or_else(invalid)?,
+                "name" => config.name = Some(field.clone()),
+                "species" => config.species = Some(field.clone()),
+                "diet" => config.diet = Diet::from_name(field).ok_or_else(invalid)?,
+                "brain" => config.brain = BrainKind::from_name(field).ok_or_else(invalid)?,
+                "aura" => config.aura = Aura::from_name(field).ok_or_else(invalid)?,
+                "body_color" => config.color = parse_color(field).ok_or_else(invalid)?,
+                "favorite_color" => config.favorite_color = parse_color(field).ok_or_else(invalid)?,
+                gene => {
+                    let value = field.parse().ok().filter(|value: &f32| value.is_finite() && *value >= 0.).ok_or_else(invalid)?;
+                    *gene_mut(&mut config, gene).unwrap() = value;
+                },
+            }
+        }
+        ret.push(config);
+    }
+    Ok(ret)
+}
+
+/// The blobs of a file, see the module.
+pub fn load(path: &str) -> Result<Vec<BlobConfig>, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    parse(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Add blobs to a world, those outside it at its nearest edge.
+pub fn insert(sim: &mut Simulation, blobs: &[BlobConfig]) {
+    let size = sim.size();
+    for config in blobs {
+        let pos = Vector2::new(config.pos.x.clamp(0., size.x), config.pos.y.clamp(0., size.y));
+        sim.insert_blob(BlobConfig { pos, ..config.clone() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+
+    #[test]
+    fn test_roster() {
+        let blobs = parse("# made by a script\nx,y,name,species,diet,speed,body_color\n\n10,20,\"Smith, \"\"Ada\"\"\",Grazer,carnivore,80,#ff8000\n5,6,,,,,\n").unwrap();
+        assert_eq!(blobs.len(), 2);
+        assert_eq!(blobs[0].pos, Vector2::new(10., 20.));
+        assert_eq!(blobs[0].name.as_deref(), Some("Smith, \"Ada\""));
+        assert_eq!(blobs[0].species.as_deref(), Some("Grazer"));
+        assert_eq!(blobs[0].diet, Diet::Carnivore);
+        assert_eq!(blobs[0].speed, 80.);
+        assert_eq!(blobs[0].color, Color::new(255, 128, 0, 255));
+        //  empty fields keep what a new blob has
+        let default = BlobConfig::new(Vector2::new(5., 6.));
+        assert_eq!((blobs[1].name.as_ref(), blobs[1].speed, blobs[1].diet), (None, default.speed, default.diet));
+
+        assert!(parse("").unwrap_err().contains("naming the columns"));
+        assert!(parse("x,name").unwrap_err().contains("missing column `y`"));
+        assert!(parse("x,y,wings").unwrap_err().contains("unknown column `wings`"));
+        assert!(parse("x,y,x").unwrap_err().contains("given twice"));
+        assert_eq!(parse("x,y,speed\n1,2,-3").unwrap_err(), "line 2: invalid speed `-3`");
+        assert_eq!(parse("x,y\n1").unwrap_err(), "line 2: 1 fields, expected 2");
+        assert_eq!(parse("x,y\n,2").unwrap_err(), "line 2: missing x");
+        assert!(parse("x,y,name\n1,2,\"Ada").unwrap_err().contains("unterminated"));
+        assert!(load("no-such-blobs.csv").unwrap_err().starts_with("no-such-blobs.csv: "));
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        insert(&mut sim, &parse("x,y\n100,100\n-50,900").unwrap());
+        let mut places: Vec<_> = sim.blobs().map(|(_, blob)| (blob.pos().x, blob.pos().y)).collect();
+        places.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(places, [(0., 300.), (100., 100.)]);
+    }
+}
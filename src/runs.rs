@@ -0,0 +1,139 @@
+//! What is known about a run besides how it went, to keep
+//! experiments organized.
+//!
+//! Every run gets metadata: when it ended, the seed and configuration
+//! hash of its world, the version of the program and notes typed in
+//! with `N`. It is kept with the summary of the run in the hall of
+//! fame, where `R` browses it, and at the end of input recordings.
+//! It is written as a line of `key=value` fields, with the notes last
+//! as they may hold spaces:
+//!
+//! ```text
+//! run time=1760000000 seed=1234 config=9c1e4f0a5b2d3e77 version=0.1.0+fa634ec notes=more food, fewer blobs
+//! ```
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::runs::Metadata;
+//! # let now = 0;
+//! let metadata = Metadata { notes: "more food".to_string(), ..Metadata::of(&sim, now) };
+//! assert_eq!(Metadata::parse(&metadata.to_line()), Ok(metadata));
+//! ```
+
+use crate::{simulation::Simulation, leaderboard};
+
+/// The version of the program, with the git commit it was built from.
+pub const VERSION: &str = concat!(env!("CARGO_PKG_VERSION"), "+", env!("BLOBS_GIT_VERSION"));
+/// How many of the latest runs the browser shows.
+pub const SHOWN: usize = 15;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metadata {
+    /// Seconds since the Unix epoch when the run ended.
+    pub time: u64,
+    pub seed: u64,
+    /// A hash of the configuration besides the seed, see `leaderboard::config_hash`.
+    pub config_hash: u64,
+    pub version: String,
+    /// Notes on the run, on a single line.
+    pub notes: String,
+}
+
+impl Metadata {
+    /// The metadata of a world, without notes.
+    pub fn of(sim: &Simulation, time: u64) -> Self {
+        Self {
+            time,
+            seed: sim.seed(),
+            config_hash: leaderboard::config_hash(sim),
+            version: VERSION.to_string(),
+            notes: String::new(),
+        }
+    }
+
+    /// The fields of the metadata, but the notes, which go last on a line.
+    pub fn fields(&self) -> String {
+        format!("time={} seed={} config={:016x} version={}", self.time, self.seed, self.config_hash, self.version)
+    }
+
+    pub fn to_line(&self) -> String {
+        format!("run {} notes={}", self.fields(), self.notes)
+    }
+
+    /// Parse a line written by `to_line`, leaving the fields it does not know to `other`.
+    pub fn parse_with<F>(line: &str, mut other: F) -> Result<Self, String>
+    where
+        F: FnMut(&str, &str) -> Result<(), String>,
+    {
+        let line = line.strip_prefix("run ").ok_or_else(|| format!("expected a run, got `{}`", line))?;
+        //  lines written before there were notes have none
+        let (fields, notes) = match line.find("notes=") {
+            Some(i) => (&line[..i], &line[i + "notes=".len()..]),
+            None => (line, ""),
+        };
+        let mut ret = Self { time: 0, seed: 0, config_hash: 0, version: String::new(), notes: notes.to_string() };
+        for field in fields.split(' ').filter(|field| !field.is_empty()) {
+            let (name, value) = field.split_once('=').ok_or_else(|| format!("invalid field `{}`", field))?;
+            let invalid = || format!("invalid {} `{}`", name, value);
+            match name {
+                "time" => ret.time = value.parse().map_err(|_| invalid())?,
+                "seed" => ret.seed = value.parse().map_err(|_| invalid())?,
+                "config" => ret.config_hash = u64::from_str_radix(value, 16).map_err(|_| invalid())?,
+                "version" => ret.version = value.to_string(),
+                _ => other(name, value)?,
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Parse a line written by `to_line`.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        //  fields added later are skipped
+        Self::parse_with(line, |_, _| Ok(()))
+    }
+}
+
+/// Make notes fit on a line.
+pub fn clean_notes(notes: &str) -> String {
+    notes.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A time in seconds since the Unix epoch as a UTC date and time.
+pub fn format_time(time: u64) -> String {
+    let (days, seconds) = (time / 86400, time % 86400);
+    //  the civil calendar from days, after Howard Hinnant
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, seconds / 3600, seconds % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata() {
+        let metadata = Metadata { time: 1, seed: 2, config_hash: 255, version: VERSION.to_string(), notes: "more food = fewer fights".to_string() };
+        let line = metadata.to_line();
+        assert!(line.starts_with("run time=1 seed=2 config=00000000000000ff version=0.1.0+"));
+        assert_eq!(Metadata::parse(&line), Ok(metadata.clone()));
+        assert_eq!(Metadata::parse("run time=1 seed=2"), Ok(Metadata { config_hash: 0, version: String::new(), notes: String::new(), ..metadata }));
+        assert!(Metadata::parse("run seed=x").is_err());
+        assert_eq!(clean_notes(" a\nb  c "), "a b c");
+
+        assert_eq!(format_time(0), "1970-01-01 00:00");
+        assert_eq!(format_time(951782400 + 3723), "2000-02-29 01:02");
+        assert_eq!(format_time(1760000000), "2025-10-09 08:53");
+    }
+}
@@ -0,0 +1,270 @@
+//! A diagram of where the energy of a world went, as a Sankey diagram.
+//!
+//! Energy enters the world as food, and flows to the species that
+//! eat it, from them to the species that hunt them, and on to decay
+//! as corpses, some of which scavengers eat again. Each flow is a
+//! band as wide as the energy of the meals of the last `WINDOW` ticks,
+//! a corpse counting as `FOOD_ENERGY`. Food is on the left and decay
+//! on the right, and every species stands in the column after the
+//! highest of what it eats, grazers first and predators after them,
+//! so the trophic structure that evolved reads from left to right.
+//! What blobs burn as they move is left out.
+//!
+//! Positions are in a unit square, scaled to wherever it is drawn.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::sankey::{self, Sankey};
+//! let sankey = Sankey::measure(&sim, sankey::WINDOW);
+//! for link in sankey.links() {
+//!     let (from, to) = (&sankey.nodes()[link.from], &sankey.nodes()[link.to]);
+//! }
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{energy::FOOD_ENERGY, simulation::{Simulation, Food, Prey}};
+
+/// How many of the latest ticks the flows count.
+pub const WINDOW: u64 = 1200;
+/// How much of a column's height is left between its nodes.
+const GAPS: f32 = 0.2;
+
+/// Where energy is, as eaten.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stage {
+    Food,
+    Species(Option<String>),
+    /// Corpses, until they are eaten or spoil.
+    Decay,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub stage: Stage,
+    pub color: Color,
+    pub column: usize,
+    /// The energy that flowed in and out of the node.
+    pub inflow: f32,
+    pub outflow: f32,
+    /// Where the node is, in a unit square.
+    pub rect: Rectangle,
+}
+
+impl Node {
+    pub fn label(&self) -> &str {
+        match &self.stage {
+            Stage::Food => "food",
+            Stage::Species(Some(species)) => species,
+            Stage::Species(None) => "no species",
+            Stage::Decay => "decay",
+        }
+    }
+
+    /// The energy the node stands for, as tall as it is drawn.
+    pub fn value(&self) -> f32 {
+        self.inflow.max(self.outflow)
+    }
+}
+
+/// Energy from the node at one index to the node at another, leaving
+/// the first and entering the second at a height in the unit square,
+/// in a band of a width.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Link {
+    pub from: usize,
+    pub to: usize,
+    pub energy: f32,
+    pub start: f32,
+    pub end: f32,
+    pub width: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sankey {
+    nodes: Vec<Node>,
+    links: Vec<Link>,
+}
+
+impl Sankey {
+    /// The flows of energy in the last ticks of a world, laid out.
+    pub fn measure(sim: &Simulation, window: u64) -> Self {
+        let since = sim.clock().tick().saturating_sub(window);
+        let mut nodes: Vec<Node> = vec![];
+        let mut links: Vec<Link> = vec![];
+        let mut flow = |nodes: &mut Vec<Node>, from: Stage, to: Stage, energy: f32| {
+            let (from, to) = (index(nodes, from), index(nodes, to));
+            match links.iter_mut().find(|link| (link.from, link.to) == (from, to)) {
+                Some(link) => link.energy += energy,
+                None => links.push(Link { from, to, energy, start: 0., end: 0., width: 0. }),
+            }
+        };
+        for (_, meal) in sim.meals_since(since) {
+            let prey = match &meal.prey {
+                Prey::Food => Stage::Food,
+                Prey::Corpse => Stage::Decay,
+                Prey::Blob(species) => Stage::Species(species.clone()),
+            };
+            flow(&mut nodes, prey, Stage::Species(meal.eater.clone()), meal.energy);
+        }
+        for (_, species) in sim.remains_since(since) {
+            flow(&mut nodes, Stage::Species(species.clone()), Stage::Decay, FOOD_ENERGY);
+        }
+        links.retain(|link| link.energy > 0.);
+
+        //  species take the color of their blobs
+        for node in &mut nodes {
+            node.color = match &node.stage {
+                Stage::Food => Food::COLOR,
+                Stage::Decay => Color::BROWN,
+                Stage::Species(species) => {
+                    let colors: Vec<Color> = sim.blobs().map(|(_, blob)| blob).filter(|blob| &blob.species == species).map(|blob| blob.color).collect();
+                    let mean = |channel: fn(&Color) -> u8| (colors.iter().map(|color| channel(color) as f32).sum::<f32>() / colors.len().max(1) as f32) as u8;
+                    if colors.is_empty() { Color::GRAY } else { Color::new(mean(|c| c.r), mean(|c| c.g), mean(|c| c.b), 255) }
+                },
+            };
+        }
+        for link in &links {
+            nodes[link.from].outflow += link.energy;
+            nodes[link.to].inflow += link.energy;
+        }
+
+        let mut ret = Self { nodes, links };
+        ret.assign_columns();
+        ret.layout();
+        ret
+    }
+
+    pub fn nodes(&self) -> &[Node] { &self.nodes }
+
+    pub fn links(&self) -> &[Link] { &self.links }
+
+    /// The column of each node: food first, each species after the
+    /// highest of what it eats, and decay last.
+    fn assign_columns(&mut self) {
+        let mut columns = vec![0; self.nodes.len()];
+        //  a column for every species at most, however they eat each other
+        for _ in 0..self.nodes.len() {
+            let mut changed = false;
+            for link in &self.links {
+                let from_decay = self.nodes[link.from].stage == Stage::Decay;
+                if from_decay || link.from == link.to || !matches!(self.nodes[link.to].stage, Stage::Species(_)) { continue; }
+                if columns[link.to] < columns[link.from] + 1 && columns[link.from] < self.nodes.len() {
+                    columns[link.to] = columns[link.from] + 1;
+                    changed = true;
+                }
+            }
+            if !changed { break; }
+        }
+        //  scavengers of nothing but corpses eat after the food
+        for (i, node) in self.nodes.iter().enumerate() {
+            if matches!(node.stage, Stage::Species(_)) {
+                columns[i] = columns[i].max(1);
+            }
+        }
+        let last = columns.iter().copied().max().unwrap_or(0) + 1;
+        for (node, column) in self.nodes.iter_mut().zip(columns) {
+            node.column = if node.stage == Stage::Decay { last } else { column };
+        }
+    }
+
+    /// Place the nodes in their columns, stacked by size, and where
+    /// the links leave and enter them.
+    fn layout(&mut self) {
+        let columns = self.nodes.iter().map(|node| node.column + 1).max().unwrap_or(0);
+        let width = 1. / (4 * columns.max(1)) as f32;
+        let total = |column: usize| self.nodes.iter().filter(|node| node.column == column).map(Node::value).sum::<f32>();
+        let fullest = (0..columns).map(total).fold(0., f32::max);
+        //  how many units of the unit square's height a unit of energy takes
+        let scale = if fullest > 0. { (1. - GAPS) / fullest } else { 0. };
+        for column in 0..columns {
+            let count = self.nodes.iter().filter(|node| node.column == column).count();
+            let gap = GAPS / count.max(1) as f32;
+            let x = if columns > 1 { column as f32 / (columns - 1) as f32 * (1. - width) } else { 0. };
+            let mut y = gap / 2.;
+            for node in self.nodes.iter_mut().filter(|node| node.column == column) {
+                let height = node.value() * scale;
+                node.rect = Rectangle::new(x, y, width, height);
+                y += height + gap;
+            }
+        }
+        //  links leave and enter from the top down, in the order of the other ends
+        let mut order: Vec<usize> = (0..self.links.len()).collect();
+        order.sort_by(|&a, &b| self.nodes[self.links[a].to].rect.y.total_cmp(&self.nodes[self.links[b].to].rect.y));
+        let mut out = vec![0.; self.nodes.len()];
+        for &i in &order {
+            let link = &mut self.links[i];
+            link.width = link.energy * scale;
+            link.start = self.nodes[link.from].rect.y + out[link.from];
+            out[link.from] += link.width;
+        }
+        order.sort_by(|&a, &b| self.nodes[self.links[a].from].rect.y.total_cmp(&self.nodes[self.links[b].from].rect.y));
+        let mut into = vec![0.; self.nodes.len()];
+        for &i in &order {
+            let link = &mut self.links[i];
+            link.end = self.nodes[link.to].rect.y + into[link.to];
+            into[link.to] += link.width;
+        }
+    }
+}
+
+/// The index of the node of a stage, added if there is none.
+fn index(nodes: &mut Vec<Node>, stage: Stage) -> usize {
+    if let Some(i) = nodes.iter().position(|node| node.stage == stage) {
+        return i;
+    }
+    nodes.push(Node { stage, color: Color::BLANK, column: 0, inflow: 0., outflow: 0., rect: Rectangle::new(0., 0., 0., 0.) });
+    nodes.len() - 1
+}
+
+/// How far along a band between two heights it is at a fraction of
+/// the way, easing in and out so bands leave and enter level.
+pub fn band(start: f32, end: f32, t: f32) -> f32 {
+    start + (end - start) * t * t * (3. - 2. * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{simulation::{SimulationConfig, BlobConfig}, species::Diet};
+
+    #[test]
+    fn test_sankey() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        assert!(Sankey::measure(&sim, WINDOW).nodes().is_empty());
+
+        //  a hungry grazer on a food, and a hunter on a weaker blob
+        let grazer = sim.insert_blob(BlobConfig { species: Some("grazer".to_string()), max_hunger: 20., hunger: 10., ..BlobConfig::new(Vector2::new(50., 50.)) });
+        sim.insert_food(Vector2::new(50., 50.));
+        sim.insert_blob(BlobConfig {
+            species: Some("hunter".to_string()), diet: Diet::Carnivore, attack: 1., defence: 2., max_hunger: 20., hunger: 10.,
+            ..BlobConfig::new(Vector2::new(200., 200.))
+        });
+        sim.insert_blob(BlobConfig { species: Some("grazer".to_string()), defence: 0., radius: 5., ..BlobConfig::new(Vector2::new(205., 200.)) });
+        for _ in 0..5 {
+            sim.step();
+        }
+        let sankey = Sankey::measure(&sim, WINDOW);
+        let node = |stage: Stage| sankey.nodes().iter().position(|node| node.stage == stage).unwrap();
+        let (food, grazers) = (node(Stage::Food), node(Stage::Species(Some("grazer".to_string()))));
+        let hunters = node(Stage::Species(Some("hunter".to_string())));
+        let columns: Vec<usize> = [food, grazers, hunters].iter().map(|&i| sankey.nodes()[i].column).collect();
+        assert_eq!(columns, [0, 1, 2], "{:?}", sankey.nodes());
+        let eaten = sankey.links().iter().find(|link| (link.from, link.to) == (food, grazers)).unwrap();
+        assert!(eaten.energy > 0. && eaten.energy <= FOOD_ENERGY, "{:?}", eaten);
+        assert!((sim.get_blob(grazer).unwrap().hunger - (10. - eaten.energy)).abs() < 1., "{:?}", eaten);
+        assert!(sankey.links().iter().any(|link| (link.from, link.to) == (grazers, hunters)));
+        for node in sankey.nodes() {
+            assert!(node.rect.y >= 0. && node.rect.y + node.rect.height <= 1. + 1e-4, "{:?}", node);
+        }
+
+        assert_eq!(band(2., 4., 0.), 2.);
+        assert_eq!(band(2., 4., 0.5), 3.);
+        assert_eq!(band(2., 4., 1.), 4.);
+    }
+}
@@ -0,0 +1,405 @@
+//! Scenarios: experiments set up in a file rather than in code.
+//!
+//! `--scenario <name>` loads `assets/scenarios/<name>.toml`, or the
+//! file at a path ending in `.toml`. A scenario has a `name`, a
+//! `description`, and a schedule for each of `[food]` and `[blobs]`
+//! that scales how often random ones are added, over the ticks of the
+//! world, from the usual rate at 1 down to none at 0:
+//!
+//! * `schedule = "constant"` with an optional `rate`, 1 by default
+//! * `schedule = "linear"` from the rate `from` to `to` over `ticks`,
+//!   staying at `to` after
+//! * `schedule = "sine"` around `mean` by up to `amplitude`, a wave
+//!   every `period` ticks, never below 0
+//! * `schedule = "steps"` with `steps = [[tick, rate], ...]`, each
+//!   rate holding from its tick, and 1 before the first
+//!
+//! Timed `events = [[tick, action, amount], ...]` happen once, when
+//! the world reaches their tick:
+//!
+//! * `"feed"` adds an amount of food at random places
+//! * `"spawn"` adds an amount of random blobs
+//! * `"cull"` removes a fraction of the blobs, chosen at random
+//!
+//! `zones = [[x, y, width, height, rule], ...]` keep food from growing
+//! in parts of the world, or let it grow only on land or in water
+//! there, see `zones`, and `scripts = [[blob, trigger, action, amount], ...]`
+//! do things when named blobs are born, eat or die, see `scripts`.
+//!
+//! ```toml
+//! name = "Boom and bust"
+//! description = "Food comes in waves."
+//! events = [[3600, "cull", 0.5]]
+//!
+//! [food]
+//! schedule = "sine"
+//! mean = 1
+//! amplitude = 0.9
+//! period = 7200
+//! ```
+//!
+//! Scenarios can also be written from the program, see
+//! `scenario_editor`, which rewrites the whole file, comments and all.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::scenario::{self, Scenario};
+//! let scenario = Scenario::load(&scenario::resolve("boom-and-bust"))?;
+//! let rate = scenario.food.rate(sim.clock().tick());
+//! for event in scenario.events_at(sim.clock().tick()) {
+//!     sim.mark(&event.label());
+//! }
+//! scenario.save(&scenario::resolve("boom-and-bust"))?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use crate::{narrator::quote, species::LoadError, toml::{self, Value}, zones::Zone, scripts::Script};
+
+/// How often something is added over time, as a multiple of the usual
+/// rate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Schedule {
+    Constant(f32),
+    Linear { from: f32, to: f32, ticks: u64 },
+    Sine { mean: f32, amplitude: f32, period: u64 },
+    /// The ticks rates start at, in order.
+    Steps(Vec<(u64, f32)>),
+}
+
+impl Default for Schedule {
+    fn default() -> Self { Self::Constant(1.) }
+}
+
+impl Schedule {
+    pub const NAMES: [&'static str; 4] = ["constant", "linear", "sine", "steps"];
+
+    /// The rate at a tick, never below 0.
+    pub fn rate(&self, tick: u64) -> f32 {
+        let rate = match self {
+            Self::Constant(rate) => *rate,
+            Self::Linear { from, to, ticks } => {
+                let t = if *ticks == 0 { 1. } else { (tick as f32 / *ticks as f32).min(1.) };
+                from + (to - from) * t
+            },
+            Self::Sine { mean, amplitude, period } => {
+                let phase = (tick % period) as f32 / *period as f32;
+                mean + amplitude * (phase * std::f32::consts::TAU).sin()
+            },
+            Self::Steps(steps) => steps.iter().rev().find(|&&(start, _)| start <= tick).map_or(1., |&(_, rate)| rate),
+        };
+        rate.max(0.)
+    }
+
+    /// Read a schedule from its table in a scenario.
+    pub fn from_table(table: &Value) -> Result<Self, String> {
+        let number = |key: &str| table.get(key).and_then(Value::as_f32).filter(|value| value.is_finite() && *value >= 0.)
+            .ok_or_else(|| format!("`{}` must be a number of at least 0", key));
+        let ticks = |key: &str| number(key).map(|value| value as u64);
+        match table.get("schedule").and_then(Value::as_str).unwrap_or("constant") {
+            "constant" => Ok(Self::Constant(if table.get("rate").is_some() { number("rate")? } else { 1. })),
+            "linear" => Ok(Self::Linear { from: number("from")?, to: number("to")?, ticks: ticks("ticks")? }),
+            "sine" => {
+                let period = ticks("period")?;
+                if period == 0 {
+                    return Err("`period` must be at least 1 tick".to_string());
+                }
+                Ok(Self::Sine { mean: number("mean")?, amplitude: number("amplitude")?, period })
+            },
+            "steps" => {
+                let steps = table.get("steps").and_then(Value::as_array).ok_or("`steps` must be [[tick, rate], ...]")?;
+                let steps = steps.iter()
+                    .map(|step| match step.as_array().map(Vec::as_slice) {
+                        Some([tick, rate]) => Some((tick.as_f64().filter(|tick| *tick >= 0.)? as u64, rate.as_f32().filter(|rate| *rate >= 0.)?)),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or("`steps` must be [[tick, rate], ...]")?;
+                if steps.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+                    return Err("`steps` must be in order of their ticks".to_string());
+                }
+                Ok(Self::Steps(steps))
+            },
+            schedule => Err(format!("unknown schedule `{}`, expected one of {}", schedule, Self::NAMES.join(", "))),
+        }
+    }
+
+    /// The schedule as the lines of its table in a scenario.
+    pub fn to_toml(&self) -> String {
+        match self {
+            Self::Constant(rate) => format!("schedule = \"constant\"\nrate = {}\n", rate),
+            Self::Linear { from, to, ticks } => format!("schedule = \"linear\"\nfrom = {}\nto = {}\nticks = {}\n", from, to, ticks),
+            Self::Sine { mean, amplitude, period } => {
+                format!("schedule = \"sine\"\nmean = {}\namplitude = {}\nperiod = {}\n", mean, amplitude, period)
+            },
+            Self::Steps(steps) => {
+                let steps: Vec<_> = steps.iter().map(|(tick, rate)| format!("[{}, {}]", tick, rate)).collect();
+                format!("schedule = \"steps\"\nsteps = [{}]\n", steps.join(", "))
+            },
+        }
+    }
+}
+
+/// What a timed event does to a world.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    /// Add an amount of food at random places.
+    Feed(u32),
+    /// Add an amount of random blobs.
+    Spawn(u32),
+    /// Remove a fraction of the blobs.
+    Cull(f32),
+}
+
+impl Action {
+    pub const NAMES: [&'static str; 3] = ["feed", "spawn", "cull"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Feed(_) => "feed",
+            Self::Spawn(_) => "spawn",
+            Self::Cull(_) => "cull",
+        }
+    }
+
+    /// The action of a name with an amount, a count or a fraction.
+    pub fn from_name(name: &str, amount: f64) -> Result<Self, String> {
+        let count = || Some(amount).filter(|amount| amount.fract() == 0. && *amount >= 0. && *amount <= u32::MAX as f64)
+            .map(|amount| amount as u32)
+            .ok_or_else(|| format!("the amount of `{}` must be a whole number of at least 0", name));
+        match name {
+            "feed" => Ok(Self::Feed(count()?)),
+            "spawn" => Ok(Self::Spawn(count()?)),
+            "cull" if (0. ..=1.).contains(&amount) => Ok(Self::Cull(amount as f32)),
+            "cull" => Err("the amount of `cull` must be a fraction from 0 to 1".to_string()),
+            name => Err(format!("unknown action `{}`, expected one of {}", name, Self::NAMES.join(", "))),
+        }
+    }
+
+    /// The amount as it is written.
+    pub fn amount(&self) -> String {
+        match self {
+            Self::Feed(count) | Self::Spawn(count) => count.to_string(),
+            Self::Cull(fraction) => fraction.to_string(),
+        }
+    }
+}
+
+/// An action at a tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedEvent {
+    pub tick: u64,
+    pub action: Action,
+}
+
+impl TimedEvent {
+    /// How the event is marked on the statistics timeline.
+    pub fn label(&self) -> String {
+        match self.action {
+            Action::Feed(count) => format!("scenario: {} food", count),
+            Action::Spawn(count) => format!("scenario: {} blobs", count),
+            Action::Cull(fraction) => format!("scenario: cull {:.0}%", fraction * 100.),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scenario {
+    pub name: String,
+    pub description: String,
+    pub food: Schedule,
+    pub blobs: Schedule,
+    /// In order of their ticks.
+    pub events: Vec<TimedEvent>,
+    /// Where food is kept from growing, see `zones`.
+    pub zones: Vec<Zone>,
+    /// What named blobs set off, see `scripts`.
+    pub scripts: Vec<Script>,
+}
+
+impl Scenario {
+    /// The directory scenarios are looked up in by name.
+    pub const DIR: &'static str = "assets/scenarios";
+
+    /// Build a scenario from a parsed file.
+    pub fn from_table(table: &toml::Table) -> Result<Self, String> {
+        for key in table.keys() {
+            if !["name", "description", "food", "blobs", "events", "zones", "scripts"].contains(&key.as_str()) {
+                return Err(format!("unknown key `{}`", key));
+            }
+        }
+        let text = |key: &str| match table.get(key) {
+            None => Ok(String::new()),
+            Some(value) => value.as_str().map(str::to_string).ok_or_else(|| format!("`{}` must be a string", key)),
+        };
+        let schedule = |key: &str| match table.get(key) {
+            None => Ok(Schedule::default()),
+            Some(value) => Schedule::from_table(value).map_err(|e| format!("[{}]: {}", key, e)),
+        };
+        let events = match table.get("events") {
+            None => vec![],
+            Some(events) => events.as_array()
+                .ok_or("`events` must be [[tick, action, amount], ...]")?
+                .iter()
+                .enumerate()
+                .map(|(i, event)| match event.as_array().map(Vec::as_slice) {
+                    Some([tick, action, amount]) => {
+                        let event = || -> Result<_, String> {
+                            let tick = tick.as_f64().filter(|tick| *tick >= 0.).ok_or("the tick of an event must be a number of at least 0")?;
+                            let action = action.as_str().ok_or("the action of an event must be a string")?;
+                            let amount = amount.as_f64().ok_or("the amount of an event must be a number")?;
+                            Ok(TimedEvent { tick: tick as u64, action: Action::from_name(action, amount)? })
+                        };
+                        event().map_err(|e| format!("events[{}]: {}", i, e))
+                    },
+                    _ => Err(format!("events[{}]: an event must be [tick, action, amount]", i)),
+                })
+                .collect::<Result<Vec<_>, String>>()?,
+        };
+        let zones = match table.get("zones") {
+            None => vec![],
+            Some(zones) => zones.as_array()
+                .ok_or("`zones` must be [[x, y, width, height, rule], ...]")?
+                .iter()
+                .enumerate()
+                .map(|(i, zone)| Zone::from_value(zone).map_err(|e| format!("zones[{}]: {}", i, e)))
+                .collect::<Result<Vec<_>, String>>()?,
+        };
+        let scripts = match table.get("scripts") {
+            None => vec![],
+            Some(scripts) => scripts.as_array()
+                .ok_or("`scripts` must be [[blob, trigger, action, amount], ...]")?
+                .iter()
+                .enumerate()
+                .map(|(i, script)| Script::from_value(script).map_err(|e| format!("scripts[{}]: {}", i, e)))
+                .collect::<Result<Vec<_>, String>>()?,
+        };
+        let mut ret = Self {
+            name: text("name")?, description: text("description")?, food: schedule("food")?, blobs: schedule("blobs")?, events, zones, scripts,
+        };
+        ret.sort_events();
+        Ok(ret)
+    }
+
+    /// Put the events back in order after their ticks changed, keeping
+    /// the order of those at the same tick.
+    pub fn sort_events(&mut self) {
+        self.events.sort_by_key(|event| event.tick);
+    }
+
+    /// The events due at a tick.
+    pub fn events_at(&self, tick: u64) -> impl Iterator<Item = &TimedEvent> {
+        self.events.iter().filter(move |event| event.tick == tick)
+    }
+
+    /// The contents of the scenario's file.
+    pub fn to_toml(&self) -> String {
+        let mut ret = format!("name = {}\ndescription = {}\n", quote(&self.name), quote(&self.description));
+        if !self.events.is_empty() {
+            ret += "events = [\n";
+            for event in &self.events {
+                ret += &format!("    [{}, \"{}\", {}],\n", event.tick, event.action.name(), event.action.amount());
+            }
+            ret += "]\n";
+        }
+        if !self.zones.is_empty() {
+            ret += "zones = [\n";
+            for zone in &self.zones {
+                ret += &format!("    {},\n", zone.to_toml());
+            }
+            ret += "]\n";
+        }
+        if !self.scripts.is_empty() {
+            ret += "scripts = [\n";
+            for script in &self.scripts {
+                ret += &format!("    {},\n", script.to_toml());
+            }
+            ret += "]\n";
+        }
+        ret += &format!("\n[food]\n{}\n[blobs]\n{}", self.food.to_toml(), self.blobs.to_toml());
+        ret
+    }
+
+    pub fn save<P: AsRef<Path> + ?Sized>(&self, path: &P) -> io::Result<()> {
+        fs::write(path, self.to_toml())
+    }
+
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+        let table = toml::parse(&source).map_err(|e| LoadError::Parse(path.to_path_buf(), e))?;
+        Self::from_table(&table).map_err(|e| LoadError::Invalid(path.to_path_buf(), e))
+    }
+}
+
+/// The file of a scenario given by name or path.
+pub fn resolve(scenario: &str) -> PathBuf {
+    if scenario.ends_with(".toml") {
+        PathBuf::from(scenario)
+    } else {
+        Path::new(Scenario::DIR).join(format!("{}.toml", scenario))
+    }
+}
+
+/// The file for a new scenario, named so it overwrites none.
+pub fn untitled() -> PathBuf {
+    (1..)
+        .map(|i| resolve(&if i == 1 { "untitled".to_string() } else { format!("untitled-{}", i) }))
+        .find(|path| !path.exists())
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_schedules() {
+        let linear = Schedule::Linear { from: 1., to: 0., ticks: 100 };
+        assert_eq!((linear.rate(0), linear.rate(50), linear.rate(1000)), (1., 0.5, 0.));
+        let sine = Schedule::Sine { mean: 0.5, amplitude: 1., period: 100 };
+        assert!((sine.rate(25) - 1.5).abs() < 1e-4);
+        assert_eq!(sine.rate(75), 0., "clamped at 0");
+        let steps = Schedule::Steps(vec![(10, 2.), (20, 0.)]);
+        assert_eq!((steps.rate(0), steps.rate(10), steps.rate(25)), (1., 2., 0.));
+
+        let table = toml::parse("name = \"Bust\"\n[food]\nschedule = \"steps\"\nsteps = [[0, 1], [500, 0.2]]\n").unwrap();
+        let scenario = Scenario::from_table(&table).unwrap();
+        assert_eq!(scenario.food, Schedule::Steps(vec![(0, 1.), (500, 0.2)]));
+        assert_eq!(scenario.blobs, Schedule::Constant(1.));
+        let bad = |source: &str| Scenario::from_table(&toml::parse(source).unwrap()).unwrap_err();
+        assert_eq!(bad("[food]\nschedule = \"sine\"\nmean = 1\namplitude = 1\nperiod = 0"), "[food]: `period` must be at least 1 tick");
+        assert!(bad("[blobs]\nschedule = \"steps\"\nsteps = [[5, 1], [1, 1]]").contains("in order"));
+        assert!(bad("speed = 1").contains("unknown key"));
+        assert_eq!(resolve("famine"), Path::new("assets/scenarios/famine.toml"));
+        assert!(bad("events = [[0, \"feed\", 1], [10, \"cull\", 2]]").starts_with("events[1]: "));
+        assert!(bad("zones = [[0, 0, 10, 10, \"swamp\"]]").starts_with("zones[0]: "));
+
+        //  a scenario written out reads back the same, events in order
+        let mut scenario = Scenario {
+            name: "Dust \"bowl\"".to_string(),
+            blobs: Schedule::Sine { mean: 1., amplitude: 0.5, period: 600 },
+            events: vec![TimedEvent { tick: 900, action: Action::Cull(0.25) }, TimedEvent { tick: 300, action: Action::Feed(40) }],
+            zones: vec![Zone { rect: raylib::prelude::Rectangle::new(0., 10., 200., 50.5), rule: crate::zones::Rule::Desert }],
+            scripts: vec![Script { blob: "Greg".to_string(), trigger: crate::scripts::Trigger::Death, step: crate::scripts::Step::Message("Greg is \"gone\"".to_string()) }],
+            ..scenario
+        };
+        scenario.sort_events();
+        assert_eq!(scenario.events[0].tick, 300);
+        assert_eq!(Scenario::from_table(&toml::parse(&scenario.to_toml()).unwrap()), Ok(scenario.clone()));
+        assert_eq!(scenario.events_at(900).map(TimedEvent::label).collect::<Vec<_>>(), ["scenario: cull 25%"]);
+        for entry in fs::read_dir(Scenario::DIR).unwrap() {
+            let path = entry.unwrap().path();
+            Scenario::load(&path).unwrap_or_else(|e| panic!("{}", e));
+        }
+    }
+}
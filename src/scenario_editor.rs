@@ -0,0 +1,427 @@
+//! A panel for editing the timed events of a scenario while the world
+//! runs, for setting up experiments without writing files by hand.
+//!
+//! The timeline spans the ticks of the run, with the food and blob
+//! schedules drawn across it, the tick the world is at, and the
+//! events as markers. Clicking the timeline adds an event there, of
+//! the kind last edited, and clicking a marker selects it; dragging a
+//! marker moves it, snapped to `SNAP` ticks. The buttons move the
+//! selected event a little, change what it does and by how much, and
+//! delete it, the zones button arms a rule for zones, see `zones`, the
+//! chunk button arms cutting a chunk, see `chunk`, and the last saves
+//! the scenario to its file. While a rule is armed, dragging over a
+//! world marks a zone of it and clicking a zone takes it off, and the
+//! zones are drawn over the worlds while the editor is open. While
+//! cutting is armed, dragging over a world cuts the rectangle out to a
+//! chunk, once. Changes reach the worlds at once, so events not yet
+//! reached happen as edited.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut editor = ScenarioEditor::new(scenario::untitled(), Scenario::default());
+//! let captured = editor.captures(&frame, screen_height);
+//! match editor.update(&frame, sim.clock().tick(), screen_height, camera.screen_to_world(frame.mouse)) {
+//!     Some(Outcome::Changed) => world.scenario = Some(Arc::new(editor.scenario().clone())),
+//!     Some(Outcome::Saved(result)) => println!("{:?}", result),
+//!     Some(Outcome::Cut(rect)) => Chunk::cut(&sim, rect).save(chunk::DIR)?,
+//!     None => (),
+//! }
+//! ```
+
+use std::path::PathBuf;
+
+use raylib::prelude::*;
+
+use crate::{
+    window::DrawingContext,
+    fonts::Fonts,
+    input::{Button, Frame},
+    scenario::{Scenario, TimedEvent, Action},
+    simulation::Food,
+    theme::Theme,
+    zones::{Rule, Zone},
+};
+
+/// What the ticks of events snap to when they are placed.
+pub const SNAP: u64 = 60;
+/// The shortest span of the timeline, and what it grows by.
+const SPAN: u64 = 18000;
+const SPAN_STEP: u64 = 3600;
+const COUNT_STEP: u32 = 10;
+/// How far a zone must be dragged to be marked, rather than clicked to be taken off.
+const MIN_ZONE: f32 = 4.;
+
+const WIDTH: f32 = 480.;
+const PADDING: f32 = 8.;
+const TIMELINE_HEIGHT: f32 = 60.;
+const BUTTON_HEIGHT: f32 = 18.;
+const MARKER_RADIUS: f32 = 4.;
+const FONT_SIZE: i32 = 10;
+
+/// The buttons under the timeline, from the left.
+const BUTTONS: [&str; 9] = ["<", ">", "kind", "-", "+", "delete", "zones", "chunk", "save"];
+
+/// What an update did.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// The scenario changed, and should reach the worlds.
+    Changed,
+    /// The scenario was saved to a file, or why not.
+    Saved(Result<PathBuf, String>),
+    /// A rectangle of the world was dragged over to cut out as a chunk.
+    Cut(Rectangle),
+}
+
+#[derive(Debug, Clone)]
+pub struct ScenarioEditor {
+    path: PathBuf,
+    scenario: Scenario,
+    selected: Option<usize>,
+    dragging: bool,
+    /// What events added on the timeline do.
+    last: Action,
+    /// Whether there are changes that are not saved.
+    unsaved: bool,
+    /// The rule of the zones dragging over a world marks, if one is armed.
+    marking: Option<Rule>,
+    /// Whether dragging over a world cuts a chunk of it.
+    cutting: bool,
+    /// Where in the world the zone or chunk being dragged was started.
+    zone_start: Option<Vector2>,
+}
+
+impl ScenarioEditor {
+    /// Edit a scenario, saving it to a path.
+    pub fn new(path: PathBuf, scenario: Scenario) -> Self {
+        Self { path, scenario, selected: None, dragging: false, last: Action::Feed(2 * COUNT_STEP), unsaved: false, marking: None, cutting: false, zone_start: None }
+    }
+
+    pub fn scenario(&self) -> &Scenario { &self.scenario }
+
+    /// Where the panel is, at the bottom right of the tuning panel.
+    pub fn rect(screen_height: i32) -> Rectangle {
+        let height = 3. * PADDING + 2. * 14. + TIMELINE_HEIGHT + 14. + BUTTON_HEIGHT;
+        Rectangle::new(320., screen_height as f32 - 110. - height, WIDTH, height)
+    }
+
+    fn timeline(screen_height: i32) -> Rectangle {
+        let rect = Self::rect(screen_height);
+        Rectangle::new(rect.x + PADDING, rect.y + PADDING + 14., rect.width - 2. * PADDING, TIMELINE_HEIGHT)
+    }
+
+    fn button(i: usize, screen_height: i32) -> Rectangle {
+        let timeline = Self::timeline(screen_height);
+        let width = (timeline.width - (BUTTONS.len() - 1) as f32 * 4.) / BUTTONS.len() as f32;
+        Rectangle::new(timeline.x + i as f32 * (width + 4.), timeline.y + timeline.height + PADDING + 14. + PADDING, width, BUTTON_HEIGHT)
+    }
+
+    /// How many ticks the timeline spans: enough for the events and
+    /// the tick the world is at, with room to add more.
+    fn span(&self, tick: u64) -> u64 {
+        let latest = self.scenario.events.iter().map(|event| event.tick).chain([tick]).max().unwrap_or(0);
+        SPAN.max((latest / SPAN_STEP + 1) * SPAN_STEP)
+    }
+
+    /// The tick at a position on the timeline, snapped.
+    fn tick_at(&self, x: f32, tick: u64, screen_height: i32) -> u64 {
+        let timeline = Self::timeline(screen_height);
+        let t = ((x - timeline.x) / timeline.width).clamp(0., 1.);
+        ((t * self.span(tick) as f32 / SNAP as f32).round() as u64) * SNAP
+    }
+
+    fn marker_x(&self, event: &TimedEvent, tick: u64, screen_height: i32) -> f32 {
+        let timeline = Self::timeline(screen_height);
+        timeline.x + event.tick as f32 / self.span(tick) as f32 * timeline.width
+    }
+
+    /// Whether the panel takes the mouse in this frame, leaving it to no gesture.
+    pub fn captures(&self, frame: &Frame, screen_height: i32) -> bool {
+        self.dragging || self.zone_start.is_some()
+            || (frame.button == Button::Pressed && (self.marking.is_some() || self.cutting || Self::rect(screen_height).check_collision_point_rec(frame.mouse)))
+    }
+
+    /// The zones of the scenario, and the one being marked up to where
+    /// the mouse is in the world.
+    pub fn zones(&self, world_pos: Vector2) -> Vec<Zone> {
+        let marked = self.zone_start.zip(self.marking).map(|(start, rule)| Zone::between(start, world_pos, rule));
+        self.scenario.zones.iter().copied().chain(marked).collect()
+    }
+
+    /// Read this frame's input, with the world at a tick and the mouse
+    /// at a place in the world.
+    pub fn update(&mut self, frame: &Frame, tick: u64, screen_height: i32, world_pos: Vector2) -> Option<Outcome> {
+        if let Some(start) = self.zone_start {
+            if frame.is_button_down() { return None; }
+            self.zone_start = None;
+            if self.cutting {
+                self.cutting = false;
+                let (a, b) = (start, world_pos);
+                let rect = Rectangle::new(a.x.min(b.x), a.y.min(b.y), (a.x - b.x).abs(), (a.y - b.y).abs());
+                return Some(Outcome::Cut(rect)).filter(|_| (world_pos - start).length() >= MIN_ZONE);
+            }
+            return self.mark(start, world_pos);
+        }
+        if self.dragging {
+            let selected = self.selected?;
+            let moved = self.tick_at(frame.mouse.x, tick, screen_height);
+            if !frame.is_button_down() {
+                self.dragging = false;
+                self.sort();
+            }
+            if self.scenario.events[selected].tick == moved { return None; }
+            self.scenario.events[selected].tick = moved;
+            return Some(self.changed());
+        }
+        if frame.button != Button::Pressed { return None; }
+
+        let timeline = Self::timeline(screen_height);
+        if timeline.check_collision_point_rec(frame.mouse) {
+            let nearest = self.scenario.events.iter()
+                .enumerate()
+                .map(|(i, event)| (i, (self.marker_x(event, tick, screen_height) - frame.mouse.x).abs()))
+                .filter(|&(_, distance)| distance <= MARKER_RADIUS + 2.)
+                .min_by(|a, b| a.1.total_cmp(&b.1));
+            self.dragging = true;
+            if let Some((i, _)) = nearest {
+                self.selected = Some(i);
+                return None;
+            }
+            self.scenario.events.push(TimedEvent { tick: self.tick_at(frame.mouse.x, tick, screen_height), action: self.last });
+            self.selected = Some(self.scenario.events.len() - 1);
+            return Some(self.changed());
+        }
+
+        if !Self::rect(screen_height).check_collision_point_rec(frame.mouse) {
+            if self.marking.is_some() || self.cutting {
+                self.zone_start = Some(world_pos);
+            }
+            return None;
+        }
+        let button = (0..BUTTONS.len()).find(|&i| Self::button(i, screen_height).check_collision_point_rec(frame.mouse))?;
+        if BUTTONS[button] == "zones" {
+            self.marking = match self.marking {
+                None => Some(Rule::ALL[0]),
+                Some(rule) => Rule::ALL.iter().position(|&r| r == rule).and_then(|i| Rule::ALL.get(i + 1)).copied(),
+            };
+            self.cutting = false;
+            return None;
+        }
+        if BUTTONS[button] == "chunk" {
+            self.cutting = !self.cutting;
+            self.marking = None;
+            return None;
+        }
+        if BUTTONS[button] == "save" {
+            self.sort();
+            return Some(Outcome::Saved(match self.scenario.save(&self.path) {
+                Ok(()) => {
+                    self.unsaved = false;
+                    Ok(self.path.clone())
+                },
+                Err(e) => Err(format!("{}: {}", self.path.display(), e)),
+            }));
+        }
+        let selected = self.selected?;
+        let event = &mut self.scenario.events[selected];
+        match BUTTONS[button] {
+            "<" => event.tick = event.tick.saturating_sub(SNAP),
+            ">" => event.tick += SNAP,
+            "kind" => event.action = match event.action {
+                Action::Feed(count) => Action::Spawn(count),
+                Action::Spawn(_) => Action::Cull(0.5),
+                Action::Cull(_) => Action::Feed(2 * COUNT_STEP),
+            },
+            "-" | "+" => {
+                let more = BUTTONS[button] == "+";
+                event.action = match event.action {
+                    Action::Feed(count) => Action::Feed(if more { count + COUNT_STEP } else { count.saturating_sub(COUNT_STEP).max(1) }),
+                    Action::Spawn(count) => Action::Spawn(if more { count + COUNT_STEP } else { count.saturating_sub(COUNT_STEP).max(1) }),
+                    //  in tenths, without the drift of adding floats
+                    Action::Cull(fraction) => {
+                        let tenths = (fraction * 10.).round() + if more { 1. } else { -1. };
+                        Action::Cull(tenths.clamp(1., 10.) / 10.)
+                    },
+                };
+            },
+            "delete" => {
+                self.scenario.events.remove(selected);
+                self.selected = None;
+                return Some(self.changed());
+            },
+            _ => unreachable!(),
+        }
+        self.last = event.action;
+        self.sort();
+        Some(self.changed())
+    }
+
+    /// Mark a zone dragged between two places of the world, or take
+    /// off the last zone at a place clicked.
+    fn mark(&mut self, start: Vector2, end: Vector2) -> Option<Outcome> {
+        if (end - start).length() >= MIN_ZONE {
+            self.scenario.zones.push(Zone::between(start, end, self.marking?));
+        } else {
+            let i = self.scenario.zones.iter().rposition(|zone| zone.rect.check_collision_point_rec(end))?;
+            self.scenario.zones.remove(i);
+        }
+        Some(self.changed())
+    }
+
+    fn changed(&mut self) -> Outcome {
+        self.unsaved = true;
+        Outcome::Changed
+    }
+
+    /// Put the events in order, keeping the selected one selected.
+    fn sort(&mut self) {
+        let selected = self.selected.map(|i| self.scenario.events[i]);
+        self.scenario.sort_events();
+        self.selected = selected.and_then(|selected| self.scenario.events.iter().position(|event| *event == selected));
+    }
+
+    pub fn draw(&self, draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, tick: u64) {
+        let screen_height = draw.get_screen_height();
+        let rect = Self::rect(screen_height);
+        draw.draw_rectangle_rec(rect, theme.panel);
+        draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+        let file = self.path.file_name().map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+        let title = format!("Scenario {}{} (shift K to close)", file, if self.unsaved { "*" } else { "" });
+        fonts.draw_text(draw, &title, (rect.x + PADDING) as i32, (rect.y + PADDING) as i32, FONT_SIZE, theme.text);
+
+        let timeline = Self::timeline(screen_height);
+        let span = self.span(tick);
+        draw.draw_rectangle_rec(timeline, theme.background);
+        for step in (SPAN_STEP..span).step_by(SPAN_STEP as usize) {
+            let x = timeline.x + step as f32 / span as f32 * timeline.width;
+            draw.draw_line_v(Vector2::new(x, timeline.y), Vector2::new(x, timeline.y + timeline.height), theme.panel_border);
+        }
+        //  the schedules, on a scale from none to twice the usual rate or more
+        let schedules = [(&self.scenario.food, Food::COLOR), (&self.scenario.blobs, theme.ok)];
+        let samples = timeline.width as usize / 4;
+        let at = |i: usize| i as u64 * span / samples as u64;
+        let top = schedules.iter().flat_map(|(schedule, _)| (0..=samples).map(move |i| schedule.rate(at(i)))).fold(2., f32::max);
+        for (schedule, color) in schedules {
+            let point = |i: usize| Vector2::new(
+                timeline.x + i as f32 / samples as f32 * timeline.width,
+                timeline.y + timeline.height * (1. - schedule.rate(at(i)) / top),
+            );
+            for i in 0..samples {
+                draw.draw_line_v(point(i), point(i + 1), color);
+            }
+        }
+        let now = timeline.x + tick.min(span) as f32 / span as f32 * timeline.width;
+        draw.draw_line_v(Vector2::new(now, timeline.y), Vector2::new(now, timeline.y + timeline.height), theme.accent);
+        for (i, event) in self.scenario.events.iter().enumerate() {
+            let x = self.marker_x(event, tick, screen_height);
+            let color = if self.selected == Some(i) { theme.accent } else { action_color(theme, &event.action) };
+            draw.draw_line_v(Vector2::new(x, timeline.y + 2. * MARKER_RADIUS), Vector2::new(x, timeline.y + timeline.height), color);
+            draw.draw_circle_v(Vector2::new(x, timeline.y + MARKER_RADIUS), MARKER_RADIUS, color);
+        }
+        draw.draw_rectangle_lines_ex(timeline, 1, theme.panel_border);
+
+        let details = match self.selected.map(|i| &self.scenario.events[i]) {
+            Some(event) => format!("tick {}: {} {}", event.tick, event.action.name(), event.action.amount()),
+            None => format!("{} events over {} ticks; click the timeline to add one", self.scenario.events.len(), span),
+        };
+        let details = match self.marking {
+            Some(rule) => format!("drag over the world to mark a {} zone, click one to take it off", rule.name()),
+            None if self.cutting => "drag over the world to cut out a chunk of it".to_string(),
+            None => details,
+        };
+        let y = (timeline.y + timeline.height + PADDING) as i32;
+        fonts.draw_text(draw, &details, timeline.x as i32, y, FONT_SIZE, theme.text);
+        for (i, label) in BUTTONS.iter().enumerate() {
+            let button = Self::button(i, screen_height);
+            let enabled = matches!(*label, "save" | "zones" | "chunk") || self.selected.is_some();
+            draw.draw_rectangle_lines_ex(button, 1, theme.panel_border);
+            let label = match (*label, self.selected) {
+                ("kind", Some(i)) => self.scenario.events[i].action.name(),
+                ("zones", _) => self.marking.map_or("zones", |rule| rule.name()),
+                ("chunk", _) if self.cutting => "cutting",
+                (label, _) => label,
+            };
+            let x = button.x as i32 + (button.width as i32 - fonts.measure(label, FONT_SIZE)) / 2;
+            fonts.draw_text(draw, label, x, button.y as i32 + 4, FONT_SIZE, if enabled { theme.text } else { theme.muted_text });
+        }
+    }
+}
+
+/// The color of the markers of an action.
+fn action_color(theme: &Theme, action: &Action) -> Color {
+    match action {
+        Action::Feed(_) => Food::COLOR,
+        Action::Spawn(_) => theme.ok,
+        Action::Cull(_) => theme.error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Schedule;
+
+    #[test]
+    fn test_scenario_editor() {
+        let path = std::env::temp_dir().join(format!("blobs-scenario-{}.toml", std::process::id()));
+        let mut editor = ScenarioEditor::new(path.clone(), Scenario { blobs: Schedule::Constant(0.5), ..Scenario::default() });
+        let click = |pos: Vector2| {
+            let mut frame = Frame::idle(0., pos);
+            frame.button = Button::Pressed;
+            frame
+        };
+        let release = |pos: Vector2| Frame::idle(0., pos);
+
+        //  a click halfway along the timeline adds an event there
+        let timeline = ScenarioEditor::timeline(720);
+        let middle = Vector2::new(timeline.x + timeline.width / 2., timeline.y + 20.);
+        assert!(editor.captures(&click(middle), 720));
+        assert_eq!(editor.update(&click(middle), 0, 720, Vector2::zero()), Some(Outcome::Changed));
+        assert_eq!(editor.update(&release(middle), 0, 720, Vector2::zero()), None);
+        assert_eq!(editor.scenario().events, [TimedEvent { tick: SPAN / 2, action: Action::Feed(20) }]);
+
+        //  dragging its marker moves it
+        let later = Vector2::new(timeline.x + timeline.width * 0.75, middle.y);
+        editor.update(&click(middle), 0, 720, Vector2::zero());
+        assert_eq!(editor.update(&release(later), 0, 720, Vector2::zero()), Some(Outcome::Changed));
+        assert_eq!(editor.scenario().events[0].tick, SPAN * 3 / 4);
+        assert!(!editor.captures(&release(later), 720));
+
+        let press = |editor: &mut ScenarioEditor, label: &str| {
+            let i = BUTTONS.iter().position(|button| *button == label).unwrap();
+            let button = ScenarioEditor::button(i, 720);
+            editor.update(&click(Vector2::new(button.x + 2., button.y + 2.)), 0, 720, Vector2::zero())
+        };
+        press(&mut editor, "kind");
+        press(&mut editor, "kind");
+        press(&mut editor, "+");
+        press(&mut editor, "<");
+        assert_eq!(editor.scenario().events[0], TimedEvent { tick: SPAN * 3 / 4 - SNAP, action: Action::Cull(0.6) });
+
+        let saved = press(&mut editor, "save");
+        assert_eq!(saved, Some(Outcome::Saved(Ok(path.clone()))));
+        assert_eq!(Scenario::load(&path).unwrap(), *editor.scenario());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(press(&mut editor, "delete"), Some(Outcome::Changed));
+        assert!(editor.scenario().events.is_empty());
+        assert_eq!(press(&mut editor, "+"), None, "nothing selected");
+
+        //  with a rule armed, dragging over the world marks a zone and clicking it takes it off
+        assert_eq!(press(&mut editor, "zones"), None);
+        let outside = Vector2::new(10., 10.);
+        assert!(editor.captures(&click(outside), 720));
+        editor.update(&click(outside), 0, 720, Vector2::new(100., 50.));
+        assert_eq!(editor.zones(Vector2::new(150., 150.)).len(), 1);
+        assert_eq!(editor.update(&release(outside), 0, 720, Vector2::new(40., 150.)), Some(Outcome::Changed));
+        assert_eq!(editor.scenario().zones, [Zone { rect: Rectangle::new(40., 50., 60., 100.), rule: Rule::Desert }]);
+        editor.update(&click(outside), 0, 720, Vector2::new(60., 60.));
+        assert_eq!(editor.update(&release(outside), 0, 720, Vector2::new(61., 60.)), Some(Outcome::Changed));
+        assert!(editor.scenario().zones.is_empty());
+
+        //  with cutting armed, dragging over the world cuts a chunk once
+        assert_eq!(press(&mut editor, "chunk"), None);
+        editor.update(&click(outside), 0, 720, Vector2::new(100., 50.));
+        assert_eq!(editor.update(&release(outside), 0, 720, Vector2::new(40., 150.)), Some(Outcome::Cut(Rectangle::new(40., 50., 60., 100.))));
+        assert!(!editor.captures(&click(outside), 720) && editor.scenario().zones.is_empty());
+    }
+}
@@ -0,0 +1,138 @@
+//! A screensaver of the living world, with `--screensaver`.
+//!
+//! The window opens fullscreen with the interface hidden, and the
+//! camera drifts from one place where blobs crowd to the next, easing
+//! between them and lingering at each for `DWELL` seconds. Places are
+//! the peaks of the blobs' density, visited busiest first and never
+//! the same one twice in a row. A world where every blob died is
+//! seeded anew. Any key, click or scroll, or moving the mouse more
+//! than `WAKE_DISTANCE` pixels, ends the program.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut screensaver = Screensaver::new(sim.size() / 2.);
+//! if screensaver.woken(&frame) {
+//!     shutdown::request();
+//! }
+//! let (center, zoom) = screensaver.update(&sim, frame_seconds);
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{input::Frame, simulation::Simulation, spatial::Density, tour::{Tour, Keyframe}};
+
+/// Seconds between one place and the next.
+const DWELL: f32 = 12.;
+/// Seconds the camera takes to move to the next place.
+const TRAVEL: f32 = 5.;
+/// How far in places are seen.
+const ZOOM: f32 = 2.;
+/// How far the mouse moves before it ends the screensaver.
+const WAKE_DISTANCE: f32 = 20.;
+/// The standard deviation of the density places are found in.
+const BANDWIDTH: f32 = 60.;
+
+/// The places blobs crowd most, busiest first: the cells of their
+/// density higher than those around them.
+pub fn hotspots(sim: &Simulation) -> Vec<Vector2> {
+    let points: Vec<_> = sim.blobs().map(|(_, blob)| blob.pos()).collect();
+    let density = Density::estimate(&points, sim.size(), BANDWIDTH);
+    let mut peaks = vec![];
+    for row in 0..density.rows {
+        for column in 0..density.columns {
+            let value = density.value(column, row);
+            let neighbors = (row.saturating_sub(1)..(row + 2).min(density.rows))
+                .flat_map(|y| (column.saturating_sub(1)..(column + 2).min(density.columns)).map(move |x| (x, y)))
+                .filter(|&neighbor| neighbor != (column, row));
+            //  of equal neighbors only the first is a peak
+            let peak = neighbors.into_iter().all(|(x, y)| {
+                let other = density.value(x, y);
+                other < value || other == value && (y, x) > (row, column)
+            });
+            if value > 0. && peak {
+                peaks.push((value, (Vector2::new(column as f32, row as f32) + Vector2::new(0.5, 0.5)) * density.cell));
+            }
+        }
+    }
+    peaks.sort_by(|a, b| b.0.total_cmp(&a.0));
+    peaks.into_iter().map(|(_, pos)| pos).collect()
+}
+
+pub struct Screensaver {
+    //  the way to the current place, timed from when it was set out on
+    leg: Tour,
+    time: f32,
+    //  where the mouse was when the screensaver started
+    mouse: Option<Vector2>,
+}
+
+impl Screensaver {
+    /// Start looking at a place.
+    pub fn new(center: Vector2) -> Self {
+        let mut leg = Tour::new();
+        leg.push(Keyframe { time: 0., center, zoom: ZOOM }).unwrap();
+        Self { leg, time: 0., mouse: None }
+    }
+
+    /// Move on by some seconds, returning the middle of the view and
+    /// the zoom.
+    pub fn update(&mut self, sim: &Simulation, seconds: f32) -> (Vector2, f32) {
+        self.time += seconds;
+        let (center, zoom) = self.leg.sample(self.time).unwrap_or_else(|| {
+            let last = self.leg.keyframes().last().unwrap();
+            (last.center, last.zoom)
+        });
+        if self.time >= TRAVEL + DWELL {
+            //  the busiest place that is not this one, or this one if there is no other
+            let next = hotspots(sim).into_iter().find(|&place| (place - center).length() > BANDWIDTH).unwrap_or(center);
+            self.leg = Tour::new();
+            self.leg.push(Keyframe { time: 0., center, zoom }).unwrap();
+            self.leg.push(Keyframe { time: TRAVEL, center: next, zoom: ZOOM }).unwrap();
+            self.time = 0.;
+        }
+        (center, zoom)
+    }
+
+    /// Whether some input of a frame ends the screensaver.
+    pub fn woken(&mut self, frame: &Frame) -> bool {
+        let start = *self.mouse.get_or_insert(frame.mouse);
+        frame.is_any_key_pressed() || frame.is_button_down() || frame.wheel != 0. || !frame.typed.is_empty()
+            || (frame.mouse - start).length() > WAKE_DISTANCE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_screensaver() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(1000., 1000.)));
+        assert!(hotspots(&sim).is_empty());
+        for i in 0..5 {
+            sim.insert_blob(BlobConfig::new(Vector2::new(200. + i as f32, 200.)));
+        }
+        sim.insert_blob(BlobConfig::new(Vector2::new(800., 700.)));
+        let places = hotspots(&sim);
+        assert_eq!(places.len(), 2);
+        assert!((places[0] - Vector2::new(200., 200.)).length() < 20., "{:?}", places);
+        assert!((places[1] - Vector2::new(800., 700.)).length() < 20., "{:?}", places);
+
+        //  the camera stays, then sets out for the busiest place
+        let mut screensaver = Screensaver::new(Vector2::new(500., 500.));
+        assert_eq!(screensaver.update(&sim, 1.), (Vector2::new(500., 500.), ZOOM));
+        screensaver.update(&sim, TRAVEL + DWELL);
+        let (center, _) = screensaver.update(&sim, TRAVEL);
+        assert!((center - places[0]).length() < 1e-3);
+
+        let mut frame = Frame::idle(0., Vector2::new(100., 100.));
+        assert!(!screensaver.woken(&frame));
+        frame.mouse = Vector2::new(105., 100.);
+        assert!(!screensaver.woken(&frame));
+        frame.mouse = Vector2::new(150., 100.);
+        assert!(screensaver.woken(&frame));
+    }
+}
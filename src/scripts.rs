@@ -0,0 +1,211 @@
+//! Scripts a scenario attaches to blobs by name, for narrative and
+//! tutorial beats, as in "when Greg dies, show a message and spawn
+//! five rivals".
+//!
+//! A scenario lists its scripts as `scripts = [[blob, trigger, action,
+//! amount], ...]`, each step run when the blob of the name does what
+//! its trigger says:
+//!
+//! * `"on_birth"` when a blob of the name appears in the world,
+//!   born or spawned
+//! * `"on_eat"` when it eats, food, a corpse or a blob
+//! * `"on_death"` when it is gone, killed, starved or removed
+//!
+//! The actions are those of the scenario's timed events, `"feed"`,
+//! `"spawn"` and `"cull"`, see `scenario`, and `"message"` with a text
+//! instead of an amount, which is shown over the world and marked on
+//! the statistics timeline. Steps of the same blob and trigger run in
+//! the order they are listed.
+//!
+//! ```toml
+//! scripts = [
+//!     ["Greg", "on_death", "message", "Greg is gone. His rivals move in."],
+//!     ["Greg", "on_death", "spawn", 5],
+//! ]
+//! ```
+//!
+//! The scripts watch the world after every tick rather than running
+//! inside it, see `Runner`, so worlds with the same seed and scenario
+//! run them the same.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::{scenario::Scenario, scripts::Runner};
+//! # let scenario = Scenario::default();
+//! let mut runner = Runner::default();
+//! let tick = sim.clock().tick();
+//! sim.step();
+//! for step in runner.fire(&scenario.scripts, &sim, tick) {
+//!     println!("{:?}", step);
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    keyed_set::Key,
+    narrator::quote,
+    scenario::Action,
+    simulation::{Blob, Event, Simulation},
+    toml::Value,
+};
+
+/// What a blob does that runs a script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger {
+    Birth,
+    Eat,
+    Death,
+}
+
+impl Trigger {
+    pub const ALL: [Self; 3] = [Self::Birth, Self::Eat, Self::Death];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Birth => "on_birth",
+            Self::Eat => "on_eat",
+            Self::Death => "on_death",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|trigger| trigger.name() == name)
+    }
+}
+
+/// What a script does.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Step {
+    /// Show a text and mark it on the timeline.
+    Message(String),
+    /// Do what a timed event of a scenario does.
+    Act(Action),
+}
+
+/// A step run when a named blob does something, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    pub blob: String,
+    pub trigger: Trigger,
+    pub step: Step,
+}
+
+impl Script {
+    /// A script from its entry in a scenario, `[blob, trigger, action, amount]`.
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        match value.as_array().map(Vec::as_slice) {
+            Some([blob, trigger, action, amount]) => {
+                let blob = blob.as_str().filter(|blob| !blob.is_empty()).ok_or("the blob of a script must be its name")?;
+                let trigger = trigger.as_str().ok_or("the trigger of a script must be a string")?;
+                let trigger = Trigger::from_name(trigger)
+                    .ok_or_else(|| format!("unknown trigger `{}`, expected on_birth, on_eat or on_death", trigger))?;
+                let step = match action.as_str().ok_or("the action of a script must be a string")? {
+                    "message" => Step::Message(amount.as_str().ok_or("the text of a message must be a string")?.to_string()),
+                    action => Step::Act(Action::from_name(action, amount.as_f64().ok_or("the amount of a script must be a number")?)?),
+                };
+                Ok(Self { blob: blob.to_string(), trigger, step })
+            },
+            _ => Err("a script must be [blob, trigger, action, amount]".to_string()),
+        }
+    }
+
+    /// The script as its entry in a scenario.
+    pub fn to_toml(&self) -> String {
+        let (action, amount) = match &self.step {
+            Step::Message(text) => ("message", quote(text)),
+            Step::Act(action) => (action.name(), action.amount()),
+        };
+        format!("[{}, \"{}\", \"{}\", {}]", quote(&self.blob), self.trigger.name(), action, amount)
+    }
+}
+
+/// Watches the blobs that scripts are attached to, firing the scripts
+/// as they are born, eat and die.
+#[derive(Debug, Clone, Default)]
+pub struct Runner {
+    /// The watched blobs alive after the last tick, with their names
+    /// and how much they had eaten.
+    watched: HashMap<Key<Blob>, (String, u32)>,
+}
+
+impl Runner {
+    /// Look at the world after the ticks since one, returning the steps
+    /// of the scripts that fire, in the order they are listed.
+    pub fn fire(&mut self, scripts: &[Script], sim: &Simulation, since: u64) -> Vec<Step> {
+        if scripts.is_empty() {
+            return vec![];
+        }
+        let names: HashSet<&str> = scripts.iter().map(|script| script.blob.as_str()).collect();
+        let mut fired: Vec<(&str, Trigger)> = vec![];
+        //  blobs that eat blobs eat what they kill
+        let killers: Vec<&str> = sim.events_since(since)
+            .filter_map(|(_, event)| match event { Event::Killed { killer: Some(killer), .. } => Some(killer.as_str()), _ => None })
+            .collect();
+        let mut alive = HashMap::new();
+        for (key, blob) in sim.blobs() {
+            let name = match blob.name.as_deref() {
+                Some(name) if names.contains(name) => name,
+                _ => continue,
+            };
+            match self.watched.get(&key) {
+                None => fired.push((name, Trigger::Birth)),
+                Some(&(_, ate)) if blob.foods_eaten > ate => fired.push((name, Trigger::Eat)),
+                Some(_) if blob.diet.eats_blobs() && killers.contains(&name) => fired.push((name, Trigger::Eat)),
+                Some(_) => (),
+            }
+            alive.insert(key, (name.to_string(), blob.foods_eaten));
+        }
+        for (key, (name, _)) in &self.watched {
+            if !alive.contains_key(key) {
+                fired.push((name, Trigger::Death));
+            }
+        }
+        let ret = scripts.iter()
+            .flat_map(|script| {
+                //  as many times as blobs of the name did it
+                let times = fired.iter().filter(|&&(name, trigger)| name == script.blob && trigger == script.trigger).count();
+                std::iter::repeat_n(script.step.clone(), times)
+            })
+            .collect();
+        self.watched = alive;
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::Vector2;
+    use crate::{simulation::{BlobConfig, SimulationConfig}, toml};
+
+    #[test]
+    fn test_scripts() {
+        let table = toml::parse(r#"scripts = [["Greg", "on_death", "message", "Greg is gone"], ["Greg", "on_death", "spawn", 5], ["Ada", "on_eat", "feed", 2]]"#).unwrap();
+        let scripts: Vec<_> = table["scripts"].as_array().unwrap().iter().map(|script| Script::from_value(script).unwrap()).collect();
+        assert_eq!(scripts[1], Script { blob: "Greg".to_string(), trigger: Trigger::Death, step: Step::Act(Action::Spawn(5)) });
+        let written = toml::parse(&format!("script = {}", scripts[0].to_toml())).unwrap();
+        assert_eq!(Script::from_value(&written["script"]), Ok(scripts[0].clone()));
+        let bad = |source: &str| Script::from_value(&toml::parse(&format!("script = {}", source)).unwrap()["script"]).unwrap_err();
+        assert!(bad(r#"["Greg", "on_sneeze", "spawn", 1]"#).contains("unknown trigger"));
+        assert!(bad(r#"["Greg", "on_death", "message", 1]"#).contains("text"));
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let mut runner = Runner::default();
+        let named = |name: &str, x: f32| BlobConfig { name: Some(name.to_string()), ..BlobConfig::new(Vector2::new(x, 100.)) };
+        let greg = sim.insert_blob(named("Greg", 50.));
+        let ada = sim.insert_blob(named("Ada", 250.));
+        //  births fire no scripts unless some are on birth
+        assert!(runner.fire(&scripts, &sim, 0).is_empty());
+        sim.get_blob_mut(ada).unwrap().foods_eaten += 1;
+        assert_eq!(runner.fire(&scripts, &sim, 0), [Step::Act(Action::Feed(2))]);
+        assert!(runner.fire(&scripts, &sim, 0).is_empty());
+        sim.remove_blob(greg);
+        assert_eq!(runner.fire(&scripts, &sim, 0), [Step::Message("Greg is gone".to_string()), Step::Act(Action::Spawn(5))]);
+        assert!(runner.fire(&scripts, &sim, 0).is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+//! What a single selected blob perceives, counted, for telling why it
+//! does what it does, such as not eating the food beside it.
+//!
+//! Under the strip of `vision`, a panel counts what the blob saw when
+//! it last looked around, as its brain was shown it, and how far the
+//! nearest of each is, refreshed every frame:
+//!
+//! * food, and corpses apart from it
+//! * prey, blobs it would kill in a fight now, if it eats blobs
+//! * predators, blobs that eat blobs and would kill it in a fight now
+//! * kin, blobs of its species that are neither
+//! * others
+//!
+//! A blob that would both kill and be killed counts as a predator. The
+//! panel ends with what the diet of the blob lets it eat, as food
+//! out of reach of a carnivore is seen but never eaten.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let senses = Senses::of(&sim, selected);
+//! println!("{} food, the nearest {:?} away", senses.get(Kind::Food).count, senses.get(Kind::Food).nearest);
+//! senses::draw(&mut draw, &theme, &fonts, blob, &senses, Vector2::new(300., 110.));
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    fonts::Fonts,
+    keyed_set::Key,
+    simulation::{Blob, CircleObject, Simulation},
+    theme::Theme,
+};
+
+const WIDTH: f32 = 250.;
+const PADDING: f32 = 10.;
+const FONT_SIZE: i32 = 16;
+const ROW_HEIGHT: i32 = 16;
+const SMALL_FONT_SIZE: i32 = 12;
+
+/// What a thing seen is to the blob, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Food,
+    Corpse,
+    Prey,
+    Predator,
+    Kin,
+    Other,
+}
+
+impl Kind {
+    pub const ALL: [Self; 6] = [Self::Food, Self::Corpse, Self::Prey, Self::Predator, Self::Kin, Self::Other];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Food => "Food",
+            Self::Corpse => "Corpses",
+            Self::Prey => "Prey",
+            Self::Predator => "Predators",
+            Self::Kin => "Kin",
+            Self::Other => "Others",
+        }
+    }
+
+    /// What another blob is to a blob, by who would win a fight now,
+    /// as a tick decides it.
+    pub fn of(blob: &Blob, other: &Blob) -> Self {
+        let kills = |attacker: &Blob, defender: &Blob| attacker.attack > defender.defence * (1. - defender.hunger / defender.max_hunger);
+        if other.diet.eats_blobs() && kills(other, blob) {
+            Self::Predator
+        } else if blob.diet.eats_blobs() && kills(blob, other) {
+            Self::Prey
+        } else if blob.species.is_some() && other.species == blob.species {
+            Self::Kin
+        } else {
+            Self::Other
+        }
+    }
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|&kind| kind == self).unwrap()
+    }
+}
+
+/// How many of a kind were seen, and how far the nearest is.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Count {
+    pub count: usize,
+    /// From the middle of the blob to the middle of the nearest.
+    pub nearest: Option<f32>,
+}
+
+/// What a blob perceives, see the module.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Senses {
+    counts: [Count; 6],
+}
+
+impl Senses {
+    /// What a blob of a world perceives, nothing if it is gone or has
+    /// not looked around yet.
+    pub fn of(sim: &Simulation, key: Key<Blob>) -> Self {
+        let mut ret = Self::default();
+        let blob = match sim.get_blob(key) {
+            Some(blob) => blob,
+            None => return ret,
+        };
+        for object in sim.seen(key) {
+            let (kind, pos) = match object {
+                CircleObject::Food(food) => match sim.get_food(food) {
+                    Some(food) => (if food.is_corpse() { Kind::Corpse } else { Kind::Food }, food.pos()),
+                    None => continue,
+                },
+                CircleObject::Blob(other) if other != key => match sim.get_blob(other) {
+                    Some(other) => (Kind::of(blob, other), other.pos()),
+                    None => continue,
+                },
+                CircleObject::Blob(_) | CircleObject::BlobSight(_) => continue,
+            };
+            let count = &mut ret.counts[kind.index()];
+            let distance = (pos - blob.pos()).length();
+            count.count += 1;
+            count.nearest = Some(count.nearest.map_or(distance, |nearest| nearest.min(distance)));
+        }
+        ret
+    }
+
+    pub fn get(&self, kind: Kind) -> Count { self.counts[kind.index()] }
+}
+
+/// What a diet lets a blob eat, in words.
+fn eats(blob: &Blob) -> &'static str {
+    match (blob.diet.eats_food(), blob.diet.eats_blobs()) {
+        (true, true) => "Eats food, corpses and prey",
+        (true, false) => "Eats food and corpses, no prey",
+        (false, _) => "Eats prey only, no food or corpses",
+    }
+}
+
+/// Draw what a blob perceives, with the panel's corner at a position.
+pub fn draw<D: RaylibDraw>(draw: &mut D, theme: &Theme, fonts: &Fonts, blob: &Blob, senses: &Senses, pos: Vector2) {
+    let height = 3. * PADDING + (FONT_SIZE + (Kind::ALL.len() as i32 + 1) * ROW_HEIGHT) as f32;
+    let rect = Rectangle::new(pos.x, pos.y, WIDTH, height);
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    let (x, mut y) = ((pos.x + PADDING) as i32, (pos.y + PADDING) as i32);
+    fonts.draw_text(draw, "Perceived", x, y, FONT_SIZE, theme.text);
+    y += FONT_SIZE + PADDING as i32;
+    for kind in Kind::ALL {
+        let Count { count, nearest } = senses.get(kind);
+        let color = if count == 0 { theme.muted_text } else { theme.text };
+        fonts.draw_text(draw, kind.name(), x, y, SMALL_FONT_SIZE, color);
+        fonts.draw_text(draw, &count.to_string(), x + 90, y, SMALL_FONT_SIZE, color);
+        if let Some(nearest) = nearest {
+            fonts.draw_text(draw, &format!("nearest {:.0}", nearest), x + 130, y, SMALL_FONT_SIZE, color);
+        }
+        y += ROW_HEIGHT;
+    }
+    fonts.draw_text(draw, eats(blob), x, y, SMALL_FONT_SIZE, theme.muted_text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{simulation::{BlobConfig, SimulationConfig}, species::Diet};
+
+    #[test]
+    fn test_senses() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(400., 400.)));
+        let species = |name: &str| Some(name.to_string());
+        let hunter = sim.insert_blob(BlobConfig {
+            species: species("Hunter"), diet: Diet::Carnivore, attack: 0.9, defence: 0.5, pov: 180., sight_depth: 150.,
+            ..BlobConfig::new(Vector2::new(200., 200.))
+        });
+        sim.insert_blob(BlobConfig { species: species("Grazer"), attack: 0.1, defence: 0.2, ..BlobConfig::new(Vector2::new(240., 200.)) });
+        sim.insert_blob(BlobConfig { species: species("Hunter"), attack: 0.1, defence: 2., ..BlobConfig::new(Vector2::new(200., 260.)) });
+        sim.insert_blob(BlobConfig { species: species("Brute"), diet: Diet::Carnivore, attack: 1., ..BlobConfig::new(Vector2::new(140., 200.)) });
+        sim.insert_food(Vector2::new(200., 150.));
+        sim.insert_food(Vector2::new(200., 300.));
+        sim.step();
+
+        //  wherever it turned in a tick, a blob looking all around sees everything near
+        let senses = Senses::of(&sim, hunter);
+        assert_eq!(senses.get(Kind::Food).count, 2);
+        assert_eq!(senses.get(Kind::Prey).count, 1);
+        assert_eq!(senses.get(Kind::Predator).count, 1);
+        assert_eq!(senses.get(Kind::Kin).count, 1);
+        assert_eq!(senses.get(Kind::Other), Count::default());
+        let nearest = senses.get(Kind::Food).nearest.unwrap();
+        assert!((40. ..60.).contains(&nearest), "{}", nearest);
+        assert_eq!(Senses::of(&sim, Key::from_index(99)), Senses::default());
+    }
+}
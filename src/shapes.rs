@@ -0,0 +1,61 @@
+//! Shapes that stay smooth at any zoom.
+//!
+//! raylib draws circles as polygons with a fixed number of sides,
+//! which shows once a blob covers much of the screen. These draw
+//! circles with as many sides as their size on screen needs, so
+//! that no side strays more than a fraction of a pixel from the
+//! true circle. Together with multisampling this keeps edges clean.
+
+use std::f32::consts::PI;
+
+use raylib::prelude::*;
+
+/// The furthest, in screen pixels, the polygon of a circle may stray from the circle.
+const MAX_ERROR: f32 = 0.25;
+const MIN_SEGMENTS: i32 = 12;
+const MAX_SEGMENTS: i32 = 720;
+
+/// How many sides a circle with a radius in screen pixels is drawn with.
+pub fn circle_segments(screen_radius: f32) -> i32 {
+    if screen_radius <= MAX_ERROR { return MIN_SEGMENTS; }
+    //  a side of angle a strays r * (1 - cos(a/2)) from the circle
+    let angle = 2. * (1. - MAX_ERROR / screen_radius).acos();
+    ((2. * PI / angle).ceil() as i32).clamp(MIN_SEGMENTS, MAX_SEGMENTS)
+}
+
+/// Draw a filled circle, where `zoom` is how many screen pixels a unit of `radius` covers.
+pub fn draw_circle<D: RaylibDraw>(draw: &mut D, center: Vector2, radius: f32, zoom: f32, color: Color) {
+    draw.draw_circle_sector(center, radius, 0, 360, circle_segments(radius * zoom), color);
+}
+
+/// Draw the outline of a circle, `thickness` wide in screen pixels.
+pub fn draw_circle_outline<D: RaylibDraw>(draw: &mut D, center: Vector2, radius: f32, thickness: f32, zoom: f32, color: Color) {
+    let half = thickness / zoom / 2.;
+    draw.draw_ring(center, radius - half, radius + half, 0, 360, circle_segments(radius * zoom), color);
+}
+
+/// Draw the outline of a circular sector between two angles in degrees.
+pub fn draw_sector_outline<D: RaylibDraw>(draw: &mut D, center: Vector2, radius: f32, start_angle: f32, end_angle: f32, zoom: f32, color: Color) {
+    let fraction = ((end_angle - start_angle).abs() / 360.).min(1.);
+    let segments = ((circle_segments(radius * zoom) as f32 * fraction).ceil() as i32).max(1);
+    draw.draw_circle_sector_lines(center, radius, start_angle as i32, end_angle as i32, segments, color);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circle_segments() {
+        assert_eq!(circle_segments(0.), MIN_SEGMENTS);
+        assert_eq!(circle_segments(1e6), MAX_SEGMENTS);
+        //  larger circles never get fewer sides
+        let counts: Vec<_> = (1..500).map(|r| circle_segments(r as f32)).collect();
+        assert!(counts.windows(2).all(|pair| pair[0] <= pair[1]));
+        //  and every side keeps within the allowed error
+        for &r in &[5f32, 50., 300.] {
+            let angle = 2. * PI / circle_segments(r) as f32;
+            assert!(r * (1. - (angle / 2.).cos()) <= MAX_ERROR + 1e-3);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! The state of the first world after every tick, in a file of fixed
+//! layout, for analysis tools on the same machine to read without
+//! parsing.
+//!
+//! `--shared-state <path>` keeps a file of a fixed size, which readers
+//! map into memory or read at offsets. It is an ordinary file written
+//! through the file system, not shared memory: on a memory file system
+//! like tmpfs at `/dev/shm` it never reaches a disk, and readers that
+//! map it share its pages, but anywhere else every snapshot is written
+//! out to the disk. It holds a header and a ring of `SLOTS` snapshots,
+//! each written in turn, a slot at once. Numbers are little-endian,
+//! `u32`, `u64` or `f32`:
+//!
+//! ```text
+//! header, HEADER_SIZE bytes at 0
+//!   0  [u8; 8]  MAGIC
+//!   8  u32      VERSION
+//!  12  u32      SLOTS
+//!  16  u32      BLOB_CAPACITY
+//!  20  u32      FOOD_CAPACITY
+//!  24  u32      SLOT_SIZE
+//!  32  u64      the sequence number of the latest snapshot, 0 before the first
+//!  40  f32, f32 the width and height of the world
+//!
+//! snapshot n, from 1, SLOT_SIZE bytes at HEADER_SIZE + (n - 1) % SLOTS * SLOT_SIZE
+//!   0  u64      n, or 0 while it is written
+//!   8  u64      the tick
+//!  16  u32      how many blobs follow, at most BLOB_CAPACITY
+//!  20  u32      how many foods follow, at most FOOD_CAPACITY
+//!  24  u32, u32 how many blobs and foods the world had, beyond the capacity too
+//!  32  one array of BLOB_CAPACITY values for each of BLOB_FIELDS in turn,
+//!      then one of FOOD_CAPACITY for each of FOOD_FIELDS
+//! ```
+//!
+//! A reader takes the latest sequence number from the header, copies
+//! what it needs of that snapshot and checks its number again, taking
+//! the copy only if it did not change meanwhile. The writer takes
+//! `SLOTS` ticks to come back to a slot, so a reader slower than that
+//! retries. `examples/shared_state_reader.rs` is such a reader.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::shared_state::SharedState;
+//! let mut shared = SharedState::create("/dev/shm/blobs", sim.size())?;
+//! //  after every tick
+//! shared.write(&sim)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fs, io::{self, Seek, SeekFrom, Write}, path::Path};
+
+use raylib::prelude::Vector2;
+
+use crate::{keyed_set::prelude::*, simulation::{Blob, Simulation}, species::Diet};
+
+pub const MAGIC: [u8; 8] = *b"BLOBSHM\0";
+pub const VERSION: u32 = 2;
+pub const SLOTS: u32 = 8;
+pub const BLOB_CAPACITY: u32 = 4096;
+pub const FOOD_CAPACITY: u32 = 16384;
+pub const HEADER_SIZE: u64 = 64;
+pub const SLOT_HEADER_SIZE: u64 = 32;
+/// A value of each blob kept in an array of its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlobField {
+    /// The low 32 bits of `Key::index`, a `u32`.
+    Key,
+    X,
+    Y,
+    /// In radians.
+    Heading,
+    Radius,
+    /// The part of the most the blob can bear.
+    Hunger,
+    /// 0 for herbivores, 1 carnivores and 2 omnivores, a `u32`.
+    Diet,
+    /// The high 32 bits of `Key::index`, a `u32`.
+    Generation,
+}
+
+impl BlobField {
+    fn bytes(self, key: Key<Blob>, blob: &Blob) -> [u8; 4] {
+        match self {
+            Self::Key => (key.index() as u32).to_le_bytes(),
+            Self::X => blob.pos().x.to_le_bytes(),
+            Self::Y => blob.pos().y.to_le_bytes(),
+            Self::Heading => blob.direction().y.atan2(blob.direction().x).to_le_bytes(),
+            Self::Radius => blob.radius().to_le_bytes(),
+            Self::Hunger => (blob.hunger / blob.max_hunger).to_le_bytes(),
+            Self::Diet => (match blob.diet { Diet::Herbivore => 0u32, Diet::Carnivore => 1, Diet::Omnivore => 2 }).to_le_bytes(),
+            Self::Generation => ((key.index() >> 32) as u32).to_le_bytes(),
+        }
+    }
+}
+
+/// The arrays of blobs, in order.
+pub const BLOB_FIELDS: [BlobField; 8] = [
+    BlobField::Key, BlobField::X, BlobField::Y, BlobField::Heading,
+    BlobField::Radius, BlobField::Hunger, BlobField::Diet, BlobField::Generation,
+];
+/// The arrays of foods, in order.
+pub const FOOD_FIELDS: [&str; 2] = ["x", "y"];
+pub const SLOT_SIZE: u32 = SLOT_HEADER_SIZE as u32 + 4 * (BLOB_FIELDS.len() as u32 * BLOB_CAPACITY + FOOD_FIELDS.len() as u32 * FOOD_CAPACITY);
+
+/// The writer of the shared state.
+pub struct SharedState {
+    file: fs::File,
+    //  of the latest snapshot
+    sequence: u64,
+    //  a slot at a time
+    buffer: Vec<u8>,
+}
+
+impl SharedState {
+    /// Create the file of the shared state, or take over an existing one.
+    pub fn create<P: AsRef<Path>>(path: P, size: Vector2) -> io::Result<Self> {
+        let file = fs::OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(HEADER_SIZE + SLOTS as u64 * SLOT_SIZE as u64)?;
+        let mut header = MAGIC.to_vec();
+        for value in &[VERSION, SLOTS, BLOB_CAPACITY, FOOD_CAPACITY, SLOT_SIZE, 0] {
+            header.extend_from_slice(&value.to_le_bytes());
+        }
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.extend_from_slice(&size.x.to_le_bytes());
+        header.extend_from_slice(&size.y.to_le_bytes());
+        let ret = Self { file, sequence: 0, buffer: vec![] };
+        ret.write_at(&header, 0)?;
+        Ok(ret)
+    }
+
+    fn write_at(&self, bytes: &[u8], at: u64) -> io::Result<()> {
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(at))?;
+        file.write_all(bytes)
+    }
+
+    /// Write a snapshot of the simulation as it is now to the next slot.
+    pub fn write(&mut self, sim: &Simulation) -> io::Result<()> {
+        self.sequence += 1;
+        let slot = HEADER_SIZE + (self.sequence - 1) % SLOTS as u64 * SLOT_SIZE as u64;
+        //  readers leave a slot alone while it is written
+        self.write_at(&0u64.to_le_bytes(), slot)?;
+
+        let blobs: Vec<_> = sim.blobs().take(BLOB_CAPACITY as usize).collect();
+        let foods: Vec<_> = sim.foods().take(FOOD_CAPACITY as usize).map(|(_, food)| food.pos()).collect();
+        let counts = [blobs.len(), foods.len(), sim.blobs().count(), sim.foods().count()];
+        self.buffer.clear();
+        self.buffer.resize(SLOT_SIZE as usize, 0);
+        self.buffer[8..16].copy_from_slice(&sim.clock().tick().to_le_bytes());
+        for (i, &count) in counts.iter().enumerate() {
+            self.buffer[16 + 4 * i..][..4].copy_from_slice(&(count as u32).to_le_bytes());
+        }
+        let mut array = SLOT_HEADER_SIZE as usize;
+        for &field in &BLOB_FIELDS {
+            for (i, &(key, blob)) in blobs.iter().enumerate() {
+                self.buffer[array + 4 * i..][..4].copy_from_slice(&field.bytes(key, blob));
+            }
+            array += 4 * BLOB_CAPACITY as usize;
+        }
+        for (i, pos) in foods.iter().enumerate() {
+            self.buffer[array + 4 * i..][..4].copy_from_slice(&pos.x.to_le_bytes());
+            self.buffer[array + 4 * (FOOD_CAPACITY as usize + i)..][..4].copy_from_slice(&pos.y.to_le_bytes());
+        }
+        self.write_at(&self.buffer[8..], slot + 8)?;
+
+        //  the number last, once the rest is in place
+        self.write_at(&self.sequence.to_le_bytes(), slot)?;
+        self.write_at(&self.sequence.to_le_bytes(), 32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_shared_state() {
+        let path = std::env::temp_dir().join(format!("blobs-shared-state-{}", std::process::id()));
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 200.)));
+        sim.insert_blob(BlobConfig::new(Vector2::new(50., 60.)));
+        sim.insert_food(Vector2::new(70., 80.));
+        let mut shared = SharedState::create(&path, sim.size()).unwrap();
+        for _ in 0..SLOTS + 2 {
+            shared.write(&sim).unwrap();
+        }
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let u32_at = |at: u64| u32::from_le_bytes(bytes[at as usize..at as usize + 4].try_into().unwrap());
+        let u64_at = |at: u64| u64::from_le_bytes(bytes[at as usize..at as usize + 8].try_into().unwrap());
+        let f32_at = |at: u64| f32::from_le_bytes(bytes[at as usize..at as usize + 4].try_into().unwrap());
+        assert_eq!((&bytes[..8], u32_at(24), bytes.len() as u64), (&MAGIC[..], SLOT_SIZE, HEADER_SIZE + SLOTS as u64 * SLOT_SIZE as u64));
+
+        //  the latest snapshot went around the ring into the second slot
+        let latest = u64_at(32);
+        let slot = HEADER_SIZE + (latest - 1) % SLOTS as u64 * SLOT_SIZE as u64;
+        assert_eq!((latest, slot, u64_at(slot)), (SLOTS as u64 + 2, HEADER_SIZE + SLOT_SIZE as u64, latest));
+        assert_eq!((u32_at(slot + 16), u32_at(slot + 20)), (1, 1));
+        let arrays = slot + SLOT_HEADER_SIZE;
+        assert_eq!((f32_at(arrays + 4 * BLOB_CAPACITY as u64), f32_at(arrays + 8 * BLOB_CAPACITY as u64)), (50., 60.));
+        let foods = arrays + BLOB_FIELDS.len() as u64 * 4 * BLOB_CAPACITY as u64;
+        assert_eq!((f32_at(foods), f32_at(foods + 4 * FOOD_CAPACITY as u64)), (70., 80.));
+    }
+}
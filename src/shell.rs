@@ -0,0 +1,154 @@
+//! A shell for headless runs, to poke at a world over SSH.
+//!
+//! `blobs --headless --shell` simulates nothing on its own but reads
+//! commands from the standard input, one a line, and answers each on
+//! the standard output:
+//!
+//! * `step [ticks]` - simulate some ticks, 1 unless given
+//! * `stats` - the tick, population, food, species and records of
+//!   every world
+//! * `spawn [count]` - add random blobs, 1 unless given
+//! * `save <path>` - save the worlds, one file each when there are
+//!   several, see `Simulation::save`
+//! * `do <command>` - send a command in the text form of
+//!   `lockstep::Command`, as in `do spawn-food 120 80`, to every world
+//!   through its handle, applied at the next tick, see `handle`
+//! * `events` - print the events of the worlds as they happen, through
+//!   their handles, until `events` again
+//! * `help` - list the commands
+//! * `quit` - stop, as the end of the input does, and write the
+//!   statistics as a headless run does at its end
+//!
+//! ```text
+//! > step 600
+//! tick 600: population 41
+//! > stats
+//! A: tick 600 (10 s), 41 blobs, 187 foods, the longest life 10 s
+//!   Grazer: 23, at most 25
+//!   Oldest blob: Ada, 10 s
+//! ```
+//!
+//! # Example
+//!
+//! ```ignore
+//! match shell::Command::parse(&line)? {
+//!     Some(Command::Stats) => shell::write_stats(&mut io::stdout(), &sim, "A")?,
+//!     ...
+//! }
+//! ```
+
+use std::io::{self, Write};
+
+use crate::{lockstep, simulation::Simulation};
+
+/// Shown before each command is read.
+pub const PROMPT: &str = "> ";
+
+pub const HELP: &str = "\
+step [ticks]   simulate some ticks, 1 unless given
+stats          the tick, population, food, species and records of every world
+spawn [count]  add random blobs, 1 unless given
+save <path>    save the worlds, one file each when there are several
+do <command>   send a command, as in `do spawn-food 120 80`, applied at the next tick
+events         print the events as they happen, until `events` again
+help           list the commands
+quit           stop and write the statistics";
+
+/// A command of the shell, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Step(u64),
+    Stats,
+    Spawn(usize),
+    Save(String),
+    Do(lockstep::Command),
+    Events,
+    Help,
+    Quit,
+}
+
+impl Command {
+    /// The command of a line, or none for a blank one.
+    pub fn parse(line: &str) -> Result<Option<Self>, String> {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+        if name == "do" {
+            let command = words.collect::<Vec<_>>().join(" ");
+            return match command.as_str() {
+                "" => Err("do needs a command".to_string()),
+                command => command.parse().map(|command| Some(Self::Do(command))),
+            };
+        }
+        let argument = words.next();
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected `{}` after `{}`", extra, name));
+        }
+        let count = |argument: Option<&str>| match argument {
+            Some(count) => count.parse().ok().filter(|&count| count > 0)
+                .ok_or_else(|| format!("invalid count `{}`, expected a number from 1", count)),
+            None => Ok(1),
+        };
+        let command = match name {
+            "step" => Self::Step(count(argument)? as u64),
+            "spawn" => Self::Spawn(count(argument)?),
+            "save" => Self::Save(argument.ok_or("save needs a path")?.to_string()),
+            "stats" | "events" | "help" | "quit" if argument.is_some() => return Err(format!("{} takes nothing", name)),
+            "stats" => Self::Stats,
+            "events" => Self::Events,
+            "help" => Self::Help,
+            "quit" => Self::Quit,
+            name => return Err(format!("unknown command `{}`, try `help`", name)),
+        };
+        Ok(Some(command))
+    }
+}
+
+/// Write how a world is doing for `stats`, see the module.
+pub fn write_stats<W: Write>(out: &mut W, sim: &Simulation, label: &str) -> io::Result<()> {
+    let clock = sim.clock();
+    writeln!(out, "{}: tick {} ({:.0} s), {} blobs, {} foods, the longest life {:.0} s",
+        label, clock.tick(), clock.time(), sim.blobs().count(), sim.foods().count(), sim.longest_life(),
+    )?;
+    for (species, record) in sim.census().records() {
+        writeln!(out, "  {}: {}, at most {}", species, record.population, record.peak)?;
+    }
+    for record in sim.records().iter() {
+        writeln!(out, "  {}", record.describe())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raylib::prelude::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_shell() {
+        assert_eq!(Command::parse("  ").unwrap(), None);
+        assert_eq!(Command::parse("step").unwrap(), Some(Command::Step(1)));
+        assert_eq!(Command::parse("step 600").unwrap(), Some(Command::Step(600)));
+        assert_eq!(Command::parse("spawn 3").unwrap(), Some(Command::Spawn(3)));
+        assert_eq!(Command::parse("save world.txt").unwrap(), Some(Command::Save("world.txt".to_string())));
+        assert!(Command::parse("step 0").unwrap_err().contains("from 1"));
+        assert!(Command::parse("save").unwrap_err().contains("needs a path"));
+        assert!(Command::parse("quit now").unwrap_err().contains("takes nothing"));
+        assert!(Command::parse("step 1 2").unwrap_err().contains("unexpected `2`"));
+        assert!(Command::parse("fly").unwrap_err().contains("try `help`"));
+        assert_eq!(Command::parse("do spawn-food 120 80").unwrap(), Some(Command::Do(lockstep::Command::SpawnFood(Vector2::new(120., 80.)))));
+        assert!(Command::parse("do fly").unwrap_err().contains("invalid command"));
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        sim.insert_blob(BlobConfig { species: Some("Grazer".to_string()), ..BlobConfig::new(Vector2::new(100., 100.)) });
+        sim.step();
+        let mut out = vec![];
+        write_stats(&mut out, &sim, "A").unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("A: tick 1 (0 s), 1 blobs, 0 foods"), "{}", out);
+        assert!(out.contains("\n  Grazer: 1, at most 1\n"), "{}", out);
+    }
+}
@@ -0,0 +1,111 @@
+//! Orderly shutdown when the window closes or the program is interrupted.
+//!
+//! Ctrl+C in the terminal, or a termination signal, only asks the
+//! main loop to stop, so the program ends the same way as when
+//! its window is closed. The hooks registered by then run in
+//! order, and one failing does not keep the rest from running. A
+//! second Ctrl+C exits at once. The program can also ask itself to
+//! stop, such as when the screensaver is woken.
+//!
+//! # Example
+//!
+//! ```ignore
+//! shutdown::install();
+//! while !shutdown::requested() {
+//!     //  run
+//! }
+//! let mut hooks = Hooks::new();
+//! hooks.register("narrator", || narrator.flush().map_err(|e| e.to_string()));
+//! hooks.run();
+//! ```
+
+use std::{
+    os::raw::c_int,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static REQUESTED: AtomicBool = AtomicBool::new(false);
+
+const SIGINT: c_int = 2;
+const SIGTERM: c_int = 15;
+
+extern "C" {
+    fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+    fn _exit(status: c_int) -> !;
+}
+
+extern "C" fn on_signal(signum: c_int) {
+    //  only what is safe in a signal handler
+    if REQUESTED.swap(true, Ordering::SeqCst) {
+        unsafe { _exit(128 + signum) }
+    }
+}
+
+/// Turn Ctrl+C and termination signals into shutdown requests.
+pub fn install() {
+    unsafe {
+        signal(SIGINT, on_signal);
+        signal(SIGTERM, on_signal);
+    }
+}
+
+/// Ask the main loop to stop, as closing the window does.
+pub fn request() {
+    REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Whether the program was asked to stop.
+pub fn requested() -> bool {
+    REQUESTED.load(Ordering::SeqCst)
+}
+
+type Hook<'a> = Box<dyn FnOnce() -> Result<(), String> + 'a>;
+
+/// Work to do before the program exits, such as flushing output.
+#[derive(Default)]
+pub struct Hooks<'a> {
+    hooks: Vec<(&'static str, Hook<'a>)>,
+}
+
+impl<'a> Hooks<'a> {
+    pub fn new() -> Self { Self::default() }
+
+    /// Add a hook, to run after the ones added before it.
+    pub fn register<F: FnOnce() -> Result<(), String> + 'a>(&mut self, name: &'static str, hook: F) {
+        self.hooks.push((name, Box::new(hook)));
+    }
+
+    /// Run every hook, reporting the ones that fail. Returns whether all succeeded.
+    pub fn run(self) -> bool {
+        let mut ok = true;
+        for (name, hook) in self.hooks {
+            if let Err(e) = hook() {
+                eprintln!("shutdown: {} failed: {}", name, e);
+                ok = false;
+            }
+        }
+        ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[test]
+    fn test_hooks() {
+        //  a failing hook does not keep the ones after it from running
+        let ran = RefCell::new(vec![]);
+        let mut hooks = Hooks::new();
+        hooks.register("first", || { ran.borrow_mut().push(1); Err("broken".to_string()) });
+        hooks.register("second", || { ran.borrow_mut().push(2); Ok(()) });
+        assert!(!hooks.run());
+        assert_eq!(ran.into_inner(), vec![1, 2]);
+
+        let mut hooks = Hooks::new();
+        hooks.register("only", || Ok(()));
+        assert!(hooks.run());
+    }
+}
@@ -7,39 +7,89 @@
 //! The API is designed to enable modification and interaction 
 //! with an executing simulation.
 //!
+//! All randomness inside the simulation comes from its own
+//! generator, seeded from the config, so two simulations created
+//! from the same config and given the same inputs stay identical.
+//! What blobs draw as they step and breed comes instead from streams
+//! of their own for the tick, see `math::random`, so it does not
+//! depend on the order blobs are stepped in.
+//!
+//! Worlds are saved to a versioned, tab-separated text file, see
+//! `save`, written by hand like the other files the game keeps, as
+//! serde is not a dependency. `StdRng` does not give out its state,
+//! so a save keeps the next number the generator would draw and the
+//! loaded world is seeded from it: blobs draw the same from their
+//! streams as if it had never been saved, but what the world draws
+//! itself, such as where food and spawned blobs go, differs.
+//!
 //! # Example
 //!
 //! ```
-//! use crate::simulation::prelude::*;
+//! use blobs::simulation::prelude::*;
+//! # use raylib::prelude::Vector2;
 //! 
 //! let mut sim = Simulation::new(SimulationConfig {
-//!     size: Vector2::new(600., 800.)
+//!     seed: 42,
+//!     ..SimulationConfig::new(Vector2::new(600., 800.))
 //! });
 //! 
-//! sim.insert_blob(Blob::new());
+//! sim.insert_food(Vector2::new(10., 10.));
 //! ```
 
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap, VecDeque},
+    io::{self, BufRead, Write},
+    mem,
+    ops::RangeInclusive,
+    str::FromStr,
+};
 
-use rand::prelude::*;
+use rand::{prelude::*, rngs::StdRng};
 
 use raylib::prelude::*;
 
 use crate::{
+    window::{RenderLayer, RenderContext},
+    style::{self, Style},
+    depth, shapes, theme,
     keyed_set::prelude::*,
     physics::{self, prelude::*},
-    window::DrawingContext,
-    math,
+    clock::{Clock, Season},
+    species::{BrainKind, Diet},
+    energy::{Audit, Flows, FOOD_ENERGY},
+    spatial,
+    histogram,
+    mutators::Mutator,
+    stats::{StatsStore, MarkKind},
+    memory::Usage,
+    history::{History, Kind, Query},
+    census::{Census, Extinction},
+    math::{self, FloatMode, Fnv, random::Stream},
+    light::{self, Light},
+    inspector,
+    tags::Tag,
+    hooks::{HookId, Hooks, Removed, RemovalHook, SimulationHooks, TickHook},
+    viewpoints::{Viewpoint, Viewpoints},
+    watch::{Alert, Measure, Region},
+    drones::Drone,
+    aura::{self, Aura},
+    trace::{self, Trace},
+    profile::Profile,
+    memorial::{self, Cause, Grave},
+    weather::{self, Weather},
+    records::{self, Record, Records},
+    taxonomy::Taxonomy,
+    overrides::Overrides,
 };
 
 
 /// Returns a vector2 with x in [0,1) and y in [0,1)
-fn random_vector2() -> Vector2 { Vector2::new(random(), random()) }
+fn random_vector2<R: Rng + ?Sized>(rng: &mut R) -> Vector2 { Vector2::new(rng.gen(), rng.gen()) }
 
 /// Returns -1 for very different colors and 1 for same color
-fn color_similarity(a: &Color, b: &Color) -> f32 {
-    let a = a.color_to_hsv();
-    let b = b.color_to_hsv();
+fn color_similarity(a: &Color, b: &Color, mode: FloatMode) -> f32 {
+    let a = mode.color_to_hsv(*a);
+    let b = mode.color_to_hsv(*b);
     let angle_difference = {
         let v = (a.x - b.x).abs();
         if v <= 180. { v } else { 360. - v } 
@@ -50,10 +100,19 @@ fn color_similarity(a: &Color, b: &Color) -> f32 {
     ret
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Blob {
     pub name: Option<String>,
+    pub species: Option<String>,
+    /// The blob it is a child of, if any, which may be gone.
+    pub parent: Option<Key<Blob>>,
+    /// 1 for blobs that were added, one more than its parent's for a child.
+    pub generation: u32,
     pub alive_time: f32,
+    /// How many foods it has eaten.
+    pub foods_eaten: u32,
+    /// How many children it had.
+    pub children: u32,
 
     pub speed: f32,
     pub rotation_speed: f32,
@@ -68,8 +127,12 @@ pub struct Blob {
 
     pos: Vector2,
     pub direction: Vector2,
+    //  movement from outside forces such as being thrown
+    velocity: Vector2,
     circle: Key<Circle>,
     sight_circle: Key<Circle>,
+    //  where the brain last decided to go, if it decided yet
+    intent: Option<BlobStep>,
 
     pub hunger: f32,
     pub max_hunger: f32,
@@ -80,209 +143,2480 @@ pub struct Blob {
 
     pub attack: f32,
     pub defence: f32,
+    pub diet: Diet,
+    pub brain: BrainKind,
+    /// How well it swims, from 0 to 1, see `Blob::SWIMMER`.
+    pub swimming: f32,
+    /// The part of how far a noise carries that it hears it from, from 0 to 1.
+    pub hearing: f32,
+    /// What it does to the blobs around it, see `aura`.
+    pub aura: Aura,
+    /// How strong its aura is, from 0 to 1.
+    pub aura_strength: f32,
+    //  the part of its speed auras take from it this tick
+    slowed: f32,
+    /// Put on by users, see `tags`.
+    pub tags: Vec<Tag>,
 }
 
-#[derive(Debug)]
+/// Everything needed to create a blob, see `Simulation::insert_blob`.
+///
+/// The traits of `new` are middling ones, so a blob can be written
+/// as the few traits that matter and the rest left as they are:
+/// `BlobConfig { speed: 120., pov: 180., ..BlobConfig::new(pos) }`,
+/// or `..BlobConfig::default()` for one at the origin.
+#[derive(Debug, Clone)]
+pub struct BlobConfig {
+    pub pos: Vector2,
+    pub name: Option<String>,
+    pub species: Option<String>,
+    /// The blob it is a child of, if any.
+    pub parent: Option<Key<Blob>>,
+    /// Before the simulation's size scale.
+    pub radius: f32,
+    pub color: Color,
+    pub speed: f32,
+    pub rotation_speed: f32,
+    pub pov: f32,
+    /// Before the simulation's sight scale.
+    pub sight_depth: f32,
+    pub favorite_color: Color,
+    pub color_attraction: f32,
+    pub color_repulsion: f32,
+    pub max_hunger: f32,
+    pub attack: f32,
+    pub defence: f32,
+    pub hunger_reduction: f32,
+    pub hunger_division: f32,
+    pub diet: Diet,
+    pub brain: BrainKind,
+    /// How hungry it starts, from 0 for full.
+    pub hunger: f32,
+    pub swimming: f32,
+    pub hearing: f32,
+    pub aura: Aura,
+    pub aura_strength: f32,
+}
+
+impl BlobConfig {
+    pub fn new(pos: Vector2) -> Self {
+        Self {
+            pos, name: None, species: None, parent: None,
+            radius: 5., color: Color::RED,
+            speed: 50., rotation_speed: 1.,
+            pov: 90., sight_depth: 60.,
+            favorite_color: Color::GREEN,
+            color_attraction: 0.5, color_repulsion: 0.5,
+            max_hunger: 10.,
+            attack: 0.5, defence: 0.5,
+            hunger_reduction: 0.1, hunger_division: 0.5,
+            diet: Diet::Herbivore,
+            brain: BrainKind::ColorAttraction,
+            hunger: 0.,
+            swimming: 0.,
+            hearing: 0.,
+            aura: Aura::None,
+            aura_strength: 0.,
+        }
+    }
+
+    /// A blob at a random place within bounds whose traits are all uniformly random.
+    pub fn randomized<R: Rng + ?Sized>(bounds: Vector2, rng: &mut R) -> Self {
+        let random_color = |rng: &mut R| Color::new(rng.gen(), rng.gen(), rng.gen(), 255);
+        let pos = Vector2::new(rng.gen(), rng.gen()) * bounds;
+        //  fields are drawn in the order they are written
+        Self {
+            radius: 20. * rng.gen::<f32>(),
+            color: random_color(rng),
+            speed: 120. * rng.gen::<f32>(),
+            rotation_speed: 5. * rng.gen::<f32>(),
+            pov: 180. * rng.gen::<f32>(),
+            sight_depth: 170. * rng.gen::<f32>(),
+            favorite_color: random_color(rng),
+            color_attraction: rng.gen(),
+            color_repulsion: rng.gen(),
+            max_hunger: 25. * rng.gen::<f32>(),
+            attack: rng.gen(),
+            defence: 2. * rng.gen::<f32>(),
+            hunger_reduction: 0.5 * rng.gen::<f32>(),
+            hunger_division: rng.gen(),
+            ..Self::new(pos)
+        }
+    }
+}
+
+impl Default for BlobConfig {
+    fn default() -> Self {
+        Self::new(Vector2::zero())
+    }
+}
+
+/// Which blobs a query picks, besides where they are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlobFilter {
+    pub species: Option<String>,
+    /// Genes, by their name in `inspector::genome`, and the range each must lie in.
+    pub genes: Vec<(&'static str, RangeInclusive<f32>)>,
+}
+
+impl BlobFilter {
+    /// Blobs of a species.
+    pub fn species(species: Option<&str>) -> Self {
+        Self { species: species.map(str::to_string), ..Self::default() }
+    }
+
+    pub fn matches(&self, blob: &Blob) -> bool {
+        if self.species.is_some() && blob.species != self.species { return false; }
+        if self.genes.is_empty() { return true; }
+        let genome = inspector::genome(blob);
+        self.genes.iter().all(|(name, range)| {
+            genome.iter().any(|gene| gene.name == *name && range.contains(&gene.value))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct Food {
     pos: Vector2,
     circle: Key<Circle>,
+    //  whether the food is what is left of a dead blob
+    corpse: bool,
+    //  how far it is from fresh to gone, from 0 to 1
+    spoiled: f32,
+    //  whether it grew in a lake
+    aquatic: bool,
 }
 
-#[derive(Debug, Clone, Copy)]
+/// A round body of water, which only blobs that swim cross unharmed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Lake {
+    pub center: Vector2,
+    pub radius: f32,
+}
+
+impl Lake {
+    pub const COLOR: Color = Color::SKYBLUE;
+    /// How far lakes are drawn from the background toward their color.
+    pub const SHADE: f32 = 0.35;
+
+    pub fn contains(&self, pos: Vector2) -> bool {
+        (pos - self.center).length_sqr() <= self.radius * self.radius
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CircleObject {
     Blob(Key<Blob>),
     Food(Key<Food>),
     BlobSight(Key<Blob>),
 }
 
+/// Something notable that happened in the simulation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// A blob was added.
+    Born(Key<Blob>),
+    /// A blob was killed by another, named by their names.
+    Killed { victim: Option<String>, killer: Option<String> },
+    /// A blob died of hunger.
+    Starved(Option<String>),
+    /// A blob was found in a state no tick should leave it in, and was
+    /// set straight or removed, see `Simulation::sanitize`.
+    Quarantined { blob: Option<String>, problem: String, removed: bool },
+    /// An alert of a watched region fired, on the value that set it
+    /// off, see `watch`.
+    Alert { region: String, alert: Alert, value: f32 },
+    /// The last blob of a species is gone, see `census`.
+    Extinct(Extinction),
+    /// Rain, a storm or a drought began, see `weather`.
+    Weather(Weather),
+    /// A record of the world was broken, see `records`.
+    Record(Record),
+}
+
+/// Who adds blobs and food, each given keys of a namespace of their
+/// own, see `keyed_set`, so that how many one source adds does not
+/// change the keys the others' get.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    /// The world itself: births, corpses and its own spawning.
+    World,
+    /// The player, directly or through a lockstep peer.
+    User,
+    /// Viewers of a stream, through the chat.
+    Chat,
+    /// The timed events of a scenario.
+    Scenario,
+    /// A host embedding the simulation, see `ffi`, or another thread,
+    /// see `handle`.
+    Embedder,
+}
+
+impl Source {
+    pub const ALL: [Self; 5] = [Self::World, Self::User, Self::Chat, Self::Scenario, Self::Embedder];
+
+    pub fn namespace(self) -> Namespace {
+        Namespace::new(self as u8)
+    }
+}
+
+/// What a blob ate.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Prey {
+    Food,
+    Corpse,
+    /// A blob of a species, killed and eaten whole.
+    Blob(Option<String>),
+}
+
+/// A blob eating, named by the species of the eater.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Meal {
+    pub eater: Option<String>,
+    pub prey: Prey,
+    /// The energy the eater gained.
+    pub energy: f32,
+}
+
+/// The circles a blob's sight touched when it last looked around.
+#[derive(Debug, Clone)]
+struct Perception {
+    //  where and when the blob looked
+    pos: Vector2,
+    tick: u64,
+    neighbors: Vec<Key<Circle>>,
+}
+
+/// Something a blob saw, see `Simulation::sightings`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sighting {
+    pub pos: Vector2,
+    pub radius: f32,
+    pub color: Color,
+    /// Whether it is food rather than a blob.
+    pub food: bool,
+}
+
+/// A sound a blob made in the last tick.
+#[derive(Debug, Clone, Copy)]
+struct Noise {
+    source: Key<Blob>,
+    pos: Vector2,
+    //  how far a blob of full hearing hears it from
+    loudness: f32,
+}
+
+/// Energy a blob gained by eating or lost by being killed in the last tick.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnergyChange {
+    pub blob: Key<Blob>,
+    /// Where the blob was.
+    pub pos: Vector2,
+    /// Positive for energy gained, negative for energy lost.
+    pub amount: f32,
+}
+
+/// Tunable rules of the simulated world.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Parameters {
+    /// Whether blobs can kill each other.
+    pub predation: bool,
+    /// Whether well fed blobs have children.
+    pub reproduction: bool,
+    /// How far offspring traits drift from their parents'.
+    pub mutation_rate: f32,
+    /// Multiplier on how often food appears.
+    pub food_rate: f32,
+    /// Multiplier on the sight depth of inserted blobs.
+    pub sight_scale: f32,
+    /// Multiplier on the radius of inserted blobs.
+    pub size_scale: f32,
+    /// Multiplier on how fast blobs grow hungry.
+    pub metabolism_scale: f32,
+    /// The part of a meal a blob gets from eating another.
+    pub predation_efficiency: f32,
+    /// Seconds food lasts uneaten before it spoils away, or 0 to last
+    /// until eaten. Corpses spoil faster, as does all food in summer.
+    pub food_lifetime: f32,
+    /// About the part of the world covered by lakes, from 0 to 0.5.
+    pub water: f32,
+    /// How much the sun dims at midnight, from 0 for no nights to 1.
+    pub night: f32,
+    /// How often rain, storms and droughts come, from 0 for never, see `weather`.
+    pub weather: f32,
+    /// How far the traits of a child drift from the founder of its
+    /// species before it founds a new one, or 0 for never, see `taxonomy`.
+    pub speciation: f32,
+}
+
+impl Default for Parameters {
+    fn default() -> Self {
+        Self {
+            predation: true,
+            reproduction: true,
+            mutation_rate: 0.1,
+            food_rate: 1.,
+            sight_scale: 1.,
+            size_scale: 1.,
+            metabolism_scale: 1.,
+            predation_efficiency: 1.,
+            food_lifetime: 0.,
+            water: 0.,
+            night: 0.,
+            weather: 0.,
+            speciation: 0.,
+        }
+    }
+}
+
+impl Parameters {
+    pub const NAMES: [&'static str; 13] = [
+        "predation", "mutation_rate", "food_rate", "sight_scale", "size_scale", "metabolism_scale", "predation_efficiency",
+        "food_lifetime", "reproduction", "water", "night", "weather", "speciation",
+    ];
+
+    /// Set a parameter from its name and textual value.
+    pub fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let number = || value.parse::<f32>().map_err(|_| format!("`{}` needs a number, got `{}`", name, value));
+        match name {
+            "predation" | "reproduction" => {
+                let flag = value.parse().map_err(|_| format!("`{}` needs true or false, got `{}`", name, value))?;
+                if name == "predation" { self.predation = flag } else { self.reproduction = flag }
+            },
+            "mutation_rate" => self.mutation_rate = number()?,
+            "food_rate" => self.food_rate = number()?,
+            "sight_scale" => self.sight_scale = number()?,
+            "size_scale" => self.size_scale = number()?,
+            "metabolism_scale" => self.metabolism_scale = number()?,
+            "predation_efficiency" => self.predation_efficiency = number()?,
+            "food_lifetime" => self.food_lifetime = number()?.max(0.),
+            "water" => self.water = number()?.clamp(0., 0.5),
+            "night" => self.night = number()?.clamp(0., 1.),
+            "weather" => self.weather = number()?.clamp(0., 10.),
+            "speciation" => self.speciation = number()?.max(0.),
+            _ => return Err(format!("unknown parameter `{}`, expected one of {}", name, Self::NAMES.join(", "))),
+        }
+        Ok(())
+    }
+
+    /// A numeric parameter by its name, or nothing for an unknown or boolean one.
+    pub fn number(&self, name: &str) -> Option<f32> {
+        match name {
+            "mutation_rate" => Some(self.mutation_rate),
+            "food_rate" => Some(self.food_rate),
+            "sight_scale" => Some(self.sight_scale),
+            "size_scale" => Some(self.size_scale),
+            "metabolism_scale" => Some(self.metabolism_scale),
+            "predation_efficiency" => Some(self.predation_efficiency),
+            "food_lifetime" => Some(self.food_lifetime),
+            "water" => Some(self.water),
+            "night" => Some(self.night),
+            "weather" => Some(self.weather),
+            "speciation" => Some(self.speciation),
+            _ => None,
+        }
+    }
+
+    /// A boolean parameter by its name, or nothing for an unknown or numeric one.
+    pub fn flag(&self, name: &str) -> Option<bool> {
+        match name {
+            "predation" => Some(self.predation),
+            "reproduction" => Some(self.reproduction),
+            _ => None,
+        }
+    }
+}
+
+/// How finely the simulation divides time, traded against speed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    /// Ticks per second of simulation time.
+    pub tick_rate: u32,
+    /// How many times movement and collisions are resolved within a tick.
+    pub substeps: u32,
+    /// How many ticks pass between the decisions of a blob's brain.
+    pub brain_period: u32,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self { tick_rate: 60, substeps: 1, brain_period: 1 }
+    }
+}
+
+impl Timing {
+    pub const TICK_RATES: RangeInclusive<u32> = 10..=240;
+    pub const SUBSTEPS: RangeInclusive<u32> = 1..=8;
+    pub const BRAIN_PERIODS: RangeInclusive<u32> = 1..=30;
+
+    /// The most seconds of a frame the window catches up on, so one
+    /// that was stalled, dragged or asleep does not return to a burst
+    /// of ticks.
+    pub const MAX_FRAME_TIME: f32 = 0.25;
+    /// The most ticks run in a frame. Time owed beyond them is dropped,
+    /// slowing the world down, rather than carried over to frames that
+    /// would only fall further behind.
+    pub const MAX_FRAME_TICKS: u32 = 512;
+
+    /// Seconds of simulation time in a single tick.
+    pub fn tick_length(&self) -> f32 { 1. / self.tick_rate as f32 }
+
+    /// The simulation time a frame owes, given what earlier frames left
+    /// over and the seconds it took at some speed, within the limits
+    /// above, and whether any was dropped for the most ticks.
+    pub fn owed(&self, left_over: f32, frame_time: f32, speed: f32) -> (f32, bool) {
+        let owed = left_over + frame_time.min(Self::MAX_FRAME_TIME) * speed;
+        let most = Self::MAX_FRAME_TICKS as f32 * self.tick_length();
+        (owed.min(most), owed > most)
+    }
+
+    /// Check that every knob is within its range.
+    pub fn validate(&self) -> Result<(), String> {
+        let check = |name: &str, value: u32, range: RangeInclusive<u32>| {
+            if range.contains(&value) { Ok(()) } else {
+                Err(format!("{} must be from {} to {}, got {}", name, range.start(), range.end(), value))
+            }
+        };
+        check("the tick rate", self.tick_rate, Self::TICK_RATES)?;
+        check("the substeps", self.substeps, Self::SUBSTEPS)?;
+        check("the brain period", self.brain_period, Self::BRAIN_PERIODS)
+    }
+}
+
+/// Everything needed to create a simulation.
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    /// The dimensions of the simulation's space.
+    pub size: Vector2,
+    /// Seed of the simulation's random number generator.
+    pub seed: u64,
+    pub parameters: Parameters,
+    /// Mutators applied on top of the parameters.
+    pub mutators: Vec<Mutator>,
+    pub timing: Timing,
+    /// Whether to check after every tick that no energy appears or
+    /// vanishes unaccounted, see `energy`.
+    pub audit_energy: bool,
+    /// How the platform's floating point functions are computed, see `math`.
+    pub float_mode: FloatMode,
+    /// Lamps placed in the world, see `light`.
+    pub lights: Vec<Light>,
+    /// How many of the latest events are kept, see `history`.
+    pub event_capacity: usize,
+}
+
+impl SimulationConfig {
+    pub fn new(size: Vector2) -> Self {
+        Self {
+            size, seed: 0, parameters: Parameters::default(), mutators: vec![], timing: Timing::default(),
+            audit_energy: false, float_mode: FloatMode::Native, lights: vec![], event_capacity: Simulation::EVENT_LOG_LENGTH,
+        }
+    }
+}
+
 pub struct Simulation {
     size: Vector2,
+    seed: u64,
+    rng: StdRng,
+    parameters: Parameters,
+    mutators: Vec<Mutator>,
+    timing: Timing,
+    float_mode: FloatMode,
+    lakes: Vec<Lake>,
+    lights: Vec<Light>,
     blobs: KeyedSet<Blob>,
     foods: KeyedSet<Food>,
     objects: HashMap<Key<Circle>, CircleObject>,
+    clock: Clock,
+    stats: StatsStore,
+    //  recent events with the tick they happened at
+    history: History,
+    census: Census,
+    //  recent meals with the tick they were eaten at
+    meals: VecDeque<(u64, Meal)>,
+    //  the species of blobs that died leaving a corpse, with the tick they died at
+    remains: VecDeque<(u64, Option<String>)>,
+    perception: BTreeMap<Key<Blob>, Perception>,
+    //  made in the last tick, by their x
+    noises: Vec<Noise>,
+    //  made in the last tick
+    energy_changes: Vec<EnergyChange>,
+    //  the longest a removed blob lived, in seconds
+    longest_life: f32,
+    records: Records,
+    //  the founders of species, see `taxonomy`
+    taxonomy: Taxonomy,
+    energy: Option<Audit>,
+    //  code embedders run around ticks, see `hooks`
+    hooks: SimulationHooks,
+    viewpoints: Viewpoints,
+    //  watched, see `watch`
+    regions: Vec<Region>,
+    //  observers, which nothing else in the world knows of, see `drones`
+    drones: KeyedSet<Drone>,
+    //  force fields with the tick they stop at, see `influence`
+    fields: Vec<(physics::Field, u64)>,
+    //  of the blob being traced, see `trace`
+    trace: Option<Trace>,
+    //  where the time of the ticks went, see `profile`
+    profile: Option<Profile>,
+    //  where blobs died lately, oldest first, see `memorial`
+    graves: VecDeque<Grave>,
+    //  the rain, storms and droughts of now, see `weather`
+    weather: Vec<Weather>,
+    //  parameters changed for single species, see `overrides`
+    overrides: Overrides,
+    //  of the blobs and food added now
+    source: Source,
     pub physics: physics::World,
 }
 
 impl Simulation {
-    const SELECTION_LAYER: physics::Layer = physics::Layer::new(4);
+    /// How far color channels of children drift at a mutation rate of 1.
+    const MUTATION_SHADE: f32 = 64.;
+    /// How many ticks pass between samples of the built-in statistics.
+    pub const STATS_PERIOD: u64 = 30;
+    /// How many of the latest events are kept, unless the config says otherwise.
+    pub const EVENT_LOG_LENGTH: usize = 1 << 16;
+    /// For how many ticks meals are kept.
+    pub const MEAL_LOG_TICKS: u64 = 3600;
+    /// How far a blob may move before it looks around again.
+    pub const PERCEPTION_TOLERANCE: f32 = 4.;
+    /// How many ticks a blob may go without looking around.
+    pub const PERCEPTION_MAX_AGE: u64 = 15;
+    /// Mixed into the seed for placing lakes.
+    const LAKE_SALT: u64 = 0x1a4e;
+    /// The systems drawing from streams of their own, see `math::random`.
+    const STEERING_STREAM: u64 = 1;
+    const MUTATION_STREAM: u64 = 2;
+    const WEATHER_STREAM: u64 = 3;
+    /// The version of the format of saved worlds.
+    pub const SAVE_VERSION: u32 = 18;
+    /// The oldest version of saved worlds that still loads, those
+    /// before 17 having no statistics and those before 18 no slots to
+    /// give out again.
+    pub const OLDEST_SAVE_VERSION: u32 = 16;
+    /// Where `F10` saves the first world and `F11` loads it from.
+    pub const SAVE_PATH: &'static str = "world.save";
+
+    /// Create a simulation from a config
+    pub fn new(config: SimulationConfig) -> Self {
+        let SimulationConfig { size, seed, mut parameters, mutators, timing, audit_energy, float_mode, lights, event_capacity } = config;
+        for mutator in &mutators {
+            mutator.apply(&mut parameters);
+        }
 
-    /// Create a simulation with a space of the given dimensions
-    pub fn new(size: Vector2) -> Self {
         let mut collision_matrix = CollisionMatrix::new();
         collision_matrix.insert(Blob::LAYER, physics::LayerMask::new(vec![Food::LAYER, Blob::LAYER]));
         collision_matrix.insert(Food::LAYER, physics::LayerMask::empty());
         collision_matrix.insert(Blob::SIGHT_LAYER, physics::LayerMask::new(vec![Food::LAYER, Blob::LAYER]));
-        collision_matrix.insert(Self::SELECTION_LAYER, physics::LayerMask::new(vec![Food::LAYER, Blob::LAYER]));
         Self {
             size,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+            lakes: Self::place_lakes(size, seed, parameters.water),
+            lights,
+            parameters,
+            mutators,
+            timing,
+            float_mode,
             blobs: KeyedSet::new(),
             foods: KeyedSet::new(),
             objects: HashMap::new(),
+            clock: Clock::new(timing.tick_length()),
+            stats: StatsStore::new(),
+            history: History::new(event_capacity),
+            census: Census::default(),
+            meals: VecDeque::new(),
+            remains: VecDeque::new(),
+            perception: BTreeMap::new(),
+            noises: vec![],
+            energy_changes: vec![],
+            longest_life: 0.,
+            records: Records::default(),
+            taxonomy: Taxonomy::default(),
+            //  an empty world stores nothing
+            energy: if audit_energy { Some(Audit::new(0.)) } else { None },
+            hooks: SimulationHooks::default(),
+            viewpoints: Viewpoints::default(),
+            regions: vec![],
+            drones: KeyedSet::new(),
+            fields: vec![],
+            overrides: Overrides::default(),
+            trace: None,
+            profile: None,
+            graves: VecDeque::new(),
+            weather: vec![],
+            source: Source::World,
             physics: physics::World::new(collision_matrix),
         }
-    }
+    }
+
+    /// An independent copy of the simulation as it is now, random
+    /// number generator included, which runs on exactly as the
+    /// original would until either is changed. For trying out what
+    /// an intervention would do, possibly on another thread.
+    pub fn fork(&self) -> Self {
+        Self {
+            size: self.size,
+            seed: self.seed,
+            rng: self.rng.clone(),
+            parameters: self.parameters.clone(),
+            mutators: self.mutators.clone(),
+            timing: self.timing,
+            float_mode: self.float_mode,
+            lakes: self.lakes.clone(),
+            lights: self.lights.clone(),
+            blobs: self.blobs.clone(),
+            foods: self.foods.clone(),
+            objects: self.objects.clone(),
+            clock: self.clock.clone(),
+            stats: self.stats.clone(),
+            history: self.history.clone(),
+            census: self.census.clone(),
+            meals: self.meals.clone(),
+            remains: self.remains.clone(),
+            perception: self.perception.clone(),
+            noises: self.noises.clone(),
+            energy_changes: self.energy_changes.clone(),
+            longest_life: self.longest_life,
+            records: self.records.clone(),
+            taxonomy: self.taxonomy.clone(),
+            energy: self.energy.clone(),
+            //  hooks belong to their embedder, which may not expect to run twice
+            hooks: SimulationHooks::default(),
+            viewpoints: self.viewpoints.clone(),
+            regions: self.regions.clone(),
+            drones: self.drones.clone(),
+            fields: self.fields.clone(),
+            overrides: self.overrides.clone(),
+            trace: self.trace.clone(),
+            //  a fork's ticks are its own
+            profile: self.profile.as_ref().map(|_| Profile::new()),
+            graves: self.graves.clone(),
+            weather: self.weather.clone(),
+            source: self.source,
+            physics: self.physics.clone(),
+        }
+    }
+
+    /// Lakes covering about a part of a world, placed by its seed.
+    fn place_lakes(size: Vector2, seed: u64, water: f32) -> Vec<Lake> {
+        //  a generator of its own, so worlds without water draw the same numbers as before
+        let mut rng = StdRng::seed_from_u64(seed ^ Self::LAKE_SALT);
+        let mut lakes = vec![];
+        let mut covered = 0.;
+        while covered < water * size.x * size.y {
+            let radius = size.x.min(size.y) * rng.gen_range(0.1..0.25);
+            let center = Vector2::new(rng.gen(), rng.gen()) * size;
+            covered += std::f32::consts::PI * radius * radius;
+            lakes.push(Lake { center, radius });
+        }
+        lakes
+    }
+
+    /// Returns the size of the simulation's space
+    pub fn size(&self) -> Vector2 { self.size }
+
+    pub fn lakes(&self) -> &[Lake] { &self.lakes }
+
+    pub fn in_water(&self, pos: Vector2) -> bool {
+        self.lakes.iter().any(|lake| lake.contains(pos))
+    }
+
+    pub fn lights(&self) -> &[Light] { &self.lights }
+
+    /// Place a lamp, saved with the world like those it was created with.
+    pub fn add_light(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+
+    /// The light of the sun now, see `light::sunlight`.
+    pub fn sunlight(&self) -> f32 {
+        light::sunlight(&self.clock, self.parameters.night, self.float_mode)
+    }
+
+    /// The light at a position, from 0 for darkness to 1.
+    pub fn light_at(&self, pos: Vector2) -> f32 {
+        light::illumination(self.sunlight(), &self.lights, pos)
+    }
+
+    /// Returns the seed the simulation was created with
+    pub fn seed(&self) -> u64 { self.seed }
+
+    /// Returns how the simulation computes functions such as sines
+    pub fn float_mode(&self) -> FloatMode { self.float_mode }
+
+    /// Returns the simulation's random number generator.
+    ///
+    /// Use it for anything random done to the simulation from the
+    /// outside, such as spawning, to keep runs reproducible.
+    pub fn rng(&mut self) -> &mut StdRng { &mut self.rng }
+
+    /// Returns the rules the simulation runs by
+    pub fn parameters(&self) -> &Parameters { &self.parameters }
+
+    /// Returns the mutators the simulation was created with
+    pub fn mutators(&self) -> &[Mutator] { &self.mutators }
+
+    /// Returns how finely the simulation divides time
+    pub fn timing(&self) -> &Timing { &self.timing }
+
+    /// The energy held by blobs, foods and corpses, see `energy`.
+    pub fn stored_energy(&self) -> f32 {
+        let blobs: f32 = self.blobs.values().map(|blob| blob.max_hunger - blob.hunger).sum();
+        blobs + self.foods.len() as f32 * FOOD_ENERGY
+    }
+
+    /// Count energy that moved, if energy is audited.
+    fn flow(&mut self, flows: Flows) {
+        if let Some(audit) = &mut self.energy {
+            audit.add(flows);
+        }
+    }
+
+    /// The longest any blob has lived so far, in seconds.
+    pub fn longest_life(&self) -> f32 {
+        self.blobs.values().map(|blob| blob.alive_time).fold(self.longest_life, f32::max)
+    }
+
+    /// Change a parameter from its name and textual value, from the next tick on.
+    pub fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), String> {
+        self.parameters.set(name, value)?;
+        if name == "water" {
+            self.lakes = Self::place_lakes(self.size, self.seed, self.parameters.water);
+        }
+        Ok(())
+    }
+
+    /// Mark something done to the world on the statistics timeline, at the current tick.
+    pub fn mark(&mut self, label: &str) {
+        self.stats.mark(self.clock.tick(), MarkKind::Change, label);
+    }
+
+    /// Bookmark a tick with a note on the statistics timeline.
+    pub fn bookmark(&mut self, tick: u64, note: &str) {
+        self.stats.mark(tick, MarkKind::Bookmark, note);
+    }
+
+    /// The numbered viewpoints of the world, see `viewpoints`.
+    pub fn viewpoints(&self) -> &Viewpoints { &self.viewpoints }
+
+    pub fn viewpoints_mut(&mut self) -> &mut Viewpoints { &mut self.viewpoints }
+
+    /// Add blobs and food as a source within a function, see `Source`.
+    pub fn spawning_as<R>(&mut self, source: Source, f: impl FnOnce(&mut Self) -> R) -> R {
+        let outer = std::mem::replace(&mut self.source, source);
+        let ret = f(self);
+        self.source = outer;
+        ret
+    }
+
+    /// The watched regions of the world, see `watch`.
+    pub fn regions(&self) -> &[Region] { &self.regions }
+
+    /// Watch a region, instead of any of the same name.
+    pub fn watch(&mut self, region: Region) {
+        match self.regions.iter_mut().find(|watched| watched.name == region.name) {
+            Some(watched) => *watched = region,
+            None => self.regions.push(region),
+        }
+    }
+
+    /// Stop watching a region, returning whether it was.
+    pub fn unwatch(&mut self, name: &str) -> bool {
+        let count = self.regions.len();
+        self.regions.retain(|region| region.name != name);
+        self.regions.len() < count
+    }
+
+    /// The drones of the world, see `drones`.
+    pub fn drones(&self) -> impl Iterator<Item = (Key<Drone>, &Drone)> + '_ {
+        self.drones.iter().map(|(&key, drone)| (key, drone))
+    }
+
+    pub fn get_drone_mut(&mut self, key: Key<Drone>) -> Option<&mut Drone> { self.drones.get_mut(key) }
+
+    pub fn add_drone(&mut self, drone: Drone) -> Key<Drone> {
+        self.drones.insert_in(self.source.namespace(), drone)
+    }
+
+    pub fn remove_drone(&mut self, key: Key<Drone>) -> Option<Drone> { self.drones.remove(key) }
+
+    /// The blobs and food in the cone of vision of a drone now.
+    pub fn drone_sightings(&self, key: Key<Drone>) -> Vec<Sighting> {
+        let drone = match self.drones.get(key) {
+            Some(drone) => drone,
+            None => return vec![],
+        };
+        let blobs = self.blobs.values()
+            .filter(|blob| drone.sees(blob.pos))
+            .map(|blob| Sighting { pos: blob.pos, radius: blob.radius, color: blob.color, food: false });
+        let foods = self.foods.values()
+            .filter(|food| drone.sees(food.pos))
+            .map(|food| Sighting { pos: food.pos, radius: Food::RADIUS, color: *food.color(), food: true });
+        blobs.chain(foods).collect()
+    }
+
+    /// The force fields acting on the blobs, see `influence`.
+    pub fn fields(&self) -> impl Iterator<Item = &physics::Field> + '_ {
+        self.fields.iter().map(|(field, _)| field)
+    }
+
+    /// Put a force field in the world for some ticks from the next on.
+    /// It replaces a field of the same force, so that one held in place
+    /// and added again every frame does not pile up.
+    pub fn add_field(&mut self, field: physics::Field, ticks: u64) {
+        self.fields.retain(|(other, _)| other.force != field.force);
+        self.fields.push((field, self.clock.tick() + ticks));
+    }
+
+    /// The parameters changed for single species, see `overrides`.
+    pub fn overrides(&self) -> &Overrides { &self.overrides }
+
+    /// Multiply a parameter for the blobs of a species from the next
+    /// tick on, see `overrides`, or with 1 stop.
+    pub fn set_override(&mut self, species: &str, parameter: &str, factor: f32) -> Result<(), String> {
+        self.overrides.set(species, parameter, factor)
+    }
+
+    /// Trace a blob from the next tick on, see `trace`, or stop tracing.
+    pub fn trace_blob(&mut self, blob: Option<Key<Blob>>) {
+        self.trace = blob.map(Trace::new);
+    }
+
+    pub fn trace(&self) -> Option<&Trace> { self.trace.as_ref() }
+
+    /// Time the phases of the ticks from the next on, see `profile`, or stop.
+    pub fn set_profiling(&mut self, on: bool) {
+        self.profile = if on { Some(self.profile.take().unwrap_or_default()) } else { None };
+    }
+
+    pub fn profile(&self) -> Option<&Profile> { self.profile.as_ref() }
+
+    /// Where blobs died in the last ticks, oldest first, see `memorial`.
+    pub fn graves(&self) -> impl Iterator<Item=&Grave> { self.graves.iter() }
+
+    /// The rain, storms and droughts over the world now, see `weather`.
+    pub fn weather(&self) -> impl Iterator<Item=&Weather> { self.weather.iter() }
+
+    /// How fast food grows at a place under the weather, as a part of
+    /// how fast it grows in the clear.
+    pub fn food_growth(&self, pos: Vector2) -> f32 {
+        weather::growth(&self.weather, pos)
+    }
+
+    /// The push of a storm this tick, the same for every blob in it.
+    pub fn gust(&self, storm: &Weather) -> Vector2 {
+        let gust = self.clock.tick() / weather::GUST_TICKS;
+        storm.gust(&mut Stream::new(self.seed, Self::WEATHER_STREAM, 2 * storm.start + 1, gust), self.float_mode)
+    }
+
+    /// Let the weather of the world change, and rain grow food, see `weather`.
+    fn update_weather(&mut self) {
+        let tick = self.clock.tick();
+        self.weather.retain(|weather| weather.until > tick);
+        if self.parameters.weather > 0. && tick > 0 && tick.is_multiple_of(weather::CHECK_PERIOD) {
+            let mut rng = Stream::new(self.seed, Self::WEATHER_STREAM, 0, tick);
+            let ticks_per_second = 1. / self.clock.tick_length();
+            for weather in weather::forecast(&mut rng, tick, self.clock.season(), self.size, ticks_per_second, self.parameters.weather) {
+                self.weather.push(weather);
+                self.bookmark(tick, &format!("{} began", weather.kind.name()));
+                self.log_event(Event::Weather(weather));
+            }
+        }
+        let rains: Vec<Weather> = self.weather.iter().filter(|weather| weather.kind == weather::Kind::Rain).copied().collect();
+        for rain in rains {
+            let mut rng = Stream::new(self.seed, Self::WEATHER_STREAM, 2 * rain.start + 2, tick);
+            if rng.gen::<f32>() < weather::RAIN_FOOD * self.clock.tick_length() {
+                let (angle, distance) = (rng.gen::<f32>() * std::f32::consts::TAU, rng.gen::<f32>().sqrt() * rain.radius);
+                let pos = rain.center + Vector2::new(self.float_mode.cos(angle), self.float_mode.sin(angle)) * distance;
+                self.insert_food(Vector2::new(pos.x.clamp(0., self.size.x), pos.y.clamp(0., self.size.y)));
+            }
+        }
+    }
+
+    /// Count the time since the last phase toward a phase, if profiling.
+    fn lap(&mut self, phase: &'static str) {
+        if let Some(profile) = &mut self.profile {
+            profile.lap(phase);
+        }
+    }
+
+    /// Change how finely the simulation divides time from the next tick on.
+    pub fn set_timing(&mut self, timing: Timing) -> Result<(), String> {
+        timing.validate()?;
+        self.timing = timing;
+        self.clock.set_tick_length(timing.tick_length());
+        Ok(())
+    }
+
+    /// Returns the simulation's clock
+    pub fn clock(&self) -> &Clock { &self.clock }
+
+    /// Returns the statistics recorded so far
+    pub fn stats(&self) -> &StatsStore { &self.stats }
+
+    /// Returns the statistics store, to register and push custom series
+    pub fn stats_mut(&mut self) -> &mut StatsStore { &mut self.stats }
+
+    /// Blobs and foods, the entities that make up most of the work.
+    pub fn entity_count(&self) -> usize {
+        self.blobs.len() + self.foods.len()
+    }
+
+    /// Add the estimated bytes held by each store, with names that start with a prefix.
+    pub fn account(&self, usage: &mut Usage, prefix: &str) {
+        let entry = |key: usize, value: usize| mem::size_of::<usize>() + key + value;
+        let names: usize = self.blobs.values()
+            .flat_map(|blob| blob.name.iter().chain(&blob.species))
+            .map(String::capacity)
+            .sum();
+        let perception: usize = self.perception.values()
+            .map(|perception| perception.neighbors.capacity() * mem::size_of::<Key<Circle>>())
+            .sum();
+        let stores = [
+            ("blobs", self.blobs.len() * entry(0, mem::size_of::<Blob>()) + names),
+            ("foods", self.foods.len() * entry(0, mem::size_of::<Food>())),
+            ("physics", self.physics.circles().len() * entry(0, mem::size_of::<Circle>())
+                + self.objects.len() * entry(mem::size_of::<Key<Circle>>(), mem::size_of::<CircleObject>())),
+            ("perception", self.perception.len() * entry(0, mem::size_of::<Perception>()) + perception),
+            ("noises", self.noises.capacity() * mem::size_of::<Noise>()),
+            ("energy changes", self.energy_changes.capacity() * mem::size_of::<EnergyChange>()),
+            ("stats", self.stats.bytes()),
+            ("events", self.history.bytes()),
+            ("meals", self.meals.capacity() * mem::size_of::<(u64, Meal)>()),
+            ("remains", self.remains.capacity() * mem::size_of::<(u64, Option<String>)>()),
+            ("graves", self.graves.capacity() * mem::size_of::<Grave>()),
+        ];
+        for &(name, bytes) in &stores {
+            usage.add(format!("{}{}", prefix, name), bytes);
+        }
+    }
+
+    /// Energy blobs gained by eating and lost by being killed in the last tick.
+    pub fn energy_changes(&self) -> &[EnergyChange] { &self.energy_changes }
+
+    /// The kept events that happened at or after a tick, oldest first.
+    pub fn events_since(&self, tick: u64) -> impl Iterator<Item=&(u64, Event)> {
+        self.history.since(tick)
+    }
+
+    /// The kept events, to query, see `history`.
+    pub fn history(&self) -> &History { &self.history }
+
+    pub fn history_mut(&mut self) -> &mut History { &mut self.history }
+
+    /// The living species and those that died out, see `census`.
+    pub fn census(&self) -> &Census { &self.census }
+
+    /// The records of the world over its whole run, see `records`.
+    pub fn records(&self) -> &Records { &self.records }
+
+    /// Give the blobs of a species, and its record, another name,
+    /// merging it with a species of that name. Returns how many
+    /// blobs were renamed.
+    pub fn rename_species(&mut self, from: &str, to: &str) -> usize {
+        let mut renamed = 0;
+        for blob in self.blobs.values_mut().filter(|blob| blob.species.as_deref() == Some(from)) {
+            blob.species = Some(to.to_string());
+            renamed += 1;
+        }
+        self.census.rename(from, to);
+        self.taxonomy.rename(from, to);
+        renamed
+    }
+
+    /// The kept meals eaten at or after a tick, oldest first.
+    pub fn meals_since(&self, tick: u64) -> impl Iterator<Item=&(u64, Meal)> {
+        self.meals.iter().skip_while(move |(t, _)| *t < tick)
+    }
+
+    /// The species of the kept blobs that died at or after a tick
+    /// leaving a corpse, oldest first. They are kept as long as meals.
+    pub fn remains_since(&self, tick: u64) -> impl Iterator<Item=&(u64, Option<String>)> {
+        self.remains.iter().skip_while(move |(t, _)| *t < tick)
+    }
+
+    fn log_meals(&mut self, meals: Vec<Meal>, remains: Vec<Option<String>>) {
+        let tick = self.clock.tick();
+        while self.meals.front().is_some_and(|&(t, _)| t + Self::MEAL_LOG_TICKS <= tick) {
+            self.meals.pop_front();
+        }
+        while self.remains.front().is_some_and(|&(t, _)| t + Self::MEAL_LOG_TICKS <= tick) {
+            self.remains.pop_front();
+        }
+        self.meals.extend(meals.into_iter().map(|meal| (tick, meal)));
+        self.remains.extend(remains.into_iter().map(|species| (tick, species)));
+    }
+
+    fn log_event(&mut self, event: Event) {
+        self.history.push(self.clock.tick(), event);
+    }
+
+    /// A hash of the evolving state of the simulation.
+    ///
+    /// Two simulations that ran the same ticks from the same seed
+    /// with the same commands have the same hash, on any machine
+    /// running the same version, whatever compiler built it.
+    pub fn state_hash(&self) -> u64 {
+        //  keys are written as 64 bits on every target
+        let mut hash = Fnv::default();
+        let mut write = |value: u64| hash.write_u64(value);
+        write(self.clock.tick());
+        for (key, blob) in &self.blobs {
+            write(key.index());
+            for v in [blob.pos, blob.direction, blob.velocity] {
+                write(v.x.to_bits() as u64);
+                write(v.y.to_bits() as u64);
+            }
+            write(blob.hunger.to_bits() as u64);
+        }
+        for (key, food) in &self.foods {
+            write(key.index());
+            write(food.pos.x.to_bits() as u64);
+            write(food.pos.y.to_bits() as u64);
+        }
+        hash.finish()
+    }
+
+    /// Write everything about the state of the simulation as text,
+    /// a line for the world and one for each blob and food.
+    pub fn write_state<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "world seed={} tick={} time={} size={:?} timing={:?} float_mode={:?} parameters={:?} mutators={:?}",
+            self.seed, self.clock.tick(), self.clock.time(), self.size, self.timing, self.float_mode, self.parameters, self.mutators,
+        )?;
+        for (key, blob) in &self.blobs {
+            writeln!(out, "blob {} {:?}", key.index(), blob)?;
+        }
+        for (key, food) in &self.foods {
+            writeln!(out, "food {} {:?}", key.index(), food)?;
+        }
+        Ok(())
+    }
+
+    /// Save everything in the world to come back to it later, see `load`.
+    ///
+    /// Statistics are kept with their marks and bookmarks, but events
+    /// and meals are history and are left out, as is what blobs last
+    /// saw and decided, which they see and decide again. The loaded
+    /// world's generator is seeded from the next number this one's
+    /// would draw.
+    pub fn save<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let color = |c: Color| format!("{}\t{}\t{}\t{}", c.r, c.g, c.b, c.a);
+        let timing = self.timing;
+        writeln!(out, "version\t{}", Self::SAVE_VERSION)?;
+        writeln!(out, "size\t{}\t{}", self.size.x, self.size.y)?;
+        writeln!(out, "seed\t{}", self.seed)?;
+        writeln!(out, "rng\t{}", self.rng.clone().gen::<u64>())?;
+        writeln!(out, "clock\t{}\t{}", self.clock.tick(), self.clock.time())?;
+        writeln!(out, "timing\t{}\t{}\t{}", timing.tick_rate, timing.substeps, timing.brain_period)?;
+        writeln!(out, "float_mode\t{}", self.float_mode.name())?;
+        writeln!(out, "audit_energy\t{}", self.energy.is_some())?;
+        writeln!(out, "longest_life\t{}", self.longest_life)?;
+        //  with the mutators already applied
+        for &name in &Parameters::NAMES {
+            match self.parameters.number(name) {
+                Some(value) => writeln!(out, "parameter\t{}\t{}", name, value)?,
+                None => writeln!(out, "parameter\t{}\t{}", name, self.parameters.flag(name).unwrap())?,
+            }
+        }
+        for mutator in &self.mutators {
+            writeln!(out, "mutator\t{}", mutator.id())?;
+        }
+        for lamp in &self.lights {
+            writeln!(out, "light\t{}\t{}\t{}\t{}", lamp.pos.x, lamp.pos.y, lamp.radius, lamp.brightness)?;
+        }
+        for (number, viewpoint) in self.viewpoints.iter() {
+            writeln!(out, "viewpoint\t{}\t{}\t{}\t{}", number, viewpoint.center.x, viewpoint.center.y, viewpoint.zoom)?;
+        }
+        for region in &self.regions {
+            writeln!(out, "region\t{}", region.to_spec())?;
+        }
+        writeln!(out, "next\t{}\t{}\t{}", self.blobs.fresh_key().index(), self.foods.fresh_key().index(), self.drones.fresh_key().index())?;
+        //  and of the other sources, those that added anything
+        for source in &Source::ALL[1..] {
+            let namespace = source.namespace();
+            let next = [self.blobs.fresh_key_in(namespace).index(), self.foods.fresh_key_in(namespace).index(), self.drones.fresh_key_in(namespace).index()];
+            if next.iter().any(|&next| next > namespace.start() as u64) {
+                writeln!(out, "next_in\t{}\t{}\t{}\t{}", namespace.index(), next[0], next[1], next[2])?;
+            }
+        }
+        for (key, blob) in &self.blobs {
+            writeln!(out, "blob\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                key.index(),
+                //  names are never empty, so an empty one stands for none
+                blob.name.as_deref().unwrap_or(""), blob.species.as_deref().unwrap_or(""), blob.diet.name(), blob.brain.id(),
+                blob.parent.map_or(String::new(), |parent| parent.index().to_string()), blob.generation,
+                blob.alive_time, blob.foods_eaten, blob.children,
+                blob.pos.x, blob.pos.y, blob.direction.x, blob.direction.y, blob.velocity.x, blob.velocity.y,
+                blob.radius, color(blob.color),
+                blob.speed, blob.rotation_speed,
+                blob.pov, blob.sight_depth,
+                color(blob.favorite_color), blob.color_attraction, blob.color_repulsion,
+                blob.hunger, blob.max_hunger, blob.hunger_reduction, blob.hunger_division,
+                blob.attack, blob.defence, blob.swimming, blob.hearing,
+                blob.aura.name(), blob.aura_strength,
+            )?;
+        }
+        for (key, blob) in &self.blobs {
+            for tag in &blob.tags {
+                writeln!(out, "tag\t{}\t{}\t{}", key.index(), tag.text, color(tag.color))?;
+            }
+        }
+        for (key, food) in &self.foods {
+            writeln!(out, "food\t{}\t{}\t{}\t{}\t{}", key.index(), food.pos.x, food.pos.y, food.corpse, food.spoiled)?;
+        }
+        for (key, drone) in &self.drones {
+            //  no target is written as empty fields
+            let (target_x, target_y) = drone.target.map_or((String::new(), String::new()), |target| (target.x.to_string(), target.y.to_string()));
+            writeln!(out, "drone\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}", key.index(), drone.pos.x, drone.pos.y,
+                drone.direction.x, drone.direction.y, drone.pov, drone.range, drone.speed, target_x, target_y)?;
+        }
+        //  the slots of removed ones, given out again in the same order
+        for namespace in Source::ALL.iter().map(|source| source.namespace()) {
+            for key in self.blobs.vacant_in(namespace) {
+                writeln!(out, "vacant\tblob\t{}", key.index())?;
+            }
+            for key in self.foods.vacant_in(namespace) {
+                writeln!(out, "vacant\tfood\t{}", key.index())?;
+            }
+            for key in self.drones.vacant_in(namespace) {
+                writeln!(out, "vacant\tdrone\t{}", key.index())?;
+            }
+        }
+        for (field, until) in &self.fields {
+            writeln!(out, "field\t{}\t{}\t{}\t{}\t{}\t{}", field.center.x, field.center.y, field.radius, field.force.name(), field.force.amount(), until)?;
+        }
+        for weather in &self.weather {
+            writeln!(out, "weather\t{}", weather.to_fields())?;
+        }
+        for (species, parameter, factor) in self.overrides.iter() {
+            writeln!(out, "override\t{}\t{}\t{}", species, parameter, factor)?;
+        }
+        for record in self.records.iter() {
+            writeln!(out, "record\t{}\t{}\t{}\t{}", record.kind.name(), record.value, record.holder.as_deref().unwrap_or(""), record.tick)?;
+        }
+        //  a line of the stats store's own format each
+        let mut stats = vec![];
+        self.stats.write_to(&mut stats)?;
+        for line in String::from_utf8_lossy(&stats).lines() {
+            writeln!(out, "stats\t{}", line)?;
+        }
+        Ok(())
+    }
+
+    /// A world saved by `save`, under the same keys, failing at the
+    /// first line that cannot be read or describes what cannot be, such
+    /// as a gene out of bounds, a blob outside the world or a parent
+    /// that never was.
+    pub fn load<R: BufRead>(input: R) -> Result<Self, String> {
+        Self::load_with(input, false).map(|(sim, _)| sim)
+    }
+
+    /// A world saved by `save`, in which what cannot be is repaired, or
+    /// dropped if it cannot be repaired, with a warning for each at its
+    /// line. Lines that cannot be read still fail.
+    pub fn load_repairing<R: BufRead>(input: R) -> Result<(Self, Vec<String>), String> {
+        Self::load_with(input, true)
+    }
+
+    fn load_with<R: BufRead>(input: R, repair: bool) -> Result<(Self, Vec<String>), String> {
+        let mut lines = input.lines().enumerate().map(|(i, line)| (i + 1, line));
+        let mut header = |name: &str| -> Result<(usize, Vec<String>), String> {
+            let (number, line) = lines.next().ok_or_else(|| format!("missing `{}`", name))?;
+            let line = line.map_err(|e| e.to_string())?;
+            let mut fields = line.split('\t').map(str::to_string);
+            match fields.next() {
+                Some(found) if found == name => Ok((number, fields.collect())),
+                found => Err(format!("line {}: expected `{}`, got `{}`", number, name, found.unwrap_or_default())),
+            }
+        };
+        let (number, fields) = header("version")?;
+        let version: u32 = Fields::new(number, &fields).next()?;
+        if !(Self::OLDEST_SAVE_VERSION..=Self::SAVE_VERSION).contains(&version) {
+            return Err(format!("saved with version {}, expected {} to {}", version, Self::OLDEST_SAVE_VERSION, Self::SAVE_VERSION));
+        }
+        let mut config = SimulationConfig::new(Vector2::zero());
+        let (number, fields) = header("size")?;
+        let mut f = Fields::new(number, &fields);
+        config.size = Vector2::new(f.next()?, f.next()?);
+        //  everything is clamped into the world, which a world of no size breaks
+        if !(config.size.x > 0. && config.size.y > 0. && config.size.x.is_finite() && config.size.y.is_finite()) {
+            return Err(format!("line {}: invalid world size {}x{}", number, config.size.x, config.size.y));
+        }
+        let (number, fields) = header("seed")?;
+        config.seed = Fields::new(number, &fields).next()?;
+        let (number, fields) = header("rng")?;
+        let rng_seed: u64 = Fields::new(number, &fields).next()?;
+        let (number, fields) = header("clock")?;
+        let mut f = Fields::new(number, &fields);
+        let (tick, time): (u64, f64) = (f.next()?, f.next()?);
+        let (number, fields) = header("timing")?;
+        let mut f = Fields::new(number, &fields);
+        config.timing = Timing { tick_rate: f.next()?, substeps: f.next()?, brain_period: f.next()? };
+        config.timing.validate().map_err(|e| format!("line {}: {}", number, e))?;
+        let (number, fields) = header("float_mode")?;
+        let mode: String = Fields::new(number, &fields).next()?;
+        config.float_mode = FloatMode::from_name(&mode).ok_or_else(|| format!("line {}: unknown float mode `{}`", number, mode))?;
+        let (number, fields) = header("audit_energy")?;
+        config.audit_energy = Fields::new(number, &fields).next()?;
+        let (number, fields) = header("longest_life")?;
+        let longest_life = Fields::new(number, &fields).next()?;
+
+        //  the parameters were saved with the mutators applied, which are kept to be shown
+        let mut mutators = vec![];
+        let mut viewpoints = Viewpoints::default();
+        let mut regions = vec![];
+        let mut force_fields = vec![];
+        let mut weather = vec![];
+        let mut overrides = Overrides::default();
+        let mut records = Records::default();
+        //  the stats store's lines, and where they start
+        let (mut stats, mut stats_line) = (String::new(), None);
+        let mut sim = None;
+        let mut warnings = vec![];
+        let mut problem = |number: usize, problem: String, repair_done: &str| if repair {
+            warnings.push(format!("line {}: {}, {}", number, problem, repair_done));
+            Ok(())
+        } else {
+            Err(format!("line {}: {}", number, problem))
+        };
+        let world = config.size;
+        let inside = |pos: Vector2| (0. ..=world.x).contains(&pos.x) && (0. ..=world.y).contains(&pos.y);
+        let clamp = |pos: Vector2| Vector2::new(pos.x.clamp(0., world.x), pos.y.clamp(0., world.y));
+        for (number, line) in lines {
+            let line = line.map_err(|e| e.to_string())?;
+            let fields: Vec<_> = line.split('\t').collect();
+            let mut f = Fields::new(number, &fields[1..]);
+            match fields[0] {
+                "parameter" => {
+                    let (name, value): (String, String) = (f.next()?, f.next()?);
+                    config.parameters.set(&name, &value).map_err(|e| format!("line {}: {}", number, e))?;
+                },
+                "mutator" => {
+                    let id: String = f.next()?;
+                    mutators.push(Mutator::from_id(&id).ok_or_else(|| format!("line {}: unknown mutator `{}`", number, id))?);
+                },
+                "light" => {
+                    let mut light = Light { pos: Vector2::new(f.next()?, f.next()?), radius: f.next()?, brightness: f.next()? };
+                    if !(light.pos.x.is_finite() && light.pos.y.is_finite() && light.radius >= 0. && light.radius.is_finite()) {
+                        problem(number, format!("a light at {:?} of radius {}", light.pos, light.radius), "dropped")?;
+                        continue;
+                    }
+                    if !inside(light.pos) {
+                        problem(number, format!("a light at {:?} outside the world", light.pos), "moved inside")?;
+                        light.pos = clamp(light.pos);
+                    }
+                    config.lights.push(light);
+                },
+                "viewpoint" => {
+                    let slot: usize = f.next()?;
+                    let center = Vector2::new(f.next()?, f.next()?);
+                    viewpoints.set(slot, Viewpoint { center, zoom: f.next()? }).map_err(|e| format!("line {}: {}", number, e))?;
+                },
+                "region" => {
+                    let spec: String = f.next()?;
+                    let mut region = Region::parse(&spec, None).map_err(|e| format!("line {}: {}", number, e))?;
+                    let Rectangle { x, y, width, height } = region.rect;
+                    let (left, top) = (x.max(0.), y.max(0.));
+                    let cut = Rectangle::new(left, top, (x + width).min(world.x) - left, (y + height).min(world.y) - top);
+                    match cut {
+                        rect if rect == region.rect => (),
+                        rect if rect.width > 0. && rect.height > 0. => {
+                            problem(number, format!("region {} reaches outside the world", region.name), "cut to it")?;
+                            region.rect = rect;
+                        },
+                        _ => {
+                            problem(number, format!("region {} is outside the world", region.name), "dropped")?;
+                            continue;
+                        },
+                    }
+                    regions.push(region);
+                },
+                "field" => {
+                    let center = Vector2::new(f.next()?, f.next()?);
+                    let radius: f32 = f.next()?;
+                    let (force, amount): (String, f32) = (f.next()?, f.next()?);
+                    let until: u64 = f.next()?;
+                    let force = physics::Force::from_name(&force, amount).ok_or_else(|| format!("line {}: unknown force `{}`", number, force))?;
+                    if !(center.x.is_finite() && center.y.is_finite() && radius >= 0. && radius.is_finite() && amount.is_finite()) {
+                        problem(number, format!("a field at {:?} of radius {} and strength {}", center, radius, amount), "dropped")?;
+                        continue;
+                    }
+                    force_fields.push((physics::Field { center, radius, force }, until));
+                },
+                "weather" => {
+                    let kind: String = f.next()?;
+                    let kind = weather::Kind::from_name(&kind).ok_or_else(|| format!("line {}: unknown weather `{}`", number, kind))?;
+                    let (center, radius): (Vector2, f32) = (Vector2::new(f.next()?, f.next()?), f.next()?);
+                    let (start, until) = (f.next()?, f.next()?);
+                    if !(center.x.is_finite() && center.y.is_finite() && radius >= 0. && radius.is_finite()) {
+                        problem(number, format!("{} at {:?} of radius {}", kind.name(), center, radius), "dropped")?;
+                        continue;
+                    }
+                    weather.push(Weather { kind, center, radius, start, until });
+                },
+                "override" => {
+                    let (species, parameter, factor): (String, String, f32) = (f.next()?, f.next()?, f.next()?);
+                    overrides.set(&species, &parameter, factor).map_err(|e| format!("line {}: {}", number, e))?;
+                },
+                "record" => {
+                    let kind: String = f.next()?;
+                    let kind = records::Kind::from_name(&kind).ok_or_else(|| format!("line {}: unknown record `{}`", number, kind))?;
+                    let (value, holder): (f32, String) = (f.next()?, f.next()?);
+                    if !(value >= 0. && value.is_finite()) {
+                        problem(number, format!("the record {} of {}", kind.name(), value), "dropped")?;
+                        continue;
+                    }
+                    records.restore(Record { kind, value, holder: Some(holder).filter(|holder| !holder.is_empty()), tick: f.next()? });
+                },
+                "next" => {
+                    let mut created = Self::new(config.clone());
+                    created.blobs.reserve(Key::from_index(f.next()?));
+                    created.foods.reserve(Key::from_index(f.next()?));
+                    created.drones.reserve(Key::from_index(f.next()?));
+                    sim = Some(created);
+                },
+                "next_in" => {
+                    let sim = sim.as_mut().ok_or_else(|| format!("line {}: expected `next` before `next_in`", number))?;
+                    let namespace: u8 = f.next()?;
+                    if namespace == 0 || namespace >= Namespace::COUNT {
+                        return Err(format!("line {}: no namespace {}", number, namespace));
+                    }
+                    let namespace = Namespace::new(namespace);
+                    sim.blobs.reserve_in(namespace, Key::from_index(f.next()?));
+                    sim.foods.reserve_in(namespace, Key::from_index(f.next()?));
+                    sim.drones.reserve_in(namespace, Key::from_index(f.next()?));
+                },
+                "drone" => {
+                    let sim = sim.as_mut().ok_or_else(|| format!("line {}: expected `next` before drones", number))?;
+                    let key: u64 = f.next()?;
+                    if !sim.drones.was_generated(Key::from_index(key)) || sim.drones.contains_key(Key::from_index(key)) {
+                        problem(number, format!("drone {} is there twice or was never given out", key), "dropped")?;
+                        continue;
+                    }
+                    let pos = Vector2::new(f.next()?, f.next()?);
+                    let direction = Vector2::new(f.next()?, f.next()?);
+                    let mut drone = Drone { pos, direction, pov: f.next()?, range: f.next()?, speed: f.next()?, target: None };
+                    let (target_x, target_y): (String, String) = (f.next()?, f.next()?);
+                    if !target_x.is_empty() {
+                        let parse = |value: &str| value.parse().map_err(|_| format!("line {}: invalid value `{}`", number, value));
+                        drone.target = Some(Vector2::new(parse(&target_x)?, parse(&target_y)?));
+                    }
+                    let finite = |v: Vector2| v.x.is_finite() && v.y.is_finite();
+                    if !finite(drone.pos) || !drone.target.is_none_or(finite) {
+                        problem(number, format!("drone {} is at {:?} going to {:?}", key, drone.pos, drone.target), "dropped")?;
+                        continue;
+                    }
+                    if !inside(drone.pos) || !drone.target.is_none_or(inside) {
+                        problem(number, format!("drone {} is at {:?} going to {:?}, outside the world", key, drone.pos, drone.target), "moved inside")?;
+                        drone.pos = clamp(drone.pos);
+                        drone.target = drone.target.map(clamp);
+                    }
+                    let unit = (drone.direction.length() - 1.).abs() < 1e-3;
+                    if !unit {
+                        problem(number, format!("drone {} looks along {:?}", key, drone.direction), "turned right")?;
+                        drone.direction = Vector2::new(1., 0.);
+                    }
+                    if !((0. ..=180.).contains(&drone.pov) && drone.range >= 0. && drone.range.is_finite() && drone.speed >= 0. && drone.speed.is_finite()) {
+                        problem(number, format!("drone {} has view {}, range {} and speed {}", key, drone.pov, drone.range, drone.speed), "reset")?;
+                        drone.pov = Drone::POV;
+                        drone.range = Drone::RANGE;
+                        drone.speed = Drone::SPEED;
+                    }
+                    sim.drones.insert_at(Key::from_index(key), drone);
+                },
+                "blob" | "food" => {
+                    let sim = sim.as_mut().ok_or_else(|| format!("line {}: expected `next` before blobs and foods", number))?;
+                    let key: u64 = f.next()?;
+                    //  keys of either kind that were given out are reserved by now
+                    let generated = if fields[0] == "food" { sim.foods.was_generated(Key::from_index(key)) } else { sim.blobs.was_generated(Key::from_index(key)) };
+                    let again = if fields[0] == "food" { sim.foods.contains_key(Key::from_index(key)) } else { sim.blobs.contains_key(Key::from_index(key)) };
+                    if !generated || again {
+                        let why = if again { "there twice" } else { "never given out" };
+                        problem(number, format!("{} {} is {}", fields[0], key, why), "dropped")?;
+                        continue;
+                    }
+                    if fields[0] == "food" {
+                        let mut pos = Vector2::new(f.next()?, f.next()?);
+                        let corpse = f.next()?;
+                        let mut spoiled: f32 = f.next()?;
+                        if !(pos.x.is_finite() && pos.y.is_finite()) {
+                            problem(number, format!("food {} has position {:?}", key, pos), "dropped")?;
+                            continue;
+                        }
+                        if !inside(pos) {
+                            problem(number, format!("food {} is at {:?}, outside the world", key, pos), "moved inside")?;
+                            pos = clamp(pos);
+                        }
+                        if !(spoiled >= 0. && spoiled.is_finite()) {
+                            problem(number, format!("food {} has spoiled {}", key, spoiled), "reset")?;
+                            spoiled = 0.;
+                        }
+                        sim.restore_food(Key::from_index(key), pos, corpse, spoiled);
+                        continue;
+                    }
+                    let name: String = f.next()?;
+                    let species: String = f.next()?;
+                    let diet: String = f.next()?;
+                    let brain: String = f.next()?;
+                    let parent: String = f.next()?;
+                    let color = |f: &mut Fields<&str>| -> Result<Color, String> { Ok(Color::new(f.next()?, f.next()?, f.next()?, f.next()?)) };
+                    let mut blob = Blob {
+                        name: Some(name).filter(|name| !name.is_empty()),
+                        species: Some(species).filter(|species| !species.is_empty()),
+                        diet: Diet::from_name(&diet).ok_or_else(|| format!("line {}: unknown diet `{}`", number, diet))?,
+                        brain: BrainKind::from_id(&brain).ok_or_else(|| format!("line {}: unknown brain `{}`", number, brain))?,
+                        parent: match parent.as_str() {
+                            "" => None,
+                            index => Some(Key::from_index(index.parse().map_err(|_| format!("line {}: invalid value `{}`", number, index))?)),
+                        },
+                        generation: f.next()?,
+                        alive_time: f.next()?,
+                        foods_eaten: f.next()?,
+                        children: f.next()?,
+                        pos: Vector2::new(f.next()?, f.next()?),
+                        direction: Vector2::new(f.next()?, f.next()?),
+                        velocity: Vector2::new(f.next()?, f.next()?),
+                        radius: f.next()?,
+                        color: color(&mut f)?,
+                        speed: f.next()?,
+                        rotation_speed: f.next()?,
+                        pov: f.next()?,
+                        sight_depth: f.next()?,
+                        favorite_color: color(&mut f)?,
+                        color_attraction: f.next()?,
+                        color_repulsion: f.next()?,
+                        hunger: f.next()?,
+                        max_hunger: f.next()?,
+                        hunger_reduction: f.next()?,
+                        hunger_division: f.next()?,
+                        attack: f.next()?,
+                        defence: f.next()?,
+                        swimming: f.next()?,
+                        hearing: f.next()?,
+                        aura: {
+                            let name: String = f.next()?;
+                            Aura::from_name(&name).ok_or_else(|| format!("line {}: unknown aura `{}`", number, name))?
+                        },
+                        aura_strength: f.next()?,
+                        slowed: 0.,
+                        tags: vec![],
+                        //  given their circles below
+                        circle: Key::from_index(0),
+                        sight_circle: Key::from_index(0),
+                        intent: None,
+                    };
+                    match blob.repair(sim.size) {
+                        Ok(problems) => for found in problems {
+                            problem(number, format!("blob {} has {}", key, found), "repaired")?;
+                        },
+                        Err(found) => {
+                            problem(number, format!("blob {} has {}", key, found), "dropped")?;
+                            continue;
+                        },
+                    }
+                    //  a parent may be gone, but not one that never was
+                    if let Some(parent) = blob.parent.filter(|&parent| !sim.blobs.was_generated(parent) || parent.index() == key) {
+                        problem(number, format!("blob {} has the parent {}, which never was", key, parent.index()), "forgotten")?;
+                        blob.parent = None;
+                    }
+                    sim.restore_blob(Key::from_index(key), blob);
+                },
+                "vacant" => {
+                    let sim = sim.as_mut().ok_or_else(|| format!("line {}: expected `next` before vacant keys", number))?;
+                    let (kind, key): (String, u64) = (f.next()?, f.next()?);
+                    let vacated = match kind.as_str() {
+                        "blob" => sim.blobs.vacate(Key::from_index(key)),
+                        "food" => sim.foods.vacate(Key::from_index(key)),
+                        "drone" => sim.drones.vacate(Key::from_index(key)),
+                        _ => return Err(format!("line {}: unknown `{}`", number, kind)),
+                    };
+                    if !vacated {
+                        problem(number, format!("{} {} is taken or was never given out", kind, key), "not given out again")?;
+                    }
+                },
+                "tag" => {
+                    let sim = sim.as_mut().ok_or_else(|| format!("line {}: expected `next` before tags", number))?;
+                    let key: u64 = f.next()?;
+                    let text: String = f.next()?;
+                    let tag = Tag { text, color: Color::new(f.next()?, f.next()?, f.next()?, f.next()?) };
+                    match sim.get_blob_mut(Key::from_index(key)) {
+                        Some(blob) => blob.tag(tag),
+                        None => problem(number, format!("no blob {} to tag", key), "dropped")?,
+                    }
+                },
+                "stats" => {
+                    stats_line.get_or_insert(number);
+                    stats += line.split_once('\t').map_or("", |(_, rest)| rest);
+                    stats.push('\n');
+                },
+                "" => (),
+                kind => return Err(format!("line {}: unknown `{}`", number, kind)),
+            }
+        }
+        let mut sim = sim.ok_or("missing `next`")?;
+        sim.rng = StdRng::seed_from_u64(rng_seed);
+        sim.clock = Clock::restore(sim.timing.tick_length(), tick, time);
+        sim.mutators = mutators;
+        sim.longest_life = longest_life;
+        sim.records = records;
+        sim.viewpoints = viewpoints;
+        sim.regions = regions;
+        sim.fields = force_fields;
+        sim.weather = weather;
+        sim.overrides = overrides;
+        if let Some(number) = stats_line {
+            sim.stats = StatsStore::read_from(stats.as_bytes()).map_err(|e| format!("line {}: {}", number, e))?;
+            //  later samples would come before those saved
+            if sim.stats.ids().any(|id| sim.stats.history(id).last().is_some_and(|sample| sample.tick > tick)) {
+                problem(number, format!("statistics past tick {}", tick), "dropped")?;
+                sim.stats = StatsStore::new();
+            }
+        }
+        if sim.energy.is_some() {
+            sim.energy = Some(Audit::new(sim.stored_energy()));
+        }
+        Ok((sim, warnings))
+    }
+
+    /// Put a saved blob back under its key, with circles of its own.
+    fn restore_blob(&mut self, key: Key<Blob>, mut blob: Blob) {
+        blob.circle = self.physics.insert(Circle {
+            center: blob.pos, radius: blob.radius, layer: Blob::LAYER,
+        });
+        blob.sight_circle = self.physics.insert(Circle {
+            center: blob.pos, radius: blob.sight_depth, layer: Blob::SIGHT_LAYER,
+        });
+        self.objects.insert(blob.circle, CircleObject::Blob(key));
+        self.objects.insert(blob.sight_circle, CircleObject::BlobSight(key));
+        self.blobs.insert_at(key, blob);
+    }
+
+    /// Put a saved food back under its key.
+    fn restore_food(&mut self, key: Key<Food>, pos: Vector2, corpse: bool, spoiled: f32) {
+        let circle = self.physics.insert(Circle {
+            center: pos, radius: Food::RADIUS, layer: Food::LAYER,
+        });
+        self.objects.insert(circle, CircleObject::Food(key));
+        let aquatic = self.in_water(pos);
+        self.foods.insert_at(key, Food { pos, circle, corpse, spoiled, aquatic });
+    }
+
+    /// Record a sample of each built-in statistic.
+    fn record_stats(&mut self) {
+        let tick = self.clock.tick();
+        let population = self.blobs.len() as f32;
+        let mean = |trait_of: fn(&Blob) -> f32| if self.blobs.is_empty() { 0. } else {
+            self.blobs.values().map(trait_of).sum::<f32>() / population
+        };
+        //  blobs added and removed since the last sample
+        let since = (tick + 1).saturating_sub(Self::STATS_PERIOD);
+        let count = |kind| self.history.query(Query { kind: Some(kind), ..Query::ticks(since..u64::MAX) }).count();
+        let births = count(Kind::Born);
+        let deaths = count(Kind::Killed) + count(Kind::Starved);
+        let samples = [
+            ("population", population),
+            ("food", self.foods.len() as f32),
+            ("births", births as f32),
+            ("deaths", deaths as f32),
+            ("mean speed", mean(|blob| blob.speed)),
+            ("mean size", mean(Blob::radius)),
+            ("mean field of view", mean(|blob| blob.pov)),
+            ("mean sight", mean(Blob::sight_depth)),
+        ];
+        for &(name, value) in &samples {
+            let id = self.stats.register(name);
+            self.stats.push(id, tick, value);
+        }
+        for (name, value) in spatial::samples(self).into_iter().chain(histogram::samples(self)) {
+            let id = self.stats.register(&name);
+            self.stats.push(id, tick, value);
+        }
+        let measured: Vec<_> = self.regions.iter().map(|region| region.measure(self)).collect();
+        let mut alerts = vec![];
+        for (region, values) in self.regions.iter_mut().zip(measured) {
+            for (&measure, &value) in Measure::ALL.iter().zip(&values) {
+                let id = self.stats.register(&region.series(measure));
+                self.stats.push(id, tick, value);
+            }
+            alerts.extend(region.sample(&values).into_iter().map(|(alert, value)| (region.name.clone(), alert, value)));
+        }
+        let drones: Vec<_> = self.drones.keys().collect();
+        for drone in drones {
+            let sightings = self.drone_sightings(drone);
+            let blobs = sightings.iter().filter(|sighting| !sighting.food).count();
+            for &(what, value) in &[("blobs", blobs), ("food", sightings.len() - blobs)] {
+                let id = self.stats.register(&format!("{} seen by drone {}", what, drone.index()));
+                self.stats.push(id, tick, value as f32);
+            }
+        }
+        for (region, alert, value) in alerts {
+            self.stats.mark(tick, MarkKind::Change, &format!("alert: {} {}", region, alert));
+            self.log_event(Event::Alert { region, alert, value });
+        }
+        let stored = self.stored_energy();
+        if let Some(audit) = &mut self.energy {
+            let flows = audit.take_sample();
+            for &(name, value) in &[("energy stored", stored), ("energy in", flows.entered()), ("energy out", flows.left())] {
+                let id = self.stats.register(name);
+                self.stats.push(id, tick, value);
+            }
+        }
+    }
+
+    /// Draw the part of the simulation that belongs to a render layer.
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, layer: RenderLayer, context: &RenderContext) {
+        match layer {
+            RenderLayer::Terrain => {
+                draw.clear_background(context.theme.background);
+                //  blobs stop at the walls, drawn just outside so none covers them
+                let thickness = 2. / context.zoom;
+                let (min, max) = (Vector2::one() * -thickness / 2., self.size + Vector2::one() * thickness / 2.);
+                let corners = [min, Vector2::new(max.x, min.y), max, Vector2::new(min.x, max.y)];
+                for i in 0..corners.len() {
+                    draw.draw_line_ex(corners[i], corners[(i + 1) % corners.len()], thickness, context.theme.panel_border);
+                }
+                let water = theme::lerp_color(context.theme.background, Lake::COLOR, Lake::SHADE);
+                for lake in &self.lakes {
+                    shapes::draw_circle(draw, lake.center, lake.radius, context.zoom, water);
+                }
+            },
+            RenderLayer::Food | RenderLayer::Corpses => {
+                let corpses = layer == RenderLayer::Corpses;
+                for (_, food) in self.foods.iter().filter(|(_, food)| food.corpse == corpses) {
+                    food.draw(draw, context);
+                }
+            },
+            RenderLayer::Blobs => {
+                //  largest first, each shadowing those under it, see `depth`
+                let crowding = if context.crowding { depth::crowding(self.blobs()) } else { BTreeMap::new() };
+                for (key, blob) in depth::order(self.blobs()) {
+                    if context.style != Style::Minimal {
+                        depth::draw_shadow(draw, blob.pos, blob.radius, context.zoom);
+                    }
+                    blob.draw(draw, context);
+                    if let Some(&overlaps) = crowding.get(&key) {
+                        depth::draw_crowding(draw, blob.pos, blob.radius, context.zoom, overlaps, context.theme);
+                    }
+                }
+            },
+            RenderLayer::Heatmap | RenderLayer::Effects | RenderLayer::Ui => (),
+        }
+    }
+
+    /// Bring the perception of blobs up to date where it is stale.
+    ///
+    /// Looking around is the most expensive part of a brain, so a
+    /// blob reuses what it saw last while it stays about where it
+    /// looked from and nothing appears or dies near it.
+    fn perceive(&mut self, blobs: &[Key<Blob>]) {
+        let tick = self.clock.tick();
+        let stale: BTreeSet<Key<Circle>> = blobs.iter()
+            .filter_map(|&key| {
+                let blob = self.blobs.get(key)?;
+                let fresh = self.perception.get(&key).is_some_and(|perception| {
+                    (blob.pos - perception.pos).length() <= Self::PERCEPTION_TOLERANCE
+                    && tick - perception.tick < Self::PERCEPTION_MAX_AGE
+                });
+                if fresh { None } else { Some(blob.sight_circle) }
+            })
+            .collect();
+        if stale.is_empty() { return; }
+        let mut sights = self.physics.collisions_where(|circle| stale.contains(&circle));
+        for &key in blobs {
+            let blob = self.blobs.get(key).unwrap();
+            if stale.contains(&blob.sight_circle) {
+                let neighbors = sights.remove(&blob.sight_circle).unwrap_or_default();
+                self.perception.insert(key, Perception { pos: blob.pos, tick, neighbors });
+            }
+        }
+    }
+
+    /// The other blobs a blob hears, from the noises of the last tick,
+    /// none of which is louder than `loudest`.
+    fn heard_by(&self, key: Key<Blob>, loudest: f32) -> BTreeSet<Key<Blob>> {
+        let blob = self.blobs.get(key).unwrap();
+        if blob.hearing <= 0. { return BTreeSet::new(); }
+        //  noises are sorted by x, so only those near enough along x are looked at
+        let reach = loudest * blob.hearing;
+        let start = self.noises.partition_point(|noise| noise.pos.x < blob.pos.x - reach);
+        self.noises[start..].iter()
+            .take_while(|noise| noise.pos.x <= blob.pos.x + reach)
+            .filter(|noise| noise.source != key && self.blobs.contains_key(noise.source))
+            .filter(|noise| (noise.pos - blob.pos).length() <= noise.loudness * blob.hearing)
+            .map(|noise| noise.source)
+            .collect()
+    }
+
+    /// A circle a blob's sight touched, with what it is and its color,
+    /// if the blob sees it: within its field of view and, in the dark,
+    /// near enough.
+    fn sees(&self, blob: &Blob, circle: Key<Circle>, sunlight: f32) -> Option<(&Circle, &CircleObject, &Color)> {
+        //  neighbors may have been removed since they were seen
+        let circle_object = self.objects.get(&circle)?;
+        let circle = self.physics.circles().get(circle)?;
+        let dir = circle.center - blob.pos();
+        //  make sure object inside blob POV 
+        let angle = math::unsigned_angle_vector2(dir, blob.direction, self.float_mode).abs();
+        if angle > blob.pov { return None; }
+        //  what is in the dark is seen only from nearer
+        let light = light::illumination(sunlight, &self.lights, circle.center);
+        //  and in a storm too
+        let visibility = weather::visibility(&self.weather, blob.pos());
+        if light < 1. || visibility < 1. {
+            let reach = blob.sight_depth * light::sight_factor(light) * visibility + circle.radius;
+            if dir.length_sqr() > reach * reach { return None; }
+        }
+        Some((circle, circle_object, circle_object.color(self)?))
+    }
+
+    /// What a blob saw when it last looked around, as its brain was
+    /// shown it, without what it only heard.
+    pub fn sightings(&self, blob: Key<Blob>) -> Vec<Sighting> {
+        let (blob, perception) = match (self.blobs.get(blob), self.perception.get(&blob)) {
+            (Some(blob), Some(perception)) => (blob, perception),
+            _ => return vec![],
+        };
+        let sunlight = self.sunlight();
+        perception.neighbors.iter()
+            .filter_map(|&circle| self.sees(blob, circle, sunlight))
+            .map(|(circle, object, &color)| Sighting {
+                pos: circle.center,
+                radius: circle.radius,
+                color,
+                food: matches!(object, CircleObject::Food(_)),
+            })
+            .collect()
+    }
+
+    /// What a blob saw when it last looked around, as the blobs and
+    /// food of the world, see `sightings`.
+    pub fn seen(&self, blob: Key<Blob>) -> Vec<CircleObject> {
+        let (blob, perception) = match (self.blobs.get(blob), self.perception.get(&blob)) {
+            (Some(blob), Some(perception)) => (blob, perception),
+            _ => return vec![],
+        };
+        let sunlight = self.sunlight();
+        perception.neighbors.iter()
+            .filter_map(|&circle| self.sees(blob, circle, sunlight))
+            .map(|(_, &object, _)| object)
+            .collect()
+    }
+
+    /// Make blobs that could see a position look around again.
+    fn invalidate_perception_near(&mut self, pos: Vector2, radius: f32) {
+        let blobs = &self.blobs;
+        self.perception.retain(|&key, _| blobs.get(key).is_some_and(|blob| {
+            (blob.pos - pos).length() > blob.sight_depth + radius + Self::PERCEPTION_TOLERANCE
+        }));
+    }
+
+    /// Run a function before every tick, see `hooks`.
+    pub fn on_pre_tick(&mut self, priority: i32, hook: TickHook) -> HookId {
+        let id = self.hooks.next_id();
+        self.hooks.pre_tick.insert(id, priority, hook);
+        id
+    }
+
+    /// Run a function after every tick, see `hooks`.
+    pub fn on_post_tick(&mut self, priority: i32, hook: TickHook) -> HookId {
+        let id = self.hooks.next_id();
+        self.hooks.post_tick.insert(id, priority, hook);
+        id
+    }
+
+    /// Run a function whenever a blob or food is removed, see `hooks`.
+    pub fn on_entity_removed(&mut self, priority: i32, hook: RemovalHook) -> HookId {
+        let id = self.hooks.next_id();
+        self.hooks.removed.insert(id, priority, hook);
+        id
+    }
+
+    /// Stop running a hook, returning whether it was registered.
+    pub fn unregister_hook(&mut self, id: HookId) -> bool {
+        self.hooks.remove(id)
+    }
+
+    /// Run the hooks of a kind in order, each taken out while it runs
+    /// so that it may change the simulation and its hooks.
+    fn run_hooks<F>(&mut self, kind: fn(&mut SimulationHooks) -> &mut Hooks<F>, mut run: impl FnMut(&mut F, &mut Self)) {
+        for id in kind(&mut self.hooks).ids() {
+            if let Some(mut hook) = kind(&mut self.hooks).take(id) {
+                run(&mut hook, self);
+                kind(&mut self.hooks).put_back(id, hook);
+            }
+        }
+    }
+
+    /// Advance the simulation by a single tick.
+    ///
+    /// Every tick advances the simulation's clock by the same
+    /// amount of simulation time, regardless of how much real
+    /// time has passed.
+    pub fn step(&mut self) {
+        if let Some(profile) = &mut self.profile {
+            profile.start();
+        }
+        self.run_hooks(|hooks| &mut hooks.pre_tick, |hook, sim| hook(sim));
+        self.lap("hooks");
+        //  a blob broken since the last tick, such as by a hook or a command, would break this one
+        self.sanitize();
+        self.update_weather();
+        let timestep = self.clock.tick_length();
+        let substeps = self.timing.substeps;
+        let substep_length = timestep / substeps as f32;
+
+        //  ordered collections keep the step deterministic
+        let mut foods_to_remove = BTreeSet::new();
+        let mut blobs_to_remove = BTreeMap::new();
+        //  energy moved by eating and moving
+        let (mut absorbed, mut metabolism) = (0., 0.);
+        let mut eaten_blobs = BTreeSet::new();
+        let mut meals = vec![];
+        //  blobs that ate, which may then have a child
+        let mut fed = BTreeSet::new();
+        let mut noises = vec![];
+        let mut energy_changes = vec![];
+
+        //  run collision detection, leaving sight to the perception below
+        let sights: BTreeSet<Key<Circle>> = self.blobs.values().map(|blob| blob.sight_circle).collect();
+        let is_body = |circle: Key<Circle>| !sights.contains(&circle);
+        let mut collisions = self.physics.collisions_where(is_body);
+        self.lap("collisions");
+
+        //  brains decide where to go, each on its own ticks so that the work
+        //  is spread evenly, and blobs keep going there between decisions
+        let (tick, brain_period) = (self.clock.tick(), self.timing.brain_period);
+        let thinking: Vec<Key<Blob>> = self.blobs.iter()
+            .filter(|(&key, blob)| blob.intent.is_none() || thinks_at(key, tick, brain_period))
+            .map(|(&key, _)| key)
+            .collect();
+        self.perceive(&thinking);
+        let sunlight = self.sunlight();
+        let loudest = self.noises.iter().map(|noise| noise.loudness).fold(0., f32::max);
+        let mut intents = vec![];
+        let traced = self.trace.as_ref().filter(|trace| !trace.ended()).map(Trace::blob);
+        let mut perceived = None;
+        for &key in &thinking {
+            let blob = self.blobs.get(key).unwrap();
+            let mut seen: Vec<(&CircleObject, &Color, &Vector2)> = self.perception[&key].neighbors.iter()
+                .filter_map(|&circle| {
+                    let (circle, circle_object, color) = self.sees(blob, circle, sunlight)?;
+                    Some((circle_object, color, &circle.center))
+                })
+                .collect();
+            let visible = seen.len();
+            //  blobs heard are heard wherever they are, but not counted again if seen
+            for source in self.heard_by(key, loudest) {
+                let source = self.blobs.get(source).unwrap();
+                let object = &self.objects[&source.circle];
+                if seen.iter().any(|&(seen, _, _)| seen == object) { continue; }
+                seen.push((object, &source.color, &self.physics.circles().get(source.circle).unwrap().center));
+            }
+            if traced == Some(key) {
+                let visible = &seen[..visible];
+                let distance = |offset: Option<Vector2>| offset.map(|offset| offset.length());
+                perceived = Some(trace::Perceived {
+                    //  a blob's sight takes in its own body, which it does not count
+                    blobs_seen: visible.iter().filter(|&&(object, _, _)| matches!(object, CircleObject::Blob(other) if *other != key)).count(),
+                    food_seen: visible.iter().filter(|&&(object, _, _)| matches!(object, CircleObject::Food(_))).count(),
+                    heard: seen.len() - visible.len(),
+                    nearest_food: distance(blob.nearest(&seen, |object| blob.eats(object))),
+                    nearest_threat: distance(blob.nearest(&seen, |object| matches!(object, CircleObject::Blob(_)) && !blob.eats(object))),
+                });
+            }
+            intents.push((key, blob.prepare_step(seen, self.float_mode)));
+        }
+        for (key, intent) in intents {
+            self.blobs.get_mut(key).unwrap().intent = Some(intent);
+        }
+        self.lap("brains");
+
+        //  force fields push blobs for the whole tick, and fade when it is over
+        let pushed_from = traced.and_then(|key| self.blobs.get(key)).map_or(Vector2::zero(), |blob| blob.velocity);
+        for (field, _) in &self.fields {
+            for blob in self.blobs.values_mut() {
+                blob.velocity = field.apply(blob.pos, blob.velocity, timestep, self.float_mode);
+            }
+        }
+        //  and so do storms, with gusts that turn
+        let storms: Vec<(Weather, Vector2)> = self.weather.iter()
+            .filter(|weather| weather.kind == weather::Kind::Storm)
+            .map(|&storm| (storm, self.gust(&storm) * timestep))
+            .collect();
+        for (storm, gust) in storms {
+            for blob in self.blobs.values_mut().filter(|blob| storm.contains(blob.pos)) {
+                blob.velocity += gust;
+            }
+        }
+        //  auras act on the blobs around theirs for the whole tick too, see `aura`
+        let mut auras = vec![];
+        for (&owner, blob) in &self.blobs {
+            let field = match aura::field(blob) {
+                Some(field) => field,
+                None => continue,
+            };
+            //  only the bodies within its radius can be in it
+            let mut near: Vec<_> = self.physics.touching(field.center, field.radius, physics::LayerMask::new(vec![Blob::LAYER])).into_iter()
+                .filter_map(|circle| self.blob_of(circle))
+                .filter_map(|key| Some((key, self.blobs.get(key)?)))
+                .collect();
+            near.sort_by_key(|&(key, _)| key);
+            for (key, other) in near {
+                let falloff = field.falloff(other.pos);
+                if key != owner && falloff > 0. && blob.aura.affects(blob, other) {
+                    auras.push((owner, blob.aura, field, key, falloff));
+                }
+            }
+        }
+        for blob in self.blobs.values_mut() {
+            blob.slowed = 0.;
+        }
+        for (owner, kind, field, key, falloff) in auras {
+            match kind {
+                Aura::Slow => {
+                    let blob = self.blobs.get_mut(key).unwrap();
+                    blob.slowed = blob.slowed.max(field.force.amount() * falloff);
+                },
+                Aura::Repel => {
+                    let blob = self.blobs.get_mut(key).unwrap();
+                    blob.velocity = field.apply(blob.pos, blob.velocity, timestep, self.float_mode);
+                },
+                //  energy is handed over, never made, and the healer keeps half of what it can store
+                Aura::Heal => {
+                    let healer = self.blobs.get(owner).unwrap();
+                    let spare = (healer.max_hunger / 2. - healer.hunger).max(0.);
+                    let blob = self.blobs.get_mut(key).unwrap();
+                    let given = (field.force.amount() * falloff * timestep).min(spare).min(blob.hunger.max(0.));
+                    blob.hunger -= given;
+                    self.blobs.get_mut(owner).unwrap().hunger += given;
+                },
+                Aura::None => (),
+            }
+        }
+        let pushed = traced.and_then(|key| self.blobs.get(key)).map_or(Vector2::zero(), |blob| blob.velocity - pushed_from);
+        self.fields.retain(|&(_, until)| until > tick + 1);
+        self.lap("forces");
+
+        for substep in 0..substeps {
+            if substep > 0 {
+                collisions = self.physics.collisions_where(is_body);
+            }
+
+            //  blobs eating, of the foods and blobs left by earlier substeps
+            let mut eaten = BTreeSet::new();
+            for (key, blob) in &mut self.blobs {
+                if !blob.diet.eats_food() || blobs_to_remove.contains_key(key) { continue; }
+                if let Some(touched) = collisions.get(&blob.circle) {
+                    for circle in touched {
+                        if let Some(&CircleObject::Food(food)) = self.objects.get(circle) {
+                            if foods_to_remove.contains(&food) { continue; }
+                            let hunger = blob.hunger;
+                            blob.feed();
+                            //  a meal gives no more than the food stores
+                            blob.hunger = blob.hunger.max(hunger - FOOD_ENERGY);
+                            absorbed += hunger - blob.hunger;
+                            energy_changes.push(EnergyChange { blob: *key, pos: blob.pos, amount: hunger - blob.hunger });
+                            blob.foods_eaten += 1;
+                            fed.insert(*key);
+                            eaten.insert(food);
+                            let prey = if self.foods.get(food).unwrap().corpse { Prey::Corpse } else { Prey::Food };
+                            meals.push(Meal { eater: blob.species.clone(), prey, energy: hunger - blob.hunger });
+                        }
+                    }
+                }
+            }
+            foods_to_remove.extend(eaten);
+
+            //  blobs fighting
+            let mut fights = BTreeSet::new();
+            for (blob_key, blob) in &self.blobs {
+                if blobs_to_remove.contains_key(blob_key) { continue; }
+                if let Some(touched) = collisions.get(&blob.circle) {
+                    for circle in touched {
+                        if let Some(&CircleObject::Blob(other_blob_key)) = self.objects.get(circle) {
+                            if blobs_to_remove.contains_key(&other_blob_key) { continue; }
+                            use std::cmp::{min, max};
+                            let a = min(*blob_key, other_blob_key);
+                            let b = max(*blob_key, other_blob_key);
+                            fights.insert((a, b));
+                        }
+                    }
+                }
+            }
+            for (blob1_key, blob2_key) in fights {
+                let blob1 = self.blobs.get(blob1_key).unwrap();
+                let blob2 = self.blobs.get(blob2_key).unwrap();
+                for &(source, blob) in &[(blob1_key, blob1), (blob2_key, blob2)] {
+                    noises.push(Noise { source, pos: blob.pos, loudness: Blob::FIGHT_NOISE });
+                }
+                let mut feeders = vec![];
+                for &(attacker, attacker_key, defender, defender_key) in &[(blob1, blob1_key, blob2, blob2_key), (blob2, blob2_key, blob1, blob1_key)] {
+                    if self.parameters.predation
+                    && attacker.attack > defender.defence * (1. - defender.hunger / defender.max_hunger) {
+                        if blobs_to_remove.insert(defender_key, (defender.pos, Some(attacker_key))).is_none() {
+                            energy_changes.push(EnergyChange { blob: defender_key, pos: defender.pos, amount: -(defender.max_hunger - defender.hunger).max(0.) });
+                        }
+                        //  a blob eaten by its killer leaves no food behind
+                        if attacker.diet.eats_blobs() {
+                            eaten_blobs.insert(defender_key);
+                            feeders.push((attacker_key, defender.species.clone(), defender.max_hunger - defender.hunger));
+                        }
+                    }
+                }
+                for (feeder, prey, stored) in feeders {
+                    let blob = self.blobs.get_mut(feeder).unwrap();
+                    let hunger = blob.hunger;
+                    blob.feed_by(self.parameters.predation_efficiency * self.overrides.factor(blob.species.as_deref(), "predation_efficiency"));
+                    //  a kill gives no more than the prey stored
+                    blob.hunger = blob.hunger.max(hunger - stored.max(0.));
+                    absorbed += hunger - blob.hunger;
+                    energy_changes.push(EnergyChange { blob: feeder, pos: blob.pos, amount: hunger - blob.hunger });
+                    fed.insert(feeder);
+                    meals.push(Meal { eater: blob.species.clone(), prey: Prey::Blob(prey), energy: hunger - blob.hunger });
+                }
+                let blob1 = self.blobs.get(blob1_key).unwrap();
+                let blob2 = self.blobs.get(blob2_key).unwrap();
+                {
+                    let delta = blob2.pos() - blob1.pos();
+                    //  blobs at the same place, such as a child in a corner, part along x
+                    let dir = if delta.length_sqr() > 0. { delta.normalized() } else { Vector2::new(1., 0.) };
+                    let pos1 = blob2.pos() - dir * (blob1.radius() + blob2.radius());
+                    let pos2 = blob1.pos() + dir * (blob1.radius() + blob2.radius());
+                    self.blobs.get_mut(blob1_key).unwrap().set_pos(&mut self.physics, pos1);
+                    self.blobs.get_mut(blob2_key).unwrap().set_pos(&mut self.physics, pos2);
+                }
+            }
+
+            //  step blobs
+            let world = &mut self.physics;
+            let moment = self.clock.tick() * substeps as u64 + substep as u64;
+            for (key, blob) in self.blobs.iter_mut() {
+                let intent = blob.intent.unwrap_or_default();
+                let hunger = blob.hunger;
+                let mut rng = Stream::new(self.seed, Self::STEERING_STREAM, key.index(), moment);
+                let metabolism_scale = self.parameters.metabolism_scale * self.overrides.factor(blob.species.as_deref(), "metabolism_scale");
+                blob.step(&intent, substep_length, self.float_mode, world, self.size, &mut rng);
+                blob.hunger += substep_length * metabolism_scale * blob.upkeep();
+                //  blobs that cannot swim suffer in water and head for the nearest shore
+                if blob.swimming < Blob::SWIMMER {
+                    if let Some(lake) = self.lakes.iter().find(|lake| lake.contains(blob.pos)) {
+                        blob.hunger += substep_length * metabolism_scale * Blob::DROWNING * blob.upkeep();
+                        let away = blob.pos - lake.center;
+                        if away.length_sqr() > 0. { blob.direction = away.normalized(); }
+                    }
+                }
+                metabolism += blob.hunger - hunger;
+            }
+        }
 
-    /// Returns the size of the simulation's space
-    pub fn size(&self) -> Vector2 { self.size }
+        self.lap("physics");
 
-    /// Draw the simulation data onto a buffer.
-    pub fn draw(&self, draw: &mut DrawingContext) {
-        //  background
-        draw.clear_background(Color::RAYWHITE);
-        //  foods
-        for (_, food) in &self.foods {
-            food.draw(draw);
+        //  blobs moving fast are heard, the farther the faster
+        for (&source, blob) in &self.blobs {
+            let loudness = Blob::STEP_NOISE * ((blob.direction * blob.speed + blob.velocity).length() - Blob::QUIET_SPEED);
+            if loudness > 0. {
+                noises.push(Noise { source, pos: blob.pos, loudness });
+            }
         }
-        //  blobs
-        for (_, blob) in &self.blobs {
-            blob.draw(draw);
+        noises.sort_by(|a, b| a.pos.x.total_cmp(&b.pos.x));
+        self.noises = noises;
+        self.energy_changes = energy_changes;
+
+        //  uneaten food spoils, at the pace of the season
+        if self.parameters.food_lifetime > 0. {
+            let rate = timestep * spoilage(self.clock.season()) / self.parameters.food_lifetime;
+            for (key, food) in &mut self.foods {
+                food.spoiled += if food.corpse { Food::CORPSE_SPOILAGE * rate } else { rate };
+                if food.spoiled >= 1. {
+                    foods_to_remove.insert(*key);
+                }
+            }
         }
-    }
 
-    /// Advance the simulation by a single iteration.
-    ///
-    /// The timestep is the fraction of seconds that has passed
-    /// since the last step in the simulation.
-    /// The step will be more accurate as the timestep is closer
-    /// to 0.
-    pub fn step(&mut self, timestep: f32) {
-        debug_assert!(timestep >= 0.);
+        //  blobs dying
+        for (key, blob) in &self.blobs {
+            if blob.hunger > blob.max_hunger {
+                blobs_to_remove.entry(*key).or_insert((blob.pos(), None));
+            }
+        }
+
+        //  name the dead while their killers are still around
+        let name = |key| self.blobs.get(key).and_then(|blob: &Blob| blob.name.clone());
+        let deaths: Vec<Event> = blobs_to_remove.iter()
+            .map(|(&key, &(_, killer))| match killer {
+                Some(killer) => Event::Killed { victim: name(key), killer: name(killer) },
+                None => Event::Starved(name(key)),
+            })
+            .collect();
+        
+        //  remove, leaving graves where they died
+        self.remove_foods(&foods_to_remove);
+        let dug = self.clock.tick();
+        while self.graves.front().is_some_and(|grave| grave.tick + memorial::LIFETIME <= dug) {
+            self.graves.pop_front();
+        }
+        let mut remains = vec![];
+        for (blob, (pos, killer)) in blobs_to_remove {
+            let cause = match killer {
+                Some(_) => Cause::Killed,
+                None if self.in_water(pos) => Cause::Drowned,
+                None => Cause::Starved,
+            };
+            self.graves.push_back(Grave { pos, tick: dug, cause });
+            if self.graves.len() > memorial::MAX_GRAVES {
+                self.graves.pop_front();
+            }
+            for region in &mut self.regions {
+                region.count_death(pos);
+            }
+            let species = self.remove_blob(blob).and_then(|blob| blob.species);
+            if !eaten_blobs.contains(&blob) {
+                self.insert_corpse(pos);
+                remains.push(species);
+            }
+        }
+        for event in deaths {
+            self.log_event(event);
+        }
+        self.log_meals(meals, remains);
+
+        //  blobs left well fed by a meal have a child
+        self.flow(Flows { absorbed, metabolism, ..Flows::default() });
+        if self.parameters.reproduction {
+            for parent in fed {
+                if self.blobs.get(parent).is_some_and(Blob::can_reproduce) {
+                    self.reproduce(parent);
+                }
+            }
+        }
 
-        let mut foods_to_remove = HashSet::new();
-        let mut blobs_to_remove = HashMap::new();
+        self.lap("lifecycle");
 
-        //  run collision detection
-        let collisions = self.physics.collisions();
+        if self.clock.tick().is_multiple_of(Self::STATS_PERIOD) {
+            self.record_stats();
+        }
+        let stored = self.stored_energy();
+        if let Some(audit) = &mut self.energy {
+            if let Err(e) = audit.check(stored) {
+                panic!("energy is not conserved at tick {}: {}", self.clock.tick(), e);
+            }
+        }
+        self.sanitize();
+        //  the end of a species is worth finding on the timeline
+        for extinction in self.census.update(self.clock.tick(), self.blobs.values()) {
+            self.bookmark(extinction.tick, &format!("{} died out", extinction.species));
+            self.log_event(Event::Extinct(extinction));
+        }
+        for record in self.records.update(self.clock.tick(), self.blobs.values()) {
+            self.log_event(Event::Record(record));
+        }
+        if cfg!(debug_assertions) {
+            let violations = self.check_invariants();
+            assert!(violations.is_empty(), "invariants broken at tick {}:\n{}", self.clock.tick(), violations.join("\n"));
+        }
+        //  drones fly on, seen by nothing
+        for drone in self.drones.values_mut() {
+            drone.fly(timestep);
+        }
+        if let Some(trace) = self.trace.as_mut().filter(|trace| !trace.ended()) {
+            match self.blobs.get(trace.blob()) {
+                Some(blob) => {
+                    let intent = blob.intent.unwrap_or_default();
+                    trace.push(trace::Entry {
+                        tick,
+                        //  what the last decision was made from, on ticks it kept to it
+                        perceived: perceived.unwrap_or_else(|| trace.entries().back().map_or_else(Default::default, |last| last.perceived)),
+                        thought: perceived.is_some(),
+                        hunger: blob.hunger / blob.max_hunger,
+                        mood: intent.mood,
+                        target: intent.target_direction,
+                        pushed,
+                        slowed: blob.slowed,
+                        pos: blob.pos,
+                        heading: blob.direction,
+                        velocity: blob.velocity,
+                    });
+                },
+                None => trace.end(),
+            }
+        }
+        self.clock.advance();
+        self.lap("upkeep");
+        self.run_hooks(|hooks| &mut hooks.post_tick, |hook, sim| hook(sim));
+        if let Some(profile) = &mut self.profile {
+            profile.end("hooks");
+        }
+    }
 
-        //  prepare blob steps
-        let mut steps = HashMap::new();
-        for (key, blob) in &self.blobs {
-            let seen: Vec<(&CircleObject, &Color, &Vector2)> = 
-                collisions.get(&blob.sight_circle)
-                .map_or_else(|| vec![], |collided| 
-                    collided.iter()
-                    .filter_map(|&key| {
-                        let circle = self.physics.circles.get(key).unwrap();
-                        let circle_object = self.objects.get(&key).unwrap();
-                        let dir = circle.center - blob.pos();
-                        //  make sure object inside blob POV 
-                        let angle = math::unsigned_angle_vector2(dir, blob.direction).abs();
-                        if angle > blob.pov { return None; }
-
-                        let color = circle_object.color(self)?;
-                        Some((circle_object, color, &circle.center))
-                    })
-                    .collect()
-                );
-            steps.insert(*key, blob.prepare_step(seen));
-        }
-
-        //  blobs eating
-        for (_, blob) in &mut self.blobs {
-            if let Some(touched) = collisions.get(&blob.circle) {
-                for circle in touched {
-                    if let Some(&CircleObject::Food(food)) = self.objects.get(circle) {
-                        blob.feed();
-                        foods_to_remove.insert(food);
-                    }
+    /// Set straight the blobs a bug left in a state no tick should,
+    /// before they spread it through the world, logging each as
+    /// `Event::Quarantined`. A broken heading or thrown velocity is
+    /// reset, hunger out of range is clamped and a position outside
+    /// the world is moved back in. A blob at no position is moved back
+    /// to its circle, and removed if that is broken too, as is one of
+    /// a broken size or hunger. Returns how many blobs were found.
+    pub fn sanitize(&mut self) -> usize {
+        let finite = |v: Vector2| v.x.is_finite() && v.y.is_finite();
+        let mut found = vec![];
+        for (&key, blob) in &self.blobs {
+            let mut problems = vec![];
+            let broken_size = !(blob.radius > 0. && blob.radius.is_finite() && blob.max_hunger > 0. && blob.max_hunger.is_finite());
+            if broken_size {
+                problems.push(format!("radius {} and most hunger {}", blob.radius, blob.max_hunger));
+            }
+            if blob.hunger.is_nan() {
+                problems.push("hunger NaN".to_string());
+            }
+            //  the circle is moved along with the blob, so it is where the blob was if that broke
+            let center = self.physics.circles().get(blob.circle).map(|circle| circle.center).filter(|&center| finite(center));
+            if !finite(blob.pos) {
+                problems.push(format!("position {:?}", blob.pos));
+            }
+            let remove = broken_size || blob.hunger.is_nan() || !finite(blob.pos) && center.is_none();
+            if !remove {
+                if !finite(blob.direction) {
+                    problems.push(format!("direction {:?}", blob.direction));
+                }
+                if !finite(blob.velocity) {
+                    problems.push(format!("velocity {:?}", blob.velocity));
+                }
+                if !(0. ..=blob.max_hunger).contains(&blob.hunger) {
+                    problems.push(format!("hunger {}", blob.hunger));
+                }
+                let pos = if finite(blob.pos) { blob.pos } else { center.unwrap() };
+                if !((0. ..=self.size.x).contains(&pos.x) && (0. ..=self.size.y).contains(&pos.y)) {
+                    problems.push(format!("position {:?} outside the world", pos));
                 }
             }
+            if !problems.is_empty() {
+                found.push((key, problems.join(", "), remove, center));
+            }
         }
 
-        //  blobs fighting
-        let mut fights = HashSet::new();
-        for (blob_key, blob) in &mut self.blobs {
-            if let Some(touched) = collisions.get(&blob.circle) {
-                for circle in touched {
-                    if let Some(&CircleObject::Blob(other_blob_key)) = self.objects.get(circle) {
-                        use std::cmp::{min, max};
-                        let a = min(*blob_key, other_blob_key);
-                        let b = max(*blob_key, other_blob_key);
-                        fights.insert((a, b));
-                    }
+        let count = found.len();
+        for (key, problem, removed, center) in found {
+            let name = self.blobs.get(key).and_then(|blob: &Blob| blob.name.clone());
+            if removed {
+                self.remove_blob(key);
+            } else {
+                let size = self.size;
+                let blob = self.blobs.get_mut(key).unwrap();
+                if !finite(blob.direction) {
+                    blob.direction = Vector2::zero();
                 }
+                if !finite(blob.velocity) {
+                    blob.velocity = Vector2::zero();
+                }
+                let hunger = blob.hunger.clamp(0., blob.max_hunger);
+                let consumed = hunger - blob.hunger;
+                blob.hunger = hunger;
+                let pos = if finite(blob.pos) { blob.pos } else { center.unwrap() };
+                let pos = Vector2::new(pos.x.clamp(0., size.x), pos.y.clamp(0., size.y));
+                self.flow(Flows { consumed, ..Flows::default() });
+                self.set_blob_pos(key, pos);
+                self.invalidate_perception_near(pos, self.blobs.get(key).unwrap().radius);
             }
+            self.log_event(Event::Quarantined { blob: name, problem, removed });
         }
-        for (blob1_key, blob2_key) in fights {
-            let blob1 = self.blobs.get(blob1_key).unwrap();
-            let blob2 = self.blobs.get(blob2_key).unwrap();
-            for &(attacker, _attacker_key, defender, defender_key) in &[(blob1, blob1_key, blob2, blob2_key), (blob2, blob2_key, blob1, blob1_key)] {
-                if attacker.attack > defender.defence * (1. - defender.hunger / defender.max_hunger) {
-                    blobs_to_remove.insert(defender_key, defender.pos);
+        count
+    }
+
+    /// Describe every way the state is inconsistent, which should be
+    /// none. Debug builds check after every tick and panic otherwise.
+    pub fn check_invariants(&self) -> Vec<String> {
+        let mut ret = vec![];
+        let finite = |v: Vector2| v.x.is_finite() && v.y.is_finite();
+        let describe = |key: Key<Blob>, blob: &Blob| format!("blob {} ({})", key.index(), blob.name.as_deref().unwrap_or("unnamed"));
+        let owned_by = |circle: Key<Circle>, object: CircleObject| match (self.physics.circles().get(circle), self.objects.get(&circle)) {
+            (None, _) => Some(format!("has no circle {}", circle.index())),
+            (Some(_), Some(owner)) if *owner == object => None,
+            (Some(_), owner) => Some(format!("has circle {} owned by {:?}", circle.index(), owner)),
+        };
+
+        for (&key, blob) in &self.blobs {
+            let name = describe(key, blob);
+            for &(field, value) in &[("position", blob.pos), ("direction", blob.direction), ("velocity", blob.velocity)] {
+                if !finite(value) {
+                    ret.push(format!("{} has {} {:?}", name, field, value));
                 }
             }
-            {
-                let dir = (blob2.pos() - blob1.pos()).normalized();
-                let pos1 = blob2.pos() - dir * (blob1.radius() + blob2.radius());
-                let pos2 = blob1.pos() + dir * (blob1.radius() + blob2.radius());
-                self.blobs.get_mut(blob1_key).unwrap().set_pos(&mut self.physics, pos1);
-                self.blobs.get_mut(blob2_key).unwrap().set_pos(&mut self.physics, pos2);
+            let inside = (0. ..=self.size.x).contains(&blob.pos.x) && (0. ..=self.size.y).contains(&blob.pos.y);
+            if finite(blob.pos) && !inside {
+                ret.push(format!("{} is at {:?}, outside the world of {:?}", name, blob.pos, self.size));
+            }
+            if !(0. ..=blob.max_hunger).contains(&blob.hunger) {
+                ret.push(format!("{} has hunger {} outside [0, {}]", name, blob.hunger, blob.max_hunger));
+            }
+            let circles = [(blob.circle, CircleObject::Blob(key)), (blob.sight_circle, CircleObject::BlobSight(key))];
+            for &(circle, object) in &circles {
+                if let Some(problem) = owned_by(circle, object) {
+                    ret.push(format!("{} {}", name, problem));
+                }
+                if let Some(circle) = self.physics.circles().get(circle).filter(|circle| circle.center != blob.pos) {
+                    ret.push(format!("{} is at {:?} but its circle is at {:?}", name, blob.pos, circle.center));
+                }
             }
         }
-
-        //  step blobs
-        let world = &mut self.physics;
-        for (key, blob) in &mut self.blobs {
-            blob.step(&steps[key], timestep, world, self.size);
+        for (&key, food) in &self.foods {
+            if !finite(food.pos) {
+                ret.push(format!("food {} has position {:?}", key.index(), food.pos));
+            }
+            if let Some(problem) = owned_by(food.circle, CircleObject::Food(key)) {
+                ret.push(format!("food {} {}", key.index(), problem));
+            }
         }
-
-        //  blobs dying
-        for (key, blob) in &self.blobs {
-            if blob.hunger > blob.max_hunger {
-                blobs_to_remove.insert(*key, blob.pos());
+        //  nothing is left behind by what was removed
+        for (&circle, object) in &self.objects {
+            let alive = match *object {
+                CircleObject::Blob(key) | CircleObject::BlobSight(key) => self.blobs.contains_key(key),
+                CircleObject::Food(key) => self.foods.contains_key(key),
+            };
+            if !alive {
+                ret.push(format!("circle {} belongs to the removed {:?}", circle.index(), object));
             }
         }
-        
-        //  remove
-        for food in foods_to_remove {
-            self.remove_food(food);
+        if self.physics.circles().len() != self.objects.len() {
+            ret.push(format!("{} circles but {} objects own circles", self.physics.circles().len(), self.objects.len()));
         }
-        for (blob, pos) in blobs_to_remove {
-            self.remove_blob(blob);
-            self.insert_food(pos);
+        for key in self.perception.keys().filter(|&&key| !self.blobs.contains_key(key)) {
+            ret.push(format!("the removed blob {} is still perceiving", key.index()));
         }
+        ret
     }
 
     /// Put a blob in the simulation.
-    pub fn insert_blob(&mut self, 
-        pos: Vector2, radius: f32, color: Color,
-        speed: f32, rotation_speed: f32,
-        pov: f32, sight_depth: f32,
-        favorite_color: Color,
-        color_attraction: f32, color_repulsion: f32,
-        max_hunger: f32,
-        attack: f32, defence: f32,
-        hunger_reduction: f32, hunger_division: f32,
-    ) -> Key<Blob> {
+    pub fn insert_blob(&mut self, config: BlobConfig) -> Key<Blob> {
+        let BlobConfig {
+            pos, name, species, parent,
+            radius, color,
+            speed, rotation_speed,
+            pov, sight_depth,
+            favorite_color,
+            color_attraction, color_repulsion,
+            max_hunger,
+            attack, defence,
+            hunger_reduction, hunger_division,
+            diet, brain, hunger, swimming, hearing,
+            aura, aura_strength,
+        } = config;
+        let radius = radius * self.parameters.size_scale;
+        let sight_depth = sight_depth * self.parameters.sight_scale;
         //  create blob
-        let circle = self.physics.circles.insert(Circle {
+        let circle = self.physics.insert(Circle {
             center: pos, radius: radius, layer: Blob::LAYER,
         });
-        let sight_circle = self.physics.circles.insert(Circle {
+        let sight_circle = self.physics.insert(Circle {
             center: pos, radius: sight_depth, layer: Blob::SIGHT_LAYER,
         });
+        let generation = parent.and_then(|parent| self.blobs.get(parent)).map_or(1, |parent| parent.generation + 1);
         let blob = Blob {
-            name: None,
+            name,
+            species,
+            parent, generation,
             alive_time: 0.,
+            foods_eaten: 0,
+            children: 0,
             pos, radius, color,
             speed, rotation_speed,
             pov, sight_depth,
             favorite_color,
             color_attraction, color_repulsion,
             direction: Vector2::zero(),
+            velocity: Vector2::zero(),
             circle, sight_circle,
-            max_hunger, hunger: 0.,
+            intent: None,
+            max_hunger, hunger,
             attack, defence,
             hunger_reduction, hunger_division,
+            diet, brain, swimming, hearing,
+            aura, aura_strength, slowed: 0.,
+            tags: vec![],
         };
         //  insert blob data
-        let key = self.blobs.insert(blob);
+        let key = self.blobs.insert_in(self.source.namespace(), blob);
         self.objects.insert(circle, CircleObject::Blob(key));
         self.objects.insert(sight_circle, CircleObject::BlobSight(key));
+        self.log_event(Event::Born(key));
+        for region in &mut self.regions {
+            region.count_birth(pos);
+        }
+        self.invalidate_perception_near(pos, radius);
+        self.flow(Flows { spawned: max_hunger - hunger, ..Flows::default() });
 
         key
     }
     
+    /// Give a blob a child beside it, with each trait of the parent
+    /// varied by up to the mutation rate and half of what it stores.
+    fn reproduce(&mut self, parent: Key<Blob>) -> Key<Blob> {
+        let Parameters { mutation_rate: rate, size_scale, sight_scale, .. } = self.parameters;
+        let blob = self.blobs.get(parent).unwrap();
+        let rate = rate * self.overrides.factor(blob.species.as_deref(), "mutation_rate");
+        let rng = &mut Stream::new(self.seed, Self::MUTATION_STREAM, parent.index(), self.clock.tick());
+        let mut vary = |value: f32| (value * (1. + rate * rng.gen_range(-1. ..1.))).max(0.);
+        let radius = (vary(blob.radius / size_scale)).max(1.);
+        let speed = vary(blob.speed);
+        let rotation_speed = vary(blob.rotation_speed);
+        let pov = vary(blob.pov).min(360.);
+        let sight_depth = vary(blob.sight_depth / sight_scale);
+        let color_attraction = vary(blob.color_attraction);
+        let color_repulsion = vary(blob.color_repulsion);
+        let max_hunger = vary(blob.max_hunger).max(Blob::CHILD_ENERGY);
+        let attack = vary(blob.attack);
+        let defence = vary(blob.defence);
+        let hunger_reduction = vary(blob.hunger_reduction);
+        let hunger_division = vary(blob.hunger_division);
+        let swimming = vary(blob.swimming).min(1.);
+        let hearing = vary(blob.hearing).min(1.);
+        let aura_strength = vary(blob.aura_strength).min(1.);
+        let mut shade = |color: Color| {
+            let mut channel = |c: u8| (c as f32 + rate * Self::MUTATION_SHADE * rng.gen_range(-1. ..1.)).round().clamp(0., 255.) as u8;
+            Color::new(channel(color.r), channel(color.g), channel(color.b), color.a)
+        };
+        let (color, favorite_color) = (shade(blob.color), shade(blob.favorite_color));
+        let angle = rng.gen_range(0. ..std::f32::consts::TAU);
+        let offset = Vector2::new(self.float_mode.cos(angle), self.float_mode.sin(angle)) * (blob.radius + radius * size_scale);
+        let pos = blob.pos + offset;
+        let pos = Vector2::new(pos.x.clamp(0., self.size.x), pos.y.clamp(0., self.size.y));
+        //  the parent hands over half of what it stores, as much as the child holds
+        let given = ((blob.max_hunger - blob.hunger) / 2.).min(max_hunger);
+        let mut config = BlobConfig {
+            pos, name: None, species: blob.species.clone(), parent: Some(parent),
+            radius, color,
+            speed, rotation_speed,
+            pov, sight_depth,
+            favorite_color,
+            color_attraction, color_repulsion,
+            max_hunger,
+            attack, defence,
+            hunger_reduction, hunger_division,
+            diet: blob.diet,
+            brain: blob.brain,
+            hunger: max_hunger - given,
+            swimming, hearing,
+            aura: blob.aura, aura_strength,
+        };
+        if self.parameters.speciation > 0. {
+            let census = &self.census;
+            let taken = |name: &str| census.records().any(|(species, _)| species == name)
+                || census.extinctions().iter().any(|extinction| extinction.species == name);
+            if let Some(species) = self.taxonomy.classify(&config, self.parameters.speciation, taken) {
+                let tick = self.clock.tick();
+                let note = format!("{} forked off {}", species, config.species.as_deref().unwrap_or("blobs of no species"));
+                self.bookmark(tick, &note);
+                config.species = Some(species);
+            }
+        }
+        let blob = self.blobs.get_mut(parent).unwrap();
+        blob.hunger += given;
+        blob.children += 1;
+        self.flow(Flows { consumed: given, ..Flows::default() });
+        self.insert_blob(config)
+    }
+
     /// Get a blob from the simulation.
     pub fn get_blob(&self, blob: Key<Blob>) -> Option<&Blob> {
         self.blobs.get(blob)
@@ -292,16 +2626,35 @@ impl Simulation {
         self.blobs.get_mut(blob)
     }
     
+    /// Iterate over all the blobs in the simulation.
+    pub fn blobs(&self) -> impl Iterator<Item=(Key<Blob>, &Blob)> {
+        self.blobs.iter().map(|(&key, blob)| (key, blob))
+    }
+
+    /// Iterate over all the foods in the simulation.
+    pub fn foods(&self) -> impl Iterator<Item=(Key<Food>, &Food)> {
+        self.foods.iter().map(|(&key, food)| (key, food))
+    }
+    
     /// Remove a blob from the simulation.
     pub fn remove_blob(&mut self, blob: Key<Blob>) -> Option<Blob> {
         //  try remove blob
+        self.perception.remove(&blob);
+        let key = blob;
         let blob = self.blobs.remove(blob);
         //  remove blob objects
         if let Some(blob) = &blob {
+            self.longest_life = self.longest_life.max(blob.alive_time);
+            self.flow(Flows { consumed: blob.max_hunger - blob.hunger, ..Flows::default() });
+            self.invalidate_perception_near(blob.pos, blob.radius);
             self.objects.remove(&blob.circle);
             self.objects.remove(&blob.sight_circle);
-            self.physics.circles.remove(blob.circle);
-            self.physics.circles.remove(blob.sight_circle);
+            self.physics.remove(blob.circle);
+            self.physics.remove(blob.sight_circle);
+            if !self.hooks.removed.is_empty() {
+                let removed = Removed::Blob(key, Box::new(blob.clone()));
+                self.run_hooks(|hooks| &mut hooks.removed, |hook, sim| hook(sim, &removed));
+            }
         }
 
         blob
@@ -313,17 +2666,35 @@ impl Simulation {
         }
     }
 
+    /// Push a blob, changing its velocity in inverse proportion to its mass.
+    pub fn apply_impulse(&mut self, blob: Key<Blob>, impulse: Vector2) {
+        if let Some(blob) = self.blobs.get_mut(blob) {
+            blob.velocity += impulse / blob.mass();
+        }
+    }
+
     /// Put a food in the simulation.
     pub fn insert_food(&mut self, pos: Vector2) -> Key<Food> {
+        self.insert_food_object(pos, false)
+    }
+
+    /// Put the food left by a dead blob in the simulation.
+    fn insert_corpse(&mut self, pos: Vector2) -> Key<Food> {
+        self.insert_food_object(pos, true)
+    }
+
+    fn insert_food_object(&mut self, pos: Vector2, corpse: bool) -> Key<Food> {
         //  create food
-        let circle = self.physics.circles.insert(Circle {
+        let circle = self.physics.insert(Circle {
             center: pos, radius: Food::RADIUS, layer: Food::LAYER,
         });
-        let food = Food { pos, circle };
+        let food = Food { pos, circle, corpse, spoiled: 0., aquatic: self.in_water(pos) };
         //  insert data
-        let key = self.foods.insert(food);
+        let key = self.foods.insert_in(self.source.namespace(), food);
         self.objects.insert(circle, CircleObject::Food(key));
-        
+        self.invalidate_perception_near(pos, Food::RADIUS);
+        self.flow(Flows { spawned: FOOD_ENERGY, ..Flows::default() });
+
         key
     }
     
@@ -336,69 +2707,253 @@ impl Simulation {
         self.foods.get_mut(food)
     }
     
-    /// Remove a food from the simulation.
-    pub fn remove_food(&mut self, food: Key<Food>) -> Option<Food> {
-        //  try remove food
-        let food = self.foods.remove(food);
-        //  remove food objects
-        if let Some(food) = &food {
+    /// Remove the foods of a set in a single pass, such as those eaten
+    /// or spoiled in a tick.
+    fn remove_foods(&mut self, foods: &BTreeSet<Key<Food>>) {
+        let mut removed = vec![];
+        self.foods.retain(|key, food| {
+            let keep = !foods.contains(&key);
+            if !keep { removed.push((key, food.clone())); }
+            keep
+        });
+        for (_, food) in &removed {
             self.objects.remove(&food.circle);
-            self.physics.circles.remove(food.circle);
+            self.physics.remove(food.circle);
+            self.flow(Flows { consumed: FOOD_ENERGY, ..Flows::default() });
+        }
+        if !self.hooks.removed.is_empty() {
+            for (key, food) in removed {
+                let removed = Removed::Food(key, food);
+                self.run_hooks(|hooks| &mut hooks.removed, |hook, sim| hook(sim, &removed));
+            }
         }
+    }
 
-        food
+    /// Whether a blob is still alive.
+    pub fn contains_blob(&self, blob: Key<Blob>) -> bool {
+        self.blobs.contains_key(blob)
     }
 
-    pub fn select(&mut self, pos: Vector2) -> (Vec<Key<Blob>>, Vec<Key<Food>>) {
-        let mut foods = vec![];
-        let mut blobs = vec![];
-        let key = self.physics.circles.insert(Circle {
-            center: pos, 
-            radius: 0.01,
-            layer: Self::SELECTION_LAYER,
-        });
-        let collisions = self.physics.collisions();
-        self.physics.circles.remove(key);
-        if let Some(collided) = collisions.get(&key) {
-            for touched in collided {
-                match self.objects.get(touched) {
-                    Some(&CircleObject::Blob(blob)) => blobs.push(blob),
-                    Some(&CircleObject::Food(food)) => foods.push(food),
-                    _ => (),
-                }
-            }
-            (blobs, foods)
-        } else {
-            (vec![], vec![])
+    /// The blob a circle is the body of.
+    fn blob_of(&self, circle: Key<Circle>) -> Option<Key<Blob>> {
+        match self.objects.get(&circle) {
+            Some(&CircleObject::Blob(blob)) => Some(blob),
+            _ => None,
         }
     }
+
+    /// The blobs a filter picks that touch a circle, nearest first.
+    pub fn select_circle(&self, center: Vector2, radius: f32, filter: &BlobFilter) -> Vec<Key<Blob>> {
+        let mut blobs: Vec<_> = self.physics.touching(center, radius, physics::LayerMask::new(vec![Blob::LAYER])).into_iter()
+            .filter_map(|circle| self.blob_of(circle))
+            .filter_map(|key| Some((key, self.blobs.get(key).filter(|blob| filter.matches(blob))?.pos)))
+            .collect();
+        blobs.sort_by(|a, b| (a.1 - center).length().total_cmp(&(b.1 - center).length()));
+        blobs.into_iter().map(|(key, _)| key).collect()
+    }
+
+    /// The blobs a filter picks whose centers lie in a rectangle, in the order of their keys.
+    pub fn select_rect(&self, rect: Rectangle, filter: &BlobFilter) -> Vec<Key<Blob>> {
+        let mut blobs: Vec<_> = self.physics.centers_in(rect, physics::LayerMask::new(vec![Blob::LAYER])).into_iter()
+            .filter_map(|circle| self.blob_of(circle))
+            .filter(|&key| self.blobs.get(key).is_some_and(|blob| filter.matches(blob)))
+            .collect();
+        blobs.sort();
+        blobs
+    }
+
+    /// Up to `count` of the blobs a filter picks whose centers are nearest a position, nearest first.
+    pub fn nearest_blobs(&self, pos: Vector2, count: usize, filter: &BlobFilter) -> Vec<Key<Blob>> {
+        let picks = |circle: Key<Circle>, _: &Circle| self.blob_of(circle)
+            .and_then(|key| self.blobs.get(key))
+            .is_some_and(|blob| filter.matches(blob));
+        self.physics.nearest(pos, count, picks).into_iter().filter_map(|circle| self.blob_of(circle)).collect()
+    }
+}
+
+/// How fast food spoils in a season, where a year averages 1.
+fn spoilage(season: Season) -> f32 {
+    match season {
+        Season::Spring | Season::Autumn => 1.,
+        Season::Summer => 1.5,
+        Season::Winter => 0.5,
+    }
+}
+
+/// Whether the brain of a blob decides on a tick, when brains decide
+/// every `period` ticks.
+///
+/// Blobs are spread over the ticks of a period by a hash of their
+/// key, so about the same number of brains work on every tick.
+fn thinks_at(key: Key<Blob>, tick: u64, period: u32) -> bool {
+    //  Fibonacci hashing scatters consecutive keys
+    let offset = key.index().wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32;
+    (tick + offset).is_multiple_of(period as u64)
+}
+
+/// The fields of a line of a saved world, parsed in order.
+struct Fields<'a, S> {
+    line: usize,
+    fields: std::slice::Iter<'a, S>,
+}
+
+impl<'a, S: AsRef<str>> Fields<'a, S> {
+    fn new(line: usize, fields: &'a [S]) -> Self {
+        Self { line, fields: fields.iter() }
+    }
+
+    fn next<T: FromStr>(&mut self) -> Result<T, String> {
+        let field = self.fields.next().ok_or_else(|| format!("line {}: too few fields", self.line))?.as_ref();
+        field.parse().map_err(|_| format!("line {}: invalid value `{}`", self.line, field))
+    }
 }
 
+#[derive(Debug, Clone, Copy, Default)]
 pub struct BlobStep {
     target_direction: Option<Vector2>,
+    mood: Mood,
+}
+
+/// What a blob with a state machine brain is doing, see `BrainKind::StateMachine`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mood {
+    /// Going on as it was.
+    #[default]
+    Wander,
+    /// Heading for the nearest thing it eats.
+    Forage,
+    /// Heading away from the nearest blob it does not eat.
+    Flee,
+}
+
+impl Mood {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Wander => "wander",
+            Self::Forage => "forage",
+            Self::Flee => "flee",
+        }
+    }
 }
 
 impl Blob {
     pub const LAYER: physics::Layer = physics::Layer::new(0);
     pub const SIGHT_LAYER: physics::Layer = physics::Layer::new(1);
+    /// How fast outside velocity fades, per second.
+    pub const DAMPING: f32 = 2.;
+    /// The speed and sight depth that cost as much as they do in a middling blob.
+    const MIDDLING_SPEED: f32 = 50.;
+    const MIDDLING_SIGHT: f32 = 60.;
+    /// The least seconds a blob lives before it can have children.
+    pub const REPRODUCTION_AGE: f32 = 5.;
+    /// The part of its most energy a blob must store after a meal to have a child.
+    pub const REPRODUCTION_SHARE: f32 = 0.9;
+    /// The least swimming a blob crosses water with unharmed.
+    pub const SWIMMER: f32 = 0.5;
+    /// How many times its upkeep a blob that cannot swim loses in water.
+    const DROWNING: f32 = 4.;
+    /// The least energy a child is born with, as much as its corpse stores.
+    pub const CHILD_ENERGY: f32 = FOOD_ENERGY;
+    /// The speed a blob moves at without a sound.
+    pub const QUIET_SPEED: f32 = 50.;
+    /// How much farther a blob is heard for every unit it moves faster than `QUIET_SPEED`.
+    const STEP_NOISE: f32 = 2.;
+    /// How far a fight is heard.
+    const FIGHT_NOISE: f32 = 200.;
+    /// The genes a blob is born with, named as the traits of a species,
+    /// with the least and most each can be.
+    pub const GENE_BOUNDS: [(&'static str, f32, f32); 15] = [
+        ("radius", 0., f32::INFINITY),
+        ("speed", 0., f32::INFINITY),
+        ("rotation_speed", 0., f32::INFINITY),
+        ("pov", 0., 360.),
+        ("sight_depth", 0., f32::INFINITY),
+        ("color_attraction", 0., f32::INFINITY),
+        ("color_repulsion", 0., f32::INFINITY),
+        ("max_hunger", 0., f32::INFINITY),
+        ("attack", 0., f32::INFINITY),
+        ("defence", 0., f32::INFINITY),
+        ("hunger_reduction", 0., f32::INFINITY),
+        ("hunger_division", 0., f32::INFINITY),
+        ("swimming", 0., 1.),
+        ("hearing", 0., 1.),
+        ("aura_strength", 0., 1.),
+    ];
+
+    /// The genes in the order of `GENE_BOUNDS`.
+    fn genes_mut(&mut self) -> [&mut f32; 15] {
+        [
+            &mut self.radius, &mut self.speed, &mut self.rotation_speed, &mut self.pov, &mut self.sight_depth,
+            &mut self.color_attraction, &mut self.color_repulsion, &mut self.max_hunger, &mut self.attack, &mut self.defence,
+            &mut self.hunger_reduction, &mut self.hunger_division, &mut self.swimming, &mut self.hearing,
+            &mut self.aura_strength,
+        ]
+    }
+
+    /// Bring what was loaded of a blob within bounds, in a world of a
+    /// size, describing each value that was not, or fail for a blob of
+    /// no size, most hunger or position, which cannot be repaired.
+    fn repair(&mut self, size: Vector2) -> Result<Vec<String>, String> {
+        let finite = |v: Vector2| v.x.is_finite() && v.y.is_finite();
+        if !(self.radius > 0. && self.radius.is_finite() && self.max_hunger > 0. && self.max_hunger.is_finite()) {
+            return Err(format!("radius {} and most hunger {}", self.radius, self.max_hunger));
+        }
+        if !finite(self.pos) {
+            return Err(format!("position {:?}", self.pos));
+        }
+        let mut ret = vec![];
+        for (gene, &(name, min, max)) in self.genes_mut().iter_mut().zip(&Self::GENE_BOUNDS) {
+            if !(min..=max).contains(*gene) {
+                ret.push(format!("{} {} outside [{}, {}]", name, gene, min, max));
+                **gene = if gene.is_nan() { min } else { gene.clamp(min, max) };
+            }
+        }
+        if !(0. ..=self.max_hunger).contains(&self.hunger) {
+            ret.push(format!("hunger {} outside [0, {}]", self.hunger, self.max_hunger));
+            self.hunger = if self.hunger.is_nan() { 0. } else { self.hunger.clamp(0., self.max_hunger) };
+        }
+        for (name, value) in [("direction", &mut self.direction), ("velocity", &mut self.velocity)].iter_mut() {
+            if !finite(**value) {
+                ret.push(format!("{} {:?}", name, value));
+                **value = Vector2::zero();
+            }
+        }
+        if !((0. ..=size.x).contains(&self.pos.x) && (0. ..=size.y).contains(&self.pos.y)) {
+            ret.push(format!("position {:?} outside the world", self.pos));
+            self.pos = Vector2::new(self.pos.x.clamp(0., size.x), self.pos.y.clamp(0., size.y));
+        }
+        Ok(ret)
+    }
 
     pub fn pos(&self) -> Vector2 { self.pos }
 
     pub fn set_pos(&mut self, world: &mut physics::World, value: Vector2) {
         self.pos = value;
-        world.circles.get_mut(self.circle).unwrap().center = value;
-        world.circles.get_mut(self.sight_circle).unwrap().center = value;
+        world.set_center(self.circle, value);
+        world.set_center(self.sight_circle, value);
     }
 
     pub fn radius(&self) -> f32 { self.radius }
 
     pub fn set_radius(&mut self, world: &mut physics::World, value: f32) {
         self.radius = value;
-        world.circles.get_mut(self.circle).unwrap().radius = value;    
+        world.set_radius(self.circle, value);
     }
 
     pub fn direction(&self) -> Vector2 { self.direction }
 
+    pub fn velocity(&self) -> Vector2 { self.velocity }
+
+    /// Where the brain last decided to go, if it sees anything it cares about.
+    pub fn target_direction(&self) -> Option<Vector2> { self.intent.and_then(|intent| intent.target_direction) }
+
+    /// What a state machine brain last decided to do, wandering until it decided.
+    pub fn mood(&self) -> Mood { self.intent.map_or(Mood::Wander, |intent| intent.mood) }
+
+    pub fn set_velocity(&mut self, value: Vector2) { self.velocity = value; }
+
+    pub fn mass(&self) -> f32 { std::f32::consts::PI * self.radius * self.radius }
+
     pub fn set_direction(&mut self, _world: &mut physics::World, value: Vector2) {
         self.direction = value;
     }
@@ -407,65 +2962,163 @@ impl Blob {
 
     pub fn set_sight_depth(&mut self, world: &mut physics::World, value: f32) {
         self.sight_depth = value;
-        world.circles.get_mut(self.sight_circle).unwrap().radius = value;
+        world.set_radius(self.sight_circle, value);
     }
 
-    fn fade_color(&self, color: &Color) -> Color {
+    /// A color faded as the blob goes hungry.
+    pub fn fade_color(&self, color: &Color) -> Color {
         color.fade(1. - self.hunger / self.max_hunger)
     }
 
-    pub fn feed(&mut self) { 
+    pub fn feed(&mut self) { self.feed_by(1.); }
+
+    /// Put a tag on the blob, in place of one of the same text.
+    pub fn tag(&mut self, tag: Tag) {
+        self.untag(&tag.text);
+        self.tags.push(tag);
+    }
+
+    /// Take a tag off the blob, whatever its case, returning whether it had it.
+    pub fn untag(&mut self, text: &str) -> bool {
+        let count = self.tags.len();
+        self.tags.retain(|tag| !tag.text.eq_ignore_ascii_case(text));
+        self.tags.len() != count
+    }
+
+    /// How many times faster than a middling blob it grows hungry, as
+    /// moving fast and seeing far take energy.
+    pub fn upkeep(&self) -> f32 {
+        0.5 + 0.25 * self.speed / Self::MIDDLING_SPEED + 0.25 * self.sight_depth / Self::MIDDLING_SIGHT + 0.25 * self.swimming + 0.25 * self.hearing
+            + if self.aura == Aura::None { 0. } else { aura::UPKEEP * self.aura_strength }
+    }
+
+    /// Whether it is old enough and stores enough to have a child.
+    pub fn can_reproduce(&self) -> bool {
+        self.alive_time >= Self::REPRODUCTION_AGE
+        && self.max_hunger - self.hunger >= Self::REPRODUCTION_SHARE * self.max_hunger
+        && self.max_hunger - self.hunger >= 2. * Self::CHILD_ENERGY
+    }
+
+    /// Eat a part of a meal, losing that part of the hunger a whole one takes away.
+    pub fn feed_by(&mut self, fraction: f32) {
         //  h1 = max( (h0 - hunger_reduction*h_max) / (1 + hunger_division),  0 )
-        self.hunger = f32::max(
+        let fed = f32::max(
             (self.hunger - self.hunger_reduction * self.max_hunger)
             /
             (1. + self.hunger_division),
             0.
         );
+        self.hunger = fed + (self.hunger - fed) * (1. - fraction);
     }
 
-    pub fn draw(&self, draw: &mut DrawingContext) {
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, context: &RenderContext) {
 
         const FONT_HEIGHT: i32 = 20;
-
-        draw.draw_circle_v(self.pos, self.radius, self.fade_color(&self.color));
+        let RenderContext { theme, fonts, zoom, labels, style, .. } = *context;
+
+        match style {
+            Style::Minimal => {
+                shapes::draw_circle(draw, self.pos, self.radius, zoom, theme.contrast(self.color));
+                return;
+            },
+            Style::Classic => shapes::draw_circle(draw, self.pos, self.radius, zoom, self.fade_color(&theme.contrast(self.color))),
+            Style::Detailed => {
+                let body = style::energy_tint(theme.contrast(self.color), self.hunger / self.max_hunger, theme);
+                shapes::draw_circle(draw, self.pos, self.radius, zoom, body);
+                shapes::draw_circle_outline(draw, self.pos, self.radius, 2., zoom, style::species_color(self.species.as_deref(), theme));
+                style::draw_eyes(draw, self.pos, self.radius, self.direction);
+            },
+        }
+        if !labels { return; }
         
         if let Some(name) = &self.name {
-            draw.draw_text(name,
+            fonts.draw_text(draw, name,
                 (self.pos().x - self.radius()) as i32,
                 (self.pos().y - self.radius() - 2. * FONT_HEIGHT as f32) as i32,
-                FONT_HEIGHT, self.fade_color(&self.favorite_color),
+                FONT_HEIGHT, self.fade_color(&theme.contrast(self.favorite_color)),
             );
         }
 
         //  draw time
-        draw.draw_text(&format!("{:.1}", self.alive_time),
+        fonts.draw_text(draw, &format!("{:.1}", self.alive_time),
             (self.pos().x - self.radius()) as i32,
             (self.pos().y - self.radius() - FONT_HEIGHT as f32) as i32,
-            FONT_HEIGHT, self.fade_color(&self.favorite_color),
+            FONT_HEIGHT, self.fade_color(&theme.contrast(self.favorite_color)),
         );
 
+        //  a flag for each tag, planted on the right of the blob
+        for (i, tag) in self.tags.iter().enumerate() {
+            let foot = self.pos + Vector2::new(self.radius * 0.7 + i as f32 * 7., -self.radius * 0.7);
+            let top = foot - Vector2::new(0., 14.);
+            draw.draw_line_v(foot, top, theme.text);
+            draw.draw_triangle(top, top + Vector2::new(0., 6.), top + Vector2::new(7., 3.), tag.color);
+        }
+
         // //  sight drawing
         // let angle = self.direction.x.atan2(self.direction.y).to_degrees();
-        // draw.draw_circle_sector_lines(
+        // shapes::draw_sector_outline(draw,
         //     self.pos,                       //  start
         //     self.sight_depth,               //  radius
-        //     (angle - self.pov / 2.) as i32, //  start_angle
-        //     (angle + self.pov / 2.) as i32, //  end_angle
-        //     25,                             //  segments
+        //     angle - self.pov / 2.,          //  start_angle
+        //     angle + self.pov / 2.,          //  end_angle
+        //     zoom,
         //     self.favorite_color,            //  color
         // );
         // draw.draw_line_v(self.pos, self.pos + self.direction * 3. * self.speed, self.favorite_color);
     }
 
-    pub fn prepare_step<'a, I>(&self, seen: I) -> BlobStep
+    pub fn prepare_step<'a, I>(&self, seen: I, mode: FloatMode) -> BlobStep
     where I: std::iter::IntoIterator<Item=(&'a CircleObject, &'a Color, &'a Vector2)> {
+        let seen: Vec<_> = seen.into_iter().collect();
+        let food = || self.nearest(&seen, |object| self.eats(object)).map(|offset| offset.normalized());
+        match self.brain {
+            BrainKind::ColorAttraction => BlobStep { target_direction: self.color_attraction(seen, mode), mood: Mood::Wander },
+            BrainKind::Greedy => BlobStep { target_direction: food(), mood: Mood::Forage },
+            BrainKind::StateMachine { hungry, fear } => {
+                let was = self.intent.map_or(Mood::Wander, |intent| intent.mood);
+                let threat = self.nearest(&seen, |object| matches!(object, CircleObject::Blob(_)) && !self.eats(object));
+                let hunger = self.hunger / self.max_hunger;
+                //  moods last a while past what started them, so blobs do not dither
+                let mood = match threat.map(|offset| offset.length()) {
+                    Some(distance) if distance < fear || (was == Mood::Flee && distance < 2. * fear) => Mood::Flee,
+                    _ if hunger > hungry || (was == Mood::Forage && hunger > hungry / 2.) => Mood::Forage,
+                    _ => Mood::Wander,
+                };
+                let target_direction = match mood {
+                    Mood::Flee => threat.map(|offset| -offset.normalized()),
+                    Mood::Forage => food(),
+                    Mood::Wander => None,
+                };
+                BlobStep { target_direction, mood }
+            },
+        }
+    }
 
+    /// Whether the blob feeds on an object.
+    fn eats(&self, object: &CircleObject) -> bool {
+        match object {
+            CircleObject::Food(_) => self.diet.eats_food(),
+            CircleObject::Blob(_) => self.diet.eats_blobs(),
+            CircleObject::BlobSight(_) => false,
+        }
+    }
+
+    /// The offset of the nearest object seen that is wanted, if any.
+    fn nearest(&self, seen: &[(&CircleObject, &Color, &Vector2)], wanted: impl Fn(&CircleObject) -> bool) -> Option<Vector2> {
+        seen.iter()
+            .filter(|&&(object, _, _)| wanted(object))
+            .map(|&(_, _, &pos)| pos - self.pos)
+            .filter(|offset| offset.length_sqr() > 0.)
+            .min_by(|a, b| a.length_sqr().total_cmp(&b.length_sqr()))
+    }
+
+    /// The direction toward colors like the favorite one and away from others.
+    fn color_attraction(&self, seen: Vec<(&CircleObject, &Color, &Vector2)>, mode: FloatMode) -> Option<Vector2> {
         let mut sum = Vector2::zero();
         let mut count = 0.;
         for (_, color, pos) in seen {
 
-            let v = color_similarity(&self.favorite_color, color);
+            let v = color_similarity(&self.favorite_color, color, mode);
             let v = v * (if v > 0. { self.color_attraction } else { self.color_repulsion });
             
             if (*pos - self.pos).length_sqr() != 0. {
@@ -475,51 +3128,51 @@ impl Blob {
             }
         }
         
-        let target_direction = if count == 0. || sum.length_sqr() == 0. {
+        if count == 0. || sum.length_sqr() == 0. {
             None
         } else {
             let d = (sum / count as f32).normalized();
             Some(d)
-        };
-
-        BlobStep { target_direction }
+        }
     }
 
-    pub fn step(&mut self, step: &BlobStep, timestep: f32, physics_world: &mut physics::World, world_size: Vector2) {
+    pub fn step<R: Rng + ?Sized>(&mut self, step: &BlobStep, timestep: f32, mode: FloatMode, physics_world: &mut physics::World, world_size: Vector2, rng: &mut R) {
         
         //  update direction
         if self.direction == Vector2::zero() {
-            self.direction = random_vector2() * 2. - 1.;
+            self.direction = random_vector2(rng) * 2. - 1.;
         }
         else if let Some(target_direction) = step.target_direction {
             let t = self.rotation_speed * timestep;
-            self.direction = math::slerp(self.direction, target_direction, t);
+            self.direction = math::slerp(self.direction, target_direction, t, mode);
         } 
 
         //  move position
-        self.pos += self.direction * self.speed * timestep;
-        physics_world.circles.get_mut(self.circle).unwrap().center = self.pos;
-        physics_world.circles.get_mut(self.sight_circle).unwrap().center = self.pos;
+        self.pos += (self.direction * self.speed * (1. - self.slowed) + self.velocity) * timestep;
+        self.velocity = physics::damp(self.velocity, Self::DAMPING, timestep, mode);
+        physics_world.set_center(self.circle, self.pos);
+        physics_world.set_center(self.sight_circle, self.pos);
         
-        //  do hunger
-        self.hunger += timestep;
-
         //  do border
         if self.pos().x > world_size.x {
             self.set_pos(physics_world, Vector2::new(world_size.x, self.pos().y));
             self.set_direction(physics_world, Vector2::new(-self.direction().x, self.direction().y));
+            self.velocity.x = -self.velocity.x;
         }
         if self.pos().y > world_size.y {
             self.set_pos(physics_world, Vector2::new(self.pos().x, world_size.y));
             self.set_direction(physics_world, Vector2::new(self.direction().x, -self.direction().y));
+            self.velocity.y = -self.velocity.y;
         }
         if self.pos().x < 0. {
             self.set_pos(physics_world, Vector2::new(0., self.pos().y));
             self.set_direction(physics_world, Vector2::new(-self.direction().x, self.direction().y));
+            self.velocity.x = -self.velocity.x;
         }
         if self.pos().y < 0. {
             self.set_pos(physics_world, Vector2::new(self.pos().x, 0.));
             self.set_direction(physics_world, Vector2::new(self.direction().x, -self.direction().y));
+            self.velocity.y = -self.velocity.y;
         }
 
         //  do time
@@ -531,20 +3184,31 @@ impl Food {
     pub const LAYER: physics::Layer = physics::Layer::new(2);
     pub const COLOR: Color = Color::GREEN;
     pub const RADIUS: f32 = 5.;
+    /// The color food turns as it spoils.
+    pub const SPOILED_COLOR: Color = Color::BROWN;
+    /// The color of food grown in a lake.
+    pub const AQUATIC_COLOR: Color = Color::DARKBLUE;
+    /// How many times faster than food corpses spoil.
+    pub const CORPSE_SPOILAGE: f32 = 2.;
 
     pub fn pos(&self) -> Vector2 { self.pos }
 
-    fn circle_mut<'a>(&self, physics_world: &'a mut physics::World) -> &'a mut Circle {
-        physics_world.circles.get_mut(self.circle).unwrap()
+    /// Whether the food is what is left of a dead blob.
+    pub fn is_corpse(&self) -> bool { self.corpse }
+
+    /// The color it is seen as, before it spoils.
+    pub fn color(&self) -> &'static Color {
+        if self.aquatic { &Self::AQUATIC_COLOR } else { &Self::COLOR }
     }
 
     pub fn set_pos(&mut self, physics_world: &mut physics::World, value: Vector2) {
         self.pos = value;
-        self.circle_mut(physics_world).center = value;
+        physics_world.set_center(self.circle, value);
     }
 
-    pub fn draw(&self, draw: &mut DrawingContext) {
-        draw.draw_circle_v(self.pos, Self::RADIUS, Self::COLOR);
+    pub fn draw<D: RaylibDraw>(&self, draw: &mut D, context: &RenderContext) {
+        let color = theme::lerp_color(*self.color(), Self::SPOILED_COLOR, self.spoiled);
+        shapes::draw_circle(draw, self.pos, Self::RADIUS, context.zoom, context.theme.contrast(color));
     }
 }
 
@@ -552,7 +3216,7 @@ impl CircleObject {
     pub fn color<'a>(&self, sim: &'a Simulation) -> Option<&'a Color> {
         match *self {
             Self::Blob(blob) => sim.get_blob(blob).map(|x| &x.color),
-            Self::Food(_) => Some(&Food::COLOR),
+            Self::Food(food) => sim.get_food(food).map(Food::color),
             Self::BlobSight(_) => None,
         }
     }
@@ -561,3 +3225,555 @@ impl CircleObject {
 pub mod prelude {
     pub use super::*;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(config: SimulationConfig) -> Vec<Vector2> {
+        let mut sim = Simulation::new(config);
+        for i in 0..5 {
+            let pos = Vector2::new(40. * i as f32 + 20., 100.);
+            sim.insert_blob(BlobConfig::new(pos));
+        }
+        for _ in 0..120 {
+            sim.step();
+        }
+        sim.blobs().map(|(_, blob)| blob.pos()).collect()
+    }
+
+    #[test]
+    fn test_owed_time() {
+        let timing = Timing::default();
+        let (owed, dropped) = timing.owed(0.005, 0.02, 1.);
+        assert!((owed - 0.025).abs() < 1e-6 && !dropped);
+        //  a stall is caught up on only in part
+        assert_eq!(timing.owed(0., 5., 1.), (Timing::MAX_FRAME_TIME, false));
+        //  and too many ticks slow the world down
+        let (owed, dropped) = timing.owed(0., 0.2, 64.);
+        assert!(dropped);
+        assert_eq!((owed / timing.tick_length()).round() as u32, Timing::MAX_FRAME_TICKS);
+    }
+
+    #[test]
+    fn test_staggered_brains() {
+        let period = 8;
+        let mut per_tick = vec![0; period as usize];
+        for index in 0..800 {
+            let ticks: Vec<u64> = (0..period as u64).filter(|&tick| thinks_at(Key::from_index(index), tick, period)).collect();
+            assert_eq!(ticks.len(), 1);
+            per_tick[ticks[0] as usize] += 1;
+        }
+        assert!(per_tick.iter().all(|&count| count > 60 && count < 140), "{:?}", per_tick);
+        assert!(thinks_at(Key::from_index(3), 5, 1));
+    }
+
+    #[test]
+    fn test_perception_cache() {
+        let mut config = SimulationConfig::new(Vector2::new(1000., 1000.));
+        config.parameters.food_rate = 0.;
+        let mut sim = Simulation::new(config);
+        let key = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.step();
+        assert!(sim.perception.contains_key(&key));
+        //  food far away goes unnoticed, food in sight makes the blob look again
+        sim.insert_food(Vector2::new(900., 900.));
+        assert!(sim.perception.contains_key(&key));
+        let food = sim.insert_food(Vector2::new(120., 100.));
+        assert!(!sim.perception.contains_key(&key));
+        sim.step();
+        assert!(sim.perception[&key].neighbors.contains(&sim.get_food(food).unwrap().circle));
+    }
+
+    #[test]
+    fn test_queries() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(1000., 1000.)));
+        let grazer = |x: f32, y: f32| BlobConfig { species: Some("Grazer".to_string()), ..BlobConfig::new(Vector2::new(x, y)) };
+        let a = sim.insert_blob(grazer(100., 100.));
+        let b = sim.insert_blob(BlobConfig { speed: 90., ..BlobConfig::new(Vector2::new(120., 100.)) });
+        let c = sim.insert_blob(grazer(400., 100.));
+        let d = sim.insert_blob(grazer(900., 900.));
+        let any = BlobFilter::default();
+        assert_eq!(sim.select_circle(Vector2::new(110., 100.), 6., &any), vec![a, b]);
+        assert_eq!(sim.select_rect(Rectangle::new(50., 50., 400., 100.), &any), vec![a, b, c]);
+        let fast = BlobFilter { genes: vec![("Speed", 60. ..=120.)], ..BlobFilter::default() };
+        assert_eq!(sim.select_rect(Rectangle::new(50., 50., 400., 100.), &fast), vec![b]);
+        //  the search widens until it finds enough, and stops at the world's edge
+        let grazers = BlobFilter::species(Some("Grazer"));
+        assert_eq!(sim.nearest_blobs(Vector2::new(130., 100.), 2, &grazers), vec![a, c]);
+        assert_eq!(sim.nearest_blobs(Vector2::new(0., 0.), 10, &grazers), vec![a, c, d]);
+    }
+
+    #[test]
+    fn test_spoilage() {
+        let mut config = SimulationConfig::new(Vector2::new(300., 300.));
+        config.parameters.food_rate = 0.;
+        config.parameters.food_lifetime = 1.;
+        config.audit_energy = true;
+        let mut sim = Simulation::new(config);
+        let food = sim.insert_food(Vector2::new(50., 50.));
+        let corpse = sim.insert_corpse(Vector2::new(250., 250.));
+        //  a spring tick spoils food by a sixtieth of its lifetime, and corpses twice as fast
+        for _ in 0..30 { sim.step(); }
+        assert!(sim.get_food(corpse).is_none());
+        assert!((sim.get_food(food).unwrap().spoiled - 0.5).abs() < 1e-4);
+        for _ in 0..31 { sim.step(); }
+        assert!(sim.get_food(food).is_none());
+        assert_eq!(spoilage(Season::Summer) + spoilage(Season::Winter), 2.);
+    }
+
+    #[test]
+    fn test_water() {
+        let mut config = SimulationConfig::new(Vector2::new(400., 300.));
+        config.parameters.water = 0.3;
+        let mut sim = Simulation::new(config.clone());
+        assert!(!sim.lakes().is_empty());
+        assert_eq!(Simulation::new(config).lakes(), sim.lakes());
+        let lake = sim.lakes()[0];
+        let food = sim.insert_food(lake.center);
+        assert_eq!(sim.get_food(food).unwrap().color(), &Food::AQUATIC_COLOR);
+
+        //  in the same water, a blob that cannot swim suffers and heads for the shore
+        let offset = Vector2::new(lake.radius / 2., 0.);
+        let walker = sim.insert_blob(BlobConfig { speed: 0., ..BlobConfig::new(lake.center + offset) });
+        let swimmer = sim.insert_blob(BlobConfig { speed: 0., swimming: 1., ..BlobConfig::new(lake.center - offset) });
+        let shore = sim.lakes().iter().find(|lake| lake.contains(sim.get_blob(walker).unwrap().pos())).unwrap().center;
+        sim.step();
+        let (walker, swimmer) = (sim.get_blob(walker).unwrap(), sim.get_blob(swimmer).unwrap());
+        assert!(walker.hunger > 2. * swimmer.hunger);
+        let away = (walker.pos() - shore).normalized();
+        assert!((walker.direction.x - away.x).abs() < 1e-4 && (walker.direction.y - away.y).abs() < 1e-4);
+
+        sim.set_parameter("water", "0").unwrap();
+        assert!(sim.lakes().is_empty());
+    }
+
+    #[test]
+    fn test_hearing() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(400., 300.)));
+        //  two blobs facing away from a fast one behind them, which neither sees
+        let prey = BlobConfig { speed: 0., pov: 30., color_repulsion: 1., ..BlobConfig::new(Vector2::new(200., 150.)) };
+        let listener = sim.insert_blob(BlobConfig { hearing: 1., ..prey.clone() });
+        let deaf = sim.insert_blob(BlobConfig { pos: Vector2::new(200., 100.), ..prey });
+        let hunter = sim.insert_blob(BlobConfig { speed: 150., ..BlobConfig::new(Vector2::new(120., 130.)) });
+        let quiet = sim.insert_blob(BlobConfig { speed: 40., ..BlobConfig::new(Vector2::new(300., 150.)) });
+        for &key in &[listener, deaf, hunter, quiet] {
+            sim.get_blob_mut(key).unwrap().direction = Vector2::new(1., 0.);
+        }
+        sim.step();
+        assert_eq!(sim.heard_by(listener, Blob::FIGHT_NOISE), [hunter].iter().cloned().collect());
+        assert!(sim.heard_by(deaf, Blob::FIGHT_NOISE).is_empty());
+
+        //  the listener turns from what it heard, the deaf blob goes on
+        sim.step();
+        let heading = |key| sim.get_blob(key).unwrap().intent.unwrap().target_direction;
+        let behind = sim.get_blob(hunter).unwrap().pos() - sim.get_blob(listener).unwrap().pos();
+        assert!(heading(listener).unwrap().dot(behind) < 0.);
+        assert_eq!(heading(deaf), None);
+    }
+
+    #[test]
+    fn test_brains() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(400., 300.)));
+        //  a blob of each brain between food on the left and a blob on the right
+        let blob = |y: f32, brain| BlobConfig { brain, pov: 180., color_repulsion: 0., ..BlobConfig::new(Vector2::new(200., y)) };
+        let hungry = BrainKind::StateMachine { hungry: 0.5, fear: 40. };
+        let greedy = sim.insert_blob(blob(60., BrainKind::Greedy));
+        let forager = sim.insert_blob(BlobConfig { hunger: 8., ..blob(150., hungry) });
+        let idler = sim.insert_blob(blob(240., hungry));
+        for &y in &[60., 150., 240.] {
+            sim.insert_food(Vector2::new(160., y));
+            sim.insert_blob(BlobConfig { speed: 0., ..BlobConfig::new(Vector2::new(290., y)) });
+        }
+        sim.step();
+        let intent = |sim: &Simulation, key| sim.get_blob(key).unwrap().intent.unwrap();
+        assert_eq!(intent(&sim, greedy).target_direction, Some(Vector2::new(-1., 0.)));
+        let (forager, idled) = (intent(&sim, forager), intent(&sim, idler));
+        assert_eq!((forager.mood, forager.target_direction), (Mood::Forage, Some(Vector2::new(-1., 0.))));
+        assert_eq!((idled.mood, idled.target_direction), (Mood::Wander, None));
+
+        //  a blob coming near scares the idler away
+        sim.set_blob_pos(idler, Vector2::new(260., 240.));
+        sim.step();
+        let fled = intent(&sim, idler);
+        assert_eq!(fled.mood, Mood::Flee);
+        assert!(fled.target_direction.unwrap().x < 0.);
+    }
+
+    #[test]
+    fn test_hooks() {
+        use std::sync::{Arc, Mutex};
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let log = Arc::new(Mutex::new(vec![]));
+        let logger = |name: &'static str| {
+            let log = log.clone();
+            Box::new(move |_: &mut Simulation| log.lock().unwrap().push(name))
+        };
+        sim.on_post_tick(0, logger("post"));
+        let early = sim.on_post_tick(5, logger("early post"));
+        sim.on_pre_tick(0, logger("pre"));
+        let removed = Arc::new(Mutex::new(vec![]));
+        let noted = removed.clone();
+        sim.on_entity_removed(0, Box::new(move |_, entity| if let Removed::Blob(key, _) = entity { noted.lock().unwrap().push(*key) }));
+        sim.step();
+        assert_eq!(*log.lock().unwrap(), ["pre", "early post", "post"]);
+
+        assert!(sim.unregister_hook(early));
+        assert!(!sim.unregister_hook(early));
+        log.lock().unwrap().clear();
+        sim.step();
+        sim.fork().step();
+        assert_eq!(*log.lock().unwrap(), ["pre", "post"]);
+
+        let blob = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.remove_blob(blob);
+        assert_eq!(*removed.lock().unwrap(), [blob]);
+    }
+
+    #[test]
+    fn test_reproduction() {
+        let mut config = SimulationConfig::new(Vector2::new(300., 300.));
+        config.parameters.food_rate = 0.;
+        config.audit_energy = true;
+        let mut sim = Simulation::new(config);
+        let parent = sim.insert_blob(BlobConfig { species: Some("Grazer".to_string()), max_hunger: 20., ..BlobConfig::new(Vector2::new(100., 100.)) });
+        sim.insert_food(Vector2::new(100., 100.));
+        //  too young to have a child
+        sim.step();
+        assert_eq!(sim.blobs().count(), 1);
+        sim.get_blob_mut(parent).unwrap().alive_time = Blob::REPRODUCTION_AGE;
+        sim.insert_food(sim.get_blob(parent).unwrap().pos());
+        sim.step();
+        let (_, child) = sim.blobs().find(|&(key, _)| key != parent).unwrap();
+        assert_eq!(child.species.as_deref(), Some("Grazer"));
+        assert!((child.speed - 50.).abs() <= 5. && child.speed != 50.);
+        //  the parent handed over half of what it stored
+        let stored = |blob: &Blob| blob.max_hunger - blob.hunger;
+        assert!((stored(sim.get_blob(parent).unwrap()) - stored(child)).abs() < 0.1);
+        assert!(!sim.get_blob(parent).unwrap().can_reproduce());
+    }
+
+    #[test]
+    fn test_energy_changes() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let blob = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.get_blob_mut(blob).unwrap().hunger = 5.;
+        sim.insert_food(Vector2::new(100., 100.));
+        sim.step();
+        assert!(matches!(sim.energy_changes(), [change] if change.blob == blob && change.amount > 0.), "{:?}", sim.energy_changes());
+        sim.step();
+        assert!(sim.energy_changes().is_empty());
+    }
+
+    #[test]
+    fn test_invariants() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let blob = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let food = sim.insert_food(Vector2::new(200., 200.));
+        assert!(sim.check_invariants().is_empty());
+
+        sim.get_blob_mut(blob).unwrap().hunger = f32::NAN;
+        let circle = sim.get_food(food).unwrap().circle;
+        sim.physics.remove(circle);
+        let violations = sim.check_invariants();
+        assert_eq!(violations.len(), 3, "{:?}", violations);
+        assert!(violations[0].starts_with("blob 0 (unnamed) has hunger NaN"));
+        assert_eq!(violations[1], format!("food 0 has no circle {}", circle.index()));
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let healthy = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let lost = sim.insert_blob(BlobConfig::new(Vector2::new(150., 150.)));
+        let broken = sim.insert_blob(BlobConfig::new(Vector2::new(200., 200.)));
+        assert_eq!(sim.sanitize(), 0);
+
+        let blob = sim.blobs.get_mut(lost).unwrap();
+        blob.pos = Vector2::new(f32::NAN, 0.);
+        blob.velocity = Vector2::new(f32::INFINITY, 0.);
+        sim.blobs.get_mut(broken).unwrap().radius = -1.;
+        let tick = sim.clock().tick();
+        assert_eq!(sim.sanitize(), 2);
+        assert!(sim.check_invariants().is_empty());
+        assert!(sim.contains_blob(healthy));
+        //  moved back to where its circle is
+        assert_eq!(sim.get_blob(lost).unwrap().pos(), Vector2::new(150., 150.));
+        assert_eq!(sim.get_blob(lost).unwrap().velocity, Vector2::zero());
+        assert!(!sim.contains_blob(broken));
+        let removed: Vec<_> = sim.events_since(tick)
+            .filter_map(|(_, event)| match event { Event::Quarantined { removed, .. } => Some(*removed), _ => None })
+            .collect();
+        assert_eq!(removed, [false, true]);
+
+        //  stepping quarantines a blob broken between ticks instead of failing on it
+        let lost = sim.insert_blob(BlobConfig::new(Vector2::new(150., 150.)));
+        let gone = sim.insert_blob(BlobConfig::new(Vector2::new(250., 250.)));
+        let blob = sim.blobs.get_mut(lost).unwrap();
+        blob.pos = Vector2::new(f32::NAN, 0.);
+        blob.velocity = Vector2::new(f32::INFINITY, f32::NEG_INFINITY);
+        let blob = sim.blobs.get_mut(gone).unwrap();
+        blob.pos = Vector2::new(f32::NAN, f32::NAN);
+        blob.velocity = Vector2::new(f32::INFINITY, 0.);
+        let circle = blob.circle;
+        sim.physics.set_center(circle, Vector2::new(f32::NAN, f32::NAN));
+        for _ in 0..3 {
+            sim.step();
+        }
+        assert!(sim.check_invariants().is_empty());
+        assert!(sim.get_blob(lost).unwrap().pos().x.is_finite());
+        assert!(!sim.contains_blob(gone));
+    }
+
+    #[test]
+    fn test_energy_audit() {
+        //  blobs crowded together eat, fight, starve and leave corpses
+        let config = SimulationConfig { seed: 3, audit_energy: true, ..SimulationConfig::new(Vector2::new(300., 300.)) };
+        run(config.clone());
+        let mut sim = Simulation::new(config);
+        let blob = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        sim.step();
+        sim.get_blob_mut(blob).unwrap().hunger -= 1.;
+        let stored = sim.stored_energy();
+        assert!(sim.energy.as_mut().unwrap().check(stored).is_err());
+    }
+
+    #[test]
+    fn test_same_seed_same_run() {
+        let config = SimulationConfig { seed: 7, ..SimulationConfig::new(Vector2::new(300., 300.)) };
+        assert_eq!(run(config.clone()), run(config.clone()));
+        assert_eq!(Simulation::new(config.clone()).state_hash(), Simulation::new(config.clone()).state_hash());
+        assert_ne!(run(config.clone()), run(SimulationConfig { seed: 8, ..config.clone() }));
+        let coarse = SimulationConfig { timing: Timing { tick_rate: 30, substeps: 4, brain_period: 3 }, ..config.clone() };
+        assert_eq!(run(coarse.clone()), run(coarse));
+        assert!(Simulation::new(config).set_timing(Timing { substeps: 0, ..Timing::default() }).is_err());
+
+        let mut parameters = Parameters::default();
+        parameters.set("food_rate", "0.5").unwrap();
+        assert_eq!(parameters.food_rate, 0.5);
+        assert!(parameters.set("food_rate", "lots").is_err());
+        assert!(parameters.set("gravity", "1").is_err());
+    }
+
+    #[test]
+    fn test_deterministic_math() {
+        //  the hash must come out the same on every platform, as it does for lockstep peers
+        let config = SimulationConfig { seed: 7, float_mode: FloatMode::Deterministic, ..SimulationConfig::new(Vector2::new(300., 300.)) };
+        let mut sim = Simulation::new(config);
+        for i in 0..5 {
+            let pos = Vector2::new(40. * i as f32 + 20., 100.);
+            sim.insert_blob(BlobConfig::new(pos));
+        }
+        for _ in 0..120 {
+            sim.step();
+        }
+        //  and through births, whose children are placed around their parents
+        let keys: Vec<_> = sim.blobs().map(|(key, _)| key).collect();
+        for key in keys {
+            let blob = sim.get_blob_mut(key).unwrap();
+            blob.alive_time = Blob::REPRODUCTION_AGE;
+            blob.max_hunger = 20.;
+            blob.hunger = 0.;
+            let pos = blob.pos();
+            sim.insert_food(pos);
+        }
+        for _ in 0..60 {
+            sim.step();
+        }
+        assert!(sim.blobs().any(|(_, blob)| blob.parent.is_some()));
+        assert_eq!(sim.state_hash(), 490922878539195158);
+    }
+
+    #[test]
+    fn test_fork() {
+        let mut sim = Simulation::new(SimulationConfig { seed: 3, ..SimulationConfig::new(Vector2::new(300., 300.)) });
+        for i in 0..5 {
+            sim.insert_blob(BlobConfig::new(Vector2::new(50. * i as f32 + 20., 100.)));
+            sim.insert_food(Vector2::new(50. * i as f32 + 20., 150.));
+        }
+        for _ in 0..30 {
+            sim.step();
+        }
+        //  a fork run on another thread ends up where the original does
+        let fork = sim.fork();
+        let forked = std::thread::spawn(move || {
+            let mut fork = fork;
+            for _ in 0..60 {
+                fork.step();
+            }
+            fork.state_hash()
+        });
+        let mut removed = sim.fork();
+        for _ in 0..60 {
+            sim.step();
+        }
+        assert_eq!(forked.join().unwrap(), sim.state_hash());
+
+        let key = removed.blobs().next().unwrap().0;
+        removed.remove_blob(key);
+        assert!(sim.get_blob(key).is_some());
+    }
+
+    #[test]
+    fn test_save_load() {
+        let mut sim = Simulation::new(SimulationConfig {
+            seed: 5, mutators: vec![Mutator::Fog], lights: vec![Light::new(Vector2::new(150., 100.), 80.)],
+            ..SimulationConfig::new(Vector2::new(300., 200.))
+        });
+        for i in 0..5 {
+            let name = Some(format!("Blob {}", i)).filter(|_| i % 2 == 0);
+            sim.insert_blob(BlobConfig { name, ..BlobConfig::new(Vector2::new(50. * i as f32 + 20., 100.)) });
+            sim.insert_food(Vector2::new(50. * i as f32 + 20., 150.));
+        }
+        for _ in 0..30 {
+            sim.step();
+        }
+        let removed = sim.blobs().next().unwrap().0;
+        sim.remove_blob(removed);
+        sim.viewpoints_mut().set(2, Viewpoint { center: Vector2::new(120., 80.), zoom: 2.5 }).unwrap();
+        let tagged = sim.blobs().last().unwrap().0;
+        sim.get_blob_mut(tagged).unwrap().tag(Tag::parse("scout:green").unwrap());
+        sim.get_blob_mut(tagged).unwrap().aura = Aura::Heal;
+        sim.get_blob_mut(tagged).unwrap().aura_strength = 0.5;
+        sim.add_field(physics::Field { center: Vector2::new(40., 60.), radius: 25., force: physics::Force::Damping(2.) }, 4);
+        sim.set_override("Deep Sea", "mutation_rate", 2.).unwrap();
+        sim.watch(Region::parse("nest@10.5,20,100,50 predators>3", None).unwrap());
+        sim.spawning_as(Source::User, |sim| sim.insert_food(Vector2::new(10., 10.)));
+        let drone = sim.add_drone(Drone { target: Some(Vector2::new(200., 20.)), ..Drone::new(Vector2::new(30., 40.)) });
+        sim.stats_mut().mark(12, MarkKind::Change, "food_rate 0.5");
+        sim.stats_mut().mark(20, MarkKind::Bookmark, "a bloom");
+        //  long enough to be downsampled into tiers
+        let long = sim.stats_mut().register("long");
+        let samples = crate::stats::RECENT + 2 * crate::stats::TIER_POINTS * crate::stats::FACTOR;
+        for i in 0..samples {
+            sim.stats_mut().push(long, (i * 30 / samples) as u64, i as f32);
+        }
+
+        let save = |sim: &Simulation| {
+            let mut out = vec![];
+            sim.save(&mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        let saved = save(&sim);
+        let mut loaded = Simulation::load(saved.as_bytes()).unwrap();
+        assert!(loaded.check_invariants().is_empty());
+        assert_eq!(loaded.clock().tick(), sim.clock().tick());
+        assert_eq!(loaded.lights(), sim.lights());
+        assert_eq!(loaded.viewpoints(), sim.viewpoints());
+        assert_eq!(loaded.regions(), sim.regions());
+        assert_eq!(loaded.overrides(), sim.overrides());
+        assert_eq!(loaded.drones().collect::<Vec<_>>(), vec![(drone, sim.drones().next().unwrap().1)]);
+        assert!(loaded.get_blob(removed).is_none());
+        assert_eq!(loaded.get_blob(tagged).unwrap().tags, sim.get_blob(tagged).unwrap().tags);
+        assert_eq!((loaded.get_blob(tagged).unwrap().aura, loaded.fields().count()), (Aura::Heal, 1));
+        for (key, blob) in sim.blobs() {
+            assert_eq!(format!("{:?}", loaded.get_blob(key).unwrap().name), format!("{:?}", blob.name));
+        }
+        //  graphs, marks and bookmarks come back as they were
+        assert!(sim.stats().ids().count() > 1);
+        for id in sim.stats().ids() {
+            assert_eq!((loaded.stats().name(id), loaded.stats().history(id)), (sim.stats().name(id), sim.stats().history(id)));
+        }
+        assert_eq!(loaded.stats().marks(), sim.stats().marks());
+        //  saves from before statistics were kept still load, without them
+        let old = saved.lines()
+            .filter(|line| !line.starts_with("stats\t"))
+            .map(|line| if line.starts_with("version\t") { "version\t16" } else { line })
+            .collect::<Vec<_>>().join("\n");
+        assert!(Simulation::load(old.as_bytes()).unwrap().stats().ids().next().is_none());
+        //  the same again, but for the generator seeded anew
+        let without_rng = |text: &str| text.lines().filter(|line| !line.starts_with("rng\t")).collect::<Vec<_>>().join("\n");
+        assert_eq!(without_rng(&save(&loaded)), without_rng(&saved));
+        //  slots of removed blobs are given out again as they would have been, under new keys
+        assert_eq!(loaded.insert_food(Vector2::zero()), sim.insert_food(Vector2::zero()));
+        let born = loaded.insert_blob(BlobConfig::default());
+        assert_eq!(born, sim.insert_blob(BlobConfig::default()));
+        assert!(born != removed && loaded.get_blob(removed).is_none());
+        //  nor of other sources' namespaces
+        let user_food = |sim: &mut Simulation| sim.spawning_as(Source::User, |sim| sim.insert_food(Vector2::zero()));
+        assert_eq!(user_food(&mut loaded), user_food(&mut sim));
+        assert_eq!(Namespace::of(user_food(&mut loaded)), Source::User.namespace());
+
+        assert!(Simulation::load(saved.replace(&format!("version\t{}", Simulation::SAVE_VERSION), "version\t99").as_bytes()).is_err());
+        assert!(Simulation::load(saved.replace("herbivore", "fungus").as_bytes()).is_err());
+        let size_line = saved.lines().position(|line| line.starts_with("size\t")).unwrap() + 1;
+        for size in ["size\t-1\t-1", "size\tnan\t0", "size\tinf\t200"] {
+            let resized = saved.replace("size\t300\t200", size);
+            let error = Simulation::load_repairing(resized.as_bytes()).err().unwrap();
+            assert!(error.starts_with(&format!("line {}: invalid world size", size_line)), "{}", error);
+        }
+
+        //  what cannot be fails at its line, or is repaired or dropped with a warning
+        let broken: Vec<_> = saved.lines().map(|line| {
+            let mut fields: Vec<_> = line.split('\t').collect();
+            if fields[0] == "blob" {
+                //  outside the world, and slower than still
+                fields[11] = "-50";
+                fields[22] = "-3";
+            }
+            fields.join("\t")
+        }).collect();
+        let first_blob = broken.iter().position(|line| line.starts_with("blob\t")).unwrap() + 1;
+        let broken = broken.join("\n") + "\ntag\t999\tghost\t0\t0\t0\t255";
+        let error = Simulation::load(broken.as_bytes()).err().unwrap();
+        assert!(error.starts_with(&format!("line {}: blob", first_blob)), "{}", error);
+        let (repaired, warnings) = Simulation::load_repairing(broken.as_bytes()).unwrap();
+        let blobs = saved.lines().filter(|line| line.starts_with("blob\t")).count();
+        assert_eq!((repaired.blobs().count(), warnings.len()), (blobs, 2 * blobs + 1));
+        assert!(repaired.blobs().all(|(_, blob)| blob.pos().x == 0. && blob.speed == 0.));
+        assert!(repaired.check_invariants().is_empty());
+    }
+
+    /// Mutate a saved world at random and load it, which must fail
+    /// with an error or repair it rather than panic, since saves come
+    /// from anywhere.
+    #[test]
+    fn test_fuzz_load() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut sim = Simulation::new(SimulationConfig {
+            seed: 7, lights: vec![Light::new(Vector2::new(100., 100.), 60.)],
+            ..SimulationConfig::new(Vector2::new(200., 200.))
+        });
+        for i in 0..4 {
+            sim.insert_blob(BlobConfig { name: Some(format!("Blob {}", i)), ..BlobConfig::new(Vector2::new(40. * i as f32 + 20., 80.)) });
+            sim.insert_food(Vector2::new(40. * i as f32 + 20., 120.));
+        }
+        for _ in 0..10 {
+            sim.step();
+        }
+        sim.add_field(physics::Field { center: Vector2::new(40., 60.), radius: 25., force: physics::Force::Damping(2.) }, 4);
+        sim.watch(Region::parse("nest@10,20,100,50 predators>3", None).unwrap());
+        sim.add_drone(Drone::new(Vector2::new(30., 40.)));
+        let mut saved = vec![];
+        sim.save(&mut saved).unwrap();
+        let saved = String::from_utf8(saved).unwrap();
+        let lines: Vec<_> = saved.lines().collect();
+
+        let values = ["", "-", "x", "0", "-1", "1e30", "-1e30", "nan", "inf", "-inf", "18446744073709551616", "4294967295", "0.5"];
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..3000 {
+            let mut lines: Vec<Vec<String>> = lines.iter().map(|line| line.split('\t').map(str::to_string).collect()).collect();
+            for _ in 0..rng.gen_range(1..4) {
+                let i = rng.gen_range(0..lines.len());
+                match rng.gen_range(0..6) {
+                    0 => lines.truncate(i),
+                    1 => { lines.remove(i); },
+                    2 => { let line = lines[i].clone(); lines.insert(rng.gen_range(0..=lines.len()), line); },
+                    3 => { lines[i].pop(); },
+                    _ => {
+                        let j = rng.gen_range(0..lines[i].len());
+                        lines[i][j] = values[rng.gen_range(0..values.len())].to_string();
+                    },
+                }
+                if lines.is_empty() { break; }
+            }
+            let text = lines.iter().map(|fields| fields.join("\t")).collect::<Vec<_>>().join("\n");
+            let _ = Simulation::load(text.as_bytes());
+            if let Ok((mut repaired, _)) = Simulation::load_repairing(text.as_bytes()) {
+                assert!(repaired.check_invariants().is_empty(), "{}", text);
+                repaired.step();
+            }
+        }
+    }
+}
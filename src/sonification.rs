@@ -0,0 +1,206 @@
+//! Ambient sound that follows the state of a world.
+//!
+//! A drone is synthesized from a few measures of the world: its
+//! pitch rises with the population, a plucked pulse on top of it
+//! quickens as more blobs are born, and a second voice drifts from
+//! a consonant fifth towards a beating tritone as predation grows.
+//! Changes glide rather than jump, so the sound stays calm and a
+//! listener can follow the world without watching it.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut sonification = Sonification::new(&thread)?;
+//! //  every frame
+//! sonification.update(&sim);
+//! ```
+
+use std::f32::consts::PI;
+
+use raylib::prelude::*;
+
+use crate::simulation::{Simulation, Event};
+
+/// The measures of a world that the sound follows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    pub population: usize,
+    /// Blobs born per second of simulation time.
+    pub birth_rate: f32,
+    /// Blobs killed by other blobs per second of simulation time.
+    pub predation_rate: f32,
+}
+
+impl Metrics {
+    /// Seconds of simulation time that rates are measured over.
+    pub const WINDOW: f32 = 30.;
+
+    pub fn measure(sim: &Simulation) -> Self {
+        let clock = sim.clock();
+        let window_ticks = (Self::WINDOW / clock.tick_length()) as u64;
+        let (mut births, mut kills) = (0, 0);
+        for (_, event) in sim.events_since(clock.tick().saturating_sub(window_ticks)) {
+            match event {
+                Event::Born(_) => births += 1,
+                Event::Killed { .. } => kills += 1,
+                Event::Starved(_) | Event::Quarantined { .. } | Event::Alert { .. } | Event::Extinct(_) | Event::Weather(_) | Event::Record(_) => (),
+            }
+        }
+        //  early on the window reaches back before the start
+        let seconds = (clock.time() as f32).clamp(1., Self::WINDOW);
+        Self {
+            population: sim.blobs().count(),
+            birth_rate: births as f32 / seconds,
+            predation_rate: kills as f32 / seconds,
+        }
+    }
+}
+
+/// Generates the sound for given metrics, one buffer at a time.
+#[derive(Debug, Clone)]
+pub struct Synth {
+    sample_rate: f32,
+    //  current values, which glide towards their targets
+    pitch: f32,
+    tempo: f32,
+    dissonance: f32,
+    target_pitch: f32,
+    target_tempo: f32,
+    target_dissonance: f32,
+    //  phases in cycles of the drone, the second voice and the pulse
+    phases: [f32; 3],
+    //  time since the last beat, in seconds
+    beat_time: f32,
+}
+
+impl Synth {
+    /// The pitch of an empty world, in hertz.
+    const BASE_PITCH: f32 = 110.;
+    /// How many octaves the pitch rises per doubling of the population.
+    const OCTAVES_PER_DOUBLING: f32 = 0.5;
+    const MAX_OCTAVES: f32 = 3.;
+    /// Beats per second with no births, and the most there can be.
+    const MIN_TEMPO: f32 = 0.5;
+    const MAX_TEMPO: f32 = 6.;
+    /// The predation rate that is as dissonant as it gets.
+    const MAX_PREDATION_RATE: f32 = 0.5;
+    /// How fast values glide to their targets, per second.
+    const GLIDE: f32 = 2.;
+    const VOLUME: f32 = 0.2;
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate: sample_rate as f32,
+            pitch: Self::BASE_PITCH,
+            tempo: Self::MIN_TEMPO,
+            dissonance: 0.,
+            target_pitch: Self::BASE_PITCH,
+            target_tempo: Self::MIN_TEMPO,
+            target_dissonance: 0.,
+            phases: [0.; 3],
+            beat_time: 0.,
+        }
+    }
+
+    /// Set what the sound glides towards.
+    pub fn set_metrics(&mut self, metrics: &Metrics) {
+        let octaves = ((1. + metrics.population as f32).log2() * Self::OCTAVES_PER_DOUBLING).min(Self::MAX_OCTAVES);
+        self.target_pitch = Self::BASE_PITCH * 2f32.powf(octaves);
+        self.target_tempo = (Self::MIN_TEMPO + metrics.birth_rate * 4.).min(Self::MAX_TEMPO);
+        self.target_dissonance = (metrics.predation_rate / Self::MAX_PREDATION_RATE).min(1.);
+    }
+
+    /// Write the next mono samples.
+    pub fn fill(&mut self, out: &mut [i16]) {
+        let dt = 1. / self.sample_rate;
+        let glide = 1. - (-Self::GLIDE * dt).exp();
+        for sample in out {
+            self.pitch += (self.target_pitch - self.pitch) * glide;
+            self.tempo += (self.target_tempo - self.tempo) * glide;
+            self.dissonance += (self.target_dissonance - self.dissonance) * glide;
+
+            //  a fifth above, bending to a slightly detuned tritone
+            let interval = 1.5 + (2f32.sqrt() * 1.01 - 1.5) * self.dissonance;
+            let frequencies = [self.pitch, self.pitch * interval, self.pitch * 2.];
+            for (phase, frequency) in self.phases.iter_mut().zip(&frequencies) {
+                *phase = (*phase + frequency * dt).fract();
+            }
+            self.beat_time += dt;
+            if self.beat_time >= 1. / self.tempo {
+                self.beat_time = 0.;
+            }
+
+            let drone = (2. * PI * self.phases[0]).sin() + 0.6 * (2. * PI * self.phases[1]).sin();
+            let pulse = (2. * PI * self.phases[2]).sin() * (-self.beat_time * 12.).exp();
+            let value = Self::VOLUME * (drone / 1.6 * 0.6 + pulse * 0.4);
+            *sample = (value.clamp(-1., 1.) * i16::MAX as f32) as i16;
+        }
+    }
+}
+
+/// The synthesized sound played on the audio device.
+pub struct Sonification {
+    //  the stream must close before the device does
+    stream: AudioStream,
+    audio: RaylibAudio,
+    synth: Synth,
+    buffer: Vec<i16>,
+}
+
+impl Sonification {
+    pub const SAMPLE_RATE: u32 = 44100;
+    /// Samples written at a time, which is as many as the stream takes.
+    const BUFFER_LENGTH: usize = 4096;
+
+    /// Open the audio device and start playing.
+    pub fn new(thread: &RaylibThread) -> Result<Self, String> {
+        let mut audio = RaylibAudio::init_audio_device();
+        if !audio.is_audio_device_ready() {
+            return Err("the audio device is not ready".to_string());
+        }
+        let mut stream = AudioStream::init_audio_stream(thread, Self::SAMPLE_RATE, 16, 1);
+        audio.play_audio_stream(&mut stream);
+        Ok(Self {
+            stream,
+            audio,
+            synth: Synth::new(Self::SAMPLE_RATE),
+            buffer: vec![0; Self::BUFFER_LENGTH],
+        })
+    }
+
+    /// Follow the world and refill the stream if it played what it had.
+    pub fn update(&mut self, sim: &Simulation) {
+        self.synth.set_metrics(&Metrics::measure(sim));
+        if self.audio.is_audio_stream_processed(&self.stream) {
+            self.synth.fill(&mut self.buffer);
+            //  raylib takes a number of samples, not the bytes `update_audio_stream` passes
+            unsafe {
+                raylib::ffi::UpdateAudioStream(*self.stream.as_ref(), self.buffer.as_ptr() as *const _, self.buffer.len() as i32);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synth() {
+        let calm = Metrics { population: 0, birth_rate: 0., predation_rate: 0. };
+        let busy = Metrics { population: 60, birth_rate: 2., predation_rate: 1. };
+        let mut synth = Synth::new(8000);
+        synth.set_metrics(&calm);
+        let calm_pitch = synth.target_pitch;
+        synth.set_metrics(&busy);
+        assert!(synth.target_pitch > calm_pitch);
+        assert_eq!((synth.target_tempo, synth.target_dissonance), (Synth::MAX_TEMPO, 1.));
+
+        //  the sound is audible, within range and glides towards the targets
+        let mut samples = vec![0; 8000];
+        synth.fill(&mut samples);
+        assert!(samples.iter().any(|&sample| sample.abs() > 1000));
+        assert!(samples.iter().all(|&sample| (sample as f32).abs() <= Synth::VOLUME * i16::MAX as f32 + 1.));
+        assert!(synth.pitch > calm_pitch && synth.pitch < synth.target_pitch);
+    }
+}
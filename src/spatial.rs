@@ -0,0 +1,254 @@
+//! Spatial statistics of where blobs are, for studying spatial ecology.
+//!
+//! * The clustering index of Clark and Evans compares the mean
+//!   distance from each blob to its nearest neighbor with what it
+//!   would be if the blobs were spread at random: about 1 for a
+//!   random spread, below 1 for clusters, above 1 for blobs keeping
+//!   apart, up to about 2.15 for a perfect grid.
+//! * The territory of a species is the convex hull of its blobs.
+//! * A kernel density estimate shows where blobs gather, as a grid
+//!   of cells each summing a Gaussian bump around every blob.
+//!
+//! The simulation records the clustering of all blobs and the
+//! clustering and territory area of each species with its other
+//! statistics, and the territories and density can be drawn over
+//! the world, as can the light map of `light`.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::spatial;
+//! let points: Vec<_> = sim.blobs().map(|(_, blob)| blob.pos()).collect();
+//! let index = spatial::clustering_index(&points, sim.size().x * sim.size().y);
+//! let area = spatial::area(&spatial::convex_hull(&points));
+//! ```
+
+use std::collections::BTreeMap;
+
+use raylib::prelude::*;
+
+use crate::{
+    simulation::Simulation,
+    theme::Theme,
+};
+
+/// What is drawn over the world, cycled through with `O`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overlay {
+    None,
+    Territories,
+    Density,
+    /// How lit the world is, see `light`.
+    Light,
+    /// Where blobs died lately, see `memorial`.
+    Memorial,
+}
+
+impl Overlay {
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Territories,
+            Self::Territories => Self::Density,
+            Self::Density => Self::Light,
+            Self::Light => Self::Memorial,
+            Self::Memorial => Self::None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Territories => "territories",
+            Self::Density => "density",
+            Self::Light => "light",
+            Self::Memorial => "memorial",
+        }
+    }
+}
+
+/// The Clark-Evans index of how clustered points are in an area,
+/// or nothing for fewer than two points.
+pub fn clustering_index(points: &[Vector2], area: f32) -> Option<f32> {
+    if points.len() < 2 || area <= 0. { return None; }
+    //  sweep the points by x, stopping once they are farther along x than the nearest so far
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x));
+    let mut total = 0.;
+    for (i, point) in sorted.iter().enumerate() {
+        let mut nearest = f32::INFINITY;
+        for other in sorted[i + 1..].iter() {
+            if other.x - point.x >= nearest { break; }
+            nearest = nearest.min(point.distance_to(*other));
+        }
+        for other in sorted[..i].iter().rev() {
+            if point.x - other.x >= nearest { break; }
+            nearest = nearest.min(point.distance_to(*other));
+        }
+        total += nearest;
+    }
+    let observed = total / points.len() as f32;
+    let expected = 0.5 * (area / points.len() as f32).sqrt();
+    Some(observed / expected)
+}
+
+/// The smallest convex polygon around points, counterclockwise,
+/// as seen with the y axis up.
+pub fn convex_hull(points: &[Vector2]) -> Vec<Vector2> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 { return sorted; }
+    let cross = |o: Vector2, a: Vector2, b: Vector2| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+    //  Andrew's monotone chain, the lower half and then the upper
+    let mut hull: Vec<Vector2> = vec![];
+    for pass in 0..2 {
+        let start = hull.len();
+        for &point in &sorted {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], point) <= 0. {
+                hull.pop();
+            }
+            hull.push(point);
+        }
+        //  the last point starts the other half
+        hull.pop();
+        if pass == 0 {
+            sorted.reverse();
+        }
+    }
+    hull
+}
+
+/// The area of a polygon.
+pub fn area(polygon: &[Vector2]) -> f32 {
+    let n = polygon.len();
+    let twice: f32 = (0..n).map(|i| {
+        let (a, b) = (polygon[i], polygon[(i + 1) % n]);
+        a.x * b.y - b.x * a.y
+    }).sum();
+    twice.abs() / 2.
+}
+
+/// The blobs of each species, by name, leaving out blobs of none.
+fn by_species(sim: &Simulation) -> BTreeMap<&str, (Vec<Vector2>, Color)> {
+    let mut ret: BTreeMap<&str, (Vec<Vector2>, Color)> = BTreeMap::new();
+    for (_, blob) in sim.blobs() {
+        if let Some(species) = &blob.species {
+            ret.entry(species).or_insert_with(|| (vec![], blob.color)).0.push(blob.pos());
+        }
+    }
+    ret
+}
+
+/// The statistics sampled with the others, by name.
+pub fn samples(sim: &Simulation) -> Vec<(String, f32)> {
+    let area = sim.size().x * sim.size().y;
+    let points: Vec<_> = sim.blobs().map(|(_, blob)| blob.pos()).collect();
+    let mut ret = vec![];
+    if let Some(index) = clustering_index(&points, area) {
+        ret.push(("clustering".to_string(), index));
+    }
+    for (species, (points, _)) in by_species(sim) {
+        if let Some(index) = clustering_index(&points, area) {
+            ret.push((format!("clustering {}", species), index));
+        }
+        ret.push((format!("territory {}", species), self::area(&convex_hull(&points))));
+    }
+    ret
+}
+
+/// A grid of how densely blobs gather.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Density {
+    /// The side of a cell.
+    pub cell: f32,
+    pub columns: usize,
+    pub rows: usize,
+    values: Vec<f32>,
+}
+
+impl Density {
+    /// How many cells the side of a world is divided into, at most.
+    pub const RESOLUTION: usize = 64;
+
+    /// Estimate the density of points in a world, with Gaussian
+    /// kernels of a standard deviation of `bandwidth`.
+    pub fn estimate(points: &[Vector2], size: Vector2, bandwidth: f32) -> Self {
+        let cell = (size.x.max(size.y) / Self::RESOLUTION as f32).max(1.);
+        let columns = (size.x / cell).ceil() as usize;
+        let rows = (size.y / cell).ceil() as usize;
+        let mut values = vec![0.; columns * rows];
+        //  bumps are cut off at three deviations
+        let reach = (3. * bandwidth / cell).ceil() as isize;
+        for point in points {
+            let (column, row) = ((point.x / cell) as isize, (point.y / cell) as isize);
+            for y in (row - reach).max(0)..(row + reach + 1).min(rows as isize) {
+                for x in (column - reach).max(0)..(column + reach + 1).min(columns as isize) {
+                    let center = Vector2::new((x as f32 + 0.5) * cell, (y as f32 + 0.5) * cell);
+                    let d2 = (center - *point).length_sqr();
+                    values[y as usize * columns + x as usize] += (-d2 / (2. * bandwidth * bandwidth)).exp();
+                }
+            }
+        }
+        Self { cell, columns, rows, values }
+    }
+
+    pub fn value(&self, column: usize, row: usize) -> f32 { self.values[row * self.columns + column] }
+
+    pub fn max(&self) -> f32 { self.values.iter().cloned().fold(0., f32::max) }
+}
+
+/// Draw the territory of each species, in world coordinates.
+pub fn draw_territories<D: RaylibDraw>(draw: &mut D, sim: &Simulation, theme: &Theme, zoom: f32) {
+    for (_, (points, color)) in by_species(sim) {
+        let hull = convex_hull(&points);
+        let color = theme.contrast(color);
+        for i in 0..hull.len() {
+            draw.draw_line_ex(hull[i], hull[(i + 1) % hull.len()], 2. / zoom, color);
+        }
+    }
+}
+
+/// Draw the density of blobs as a heatmap, in world coordinates.
+pub fn draw_density<D: RaylibDraw>(draw: &mut D, sim: &Simulation, theme: &Theme) {
+    let points: Vec<_> = sim.blobs().map(|(_, blob)| blob.pos()).collect();
+    let density = Density::estimate(&points, sim.size(), 40.);
+    let max = density.max();
+    if max <= 0. { return; }
+    for row in 0..density.rows {
+        for column in 0..density.columns {
+            let t = density.value(column, row) / max;
+            if t < 0.05 { continue; }
+            let pos = Vector2::new(column as f32, row as f32) * density.cell;
+            draw.draw_rectangle_v(pos, Vector2::new(density.cell, density.cell), theme.gradient.sample(t).fade(0.6 * t));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial() {
+        let grid: Vec<_> = (0..100).map(|i| Vector2::new((i % 10) as f32 * 10. + 5., (i / 10) as f32 * 10. + 5.)).collect();
+        let spread = clustering_index(&grid, 100. * 100.).unwrap();
+        assert!((spread - 2.).abs() < 1e-3, "{}", spread);
+        let clumped: Vec<_> = grid.iter().map(|&pos| pos * 0.1).collect();
+        assert!(clustering_index(&clumped, 100. * 100.).unwrap() < 0.5);
+        assert_eq!(clustering_index(&grid[..1], 100.), None);
+
+        let hull = convex_hull(&grid);
+        assert_eq!(hull, vec![Vector2::new(5., 5.), Vector2::new(95., 5.), Vector2::new(95., 95.), Vector2::new(5., 95.)]);
+        assert_eq!(area(&hull), 90. * 90.);
+
+        let density = Density::estimate(&[Vector2::new(50., 50.)], Vector2::new(100., 100.), 5.);
+        assert_eq!((density.columns, density.rows), (64, 64));
+        //  the bump peaks at the point and fades away from it
+        assert!(density.value(31, 31) > 0.9 && density.value(31, 31) == density.max());
+        assert!(density.value(31, 40) < density.value(31, 33));
+        assert_eq!(density.value(0, 0), 0.);
+    }
+}
@@ -0,0 +1,253 @@
+//! Where new blobs and food are put, behind the `Spawner` trait so a
+//! world can be shaped without changing the simulation.
+//!
+//! A spawner picks a place for a blob, of a species if it has one, or
+//! for food, drawing from the generator it is given. The built-in
+//! ones are chosen for blobs and food apart, with `--blob-spawner` and
+//! `--food-spawner`:
+//!
+//! * `uniform` anywhere in the world, as always
+//! * `edge` near one of the walls
+//! * `kin` near a blob of the same species, or for food near other food
+//! * `safe` the farthest of a few places from the blobs that hunt
+//! * `nests:x,y;x,y` around one of the given points
+//!
+//! Food is only put where the zones of the scenario let it grow, see
+//! `zones` and `place_food`, and food added on a fertility map is then
+//! kept or not as the map says.
+//! Spawners only place the blobs and food added over time and at the
+//! start; those added by hand, by the chat or by a lockstep peer are
+//! put anywhere, as before.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::spawning::{self, Entity};
+//! let spawner = spawning::parse("nests:100,100;500,300")?;
+//! let pos = spawning::place(spawner.as_ref(), &mut sim, Entity::Food);
+//! sim.insert_food(pos);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fmt, mem, sync::Arc};
+
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+use raylib::prelude::*;
+
+use crate::{simulation::Simulation, zones::{self, Zone}};
+
+/// What is to be placed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entity<'a> {
+    /// A blob, of a species if it has one.
+    Blob(Option<&'a str>),
+    Food,
+}
+
+/// A way of choosing where things are added to a world.
+pub trait Spawner: fmt::Debug + Send + Sync {
+    /// A place in the world for something new.
+    fn place(&self, sim: &Simulation, entity: Entity, rng: &mut dyn RngCore) -> Vector2;
+}
+
+/// Anywhere in the world, each place as likely as another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uniform;
+
+impl Spawner for Uniform {
+    fn place(&self, sim: &Simulation, _: Entity, rng: &mut dyn RngCore) -> Vector2 {
+        Vector2::new(rng.gen(), rng.gen()) * sim.size()
+    }
+}
+
+/// Along the walls, no farther from one than a margin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Edge {
+    pub margin: f32,
+}
+
+impl Spawner for Edge {
+    fn place(&self, sim: &Simulation, _: Entity, rng: &mut dyn RngCore) -> Vector2 {
+        let size = sim.size();
+        let (along, inward): (f32, f32) = (rng.gen(), rng.gen::<f32>() * self.margin);
+        match rng.gen_range(0..4) {
+            0 => Vector2::new(along * size.x, inward.min(size.y)),
+            1 => Vector2::new(along * size.x, (size.y - inward).max(0.)),
+            2 => Vector2::new(inward.min(size.x), along * size.y),
+            _ => Vector2::new((size.x - inward).max(0.), along * size.y),
+        }
+    }
+}
+
+/// Within a spread of a blob of the same species, or of food for food,
+/// and anywhere while there is none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NearKin {
+    pub spread: f32,
+}
+
+impl Spawner for NearKin {
+    fn place(&self, sim: &Simulation, entity: Entity, rng: &mut dyn RngCore) -> Vector2 {
+        let kin: Vec<Vector2> = match entity {
+            Entity::Blob(species) => sim.blobs().filter(|(_, blob)| blob.species.as_deref() == species).map(|(_, blob)| blob.pos()).collect(),
+            Entity::Food => sim.foods().map(|(_, food)| food.pos()).collect(),
+        };
+        if kin.is_empty() {
+            return Uniform.place(sim, entity, rng);
+        }
+        let center = kin[rng.gen_range(0..kin.len())];
+        around(center, self.spread, sim.size(), rng)
+    }
+}
+
+/// The farthest of a few uniform places from the nearest blob that
+/// eats blobs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AwayFromPredators {
+    pub candidates: usize,
+}
+
+impl Spawner for AwayFromPredators {
+    fn place(&self, sim: &Simulation, entity: Entity, rng: &mut dyn RngCore) -> Vector2 {
+        let predators: Vec<Vector2> = sim.blobs().filter(|(_, blob)| blob.diet.eats_blobs()).map(|(_, blob)| blob.pos()).collect();
+        let safety = |pos: Vector2| predators.iter().map(|&predator| (predator - pos).length_sqr()).fold(f32::INFINITY, f32::min);
+        let mut best = Uniform.place(sim, entity, rng);
+        if predators.is_empty() {
+            return best;
+        }
+        for _ in 1..self.candidates {
+            let pos = Uniform.place(sim, entity, rng);
+            if safety(pos) > safety(best) {
+                best = pos;
+            }
+        }
+        best
+    }
+}
+
+/// Within a radius of one of a few points.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Nests {
+    pub nests: Vec<Vector2>,
+    pub radius: f32,
+}
+
+impl Spawner for Nests {
+    fn place(&self, sim: &Simulation, _: Entity, rng: &mut dyn RngCore) -> Vector2 {
+        let nest = self.nests[rng.gen_range(0..self.nests.len())];
+        around(nest, self.radius, sim.size(), rng)
+    }
+}
+
+/// How far from the walls `edge` places things.
+pub const EDGE_MARGIN: f32 = 40.;
+/// How far from its kin `kin` places things.
+pub const KIN_SPREAD: f32 = 60.;
+/// How many places `safe` chooses from.
+pub const SAFE_CANDIDATES: usize = 8;
+/// How far from a nest `nests` places things.
+pub const NEST_RADIUS: f32 = 50.;
+
+/// A point in a disc around a center, moved into the world.
+fn around(center: Vector2, radius: f32, size: Vector2, rng: &mut dyn RngCore) -> Vector2 {
+    let angle = rng.gen_range(0. ..std::f32::consts::TAU);
+    //  the square root spreads the points evenly over the disc
+    let distance = radius * rng.gen::<f32>().sqrt();
+    let pos = center + Vector2::new(angle.cos(), angle.sin()) * distance;
+    Vector2::new(pos.x.clamp(0., size.x), pos.y.clamp(0., size.y))
+}
+
+/// A built-in spawner by its name, see the module.
+pub fn parse(text: &str) -> Result<Arc<dyn Spawner>, String> {
+    let spawner: Arc<dyn Spawner> = match text {
+        "uniform" => Arc::new(Uniform),
+        "edge" => Arc::new(Edge { margin: EDGE_MARGIN }),
+        "kin" => Arc::new(NearKin { spread: KIN_SPREAD }),
+        "safe" => Arc::new(AwayFromPredators { candidates: SAFE_CANDIDATES }),
+        _ => {
+            let points = text.strip_prefix("nests:")
+                .ok_or_else(|| format!("unknown spawner `{}`, expected uniform, edge, kin, safe or nests:x,y;x,y", text))?;
+            let nests = points.split(';').map(|point| {
+                let (x, y) = point.split_once(',').ok_or_else(|| format!("expected a nest as `x,y`, got `{}`", point))?;
+                let number = |value: &str| value.trim().parse().map_err(|_| format!("invalid coordinate `{}`", value));
+                Ok(Vector2::new(number(x)?, number(y)?))
+            }).collect::<Result<Vec<_>, String>>()?;
+            Arc::new(Nests { nests, radius: NEST_RADIUS })
+        },
+    };
+    Ok(spawner)
+}
+
+/// Place something with the world's own generator, so that worlds
+/// with the same seed place the same.
+pub fn place(spawner: &dyn Spawner, sim: &mut Simulation, entity: Entity) -> Vector2 {
+    //  the generator is lent out while the spawner looks at the world
+    let mut rng = mem::replace(sim.rng(), StdRng::seed_from_u64(0));
+    let pos = spawner.place(sim, entity, &mut rng);
+    *sim.rng() = rng;
+    pos
+}
+
+/// How many places are drawn for food before giving up on zones that allow none.
+pub const ATTEMPTS: usize = 16;
+
+/// Place food where the zones let it grow, drawing places until one
+/// is allowed, or none if none was. Without zones it draws what
+/// `place` does.
+pub fn place_food(spawner: &dyn Spawner, sim: &mut Simulation, zones: &[Zone]) -> Option<Vector2> {
+    for _ in 0..ATTEMPTS {
+        let pos = place(spawner, sim, Entity::Food);
+        if zones::allows(zones, pos, sim.in_water(pos)) {
+            return Some(pos);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{simulation::{BlobConfig, SimulationConfig}, species::Diet};
+
+    #[test]
+    fn test_spawning() {
+        let mut sim = Simulation::new(SimulationConfig { seed: 5, ..SimulationConfig::new(Vector2::new(400., 300.)) });
+        let kin = Vector2::new(100., 100.);
+        sim.insert_blob(BlobConfig { species: Some("Grazer".to_string()), ..BlobConfig::new(kin) });
+        let predator = Vector2::new(300., 200.);
+        sim.insert_blob(BlobConfig { diet: Diet::Carnivore, ..BlobConfig::new(predator) });
+
+        let inside = |pos: Vector2| (0. ..=400.).contains(&pos.x) && (0. ..=300.).contains(&pos.y);
+        for _ in 0..50 {
+            let pos = place(parse("edge").unwrap().as_ref(), &mut sim, Entity::Food);
+            assert!(inside(pos) && [pos.x, pos.y, 400. - pos.x, 300. - pos.y].iter().any(|&d| d <= EDGE_MARGIN), "{:?}", pos);
+            let pos = place(parse("kin").unwrap().as_ref(), &mut sim, Entity::Blob(Some("Grazer")));
+            assert!(inside(pos) && (pos - kin).length() <= KIN_SPREAD + 0.01, "{:?}", pos);
+            let pos = place(parse("nests:50,250").unwrap().as_ref(), &mut sim, Entity::Food);
+            assert!(inside(pos) && (pos - Vector2::new(50., 250.)).length() <= NEST_RADIUS + 0.01, "{:?}", pos);
+        }
+        //  away from the hunter, on average
+        let mean = (0..50).map(|_| (place(parse("safe").unwrap().as_ref(), &mut sim, Entity::Food) - predator).length()).sum::<f32>() / 50.;
+        let uniform = (0..50).map(|_| (place(&Uniform, &mut sim, Entity::Food) - predator).length()).sum::<f32>() / 50.;
+        assert!(mean > uniform, "{} {}", mean, uniform);
+
+        //  uniform placing draws what the world drew before spawners
+        let mut a = Simulation::new(SimulationConfig { seed: 9, ..SimulationConfig::new(Vector2::new(400., 300.)) });
+        let mut b = Simulation::new(SimulationConfig { seed: 9, ..SimulationConfig::new(Vector2::new(400., 300.)) });
+        let size = b.size();
+        assert_eq!(place(&Uniform, &mut a, Entity::Food), Vector2::new(b.rng().gen(), b.rng().gen()) * size);
+        assert!(parse("nests:1,2;x").is_err() && parse("everywhere").is_err());
+
+        //  no spawner puts food in a desert, and a world of desert gets none
+        let desert = [Zone { rect: Rectangle::new(0., 0., 200., 300.), rule: zones::Rule::Desert }];
+        for spawner in ["uniform", "edge", "kin", "nests:50,250;350,150"] {
+            let pos = place_food(parse(spawner).unwrap().as_ref(), &mut sim, &desert);
+            assert!(pos.is_some_and(|pos| pos.x >= 200.), "{} {:?}", spawner, pos);
+        }
+        let everywhere = [Zone { rect: Rectangle::new(0., 0., 400., 300.), rule: zones::Rule::Water }];
+        assert_eq!(place_food(&Uniform, &mut sim, &everywhere), None);
+    }
+}
@@ -0,0 +1,575 @@
+//! Species archetypes loaded from asset files.
+//!
+//! A species describes the range every trait of its blobs is
+//! sampled from, together with its diet, color scheme, brain, aura
+//! and how often it is spawned relative to other species. Blobs of a
+//! species with an aura, see `aura`, draw its strength from the trait
+//! `aura_strength`, which is otherwise not drawn.
+//!
+//! The brain is named, or given as a table of its kind and
+//! parameters, see `BrainKind`:
+//!
+//! ```toml
+//! [brain]
+//! kind = "state_machine"
+//! hungry = 0.4
+//! fear = 50
+//! ```
+//!
+//! # Example
+//!
+//! ```toml
+//! name = "Grazer"
+//! spawn_weight = 3
+//! diet = "herbivore"
+//! brain = "color_attraction"
+//!
+//! [traits]
+//! radius = [8, 14]
+//! speed = [40, 90]
+//!
+//! [colors]
+//! body = [[20, 140, 20], [90, 230, 90]]
+//! favorite = [[0, 200, 0], [60, 255, 60]]
+//! ```
+
+use std::{
+    fmt,
+    fs,
+    io,
+    path::{Path, PathBuf},
+};
+
+use rand::Rng;
+
+use raylib::prelude::*;
+
+use crate::{
+    keyed_set::Key,
+    simulation::{Blob, BlobConfig, Simulation},
+    aura::Aura,
+    toml::{self, Value},
+};
+
+/// What a blob gains nourishment from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diet {
+    /// Eats food only.
+    Herbivore,
+    /// Feeds on the blobs it kills only.
+    Carnivore,
+    /// Eats food and the blobs it kills.
+    Omnivore,
+}
+
+impl Diet {
+    pub fn eats_food(&self) -> bool { *self != Self::Carnivore }
+
+    pub fn eats_blobs(&self) -> bool { *self != Self::Herbivore }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Herbivore => "herbivore",
+            Self::Carnivore => "carnivore",
+            Self::Omnivore => "omnivore",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "herbivore" => Some(Self::Herbivore),
+            "carnivore" => Some(Self::Carnivore),
+            "omnivore" => Some(Self::Omnivore),
+            _ => None,
+        }
+    }
+}
+
+/// The decision making a species' blobs use.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BrainKind {
+    /// Steer towards colors similar to the favorite color and
+    /// away from different ones.
+    ColorAttraction,
+    /// Head for the nearest thing it eats, whatever else it sees.
+    Greedy,
+    /// Wander until hungry, then forage until half as hungry, and
+    /// flee blobs it does not eat that come near, see `Mood`.
+    StateMachine {
+        /// The part of its most hunger past which it forages.
+        hungry: f32,
+        /// How near a blob it does not eat it flees from.
+        fear: f32,
+    },
+}
+
+impl BrainKind {
+    pub const NAMES: [&'static str; 3] = ["color_attraction", "greedy", "state_machine"];
+    pub const STATE_MACHINE: Self = Self::StateMachine { hungry: 0.5, fear: 40. };
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ColorAttraction => "color_attraction",
+            Self::Greedy => "greedy",
+            Self::StateMachine { .. } => "state_machine",
+        }
+    }
+
+    /// The brain of a kind, with default parameters.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "color_attraction" => Some(Self::ColorAttraction),
+            "greedy" => Some(Self::Greedy),
+            "state_machine" => Some(Self::STATE_MACHINE),
+            _ => None,
+        }
+    }
+
+    /// The parameters of the brain, by name.
+    pub fn parameters(&self) -> Vec<(&'static str, f32)> {
+        match *self {
+            Self::StateMachine { hungry, fear } => vec![("hungry", hungry), ("fear", fear)],
+            _ => vec![],
+        }
+    }
+
+    /// A brain given as a name, or as a table of its kind and parameters.
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        let unknown = |name: &str| format!("unknown brain `{}`, expected one of {}", name, Self::NAMES.join(", "));
+        let table = match value {
+            Value::String(name) => return Self::from_name(name).ok_or_else(|| unknown(name)),
+            Value::Table(table) => table,
+            _ => return Err("`brain` must be a name or a table".to_string()),
+        };
+        let kind = table.get("kind").and_then(Value::as_str).ok_or("`brain.kind` must be a name")?;
+        let mut brain = Self::from_name(kind).ok_or_else(|| unknown(kind))?;
+        let number = |name: &str, default: f32, valid: fn(f32) -> bool| match table.get(name) {
+            None => Ok(default),
+            Some(value) => value.as_f32().filter(|&x| valid(x)).ok_or_else(|| format!("invalid `brain.{}`", name)),
+        };
+        if let Self::StateMachine { hungry, fear } = &mut brain {
+            *hungry = number("hungry", *hungry, |x| (0. ..=1.).contains(&x))?;
+            *fear = number("fear", *fear, |x| x >= 0.)?;
+        }
+        let names: Vec<_> = brain.parameters().iter().map(|&(name, _)| name).collect();
+        if let Some(key) = table.keys().find(|&key| key != "kind" && !names.contains(&key.as_str())) {
+            return Err(format!("unknown parameter `brain.{}` of a {} brain", key, kind));
+        }
+        Ok(brain)
+    }
+
+    /// The brain as TOML, a `brain` key when it has no parameters and
+    /// a `[brain]` table otherwise, which goes after all other keys.
+    pub fn to_toml(self) -> String {
+        let parameters = self.parameters();
+        if parameters.is_empty() {
+            return format!("brain = \"{}\"\n", self.name());
+        }
+        let mut ret = format!("\n[brain]\nkind = \"{}\"\n", self.name());
+        for (name, value) in parameters {
+            ret += &format!("{} = {}\n", name, value);
+        }
+        ret
+    }
+
+    /// The brain as one word, see `from_id`.
+    pub fn id(&self) -> String {
+        std::iter::once(self.name().to_string())
+            .chain(self.parameters().into_iter().map(|(_, value)| value.to_string()))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// A brain written by `id`.
+    pub fn from_id(id: &str) -> Option<Self> {
+        let mut words = id.split(',');
+        let brain = Self::from_name(words.next()?)?;
+        let numbers: Vec<f32> = words.map(str::parse).collect::<Result<_, _>>().ok()?;
+        match (brain, &numbers[..]) {
+            (Self::StateMachine { .. }, &[hungry, fear]) => Some(Self::StateMachine { hungry, fear }),
+            (brain, []) => Some(brain),
+            _ => None,
+        }
+    }
+}
+
+/// An inclusive range a trait is sampled uniformly from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Range {
+    pub const fn new(min: f32, max: f32) -> Self { Self { min, max } }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f32 {
+        self.min + (self.max - self.min) * rng.gen::<f32>()
+    }
+}
+
+/// The ranges of a species' numeric traits.
+///
+/// The default ranges are those of a blob with uniformly random
+/// traits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraitRanges {
+    pub radius: Range,
+    pub speed: Range,
+    pub rotation_speed: Range,
+    pub pov: Range,
+    pub sight_depth: Range,
+    pub color_attraction: Range,
+    pub color_repulsion: Range,
+    pub max_hunger: Range,
+    pub attack: Range,
+    pub defence: Range,
+    pub hunger_reduction: Range,
+    pub hunger_division: Range,
+    pub aura_strength: Range,
+}
+
+impl Default for TraitRanges {
+    fn default() -> Self {
+        Self {
+            radius: Range::new(0., 20.),
+            speed: Range::new(0., 120.),
+            rotation_speed: Range::new(0., 5.),
+            pov: Range::new(0., 180.),
+            sight_depth: Range::new(0., 170.),
+            color_attraction: Range::new(0., 1.),
+            color_repulsion: Range::new(0., 1.),
+            max_hunger: Range::new(0., 25.),
+            attack: Range::new(0., 1.),
+            defence: Range::new(0., 2.),
+            hunger_reduction: Range::new(0., 0.5),
+            hunger_division: Range::new(0., 1.),
+            aura_strength: Range::new(0., 1.),
+        }
+    }
+}
+
+impl TraitRanges {
+    fn fields_mut(&mut self) -> [(&'static str, &mut Range); 13] {
+        [
+            ("radius", &mut self.radius),
+            ("speed", &mut self.speed),
+            ("rotation_speed", &mut self.rotation_speed),
+            ("pov", &mut self.pov),
+            ("sight_depth", &mut self.sight_depth),
+            ("color_attraction", &mut self.color_attraction),
+            ("color_repulsion", &mut self.color_repulsion),
+            ("max_hunger", &mut self.max_hunger),
+            ("attack", &mut self.attack),
+            ("defence", &mut self.defence),
+            ("hunger_reduction", &mut self.hunger_reduction),
+            ("hunger_division", &mut self.hunger_division),
+            ("aura_strength", &mut self.aura_strength),
+        ]
+    }
+}
+
+/// A range of colors, sampled per channel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorRange {
+    pub min: Color,
+    pub max: Color,
+}
+
+impl ColorRange {
+    pub const ANY: Self = Self {
+        min: Color { r: 0, g: 0, b: 0, a: 255 },
+        max: Color { r: 255, g: 255, b: 255, a: 255 },
+    };
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Color {
+        let mut channel = |min: u8, max: u8| rng.gen_range(min.min(max)..=max.max(min));
+        Color::new(
+            channel(self.min.r, self.max.r),
+            channel(self.min.g, self.max.g),
+            channel(self.min.b, self.max.b),
+            255,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Species {
+    pub name: String,
+    /// How likely the species is to be spawned, relative to the others.
+    pub spawn_weight: f32,
+    pub diet: Diet,
+    pub brain: BrainKind,
+    pub aura: Aura,
+    pub traits: TraitRanges,
+    pub body_color: ColorRange,
+    pub favorite_color: ColorRange,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::Error),
+    Invalid(PathBuf, String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            Self::Invalid(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl Species {
+    /// The directory species definitions are loaded from.
+    pub const ASSETS_DIR: &'static str = "assets/species";
+
+    /// Build a species from a parsed definition file.
+    pub fn from_table(table: &toml::Table) -> Result<Self, String> {
+        let name = table.get("name")
+            .and_then(Value::as_str)
+            .ok_or("missing string `name`")?
+            .to_string();
+        let spawn_weight = match table.get("spawn_weight") {
+            None => 1.,
+            Some(value) => value.as_f32()
+                .filter(|&w| w >= 0.)
+                .ok_or("`spawn_weight` must be a non-negative number")?,
+        };
+        let diet = match table.get("diet") {
+            None => Diet::Herbivore,
+            Some(value) => value.as_str()
+                .and_then(Diet::from_name)
+                .ok_or("`diet` must be \"herbivore\", \"carnivore\" or \"omnivore\"")?,
+        };
+        let brain = match table.get("brain") {
+            None => BrainKind::ColorAttraction,
+            Some(value) => BrainKind::from_value(value)?,
+        };
+        let aura = match table.get("aura") {
+            None => Aura::None,
+            Some(value) => value.as_str()
+                .and_then(Aura::from_name)
+                .ok_or("`aura` must be \"none\", \"slow\", \"heal\" or \"repel\"")?,
+        };
+
+        let mut traits = TraitRanges::default();
+        if let Some(table) = table.get("traits") {
+            let table = table.as_table().ok_or("`traits` must be a table")?;
+            for (key, range) in traits.fields_mut().iter_mut() {
+                if let Some(value) = table.get(*key) {
+                    **range = parse_range(value).ok_or_else(|| format!("`traits.{}` must be [min, max]", key))?;
+                    let &(_, least, most) = Blob::GENE_BOUNDS.iter().find(|(gene, _, _)| gene == key).unwrap();
+                    if range.min < least || range.max > most {
+                        return Err(format!("`traits.{}` must be within [{}, {}]", key, least, most));
+                    }
+                }
+            }
+            for key in table.keys() {
+                if !traits.fields_mut().iter().any(|(name, _)| name == key) {
+                    return Err(format!("unknown trait `{}`", key));
+                }
+            }
+        }
+
+        let colors = table.get("colors");
+        let color_range = |key: &str| match colors.and_then(|colors| colors.get(key)) {
+            None => Ok(ColorRange::ANY),
+            Some(value) => parse_color_range(value)
+                .ok_or_else(|| format!("`colors.{}` must be [[r, g, b], [r, g, b]]", key)),
+        };
+
+        Ok(Self {
+            name,
+            spawn_weight,
+            diet,
+            brain,
+            aura,
+            traits,
+            body_color: color_range("body")?,
+            favorite_color: color_range("favorite")?,
+        })
+    }
+
+    /// Load a species from a definition file.
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LoadError::Io(path.to_path_buf(), e))?;
+        let table = toml::parse(&source).map_err(|e| LoadError::Parse(path.to_path_buf(), e))?;
+        Self::from_table(&table).map_err(|e| LoadError::Invalid(path.to_path_buf(), e))
+    }
+
+    /// Load every `.toml` species definition in a directory, sorted by file name.
+    ///
+    /// A missing directory has no species.
+    pub fn load_dir<P: AsRef<Path> + ?Sized>(dir: &P) -> Result<Vec<Self>, LoadError> {
+        let dir = dir.as_ref();
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(LoadError::Io(dir.to_path_buf(), e)),
+        };
+        let mut paths = entries
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()
+            .map_err(|e| LoadError::Io(dir.to_path_buf(), e))?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == "toml"));
+        paths.sort();
+        paths.iter().map(Self::load).collect()
+    }
+
+    /// Put a blob of this species with sampled traits in the simulation.
+    pub fn spawn<R: Rng + ?Sized>(&self, sim: &mut Simulation, pos: Vector2, rng: &mut R) -> Key<Blob> {
+        let t = &self.traits;
+        //  fields are drawn in the order they are written
+        let config = BlobConfig {
+            species: Some(self.name.clone()),
+            radius: t.radius.sample(rng),
+            color: self.body_color.sample(rng),
+            speed: t.speed.sample(rng),
+            rotation_speed: t.rotation_speed.sample(rng),
+            pov: t.pov.sample(rng),
+            sight_depth: t.sight_depth.sample(rng),
+            favorite_color: self.favorite_color.sample(rng),
+            color_attraction: t.color_attraction.sample(rng),
+            color_repulsion: t.color_repulsion.sample(rng),
+            max_hunger: t.max_hunger.sample(rng),
+            attack: t.attack.sample(rng),
+            defence: t.defence.sample(rng),
+            hunger_reduction: t.hunger_reduction.sample(rng),
+            hunger_division: t.hunger_division.sample(rng),
+            diet: self.diet,
+            brain: self.brain,
+            aura: self.aura,
+            ..BlobConfig::new(pos)
+        };
+        //  drawn last, so that species without an aura spawn the blobs they did before
+        let aura_strength = if self.aura == Aura::None { 0. } else { t.aura_strength.sample(rng) };
+        sim.insert_blob(BlobConfig { aura_strength, ..config })
+    }
+}
+
+/// Pick a species at random, in proportion to the spawn weights.
+pub fn choose<'a, R: Rng + ?Sized>(species: &'a [Species], rng: &mut R) -> Option<&'a Species> {
+    let total: f32 = species.iter().map(|s| s.spawn_weight).sum();
+    if total <= 0. { return None; }
+    let mut x = rng.gen::<f32>() * total;
+    for s in species {
+        if x < s.spawn_weight { return Some(s); }
+        x -= s.spawn_weight;
+    }
+    species.iter().rev().find(|s| s.spawn_weight > 0.)
+}
+
+fn parse_range(value: &Value) -> Option<Range> {
+    match value.as_array()?.as_slice() {
+        [min, max] => {
+            let (min, max) = (min.as_f32()?, max.as_f32()?);
+            if min <= max { Some(Range::new(min, max)) } else { None }
+        },
+        _ => None,
+    }
+}
+
+fn parse_color(value: &Value) -> Option<Color> {
+    let channel = |v: &Value| v.as_f64().filter(|&c| (0. ..=255.).contains(&c)).map(|c| c as u8);
+    match value.as_array()?.as_slice() {
+        [r, g, b] => Some(Color::new(channel(r)?, channel(g)?, channel(b)?, 255)),
+        _ => None,
+    }
+}
+
+fn parse_color_range(value: &Value) -> Option<ColorRange> {
+    match value.as_array()?.as_slice() {
+        [min, max] => Some(ColorRange { min: parse_color(min)?, max: parse_color(max)? }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_table() {
+        let table = toml::parse(r#"
+            name = "Hunter"
+            diet = "carnivore"
+            [traits]
+            speed = [80, 120]
+            [colors]
+            body = [[150, 20, 20], [230, 70, 60]]
+        "#).unwrap();
+        let species = Species::from_table(&table).unwrap();
+
+        assert_eq!(species.name, "Hunter");
+        assert_eq!(species.spawn_weight, 1.);
+        assert_eq!(species.diet, Diet::Carnivore);
+        assert_eq!(species.traits.speed, Range::new(80., 120.));
+        assert_eq!(species.traits.radius, TraitRanges::default().radius);
+        assert_eq!(species.body_color.min, Color::new(150, 20, 20, 255));
+
+        let bad = toml::parse("name = \"X\"\n[traits]\nwings = [1, 2]").unwrap();
+        assert!(Species::from_table(&bad).is_err());
+        let bad = toml::parse("name = \"X\"\n[traits]\npov = [90, 400]").unwrap();
+        assert!(Species::from_table(&bad).unwrap_err().starts_with("`traits.pov` must be within"));
+    }
+
+    #[test]
+    fn test_brains() {
+        let brain = |source: &str| Species::from_table(&toml::parse(&format!("name = \"X\"\n{}", source)).unwrap()).map(|species| species.brain);
+        assert_eq!(brain(""), Ok(BrainKind::ColorAttraction));
+        assert_eq!(brain("brain = \"greedy\""), Ok(BrainKind::Greedy));
+        assert_eq!(brain("[brain]\nkind = \"state_machine\"\nfear = 60"), Ok(BrainKind::StateMachine { hungry: 0.5, fear: 60. }));
+        assert!(brain("brain = \"neural\"").unwrap_err().contains("greedy"));
+        assert!(brain("[brain]\nkind = \"greedy\"\nfear = 60").unwrap_err().contains("brain.fear"));
+        assert!(brain("[brain]\nkind = \"state_machine\"\nhungry = 2").is_err());
+
+        for brain in [BrainKind::ColorAttraction, BrainKind::Greedy, BrainKind::StateMachine { hungry: 0.25, fear: 30. }] {
+            assert_eq!(BrainKind::from_id(&brain.id()), Some(brain));
+            let table = toml::parse(&brain.to_toml()).unwrap();
+            assert_eq!(BrainKind::from_value(&table["brain"]), Ok(brain));
+        }
+    }
+
+    /// Mutate the bundled definitions at random and load them, which
+    /// must fail with an error rather than a panic, since mods come
+    /// from anywhere.
+    #[test]
+    fn test_fuzz() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let corpus = [
+            &include_bytes!("../assets/species/grazer.toml")[..],
+            &include_bytes!("../assets/species/hunter.toml")[..],
+            &include_bytes!("../assets/species/scavenger.toml")[..],
+        ];
+        let special = b"[]=\"\\#.,\n-+e0123456789 \t";
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..3000 {
+            let mut bytes = corpus[rng.gen_range(0..corpus.len())].to_vec();
+            for _ in 0..rng.gen_range(1..8) {
+                let i = rng.gen_range(0..=bytes.len());
+                match rng.gen_range(0..5) {
+                    0 => bytes.insert(i, special[rng.gen_range(0..special.len())]),
+                    1 => bytes.insert(i, rng.gen()),
+                    2 if i < bytes.len() => { bytes.remove(i); },
+                    3 => bytes.truncate(i),
+                    _ => {
+                        let j = rng.gen_range(i..=bytes.len());
+                        let copy = bytes[i..j].to_vec();
+                        bytes.splice(i..i, copy);
+                    },
+                }
+            }
+            if let Ok(table) = toml::parse(&String::from_utf8_lossy(&bytes)) {
+                let _ = Species::from_table(&table);
+            }
+        }
+    }
+}
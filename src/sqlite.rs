@@ -0,0 +1,313 @@
+//! Statistics of headless runs in an SQLite database, for querying
+//! long experiments with SQL.
+//!
+//! `--stats-sqlite <path>` adds every world of a headless run to the
+//! database at a path as a run of its own, creating the database if
+//! there is none. The database is written through the `sqlite3`
+//! command-line program, which must be installed, in a single
+//! transaction when the run ends; the run checks for it before it
+//! starts, and so does `blobs doctor`. Its tables are:
+//!
+//! * `runs`: a row for each run, with its seed, world label, mutators,
+//!   parameters, ticks, when it ended and the version of the build, as
+//!   `runs::VERSION`
+//! * `ticks`: the history of every statistic, by run, series and tick,
+//!   older samples downsampled into the mean of each point of the
+//!   tiers `stats` keeps them in, at its first tick
+//! * `species`: the population and mean traits of each species every
+//!   `Simulation::STATS_PERIOD` ticks, blobs of no species under NULL
+//! * `events`: births, kills, deaths of hunger and quarantines, the
+//!   latest `Telemetry::EVENT_CAPACITY` of a run
+//!
+//! ```sql
+//! SELECT runs.seed, max(value) FROM ticks JOIN runs ON runs.id = run
+//! WHERE series = 'population' GROUP BY run;
+//! ```
+//!
+//! The schema is versioned with SQLite's `user_version`, and older
+//! databases are brought up to date by the `MIGRATIONS` they lack
+//! before a run is added. A database of a newer schema than this
+//! build knows is left alone.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::sqlite::{self, Telemetry};
+//! let mut telemetry = Telemetry::new();
+//! //  after every tick
+//! telemetry.collect(&sim);
+//! sqlite::write("runs.db", &[(&sim, "world", &telemetry)])?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    io::{self, Write},
+    process::{Command, Stdio},
+    time,
+};
+
+use crate::{
+    runs,
+    simulation::{Simulation, Event},
+};
+
+/// The statements that bring a database from each version of the
+/// schema to the next, the first creating it.
+pub const MIGRATIONS: [&str; 1] = [
+    "CREATE TABLE runs (
+        id INTEGER PRIMARY KEY,
+        seed TEXT NOT NULL,
+        world TEXT NOT NULL,
+        mutators TEXT NOT NULL,
+        parameters TEXT NOT NULL,
+        ticks INTEGER NOT NULL,
+        ended INTEGER NOT NULL,
+        version TEXT NOT NULL
+    );
+    CREATE TABLE ticks (
+        run INTEGER NOT NULL REFERENCES runs(id),
+        series TEXT NOT NULL,
+        tick INTEGER NOT NULL,
+        value REAL,
+        PRIMARY KEY (run, series, tick)
+    );
+    CREATE TABLE species (
+        run INTEGER NOT NULL REFERENCES runs(id),
+        tick INTEGER NOT NULL,
+        species TEXT,
+        population INTEGER NOT NULL,
+        mean_speed REAL,
+        mean_size REAL,
+        mean_sight REAL,
+        mean_hunger REAL
+    );
+    CREATE INDEX species_by_run ON species(run, tick);
+    CREATE TABLE events (
+        run INTEGER NOT NULL REFERENCES runs(id),
+        tick INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        blob TEXT,
+        other TEXT,
+        detail TEXT
+    );
+    CREATE INDEX events_by_run ON events(run, tick);",
+];
+
+/// The version of the schema this build writes.
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// The population and mean traits of a species at a tick.
+#[derive(Debug, Clone, PartialEq)]
+struct SpeciesSample {
+    tick: u64,
+    species: Option<String>,
+    population: usize,
+    speed: f32,
+    size: f32,
+    sight: f32,
+    hunger: f32,
+}
+
+/// An event as stored: what happened, to whom, by whom, and how.
+#[derive(Debug, Clone, PartialEq)]
+struct EventRow {
+    tick: u64,
+    kind: &'static str,
+    blob: Option<String>,
+    other: Option<String>,
+    detail: Option<String>,
+}
+
+/// What a run leaves behind that the simulation does not keep for
+/// long: its latest events and the traits of its species.
+#[derive(Debug, Clone, Default)]
+pub struct Telemetry {
+    species: Vec<SpeciesSample>,
+    events: VecDeque<EventRow>,
+}
+
+impl Telemetry {
+    /// How many of the latest events of a run are kept, so that long
+    /// runs do not hold every birth in memory until they end.
+    pub const EVENT_CAPACITY: usize = 1 << 20;
+
+    pub fn new() -> Self { Self::default() }
+
+    /// Keep the events of the last tick, and the species when the
+    /// statistics are sampled.
+    pub fn collect(&mut self, sim: &Simulation) {
+        let tick = sim.clock().tick();
+        for (tick, event) in sim.events_since(tick) {
+            let name = |key| sim.get_blob(key).and_then(|blob| blob.name.clone());
+            let (kind, blob, other, detail) = match event {
+                Event::Born(key) => ("born", name(*key), None, None),
+                Event::Killed { victim, killer } => ("killed", victim.clone(), killer.clone(), None),
+                Event::Starved(blob) => ("starved", blob.clone(), None, None),
+                Event::Quarantined { blob, problem, removed } => {
+                    ("quarantined", blob.clone(), None, Some(format!("{}{}", problem, if *removed { ", removed" } else { "" })))
+                },
+                Event::Alert { region, alert, value } => ("alert", None, None, Some(format!("{} {} at {}", region, alert, value))),
+                Event::Extinct(extinction) => ("extinct", None, None, Some(format!("{}, at most {}", extinction.species, extinction.peak))),
+                Event::Weather(weather) => ("weather", None, None, Some(format!("{} until tick {}", weather.kind.name(), weather.until))),
+                Event::Record(record) => ("record", record.holder.clone(), None, Some(record.describe())),
+            };
+            if self.events.len() == Self::EVENT_CAPACITY {
+                self.events.pop_front();
+            }
+            self.events.push_back(EventRow { tick: *tick, kind, blob, other, detail });
+        }
+        if !tick.is_multiple_of(Simulation::STATS_PERIOD) { return; }
+        let mut sums: BTreeMap<Option<String>, (usize, [f32; 4])> = BTreeMap::new();
+        for (_, blob) in sim.blobs() {
+            let (count, sum) = sums.entry(blob.species.clone()).or_default();
+            *count += 1;
+            for (sum, value) in sum.iter_mut().zip([blob.speed, blob.radius(), blob.sight_depth(), blob.hunger]) {
+                *sum += value;
+            }
+        }
+        for (species, (population, sum)) in sums {
+            let mean = |i: usize| sum[i] / population as f32;
+            self.species.push(SpeciesSample { tick, species, population, speed: mean(0), size: mean(1), sight: mean(2), hunger: mean(3) });
+        }
+    }
+}
+
+/// Text as an SQL literal.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+fn optional(text: &Option<String>) -> String {
+    text.as_deref().map_or("NULL".to_string(), quote)
+}
+
+/// A number as an SQL literal, NULL if it is not finite.
+fn real(value: f32) -> String {
+    if value.is_finite() { value.to_string() } else { "NULL".to_string() }
+}
+
+/// The statements that bring a database at a version of the schema up
+/// to date and add runs of labelled worlds to it, in one transaction.
+pub fn script(version: usize, runs: &[(&Simulation, &str, &Telemetry)], ended: u64) -> Result<String, String> {
+    if version > SCHEMA_VERSION {
+        return Err(format!("the database has schema version {}, newer than {} this build writes", version, SCHEMA_VERSION));
+    }
+    let mut ret = "BEGIN;\n".to_string();
+    for migration in &MIGRATIONS[version..] {
+        ret += migration;
+        ret += "\n";
+    }
+    ret += &format!("PRAGMA user_version = {};\n", SCHEMA_VERSION);
+    for &(sim, world, telemetry) in runs {
+        let mutators: Vec<_> = sim.mutators().iter().map(|mutator| mutator.id()).collect();
+        ret += &format!("INSERT INTO runs (seed, world, mutators, parameters, ticks, ended, version) VALUES ({}, {}, {}, {}, {}, {}, {});\n",
+            quote(&sim.seed().to_string()), quote(world), quote(&mutators.join(",")), quote(&format!("{:?}", sim.parameters())),
+            sim.clock().tick(), ended, quote(runs::VERSION),
+        );
+        //  the run's key, for the rows that follow
+        ret += "DROP TABLE IF EXISTS temp.run;\nCREATE TEMP TABLE run AS SELECT last_insert_rowid() AS id;\n";
+        let stats = sim.stats();
+        for id in stats.ids() {
+            for sample in stats.history(id) {
+                ret += &format!("INSERT INTO ticks VALUES ((SELECT id FROM temp.run), {}, {}, {});\n", quote(stats.name(id)), sample.tick, real(sample.value));
+            }
+        }
+        for sample in &telemetry.species {
+            ret += &format!("INSERT INTO species VALUES ((SELECT id FROM temp.run), {}, {}, {}, {}, {}, {}, {});\n",
+                sample.tick, optional(&sample.species), sample.population, real(sample.speed), real(sample.size), real(sample.sight), real(sample.hunger),
+            );
+        }
+        for event in &telemetry.events {
+            ret += &format!("INSERT INTO events VALUES ((SELECT id FROM temp.run), {}, {}, {}, {}, {});\n",
+                event.tick, quote(event.kind), optional(&event.blob), optional(&event.other), optional(&event.detail),
+            );
+        }
+    }
+    ret += "COMMIT;\n";
+    Ok(ret)
+}
+
+/// Why the `sqlite3` program could not be started.
+fn missing(e: io::Error) -> String {
+    match e.kind() {
+        io::ErrorKind::NotFound => "the sqlite3 program was not found, install it to write statistics to SQLite".to_string(),
+        _ => format!("failed to run sqlite3: {}", e),
+    }
+}
+
+/// The version of the `sqlite3` program, or why it cannot be run.
+pub fn version() -> Result<String, String> {
+    let output = Command::new("sqlite3").arg("-version").stdin(Stdio::null()).output().map_err(missing)?;
+    if !output.status.success() {
+        return Err(format!("sqlite3 -version: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let version = String::from_utf8_lossy(&output.stdout);
+    Ok(version.split_whitespace().next().unwrap_or_default().to_string())
+}
+
+/// Run statements with the `sqlite3` program on a database, returning
+/// what it printed.
+fn run_sqlite(path: &str, statements: &str) -> Result<String, String> {
+    let mut child = Command::new("sqlite3")
+        .args(["-batch", "-bail", path])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(missing)?;
+    child.stdin.take().unwrap().write_all(statements.as_bytes()).map_err(|e| format!("failed to write to sqlite3: {}", e))?;
+    let output = child.wait_with_output().map_err(|e| format!("failed to run sqlite3: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("{}: {}", path, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Add runs of labelled worlds to the database at a path, creating
+/// or updating it as needed.
+pub fn write(path: &str, runs: &[(&Simulation, &str, &Telemetry)]) -> Result<(), String> {
+    let version = run_sqlite(path, "PRAGMA user_version;")?;
+    let version: usize = version.trim().parse().map_err(|_| format!("{}: invalid schema version `{}`", path, version.trim()))?;
+    let ended = time::SystemTime::now().duration_since(time::UNIX_EPOCH).unwrap_or_default().as_secs();
+    run_sqlite(path, &script(version, runs, ended)?).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::prelude::Vector2;
+
+    use super::*;
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_sqlite() {
+        assert_eq!(quote("it's"), "'it''s'");
+        assert_eq!(real(f32::NAN), "NULL");
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        sim.insert_blob(BlobConfig { species: Some("o'brien".to_string()), ..BlobConfig::new(Vector2::new(100., 100.)) });
+        sim.insert_blob(BlobConfig::new(Vector2::new(200., 100.)));
+        let mut telemetry = Telemetry::new();
+        for _ in 0..Simulation::STATS_PERIOD {
+            sim.step();
+            telemetry.collect(&sim);
+        }
+        assert_eq!(telemetry.species.len(), 2, "{:?}", telemetry.species);
+        assert_eq!(telemetry.species[0].species, None);
+
+        let fresh = script(0, &[(&sim, "world", &telemetry)], 0).unwrap();
+        assert!(fresh.starts_with("BEGIN;\nCREATE TABLE runs"));
+        assert!(fresh.contains(&format!("PRAGMA user_version = {};", SCHEMA_VERSION)));
+        assert!(fresh.contains("'o''brien'") && fresh.contains(&format!("'{}');", runs::VERSION)));
+        assert!(fresh.contains("'population', 0, 2);"), "{}", fresh);
+        assert!(fresh.ends_with("COMMIT;\n"));
+        //  an up to date database only gets the run
+        let current = script(SCHEMA_VERSION, &[(&sim, "world", &telemetry)], 0).unwrap();
+        assert!(!current.contains("CREATE TABLE runs"));
+        assert!(script(SCHEMA_VERSION + 1, &[], 0).is_err());
+    }
+}
@@ -0,0 +1,439 @@
+//! An in-memory store of named time series.
+//!
+//! Series are registered by name and receive samples stamped
+//! with the simulation tick they were taken at. Ranges of a
+//! series can be queried with downsampling, which averages
+//! neighbouring samples while keeping their extremes. Marks
+//! label ticks where something was done to the world, such as
+//! changing a parameter, or bookmarked with a note.
+//!
+//! So that runs of millions of ticks stay small and quick to graph,
+//! only the latest `RECENT` samples of a series are kept as they are.
+//! Older ones are merged `FACTOR` at a time into the first of a few
+//! tiers of points, and once a tier holds `TIER_POINTS` its oldest are
+//! merged the same way into the next, coarser, tier. A point keeps the
+//! mean of its samples and their exact extremes, so the lowest and
+//! highest values of the whole run are never lost.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::stats::StatsStore;
+//! let mut stats = StatsStore::new();
+//! let population = stats.register("population");
+//! stats.push(population, 0, 10.);
+//! stats.push(population, 1, 12.);
+//! assert_eq!(stats.query(population, 0..2, 1)[0].mean, 11.);
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, Write},
+    mem,
+    ops::Range,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SeriesId(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub tick: u64,
+    pub value: f32,
+}
+
+/// A run of consecutive samples summarized into one point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    /// The tick of the first sample in the run.
+    pub tick: u64,
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkKind {
+    /// Something done to the world, such as changing a parameter.
+    Change,
+    /// A moment bookmarked with a note.
+    Bookmark,
+}
+
+/// A labelled tick on the timeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mark {
+    pub tick: u64,
+    pub kind: MarkKind,
+    pub label: String,
+}
+
+/// How many of the latest samples of a series are kept as they are.
+pub const RECENT: usize = 4096;
+/// How many samples or points are merged into a point of a tier.
+pub const FACTOR: usize = 8;
+/// How many points a tier holds before its oldest are merged into the next.
+pub const TIER_POINTS: usize = 512;
+
+/// A point of a tier, with how many samples it was merged from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Bucket {
+    point: Point,
+    count: u64,
+}
+
+impl Bucket {
+    fn of(sample: &Sample) -> Self {
+        Self { point: Point { tick: sample.tick, mean: sample.value, min: sample.value, max: sample.value }, count: 1 }
+    }
+
+    fn merge(buckets: &[Bucket]) -> Self {
+        let count = buckets.iter().map(|b| b.count).sum::<u64>();
+        Self {
+            point: Point {
+                tick: buckets[0].point.tick,
+                mean: (buckets.iter().map(|b| b.point.mean as f64 * b.count as f64).sum::<f64>() / count as f64) as f32,
+                min: buckets.iter().map(|b| b.point.min).fold(f32::INFINITY, f32::min),
+                max: buckets.iter().map(|b| b.point.max).fold(f32::NEG_INFINITY, f32::max),
+            },
+            count,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Series {
+    name: String,
+    samples: Vec<Sample>,
+    /// The older samples, downsampled, finest and latest tier first
+    /// and each tier oldest point first.
+    tiers: Vec<Vec<Bucket>>,
+}
+
+impl Series {
+    fn new(name: &str) -> Self {
+        Self { name: name.to_string(), samples: vec![], tiers: vec![] }
+    }
+
+    /// Merge the oldest samples into the tiers, and full tiers into coarser ones.
+    fn downsample(&mut self) {
+        if self.samples.len() < RECENT + FACTOR { return; }
+        let merged = Bucket::merge(&self.samples.drain(..FACTOR).map(|sample| Bucket::of(&sample)).collect::<Vec<_>>());
+        let mut carried = Some(merged);
+        let mut tier = 0;
+        while let Some(bucket) = carried.take() {
+            if self.tiers.len() == tier {
+                self.tiers.push(vec![]);
+            }
+            let points = &mut self.tiers[tier];
+            points.push(bucket);
+            if points.len() >= TIER_POINTS + FACTOR {
+                carried = Some(Bucket::merge(&points.drain(..FACTOR).collect::<Vec<_>>()));
+            }
+            tier += 1;
+        }
+    }
+
+    /// Every bucket kept, oldest first.
+    fn buckets(&self) -> impl Iterator<Item=Bucket> + '_ {
+        self.tiers.iter().rev().flatten().copied().chain(self.samples.iter().map(Bucket::of))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StatsStore {
+    series: Vec<Series>,
+    marks: Vec<Mark>,
+}
+
+impl StatsStore {
+    /// Series shorter than this are left alone by `thin`.
+    pub const MIN_THIN_SAMPLES: usize = 256;
+
+    pub fn new() -> Self { Self::default() }
+
+    /// Get the series with the given name, creating it if needed.
+    pub fn register(&mut self, name: &str) -> SeriesId {
+        if let Some(id) = self.id(name) { return id; }
+        self.series.push(Series::new(name));
+        SeriesId(self.series.len() - 1)
+    }
+
+    pub fn id(&self, name: &str) -> Option<SeriesId> {
+        self.series.iter().position(|s| s.name == name).map(SeriesId)
+    }
+
+    pub fn name(&self, id: SeriesId) -> &str { &self.series[id.0].name }
+
+    /// Iterate over the ids of all the registered series.
+    pub fn ids(&self) -> impl Iterator<Item=SeriesId> {
+        (0..self.series.len()).map(SeriesId)
+    }
+
+    /// Add a sample to a series.
+    ///
+    /// Samples must be pushed in non-decreasing tick order.
+    pub fn push(&mut self, id: SeriesId, tick: u64, value: f32) {
+        let series = &mut self.series[id.0];
+        debug_assert!(series.samples.last().is_none_or(|last| last.tick <= tick));
+        series.samples.push(Sample { tick, value });
+        series.downsample();
+    }
+
+    /// The latest samples of a series, those not yet downsampled.
+    pub fn samples(&self, id: SeriesId) -> &[Sample] { &self.series[id.0].samples }
+
+    /// Every sample of a series still kept, with the downsampled ones
+    /// as the mean of each point at its first tick.
+    pub fn history(&self, id: SeriesId) -> Vec<Sample> {
+        self.series[id.0].buckets().map(|b| Sample { tick: b.point.tick, value: b.point.mean }).collect()
+    }
+
+    /// The first tick of a series, downsampled or not.
+    pub fn first(&self, id: SeriesId) -> Option<u64> {
+        self.series[id.0].buckets().next().map(|b| b.point.tick)
+    }
+
+    /// The highest value a series ever had.
+    pub fn max(&self, id: SeriesId) -> Option<f32> {
+        self.series[id.0].buckets().map(|b| b.point.max).reduce(f32::max)
+    }
+
+    /// The value of a series at the last sample at or before a tick,
+    /// or the mean of the point it was downsampled into.
+    pub fn at(&self, id: SeriesId, tick: u64) -> Option<f32> {
+        let series = &self.series[id.0];
+        let end = series.samples.partition_point(|sample| sample.tick <= tick);
+        if end > 0 {
+            return Some(series.samples[end - 1].value);
+        }
+        series.tiers.iter().find_map(|points| {
+            let end = points.partition_point(|b| b.point.tick <= tick);
+            points[..end].last().map(|b| b.point.mean)
+        })
+    }
+
+    pub fn latest(&self, id: SeriesId) -> Option<Sample> {
+        self.samples(id).last().cloned()
+    }
+
+    /// Label a tick, after any marks of the same tick.
+    pub fn mark(&mut self, tick: u64, kind: MarkKind, label: &str) {
+        let i = self.marks.partition_point(|mark| mark.tick <= tick);
+        self.marks.insert(i, Mark { tick, kind, label: label.to_string() });
+    }
+
+    /// The marks in tick order.
+    pub fn marks(&self) -> &[Mark] { &self.marks }
+
+    /// The estimated bytes held by the store.
+    pub fn bytes(&self) -> usize {
+        self.series.iter()
+            .map(|s| mem::size_of::<Series>() + s.name.capacity() + s.samples.capacity() * mem::size_of::<Sample>()
+                + s.tiers.iter().map(|points| points.capacity() * mem::size_of::<Bucket>()).sum::<usize>())
+            .sum::<usize>()
+            + self.marks.iter().map(|m| mem::size_of::<Mark>() + m.label.capacity()).sum::<usize>()
+    }
+
+    /// Drop every other of the latest samples of the longer series,
+    /// halving their resolution but keeping their whole history. Returns whether
+    /// any series was long enough to thin.
+    pub fn thin(&mut self) -> bool {
+        let mut thinned = false;
+        for series in self.series.iter_mut().filter(|s| s.samples.len() >= Self::MIN_THIN_SAMPLES) {
+            let mut i = 0;
+            series.samples.retain(|_| { i += 1; i % 2 == 1 });
+            series.samples.shrink_to_fit();
+            thinned = true;
+        }
+        thinned
+    }
+
+    /// The samples of a series within a range of ticks, merged
+    /// down to at most `max_points` points.
+    pub fn query(&self, id: SeriesId, ticks: Range<u64>, max_points: usize) -> Vec<Point> {
+        let buckets: Vec<_> = self.series[id.0].buckets()
+            .filter(|b| ticks.contains(&b.point.tick))
+            .collect();
+        if buckets.is_empty() || max_points == 0 { return vec![]; }
+
+        let per_point = buckets.len().div_ceil(max_points);
+        buckets.chunks(per_point).map(|chunk| Bucket::merge(chunk).point).collect()
+    }
+
+    /// Write the store in a line based text format.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        for mark in &self.marks {
+            let kind = match mark.kind {
+                MarkKind::Change => "mark",
+                MarkKind::Bookmark => "bookmark",
+            };
+            writeln!(w, "{} {} {}", kind, mark.tick, mark.label)?;
+        }
+        for series in &self.series {
+            writeln!(w, "series {}", series.name)?;
+            for b in series.tiers.iter().rev().flatten() {
+                writeln!(w, "{} {} {} {} {}", b.point.tick, b.point.mean, b.point.min, b.point.max, b.count)?;
+            }
+            for sample in &series.samples {
+                writeln!(w, "{} {}", sample.tick, sample.value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the series as CSV, a column each and a row for each tick
+    /// sampled, leaving out series not sampled at a tick.
+    pub fn write_csv<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut rows: BTreeMap<u64, Vec<Option<f32>>> = BTreeMap::new();
+        for i in 0..self.series.len() {
+            for sample in self.history(SeriesId(i)) {
+                rows.entry(sample.tick).or_insert_with(|| vec![None; self.series.len()])[i] = Some(sample.value);
+            }
+        }
+        let names: Vec<_> = self.series.iter().map(|series| series.name.as_str()).collect();
+        writeln!(w, "tick,{}", names.join(","))?;
+        for (tick, values) in rows {
+            let values: Vec<_> = values.iter().map(|value| value.map_or(String::new(), |value| value.to_string())).collect();
+            writeln!(w, "{},{}", tick, values.join(","))?;
+        }
+        Ok(())
+    }
+
+    /// Read a store written by `write_to`, downsampled points being
+    /// lines of their tick, mean, least, greatest and sample count.
+    pub fn read_from<R: BufRead>(r: R) -> io::Result<Self> {
+        let invalid = |line: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid stats line `{}`", line));
+        let mut ret = Self::new();
+        //  the tick of the series' last point or sample, which come in order
+        let mut last = 0;
+        for line in r.lines() {
+            let line = line?;
+            if line.is_empty() { continue; }
+            let kind = match line.split(' ').next() {
+                Some("mark") => Some(MarkKind::Change),
+                Some("bookmark") => Some(MarkKind::Bookmark),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                let mut parts = line.splitn(3, ' ').skip(1);
+                let tick = parts.next().and_then(|t| t.parse().ok()).ok_or_else(|| invalid(&line))?;
+                let label = parts.next().unwrap_or("");
+                ret.marks.push(Mark { tick, kind, label: label.to_string() });
+                continue;
+            }
+            if let Some(name) = line.strip_prefix("series ") {
+                ret.series.push(Series::new(name));
+                last = 0;
+                continue;
+            }
+            let series = ret.series.last_mut().ok_or_else(|| invalid(&line))?;
+            let mut parts = line.split(' ');
+            let tick = parts.next().and_then(|t| t.parse().ok()).ok_or_else(|| invalid(&line))?;
+            let value = parts.next().and_then(|v| v.parse().ok()).ok_or_else(|| invalid(&line))?;
+            if tick < last {
+                return Err(invalid(&line));
+            }
+            last = tick;
+            let rest: Vec<_> = parts.collect();
+            if rest.is_empty() {
+                series.samples.push(Sample { tick, value });
+                continue;
+            }
+            let (min, max, count) = match rest[..] {
+                [min, max, count] => match (min.parse(), max.parse(), count.parse::<u64>()) {
+                    (Ok(min), Ok(max), Ok(count)) if count > 0 => (min, max, count),
+                    _ => return Err(invalid(&line)),
+                },
+                _ => return Err(invalid(&line)),
+            };
+            //  the tier of a point is told by how many samples it was merged from
+            let mut tier = 0;
+            let mut size = FACTOR as u64;
+            while size < count {
+                size = size.saturating_mul(FACTOR as u64);
+                tier += 1;
+            }
+            //  tiers are written coarsest first
+            if series.tiers.len() <= tier {
+                series.tiers.resize(tier + 1, vec![]);
+            }
+            series.tiers[tier].push(Bucket { point: Point { tick, mean: value, min, max }, count });
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query() {
+        let mut stats = StatsStore::new();
+        let a = stats.register("a");
+        assert_eq!(stats.register("a"), a);
+        for tick in 0..10 {
+            stats.push(a, tick, tick as f32);
+        }
+
+        let points = stats.query(a, 2..8, 3);
+        assert_eq!(points, vec![
+            Point { tick: 2, mean: 2.5, min: 2., max: 3. },
+            Point { tick: 4, mean: 4.5, min: 4., max: 5. },
+            Point { tick: 6, mean: 6.5, min: 6., max: 7. },
+        ]);
+        assert_eq!(stats.query(a, 0..100, 100).len(), 10);
+        assert!(stats.query(a, 20..30, 10).is_empty());
+
+        assert!(!stats.thin());
+        for tick in 10..1000 {
+            stats.push(a, tick, tick as f32);
+        }
+        let bytes = stats.bytes();
+        assert!(stats.thin());
+        assert!(stats.bytes() < bytes);
+        assert_eq!(stats.samples(a).len(), 500);
+        assert_eq!((stats.samples(a)[0].tick, stats.samples(a)[499].tick), (0, 998));
+
+        //  a long series keeps its latest samples, and older ones downsampled with their extremes
+        let b = stats.register("b");
+        let long = (RECENT + TIER_POINTS * FACTOR * 3) as u64;
+        for tick in 0..long {
+            stats.push(b, tick, if tick == 5 { -100. } else { (tick % 10) as f32 });
+        }
+        assert_eq!(stats.samples(b).len(), RECENT + (long as usize - RECENT) % FACTOR);
+        assert!(stats.series[b.0].tiers.len() == 2 && stats.series[b.0].tiers.iter().all(|points| points.len() < TIER_POINTS + FACTOR));
+        assert_eq!((stats.first(b), stats.max(b)), (Some(0), Some(9.)));
+        let whole = stats.query(b, 0..u64::MAX, 1)[0];
+        assert_eq!((whole.tick, whole.min, whole.max), (0, -100., 9.));
+        assert!((whole.mean - 4.5).abs() < 0.01);
+        assert_eq!((stats.at(b, 5), stats.at(b, long - 1)), (Some(stats.query(b, 0..1, 1)[0].mean), Some(((long - 1) % 10) as f32)));
+        let mut buffer = vec![];
+        stats.write_to(&mut buffer).unwrap();
+        assert_eq!(StatsStore::read_from(&buffer[..]).unwrap(), stats);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut stats = StatsStore::new();
+        let a = stats.register("mean speed");
+        let b = stats.register("population");
+        stats.push(a, 3, 0.1);
+        stats.push(b, 3, 42.);
+        stats.push(b, 9, 40.);
+        stats.mark(5, MarkKind::Change, "food_rate 0.5");
+        stats.mark(4, MarkKind::Bookmark, "");
+        assert_eq!(stats.marks()[0].tick, 4);
+
+        let mut buffer = vec![];
+        stats.write_to(&mut buffer).unwrap();
+        assert_eq!(StatsStore::read_from(&buffer[..]).unwrap(), stats);
+        assert!(StatsStore::read_from(&b"1 2\n"[..]).is_err());
+        assert!(StatsStore::read_from(&b"series a\n5 1\n4 2\n"[..]).is_err());
+
+        let mut csv = vec![];
+        stats.write_csv(&mut csv).unwrap();
+        assert_eq!(String::from_utf8(csv).unwrap(), "tick,mean speed,population\n3,0.1,42\n9,,40\n");
+    }
+}
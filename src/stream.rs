@@ -0,0 +1,132 @@
+//! Output for compositing the world into a live stream, such as with
+//! OBS.
+//!
+//! `--chroma-key <color>` draws the world on a flat color to key out,
+//! `green`, `blue`, `magenta` or `r,g,b`, without the interface or
+//! post-processing, which would tint the key. The interface can still
+//! be shown with F7. `--stream-stats <path>` keeps the statistics of
+//! the first world in a text file, written every `PERIOD` in full so a
+//! text source reading it never shows half of it:
+//!
+//! ```text
+//! Year 1, Day 3 (Spring)
+//! Population: 42
+//! Food: 118
+//! Births a minute: 4.0
+//! Kills a minute: 1.5
+//! ```
+//!
+//! A separate window for the interface is left out, as the window
+//! library opens only one.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use std::time;
+//! # use blobs::{stream::{self, StatsFile}, theme::Theme};
+//! let theme = stream::keyed(Theme::DARK, stream::parse_key("green")?);
+//! let mut stats = StatsFile::new("stats.txt");
+//! //  every frame
+//! stats.update(&sim, time::Instant::now())?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fs, io, path::PathBuf, time};
+
+use raylib::prelude::Color;
+
+use crate::{simulation::Simulation, sonification::Metrics, theme::Theme};
+
+/// How often the statistics are written.
+pub const PERIOD: time::Duration = time::Duration::from_secs(1);
+
+/// Parse a key color, by name or as `r,g,b`.
+pub fn parse_key(text: &str) -> Result<Color, String> {
+    match text {
+        "green" => return Ok(Color::new(0, 255, 0, 255)),
+        "blue" => return Ok(Color::new(0, 0, 255, 255)),
+        "magenta" => return Ok(Color::new(255, 0, 255, 255)),
+        _ => (),
+    }
+    let channels = text.split(',')
+        .map(|channel| channel.trim().parse::<u8>().map_err(|_| format!("invalid channel `{}`, expected 0 to 255", channel)))
+        .collect::<Result<Vec<_>, _>>()?;
+    match channels[..] {
+        [r, g, b] => Ok(Color::new(r, g, b, 255)),
+        _ => Err(format!("expected green, blue, magenta or r,g,b, got `{}`", text)),
+    }
+}
+
+/// A theme with the world drawn on a key color.
+pub fn keyed(theme: Theme, key: Color) -> Theme {
+    Theme { background: key, ..theme }
+}
+
+/// The statistics of a world as shown in the file.
+pub fn text(sim: &Simulation) -> String {
+    let metrics = Metrics::measure(sim);
+    format!("{}\nPopulation: {}\nFood: {}\nBirths a minute: {:.1}\nKills a minute: {:.1}\n",
+        sim.clock(), metrics.population, sim.foods().count(), metrics.birth_rate * 60., metrics.predation_rate * 60.,
+    )
+}
+
+/// Keeps the statistics of a world in a file.
+pub struct StatsFile {
+    path: PathBuf,
+    //  when the file was last written, if it was
+    written: Option<time::Instant>,
+}
+
+impl StatsFile {
+    pub fn new(path: &str) -> Self {
+        Self { path: PathBuf::from(path), written: None }
+    }
+
+    /// Write the statistics if it is time to, to a file beside the
+    /// path first and then over it.
+    pub fn update(&mut self, sim: &Simulation, now: time::Instant) -> io::Result<()> {
+        if self.written.is_some_and(|written| now.duration_since(written) < PERIOD) {
+            return Ok(());
+        }
+        self.written = Some(now);
+        let mut partial = self.path.clone().into_os_string();
+        partial.push(".partial");
+        fs::write(&partial, text(sim))?;
+        fs::rename(&partial, &self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use raylib::prelude::Vector2;
+
+    use super::*;
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_stream() {
+        assert_eq!(parse_key("green"), Ok(Color::new(0, 255, 0, 255)));
+        assert_eq!(parse_key("1, 2,3"), Ok(Color::new(1, 2, 3, 255)));
+        assert!(parse_key("1,2").is_err());
+        assert!(parse_key("1,2,300").is_err());
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+        let path = std::env::temp_dir().join(format!("blobs-stream-{}.txt", std::process::id()));
+        let mut stats = StatsFile::new(path.to_str().unwrap());
+        let now = time::Instant::now();
+        stats.update(&sim, now).unwrap();
+        let written = fs::read_to_string(&path).unwrap();
+        assert!(written.contains("\nPopulation: 1\nFood: 0\nBirths a minute: "), "{}", written);
+        //  not again within the period
+        sim.insert_blob(BlobConfig::new(Vector2::new(200., 100.)));
+        stats.update(&sim, now + PERIOD / 2).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+        stats.update(&sim, now + PERIOD).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("Population: 2"));
+        fs::remove_file(&path).unwrap();
+    }
+}
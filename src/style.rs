@@ -0,0 +1,159 @@
+//! The styles blobs are drawn in, and which one a zoom draws.
+//!
+//! * `minimal` draws a flat circle in the color of the blob, and
+//!   nothing else, for crowded worlds seen from afar
+//! * `classic` draws the circle faded as the blob starves, with its
+//!   name, age and tags over it
+//! * `detailed` draws the classic blob tinted toward the error color
+//!   of the theme as it starves rather than faded, outlined in the
+//!   color of its species, with eyes looking where it heads
+//!
+//! With `--style auto`, as by default, the zoom picks: minimal below
+//! `MINIMAL_BELOW`, detailed from `DETAILED_FROM` and classic between.
+//! `\` goes through the choices in game.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let choice = Choice::parse("auto")?;
+//! let context = RenderContext { style: choice.style(view.zoom), ..context };
+//! blob.draw(&mut draw, &context);
+//! ```
+
+use raylib::prelude::*;
+
+use crate::theme::{self, Theme};
+
+/// The zoom below which the automatic choice draws blobs minimal.
+pub const MINIMAL_BELOW: f32 = 0.5;
+/// The zoom from which the automatic choice draws blobs detailed.
+pub const DETAILED_FROM: f32 = 2.;
+
+/// How a blob is drawn, see the module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Minimal,
+    Classic,
+    Detailed,
+}
+
+impl Style {
+    pub const ALL: [Self; 3] = [Self::Minimal, Self::Classic, Self::Detailed];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Minimal => "minimal",
+            Self::Classic => "classic",
+            Self::Detailed => "detailed",
+        }
+    }
+}
+
+/// Which style blobs are drawn in: the one the zoom picks, or one for
+/// every zoom.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Choice {
+    #[default]
+    Auto,
+    Fixed(Style),
+}
+
+impl Choice {
+    pub const NAMES: [&'static str; 4] = ["auto", "minimal", "classic", "detailed"];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "auto" => Ok(Self::Auto),
+            name => Style::ALL.iter().find(|style| style.name() == name).map(|&style| Self::Fixed(style))
+                .ok_or_else(|| format!("unknown style `{}`, expected one of {}", name, Self::NAMES.join(", "))),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Fixed(style) => style.name(),
+        }
+    }
+
+    /// The choice after this one, back to the first after the last.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Auto => Self::Fixed(Style::Minimal),
+            Self::Fixed(Style::Minimal) => Self::Fixed(Style::Classic),
+            Self::Fixed(Style::Classic) => Self::Fixed(Style::Detailed),
+            Self::Fixed(Style::Detailed) => Self::Auto,
+        }
+    }
+
+    /// The style blobs are drawn in at a zoom.
+    pub fn style(self, zoom: f32) -> Style {
+        match self {
+            Self::Fixed(style) => style,
+            Self::Auto if zoom < MINIMAL_BELOW => Style::Minimal,
+            Self::Auto if zoom >= DETAILED_FROM => Style::Detailed,
+            Self::Auto => Style::Classic,
+        }
+    }
+}
+
+/// The color a species is outlined in, from the gradient of the theme
+/// and the same for a name in every world and run, and the muted text
+/// color for blobs of none.
+pub fn species_color(species: Option<&str>, theme: &Theme) -> Color {
+    let species = match species {
+        Some(species) => species,
+        None => return theme.muted_text,
+    };
+    //  FNV-1a, which unlike the hasher of the standard library is the same everywhere
+    let hash = species.bytes().fold(0x811c9dc5u32, |hash, byte| (hash ^ byte as u32).wrapping_mul(0x01000193));
+    theme.contrast(theme.gradient.sample((hash % 1024) as f32 / 1023.))
+}
+
+/// The body color of a detailed blob, its own tinted toward the error
+/// color as it starves.
+pub fn energy_tint(color: Color, hunger: f32, theme: &Theme) -> Color {
+    theme::lerp_color(color, theme.error, hunger.clamp(0., 1.).powi(2))
+}
+
+/// Draw two eyes on a blob of a radius at a place, looking along a
+/// direction, if it has one.
+pub fn draw_eyes<D: RaylibDraw>(draw: &mut D, pos: Vector2, radius: f32, direction: Vector2) {
+    if direction.length_sqr() < 1e-6 { return; }
+    let forward = direction.normalized();
+    let side = Vector2::new(-forward.y, forward.x);
+    for sign in [-1., 1.] {
+        let eye = pos + forward * radius * 0.45 + side * sign * radius * 0.35;
+        draw.draw_circle_v(eye, radius * 0.22, Color::WHITE);
+        draw.draw_circle_v(eye + forward * radius * 0.08, radius * 0.11, Color::BLACK);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style() {
+        assert_eq!(Choice::parse("detailed").unwrap(), Choice::Fixed(Style::Detailed));
+        assert!(Choice::parse("fancy").unwrap_err().contains("expected one of auto"));
+        let mut choice = Choice::Auto;
+        for &name in &Choice::NAMES[1..] {
+            choice = choice.next();
+            assert_eq!(choice.name(), name);
+        }
+        assert_eq!(choice.next(), Choice::Auto);
+
+        //  the zoom picks unless a style is chosen
+        assert_eq!(Choice::Auto.style(0.25), Style::Minimal);
+        assert_eq!(Choice::Auto.style(1.), Style::Classic);
+        assert_eq!(Choice::Auto.style(DETAILED_FROM), Style::Detailed);
+        assert_eq!(Choice::Fixed(Style::Classic).style(4.), Style::Classic);
+
+        let theme = Theme::from_name("dark").unwrap();
+        assert_eq!(species_color(Some("Grazer"), &theme), species_color(Some("Grazer"), &theme));
+        assert_eq!(species_color(None, &theme), theme.muted_text);
+        assert_eq!(energy_tint(Color::WHITE, 0., &theme), Color::WHITE);
+        assert_eq!(energy_tint(Color::WHITE, 1., &theme), theme.error);
+    }
+}
@@ -0,0 +1,115 @@
+//! Snapshots of a world as SVG, for figures in papers and posters.
+//!
+//! Everything is written in world coordinates as shapes, so the
+//! image can be scaled to any size without blurring. Each render
+//! layer is a group of its own, named after the layer, in the order
+//! the window draws them, followed by the names of the blobs.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use std::fs;
+//! # use blobs::{svg, theme::Theme};
+//! # let theme = Theme::DARK;
+//! let mut file = fs::File::create("world.svg")?;
+//! svg::write(&mut file, &sim, &theme)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::io::{self, Write};
+
+use raylib::prelude::*;
+
+use crate::{
+    simulation::{Food, Lake, Simulation},
+    theme::{self, Theme},
+};
+
+/// The size of the names over blobs, as in the window.
+const FONT_SIZE: i32 = 20;
+
+/// Write a snapshot of a world as an SVG document.
+pub fn write<W: Write>(w: &mut W, sim: &Simulation, theme: &Theme) -> io::Result<()> {
+    let size = sim.size();
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(w, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#, size.x, size.y)?;
+
+    writeln!(w, r#"<g id="terrain">"#)?;
+    writeln!(w, r#"<rect width="{}" height="{}" {}/>"#, size.x, size.y, fill(theme.background))?;
+    for lake in sim.lakes() {
+        circle(w, lake.center, lake.radius, theme::lerp_color(theme.background, Lake::COLOR, Lake::SHADE))?;
+    }
+    writeln!(w, "</g>")?;
+
+    for &(id, corpses) in &[("food", false), ("corpses", true)] {
+        writeln!(w, r#"<g id="{}">"#, id)?;
+        for (_, food) in sim.foods().filter(|(_, food)| food.is_corpse() == corpses) {
+            circle(w, food.pos(), Food::RADIUS, theme.contrast(*food.color()))?;
+        }
+        writeln!(w, "</g>")?;
+    }
+
+    writeln!(w, r#"<g id="blobs">"#)?;
+    for (_, blob) in sim.blobs() {
+        circle(w, blob.pos(), blob.radius(), blob.fade_color(&theme.contrast(blob.color)))?;
+    }
+    writeln!(w, "</g>")?;
+
+    writeln!(w, r#"<g id="names" font-family="sans-serif" font-size="{}">"#, FONT_SIZE)?;
+    for (_, blob) in sim.blobs() {
+        if let Some(name) = &blob.name {
+            //  text is placed by its baseline rather than its top
+            let pos = blob.pos() - Vector2::new(blob.radius(), blob.radius() + FONT_SIZE as f32);
+            let color = blob.fade_color(&theme.contrast(blob.favorite_color));
+            writeln!(w, r#"<text x="{}" y="{}" {}>{}</text>"#, pos.x, pos.y, fill(color), escape(name))?;
+        }
+    }
+    writeln!(w, "</g>")?;
+
+    writeln!(w, "</svg>")
+}
+
+fn circle<W: Write>(w: &mut W, center: Vector2, radius: f32, color: Color) -> io::Result<()> {
+    writeln!(w, r#"<circle cx="{}" cy="{}" r="{}" {}/>"#, center.x, center.y, radius, fill(color))
+}
+
+/// The fill attributes of a color, leaving out the opacity of opaque ones.
+fn fill(color: Color) -> String {
+    let rgb = format!(r##"fill="#{:02x}{:02x}{:02x}""##, color.r, color.g, color.b);
+    match color.a {
+        255 => rgb,
+        a => format!(r#"{} fill-opacity="{:.3}""#, rgb, a as f32 / 255.),
+    }
+}
+
+/// Text with the characters XML gives meaning to escaped.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+
+    #[test]
+    fn test_write() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(200., 100.)));
+        sim.insert_food(Vector2::new(10., 20.));
+        let theme = Theme::default();
+        let mut out = vec![];
+        write(&mut out, &sim, &theme).unwrap();
+        let svg = String::from_utf8(out).unwrap();
+        assert!(svg.contains(r#"viewBox="0 0 200 100""#));
+        assert!(svg.contains(r#"<circle cx="10" cy="20" r="5""#));
+        assert!(svg.find(r#"<g id="terrain">"#) < svg.find(r#"<g id="food">"#));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        assert_eq!(fill(Color::new(255, 0, 16, 255)), r##"fill="#ff0010""##);
+        assert_eq!(fill(Color::new(0, 0, 0, 51)), r##"fill="#000000" fill-opacity="0.200""##);
+        assert_eq!(escape("<Bob & co>"), "&lt;Bob &amp; co&gt;");
+    }
+}
@@ -0,0 +1,126 @@
+//! Tags users put on blobs, to keep track of the ones they care about.
+//!
+//! A tag is a short text and a color, drawn as a small flag over the
+//! blob and listed in the inspector. Shift and `T` opens a text box
+//! for the selected blobs, where `name` tags them, `name:color` tags
+//! them in one of `PALETTE` or a `#rrggbb` color, `-name` takes the
+//! tag off and `?name` selects every blob tagged so. Without a color
+//! a tag takes one of the palette's by its text, the same each time.
+//! Tags are saved with the world, but not handed down to children.
+//!
+//! # Example
+//!
+//! ```ignore
+//! match tags::parse_edit("scout:green")? {
+//!     Edit::Add(tag) => blob.tag(tag),
+//!     Edit::Remove(text) => blob.untag(&text),
+//!     Edit::Find(text) => selected.retain(|blob| tags::has(blob, &text)),
+//! }
+//! ```
+
+use raylib::prelude::*;
+
+use crate::simulation::Blob;
+
+/// The longest text of a tag.
+pub const MAX_LEN: usize = 20;
+
+/// The colors tags can be given by name.
+pub const PALETTE: [(&str, Color); 8] = [
+    ("red", Color::RED),
+    ("orange", Color::ORANGE),
+    ("yellow", Color::YELLOW),
+    ("green", Color::GREEN),
+    ("blue", Color::BLUE),
+    ("purple", Color::PURPLE),
+    ("pink", Color::PINK),
+    ("white", Color::RAYWHITE),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tag {
+    pub text: String,
+    pub color: Color,
+}
+
+impl Tag {
+    /// A tag of a text in the palette's color for it.
+    pub fn new(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() || text.chars().count() > MAX_LEN {
+            return Err(format!("a tag must be 1 to {} characters", MAX_LEN));
+        }
+        if text.chars().any(|c| c.is_control() || c == ':') {
+            return Err(format!("`{}` cannot be a tag", text));
+        }
+        let hash = text.bytes().fold(0usize, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as usize));
+        Ok(Self { text: text.to_string(), color: PALETTE[hash % PALETTE.len()].1 })
+    }
+
+    /// A tag written as `text` or `text:color`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (text, color) = match spec.rsplit_once(':') {
+            Some((text, color)) => (text, Some(color.trim())),
+            None => (spec, None),
+        };
+        let mut ret = Self::new(text)?;
+        if let Some(color) = color {
+            ret.color = parse_color(color).ok_or_else(|| {
+                let names: Vec<_> = PALETTE.iter().map(|(name, _)| *name).collect();
+                format!("unknown color `{}`, expected one of {} or #rrggbb", color, names.join(", "))
+            })?;
+        }
+        Ok(ret)
+    }
+}
+
+/// A color of the palette by name, or written as `#rrggbb`.
+fn parse_color(text: &str) -> Option<Color> {
+    if let Some(&(_, color)) = PALETTE.iter().find(|(name, _)| name.eq_ignore_ascii_case(text)) {
+        return Some(color);
+    }
+    let hex = text.strip_prefix('#').filter(|hex| hex.len() == 6)?;
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color::new(channel(0)?, channel(2)?, channel(4)?, 255))
+}
+
+/// What is typed in the text box of tags.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    Add(Tag),
+    Remove(String),
+    Find(String),
+}
+
+pub fn parse_edit(typed: &str) -> Result<Edit, String> {
+    let typed = typed.trim();
+    if let Some(text) = typed.strip_prefix('-') {
+        Ok(Edit::Remove(Tag::new(text)?.text))
+    } else if let Some(text) = typed.strip_prefix('?') {
+        Ok(Edit::Find(Tag::new(text)?.text))
+    } else {
+        Tag::parse(typed).map(Edit::Add)
+    }
+}
+
+/// Whether a blob has a tag, whatever its case.
+pub fn has(blob: &Blob, text: &str) -> bool {
+    blob.tags.iter().any(|tag| tag.text.eq_ignore_ascii_case(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tags() {
+        assert_eq!(Tag::parse("scout:green"), Ok(Tag { text: "scout".to_string(), color: PALETTE[3].1 }));
+        assert_eq!(Tag::parse(" alpha : #ff0010 ").unwrap().color, Color::new(255, 0, 16, 255));
+        assert_eq!(Tag::new("scout").unwrap().color, Tag::new("scout").unwrap().color);
+        assert!(Tag::parse("scout:mauve").unwrap_err().contains("unknown color"));
+        assert!(Tag::new("").is_err());
+        assert!(Tag::new("a\ttab").is_err());
+        assert_eq!(parse_edit("-scout"), Ok(Edit::Remove("scout".to_string())));
+        assert_eq!(parse_edit("?Scout"), Ok(Edit::Find("Scout".to_string())));
+    }
+}
@@ -0,0 +1,215 @@
+//! New species forking off as their traits drift, named after what
+//! sets them apart.
+//!
+//! With the `speciation` parameter above 0, a child whose traits
+//! drifted further than it from the founder of its species founds a
+//! species of its own, which its children are of in turn. How far two
+//! blobs are apart is the distance between their `Traits`, each trait
+//! a part of the most a random blob has and each color channel a part
+//! of 255, so a parameter of 0.5 is about half of the range of one
+//! trait.
+//!
+//! The new species is named by rules, not drawn from a pack: the trait
+//! that drifted the most, as `Swift` for a faster one or `Tiny` for a
+//! smaller one, its color, as `Crimson`, and what it
+//! eats, as `Grazers`, so a species reads as `Swift Crimson Grazers`
+//! in the census and wherever species are listed. A name another
+//! species has, or had, takes the trait that drifted the next most as
+//! well, and then a numeral, as in `Swift Crimson Grazers II`.
+//!
+//! Founders are kept for the run, as the census is, and not saved: a
+//! loaded world takes the first child of each species as its founder.
+//! Renaming a species renames its founder with it.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let mut taxonomy = Taxonomy::default();
+//! if let Some(species) = taxonomy.classify(&child, 0.5, |name| census.contains(name)) {
+//!     println!("{} forked off", species);
+//! }
+//! ```
+
+use std::collections::BTreeMap;
+
+use raylib::prelude::*;
+
+use crate::{
+    naming,
+    simulation::BlobConfig,
+    species::Diet,
+};
+
+/// The traits named, with the most a random blob has and the word for
+/// a species of more and of less of it.
+const TRAITS: [(&str, f32, &str, &str); 8] = [
+    ("speed", 120., "Swift", "Sluggish"),
+    ("radius", 20., "Giant", "Tiny"),
+    ("sight_depth", 170., "Keen-eyed", "Nearsighted"),
+    ("attack", 1., "Fierce", "Meek"),
+    ("defence", 2., "Armored", "Frail"),
+    ("max_hunger", 25., "Hardy", "Delicate"),
+    ("swimming", 1., "Aquatic", "Landbound"),
+    ("hearing", 1., "Sharp-eared", "Dull-eared"),
+];
+
+/// What species are told apart by, see the module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Traits {
+    //  in the order of `TRAITS`, each a part of its most
+    values: [f32; 8],
+    color: Color,
+}
+
+impl Traits {
+    pub fn of(config: &BlobConfig) -> Self {
+        let values = [
+            config.speed, config.radius, config.sight_depth, config.attack,
+            config.defence, config.max_hunger, config.swimming, config.hearing,
+        ];
+        let mut ret = Self { values, color: config.color };
+        for (value, (_, most, _, _)) in ret.values.iter_mut().zip(TRAITS) {
+            *value /= most;
+        }
+        ret
+    }
+
+    /// How far apart the traits of two blobs are.
+    pub fn distance(&self, other: &Self) -> f32 {
+        let channel = |a: u8, b: u8| (a as f32 - b as f32) / 255.;
+        let colors = [
+            channel(self.color.r, other.color.r),
+            channel(self.color.g, other.color.g),
+            channel(self.color.b, other.color.b),
+        ];
+        self.values.iter().zip(other.values).map(|(a, b)| a - b).chain(colors)
+            .map(|delta| delta * delta)
+            .sum::<f32>()
+            .sqrt()
+    }
+}
+
+/// The word for a color, by its hue, or for how dark or gray it is.
+fn color_word(color: Color) -> &'static str {
+    let (r, g, b) = (color.r as f32 / 255., color.g as f32 / 255., color.b as f32 / 255.);
+    let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+    if max < 0.2 {
+        return "Dusky";
+    }
+    if max - min < 0.2 * max {
+        return "Ashen";
+    }
+    let hue = if max == r {
+        60. * ((g - b) / (max - min)).rem_euclid(6.)
+    } else if max == g {
+        60. * ((b - r) / (max - min) + 2.)
+    } else {
+        60. * ((r - g) / (max - min) + 4.)
+    };
+    match hue as u32 {
+        0..=14 | 345.. => "Crimson",
+        15..=44 => "Amber",
+        45..=69 => "Golden",
+        70..=159 => "Verdant",
+        160..=199 => "Teal",
+        200..=249 => "Azure",
+        250..=289 => "Violet",
+        _ => "Magenta",
+    }
+}
+
+fn diet_word(diet: Diet) -> &'static str {
+    match diet {
+        Diet::Herbivore => "Grazers",
+        Diet::Carnivore => "Hunters",
+        Diet::Omnivore => "Foragers",
+    }
+}
+
+/// The name of a species forked off one with a founder, see the
+/// module, unless taken.
+pub fn name(founder: &Traits, forked: &Traits, diet: Diet, taken: impl Fn(&str) -> bool) -> String {
+    let mut drifts: Vec<_> = founder.values.iter().zip(forked.values).zip(TRAITS)
+        .filter(|((was, is), _)| is != *was)
+        .map(|((was, is), (_, _, more, less))| ((is - was).abs(), if is > *was { more } else { less }))
+        .collect();
+    drifts.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+    //  a species that only changed color is named by it alone
+    let words: Vec<_> = drifts.iter().take(2).map(|&(_, word)| word).collect();
+    let name = |words: &[&str]| {
+        words.iter().chain(&[color_word(forked.color), diet_word(diet)]).copied().collect::<Vec<_>>().join(" ")
+    };
+    let first = name(&words[..words.len().min(1)]);
+    if !taken(&first) {
+        return first;
+    }
+    let second = name(&words);
+    (1..).map(|n| if n == 1 { second.clone() } else { format!("{} {}", second, naming::roman(n)) })
+        .find(|name| !taken(name))
+        .unwrap()
+}
+
+/// The founders of the species of a world, see the module.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Taxonomy {
+    //  blobs without a species have a founder too, and fork off the same
+    founders: BTreeMap<Option<String>, Traits>,
+}
+
+impl Taxonomy {
+    /// The species a child founds if its traits drifted further than
+    /// some distance from the founder of its species, or none if it is
+    /// of it still. Names are not those of another species founded, or
+    /// those taken.
+    pub fn classify(&mut self, child: &BlobConfig, drift: f32, taken: impl Fn(&str) -> bool) -> Option<String> {
+        let traits = Traits::of(child);
+        let founder = *self.founders.entry(child.species.clone()).or_insert(traits);
+        if founder.distance(&traits) <= drift {
+            return None;
+        }
+        let species = name(&founder, &traits, child.diet, |name| taken(name) || self.founders.contains_key(&Some(name.to_string())));
+        self.founders.insert(Some(species.clone()), traits);
+        Some(species)
+    }
+
+    /// Give the founder of a species another name, see
+    /// `Simulation::rename_species`.
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if let Some(founder) = self.founders.remove(&Some(from.to_string())) {
+            //  merged into a species with a founder of its own, that founder stays
+            self.founders.entry(Some(to.to_string())).or_insert(founder);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_taxonomy() {
+        let founder = BlobConfig { color: Color::new(200, 20, 30, 255), ..BlobConfig::default() };
+        let mut taxonomy = Taxonomy::default();
+        assert_eq!(taxonomy.classify(&founder, 0.5, |_| false), None);
+        //  a little drift is the same species
+        let child = BlobConfig { speed: founder.speed + 12., ..founder.clone() };
+        assert_eq!(taxonomy.classify(&child, 0.5, |_| false), None);
+
+        let swift = BlobConfig { speed: founder.speed + 90., attack: founder.attack + 0.2, ..founder.clone() };
+        assert_eq!(taxonomy.classify(&swift, 0.5, |_| false).as_deref(), Some("Swift Crimson Grazers"));
+        //  the name is not given twice, and the next blob of the new species is measured against its founder
+        assert_eq!(taxonomy.classify(&swift, 0.5, |_| false).as_deref(), Some("Swift Fierce Crimson Grazers"));
+        let grandchild = BlobConfig { species: Some("Swift Crimson Grazers".to_string()), ..swift.clone() };
+        assert_eq!(taxonomy.classify(&grandchild, 0.5, |_| false), None);
+        let taken = |name: &str| name.ends_with(" II");
+        assert_eq!(taxonomy.classify(&BlobConfig { species: None, ..swift }, 0.5, taken).as_deref(), Some("Swift Fierce Crimson Grazers III"));
+
+        let tiny = BlobConfig { radius: 1., diet: Diet::Carnivore, color: Color::new(30, 60, 220, 255), ..founder.clone() };
+        assert_eq!(taxonomy.classify(&tiny, 0.1, |_| false).as_deref(), Some("Tiny Azure Hunters"));
+        taxonomy.rename("Tiny Azure Hunters", "Minnows");
+        assert!(taxonomy.founders.contains_key(&Some("Minnows".to_string())));
+        assert_eq!(color_word(Color::new(128, 128, 128, 255)), "Ashen");
+        let green = Traits { color: Color::new(20, 200, 40, 255), ..Traits::of(&founder) };
+        assert_eq!(name(&Traits::of(&founder), &green, Diet::Omnivore, |_| false), "Verdant Foragers");
+    }
+}
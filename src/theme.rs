@@ -0,0 +1,162 @@
+//! Color themes for the world background and the interface.
+//!
+//! Blob colors are data, chosen by evolution rather than by the
+//! theme, so anything drawn with them goes through `Theme::contrast`
+//! which pushes colors that would vanish into the background
+//! towards the theme's text color.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let theme = Theme::DARK;
+//! draw.clear_background(theme.background);
+//! draw.draw_circle_v(pos, radius, theme.contrast(blob.color));
+//! ```
+
+use raylib::prelude::*;
+
+/// Colors spread over the range [0,1], for drawing values such as densities.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient {
+    pub stops: &'static [Color],
+}
+
+impl Gradient {
+    /// The color at a point of the range, clamped to it.
+    pub fn sample(&self, t: f32) -> Color {
+        let last = self.stops.len() - 1;
+        if last == 0 { return self.stops[0]; }
+        let x = t.clamp(0., 1.) * last as f32;
+        let i = (x as usize).min(last - 1);
+        lerp_color(self.stops[i], self.stops[i + 1], x - i as f32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    pub name: &'static str,
+    /// Behind the world.
+    pub background: Color,
+    /// Behind panels such as the graph.
+    pub panel: Color,
+    pub panel_border: Color,
+    pub text: Color,
+    /// Secondary text, such as hints.
+    pub muted_text: Color,
+    /// Selections and other highlights.
+    pub accent: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub ok: Color,
+    /// The default gradient map for heatmaps.
+    pub gradient: Gradient,
+}
+
+impl Theme {
+    /// The lowest contrast ratio, as defined by WCAG, that data colors are drawn with.
+    pub const MIN_CONTRAST: f32 = 1.6;
+
+    pub const LIGHT: Self = Self {
+        name: "light",
+        background: Color { r: 245, g: 245, b: 245, a: 255 },
+        panel: Color { r: 255, g: 255, b: 255, a: 204 },
+        panel_border: Color { r: 130, g: 130, b: 130, a: 255 },
+        text: Color { r: 20, g: 20, b: 20, a: 255 },
+        muted_text: Color { r: 80, g: 80, b: 80, a: 255 },
+        accent: Color { r: 0, g: 82, b: 172, a: 255 },
+        warning: Color { r: 230, g: 120, b: 0, a: 255 },
+        error: Color { r: 200, g: 30, b: 40, a: 255 },
+        ok: Color { r: 0, g: 117, b: 44, a: 255 },
+        gradient: Gradient { stops: &[
+            Color { r: 255, g: 255, b: 204, a: 255 },
+            Color { r: 253, g: 141, b: 60, a: 255 },
+            Color { r: 128, g: 0, b: 38, a: 255 },
+        ] },
+    };
+
+    pub const DARK: Self = Self {
+        name: "dark",
+        background: Color { r: 24, g: 26, b: 30, a: 255 },
+        panel: Color { r: 40, g: 42, b: 48, a: 220 },
+        panel_border: Color { r: 110, g: 110, b: 120, a: 255 },
+        text: Color { r: 230, g: 230, b: 230, a: 255 },
+        muted_text: Color { r: 160, g: 160, b: 165, a: 255 },
+        accent: Color { r: 102, g: 191, b: 255, a: 255 },
+        warning: Color { r: 255, g: 170, b: 60, a: 255 },
+        error: Color { r: 255, g: 90, b: 90, a: 255 },
+        ok: Color { r: 110, g: 220, b: 120, a: 255 },
+        gradient: Gradient { stops: &[
+            Color { r: 13, g: 8, b: 135, a: 255 },
+            Color { r: 204, g: 71, b: 120, a: 255 },
+            Color { r: 240, g: 249, b: 33, a: 255 },
+        ] },
+    };
+
+    pub const ALL: [Self; 2] = [Self::LIGHT, Self::DARK];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().cloned().find(|theme| theme.name == name)
+    }
+
+    /// The theme after this one, for cycling through them.
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|theme| theme.name == self.name).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// A data color adjusted to stand out from the background.
+    ///
+    /// Colors that already do are returned unchanged.
+    pub fn contrast(&self, color: Color) -> Color {
+        let background = luminance(self.background);
+        let target = self.text;
+        let mut adjusted = color;
+        for step in 1..=10 {
+            if contrast_ratio(luminance(adjusted), background) >= Self::MIN_CONTRAST { break; }
+            adjusted = lerp_color(color, target, step as f32 / 10.);
+        }
+        Color { a: color.a, ..adjusted }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self { Self::LIGHT }
+}
+
+/// The color part of the way from one to another.
+pub fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color::new(channel(a.r, b.r), channel(a.g, b.g), channel(a.b, b.b), channel(a.a, b.a))
+}
+
+/// The relative luminance of a color, in [0,1].
+fn luminance(color: Color) -> f32 {
+    let channel = |c: u8| {
+        let c = c as f32 / 255.;
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+}
+
+fn contrast_ratio(a: f32, b: f32) -> f32 {
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_contrast() {
+        for theme in &Theme::ALL {
+            for &color in &[Color::WHITE, Color::BLACK, Color::YELLOW, Color::DARKBLUE, theme.background] {
+                let adjusted = theme.contrast(color);
+                assert!(contrast_ratio(luminance(adjusted), luminance(theme.background)) >= Theme::MIN_CONTRAST);
+            }
+        }
+        //  visible colors are left alone
+        assert_eq!(Theme::LIGHT.contrast(Color::DARKBLUE), Color::DARKBLUE);
+        assert_eq!(Theme::DARK.contrast(Color::YELLOW), Color::YELLOW);
+        assert_eq!(Theme::LIGHT.gradient.sample(2.), Theme::LIGHT.gradient.stops[2]);
+    }
+}
@@ -0,0 +1,231 @@
+//! Thinning the events of a world before they are shown, so a dense
+//! world does not bury its notices and log under thousands of them.
+//!
+//! A throttle stands between the events a world logs and what shows
+//! them, the notices of the game and the log of a run, and lets
+//! through of the events of each frame:
+//!
+//! * no more of a kind than its rate, see `Limit`, with a burst
+//!   saved up while it was quiet; extinctions, weather and records
+//!   are rare and always let through
+//! * no event near one of its kind let through a moment ago, within
+//!   `NEAR` world units and `RECENT` seconds, a crowd of deaths being
+//!   one thing to tell
+//! * the nearest to what is looked at first, when more come than are
+//!   let through, after those that happen nowhere in particular
+//!
+//! Where an event happened is where its blob is for a birth, the
+//! middle of its region for an alert and of its circle for weather,
+//! and the grave dug that tick for a death, see `memorial`.
+//!
+//! The first event of a kind let through tells how many of its kind
+//! were held back since the last one, those of the same frame too,
+//! for a notice to say `and 12 more`.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::throttle::Throttle;
+//! # let (first_tick, seconds, camera_center) = (sim.clock().tick(), 0., sim.size() / 2.);
+//! let mut throttle = Throttle::default();
+//! sim.step();
+//! for passed in throttle.filter(&sim, first_tick, seconds, Some(camera_center)) {
+//!     println!("{:?} and {} more", passed.event, passed.held);
+//! }
+//! ```
+
+use std::collections::VecDeque;
+
+use raylib::prelude::*;
+
+use crate::{
+    history::Kind,
+    memorial::Cause,
+    simulation::{Event, Simulation},
+};
+
+/// How far apart events of a kind are told apart, in world units.
+pub const NEAR: f32 = 80.;
+/// How long an event let through stands for those near it, in seconds.
+pub const RECENT: f64 = 1.;
+
+/// How many events of a kind are let through, per second and at once.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limit {
+    pub per_second: f32,
+    pub burst: f32,
+}
+
+impl Limit {
+    /// The limit of a kind, if it has one.
+    pub fn of(kind: Kind) -> Option<Self> {
+        match kind {
+            Kind::Born | Kind::Killed | Kind::Starved => Some(Self { per_second: 2., burst: 4. }),
+            Kind::Quarantined => Some(Self { per_second: 1., burst: 3. }),
+            Kind::Alert => Some(Self { per_second: 1., burst: 2. }),
+            Kind::Extinct | Kind::Weather | Kind::Record => None,
+        }
+    }
+}
+
+/// An event let through, see the module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Passed<'a> {
+    pub tick: u64,
+    pub event: &'a Event,
+    pub pos: Option<Vector2>,
+    /// How many of its kind were held back since the last let through.
+    pub held: usize,
+}
+
+/// What events were let through lately, see the module.
+#[derive(Debug, Clone, Default)]
+pub struct Throttle {
+    //  the events left of each limit, by kind, full until first used
+    tokens: Vec<(Kind, f32)>,
+    //  held back since the last of the kind let through
+    held: Vec<(Kind, usize)>,
+    //  where and when events were let through, oldest first
+    recent: VecDeque<(Kind, Vector2, f64)>,
+    //  the seconds of the last filter
+    last: Option<f64>,
+}
+
+impl Throttle {
+    /// The events of a world since a tick to let through at some
+    /// seconds, nearest to a place first, in the order they happened.
+    pub fn filter<'a>(&mut self, sim: &'a Simulation, since: u64, now: f64, focus: Option<Vector2>) -> Vec<Passed<'a>> {
+        //  refill the limits for the time since the last
+        let elapsed = self.last.map_or(0., |last| (now - last).max(0.)) as f32;
+        self.last = Some(now);
+        for (kind, tokens) in &mut self.tokens {
+            let limit = Limit::of(*kind).unwrap();
+            *tokens = (*tokens + limit.per_second * elapsed).min(limit.burst);
+        }
+        while self.recent.front().is_some_and(|&(_, _, at)| at + RECENT <= now) {
+            self.recent.pop_front();
+        }
+
+        let mut candidates: Vec<_> = locate(sim, since).into_iter().enumerate().collect();
+        let distance = |pos: Option<Vector2>| match (pos, focus) {
+            (Some(pos), Some(focus)) => (pos - focus).length(),
+            (Some(_), None) => 0.,
+            (None, _) => -1.,
+        };
+        candidates.sort_by(|(_, a), (_, b)| distance(a.pos).total_cmp(&distance(b.pos)));
+
+        let mut ret = vec![];
+        for (order, passed) in candidates {
+            let kind = Kind::of(passed.event);
+            let near = passed.pos.is_some_and(|pos| {
+                self.recent.iter().any(|&(recent, at, _)| recent == kind && (at - pos).length() < NEAR)
+            });
+            let limited = match Limit::of(kind) {
+                Some(limit) => {
+                    let tokens = match self.tokens.iter_mut().find(|(k, _)| *k == kind) {
+                        Some((_, tokens)) => tokens,
+                        None => {
+                            self.tokens.push((kind, limit.burst));
+                            &mut self.tokens.last_mut().unwrap().1
+                        },
+                    };
+                    let limited = near || *tokens < 1.;
+                    if !limited {
+                        *tokens -= 1.;
+                    }
+                    limited
+                },
+                None => false,
+            };
+            if limited {
+                match self.held.iter_mut().find(|(k, _)| *k == kind) {
+                    Some((_, held)) => *held += 1,
+                    None => self.held.push((kind, 1)),
+                }
+                continue;
+            }
+            if let Some(pos) = passed.pos {
+                self.recent.push_back((kind, pos, now));
+            }
+            ret.push((order, passed));
+        }
+        ret.sort_by_key(|&(order, _)| order);
+        //  the first of a kind let through tells of those held back
+        let mut ret: Vec<_> = ret.into_iter().map(|(_, passed)| passed).collect();
+        for passed in &mut ret {
+            if let Some((_, held)) = self.held.iter_mut().find(|(k, _)| *k == Kind::of(passed.event)) {
+                passed.held = std::mem::take(held);
+            }
+        }
+        ret
+    }
+}
+
+/// The events of a world since a tick, with where they happened if
+/// they happened somewhere, see the module.
+fn locate(sim: &Simulation, since: u64) -> Vec<Passed<'_>> {
+    let mut ret = vec![];
+    //  the graves of a tick, taken in the order the deaths were logged
+    let mut graves: Vec<_> = vec![];
+    let mut graves_tick = None;
+    for (tick, event) in sim.events_since(since) {
+        if graves_tick != Some(*tick) {
+            graves_tick = Some(*tick);
+            graves = sim.graves().filter(|grave| grave.tick == *tick).collect();
+            graves.reverse();
+        }
+        let mut grave = |killed: bool| {
+            let index = graves.iter().rposition(|grave| (grave.cause == Cause::Killed) == killed)?;
+            Some(graves.remove(index).pos)
+        };
+        let pos = match event {
+            Event::Born(key) => sim.get_blob(*key).map(|blob| blob.pos()),
+            Event::Killed { .. } => grave(true),
+            Event::Starved(_) => grave(false),
+            Event::Alert { region, .. } => sim.regions().iter().find(|r| r.name == *region).map(|r| {
+                Vector2::new(r.rect.x + r.rect.width / 2., r.rect.y + r.rect.height / 2.)
+            }),
+            Event::Weather(weather) => Some(weather.center),
+            Event::Quarantined { .. } | Event::Extinct(_) | Event::Record(_) => None,
+        };
+        ret.push(Passed { tick: *tick, event, pos, held: 0 });
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    #[test]
+    fn test_throttle() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(1000., 1000.)));
+        for i in 0..6 {
+            sim.insert_blob(BlobConfig::new(Vector2::new(100. + i as f32, 100.)));
+        }
+        let far = sim.insert_blob(BlobConfig::new(Vector2::new(900., 900.)));
+        let mut throttle = Throttle::default();
+
+        //  the crowd near each other is one birth, and the one far away another
+        let passed = throttle.filter(&sim, 0, 0., Some(Vector2::new(900., 900.)));
+        assert_eq!(passed.len(), 2);
+        assert_eq!(passed[1].event, &Event::Born(far));
+        assert_eq!((passed[0].held, passed[1].held), (5, 0));
+
+        //  a while later the births are told apart again, but only as many as the limit
+        let start = sim.clock().tick();
+        sim.step();
+        for i in 0..8 {
+            sim.insert_blob(BlobConfig::new(Vector2::new(100. * i as f32, 500.)));
+        }
+        let limit = Limit::of(Kind::Born).unwrap();
+        let passed = throttle.filter(&sim, start + 1, RECENT, Some(Vector2::zero()));
+        assert_eq!(passed.len() as f32, (limit.burst - 2. + limit.per_second * RECENT as f32).floor());
+        //  nearest to the focus first
+        assert_eq!(passed[0].pos, Some(Vector2::new(0., 500.)));
+    }
+}
@@ -0,0 +1,274 @@
+//! A parser for the subset of TOML used by the asset files.
+//!
+//! Supports comments, `[table]` and `[table.sub]` headers,
+//! `key = value` pairs, and values that are strings, numbers,
+//! booleans or (possibly nested and multi-line) arrays.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::toml::{self, Value};
+//! let table = toml::parse("speed = [40, 90]\n[colors]\nbody = \"green\"").unwrap();
+//! assert_eq!(table["colors"].get("body").and_then(Value::as_str), Some("green"));
+//! ```
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+};
+
+pub type Table = BTreeMap<String, Value>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self { Self::String(s) => Some(s), _ => None }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self { Self::Number(n) => Some(*n), _ => None }
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.as_f64().map(|n| n as f32)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self { Self::Bool(b) => Some(*b), _ => None }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self { Self::Array(a) => Some(a), _ => None }
+    }
+
+    pub fn as_table(&self) -> Option<&Table> {
+        match self { Self::Table(t) => Some(t), _ => None }
+    }
+
+    /// Look up a key if this value is a table.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.as_table()?.get(key)
+    }
+}
+
+/// Parse a TOML document into its root table.
+pub fn parse(source: &str) -> Result<Table, Error> {
+    let mut root = Table::new();
+    let mut path: Vec<String> = vec![];
+    let mut lines = source.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let error = |message: &str| Error { line: line_number, message: message.to_string() };
+        let mut line = strip_comment(line).trim().to_string();
+        if line.is_empty() { continue; }
+
+        //  table header
+        if line.starts_with('[') {
+            if !line.ends_with(']') {
+                return Err(error("unterminated table header"));
+            }
+            path = line[1..line.len() - 1]
+                .split('.')
+                .map(|part| parse_key(part.trim()).map_err(|message| error(&message)))
+                .collect::<Result<_, _>>()?;
+            table_at(&mut root, &path).map_err(|message| error(&message))?;
+            continue;
+        }
+
+        //  key = value, where arrays may span multiple lines
+        let eq = line.find('=').ok_or_else(|| error("expected `key = value`"))?;
+        while bracket_depth(&line[eq + 1..]) > 0 {
+            match lines.next() {
+                Some((_, next)) => {
+                    line.push(' ');
+                    line.push_str(strip_comment(next).trim());
+                },
+                None => return Err(error("unterminated array")),
+            }
+        }
+        let key = parse_key(line[..eq].trim()).map_err(|message| error(&message))?;
+        let (value, rest) = parse_value(line[eq + 1..].trim()).map_err(|message| error(&message))?;
+        if !rest.trim().is_empty() {
+            return Err(error(&format!("unexpected `{}` after value", rest.trim())));
+        }
+        let table = table_at(&mut root, &path).map_err(|message| error(&message))?;
+        if table.insert(key.clone(), value).is_some() {
+            return Err(error(&format!("duplicate key `{}`", key)));
+        }
+    }
+
+    Ok(root)
+}
+
+/// Remove a `#` comment that is not inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '\\' if in_string => { escaped = !escaped; continue; },
+            '"' if !escaped => in_string = !in_string,
+            '#' if !in_string => return &line[..i],
+            _ => (),
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// How many array brackets are left open, ignoring strings.
+fn bracket_depth(text: &str) -> i32 {
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            '\\' if in_string => { escaped = !escaped; continue; },
+            '"' if !escaped => in_string = !in_string,
+            '[' if !in_string => depth += 1,
+            ']' if !in_string => depth -= 1,
+            _ => (),
+        }
+        escaped = false;
+    }
+    depth
+}
+
+fn parse_key(key: &str) -> Result<String, String> {
+    if key.starts_with('"') {
+        match parse_string(key)? {
+            (key, "") => Ok(key),
+            _ => Err(format!("invalid key `{}`", key)),
+        }
+    } else if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+        Ok(key.to_string())
+    } else {
+        Err(format!("invalid key `{}`", key))
+    }
+}
+
+/// Find or create the table at a path of keys.
+fn table_at<'a>(root: &'a mut Table, path: &[String]) -> Result<&'a mut Table, String> {
+    let mut table = root;
+    for key in path {
+        let value = table.entry(key.clone()).or_insert_with(|| Value::Table(Table::new()));
+        table = match value {
+            Value::Table(t) => t,
+            _ => return Err(format!("`{}` is not a table", key)),
+        };
+    }
+    Ok(table)
+}
+
+/// Parse a value from the start of `text`, returning it with the remaining text.
+fn parse_value(text: &str) -> Result<(Value, &str), String> {
+    let text = text.trim_start();
+    if text.starts_with('"') {
+        let (s, rest) = parse_string(text)?;
+        Ok((Value::String(s), rest))
+    } else if let Some(array) = text.strip_prefix('[') {
+        let mut values = vec![];
+        let mut rest = array.trim_start();
+        loop {
+            if let Some(after) = rest.strip_prefix(']') {
+                return Ok((Value::Array(values), after));
+            }
+            let (value, after) = parse_value(rest)?;
+            values.push(value);
+            rest = after.trim_start();
+            if let Some(after) = rest.strip_prefix(',') {
+                rest = after.trim_start();
+            } else if !rest.starts_with(']') {
+                return Err("expected `,` or `]` in array".to_string());
+            }
+        }
+    } else if let Some(rest) = text.strip_prefix("true") {
+        Ok((Value::Bool(true), rest))
+    } else if let Some(rest) = text.strip_prefix("false") {
+        Ok((Value::Bool(false), rest))
+    } else {
+        let end = text.find(|c: char| c == ',' || c == ']' || c.is_whitespace()).unwrap_or(text.len());
+        let number = text[..end].replace('_', "");
+        number.parse::<f64>()
+            .map(|n| (Value::Number(n), &text[end..]))
+            .map_err(|_| format!("invalid value `{}`", &text[..end]))
+    }
+}
+
+/// Parse a double-quoted string from the start of `text`.
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let mut ret = String::new();
+    let mut chars = text.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((ret, &text[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, 'n')) => ret.push('\n'),
+                Some((_, 't')) => ret.push('\t'),
+                Some((_, '"')) => ret.push('"'),
+                Some((_, '\\')) => ret.push('\\'),
+                _ => return Err("invalid escape in string".to_string()),
+            },
+            c => ret.push(c),
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let table = parse(r#"
+            # a species
+            name = "Grazer \"green\"" # trailing comment
+            spawn_weight = 2.5
+            enabled = true
+
+            [traits]
+            speed = [40, 90]
+            colors = [
+                [1, 2, 3],
+                [4, 5, 6],
+            ]
+        "#).unwrap();
+
+        assert_eq!(table["name"].as_str(), Some("Grazer \"green\""));
+        assert_eq!(table["spawn_weight"].as_f64(), Some(2.5));
+        assert_eq!(table["enabled"].as_bool(), Some(true));
+        assert_eq!(table["traits"].get("speed"), Some(&Value::Array(vec![Value::Number(40.), Value::Number(90.)])));
+        assert_eq!(table["traits"].get("colors").and_then(Value::as_array).map(Vec::len), Some(2));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(parse("a = 1\na = 2").unwrap_err().line, 2);
+        assert_eq!(parse("a = [1, 2").unwrap_err().line, 1);
+        assert_eq!(parse("\n\nb = nope").unwrap_err().line, 3);
+    }
+}
@@ -0,0 +1,156 @@
+//! Camera tours, for demo videos and presentations.
+//!
+//! A tour is a list of keyframes, each a time in seconds from the
+//! start of the tour, the world position at the middle of the view
+//! and a zoom. Playing it moves the camera through them in turn,
+//! easing in and out of each, while the world keeps running.
+//!
+//! Tours are written one keyframe a line, with `#` starting a
+//! comment, and are loaded with `--tour`. They can also be recorded
+//! live: `J` adds the current view as a keyframe, timed from the
+//! first one, and saves the tour to `tour.txt`.
+//!
+//! ```text
+//! # seconds x y zoom
+//! 0 650 340 1
+//! 4 300 200 3.5
+//! 10 650 340 1
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use blobs::tour::Tour;
+//! # use raylib::prelude::*;
+//! # let mut camera = Camera2D { offset: Vector2::zero(), target: Vector2::zero(), rotation: 0., zoom: 1. };
+//! # let seconds = 0.;
+//! let tour = Tour::load("tour.txt")?;
+//! if let Some((center, zoom)) = tour.sample(seconds) {
+//!     camera.zoom = zoom;
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{fmt, fs, io, path::Path, str::FromStr};
+
+use raylib::prelude::*;
+
+/// Where the recorded tour is saved.
+pub const PATH: &str = "tour.txt";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Keyframe {
+    /// Seconds from the start of the tour.
+    pub time: f32,
+    /// The world position at the middle of the view.
+    pub center: Vector2,
+    pub zoom: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Tour {
+    keyframes: Vec<Keyframe>,
+}
+
+impl Tour {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn keyframes(&self) -> &[Keyframe] { &self.keyframes }
+
+    /// Add a keyframe after the others.
+    pub fn push(&mut self, keyframe: Keyframe) -> Result<(), String> {
+        if let Some(last) = self.keyframes.last().filter(|last| keyframe.time <= last.time) {
+            return Err(format!("keyframes must follow each other, got {} after {} seconds", keyframe.time, last.time));
+        }
+        if keyframe.zoom.is_nan() || keyframe.zoom <= 0. {
+            return Err(format!("the zoom must be positive, got {}", keyframe.zoom));
+        }
+        self.keyframes.push(keyframe);
+        Ok(())
+    }
+
+    /// How long the tour lasts, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0., |last| last.time)
+    }
+
+    /// The middle of the view and the zoom some seconds into the tour,
+    /// or nothing once it is over or if it has no keyframes.
+    pub fn sample(&self, time: f32) -> Option<(Vector2, f32)> {
+        let next = self.keyframes.iter().position(|keyframe| keyframe.time >= time)?;
+        let to = self.keyframes[next];
+        let from = match next.checked_sub(1) {
+            Some(previous) => self.keyframes[previous],
+            None => return Some((to.center, to.zoom)),
+        };
+        let t = (time - from.time) / (to.time - from.time);
+        //  ease in and out, and zoom by the same factor every second
+        let t = t * t * (3. - 2. * t);
+        let center = from.center + (to.center - from.center) * t;
+        Some((center, from.zoom * (to.zoom / from.zoom).powf(t)))
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        fs::read_to_string(path)?.parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl fmt::Display for Tour {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# seconds x y zoom")?;
+        for Keyframe { time, center, zoom } in &self.keyframes {
+            writeln!(f, "{} {} {} {}", time, center.x, center.y, zoom)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Tour {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut ret = Self::new();
+        for (i, line) in s.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() { continue; }
+            let invalid = |reason: String| format!("line {}: {}", i + 1, reason);
+            let numbers = line.split_whitespace()
+                .map(|part| part.parse::<f32>().map_err(|_| invalid(format!("`{}` is not a number", part))))
+                .collect::<Result<Vec<_>, _>>()?;
+            match numbers[..] {
+                [time, x, y, zoom] => ret.push(Keyframe { time, center: Vector2::new(x, y), zoom }).map_err(invalid)?,
+                _ => return Err(invalid(format!("expected seconds, x, y and zoom, got `{}`", line))),
+            }
+        }
+        Ok(ret)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tour() {
+        let tour: Tour = "# a tour\n0 0 0 1\n\n2 100 50 4 # closer\n".parse().unwrap();
+        assert_eq!(tour.duration(), 2.);
+        assert_eq!(tour.to_string().parse::<Tour>(), Ok(tour.clone()));
+        assert_eq!(tour.sample(0.), Some((Vector2::zero(), 1.)));
+        assert_eq!(tour.sample(1.), Some((Vector2::new(50., 25.), 2.)));
+        assert_eq!(tour.sample(2.), Some((Vector2::new(100., 50.), 4.)));
+        assert_eq!(tour.sample(2.5), None);
+        //  easing starts slowly
+        assert!(tour.sample(0.2).unwrap().0.x < 20.);
+
+        assert!("0 0 0".parse::<Tour>().unwrap_err().starts_with("line 1"));
+        assert!("1 0 0 1\n0 0 0 1".parse::<Tour>().is_err());
+        assert!("0 0 0 0".parse::<Tour>().is_err());
+        assert_eq!(Tour::new().sample(0.), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Round-robin tournaments between exported blobs.
+//!
+//! Every pair of contestants meets in the same arena, twice, with
+//! their starting sides swapped, and nothing is drawn. A match
+//! ends when its ticks run out, and each blob scores for how long
+//! it survived, the food it ate and the blobs it killed. The
+//! ranking is by total points over all matches:
+//!
+//! * `SURVIVAL_POINTS` for surviving a whole match, less for dying early
+//! * `FOOD_POINTS` for each food eaten
+//! * `KILL_POINTS` for each kill
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::io;
+//! # use blobs::tournament::{self, Arena, Contestant};
+//! let contestants = vec![Contestant::load("ada.blob")?, Contestant::load("bob.blob")?];
+//! let scores = tournament::run(&Arena::default(), &contestants);
+//! tournament::write_table(&mut io::stdout(), &Arena::default(), &contestants, &scores)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::{
+    io::{self, Write},
+    path::Path,
+};
+
+use rand::Rng;
+
+use raylib::prelude::*;
+
+use crate::{
+    exchange::Exported,
+    simulation::{Simulation, SimulationConfig, Event},
+    light::Light,
+};
+
+pub const SURVIVAL_POINTS: f32 = 100.;
+pub const FOOD_POINTS: f32 = 5.;
+pub const KILL_POINTS: f32 = 50.;
+
+/// The scenario every match is played in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arena {
+    pub size: Vector2,
+    /// The seed of every match, so all pairs meet the same food.
+    pub seed: u64,
+    /// Foods placed before a match starts.
+    pub foods: usize,
+    /// Ticks between foods added during a match.
+    pub food_period: u64,
+    /// How long a match lasts.
+    pub ticks: u64,
+    /// Lamps placed before a match starts.
+    pub lights: Vec<Light>,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self { size: Vector2::new(600., 600.), seed: 1, foods: 40, food_period: 30, ticks: 3600, lights: vec![] }
+    }
+}
+
+/// A blob taking part, with the label it is ranked by.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contestant {
+    pub label: String,
+    pub blob: Exported,
+}
+
+impl Contestant {
+    /// Load an exported blob, labeled by its file name.
+    pub fn load<P: AsRef<Path> + ?Sized>(path: &P) -> Result<Self, String> {
+        let path = path.as_ref();
+        let label = path.file_stem().map_or_else(|| path.display().to_string(), |stem| stem.to_string_lossy().into_owned());
+        Ok(Self { label, blob: Exported::load(path)? })
+    }
+}
+
+/// What a contestant achieved over its matches.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Score {
+    pub matches: u32,
+    /// Ticks survived, over all matches.
+    pub survived: u64,
+    pub foods: u32,
+    pub kills: u32,
+}
+
+impl Score {
+    pub fn points(&self, arena: &Arena) -> f32 {
+        SURVIVAL_POINTS * self.survived as f32 / arena.ticks as f32
+            + FOOD_POINTS * self.foods as f32
+            + KILL_POINTS * self.kills as f32
+    }
+
+    fn add(&mut self, other: &Score) {
+        self.matches += other.matches;
+        self.survived += other.survived;
+        self.foods += other.foods;
+        self.kills += other.kills;
+    }
+}
+
+/// Play a match between two blobs, returning their scores.
+pub fn play(arena: &Arena, left: &Exported, right: &Exported) -> [Score; 2] {
+    let config = SimulationConfig { seed: arena.seed, lights: arena.lights.clone(), ..SimulationConfig::new(arena.size) };
+    let mut sim = Simulation::new(config);
+    for _ in 0..arena.foods {
+        let pos = Vector2::new(sim.rng().gen::<f32>(), sim.rng().gen::<f32>()) * arena.size;
+        sim.insert_food(pos);
+    }
+    //  the blobs are named by their side, as contestants may share a name
+    let sides = ["left", "right"];
+    let keys = [
+        left.insert(&mut sim, Vector2::new(arena.size.x / 4., arena.size.y / 2.)),
+        right.insert(&mut sim, Vector2::new(arena.size.x * 3. / 4., arena.size.y / 2.)),
+    ];
+    for (&key, side) in keys.iter().zip(&sides) {
+        sim.get_blob_mut(key).unwrap().name = Some(side.to_string());
+    }
+
+    let mut scores = [Score { matches: 1, ..Score::default() }; 2];
+    for tick in 1..=arena.ticks {
+        sim.step();
+        if tick % arena.food_period == 0 {
+            let pos = Vector2::new(sim.rng().gen::<f32>(), sim.rng().gen::<f32>()) * arena.size;
+            sim.insert_food(pos);
+        }
+        let mut alive = false;
+        for (score, &key) in scores.iter_mut().zip(&keys) {
+            if let Some(blob) = sim.get_blob(key) {
+                score.survived = tick;
+                score.foods = blob.foods_eaten;
+                alive = true;
+            }
+        }
+        if !alive {
+            break;
+        }
+    }
+    for (_, event) in sim.events_since(0) {
+        if let Event::Killed { killer: Some(killer), .. } = event {
+            if let Some(i) = sides.iter().position(|side| side == killer) {
+                scores[i].kills += 1;
+            }
+        }
+    }
+    scores
+}
+
+/// Play every pair of contestants on both sides, returning their total scores.
+pub fn run(arena: &Arena, contestants: &[Contestant]) -> Vec<Score> {
+    let mut ret = vec![Score::default(); contestants.len()];
+    for a in 0..contestants.len() {
+        for b in a + 1..contestants.len() {
+            for &(left, right) in &[(a, b), (b, a)] {
+                let [left_score, right_score] = play(arena, &contestants[left].blob, &contestants[right].blob);
+                ret[left].add(&left_score);
+                ret[right].add(&right_score);
+            }
+        }
+    }
+    ret
+}
+
+/// Write the contestants as a table ranked by points.
+pub fn write_table<W: Write>(out: &mut W, arena: &Arena, contestants: &[Contestant], scores: &[Score]) -> io::Result<()> {
+    let mut ranked: Vec<_> = contestants.iter().zip(scores).collect();
+    ranked.sort_by(|(_, a), (_, b)| b.points(arena).partial_cmp(&a.points(arena)).unwrap());
+    let width = contestants.iter().map(|c| c.label.len()).max().unwrap_or(0).max("blob".len());
+    writeln!(out, "rank  {:width$}  points  matches  survived  food  kills", "blob", width = width)?;
+    for (i, (contestant, score)) in ranked.iter().enumerate() {
+        writeln!(out, "{:>4}  {:width$}  {:>6.1}  {:>7}  {:>7.0}%  {:>4}  {:>5}",
+            i + 1, contestant.label, score.points(arena), score.matches,
+            100. * score.survived as f32 / (arena.ticks * score.matches.max(1) as u64) as f32,
+            score.foods, score.kills,
+            width = width,
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, SimulationConfig};
+
+    fn contestant(label: &str, speed: f32, max_hunger: f32) -> Contestant {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        //  harmless, so matches come down to hunger
+        let key = sim.insert_blob(BlobConfig { speed, max_hunger, attack: 0., ..BlobConfig::new(Vector2::new(100., 100.)) });
+        Contestant { label: label.to_string(), blob: Exported::from_blob(sim.get_blob(key).unwrap(), sim.parameters()) }
+    }
+
+    #[test]
+    fn test_tournament() {
+        let arena = Arena { ticks: 600, ..Arena::default() };
+        let contestants = vec![contestant("frail", 50., 0.5), contestant("hardy", 50., 1000.), contestant("still", 0., 1000.)];
+        let scores = run(&arena, &contestants);
+        assert!(scores.iter().all(|score| score.matches == 4));
+        //  a blob that starves at once survives less than one that cannot starve in time
+        assert!(scores[0].survived < scores[1].survived);
+        assert_eq!(scores[1].survived, 4 * arena.ticks);
+        //  matches are the same every time
+        assert_eq!(run(&arena, &contestants), scores);
+
+        let mut out = vec![];
+        write_table(&mut out, &arena, &contestants, &scores).unwrap();
+        let table = String::from_utf8(out).unwrap();
+        assert!(table.starts_with("rank  blob "));
+        assert_eq!(table.lines().count(), 4);
+        assert!(table.lines().nth(3).unwrap().contains("frail"));
+    }
+}
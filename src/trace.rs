@@ -0,0 +1,206 @@
+//! A log of what one blob perceived, decided and was pushed by at
+//! every tick, to tell why it did what it did.
+//!
+//! With a blob selected, `L` traces it, and again stops. From then on
+//! each tick adds an `Entry`: how many blobs and food it saw and how
+//! many blobs it heard, how far the nearest food and threat were and
+//! how hungry it was, which is what its brain decides from; whether it
+//! thought that tick or went on with its last decision, the mood and
+//! direction it chose; and the velocity force fields gave it, how much
+//! auras slowed it, where it went and how fast. The latest `LENGTH` entries are kept and shown
+//! in a panel that scrolls with the wheel, and shift and `L` writes
+//! them to a file.
+//!
+//! Perception is only looked at on the ticks a blob thinks, see
+//! `Timing::brain_period`, so on others the entry tells what the last
+//! decision was made from. A trace ends when its blob is removed, and
+//! is not saved with the world.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # let key = sim.insert_blob(BlobConfig::new(Vector2::new(100., 100.)));
+//! sim.trace_blob(Some(key));
+//! sim.step();
+//! let entry = sim.trace().unwrap().entries().back().unwrap();
+//! println!("{}", entry);
+//! ```
+
+use std::{collections::VecDeque, fmt, fs, io::{self, Write}};
+
+use raylib::prelude::*;
+
+use crate::{keyed_set::Key, simulation::{Blob, Mood}};
+
+/// How many entries a trace keeps.
+pub const LENGTH: usize = 3600;
+
+/// What a blob perceived when it last looked around.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Perceived {
+    pub blobs_seen: usize,
+    pub food_seen: usize,
+    pub heard: usize,
+    /// How far the nearest thing it eats and the nearest blob it does
+    /// not eat were.
+    pub nearest_food: Option<f32>,
+    pub nearest_threat: Option<f32>,
+}
+
+/// A tick of a traced blob.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Entry {
+    pub tick: u64,
+    pub perceived: Perceived,
+    /// Whether it decided anew this tick.
+    pub thought: bool,
+    /// Its hunger as a part of the most it can have.
+    pub hunger: f32,
+    pub mood: Mood,
+    /// The direction it chose to turn toward, if any.
+    pub target: Option<Vector2>,
+    /// The velocity force fields gave it, see `influence` and `aura`.
+    pub pushed: Vector2,
+    /// The part of its speed auras took from it.
+    pub slowed: f32,
+    /// Where it was and how it moved at the end of the tick.
+    pub pos: Vector2,
+    pub heading: Vector2,
+    pub velocity: Vector2,
+}
+
+impl fmt::Display for Entry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let distance = |distance: Option<f32>| distance.map_or("-".to_string(), |distance| format!("{:.0}", distance));
+        let p = &self.perceived;
+        write!(f, "tick={} {} seen={}b,{}f heard={} food={} threat={} hunger={:.2} mood={} target={} pushed={:.1},{:.1} slowed={:.2} pos={:.1},{:.1} heading={:.2},{:.2} velocity={:.1},{:.1}",
+            self.tick, if self.thought { "thought" } else { "kept" }, p.blobs_seen, p.food_seen, p.heard,
+            distance(p.nearest_food), distance(p.nearest_threat), self.hunger, self.mood.name(),
+            self.target.map_or("-".to_string(), |target| format!("{:.2},{:.2}", target.x, target.y)),
+            self.pushed.x, self.pushed.y, self.slowed, self.pos.x, self.pos.y, self.heading.x, self.heading.y, self.velocity.x, self.velocity.y,
+        )
+    }
+}
+
+/// The latest ticks of a blob, see the module.
+#[derive(Debug, Clone)]
+pub struct Trace {
+    blob: Key<Blob>,
+    entries: VecDeque<Entry>,
+    /// Whether the blob was removed, which ends the trace.
+    ended: bool,
+}
+
+impl Trace {
+    pub fn new(blob: Key<Blob>) -> Self {
+        Self { blob, entries: VecDeque::new(), ended: false }
+    }
+
+    pub fn blob(&self) -> Key<Blob> { self.blob }
+
+    pub fn entries(&self) -> &VecDeque<Entry> { &self.entries }
+
+    pub fn ended(&self) -> bool { self.ended }
+
+    pub fn push(&mut self, entry: Entry) {
+        if self.entries.len() == LENGTH {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn end(&mut self) {
+        self.ended = true;
+    }
+
+    /// Write the entries a line each, oldest first.
+    pub fn write<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(out, "# trace of blob {}", self.blob.index())?;
+        for entry in &self.entries {
+            writeln!(out, "{}", entry)?;
+        }
+        Ok(())
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        self.write(&mut file)?;
+        file.flush()
+    }
+}
+
+/// The scrolling of the trace panel, in lines up from the latest.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct View {
+    pub scroll: usize,
+}
+
+impl View {
+    /// How many lines the panel shows.
+    pub const LINES: usize = 16;
+    pub const FONT_SIZE: i32 = 14;
+
+    /// Where the panel is drawn, along the bottom of the window.
+    pub fn rect(screen_width: i32, screen_height: i32) -> Rectangle {
+        let height = (Self::LINES as i32 + 1) * Self::FONT_SIZE + 8;
+        Rectangle::new(10., (screen_height - height - 40) as f32, (screen_width - 20) as f32, height as f32)
+    }
+
+    /// Scroll by the wheel, up into older entries.
+    pub fn scroll(&mut self, wheel: f32, trace: &Trace) {
+        let most = trace.entries().len().saturating_sub(Self::LINES);
+        self.scroll = (self.scroll as isize + (wheel * 3.) as isize).clamp(0, most as isize) as usize;
+    }
+
+    /// The lines shown, oldest first, with the title.
+    pub fn lines(&self, trace: &Trace) -> (String, Vec<String>) {
+        let entries = trace.entries();
+        let end = entries.len().saturating_sub(self.scroll);
+        let start = end.saturating_sub(Self::LINES);
+        let title = format!("Trace of blob {}{}, ticks {} of {}, scroll for older, shift L to save",
+            trace.blob().index(), if trace.ended() { " (gone)" } else { "" }, end - start, entries.len());
+        (title, entries.range(start..end).map(Entry::to_string).collect())
+    }
+}
+
+/// Where shift and `L` saves a trace.
+pub fn path(blob: Key<Blob>, tick: u64) -> String {
+    format!("trace-{}-{}.txt", blob.index(), tick)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, Simulation, SimulationConfig};
+
+    #[test]
+    fn test_trace() {
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        let blob = sim.insert_blob(BlobConfig { pov: 180., ..BlobConfig::new(Vector2::new(100., 100.)) });
+        sim.insert_food(Vector2::new(120., 100.));
+        sim.trace_blob(Some(blob));
+        for _ in 0..3 {
+            sim.step();
+        }
+        let trace = sim.trace().unwrap();
+        let ticks: Vec<_> = trace.entries().iter().map(|entry| entry.tick).collect();
+        assert_eq!(ticks, [0, 1, 2]);
+        let first = trace.entries()[0];
+        assert!(first.thought && first.perceived.food_seen == 1 && first.perceived.nearest_food.is_some());
+
+        let mut view = View::default();
+        view.scroll(10., trace);
+        let (title, lines) = view.lines(trace);
+        assert!(title.contains("blob 0") && lines.len() == 3 && lines[0].starts_with("tick=0 thought"));
+        let mut text = vec![];
+        trace.write(&mut text).unwrap();
+        assert_eq!(String::from_utf8(text).unwrap().lines().count(), 4);
+
+        sim.remove_blob(blob);
+        sim.step();
+        assert!(sim.trace().unwrap().ended() && sim.trace().unwrap().entries().len() == 3);
+    }
+}
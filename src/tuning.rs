@@ -0,0 +1,170 @@
+//! A panel of sliders for tuning parameters while the world runs.
+//!
+//! Dragging a slider changes its parameter from the next tick on,
+//! in every world, and letting go marks the change on the
+//! statistics timeline so the graph shows what it did. Values snap
+//! to steps of `STEP` and are applied through `Parameters::set`,
+//! as if given on the command line.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let captured = panel.captures(&frame, screen_height);
+//! if let Some(change) = panel.update(&frame, sim.parameters(), screen_height) {
+//!     sim.set_parameter(change.name, &change.value)?;
+//!     if change.done {
+//!         sim.mark(&change.label());
+//!     }
+//! }
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    window::DrawingContext,
+    fonts::Fonts,
+    input::{Button, Frame},
+    simulation::Parameters,
+    theme::Theme,
+};
+
+/// A parameter and the range its slider covers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slider {
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+}
+
+pub const SLIDERS: [Slider; 6] = [
+    Slider { name: "food_rate", min: 0., max: 4. },
+    Slider { name: "mutation_rate", min: 0., max: 1. },
+    Slider { name: "metabolism_scale", min: 0., max: 3. },
+    Slider { name: "predation_efficiency", min: 0., max: 1. },
+    Slider { name: "food_lifetime", min: 0., max: 120. },
+    Slider { name: "speciation", min: 0., max: 2. },
+];
+/// What values snap to.
+pub const STEP: f32 = 0.05;
+
+const WIDTH: f32 = 300.;
+const ROW_HEIGHT: f32 = 34.;
+const PADDING: f32 = 8.;
+const FONT_SIZE: i32 = 10;
+
+/// A parameter set by a slider.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub name: &'static str,
+    pub value: String,
+    /// Whether the slider was let go, ending the change.
+    pub done: bool,
+}
+
+impl Change {
+    /// How the change is marked on the timeline.
+    pub fn label(&self) -> String { format!("{} {}", self.name, self.value) }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TuningPanel {
+    //  the slider being dragged and the value it started from
+    dragging: Option<(usize, String)>,
+}
+
+impl TuningPanel {
+    pub fn new() -> Self { Self::default() }
+
+    /// Where the panel is, at the bottom left above the clock.
+    pub fn rect(screen_height: i32) -> Rectangle {
+        let height = 2. * PADDING + 14. + SLIDERS.len() as f32 * ROW_HEIGHT;
+        Rectangle::new(10., screen_height as f32 - 110. - height, WIDTH, height)
+    }
+
+    fn track(i: usize, screen_height: i32) -> Rectangle {
+        let rect = Self::rect(screen_height);
+        let y = rect.y + PADDING + 14. + i as f32 * ROW_HEIGHT + 16.;
+        Rectangle::new(rect.x + PADDING, y, rect.width - 2. * PADDING, 10.)
+    }
+
+    /// Whether the panel takes the mouse in this frame, leaving it to no gesture.
+    pub fn captures(&self, frame: &Frame, screen_height: i32) -> bool {
+        self.dragging.is_some()
+            || (frame.button == Button::Pressed && Self::rect(screen_height).check_collision_point_rec(frame.mouse))
+    }
+
+    /// Read this frame's input, returning the parameter it changes.
+    pub fn update(&mut self, frame: &Frame, parameters: &Parameters, screen_height: i32) -> Option<Change> {
+        if frame.button == Button::Pressed {
+            let row = |i: &usize| {
+                let track = Self::track(*i, screen_height);
+                Rectangle { y: track.y - 8., height: track.height + 16., ..track }.check_collision_point_rec(frame.mouse)
+            };
+            self.dragging = (0..SLIDERS.len()).find(row).map(|i| (i, format(parameters.number(SLIDERS[i].name).unwrap())));
+        }
+        let (i, start) = self.dragging.clone()?;
+        let slider = SLIDERS[i];
+        let track = Self::track(i, screen_height);
+        let t = ((frame.mouse.x - track.x) / track.width).clamp(0., 1.);
+        let value = format(slider.min + t * (slider.max - slider.min));
+        if !frame.is_button_down() {
+            self.dragging = None;
+            return Some(Change { name: slider.name, value, done: true }).filter(|change| change.value != start);
+        }
+        let current = format(parameters.number(slider.name).unwrap());
+        Some(Change { name: slider.name, value, done: false }).filter(|change| change.value != current)
+    }
+
+    pub fn draw(&self, draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts, parameters: &Parameters) {
+        let screen_height = draw.get_screen_height();
+        let rect = Self::rect(screen_height);
+        draw.draw_rectangle_rec(rect, theme.panel);
+        draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+        fonts.draw_text(draw, "Tuning (K to close)", (rect.x + PADDING) as i32, (rect.y + PADDING) as i32, FONT_SIZE, theme.text);
+        for (i, slider) in SLIDERS.iter().enumerate() {
+            let track = Self::track(i, screen_height);
+            let value = parameters.number(slider.name).unwrap();
+            let active = self.dragging.as_ref().is_some_and(|(dragged, _)| *dragged == i);
+            let label = format!("{} {}", slider.name, format(value));
+            fonts.draw_text(draw, &label, track.x as i32, track.y as i32 - 14, FONT_SIZE, if active { theme.accent } else { theme.text });
+            draw.draw_rectangle_rec(Rectangle { y: track.y + track.height / 2. - 1., height: 2., ..track }, theme.muted_text);
+            //  values beyond the range, as given on the command line, pin the knob to its end
+            let t = ((value - slider.min) / (slider.max - slider.min)).clamp(0., 1.);
+            let knob = Vector2::new(track.x + t * track.width, track.y + track.height / 2.);
+            draw.draw_circle_v(knob, track.height / 2., if active { theme.accent } else { theme.text });
+        }
+    }
+}
+
+/// A value snapped to the step, as text.
+fn format(value: f32) -> String {
+    format!("{:.2}", (value / STEP).round() * STEP)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sliders() {
+        let parameters = Parameters::default();
+        for slider in &SLIDERS {
+            assert!(parameters.number(slider.name).is_some(), "{}", slider.name);
+            assert!(Parameters::default().set(slider.name, &format(slider.max)).is_ok());
+        }
+        assert_eq!(format(0.337), "0.35");
+        assert_eq!(format(1.), "1.00");
+
+        let mut panel = TuningPanel::new();
+        let track = TuningPanel::track(0, 720);
+        let mut press = Frame::idle(0., Vector2::new(track.x + track.width / 2., track.y));
+        press.button = Button::Pressed;
+        assert!(panel.captures(&press, 720));
+        let change = panel.update(&press, &parameters, 720).unwrap();
+        assert_eq!(change, Change { name: "food_rate", value: "2.00".to_string(), done: false });
+        let release = Frame::idle(0., Vector2::new(track.x - 10., track.y));
+        assert_eq!(panel.update(&release, &parameters, 720).unwrap().value, "0.00");
+        assert!(!panel.captures(&release, 720));
+        assert_eq!(panel.update(&release, &parameters, 720), None);
+    }
+}
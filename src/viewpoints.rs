@@ -0,0 +1,63 @@
+//! Numbered viewpoints of a world, to watch several places in turn.
+//!
+//! `Ctrl+Shift` with a number key from 1 to 9 saves the current view
+//! under that number, and `Ctrl` with the number brings it back. A
+//! viewpoint is the world position at the middle of the first view and
+//! a zoom, like a keyframe of a tour, so it still frames the same
+//! place in a window of another size. Viewpoints belong to the world
+//! and are saved and loaded with it.
+//!
+//! # Example
+//!
+//! ```ignore
+//! sim.viewpoints_mut().set(3, Viewpoint { center: Vector2::new(650., 340.), zoom: 2. })?;
+//! if let Some(viewpoint) = sim.viewpoints().get(3) {
+//!     camera.zoom = viewpoint.zoom;
+//! }
+//! ```
+
+use raylib::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewpoint {
+    /// The world position at the middle of the view.
+    pub center: Vector2,
+    pub zoom: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Viewpoints {
+    //  by number, from 1
+    slots: [Option<Viewpoint>; Self::COUNT],
+}
+
+impl Viewpoints {
+    /// How many viewpoints a world keeps, one for each number key but 0.
+    pub const COUNT: usize = 9;
+    /// The number keys, from 1.
+    pub const KEYS: [KeyboardKey; Self::COUNT] = [
+        KeyboardKey::KEY_ONE, KeyboardKey::KEY_TWO, KeyboardKey::KEY_THREE,
+        KeyboardKey::KEY_FOUR, KeyboardKey::KEY_FIVE, KeyboardKey::KEY_SIX,
+        KeyboardKey::KEY_SEVEN, KeyboardKey::KEY_EIGHT, KeyboardKey::KEY_NINE,
+    ];
+
+    pub fn get(&self, number: usize) -> Option<Viewpoint> {
+        self.slots.get(number.checked_sub(1)?).copied().flatten()
+    }
+
+    /// Save a viewpoint under a number from 1 to `COUNT`.
+    pub fn set(&mut self, number: usize, viewpoint: Viewpoint) -> Result<(), String> {
+        if viewpoint.zoom.is_nan() || viewpoint.zoom <= 0. {
+            return Err(format!("the zoom must be positive, got {}", viewpoint.zoom));
+        }
+        let slot = number.checked_sub(1).and_then(|i| self.slots.get_mut(i))
+            .ok_or_else(|| format!("viewpoints are numbered from 1 to {}, got {}", Self::COUNT, number))?;
+        *slot = Some(viewpoint);
+        Ok(())
+    }
+
+    /// The viewpoints saved, with their numbers.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Viewpoint)> + '_ {
+        self.slots.iter().enumerate().filter_map(|(i, slot)| slot.map(|viewpoint| (i + 1, viewpoint)))
+    }
+}
@@ -0,0 +1,133 @@
+//! What a single selected blob sees, as a strip like the view of a
+//! raycaster.
+//!
+//! The strip spans the blob's field of view from its left to its
+//! right, split into `COLUMNS`. Each column shows the nearest thing
+//! the blob saw along that direction when it last looked around, in
+//! its color and the dimmer and shorter the farther it is, out to the
+//! depth of the blob's sight. Columns where it saw nothing are empty.
+//! It shows what the brain was shown, not what is around the blob, so
+//! it is also a way to check the perception code.
+//!
+//! # Example
+//!
+//! ```ignore
+//! let blob = sim.get_blob(selected).unwrap();
+//! let sightings = sim.sightings(selected);
+//! let hits = vision::cast(blob.pos(), blob.direction(), blob.pov, blob.sight_depth(), &sightings, vision::COLUMNS);
+//! vision::draw(&mut draw, &theme, &fonts, blob, &sightings, Vector2::new(500., 10.));
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{
+    simulation::{Blob, Sighting},
+    theme::{self, Theme},
+    fonts::Fonts,
+};
+
+/// How many directions the strip shows.
+pub const COLUMNS: usize = 64;
+const COLUMN_WIDTH: f32 = 5.;
+const STRIP_HEIGHT: f32 = 48.;
+const PADDING: f32 = 10.;
+const FONT_SIZE: i32 = 16;
+/// How tall the panel is.
+pub const HEIGHT: f32 = STRIP_HEIGHT + FONT_SIZE as f32 + 3. * PADDING;
+/// How little of its color and height the farthest thing seen keeps.
+const FAR: f32 = 0.2;
+
+/// The nearest thing seen along a direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    pub color: Color,
+    pub distance: f32,
+    pub food: bool,
+}
+
+/// Where the nearest thing along a ray from a point is hit, if any.
+fn hit_distance(origin: Vector2, ray: Vector2, sighting: &Sighting) -> Option<f32> {
+    let to_center = sighting.pos - origin;
+    let along = to_center.dot(ray);
+    let off_sq = to_center.length_sqr() - along * along;
+    let radius_sq = sighting.radius * sighting.radius;
+    if off_sq > radius_sq { return None; }
+    let half_chord = (radius_sq - off_sq).sqrt();
+    if along + half_chord < 0. { return None; }
+    //  from inside a circle it is hit at once
+    Some((along - half_chord).max(0.))
+}
+
+/// What is seen in each of some columns across a field of view, from
+/// a point looking along a heading, with the field of view in degrees
+/// to either side as blobs have it.
+pub fn cast(pos: Vector2, heading: Vector2, pov: f32, depth: f32, sightings: &[Sighting], columns: usize) -> Vec<Option<Hit>> {
+    //  a blob that has not moved yet looks along x
+    let facing = if heading.length_sqr() > 0. { heading.y.atan2(heading.x) } else { 0. };
+    let half = pov.min(180.).to_radians();
+    (0..columns)
+        .map(|column| {
+            let angle = facing - half + (column as f32 + 0.5) / columns as f32 * 2. * half;
+            let ray = Vector2::new(angle.cos(), angle.sin());
+            sightings.iter()
+                .filter_map(|sighting| Some((hit_distance(pos, ray, sighting)?, sighting)))
+                .filter(|&(distance, _)| distance <= depth)
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(distance, sighting)| Hit { color: sighting.color, distance, food: sighting.food })
+        })
+        .collect()
+}
+
+/// Draw the strip of a blob under a title, with its corner at a
+/// position.
+pub fn draw<D: RaylibDraw>(draw: &mut D, theme: &Theme, fonts: &Fonts, blob: &Blob, sightings: &[Sighting], pos: Vector2) {
+    let hits = cast(blob.pos(), blob.direction(), blob.pov, blob.sight_depth(), sightings, COLUMNS);
+    let width = COLUMNS as f32 * COLUMN_WIDTH;
+    let rect = Rectangle::new(pos.x, pos.y, width + 2. * PADDING, HEIGHT);
+    draw.draw_rectangle_rec(rect, theme.panel);
+    draw.draw_rectangle_lines_ex(rect, 1, theme.panel_border);
+    let title = format!("What {} sees", blob.name.as_deref().unwrap_or("it"));
+    fonts.draw_text(draw, &title, (pos.x + PADDING) as i32, (pos.y + PADDING) as i32, FONT_SIZE, theme.text);
+
+    let strip = Rectangle::new(pos.x + PADDING, pos.y + 2. * PADDING + FONT_SIZE as f32, width, STRIP_HEIGHT);
+    draw.draw_rectangle_rec(strip, theme.background);
+    let middle = strip.y + strip.height / 2.;
+    for (column, hit) in hits.into_iter().enumerate() {
+        let hit = match hit {
+            Some(hit) => hit,
+            None => continue,
+        };
+        let nearness = 1. - (1. - FAR) * (hit.distance / blob.sight_depth()).min(1.);
+        //  food lies low, so it is drawn below the middle
+        let height = strip.height * nearness * if hit.food { 0.5 } else { 1. };
+        let top = if hit.food { middle + strip.height / 2. - height } else { middle - height / 2. };
+        let color = theme::lerp_color(theme.background, hit.color, nearness);
+        draw.draw_rectangle_rec(Rectangle::new(strip.x + column as f32 * COLUMN_WIDTH, top, COLUMN_WIDTH, height), color);
+    }
+    draw.draw_rectangle_lines_ex(strip, 1, theme.panel_border);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vision() {
+        let sighting = |x: f32, y: f32, color: Color| Sighting { pos: Vector2::new(x, y), radius: 5., color, food: false };
+        //  a red blob straight ahead, a nearer blue one to the right, and a green one out of reach
+        let sightings = [sighting(50., 0., Color::RED), sighting(20., 20., Color::BLUE), sighting(0., -200., Color::GREEN)];
+        let hits = cast(Vector2::zero(), Vector2::new(1., 0.), 90., 100., &sightings, 9);
+        assert_eq!(hits.len(), 9);
+        assert_eq!(hits[0], None);
+        let ahead = hits[4].unwrap();
+        assert_eq!(ahead.color, Color::RED);
+        assert!((ahead.distance - 45.).abs() < 1., "{:?}", ahead);
+        //  y grows downward, so positive angles are to the right
+        assert_eq!(hits[6].map(|hit| hit.color), Some(Color::BLUE));
+        assert!(hits.iter().flatten().all(|hit| hit.color != Color::GREEN));
+
+        //  from inside a circle it fills the view
+        let hits = cast(Vector2::new(50., 0.), Vector2::zero(), 90., 100., &sightings[..1], 4);
+        assert!(hits.iter().all(|hit| hit.is_some_and(|hit| hit.distance == 0.)));
+    }
+}
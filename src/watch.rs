@@ -0,0 +1,249 @@
+//! Named regions of a world to keep an eye on, and alerts for when
+//! what happens in them crosses a threshold.
+//!
+//! In the Watch mode, `5`, dragging a rectangle over a world opens a
+//! text box to name it, followed by any alerts, like `nest
+//! predators>3 food<10`, and `-name` in the box stops watching a
+//! region. `--watch` does the same from the command line, with the
+//! rectangle after the name, as in `nest@100,100,200,200 predators>3`.
+//!
+//! The simulation records, with its other statistics, each of
+//! `Measure::ALL` for each region as the series `<measure> in <name>`,
+//! births and deaths counting those since the last sample. An alert
+//! is checked at each sample and fires once when its condition starts
+//! to hold, as an `Event::Alert`, and again only after it stopped to.
+//! Regions are saved and loaded with the world.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::watch::Region;
+//! # let tick = sim.clock().tick();
+//! sim.watch(Region::parse("nest@100,100,200,200 predators>3", None)?);
+//! for (_, event) in sim.events_since(tick) {
+//!     if let Event::Alert { region, alert, value } = event {
+//!         println!("{}: {} at {}", region, alert, value);
+//!     }
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::fmt;
+
+use raylib::prelude::*;
+
+use crate::{
+    fonts::Fonts,
+    simulation::Simulation,
+    theme::Theme,
+};
+
+/// The longest name of a region.
+pub const MAX_NAME_LEN: usize = 20;
+
+const FONT_HEIGHT: i32 = 20;
+
+/// What is measured in a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Measure {
+    Population,
+    Food,
+    /// The blobs that eat other blobs.
+    Predators,
+    Births,
+    Deaths,
+}
+
+impl Measure {
+    pub const ALL: [Self; 5] = [Self::Population, Self::Food, Self::Predators, Self::Births, Self::Deaths];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Population => "population",
+            Self::Food => "food",
+            Self::Predators => "predators",
+            Self::Births => "births",
+            Self::Deaths => "deaths",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|measure| measure.name() == name)
+    }
+}
+
+/// A threshold of a measure, crossed upward or downward.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Alert {
+    pub measure: Measure,
+    /// Whether the alert fires above the threshold, or below.
+    pub above: bool,
+    pub threshold: f32,
+    //  whether the condition held at the last sample
+    holding: bool,
+}
+
+impl Alert {
+    /// An alert written as `<measure>><threshold>` or `<measure><<threshold>`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (measure, threshold, above) = match (spec.split_once('>'), spec.split_once('<')) {
+            (Some((measure, threshold)), None) => (measure, threshold, true),
+            (None, Some((measure, threshold))) => (measure, threshold, false),
+            _ => return Err(format!("expected an alert like predators>3 or food<10, got `{}`", spec)),
+        };
+        let measure = Measure::from_name(measure.trim()).ok_or_else(|| {
+            let names: Vec<_> = Measure::ALL.iter().map(|measure| measure.name()).collect();
+            format!("unknown measure `{}`, expected one of {}", measure.trim(), names.join(", "))
+        })?;
+        let threshold = threshold.trim().parse().ok()
+            .filter(|threshold: &f32| threshold.is_finite())
+            .ok_or_else(|| format!("invalid threshold `{}`", threshold.trim()))?;
+        Ok(Self { measure, above, threshold, holding: false })
+    }
+
+    pub fn holds(&self, value: f32) -> bool {
+        if self.above { value > self.threshold } else { value < self.threshold }
+    }
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.measure.name(), if self.above { '>' } else { '<' }, self.threshold)
+    }
+}
+
+/// A named rectangle of a world, watched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Region {
+    pub name: String,
+    pub rect: Rectangle,
+    pub alerts: Vec<Alert>,
+    //  since the last sample
+    births: u32,
+    deaths: u32,
+}
+
+impl Region {
+    /// A region written as `<name>[@<x>,<y>,<width>,<height>] <alert>...`,
+    /// in the rectangle written, or else the one given.
+    pub fn parse(spec: &str, rect: Option<Rectangle>) -> Result<Self, String> {
+        let mut words = spec.split_whitespace();
+        let head = words.next().ok_or("a region needs a name")?;
+        let (name, rect) = match head.split_once('@') {
+            Some((name, numbers)) => {
+                let numbers: Vec<f32> = numbers.split(',').filter_map(|n| n.trim().parse().ok()).collect();
+                match numbers[..] {
+                    [x, y, width, height] if width > 0. && height > 0. => (name, Rectangle::new(x, y, width, height)),
+                    _ => return Err(format!("expected <name>@<x>,<y>,<width>,<height> with a positive size, got `{}`", head)),
+                }
+            },
+            None => (head, rect.ok_or_else(|| format!("expected <name>@<x>,<y>,<width>,<height>, got `{}`", head))?),
+        };
+        if name.is_empty() || name.chars().count() > MAX_NAME_LEN || name.chars().any(|c| c.is_control()) {
+            return Err(format!("a region's name must be 1 to {} characters", MAX_NAME_LEN));
+        }
+        let alerts = words.map(Alert::parse).collect::<Result<_, _>>()?;
+        Ok(Self { name: name.to_string(), rect, alerts, births: 0, deaths: 0 })
+    }
+
+    /// The region written as it is parsed, rectangle included.
+    pub fn to_spec(&self) -> String {
+        let mut ret = format!("{}@{},{},{},{}", self.name, self.rect.x, self.rect.y, self.rect.width, self.rect.height);
+        for alert in &self.alerts {
+            ret += &format!(" {}", alert);
+        }
+        ret
+    }
+
+    /// The name of the series of a measure of the region.
+    pub fn series(&self, measure: Measure) -> String {
+        format!("{} in {}", measure.name(), self.name)
+    }
+
+    pub fn contains(&self, pos: Vector2) -> bool {
+        self.rect.check_collision_point_rec(pos)
+    }
+
+    /// Count a blob born at a position, if in the region.
+    pub fn count_birth(&mut self, pos: Vector2) {
+        self.births += self.contains(pos) as u32;
+    }
+
+    /// Count a blob that died at a position, if in the region.
+    pub fn count_death(&mut self, pos: Vector2) {
+        self.deaths += self.contains(pos) as u32;
+    }
+
+    /// Each of `Measure::ALL` in the region now.
+    pub fn measure(&self, sim: &Simulation) -> [f32; 5] {
+        let blobs = sim.blobs().filter(|(_, blob)| self.contains(blob.pos()));
+        let (population, predators) = blobs.fold((0, 0), |(all, predators), (_, blob)| (all + 1, predators + blob.diet.eats_blobs() as u32));
+        let food = sim.foods().filter(|(_, food)| self.contains(food.pos())).count();
+        [population as f32, food as f32, predators as f32, self.births as f32, self.deaths as f32]
+    }
+
+    /// Take a sample of what `measure` measured, starting to count
+    /// births and deaths anew, and return the alerts it set off with
+    /// the values that did.
+    pub fn sample(&mut self, values: &[f32; 5]) -> Vec<(Alert, f32)> {
+        self.births = 0;
+        self.deaths = 0;
+        let mut ret = vec![];
+        for alert in &mut self.alerts {
+            let value = values[Measure::ALL.iter().position(|&measure| measure == alert.measure).unwrap()];
+            let holds = alert.holds(value);
+            if holds && !alert.holding {
+                ret.push((*alert, value));
+            }
+            alert.holding = holds;
+        }
+        ret
+    }
+}
+
+/// Draw the outlines and names of a world's regions, those with an
+/// alert holding in the warning color.
+pub fn draw<D: RaylibDraw>(draw: &mut D, sim: &Simulation, theme: &Theme, fonts: &Fonts, zoom: f32) {
+    for region in sim.regions() {
+        let color = if region.alerts.iter().any(|alert| alert.holding) { theme.warning } else { theme.accent };
+        draw.draw_rectangle_rec(region.rect, color.fade(0.08));
+        let Rectangle { x, y, width, height } = region.rect;
+        let corners = [Vector2::new(x, y), Vector2::new(x + width, y), Vector2::new(x + width, y + height), Vector2::new(x, y + height)];
+        for i in 0..corners.len() {
+            draw.draw_line_ex(corners[i], corners[(i + 1) % corners.len()], 2. / zoom, color);
+        }
+        fonts.draw_text(draw, &region.name, x as i32 + 4, y as i32 + 4, FONT_HEIGHT, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationConfig, BlobConfig};
+
+    #[test]
+    fn test_watch() {
+        let region = Region::parse("nest@10,20,30,40 predators>3 food<0.5", None).unwrap();
+        assert_eq!((region.name.as_str(), region.rect.width, region.alerts.len()), ("nest", 30., 2));
+        assert_eq!(Region::parse(&region.to_spec(), None), Ok(region));
+        assert!(Region::parse("nest", None).is_err());
+        assert!(Region::parse("nest@0,0,-1,5", None).is_err());
+        assert!(Region::parse("nest prey>3", Some(Rectangle::new(0., 0., 1., 1.))).unwrap_err().contains("unknown measure"));
+
+        let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(300., 300.)));
+        sim.watch(Region::parse("corner@0,0,100,100 population>1", None).unwrap());
+        sim.insert_blob(BlobConfig::new(Vector2::new(50., 50.)));
+        sim.insert_blob(BlobConfig::new(Vector2::new(60., 50.)));
+        sim.insert_blob(BlobConfig::new(Vector2::new(250., 250.)));
+        let mut region = sim.regions()[0].clone();
+        let values = region.measure(&sim);
+        assert_eq!((values[0], values[3]), (2., 2.));
+        //  an alert fires when its condition starts to hold, not while it goes on holding
+        assert_eq!(region.sample(&values).len(), 1);
+        assert!(region.sample(&values).is_empty());
+        assert_eq!(region.measure(&sim)[3], 0.);
+    }
+}
@@ -0,0 +1,236 @@
+//! Weather over the seasons: rain, storms and droughts that come and
+//! go over parts of a world.
+//!
+//! With the `weather` parameter above 0, such as `--compare weather=1`,
+//! the world looks to the sky every `CHECK_PERIOD` ticks, and each kind
+//! of weather may begin with a chance that depends on the season,
+//! scaled by the parameter, over a circle of the world for a while:
+//!
+//! * rain grows food inside it, mostly in spring and autumn
+//! * a storm pushes the blobs inside it with gusts that turn every
+//!   half second, and they see half as far, mostly in autumn and winter
+//! * a drought lets only a quarter of the food grow inside it, in summer
+//!
+//! The weather is drawn from a stream of its own, see `math::random`,
+//! so worlds with the same seed have the same weather and worlds
+//! without it run as they always did. Weather beginning is logged as
+//! `Event::Weather` and marked on the timeline, it is drawn over the
+//! world, and it is saved with it.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::{math::random::Stream, weather};
+//! # const WEATHER_STREAM: u64 = 3;
+//! # let (seed, tick, size, ticks_per_second, clock) = (42, 0, sim.size(), 60., sim.clock());
+//! let mut rng = Stream::new(seed, WEATHER_STREAM, 0, tick);
+//! for weather in weather::forecast(&mut rng, tick, clock.season(), size, ticks_per_second, 1.) {
+//!     println!("{} at {:?}", weather.kind.name(), weather.center);
+//! }
+//! ```
+
+use rand::Rng;
+use raylib::prelude::*;
+
+use crate::{clock::Season, math::{random::Stream, FloatMode}, shapes, simulation::Simulation, theme::Theme};
+
+/// Ticks between looks to the sky.
+pub const CHECK_PERIOD: u64 = 600;
+/// Ticks a gust of a storm blows the same way.
+pub const GUST_TICKS: u64 = 30;
+/// How hard a storm pushes, in units a second squared.
+const GUST: f32 = 120.;
+/// How far blobs in a storm see, as a part of how far they see in the clear.
+const STORM_VISIBILITY: f32 = 0.5;
+/// The part of the food that grows in a drought.
+const DROUGHT_GROWTH: f32 = 0.25;
+/// Foods rain grows a second.
+pub const RAIN_FOOD: f32 = 0.5;
+/// Raindrops drawn for each 10000 square units of rain.
+const RAINDROPS: f32 = 4.;
+const MAX_RAINDROPS: u64 = 400;
+
+/// What the weather is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Rain,
+    Storm,
+    Drought,
+}
+
+impl Kind {
+    pub const ALL: [Self; 3] = [Self::Rain, Self::Storm, Self::Drought];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Rain => "rain",
+            Self::Storm => "storm",
+            Self::Drought => "drought",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|kind| kind.name() == name)
+    }
+
+    /// The chance it begins at a look to the sky in a season, at a
+    /// `weather` parameter of 1.
+    fn chance(&self, season: Season) -> f32 {
+        match (self, season) {
+            (Self::Rain, Season::Spring) => 0.15,
+            (Self::Rain, Season::Summer) => 0.05,
+            (Self::Rain, Season::Autumn) => 0.12,
+            (Self::Rain, Season::Winter) => 0.06,
+            (Self::Storm, Season::Spring) => 0.04,
+            (Self::Storm, Season::Summer) => 0.04,
+            (Self::Storm, Season::Autumn) => 0.1,
+            (Self::Storm, Season::Winter) => 0.08,
+            (Self::Drought, Season::Summer) => 0.08,
+            (Self::Drought, Season::Spring | Season::Autumn) => 0.02,
+            (Self::Drought, Season::Winter) => 0.,
+        }
+    }
+
+    /// How long it lasts, in seconds, and how large it is, as a part
+    /// of the shorter side of the world.
+    fn extent(&self) -> ((f32, f32), (f32, f32)) {
+        match self {
+            Self::Rain => ((20., 40.), (0.15, 0.3)),
+            Self::Storm => ((10., 20.), (0.1, 0.25)),
+            Self::Drought => ((40., 80.), (0.3, 0.5)),
+        }
+    }
+}
+
+/// Weather over a circle of a world for a span of ticks.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Weather {
+    pub kind: Kind,
+    pub center: Vector2,
+    pub radius: f32,
+    pub start: u64,
+    /// The tick it is over at.
+    pub until: u64,
+}
+
+impl Weather {
+    pub fn contains(&self, pos: Vector2) -> bool {
+        (pos - self.center).length_sqr() <= self.radius * self.radius
+    }
+
+    /// The push of a storm at a tick, from the stream of its gust.
+    pub fn gust(&self, rng: &mut Stream, mode: FloatMode) -> Vector2 {
+        let angle = rng.gen::<f32>() * std::f32::consts::TAU;
+        Vector2::new(mode.cos(angle), mode.sin(angle)) * GUST * rng.gen_range(0.5..1.)
+    }
+
+    /// The weather as saved, the fields of its line, see `Simulation::save`.
+    pub fn to_fields(self) -> String {
+        format!("{}\t{}\t{}\t{}\t{}\t{}", self.kind.name(), self.center.x, self.center.y, self.radius, self.start, self.until)
+    }
+}
+
+/// The weather that begins at a tick, if any, see the module.
+pub fn forecast(rng: &mut Stream, tick: u64, season: Season, size: Vector2, ticks_per_second: f32, frequency: f32) -> Vec<Weather> {
+    let mut ret = vec![];
+    for kind in Kind::ALL {
+        //  every kind draws the same numbers whether it begins or not
+        let (roll, x, y, length, radius): (f32, f32, f32, f32, f32) = (rng.gen(), rng.gen(), rng.gen(), rng.gen(), rng.gen());
+        if roll >= kind.chance(season) * frequency {
+            continue;
+        }
+        let ((shortest, longest), (smallest, largest)) = kind.extent();
+        let seconds = shortest + (longest - shortest) * length;
+        ret.push(Weather {
+            kind,
+            center: Vector2::new(x, y) * size,
+            radius: size.x.min(size.y) * (smallest + (largest - smallest) * radius),
+            start: tick,
+            until: tick + (seconds * ticks_per_second) as u64,
+        });
+    }
+    ret
+}
+
+/// How far blobs at a place see, as a part of how far they see in the clear.
+pub fn visibility(weather: &[Weather], pos: Vector2) -> f32 {
+    if weather.iter().any(|weather| weather.kind == Kind::Storm && weather.contains(pos)) { STORM_VISIBILITY } else { 1. }
+}
+
+/// The part of the food that grows at a place, under the droughts over it.
+pub fn growth(weather: &[Weather], pos: Vector2) -> f32 {
+    if weather.iter().any(|weather| weather.kind == Kind::Drought && weather.contains(pos)) { DROUGHT_GROWTH } else { 1. }
+}
+
+/// Draw the weather of a world in world coordinates: a tint over each
+/// circle, with falling raindrops in rain and streaks along the gust
+/// in a storm.
+pub fn draw<D: RaylibDraw>(draw: &mut D, sim: &Simulation, theme: &Theme, zoom: f32) {
+    let (tick, mode) = (sim.clock().tick(), sim.float_mode());
+    for weather in sim.weather() {
+        let color = match weather.kind {
+            Kind::Rain => theme.accent,
+            Kind::Storm => theme.muted_text,
+            Kind::Drought => theme.warning,
+        };
+        shapes::draw_circle(draw, weather.center, weather.radius, zoom, color.fade(0.08));
+        if weather.kind == Kind::Drought {
+            continue;
+        }
+        //  drops at fixed places of the circle, falling through it over and over
+        let drops = ((RAINDROPS * weather.radius * weather.radius / 10000.) as u64).min(MAX_RAINDROPS);
+        let mut rng = Stream::new(weather.start, 0, 0, 0);
+        let slant = match weather.kind {
+            Kind::Storm => sim.gust(weather).normalized() * 10.,
+            _ => Vector2::new(0., 6.),
+        };
+        for _ in 0..drops {
+            let (angle, distance, phase): (f32, f32, f32) = (rng.gen::<f32>() * std::f32::consts::TAU, rng.gen::<f32>().sqrt(), rng.gen());
+            let fall = ((tick as f32 / 40. + phase).fract() - 0.5) * 2. * weather.radius * 0.2;
+            let pos = weather.center + Vector2::new(mode.cos(angle), mode.sin(angle)) * distance * weather.radius * 0.8 + Vector2::new(0., fall);
+            draw.draw_line_ex(pos, pos + slant, 1. / zoom, color.fade(0.4));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BlobConfig, Event, Parameters, SimulationConfig};
+
+    #[test]
+    fn test_weather() {
+        let size = Vector2::new(600., 400.);
+        let mut rng = Stream::new(1, 0, 0, 0);
+        assert!(forecast(&mut rng, 0, Season::Spring, size, 60., 0.).is_empty());
+        let certain = forecast(&mut rng, 600, Season::Summer, size, 60., 100.);
+        assert_eq!(certain.iter().map(|weather| weather.kind).collect::<Vec<_>>(), Kind::ALL);
+        let drought = certain[2];
+        assert!(drought.radius >= 120. && drought.radius <= 200. && drought.until >= 600 + 40 * 60);
+        assert!(growth(&certain, drought.center) < 1. && visibility(&certain, certain[1].center) < 1.);
+        assert_eq!((growth(&[], drought.center), visibility(&[], drought.center)), (1., 1.));
+        let far = drought.center + Vector2::new(drought.radius + 1., 0.);
+        assert!(!drought.contains(far) && drought.contains(drought.center));
+
+        let parameters = Parameters { weather: 20., reproduction: false, ..Parameters::default() };
+        let config = SimulationConfig { parameters, ..SimulationConfig::new(size) };
+        let mut sim = Simulation::new(config.clone());
+        sim.insert_blob(BlobConfig { max_hunger: 1e6, ..BlobConfig::new(Vector2::new(300., 200.)) });
+        let mut twin = sim.fork();
+        for _ in 0..CHECK_PERIOD * 3 {
+            sim.step();
+            twin.step();
+        }
+        assert!(sim.weather().next().is_some());
+        assert!(sim.events_since(0).any(|(_, event)| matches!(event, Event::Weather(_))));
+        //  the same seed has the same weather, and it is saved with the world
+        assert_eq!(sim.state_hash(), twin.state_hash());
+        let mut saved = vec![];
+        sim.save(&mut saved).unwrap();
+        let loaded = Simulation::load(&saved[..]).unwrap();
+        assert_eq!(loaded.weather().collect::<Vec<_>>(), sim.weather().collect::<Vec<_>>());
+    }
+}
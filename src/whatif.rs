@@ -0,0 +1,121 @@
+//! Predictions of where a world is heading, for what-if analysis.
+//!
+//! A prediction runs a fork of the world, see `Simulation::fork`, on
+//! a thread of its own while the live world carries on, and is done
+//! once the fork is `SECONDS` of simulation time ahead. The fork may
+//! be changed first, as a hypothetical intervention, to compare what
+//! the world would do with and without it.
+//!
+//! `X` predicts the first world as it is, and `Shift+X` predicts it
+//! without its selected blobs.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::whatif::{Outcome, Prediction};
+//! # let ticks = 60;
+//! let mut fork = sim.fork();
+//! let prediction = Prediction::start("as it is".to_string(), Outcome::of(&sim), move || {
+//!     for _ in 0..ticks { fork.step(); }
+//!     Outcome::of(&fork)
+//! });
+//! ```
+
+use std::{collections::BTreeMap, sync::mpsc, thread};
+
+use crate::simulation::Simulation;
+
+/// How far ahead predictions look, in seconds of simulation time.
+pub const SECONDS: u32 = 60;
+
+/// What a world looks like at some tick.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub tick: u64,
+    pub population: usize,
+    pub foods: usize,
+    /// Blobs by species, leaving out blobs of none.
+    pub species: BTreeMap<String, usize>,
+}
+
+impl Outcome {
+    pub fn of(sim: &Simulation) -> Self {
+        let mut species = BTreeMap::new();
+        for (_, blob) in sim.blobs() {
+            if let Some(name) = &blob.species {
+                *species.entry(name.clone()).or_insert(0) += 1;
+            }
+        }
+        Self {
+            tick: sim.clock().tick(),
+            population: sim.blobs().count(),
+            foods: sim.foods().count(),
+            species,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Prediction {
+    /// What was changed in the fork, such as "as it is".
+    pub intervention: String,
+    /// The world when the prediction started.
+    pub start: Outcome,
+    receiver: mpsc::Receiver<Outcome>,
+    outcome: Option<Outcome>,
+}
+
+impl Prediction {
+    /// Run a fork to its outcome on another thread.
+    pub fn start<F>(intervention: String, start: Outcome, run: F) -> Self
+    where
+        F: FnOnce() -> Outcome + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            //  the prediction may have been dropped by the time it is done
+            let _ = sender.send(run());
+        });
+        Self { intervention, start, receiver, outcome: None }
+    }
+
+    /// Take the outcome if the fork finished running.
+    pub fn poll(&mut self) {
+        if self.outcome.is_none() {
+            self.outcome = self.receiver.try_recv().ok();
+        }
+    }
+
+    pub fn outcome(&self) -> Option<&Outcome> { self.outcome.as_ref() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::SimulationConfig;
+    use raylib::prelude::*;
+
+    #[test]
+    fn test_prediction() {
+        let sim = Simulation::new(SimulationConfig::new(Vector2::new(200., 200.)));
+        let start = Outcome::of(&sim);
+        assert_eq!(start.population, 0);
+        let mut fork = sim.fork();
+        let mut prediction = Prediction::start("as it is".to_string(), start.clone(), move || {
+            for _ in 0..10 {
+                fork.step();
+            }
+            Outcome::of(&fork)
+        });
+        while prediction.outcome().is_none() {
+            prediction.poll();
+            thread::yield_now();
+        }
+        let outcome = prediction.outcome().unwrap();
+        assert_eq!(outcome.tick, start.tick + 10);
+        assert_eq!(Outcome::of(&sim), start, "the original is left as it was");
+    }
+}
@@ -1,5 +1,9 @@
+use std::{collections::VecDeque, ffi::CString};
+
 use raylib::prelude::*;
 
+use crate::{theme::Theme, fonts::Fonts, shutdown, input::{Frame, Button}, placement, style::Style};
+
 pub struct Window {
     handle: RaylibHandle,
     thread: RaylibThread,
@@ -7,24 +11,85 @@ pub struct Window {
 
 pub type DrawingContext<'a> = RaylibDrawHandle<'a>;
 
-pub use raylib::prelude::MouseButton;
 pub use raylib::prelude::KeyboardKey;
 
+/// Put text on the clipboard, which the drawing context cannot as it
+/// only borrows the handle.
+pub fn set_clipboard_text(text: &str) {
+    if let Ok(text) = CString::new(text) {
+        unsafe { raylib::ffi::SetClipboardText(text.as_ptr()) }
+    }
+}
+
+/// Cap the frames drawn a second, or lift the cap with 0, which the
+/// drawing context cannot as it only borrows the handle.
+pub fn set_target_fps(fps: u32) {
+    unsafe { raylib::ffi::SetTargetFPS(fps as i32) }
+}
+
 pub struct WindowConfig {
     pub width: u32,
     pub height: u32,
     pub title: &'static str,
+    /// Smooth the edges of shapes with multisampling.
+    pub antialiasing: bool,
+    /// The monitor to open on, counted from 0, see `placement`.
+    pub monitor: Option<usize>,
+    /// Where on the monitor the corner of the window goes, if not centered.
+    pub position: Option<(i32, i32)>,
+    /// Keep the window above the others.
+    pub on_top: bool,
+    /// Cover the whole monitor, without a cursor.
+    pub fullscreen: bool,
+}
+
+/// The rectangles the monitors take on the desktop.
+fn monitors() -> Vec<Rectangle> {
+    (0..get_monitor_count())
+        .map(|monitor| {
+            let corner: Vector2 = unsafe { raylib::ffi::GetMonitorPosition(monitor) }.into();
+            Rectangle::new(corner.x, corner.y, get_monitor_width(monitor) as f32, get_monitor_height(monitor) as f32)
+        })
+        .collect()
 }
 
 impl Window {
-    pub fn new(WindowConfig { width, height, title }: &WindowConfig) -> Self {
-        let (handle, thread) = raylib::init()
+    pub fn new(WindowConfig { width, height, title, antialiasing, monitor, position, on_top, fullscreen }: &WindowConfig) -> Self {
+        let mut builder = raylib::init();
+        builder
             .title(title)
-            .size(*width as i32, *height as i32)
-            .build();
+            .size(*width as i32, *height as i32);
+        if *antialiasing {
+            builder.msaa_4x();
+        }
+        let (mut handle, thread) = builder.build();
+        //  a position alone is on the primary monitor
+        if monitor.is_some() || position.is_some() {
+            let monitors = monitors();
+            let monitor = monitor.unwrap_or(0);
+            match placement::corner(&monitors, monitor, *position, (*width as i32, *height as i32)) {
+                Some((x, y)) => handle.set_window_position(x, y),
+                None => eprintln!("there is no monitor {} of {}, opening on the primary one", monitor + 1, monitors.len()),
+            }
+        }
+        if *on_top {
+            handle.set_window_state(WindowState::default().set_window_topmost(true));
+        }
+        //  at the monitor's own resolution, rather than the window's
+        if let Some(rect) = monitors().get(monitor.unwrap_or(0)).filter(|_| *fullscreen) {
+            handle.set_window_size(rect.width as i32, rect.height as i32);
+            handle.toggle_fullscreen();
+            handle.hide_cursor();
+        }
         Self { handle, thread }
     }
 
+    /// The monitor the middle of the window is on, counted from 0.
+    pub fn monitor(&self) -> Option<usize> {
+        let middle = self.handle.get_window_position() + Vector2::new(self.width() as f32, self.height() as f32) / 2.;
+        placement::monitor_at(&monitors(), middle)
+    }
+
     pub fn width(&self) -> u32 {
         self.handle.get_screen_width() as u32
     }
@@ -33,16 +98,541 @@ impl Window {
         self.handle.get_screen_height() as u32
     }
 
+    /// Draw frames until the window is closed or a shutdown is requested.
     pub fn draw_loop<F>(&mut self, mut draw: F)
-    where F: FnMut(DrawingContext) {
-        while !self.handle.window_should_close() {
-            draw(self.handle.begin_drawing(&self.thread));
+    where F: FnMut(DrawingContext, &RaylibThread) {
+        while !self.handle.window_should_close() && !shutdown::requested() {
+            draw(self.handle.begin_drawing(&self.thread), &self.thread);
+            //  dropped files are kept until cleared, and each frame's input has only the new ones
+            if self.handle.is_file_dropped() {
+                self.handle.clear_dropped_files();
+            }
         }
     }
 
     pub fn handle(&self) -> &RaylibHandle { &self.handle }
+
+    /// Load the font in a file, at the sizes the interface uses.
+    pub fn load_fonts(&mut self, path: &str) -> Result<Fonts, String> {
+        Fonts::load(&mut self.handle, &self.thread, path)
+    }
+
+    /// Draw into a texture offscreen and read its pixels back, upright.
+    pub fn render_offscreen<F>(&mut self, width: u32, height: u32, draw: F) -> Result<Image, String>
+    where F: FnOnce(&mut RaylibTextureMode<&mut RaylibHandle>) {
+        let mut target = self.handle.load_render_texture(&self.thread, width, height)?;
+        {
+            let mut handle = &mut self.handle;
+            let mut texture_mode = handle.begin_texture_mode(&self.thread, &mut target);
+            draw(&mut texture_mode);
+        }
+        let mut image = target.texture().get_texture_data()?;
+        image.flip_vertical();
+        Ok(image)
+    }
+
+    /// Load the post-processing shader and a render target the size of the window.
+    pub fn load_post_processing(&mut self) -> Result<PostProcessing, String> {
+        PostProcessing::load(&mut self.handle, &self.thread, PostProcessing::SHADER_PATH)
+    }
+}
+
+/// A pass over the drawn world that adds bloom around bright
+/// colors, a vignette and the lens distortion of looking into a
+/// petri dish.
+///
+/// The world is drawn into a texture between `begin` and
+/// `present`, which draws the texture onto the window through the
+/// shader. Anything drawn after `present`, such as the interface,
+/// is left untouched.
+pub struct PostProcessing {
+    target: RenderTexture2D,
+    shader: Shader,
+    //  uniform locations
+    resolution_loc: i32,
+    bloom_loc: i32,
+    vignette_loc: i32,
+    lens_loc: i32,
+    pub bloom: bool,
+    pub vignette: bool,
+    pub lens: bool,
+}
+
+impl PostProcessing {
+    pub const SHADER_PATH: &'static str = "assets/shaders/post.fs";
+
+    pub fn load(handle: &mut RaylibHandle, thread: &RaylibThread, path: &str) -> Result<Self, String> {
+        let (width, height) = (handle.get_screen_width() as u32, handle.get_screen_height() as u32);
+        let target = handle.load_render_texture(thread, width, height)?;
+        let shader = handle.load_shader(thread, None, Some(path))?;
+        Ok(Self {
+            resolution_loc: shader.get_shader_location("resolution"),
+            bloom_loc: shader.get_shader_location("bloom"),
+            vignette_loc: shader.get_shader_location("vignette"),
+            lens_loc: shader.get_shader_location("lens"),
+            target,
+            shader,
+            bloom: true,
+            vignette: true,
+            lens: false,
+        })
+    }
+
+    /// Toggle the effects whose keys were pressed this frame:
+    /// shift and B for bloom, V for the vignette and shift and V for the lens.
+    pub fn update(&mut self, input: &Frame) {
+        //  B alone bookmarks
+        if input.is_key_pressed(KeyboardKey::KEY_B) && input.is_shift_down() {
+            self.bloom = !self.bloom;
+        }
+        //  L traces the selected blob, so the lens is on shift and V
+        if input.is_key_pressed(KeyboardKey::KEY_V) && input.is_shift_down() {
+            self.lens = !self.lens;
+        } else if input.is_key_pressed(KeyboardKey::KEY_V) {
+            self.vignette = !self.vignette;
+        }
+    }
+
+    /// Start drawing into the texture that `present` processes.
+    pub fn begin<'a, 'b>(&'a mut self, draw: &'a mut DrawingContext<'b>, thread: &RaylibThread) -> RaylibTextureMode<'a, DrawingContext<'b>> {
+        draw.begin_texture_mode(thread, &mut self.target)
+    }
+
+    /// Draw what was drawn since `begin` onto the window with the effects applied.
+    pub fn present(&mut self, draw: &mut DrawingContext) {
+        let texture = self.target.texture();
+        let (width, height) = (texture.width as f32, texture.height as f32);
+        self.shader.set_shader_value(self.resolution_loc, Vector2::new(width, height));
+        self.shader.set_shader_value(self.bloom_loc, self.bloom as i32);
+        self.shader.set_shader_value(self.vignette_loc, self.vignette as i32);
+        self.shader.set_shader_value(self.lens_loc, self.lens as i32);
+        let mut shaded = draw.begin_shader_mode(&self.shader);
+        //  render textures are stored upside down
+        shaded.draw_texture_rec(texture, Rectangle::new(0., 0., width, -height), Vector2::zero(), Color::WHITE);
+    }
+}
+
+/// The view of the world that is drawn onto the window.
+///
+/// `target` is the world position shown at `offset`, the top
+/// left corner of the view on the window, and `zoom` is how many
+/// screen pixels a unit of the world covers.
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub target: Vector2,
+    pub offset: Vector2,
+    pub zoom: f32,
+}
+
+impl Camera {
+    pub const MIN_ZOOM: f32 = 0.25;
+    pub const MAX_ZOOM: f32 = 16.;
+
+    pub fn new() -> Self {
+        Self { target: Vector2::zero(), offset: Vector2::zero(), zoom: 1. }
+    }
+
+    /// Multiply the zoom, keeping the world position under a screen position in place.
+    pub fn zoom_at(&mut self, screen_pos: Vector2, factor: f32) {
+        let anchor = self.screen_to_world(screen_pos);
+        self.zoom = (self.zoom * factor).clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+        self.target = anchor - (screen_pos - self.offset) / self.zoom;
+    }
+
+    /// The same view placed at another position on the window.
+    pub fn with_offset(&self, offset: Vector2) -> Self {
+        Self { offset, ..*self }
+    }
+
+    /// Move the view by a delta given in screen coordinates.
+    pub fn pan(&mut self, screen_delta: Vector2) {
+        self.target -= screen_delta / self.zoom;
+    }
+
+    pub fn screen_to_world(&self, pos: Vector2) -> Vector2 {
+        (pos - self.offset) / self.zoom + self.target
+    }
+
+    pub fn world_to_screen(&self, pos: Vector2) -> Vector2 {
+        (pos - self.target) * self.zoom + self.offset
+    }
+
+    /// Where on the border of a view, `margin` pixels in, to point
+    /// toward a world position off the view, along with the direction
+    /// to it. None while the position is in view.
+    pub fn edge_toward(&self, view: Rectangle, pos: Vector2, margin: f32) -> Option<(Vector2, Vector2)> {
+        let screen_pos = self.world_to_screen(pos);
+        let inside = |x: f32, start: f32, length: f32| x >= start && x <= start + length;
+        if inside(screen_pos.x, view.x, view.width) && inside(screen_pos.y, view.y, view.height) { return None; }
+        let half = Vector2::new(view.width / 2. - margin, view.height / 2. - margin);
+        let center = Vector2::new(view.x + view.width / 2., view.y + view.height / 2.);
+        let delta = screen_pos - center;
+        //  the nearer of the two sides the line to the position crosses
+        let scale = (half.x / delta.x.abs()).min(half.y / delta.y.abs());
+        Some((center + delta * scale, delta.normalized()))
+    }
+
+    /// The raylib camera to pass to `begin_mode2D`.
+    pub fn camera2d(&self) -> Camera2D {
+        Camera2D {
+            offset: self.offset,
+            target: self.target,
+            rotation: 0.,
+            zoom: self.zoom,
+        }
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self { Self::new() }
+}
+
+/// What drawing the world needs besides the draw handle.
+pub struct RenderContext<'a> {
+    pub theme: &'a Theme,
+    pub fonts: &'a Fonts,
+    /// How many screen pixels a unit of the world covers.
+    pub zoom: f32,
+    /// Whether to draw the names and ages over blobs.
+    pub labels: bool,
+    /// How blobs are drawn, see `style`.
+    pub style: Style,
+    /// Whether blobs are tinted by how many others they overlap, see `depth`.
+    pub crowding: bool,
+}
+
+/// The layers a frame is drawn in, from bottom to top.
+///
+/// Everything drawn belongs to a layer, so what is on top of what
+/// does not depend on the order the drawing code happens to run in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RenderLayer {
+    Terrain,
+    Heatmap,
+    Food,
+    Corpses,
+    Blobs,
+    /// Highlights and other transient marks over the entities.
+    Effects,
+    /// The interface, drawn in screen coordinates.
+    Ui,
+}
+
+impl RenderLayer {
+    /// Every layer in draw order.
+    pub const ALL: [Self; 7] = [
+        Self::Terrain,
+        Self::Heatmap,
+        Self::Food,
+        Self::Corpses,
+        Self::Blobs,
+        Self::Effects,
+        Self::Ui,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Terrain => "Terrain",
+            Self::Heatmap => "Heatmap",
+            Self::Food => "Food",
+            Self::Corpses => "Corpses",
+            Self::Blobs => "Blobs",
+            Self::Effects => "Effects",
+            Self::Ui => "UI",
+        }
+    }
+
+    /// The function key that toggles the layer.
+    pub fn toggle_key(&self) -> KeyboardKey {
+        match self {
+            Self::Terrain => KeyboardKey::KEY_F1,
+            Self::Heatmap => KeyboardKey::KEY_F2,
+            Self::Food => KeyboardKey::KEY_F3,
+            Self::Corpses => KeyboardKey::KEY_F4,
+            Self::Blobs => KeyboardKey::KEY_F5,
+            Self::Effects => KeyboardKey::KEY_F6,
+            Self::Ui => KeyboardKey::KEY_F7,
+        }
+    }
+
+    fn bit(&self) -> u8 { 1 << *self as u8 }
+}
+
+/// Which render layers are shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerVisibility(u8);
+
+impl LayerVisibility {
+    /// Every layer shown.
+    pub fn new() -> Self { Self(!0) }
+
+    pub fn is_visible(&self, layer: RenderLayer) -> bool { self.0 & layer.bit() != 0 }
+
+    pub fn toggle(&mut self, layer: RenderLayer) { self.0 ^= layer.bit(); }
+
+    /// Toggle the layers whose keys were pressed this frame.
+    pub fn update(&mut self, input: &Frame) {
+        for &layer in &RenderLayer::ALL {
+            if input.is_key_pressed(layer.toggle_key()) {
+                self.toggle(layer);
+            }
+        }
+    }
+
+    /// The shown layers in draw order.
+    pub fn visible(self) -> impl Iterator<Item=RenderLayer> {
+        RenderLayer::ALL.iter().cloned().filter(move |&layer| self.is_visible(layer))
+    }
+
+    pub fn hidden(self) -> impl Iterator<Item=RenderLayer> {
+        RenderLayer::ALL.iter().cloned().filter(move |&layer| !self.is_visible(layer))
+    }
+}
+
+impl Default for LayerVisibility {
+    fn default() -> Self { Self::new() }
+}
+
+/// What a left mouse gesture does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InteractionMode {
+    /// Drag a marquee rectangle, or click, to select blobs.
+    Select,
+    /// Grab the blobs under the cursor and move them.
+    Drag,
+    /// Move the camera.
+    Pan,
+    /// Spread food along the mouse path.
+    Paint,
+    /// Drag a rectangle to watch it, see `watch`.
+    Watch,
+    /// Click to place or remove a drone, or drag from one to send it, see `drones`.
+    Drone,
+    /// Push the blobs around the cursor away, see `influence`.
+    Repel,
+    /// Pull the blobs around the cursor in.
+    Attract,
+    /// Slow the blobs around the cursor down.
+    Calm,
+}
+
+impl InteractionMode {
+    pub const ALL: [Self; 9] = [Self::Select, Self::Drag, Self::Pan, Self::Paint, Self::Watch, Self::Drone, Self::Repel, Self::Attract, Self::Calm];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Select => "Select",
+            Self::Drag => "Drag",
+            Self::Pan => "Pan",
+            Self::Paint => "Paint",
+            Self::Watch => "Watch",
+            Self::Drone => "Drone",
+            Self::Repel => "Repel",
+            Self::Attract => "Attract",
+            Self::Calm => "Calm",
+        }
+    }
+
+    /// The number key that switches to the mode.
+    pub fn hotkey(&self) -> KeyboardKey {
+        match self {
+            Self::Select => KeyboardKey::KEY_ONE,
+            Self::Drag => KeyboardKey::KEY_TWO,
+            Self::Pan => KeyboardKey::KEY_THREE,
+            Self::Paint => KeyboardKey::KEY_FOUR,
+            Self::Watch => KeyboardKey::KEY_FIVE,
+            Self::Drone => KeyboardKey::KEY_SIX,
+            Self::Repel => KeyboardKey::KEY_SEVEN,
+            Self::Attract => KeyboardKey::KEY_EIGHT,
+            Self::Calm => KeyboardKey::KEY_NINE,
+        }
+    }
+
+    /// The mode temporarily forced by a held modifier key.
+    fn from_modifiers(input: &Frame) -> Option<Self> {
+        if input.is_shift_down() {
+            Some(Self::Select)
+        } else if input.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || input.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL) {
+            Some(Self::Pan)
+        } else if input.is_key_down(KeyboardKey::KEY_LEFT_ALT) || input.is_key_down(KeyboardKey::KEY_RIGHT_ALT) {
+            Some(Self::Paint)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GesturePhase {
+    Begin,
+    Update,
+    End,
+}
+
+/// A left mouse gesture, resolved against the interaction mode
+/// that was active when the button was pressed.
+///
+/// Positions are in screen coordinates and the velocity is in
+/// screen pixels per second.
+#[derive(Debug, Clone, Copy)]
+pub struct Gesture {
+    pub mode: InteractionMode,
+    pub phase: GesturePhase,
+    pub start: Vector2,
+    pub pos: Vector2,
+    pub delta: Vector2,
+    pub velocity: Vector2,
+}
+
+/// Turns raw mouse and keyboard state into gestures.
+///
+/// The mode is chosen with the number keys or the toolbar, and
+/// can be overridden while a modifier is held: shift selects,
+/// control pans and alt paints. A gesture keeps the mode it
+/// started with until the button is released.
+pub struct InputController {
+    mode: InteractionMode,
+    //  the mode forced by the modifiers held in the last frame
+    forced: Option<InteractionMode>,
+    active: Option<(InteractionMode, Vector2)>,
+    last_pos: Vector2,
+    //  recent (time, position) samples of the mouse
+    samples: VecDeque<(f64, Vector2)>,
+}
+
+impl InputController {
+    const BUTTON_WIDTH: i32 = 90;
+    const BUTTON_HEIGHT: i32 = 26;
+    const FONT_SIZE: i32 = 20;
+    /// How far back in seconds mouse movement counts towards its velocity.
+    const VELOCITY_WINDOW: f64 = 0.1;
+
+    pub fn new(mode: InteractionMode) -> Self {
+        Self { mode, forced: None, active: None, last_pos: Vector2::zero(), samples: VecDeque::new() }
+    }
+
+    /// The mouse velocity over the last few frames, in pixels per second.
+    pub fn mouse_velocity(&self) -> Vector2 {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(&(t0, p0)), Some(&(t1, p1))) if t1 > t0 => (p1 - p0) / (t1 - t0) as f32,
+            _ => Vector2::zero(),
+        }
+    }
+
+    /// The mode a gesture would use if it started now.
+    pub fn mode(&self) -> InteractionMode {
+        match self.active {
+            Some((mode, _)) => mode,
+            None => self.forced.unwrap_or(self.mode),
+        }
+    }
+
+    /// The mode and start position of the gesture in progress.
+    pub fn active_gesture(&self) -> Option<(InteractionMode, Vector2)> { self.active }
+
+    fn button_rect(index: usize, screen_width: i32) -> Rectangle {
+        let count = InteractionMode::ALL.len() as i32;
+        Rectangle::new(
+            (screen_width - (count - index as i32) * Self::BUTTON_WIDTH - 10) as f32,
+            10.,
+            Self::BUTTON_WIDTH as f32,
+            Self::BUTTON_HEIGHT as f32,
+        )
+    }
+
+    fn toolbar_button_at(screen_width: i32, pos: Vector2) -> Option<InteractionMode> {
+        InteractionMode::ALL.iter()
+            .enumerate()
+            .find(|(i, _)| Self::button_rect(*i, screen_width).check_collision_point_rec(pos))
+            .map(|(_, &mode)| mode)
+    }
+
+    /// Read this frame's input and return the gesture it produced.
+    pub fn update(&mut self, input: &Frame, screen_width: i32) -> Option<Gesture> {
+        //  with Ctrl, number keys are for viewpoints
+        let control = input.is_key_down(KeyboardKey::KEY_LEFT_CONTROL) || input.is_key_down(KeyboardKey::KEY_RIGHT_CONTROL);
+        for &mode in InteractionMode::ALL.iter().filter(|_| !control) {
+            if input.is_key_pressed(mode.hotkey()) {
+                self.mode = mode;
+            }
+        }
+        self.forced = InteractionMode::from_modifiers(input);
+
+        let pos = input.mouse;
+        let delta = pos - self.last_pos;
+        self.last_pos = pos;
+
+        let now = input.time;
+        self.samples.push_back((now, pos));
+        while self.samples.front().is_some_and(|&(t, _)| now - t > Self::VELOCITY_WINDOW) {
+            self.samples.pop_front();
+        }
+        let velocity = self.mouse_velocity();
+
+        if input.button == Button::Pressed {
+            if let Some(mode) = Self::toolbar_button_at(screen_width, pos) {
+                self.mode = mode;
+                return None;
+            }
+            let mode = self.mode();
+            self.active = Some((mode, pos));
+            return Some(Gesture { mode, phase: GesturePhase::Begin, start: pos, pos, delta: Vector2::zero(), velocity });
+        }
+
+        let (mode, start) = self.active?;
+        if input.is_button_down() {
+            Some(Gesture { mode, phase: GesturePhase::Update, start, pos, delta, velocity })
+        } else {
+            self.active = None;
+            Some(Gesture { mode, phase: GesturePhase::End, start, pos, delta, velocity })
+        }
+    }
+
+    /// Draw the mode buttons at the top right of the window.
+    pub fn draw_toolbar(&self, draw: &mut DrawingContext, theme: &Theme, fonts: &Fonts) {
+        let current = self.mode();
+        for (i, &mode) in InteractionMode::ALL.iter().enumerate() {
+            let rect = Self::button_rect(i, draw.get_screen_width());
+            let (background, foreground) = if mode == current {
+                (theme.text, theme.background)
+            } else {
+                (theme.panel, theme.text)
+            };
+            draw.draw_rectangle_rec(rect, background);
+            //  center the label on the button
+            let label = format!("{} {}", i + 1, mode.name());
+            fonts.draw_text(draw, &label,
+                rect.x as i32 + (Self::BUTTON_WIDTH - fonts.measure(&label, Self::FONT_SIZE)) / 2,
+                rect.y as i32 + (Self::BUTTON_HEIGHT - Self::FONT_SIZE) / 2,
+                Self::FONT_SIZE, foreground,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edge_toward() {
+        let view = Rectangle::new(100., 0., 200., 100.);
+        let camera = Camera { target: Vector2::new(50., 50.), offset: Vector2::new(100., 0.), zoom: 2. };
+        //  world (100, 75) is at the middle of the view
+        assert_eq!(camera.edge_toward(view, Vector2::new(100., 75.), 10.), None);
+        //  straight right of the middle, the arrow sits on the right side
+        let (pos, direction) = camera.edge_toward(view, Vector2::new(400., 75.), 10.).unwrap();
+        assert_eq!((pos, direction), (Vector2::new(290., 50.), Vector2::new(1., 0.)));
+        //  far above, it sits on the top side
+        let (pos, _) = camera.edge_toward(view, Vector2::new(110., -1000.), 10.).unwrap();
+        assert!((pos.y - 10.).abs() < 1e-3 && pos.x > 200. && pos.x < 201.);
+    }
 }
 
 pub mod prelude {
-    pub use super::{Window, DrawingContext, WindowConfig};
-}
\ No newline at end of file
+    pub use super::{
+        Window, DrawingContext, WindowConfig,
+        InteractionMode, Gesture, GesturePhase, InputController,
+        RenderLayer, LayerVisibility, RenderContext,
+    };
+}
@@ -0,0 +1,194 @@
+//! Short codes that recreate a world, for sharing it in chat.
+//!
+//! A world grows the same way from the same setup, so a seed, the
+//! size, mutators and timing of the world, and how many ticks it ran
+//! are enough to rebuild it by simulating those ticks again. They are
+//! packed into bytes and written in URL-safe base64 after a prefix:
+//!
+//! ```text
+//! blobs:AdIEAAAAAAAAAICiRAAAKkQ8AQEAoJkC9Q
+//! ```
+//!
+//! The bytes are a format version, the seed, the size as two floats,
+//! the tick rate, substeps and brain period as a byte each, the
+//! mutators as a bit for each in `Mutator::ALL`, the tick as a
+//! variable-length number and a checksum byte that catches typos.
+//!
+//! Only what the world does by itself is recreated. Blobs and foods
+//! added, moved or imported by hand are not in the code, and neither
+//! are the species and mods, which must match on both ends.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::{lockstep::Setup, simulation::Timing, world_code::WorldCode};
+//! # let setup = Setup { seed: 42, size: sim.size(), mutators: vec![], timing: Timing::default() };
+//! let code = WorldCode { setup, tick: sim.clock().tick() }.encode();
+//! let WorldCode { setup, tick } = WorldCode::decode(&code)?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use std::convert::TryInto;
+
+use raylib::prelude::*;
+
+use crate::{lockstep::Setup, mutators::Mutator, simulation::Timing};
+
+/// What every code starts with.
+pub const PREFIX: &str = "blobs:";
+const VERSION: u8 = 1;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A world as its setup and how far it ran.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorldCode {
+    pub setup: Setup,
+    pub tick: u64,
+}
+
+impl WorldCode {
+    pub fn encode(&self) -> String {
+        let Setup { seed, size, mutators, timing } = &self.setup;
+        let mut bytes = vec![VERSION];
+        bytes.extend_from_slice(&seed.to_le_bytes());
+        bytes.extend_from_slice(&size.x.to_le_bytes());
+        bytes.extend_from_slice(&size.y.to_le_bytes());
+        //  the ranges of the timing fit in a byte each
+        for &value in &[timing.tick_rate, timing.substeps, timing.brain_period] {
+            bytes.push(value as u8);
+        }
+        let mask = Mutator::ALL.iter().enumerate()
+            .filter(|(_, mutator)| mutators.contains(mutator))
+            .fold(0u8, |mask, (i, _)| mask | 1 << i);
+        bytes.push(mask);
+        let mut tick = self.tick;
+        loop {
+            let low = (tick & 0x7f) as u8;
+            tick >>= 7;
+            if tick == 0 {
+                bytes.push(low);
+                break;
+            }
+            bytes.push(low | 0x80);
+        }
+        bytes.push(checksum(&bytes));
+        format!("{}{}", PREFIX, base64(&bytes))
+    }
+
+    /// Read a code written by `encode`, ignoring whitespace around it.
+    pub fn decode(code: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("invalid world code: {}", reason);
+        let code = code.trim().strip_prefix(PREFIX).ok_or_else(|| invalid(&format!("it does not start with {}", PREFIX)))?;
+        let bytes = unbase64(code).ok_or_else(|| invalid("it is not base64"))?;
+        let (&sum, bytes) = bytes.split_last().ok_or_else(|| invalid("it is empty"))?;
+        if checksum(bytes) != sum {
+            return Err(invalid("the checksum does not match, it may be mistyped"));
+        }
+        let mut rest = bytes;
+        let mut take = |count: usize| {
+            let (taken, after) = (rest.get(..count)?, rest.get(count..)?);
+            rest = after;
+            Some(taken)
+        };
+        let truncated = || invalid("it is too short");
+        let version = take(1).ok_or_else(truncated)?[0];
+        if version != VERSION {
+            return Err(invalid(&format!("version {} is not supported", version)));
+        }
+        let mut word = |count: usize| take(count).ok_or_else(truncated);
+        let seed = u64::from_le_bytes(word(8)?.try_into().unwrap());
+        let x = f32::from_le_bytes(word(4)?.try_into().unwrap());
+        let y = f32::from_le_bytes(word(4)?.try_into().unwrap());
+        let timing = word(3)?;
+        let timing = Timing { tick_rate: timing[0] as u32, substeps: timing[1] as u32, brain_period: timing[2] as u32 };
+        let mask = word(1)?[0];
+        let mut tick = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = word(1)?[0];
+            tick |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 { break; }
+        }
+        if !rest.is_empty() {
+            return Err(invalid("it is too long"));
+        }
+        if !(x > 0. && y > 0. && x.is_finite() && y.is_finite()) {
+            return Err(invalid(&format!("the size {}x{} is not positive", x, y)));
+        }
+        timing.validate().map_err(|e| invalid(&e))?;
+        let mutators = Mutator::ALL.iter().enumerate()
+            .filter(|(i, _)| mask & 1 << i != 0)
+            .map(|(_, &mutator)| mutator)
+            .collect();
+        Ok(Self { setup: Setup { seed, size: Vector2::new(x, y), mutators, timing }, tick })
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0x5au8, |sum, &byte| sum.rotate_left(3) ^ byte)
+}
+
+fn base64(bytes: &[u8]) -> String {
+    let mut ret = String::new();
+    for chunk in bytes.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &byte)| bits | (byte as u32) << (16 - 8 * i));
+        //  a chunk of n bytes takes n + 1 characters, without padding
+        for i in 0..=chunk.len() {
+            ret.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    ret
+}
+
+fn unbase64(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u32> = text.bytes()
+        .map(|c| ALPHABET.iter().position(|&a| a == c).map(|digit| digit as u32))
+        .collect::<Option<_>>()?;
+    let mut ret = vec![];
+    for chunk in digits.chunks(4) {
+        if chunk.len() == 1 { return None; }
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, &digit)| bits | digit << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            ret.push((bits >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(ret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_world_code() {
+        let setup = Setup {
+            seed: 0xdead_beef_1234,
+            size: Vector2::new(650., 680.),
+            mutators: vec![Mutator::ScarceFood, Mutator::Fog],
+            timing: Timing { tick_rate: 240, substeps: 4, brain_period: 3 },
+        };
+        for &tick in &[0, 127, 128, 36_000, u64::MAX] {
+            let code = WorldCode { setup: setup.clone(), tick };
+            let text = code.encode();
+            assert!(text.starts_with(PREFIX) && text.len() < 50, "{}", text);
+            assert_eq!(WorldCode::decode(&format!(" {}\n", text)), Ok(code));
+        }
+        for bytes in &[&b""[..], b"a", b"ab", b"abc", b"abcd"] {
+            assert_eq!(unbase64(&base64(bytes)).unwrap(), bytes.to_vec());
+        }
+
+        //  the code in the documentation, of a default world at tick 36000
+        let default = Setup { seed: 1234, size: Vector2::new(1300., 680.), mutators: vec![], timing: Timing::default() };
+        assert_eq!(WorldCode::decode("blobs:AdIEAAAAAAAAAICiRAAAKkQ8AQEAoJkC9Q"), Ok(WorldCode { setup: default, tick: 36_000 }));
+
+        let text = WorldCode { setup, tick: 600 }.encode();
+        let mut typo = text.clone().into_bytes();
+        typo[10] = if typo[10] == b'A' { b'B' } else { b'A' };
+        assert!(WorldCode::decode(&String::from_utf8(typo).unwrap()).unwrap_err().contains("checksum"));
+        assert!(WorldCode::decode(&text[PREFIX.len()..]).is_err());
+        assert!(WorldCode::decode(&format!("{}!", text)).is_err());
+        assert!(WorldCode::decode(&text[..text.len() - 4]).is_err());
+    }
+}
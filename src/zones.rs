@@ -0,0 +1,163 @@
+//! Zones of a world where food does not grow, or grows only on land
+//! or only in water, as a mask over where spawners may put food.
+//!
+//! A scenario lists its zones as `zones = [[x, y, width, height, rule], ...]`,
+//! each a rectangle of the world with one of the rules:
+//!
+//! * `"desert"` no food grows in it
+//! * `"land"` only food on land, none in its lakes
+//! * `"water"` only food in lakes, none on its land
+//!
+//! ```toml
+//! zones = [[0, 0, 200, 600, "desert"], [300, 200, 150, 150, "water"]]
+//! ```
+//!
+//! Every spawner places food through the mask, see `spawning::place_food`,
+//! drawing places until one is allowed. Food put down by hand, by the
+//! chat or by a lockstep peer goes anywhere, as before. In the scenario
+//! editor, the zones button arms a rule, and then dragging over the
+//! world marks a zone of it and clicking a zone takes it off; zones
+//! are drawn over the world while the editor is open.
+//!
+//! # Example
+//!
+//! ```
+//! # use blobs::simulation::prelude::*;
+//! # use raylib::prelude::*;
+//! # let mut sim = Simulation::new(SimulationConfig::new(Vector2::new(600., 400.)));
+//! # use blobs::{spawning, zones::{self, Rule, Zone}};
+//! # let spawner = spawning::parse("nests:100,100;500,300")?;
+//! let zones = vec![Zone { rect: Rectangle::new(0., 0., 200., 600.), rule: Rule::Desert }];
+//! assert!(!zones::allows(&zones, Vector2::new(100., 100.), false));
+//! let pos = spawning::place_food(spawner.as_ref(), &mut sim, &zones);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use raylib::prelude::*;
+
+use crate::{fonts::Fonts, theme::Theme, toml::Value};
+
+/// What may grow in a zone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    Desert,
+    /// Only food on land.
+    Land,
+    /// Only food in water.
+    Water,
+}
+
+impl Rule {
+    pub const ALL: [Self; 3] = [Self::Desert, Self::Land, Self::Water];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Desert => "desert",
+            Self::Land => "land",
+            Self::Water => "water",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|rule| rule.name() == name)
+    }
+
+    /// Whether food may grow in the zone, on land or in water.
+    pub fn allows(&self, aquatic: bool) -> bool {
+        match self {
+            Self::Desert => false,
+            Self::Land => !aquatic,
+            Self::Water => aquatic,
+        }
+    }
+}
+
+/// A rectangle of a world with a rule, see the module.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zone {
+    pub rect: Rectangle,
+    pub rule: Rule,
+}
+
+impl Zone {
+    /// A zone from its entry in a scenario, `[x, y, width, height, rule]`.
+    pub fn from_value(value: &Value) -> Result<Self, String> {
+        match value.as_array().map(Vec::as_slice) {
+            Some([x, y, width, height, rule]) => {
+                let number = |value: &Value| value.as_f32().filter(|value| value.is_finite()).ok_or("the corner and size of a zone must be numbers");
+                let (width, height) = (number(width)?, number(height)?);
+                if width <= 0. || height <= 0. {
+                    return Err("a zone must be wider and taller than 0".to_string());
+                }
+                let rule = rule.as_str().ok_or("the rule of a zone must be a string")?;
+                let rule = Rule::from_name(rule)
+                    .ok_or_else(|| format!("unknown zone rule `{}`, expected desert, land or water", rule))?;
+                Ok(Self { rect: Rectangle::new(number(x)?, number(y)?, width, height), rule })
+            },
+            _ => Err("a zone must be [x, y, width, height, rule]".to_string()),
+        }
+    }
+
+    /// The zone as its entry in a scenario.
+    pub fn to_toml(self) -> String {
+        let Rectangle { x, y, width, height } = self.rect;
+        format!("[{}, {}, {}, {}, \"{}\"]", x, y, width, height, self.rule.name())
+    }
+
+    /// The zone between two corners, in any order.
+    pub fn between(a: Vector2, b: Vector2, rule: Rule) -> Self {
+        let rect = Rectangle::new(a.x.min(b.x), a.y.min(b.y), (a.x - b.x).abs(), (a.y - b.y).abs());
+        Self { rect, rule }
+    }
+}
+
+/// Whether food may grow at a place, on land or in water, under every
+/// zone it is in.
+pub fn allows(zones: &[Zone], pos: Vector2, aquatic: bool) -> bool {
+    zones.iter().filter(|zone| zone.rect.check_collision_point_rec(pos)).all(|zone| zone.rule.allows(aquatic))
+}
+
+/// The color a rule is drawn in.
+fn color(theme: &Theme, rule: Rule) -> Color {
+    match rule {
+        Rule::Desert => theme.warning,
+        Rule::Land => theme.ok,
+        Rule::Water => theme.accent,
+    }
+}
+
+/// Draw zones in world coordinates, labeled by their rule.
+pub fn draw<D: RaylibDraw>(draw: &mut D, zones: &[Zone], theme: &Theme, fonts: &Fonts, zoom: f32) {
+    for zone in zones {
+        let color = color(theme, zone.rule);
+        draw.draw_rectangle_rec(zone.rect, color.fade(0.12));
+        draw.draw_rectangle_lines_ex(zone.rect, (2. / zoom).max(1.) as i32, color);
+        let label = format!("{} zone", zone.rule.name());
+        fonts.draw_text(draw, &label, zone.rect.x as i32 + 4, zone.rect.y as i32 + 4, 10, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::toml;
+
+    #[test]
+    fn test_zones() {
+        let zones = vec![
+            Zone { rect: Rectangle::new(0., 0., 100., 100.), rule: Rule::Desert },
+            Zone::between(Vector2::new(300., 300.), Vector2::new(200., 150.), Rule::Water),
+        ];
+        assert_eq!(zones[1].rect, Rectangle::new(200., 150., 100., 150.));
+        assert!(!allows(&zones, Vector2::new(50., 50.), false) && !allows(&zones, Vector2::new(50., 50.), true));
+        assert!(allows(&zones, Vector2::new(250., 200.), true) && !allows(&zones, Vector2::new(250., 200.), false));
+        assert!(allows(&zones, Vector2::new(150., 50.), false) && allows(&[], Vector2::zero(), false));
+
+        let table = toml::parse(&format!("zones = [{}]", zones[1].to_toml())).unwrap();
+        assert_eq!(Zone::from_value(&table["zones"].as_array().unwrap()[0]), Ok(zones[1]));
+        let bad = |source: &str| Zone::from_value(&toml::parse(&format!("zone = {}", source)).unwrap()["zone"]).unwrap_err();
+        assert!(bad("[0, 0, 10, 10, \"swamp\"]").contains("unknown zone rule"));
+        assert!(bad("[0, 0, 0, 10, \"desert\"]").contains("wider"));
+        assert!(bad("[0, 0, 10]").contains("must be [x, y"));
+    }
+}
@@ -0,0 +1,55 @@
+//! The C interface as a host sees it, through the library.
+
+use std::{ffi::CString, ptr};
+
+use blobs::ffi::*;
+
+#[test]
+fn test_library() {
+    unsafe {
+        let sim = blobs_sim_create(400., 300., 42);
+        assert!(!sim.is_null());
+        for command in &["spawn-blob", "spawn-blob", "spawn-blob", "spawn-food 120 80"] {
+            assert_eq!(blobs_sim_push_command(sim, CString::new(*command).unwrap().as_ptr()), 0);
+        }
+        blobs_sim_step(sim, 60);
+        assert_eq!(blobs_sim_tick(sim), 60);
+
+        //  the arrays of blobs agree on how many there are
+        let count = blobs_sim_blob_count(sim);
+        assert!(count > 0);
+        let mut keys = vec![0; count];
+        let mut positions = vec![0.; 2 * count];
+        let mut radii = vec![0.; count];
+        let mut colors = vec![0; 4 * count];
+        assert_eq!(blobs_sim_blob_keys(sim, keys.as_mut_ptr(), count), count);
+        assert_eq!(blobs_sim_blob_positions(sim, positions.as_mut_ptr(), count), count);
+        assert_eq!(blobs_sim_blob_radii(sim, radii.as_mut_ptr(), count), count);
+        assert_eq!(blobs_sim_blob_colors(sim, colors.as_mut_ptr(), count), count);
+        assert!(positions.chunks(2).all(|pos| (0. ..=400.).contains(&pos[0]) && (0. ..=300.).contains(&pos[1])));
+        assert!(radii.iter().all(|&radius| radius > 0.));
+
+        //  a blob moved by its key is where it was put
+        let command = CString::new(format!("move {} 200 150", keys[0])).unwrap();
+        assert_eq!(blobs_sim_push_command(sim, command.as_ptr()), 0);
+        assert_eq!(blobs_sim_blob_positions(sim, positions.as_mut_ptr(), 1), 1);
+        assert_eq!(&positions[..2], &[200., 150.]);
+
+        //  drones are found by their keys, and see what is around them
+        assert_eq!(blobs_sim_push_command(sim, CString::new("spawn-drone 200 150").unwrap().as_ptr()), 0);
+        let mut drone = 0;
+        assert_eq!(blobs_sim_drone_keys(sim, &mut drone, 1), 1);
+        let mut sightings = [0.; 4 * 8];
+        assert!(blobs_sim_drone_sightings(sim, drone, sightings.as_mut_ptr(), 8) > 0);
+
+        blobs_sim_destroy(sim);
+    }
+
+    //  a host that gets no simulation is told so rather than crashing
+    unsafe {
+        assert_eq!(blobs_sim_tick(ptr::null()), 0);
+        assert_eq!(blobs_sim_blob_count(ptr::null()), 0);
+        assert_eq!(blobs_sim_push_command(ptr::null_mut(), CString::new("spawn-blob").unwrap().as_ptr()), -1);
+        blobs_sim_destroy(ptr::null_mut());
+    }
+}